@@ -0,0 +1,134 @@
+// 编译期收集版本信息：git 提交、构建时间、启用的 cargo feature 和内嵌数据指纹，
+// 通过环境变量传给 `rtoolkit --version --json`
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const REGION_FILES: [&str; 4] = [
+    "data/provinces.csv",
+    "data/cities.csv",
+    "data/areas.csv",
+    "data/streets.csv",
+];
+const SERVICES_FILE: &str = "data/services.csv";
+const OUI_FILE: &str = "data/oui.csv";
+
+fn main() {
+    // 源码包中没有 .git，监听不存在的路径会让 cargo 每次都重跑构建脚本
+    for path in [".git/HEAD", ".git/index"] {
+        if Path::new(path).exists() {
+            println!("cargo:rerun-if-changed={}", path);
+        }
+    }
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    for file in REGION_FILES.iter().chain([&SERVICES_FILE, &OUI_FILE]) {
+        println!("cargo:rerun-if-changed={}", file);
+    }
+
+    let commit = git(&["rev-parse", "--short=12", "HEAD"]).unwrap_or_default();
+    let dirty = git(&["status", "--porcelain", "--untracked-files=no"])
+        .is_some_and(|status| !status.is_empty());
+    println!("cargo:rustc-env=RTOOLKIT_GIT_COMMIT={}", commit);
+    println!("cargo:rustc-env=RTOOLKIT_GIT_DIRTY={}", dirty);
+
+    // 支持 SOURCE_DATE_EPOCH，便于可复现构建
+    let epoch = env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or(0)
+        });
+    println!("cargo:rustc-env=RTOOLKIT_BUILD_DATE={}", rfc3339(epoch));
+
+    let mut features: Vec<String> = env::vars()
+        .filter_map(|(key, _)| {
+            key.strip_prefix("CARGO_FEATURE_")
+                .map(|name| name.to_lowercase().replace('_', "-"))
+        })
+        .collect();
+    features.sort();
+    println!("cargo:rustc-env=RTOOLKIT_FEATURES={}", features.join(","));
+    println!(
+        "cargo:rustc-env=RTOOLKIT_TARGET={}",
+        env::var("TARGET").unwrap_or_default()
+    );
+    println!(
+        "cargo:rustc-env=RTOOLKIT_PROFILE={}",
+        env::var("PROFILE").unwrap_or_default()
+    );
+
+    // 地区表没有上游版本号，用内容指纹和各级记录数标识
+    let mut hash = FNV_OFFSET;
+    let mut counts = Vec::new();
+    for file in REGION_FILES {
+        counts.push(fingerprint(file, &mut hash).to_string());
+    }
+    println!("cargo:rustc-env=RTOOLKIT_REGION_FINGERPRINT={:016x}", hash);
+    println!(
+        "cargo:rustc-env=RTOOLKIT_REGION_COUNTS={}",
+        counts.join(",")
+    );
+
+    let mut hash = FNV_OFFSET;
+    let rows = fingerprint(SERVICES_FILE, &mut hash);
+    println!(
+        "cargo:rustc-env=RTOOLKIT_SERVICES_FINGERPRINT={:016x}",
+        hash
+    );
+    println!("cargo:rustc-env=RTOOLKIT_SERVICES_COUNT={}", rows);
+
+    let mut hash = FNV_OFFSET;
+    let rows = fingerprint(OUI_FILE, &mut hash);
+    println!("cargo:rustc-env=RTOOLKIT_OUI_FINGERPRINT={:016x}", hash);
+    println!("cargo:rustc-env=RTOOLKIT_OUI_COUNT={}", rows);
+}
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+
+// 把 CSV 内容累加进 FNV-1a 指纹，返回去掉表头后的行数
+fn fingerprint(file: &str, hash: &mut u64) -> usize {
+    let content = fs::read(file).unwrap_or_default();
+    for byte in &content {
+        *hash ^= u64::from(*byte);
+        *hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    let rows = content.iter().filter(|&&byte| byte == b'\n').count();
+    rows.saturating_sub(1)
+}
+
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+// 不依赖 chrono，把 Unix 时间戳换算成 UTC 的 RFC 3339 时间
+fn rfc3339(epoch: u64) -> String {
+    let days = (epoch / 86_400) as i64;
+    let secs = epoch % 86_400;
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        secs / 3_600,
+        secs % 3_600 / 60,
+        secs % 60
+    )
+}
@@ -0,0 +1,555 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, UdpSocket};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use ipnet::{IpNet, Ipv4Net};
+use serde::Serialize;
+
+use crate::commands::mac::{self, MacAddr, MacError};
+use crate::utils::a11y;
+use crate::utils::netif::{self, Interface};
+use crate::utils::network::{self, NetworkError};
+use crate::utils::oui;
+use crate::utils::output::RecordOutput;
+use crate::utils::ratelimit::RateLimiter;
+
+// 大于 /16 的网段扫描太慢，通常也不是同一个广播域
+const MIN_PREFIX: u8 = 16;
+
+#[derive(clap::Args)]
+pub struct ArpScanOpts {
+    #[arg(
+        short = 'i',
+        long,
+        value_name = "NAME",
+        help = "扫描的网卡，例如 eth0、en0；默认为默认网关所在的网卡"
+    )]
+    interface: Option<String>,
+
+    #[arg(
+        short = 't',
+        long,
+        value_name = "CIDR",
+        help = "扫描的网段或单个 IP，例如 192.168.1.0/24；默认为网卡所在的网段，必须与网卡在同一网段"
+    )]
+    target: Option<String>,
+
+    #[arg(
+        short = 'r',
+        long,
+        value_name = "N",
+        default_value_t = 2,
+        help = "对没有应答的地址再询问的轮数"
+    )]
+    retries: u32,
+
+    #[arg(
+        long = "timeout",
+        value_name = "MS",
+        default_value_t = 500,
+        help = "每一轮请求发完后等待应答的时间（毫秒）"
+    )]
+    time_out: u64,
+
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 500,
+        help = "每秒最多发送的 ARP 请求数"
+    )]
+    rate: u32,
+
+    #[arg(
+        long = "oui-file",
+        value_name = "FILE",
+        help = "额外加载 IEEE 发布的 oui.txt 或 oui.csv，优先于内置的常见厂商表"
+    )]
+    oui_file: Option<PathBuf>,
+
+    #[arg(long, help = "以 JSON 输出结果")]
+    json: bool,
+
+    // 全局 --where / --fields / --rename，由命令行入口注入
+    #[arg(skip)]
+    pub(crate) record_output: RecordOutput,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ArpScanError {
+    #[error("no interface named '{0}'")]
+    NotFound(String),
+    #[error("no usable interface found, specify one with --interface")]
+    NoInterface,
+    #[error("interface {0} has no IPv4 address")]
+    NoIpv4(String),
+    #[error("interface {0} has no MAC address, ARP only works on Ethernet and Wi-Fi interfaces")]
+    NoMac(String),
+    #[error("invalid target '{0}', expected a CIDR such as 192.168.1.0/24 or an IPv4 address")]
+    InvalidTarget(String),
+    #[error("{0} is too large, ARP scans are limited to /16")]
+    TooLarge(Ipv4Net),
+    #[error("{target} is not on the local network of {interface} ({network})")]
+    NotLocal {
+        target: Ipv4Net,
+        interface: String,
+        network: Ipv4Net,
+    },
+    #[error(transparent)]
+    Mac(#[from] MacError),
+    #[error(transparent)]
+    Network(#[from] NetworkError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("json serialize failed: {0}")]
+    Serialize(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Serialize)]
+pub struct ArpHost {
+    pub ip: Ipv4Addr,
+    pub mac: String,
+    pub vendor: Option<String>,
+    // 最后一次询问到收到应答的时间，读取 ARP 缓存时没有
+    pub rtt_ms: Option<f64>,
+    // 同一 IP 有多个 MAC 应答，可能是地址冲突或 ARP 欺骗
+    pub duplicate: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ArpScanReport {
+    pub interface: String,
+    pub network: String,
+    // arp 为直接收发 ARP，neighbor-cache 为读取系统 ARP 缓存
+    pub method: &'static str,
+    pub scanned: usize,
+    pub elapsed_ms: u64,
+    pub hosts: Vec<ArpHost>,
+}
+
+// 一个应答：IP、MAC 和往返时间
+type Answer = (Ipv4Addr, [u8; 6], Option<Duration>);
+
+pub fn run_arp_scan(opts: ArpScanOpts) -> Result<(), ArpScanError> {
+    let extra = opts
+        .oui_file
+        .as_deref()
+        .map(mac::load_oui_file)
+        .transpose()?;
+    let (iface, local) = pick_interface(
+        netif::interfaces()?,
+        &netif::default_gateways(),
+        opts.interface.as_deref(),
+    )?;
+    let mac = iface
+        .mac
+        .ok_or_else(|| ArpScanError::NoMac(iface.name.clone()))?;
+    let network = match &opts.target {
+        Some(raw) => parse_target(raw)?,
+        None => local.trunc(),
+    };
+    if network.prefix_len() < MIN_PREFIX {
+        return Err(ArpScanError::TooLarge(network));
+    }
+    if !local.trunc().contains(&network) {
+        return Err(ArpScanError::NotLocal {
+            target: network,
+            interface: iface.name,
+            network: local.trunc(),
+        });
+    }
+    network::require("arpscan", &network.to_string())?;
+
+    let hosts: Vec<Ipv4Addr> = network.hosts().filter(|ip| *ip != local.addr()).collect();
+    let started = Instant::now();
+    let (method, answers) = scan(&opts, &iface.name, mac, local.addr(), &hosts)?;
+    let mut report = ArpScanReport {
+        interface: iface.name,
+        network: network.to_string(),
+        method,
+        scanned: hosts.len(),
+        elapsed_ms: started.elapsed().as_millis() as u64,
+        hosts: build_hosts(answers, |oui| {
+            extra
+                .as_ref()
+                .and_then(|extra| extra.get(&oui).cloned())
+                .or_else(|| oui::vendor(oui).map(str::to_string))
+        }),
+    };
+    opts.record_output.retain(&mut report.hosts)?;
+
+    if opts.json {
+        opts.record_output
+            .write_report(&report, Some("hosts"), io::stdout().lock())?;
+        return Ok(());
+    }
+    print_hosts(&report.hosts);
+    eprintln!(
+        "{} host{} up, {} address{} scanned on {} ({}) in {:.1}s",
+        report.hosts.len(),
+        if report.hosts.len() == 1 { "" } else { "s" },
+        report.scanned,
+        if report.scanned == 1 { "" } else { "es" },
+        report.interface,
+        report.network,
+        report.elapsed_ms as f64 / 1000.0
+    );
+    Ok(())
+}
+
+// 指定网卡时直接使用；否则优先选默认 IPv4 网关所在的网卡，再退回第一块带 IPv4 和 MAC 的已启用网卡
+fn pick_interface(
+    list: Vec<Interface>,
+    gateways: &[netif::Gateway],
+    name: Option<&str>,
+) -> Result<(Interface, Ipv4Net), ArpScanError> {
+    let ipv4 = |iface: &Interface| {
+        iface.addrs.iter().find_map(|net| match net {
+            IpNet::V4(net) => Some(*net),
+            IpNet::V6(_) => None,
+        })
+    };
+    if let Some(name) = name {
+        let iface = list
+            .into_iter()
+            .find(|iface| iface.name == name)
+            .ok_or_else(|| ArpScanError::NotFound(name.to_string()))?;
+        let local = ipv4(&iface).ok_or_else(|| ArpScanError::NoIpv4(iface.name.clone()))?;
+        return Ok((iface, local));
+    }
+    let usable = |iface: &Interface| iface.up && !iface.loopback && iface.mac.is_some();
+    let preferred = gateways
+        .iter()
+        .filter(|gateway| gateway.addr.is_ipv4())
+        .filter_map(|gateway| gateway.interface.as_deref())
+        .find_map(|name| {
+            list.iter()
+                .find(|iface| iface.name == name && usable(iface))
+        });
+    preferred
+        .or_else(|| {
+            list.iter()
+                .find(|iface| usable(iface) && ipv4(iface).is_some())
+        })
+        .and_then(|iface| Some((iface.clone(), ipv4(iface)?)))
+        .ok_or(ArpScanError::NoInterface)
+}
+
+// 192.168.1.0/24，或单个地址（按 /32 处理）
+fn parse_target(raw: &str) -> Result<Ipv4Net, ArpScanError> {
+    let invalid = || ArpScanError::InvalidTarget(raw.to_string());
+    match raw.parse::<IpAddr>() {
+        Ok(IpAddr::V4(ip)) => Ok(Ipv4Net::from(ip)),
+        Ok(IpAddr::V6(_)) => Err(invalid()),
+        Err(_) => raw
+            .parse::<Ipv4Net>()
+            .map(|net| net.trunc())
+            .map_err(|_| invalid()),
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn scan(
+    opts: &ArpScanOpts,
+    interface: &str,
+    mac: [u8; 6],
+    source: Ipv4Addr,
+    hosts: &[Ipv4Addr],
+) -> Result<(&'static str, Vec<Answer>), ArpScanError> {
+    match crate::utils::arp::ArpSocket::open(interface) {
+        Ok(socket) => Ok(("arp", scan_raw(&socket, opts, mac, source, hosts)?)),
+        Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
+            eprintln!(
+                "warning: sending ARP needs root or CAP_NET_RAW; reading the system ARP cache instead, which may miss hosts"
+            );
+            Ok((
+                "neighbor-cache",
+                scan_cache(opts, interface, source, hosts)?,
+            ))
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn scan(
+    opts: &ArpScanOpts,
+    interface: &str,
+    _mac: [u8; 6],
+    source: Ipv4Addr,
+    hosts: &[Ipv4Addr],
+) -> Result<(&'static str, Vec<Answer>), ArpScanError> {
+    eprintln!("note: sending ARP directly is only supported on Linux; reading the system ARP cache instead");
+    Ok((
+        "neighbor-cache",
+        scan_cache(opts, interface, source, hosts)?,
+    ))
+}
+
+// 按轮次广播 ARP 请求，已应答的地址不再询问；发送和接收分别在两个线程中进行
+#[cfg(target_os = "linux")]
+fn scan_raw(
+    socket: &crate::utils::arp::ArpSocket,
+    opts: &ArpScanOpts,
+    mac: [u8; 6],
+    source: Ipv4Addr,
+    hosts: &[Ipv4Addr],
+) -> io::Result<Vec<Answer>> {
+    use std::sync::{Mutex, PoisonError};
+
+    use crate::utils::arp;
+
+    let wait = Duration::from_millis(opts.time_out);
+    let limiter = RateLimiter::new(opts.rate, (opts.rate / 10).max(1));
+    let wanted: HashSet<Ipv4Addr> = hosts.iter().copied().collect();
+    let sent: Mutex<HashMap<Ipv4Addr, Instant>> = Mutex::new(HashMap::new());
+    let answers: Mutex<Vec<Answer>> = Mutex::new(Vec::new());
+    // 所有轮次发完的时间，接收线程在此之后再等 wait 结束
+    let finished: Mutex<Option<Instant>> = Mutex::new(None);
+
+    let sending = std::thread::scope(|scope| {
+        let sender = scope.spawn(|| {
+            // 发送出错或 panic 时同样标记结束，否则接收循环会一直等下去
+            struct Finish<'a>(&'a Mutex<Option<Instant>>);
+            impl Drop for Finish<'_> {
+                fn drop(&mut self) {
+                    *self.0.lock().unwrap_or_else(PoisonError::into_inner) = Some(Instant::now());
+                }
+            }
+            let _finish = Finish(&finished);
+            for round in 0..=opts.retries {
+                if round > 0 {
+                    std::thread::sleep(wait);
+                }
+                let answered: HashSet<Ipv4Addr> = answers
+                    .lock()
+                    .expect("arp answers lock poisoned")
+                    .iter()
+                    .map(|answer| answer.0)
+                    .collect();
+                for ip in hosts.iter().filter(|ip| !answered.contains(ip)) {
+                    limiter.acquire_blocking();
+                    // 先记录发送时间，应答可能在 send 返回前就到达
+                    sent.lock()
+                        .expect("arp sent lock poisoned")
+                        .insert(*ip, Instant::now());
+                    socket.send_broadcast(&arp::request(mac, source, *ip))?;
+                }
+            }
+            Ok::<_, io::Error>(())
+        });
+
+        let mut buf = [0u8; 1500];
+        loop {
+            if finished
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .is_some_and(|at| at.elapsed() >= wait)
+            {
+                break;
+            }
+            let Some(n) = socket.recv(&mut buf, Duration::from_millis(50))? else {
+                continue;
+            };
+            let Some(reply) = arp::parse_reply(&buf[..n]) else {
+                continue;
+            };
+            // 只要发给本机、且来自扫描范围内的应答，同一主机的多次应答只记一次
+            if reply.target_ip != source || !wanted.contains(&reply.sender_ip) {
+                continue;
+            }
+            let rtt = sent
+                .lock()
+                .expect("arp sent lock poisoned")
+                .get(&reply.sender_ip)
+                .map(Instant::elapsed);
+            let mut answers = answers.lock().expect("arp answers lock poisoned");
+            if !answers
+                .iter()
+                .any(|(ip, mac, _)| *ip == reply.sender_ip && *mac == reply.sender_mac)
+            {
+                answers.push((reply.sender_ip, reply.sender_mac, rtt));
+            }
+        }
+        sender
+            .join()
+            .map_err(|_| io::Error::other("arp sender thread panicked"))?
+    });
+    sending?;
+    Ok(answers.into_inner().expect("arp answers lock poisoned"))
+}
+
+// 向每个地址发一个空 UDP 数据报（discard 端口），内核为此发出 ARP 请求，稍后读取 ARP 缓存
+fn scan_cache(
+    opts: &ArpScanOpts,
+    interface: &str,
+    source: Ipv4Addr,
+    hosts: &[Ipv4Addr],
+) -> io::Result<Vec<Answer>> {
+    let limiter = RateLimiter::new(opts.rate, (opts.rate / 10).max(1));
+    let socket = UdpSocket::bind((source, 0))?;
+    for ip in hosts {
+        limiter.acquire_blocking();
+        // 前面的地址解析失败时后续发送可能报主机不可达，忽略即可
+        let _ = socket.send_to(&[], (*ip, 9));
+    }
+    // 内核自己会重试，等待时间按轮数放宽
+    std::thread::sleep(Duration::from_millis(opts.time_out) * (opts.retries + 1).max(2));
+    let wanted: HashSet<Ipv4Addr> = hosts.iter().copied().collect();
+    Ok(netif::neighbors()?
+        .into_iter()
+        .filter(|neighbor| wanted.contains(&neighbor.addr))
+        .filter(|neighbor| {
+            neighbor
+                .interface
+                .as_deref()
+                .is_none_or(|name| name == interface)
+        })
+        .map(|neighbor| (neighbor.addr, neighbor.mac, None))
+        .collect())
+}
+
+// 按 IP 排序，同一 IP 多个 MAC 时都列出并标记 duplicate
+fn build_hosts(answers: Vec<Answer>, vendor: impl Fn([u8; 3]) -> Option<String>) -> Vec<ArpHost> {
+    let mut by_ip: BTreeMap<Ipv4Addr, Vec<Answer>> = BTreeMap::new();
+    for answer in answers {
+        let macs = by_ip.entry(answer.0).or_default();
+        if !macs.iter().any(|known| known.1 == answer.1) {
+            macs.push(answer);
+        }
+    }
+    by_ip
+        .into_values()
+        .flat_map(|macs| {
+            let duplicate = macs.len() > 1;
+            macs.into_iter()
+                .map(move |(ip, mac, rtt)| (ip, mac, rtt, duplicate))
+        })
+        .map(|(ip, mac, rtt, duplicate)| {
+            let mac = MacAddr(mac);
+            ArpHost {
+                ip,
+                vendor: vendor(mac.oui()),
+                mac: mac.to_string(),
+                rtt_ms: rtt.map(|rtt| (rtt.as_secs_f64() * 10_000.0).round() / 10.0),
+                duplicate,
+            }
+        })
+        .collect()
+}
+
+fn print_hosts(hosts: &[ArpHost]) {
+    let rtt = |host: &ArpHost| {
+        host.rtt_ms
+            .map(|ms| format!("{:.1} ms", ms))
+            .unwrap_or_else(|| "-".to_string())
+    };
+    let vendor = |host: &ArpHost| {
+        let name = match (&host.vendor, MacAddr::parse(&host.mac)) {
+            (Some(vendor), _) => vendor.clone(),
+            // 手机等设备的随机 MAC 设置了本地管理位，不属于任何厂商
+            (None, Some(mac)) if mac.is_local() => "(locally administered)".to_string(),
+            (None, _) => "(unknown vendor)".to_string(),
+        };
+        if host.duplicate {
+            format!("{}  [duplicate IP]", name)
+        } else {
+            name
+        }
+    };
+    if a11y::is_enabled() {
+        for host in hosts {
+            println!(
+                "{}",
+                a11y::line(&[
+                    ("IP", &host.ip),
+                    ("MAC", &host.mac),
+                    ("RTT", &rtt(host)),
+                    ("vendor", &vendor(host)),
+                ])
+            );
+        }
+        return;
+    }
+    println!(
+        "{:<15}  {:<17}  {:<9}  VENDOR",
+        "IP ADDRESS", "MAC ADDRESS", "RTT"
+    );
+    for host in hosts {
+        println!(
+            "{:<15}  {:<17}  {:<9}  {}",
+            host.ip.to_string(),
+            host.mac,
+            rtt(host),
+            vendor(host)
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pick_interface_and_target() {
+        let iface = |name: &str, mac: Option<[u8; 6]>, addr: &str| Interface {
+            name: name.into(),
+            up: true,
+            loopback: false,
+            mtu: Some(1500),
+            mac,
+            addrs: vec![addr.parse().unwrap()],
+        };
+        let list = vec![
+            iface("tun0", None, "10.8.0.2/24"),
+            iface("eth0", Some([0x52, 0x54, 0, 1, 2, 3]), "192.168.1.23/24"),
+            iface("wlan0", Some([0x52, 0x54, 0, 4, 5, 6]), "192.168.50.9/24"),
+        ];
+        let gateways = vec![netif::Gateway {
+            addr: "192.168.50.1".parse().unwrap(),
+            interface: Some("wlan0".into()),
+        }];
+        let (picked, local) = pick_interface(list.clone(), &gateways, None).unwrap();
+        assert_eq!(picked.name, "wlan0");
+        assert_eq!(local.to_string(), "192.168.50.9/24");
+        let (picked, _) = pick_interface(list.clone(), &[], None).unwrap();
+        assert_eq!(picked.name, "eth0");
+        assert!(matches!(
+            pick_interface(list, &[], Some("eth9")),
+            Err(ArpScanError::NotFound(_))
+        ));
+
+        assert_eq!(
+            parse_target("192.168.1.77/24").unwrap().to_string(),
+            "192.168.1.0/24"
+        );
+        assert_eq!(parse_target("192.168.1.5").unwrap().prefix_len(), 32);
+        assert!(parse_target("fe80::1").is_err());
+    }
+
+    #[test]
+    fn test_build_hosts_marks_duplicates() {
+        let router = [0xa0, 0xb1, 0xc2, 0xd3, 0xe4, 0xf5];
+        let rogue = [0x02, 0x11, 0x22, 0x33, 0x44, 0x55];
+        let hosts = build_hosts(
+            vec![
+                (Ipv4Addr::new(192, 168, 1, 20), rogue, None),
+                (
+                    Ipv4Addr::new(192, 168, 1, 1),
+                    router,
+                    Some(Duration::from_micros(1234)),
+                ),
+                (Ipv4Addr::new(192, 168, 1, 1), rogue, None),
+                (Ipv4Addr::new(192, 168, 1, 1), router, None),
+            ],
+            |oui| (oui == [0xa0, 0xb1, 0xc2]).then(|| "Example Corp".to_string()),
+        );
+        assert_eq!(hosts.len(), 3);
+        assert_eq!(hosts[0].ip, Ipv4Addr::new(192, 168, 1, 1));
+        assert!(hosts[0].duplicate && hosts[1].duplicate && !hosts[2].duplicate);
+        assert_eq!(hosts[0].vendor.as_deref(), Some("Example Corp"));
+        assert_eq!(hosts[0].rtt_ms, Some(1.2));
+        assert_eq!(hosts[2].mac, "02:11:22:33:44:55");
+    }
+}
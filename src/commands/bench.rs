@@ -0,0 +1,613 @@
+use std::collections::BTreeMap;
+use std::io::{self, IsTerminal};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::commands::http::{parse_header, read_body, HttpCmdError};
+use crate::utils::duration::parse_duration;
+use crate::utils::format::human_bytes;
+use crate::utils::http::{Connection, HttpError, Request, Url};
+use crate::utils::network::{self, NetworkError};
+use crate::utils::output::RecordOutput;
+use crate::utils::progress::{Progress, ProgressDisplay};
+use crate::utils::ratelimit::RateLimiter;
+
+// 单个响应体保留的上限，超出的部分读出后丢弃，只计入字节数
+const MAX_BODY_BYTES: usize = 16 << 20;
+
+#[derive(clap::Args)]
+pub struct BenchOpts {
+    #[arg(value_name = "URL", help = "压测的 URL，省略协议时为 http://")]
+    url: String,
+
+    #[arg(
+        short = 'c',
+        long,
+        default_value_t = 10,
+        value_name = "N",
+        value_parser = clap::value_parser!(u32).range(1..=10_000),
+        help = "并发连接数"
+    )]
+    concurrency: u32,
+
+    #[arg(
+        short = 'd',
+        long,
+        value_name = "DURATION",
+        conflicts_with = "requests",
+        help = "压测时长，例如 30s、2m；未指定 -n 时默认 10s"
+    )]
+    duration: Option<String>,
+
+    #[arg(
+        short = 'n',
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "发送的请求总数，达到后结束"
+    )]
+    requests: Option<u64>,
+
+    #[arg(short = 'm', long, default_value = "GET", help = "请求方法")]
+    method: String,
+
+    #[arg(
+        short = 'H',
+        long = "header",
+        value_name = "NAME: VALUE",
+        help = "请求头，可重复"
+    )]
+    headers: Vec<String>,
+
+    #[arg(
+        short = 'b',
+        long,
+        value_name = "DATA",
+        help = "请求体，@FILE 读取文件，@- 读取 stdin"
+    )]
+    body: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(u32).range(1..),
+        help = "每秒最多发出的请求数，默认不限速"
+    )]
+    rate: Option<u32>,
+
+    #[arg(
+        long = "timeout",
+        default_value_t = 10000,
+        value_name = "MS",
+        help = "单个请求（含建立连接）的超时时间(毫秒)"
+    )]
+    time_out: u64,
+
+    #[arg(long = "no-keepalive", help = "每个请求新建连接")]
+    no_keepalive: bool,
+
+    #[arg(short = 'k', long, help = "不校验 HTTPS 证书")]
+    insecure: bool,
+
+    #[arg(long, help = "以 JSON 输出报告")]
+    json: bool,
+
+    // 全局 --where / --fields / --rename，由命令行入口注入
+    #[arg(skip)]
+    pub(crate) record_output: RecordOutput,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum BenchError {
+    #[error("invalid duration '{0}', expected e.g. 30s, 2m")]
+    InvalidDuration(String),
+    #[error("invalid method '{0}'")]
+    InvalidMethod(String),
+    #[error("all {0} requests failed")]
+    AllFailed(u64),
+    #[error(transparent)]
+    Http(#[from] HttpCmdError),
+    #[error(transparent)]
+    Request(#[from] HttpError),
+    #[error(transparent)]
+    Network(#[from] NetworkError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("json serialize failed: {0}")]
+    Serialize(#[from] serde_json::Error),
+}
+
+// 延迟统计，单位毫秒；百分位按最近秩计算
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct LatencyStats {
+    pub min: f64,
+    pub mean: f64,
+    pub p50: f64,
+    pub p90: f64,
+    pub p95: f64,
+    pub p99: f64,
+    pub max: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BenchReport {
+    pub url: String,
+    pub method: String,
+    pub concurrency: u32,
+    pub duration_secs: f64,
+    // 完成的请求数，含失败的请求
+    pub requests: u64,
+    // 收到响应的请求数，不论状态码
+    pub responses: u64,
+    pub failed: u64,
+    // 状态码不是 2xx 的响应数
+    pub non_2xx: u64,
+    pub requests_per_sec: f64,
+    // 响应体字节数
+    pub bytes_received: u64,
+    pub bytes_per_sec: f64,
+    // 没有收到任何响应时为 None
+    pub latency_ms: Option<LatencyStats>,
+    pub status_codes: BTreeMap<u16, u64>,
+    pub errors: BTreeMap<&'static str, u64>,
+}
+
+// 所有并发任务共享的请求参数和结束条件
+struct Plan {
+    url: Url,
+    method: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+    insecure: bool,
+    keep_alive: bool,
+    timeout: Duration,
+    rate: Option<RateLimiter>,
+    // 按时长压测的结束时刻
+    deadline: Option<Instant>,
+    // 按请求数压测时还可以发出的请求数
+    remaining: Option<AtomicU64>,
+    stopped: AtomicBool,
+    progress: Option<Arc<Progress>>,
+}
+
+impl Plan {
+    // 领取下一个请求的名额
+    fn next(&self) -> bool {
+        if self.stopped.load(Ordering::Relaxed) {
+            return false;
+        }
+        if self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            return false;
+        }
+        match &self.remaining {
+            Some(remaining) => remaining
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1))
+                .is_ok(),
+            None => true,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct WorkerStats {
+    // 收到响应的请求的延迟，单位微秒
+    latencies: Vec<u64>,
+    bytes: u64,
+    status_codes: BTreeMap<u16, u64>,
+    errors: BTreeMap<&'static str, u64>,
+}
+
+impl WorkerStats {
+    fn merge(&mut self, other: WorkerStats) {
+        self.latencies.extend(other.latencies);
+        self.bytes += other.bytes;
+        for (status, count) in other.status_codes {
+            *self.status_codes.entry(status).or_default() += count;
+        }
+        for (kind, count) in other.errors {
+            *self.errors.entry(kind).or_default() += count;
+        }
+    }
+}
+
+pub fn run_bench(opts: BenchOpts) -> Result<(), BenchError> {
+    let url = if opts.url.contains("://") {
+        Url::parse(&opts.url)?
+    } else {
+        Url::parse(&format!("http://{}", opts.url))?
+    };
+    network::check_host("bench", &url.host)?;
+    let method = opts.method.to_ascii_uppercase();
+    if method.is_empty() || !method.bytes().all(|b| b.is_ascii_alphabetic()) {
+        return Err(BenchError::InvalidMethod(opts.method));
+    }
+    let duration = match (&opts.duration, opts.requests) {
+        (_, Some(_)) => None,
+        (Some(raw), None) => {
+            Some(parse_duration(raw).ok_or_else(|| BenchError::InvalidDuration(raw.clone()))?)
+        }
+        (None, None) => Some(Duration::from_secs(10)),
+    };
+    let headers = opts
+        .headers
+        .iter()
+        .map(|header| parse_header(header))
+        .collect::<Result<Vec<_>, _>>()?;
+    let body = opts.body.as_deref().map(read_body).transpose()?;
+
+    let progress = (!opts.json && io::stderr().is_terminal()).then(|| {
+        let progress = Arc::new(Progress::default());
+        progress.set_total(match (duration, opts.requests) {
+            (Some(duration), _) => duration.as_secs(),
+            (None, requests) => requests.unwrap_or_default(),
+        });
+        progress
+    });
+    let target = url.to_string();
+    let mut plan = Plan {
+        url,
+        method,
+        headers,
+        body: body.unwrap_or_default(),
+        insecure: opts.insecure,
+        keep_alive: !opts.no_keepalive,
+        timeout: Duration::from_millis(opts.time_out.max(1)),
+        rate: opts.rate.map(|rate| RateLimiter::new(rate, 1)),
+        deadline: None,
+        remaining: opts.requests.map(AtomicU64::new),
+        stopped: AtomicBool::new(false),
+        progress: progress.clone(),
+    };
+
+    if !opts.json {
+        let limit = match (duration, opts.requests) {
+            (Some(duration), _) => format!("for {}s", duration.as_secs()),
+            (None, requests) => format!("{} requests", requests.unwrap_or_default()),
+        };
+        println!(
+            "Benchmarking {} {}, {} with {} connections",
+            plan.method, plan.url, limit, opts.concurrency
+        );
+    }
+    let display = progress.clone().map(|progress| match duration {
+        Some(_) => ProgressDisplay::start(progress, "s", "requests"),
+        None => ProgressDisplay::start(progress, "requests", "failed"),
+    });
+
+    let rt = tokio::runtime::Runtime::new()?;
+    let started = Instant::now();
+    plan.deadline = duration.map(|duration| started + duration);
+    let stats = rt.block_on(async {
+        let plan = Arc::new(plan);
+        // Ctrl-C 后不再发出新请求，在途的请求完成后照常输出报告
+        let interrupt = {
+            let plan = Arc::clone(&plan);
+            tokio::spawn(async move {
+                if tokio::signal::ctrl_c().await.is_ok() {
+                    plan.stopped.store(true, Ordering::Relaxed);
+                }
+            })
+        };
+        // 按时长压测时进度条按秒推进，完成数显示在发现数的位置
+        let ticker = match (&plan.progress, duration) {
+            (Some(progress), Some(_)) => {
+                let progress = Arc::clone(progress);
+                Some(tokio::spawn(async move {
+                    let mut interval = tokio::time::interval(Duration::from_secs(1));
+                    interval.tick().await;
+                    loop {
+                        interval.tick().await;
+                        progress.advance(1);
+                    }
+                }))
+            }
+            _ => None,
+        };
+        let workers: Vec<_> = (0..opts.concurrency)
+            .map(|_| tokio::spawn(worker(Arc::clone(&plan), duration.is_some())))
+            .collect();
+        let mut stats = WorkerStats::default();
+        for worker in workers {
+            if let Ok(worker) = worker.await {
+                stats.merge(worker);
+            }
+        }
+        interrupt.abort();
+        if let Some(ticker) = ticker {
+            ticker.abort();
+        }
+        stats
+    });
+    let elapsed = started.elapsed();
+    if let Some(display) = display {
+        display.finish();
+    }
+
+    let report = summarize(&opts, target, stats, elapsed);
+    // --where 不匹配时不输出报告，退出状态照常
+    if opts.record_output.keep(&report)? {
+        if opts.json {
+            opts.record_output
+                .write_report(&report, None, io::stdout().lock())?;
+        } else {
+            print_report(&report);
+        }
+    }
+    if report.responses == 0 && report.failed > 0 {
+        return Err(BenchError::AllFailed(report.failed));
+    }
+    Ok(())
+}
+
+// 每个任务复用一条连接依次发送请求，出错或服务端要求关闭时重新连接
+async fn worker(plan: Arc<Plan>, count_found: bool) -> WorkerStats {
+    let mut stats = WorkerStats::default();
+    let mut connection: Option<Connection> = None;
+    let request = Request {
+        method: &plan.method,
+        url: &plan.url,
+        headers: &plan.headers,
+        body: &plan.body,
+    };
+    while plan.next() {
+        if let Some(rate) = &plan.rate {
+            rate.acquire().await;
+        }
+        let started = Instant::now();
+        let result = tokio::time::timeout(plan.timeout, async {
+            // 复用的连接可能已被服务端因空闲关闭，此时重新连接再试一次
+            if let Some(mut reused) = connection.take() {
+                if let Ok(result) = reused.send(&request, MAX_BODY_BYTES).await {
+                    return Ok((result, reused));
+                }
+            }
+            let mut fresh = Connection::open(&plan.url, None, plan.insecure)
+                .await
+                .map_err(|e| connect_error(&e))?;
+            match fresh.send(&request, MAX_BODY_BYTES).await {
+                Ok(result) => Ok((result, fresh)),
+                Err(e) => Err(request_error(&e)),
+            }
+        })
+        .await;
+        let responded = matches!(result, Ok(Ok(_)));
+        match result {
+            Ok(Ok(((response, reusable), fresh))) => {
+                stats.latencies.push(started.elapsed().as_micros() as u64);
+                stats.bytes += response.body.len() as u64;
+                *stats.status_codes.entry(response.status).or_default() += 1;
+                if reusable && plan.keep_alive {
+                    connection = Some(fresh);
+                }
+            }
+            Ok(Err(kind)) => *stats.errors.entry(kind).or_default() += 1,
+            Err(_) => *stats.errors.entry("timeout").or_default() += 1,
+        }
+        if let Some(progress) = &plan.progress {
+            if count_found {
+                progress.add_found(1);
+            } else {
+                progress.advance(1);
+                if !responded {
+                    progress.add_found(1);
+                }
+            }
+        }
+    }
+    stats
+}
+
+fn connect_error(error: &HttpError) -> &'static str {
+    match error {
+        HttpError::Tls(_) => "tls",
+        _ => "connect",
+    }
+}
+
+fn request_error(error: &HttpError) -> &'static str {
+    match error {
+        HttpError::InvalidResponse(_) => "protocol",
+        HttpError::Io(e)
+            if matches!(
+                e.kind(),
+                io::ErrorKind::ConnectionReset
+                    | io::ErrorKind::BrokenPipe
+                    | io::ErrorKind::UnexpectedEof
+            ) =>
+        {
+            "reset"
+        }
+        _ => "io",
+    }
+}
+
+fn summarize(
+    opts: &BenchOpts,
+    url: String,
+    mut stats: WorkerStats,
+    elapsed: Duration,
+) -> BenchReport {
+    let round = |value: f64| (value * 100.0).round() / 100.0;
+    let secs = elapsed.as_secs_f64().max(f64::EPSILON);
+    let responses = stats.latencies.len() as u64;
+    let failed = stats.errors.values().sum::<u64>();
+    let non_2xx = stats
+        .status_codes
+        .iter()
+        .filter(|(status, _)| !(200..300).contains(*status))
+        .map(|(_, count)| count)
+        .sum();
+    stats.latencies.sort_unstable();
+    BenchReport {
+        url,
+        method: opts.method.to_ascii_uppercase(),
+        concurrency: opts.concurrency,
+        duration_secs: round(secs),
+        requests: responses + failed,
+        responses,
+        failed,
+        non_2xx,
+        requests_per_sec: round((responses + failed) as f64 / secs),
+        bytes_received: stats.bytes,
+        bytes_per_sec: round(stats.bytes as f64 / secs),
+        latency_ms: latency_stats(&stats.latencies),
+        status_codes: stats.status_codes,
+        errors: stats.errors,
+    }
+}
+
+// sorted 为升序排列的微秒数
+pub fn latency_stats(sorted: &[u64]) -> Option<LatencyStats> {
+    let ms = |us: u64| (us as f64 / 10.0).round() / 100.0;
+    let percentile = |p: usize| {
+        let rank = (sorted.len() * p).div_ceil(100).clamp(1, sorted.len());
+        ms(sorted[rank - 1])
+    };
+    let (min, max) = (*sorted.first()?, *sorted.last()?);
+    let mean = sorted.iter().sum::<u64>() as f64 / sorted.len() as f64;
+    Some(LatencyStats {
+        min: ms(min),
+        mean: (mean / 10.0).round() / 100.0,
+        p50: percentile(50),
+        p90: percentile(90),
+        p95: percentile(95),
+        p99: percentile(99),
+        max: ms(max),
+    })
+}
+
+fn print_report(report: &BenchReport) {
+    println!();
+    println!(
+        "Requests:     {} total, {} responses, {} failed",
+        report.requests, report.responses, report.failed
+    );
+    println!("Duration:     {} s", report.duration_secs);
+    println!(
+        "Throughput:   {} req/s, {}/s ({} received)",
+        report.requests_per_sec,
+        human_bytes(report.bytes_per_sec as u64),
+        human_bytes(report.bytes_received)
+    );
+    if let Some(latency) = &report.latency_ms {
+        println!(
+            "Latency (ms): min {}  mean {}  max {}",
+            latency.min, latency.mean, latency.max
+        );
+        for (name, value) in [
+            ("p50", latency.p50),
+            ("p90", latency.p90),
+            ("p95", latency.p95),
+            ("p99", latency.p99),
+        ] {
+            println!("  {}  {}", name, value);
+        }
+    }
+    if !report.status_codes.is_empty() {
+        println!("Status codes:");
+        for (status, count) in &report.status_codes {
+            println!("  {}  {}", status, count);
+        }
+        if report.non_2xx > 0 {
+            println!("  non-2xx responses: {}", report.non_2xx);
+        }
+    }
+    if !report.errors.is_empty() {
+        println!("Errors:");
+        for (kind, count) in &report.errors {
+            println!("  {}  {}", kind, count);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latency_stats() {
+        assert_eq!(latency_stats(&[]), None);
+        let sorted: Vec<u64> = (1..=100).map(|ms| ms * 1000).collect();
+        let stats = latency_stats(&sorted).unwrap();
+        assert_eq!(
+            stats,
+            LatencyStats {
+                min: 1.0,
+                mean: 50.5,
+                p50: 50.0,
+                p90: 90.0,
+                p95: 95.0,
+                p99: 99.0,
+                max: 100.0,
+            }
+        );
+        let single = latency_stats(&[1234]).unwrap();
+        assert_eq!((single.p50, single.p99), (1.23, 1.23));
+    }
+
+    #[test]
+    fn test_worker_reuses_connection() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            let accepted = Arc::new(AtomicU64::new(0));
+            let counter = Arc::clone(&accepted);
+            tokio::spawn(async move {
+                while let Ok((stream, _)) = listener.accept().await {
+                    counter.fetch_add(1, Ordering::Relaxed);
+                    tokio::spawn(async move {
+                        let mut stream = BufReader::new(stream);
+                        let mut line = String::new();
+                        let mut served = 0;
+                        while stream.read_line(&mut line).await.unwrap_or(0) > 0 {
+                            if line == "\r\n" {
+                                served += 1;
+                                let status = if served == 3 {
+                                    "404 Not Found"
+                                } else {
+                                    "200 OK"
+                                };
+                                let response =
+                                    format!("HTTP/1.1 {}\r\nContent-Length: 2\r\n\r\nok", status);
+                                stream
+                                    .get_mut()
+                                    .write_all(response.as_bytes())
+                                    .await
+                                    .unwrap();
+                            }
+                            line.clear();
+                        }
+                    });
+                }
+            });
+            let plan = Arc::new(Plan {
+                url: Url::parse(&format!("http://{}/", addr)).unwrap(),
+                method: "GET".into(),
+                headers: Vec::new(),
+                body: Vec::new(),
+                insecure: false,
+                keep_alive: true,
+                timeout: Duration::from_secs(5),
+                rate: None,
+                deadline: None,
+                remaining: Some(AtomicU64::new(5)),
+                stopped: AtomicBool::new(false),
+                progress: None,
+            });
+            let stats = worker(plan, false).await;
+            assert_eq!(stats.latencies.len(), 5);
+            assert_eq!(stats.bytes, 10);
+            assert_eq!(stats.status_codes, BTreeMap::from([(200, 4), (404, 1)]));
+            assert!(stats.errors.is_empty());
+            assert_eq!(accepted.load(Ordering::Relaxed), 1);
+        });
+    }
+}
@@ -0,0 +1,1025 @@
+use std::env;
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+
+use clap::Subcommand;
+use p12_keystore::{
+    Certificate, EncryptionAlgorithm, KeyStore, KeyStoreEntry, MacAlgorithm, PrivateKeyChain,
+};
+use rcgen::{KeyPair, PublicKeyData};
+use sha2::{Digest, Sha256};
+use x509_parser::certificate::X509Certificate;
+use x509_parser::pem::Pem;
+
+use crate::utils::pki::{self, PkiError};
+use crate::utils::tls;
+
+// 证书链最多向上查找的层数，防止交叉签名形成环
+const MAX_CHAIN_DEPTH: usize = 8;
+
+const PASSWORD_ENV: &str = "RTOOLKIT_CERT_PASSWORD";
+
+#[derive(clap::Args)]
+pub struct CertOpts {
+    #[command(subcommand)]
+    command: CertCommand,
+}
+
+#[derive(Subcommand)]
+enum CertCommand {
+    #[command(about = "离线校验本地证书文件：有效期、证书链、主机名和密钥用途")]
+    Verify(VerifyOpts),
+    #[command(about = "在 PEM、DER 和 PKCS#12 之间转换证书与私钥，或从中提取私钥")]
+    Convert(ConvertOpts),
+}
+
+#[derive(clap::Args)]
+struct VerifyOpts {
+    #[arg(
+        value_name = "CERT",
+        help = "待校验的证书文件（PEM 或 DER），文件中其余证书作为中间证书"
+    )]
+    cert: PathBuf,
+
+    #[arg(
+        long = "chain",
+        value_name = "FILE",
+        help = "信任的中间证书和根证书（PEM 或 DER），可重复；证书链必须终止于其中的自签名根证书"
+    )]
+    chain: Vec<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "要求证书的 SAN 覆盖该域名或 IP 地址"
+    )]
+    hostname: Option<String>,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = Purpose::Server,
+        help = "证书用途 server | client | any，用于检查密钥用途扩展"
+    )]
+    purpose: Purpose,
+
+    #[arg(
+        long = "min-days",
+        value_name = "N",
+        default_value_t = 0,
+        help = "叶子证书剩余有效天数少于 N 天时判定失败"
+    )]
+    min_days: i64,
+}
+
+#[derive(clap::Args)]
+struct ConvertOpts {
+    #[arg(
+        value_name = "INPUT",
+        required = true,
+        help = "输入文件：PEM、DER 证书或私钥、PKCS#12（.p12/.pfx）；证书和私钥分开存放时可以给多个"
+    )]
+    inputs: Vec<PathBuf>,
+
+    #[arg(
+        long,
+        value_enum,
+        value_name = "FORMAT",
+        help = "输出格式 pem | der | p12"
+    )]
+    to: ConvertFormat,
+
+    #[arg(
+        short = 'p',
+        long,
+        value_name = "PASSWORD",
+        help = "PKCS#12 密码，读取和生成时都使用；也可通过环境变量 RTOOLKIT_CERT_PASSWORD 传入，默认为空"
+    )]
+    password: Option<String>,
+
+    #[arg(long, value_name = "FILE", help = "结果写入文件，默认输出到 stdout")]
+    out: Option<PathBuf>,
+
+    #[arg(
+        long = "key-out",
+        value_name = "FILE",
+        help = "私钥单独写入该文件（PKCS#8 PEM，权限 0600），结果中不再包含私钥"
+    )]
+    key_out: Option<PathBuf>,
+
+    #[arg(long = "no-key", conflicts_with = "key_out", help = "结果中不包含私钥")]
+    no_key: bool,
+
+    #[arg(
+        long = "key-only",
+        conflicts_with_all = ["key_out", "no_key"],
+        help = "只输出私钥（PKCS#8），不能与 --to p12 同时使用"
+    )]
+    key_only: bool,
+
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "PKCS#12 中的条目别名，默认取证书的 CN"
+    )]
+    name: Option<String>,
+
+    #[arg(
+        long,
+        help = "生成 PKCS#12 时使用 3DES 加密和 SHA-1 MAC，兼容旧版 Java、Windows 和 macOS 钥匙串"
+    )]
+    legacy: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum ConvertFormat {
+    Pem,
+    Der,
+    P12,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum Purpose {
+    Server,
+    Client,
+    Any,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum CertError {
+    #[error("cannot read {path}: {source}")]
+    Open {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("{path}: {reason}")]
+    Parse { path: PathBuf, reason: String },
+    #[error("no certificate found in {0}")]
+    NoCertificate(PathBuf),
+    #[error("{failed} of {total} checks failed")]
+    Failed { failed: usize, total: usize },
+    #[error("{path}: not a PEM, DER or PKCS#12 file, or wrong password ({reason})")]
+    Pkcs12 { path: PathBuf, reason: String },
+    #[error("cannot write PKCS#12: {0}")]
+    Pkcs12Write(String),
+    #[error("no private key found in the input")]
+    NoKey,
+    #[error("private key does not match any of the certificates")]
+    KeyMismatch,
+    #[error("--key-only cannot be combined with --to p12")]
+    KeyOnlyP12,
+    #[error("refusing to write binary output to a terminal, use --out FILE")]
+    BinaryTerminal,
+    #[error(transparent)]
+    Pki(#[from] PkiError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+pub fn run_cert(opts: CertOpts) -> Result<(), CertError> {
+    match opts.command {
+        CertCommand::Verify(opts) => {
+            let mut bundle = load_certificates(&opts.cert)?;
+            let leaf = bundle.remove(0);
+            // 叶子证书文件中附带的证书只用于补全链，不作为信任锚
+            let mut pool: Vec<(Vec<u8>, bool)> = Vec::new();
+            for der in bundle {
+                add_to_pool(&mut pool, der, false);
+            }
+            for path in &opts.chain {
+                for der in load_certificates(path)? {
+                    add_to_pool(&mut pool, der, true);
+                }
+            }
+            let report = verify(&leaf, &pool, &opts, chrono::Utc::now().timestamp());
+            write_report(&report, &mut io::stdout().lock())?;
+            let failed = report.checks.iter().filter(|check| !check.passed).count();
+            if failed > 0 {
+                return Err(CertError::Failed {
+                    failed,
+                    total: report.checks.len(),
+                });
+            }
+            Ok(())
+        }
+        CertCommand::Convert(opts) => {
+            let password = opts
+                .password
+                .clone()
+                .or_else(|| env::var(PASSWORD_ENV).ok())
+                .unwrap_or_default();
+            let mut bundle = Bundle::default();
+            for path in &opts.inputs {
+                bundle.load(path, &password)?;
+            }
+            if bundle.certs.is_empty() && bundle.key.is_none() {
+                return Err(CertError::NoCertificate(opts.inputs[0].clone()));
+            }
+            bundle.sort_leaf_first();
+            if let Some(path) = &opts.key_out {
+                let key = bundle.key.as_ref().ok_or(CertError::NoKey)?;
+                pki::write_private_file(path, key.serialize_pem().as_bytes())?;
+                eprintln!("Wrote {}", path.display());
+            }
+            let output = convert(&bundle, &opts, &password)?;
+            match &opts.out {
+                Some(path) => {
+                    if output.has_key {
+                        pki::write_private_file(path, &output.data)?;
+                    } else {
+                        fs::write(path, &output.data)?;
+                    }
+                    eprintln!("Wrote {}", path.display());
+                }
+                None => {
+                    let mut stdout = io::stdout().lock();
+                    if opts.to != ConvertFormat::Pem && stdout.is_terminal() {
+                        return Err(CertError::BinaryTerminal);
+                    }
+                    stdout.write_all(&output.data)?;
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+// PEM 文件可包含多张证书，其他内容按单张 DER 证书解析
+fn load_certificates(path: &Path) -> Result<Vec<Vec<u8>>, CertError> {
+    let data = fs::read(path).map_err(|source| CertError::Open {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let parse_error = |reason: String| CertError::Parse {
+        path: path.to_path_buf(),
+        reason,
+    };
+    let certs = if data.windows(11).any(|window| window == b"-----BEGIN ") {
+        let mut certs = Vec::new();
+        for pem in Pem::iter_from_buffer(&data) {
+            let pem = pem.map_err(|e| parse_error(e.to_string()))?;
+            if pem.label == "CERTIFICATE" {
+                certs.push(pem.contents);
+            }
+        }
+        certs
+    } else {
+        vec![data]
+    };
+    for der in &certs {
+        x509_parser::parse_x509_certificate(der).map_err(|e| parse_error(e.to_string()))?;
+    }
+    if certs.is_empty() {
+        return Err(CertError::NoCertificate(path.to_path_buf()));
+    }
+    Ok(certs)
+}
+
+// 同一张证书既出现在叶子文件又通过 --chain 传入时按信任处理
+fn add_to_pool(pool: &mut Vec<(Vec<u8>, bool)>, der: Vec<u8>, trusted: bool) {
+    match pool.iter_mut().find(|(existing, _)| *existing == der) {
+        Some(entry) => entry.1 |= trusted,
+        None => pool.push((der, trusted)),
+    }
+}
+
+#[derive(Debug)]
+struct ChainEntry {
+    subject: String,
+    issuer: String,
+    not_before: String,
+    not_after: String,
+    days_left: i64,
+    trusted: bool,
+}
+
+#[derive(Debug)]
+struct Check {
+    name: &'static str,
+    passed: bool,
+    detail: String,
+}
+
+#[derive(Debug)]
+struct Report {
+    chain: Vec<ChainEntry>,
+    checks: Vec<Check>,
+}
+
+fn verify(leaf: &[u8], pool: &[(Vec<u8>, bool)], opts: &VerifyOpts, now: i64) -> Report {
+    let parse = |der| {
+        x509_parser::parse_x509_certificate(der)
+            .expect("validated when loading")
+            .1
+    };
+    let leaf = parse(leaf);
+    let pool: Vec<(X509Certificate, bool)> = pool
+        .iter()
+        .map(|(der, trusted)| (parse(der), *trusted))
+        .collect();
+    let (chain, chain_result) = build_chain(&leaf, &pool);
+    let is_trusted = |cert: &X509Certificate| {
+        pool.iter()
+            .any(|(other, trusted)| *trusted && other.as_raw() == cert.as_raw())
+    };
+
+    let mut checks = vec![
+        check_expiry(&chain, opts.min_days, now),
+        Check {
+            name: "chain",
+            passed: chain_result.is_ok(),
+            detail: chain_result.unwrap_or_else(|reason| reason),
+        },
+    ];
+    if let Some(hostname) = &opts.hostname {
+        checks.push(check_hostname(&leaf, hostname));
+    }
+    checks.push(check_key_usage(&leaf, opts.purpose));
+
+    let chain = chain
+        .iter()
+        .map(|cert| {
+            let validity = cert.validity();
+            let not_after = validity.not_after.timestamp();
+            ChainEntry {
+                subject: cert.subject().to_string(),
+                issuer: cert.issuer().to_string(),
+                not_before: tls::format_timestamp(validity.not_before.timestamp()),
+                not_after: tls::format_timestamp(not_after),
+                days_left: (not_after - now).div_euclid(86_400),
+                trusted: is_trusted(cert),
+            }
+        })
+        .collect();
+    Report { chain, checks }
+}
+
+// 从叶子证书开始逐级查找签发者并验证签名，直到 --chain 中提供的自签名根证书；
+// 返回已经找到的部分链和校验结论
+fn build_chain<'c, 'a>(
+    leaf: &'c X509Certificate<'a>,
+    pool: &'c [(X509Certificate<'a>, bool)],
+) -> (Vec<&'c X509Certificate<'a>>, Result<String, String>) {
+    let mut chain = vec![leaf];
+    loop {
+        let current = *chain.last().expect("chain starts with the leaf");
+        if current.subject() == current.issuer() && current.verify_signature(None).is_ok() {
+            let trusted = pool
+                .iter()
+                .any(|(cert, trusted)| *trusted && cert.as_raw() == current.as_raw());
+            if !trusted {
+                return (
+                    chain,
+                    Err(format!(
+                        "self-signed {} is not trusted; pass it with --chain",
+                        current.subject()
+                    )),
+                );
+            }
+            if let Err(reason) = check_issuers(&chain) {
+                return (chain, Err(reason));
+            }
+            let detail = format!(
+                "{} certificate(s) up to trusted root {}",
+                chain.len(),
+                current.subject()
+            );
+            return (chain, Ok(detail));
+        }
+        if chain.len() >= MAX_CHAIN_DEPTH {
+            return (
+                chain,
+                Err(format!(
+                    "chain is longer than {} certificates",
+                    MAX_CHAIN_DEPTH
+                )),
+            );
+        }
+        let mut candidates = pool
+            .iter()
+            .map(|(cert, _)| cert)
+            .filter(|cert| {
+                cert.subject() == current.issuer()
+                    && !chain.iter().any(|seen| seen.as_raw() == cert.as_raw())
+            })
+            .peekable();
+        if candidates.peek().is_none() {
+            return (
+                chain,
+                Err(format!(
+                    "issuer {} of {} not found; pass it with --chain",
+                    current.issuer(),
+                    current.subject()
+                )),
+            );
+        }
+        match candidates.find(|cert| current.verify_signature(Some(cert.public_key())).is_ok()) {
+            Some(issuer) => chain.push(issuer),
+            None => {
+                return (
+                    chain,
+                    Err(format!(
+                        "signature of {} does not verify with the key of {}",
+                        current.subject(),
+                        current.issuer()
+                    )),
+                )
+            }
+        }
+    }
+}
+
+// 签发者必须是 CA，声明了 keyUsage 时必须包含 keyCertSign，并满足 pathLenConstraint
+fn check_issuers(chain: &[&X509Certificate]) -> Result<(), String> {
+    for (depth, cert) in chain.iter().enumerate().skip(1) {
+        let constraints = cert.basic_constraints().ok().flatten();
+        match constraints {
+            Some(ext) if ext.value.ca => {
+                // depth - 1 为该证书之下的中间 CA 数量
+                if let Some(limit) = ext.value.path_len_constraint {
+                    if depth - 1 > limit as usize {
+                        return Err(format!(
+                            "{} allows {} intermediate CA(s) below it, chain has {}",
+                            cert.subject(),
+                            limit,
+                            depth - 1
+                        ));
+                    }
+                }
+            }
+            _ => return Err(format!("issuer {} is not a CA certificate", cert.subject())),
+        }
+        if let Ok(Some(usage)) = cert.key_usage() {
+            if !usage.value.key_cert_sign() {
+                return Err(format!(
+                    "issuer {} key usage lacks Key Cert Sign",
+                    cert.subject()
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn check_expiry(chain: &[&X509Certificate], min_days: i64, now: i64) -> Check {
+    let fail = |detail| Check {
+        name: "expiry",
+        passed: false,
+        detail,
+    };
+    for cert in chain {
+        let validity = cert.validity();
+        if validity.not_before.timestamp() > now {
+            return fail(format!(
+                "{} is not valid before {}",
+                cert.subject(),
+                tls::format_timestamp(validity.not_before.timestamp())
+            ));
+        }
+        if validity.not_after.timestamp() < now {
+            return fail(format!(
+                "{} expired at {}",
+                cert.subject(),
+                tls::format_timestamp(validity.not_after.timestamp())
+            ));
+        }
+    }
+    let not_after = chain[0].validity().not_after.timestamp();
+    let days_left = (not_after - now).div_euclid(86_400);
+    let detail = format!(
+        "leaf expires in {} days ({})",
+        days_left,
+        tls::format_timestamp(not_after)
+    );
+    if days_left < min_days {
+        return fail(format!("{}, minimum {}", detail, min_days));
+    }
+    Check {
+        name: "expiry",
+        passed: true,
+        detail,
+    }
+}
+
+// 只看 SAN；通配符只匹配最左侧的一整级标签，*.example.com 不匹配 example.com
+fn check_hostname(leaf: &X509Certificate, hostname: &str) -> Check {
+    let host = hostname.trim_end_matches('.').to_ascii_lowercase();
+    let sans = tls::subject_alt_names(leaf);
+    let matched = match host.parse::<IpAddr>() {
+        Ok(ip) => sans
+            .iter()
+            .find(|san| san.parse::<IpAddr>().is_ok_and(|san| san == ip)),
+        Err(_) => sans.iter().find(|san| {
+            let pattern = san.trim_end_matches('.').to_ascii_lowercase();
+            match pattern.strip_prefix("*.") {
+                Some(suffix) => host
+                    .split_once('.')
+                    .is_some_and(|(label, rest)| !label.is_empty() && rest == suffix),
+                None => pattern == host,
+            }
+        }),
+    };
+    let (passed, detail) = match matched {
+        Some(san) => (true, format!("{} matches {}", hostname, san)),
+        None if sans.is_empty() => (
+            false,
+            "certificate has no DNS or IP subjectAltName".to_string(),
+        ),
+        None => (
+            false,
+            format!("{} not in subjectAltName [{}]", hostname, sans.join(", ")),
+        ),
+    };
+    Check {
+        name: "hostname",
+        passed,
+        detail,
+    }
+}
+
+// 没有 keyUsage / extendedKeyUsage 扩展时不做限制
+fn check_key_usage(leaf: &X509Certificate, purpose: Purpose) -> Check {
+    let mut problems = Vec::new();
+    let mut present = Vec::new();
+    if let Ok(Some(usage)) = leaf.key_usage() {
+        let usage = usage.value;
+        present.push(usage.to_string());
+        let allowed = match purpose {
+            Purpose::Server => {
+                usage.digital_signature() || usage.key_encipherment() || usage.key_agreement()
+            }
+            Purpose::Client => usage.digital_signature() || usage.key_agreement(),
+            Purpose::Any => true,
+        };
+        if !allowed {
+            problems.push("key usage does not allow TLS handshakes".to_string());
+        }
+    }
+    if let Ok(Some(eku)) = leaf.extended_key_usage() {
+        let eku = eku.value;
+        for (set, name) in [
+            (eku.any, "Any"),
+            (eku.server_auth, "Server Auth"),
+            (eku.client_auth, "Client Auth"),
+            (eku.code_signing, "Code Signing"),
+            (eku.email_protection, "Email Protection"),
+        ] {
+            if set {
+                present.push(name.to_string());
+            }
+        }
+        let (allowed, name) = match purpose {
+            Purpose::Server => (eku.server_auth, "Server Auth"),
+            Purpose::Client => (eku.client_auth, "Client Auth"),
+            Purpose::Any => (true, ""),
+        };
+        if !allowed && !eku.any {
+            problems.push(format!("extended key usage lacks {}", name));
+        }
+    }
+    let detail = if !problems.is_empty() {
+        problems.join("; ")
+    } else if present.is_empty() {
+        "no key usage restrictions".to_string()
+    } else {
+        present.join(", ")
+    };
+    Check {
+        name: "key usage",
+        passed: problems.is_empty(),
+        detail,
+    }
+}
+
+fn write_report<W: Write>(report: &Report, out: &mut W) -> io::Result<()> {
+    writeln!(out, "Certificate chain:")?;
+    for (depth, entry) in report.chain.iter().enumerate() {
+        writeln!(
+            out,
+            "  {} {}{}",
+            depth,
+            entry.subject,
+            if entry.trusted { " (trusted)" } else { "" }
+        )?;
+        writeln!(out, "      issuer: {}", entry.issuer)?;
+        writeln!(
+            out,
+            "      valid:  {} .. {} ({} days left)",
+            entry.not_before, entry.not_after, entry.days_left
+        )?;
+    }
+    writeln!(out)?;
+    for check in &report.checks {
+        writeln!(
+            out,
+            "{:<6}  {:<9}  {}",
+            if check.passed { "PASS" } else { "FAIL" },
+            check.name,
+            check.detail
+        )?;
+    }
+    Ok(())
+}
+
+// 从一个或多个输入文件收集到的证书和私钥，证书按出现顺序去重
+#[derive(Default)]
+struct Bundle {
+    certs: Vec<Vec<u8>>,
+    key: Option<KeyPair>,
+}
+
+impl Bundle {
+    // 依次尝试 PEM、DER 证书、PKCS#8 DER 私钥，最后按 PKCS#12 解密
+    fn load(&mut self, path: &Path, password: &str) -> Result<(), CertError> {
+        let data = fs::read(path).map_err(|source| CertError::Open {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let parse_error = |reason: String| CertError::Parse {
+            path: path.to_path_buf(),
+            reason,
+        };
+        if data.windows(11).any(|window| window == b"-----BEGIN ") {
+            for pem in Pem::iter_from_buffer(&data) {
+                let pem = pem.map_err(|e| parse_error(e.to_string()))?;
+                if pem.label == "CERTIFICATE" {
+                    x509_parser::parse_x509_certificate(&pem.contents)
+                        .map_err(|e| parse_error(e.to_string()))?;
+                    self.add_cert(pem.contents);
+                } else if pem.label.ends_with("PRIVATE KEY") {
+                    self.set_key(path, pki::decode_key(&pem.label, &pem.contents)?);
+                }
+            }
+            return Ok(());
+        }
+        if x509_parser::parse_x509_certificate(&data).is_ok() {
+            self.add_cert(data);
+            return Ok(());
+        }
+        if let Ok(key) = pki::decode_key("PRIVATE KEY", &data) {
+            self.set_key(path, key);
+            return Ok(());
+        }
+        let store = KeyStore::from_pkcs12(&data, password).map_err(|e| CertError::Pkcs12 {
+            path: path.to_path_buf(),
+            reason: e.to_string(),
+        })?;
+        for (_, entry) in store.entries() {
+            match entry {
+                KeyStoreEntry::PrivateKeyChain(chain) => {
+                    self.set_key(path, pki::decode_key("PRIVATE KEY", chain.key())?);
+                    for cert in chain.chain() {
+                        self.add_cert(cert.as_der().to_vec());
+                    }
+                }
+                KeyStoreEntry::Certificate(cert) => self.add_cert(cert.as_der().to_vec()),
+            }
+        }
+        Ok(())
+    }
+
+    fn add_cert(&mut self, der: Vec<u8>) {
+        if !self.certs.contains(&der) {
+            self.certs.push(der);
+        }
+    }
+
+    fn set_key(&mut self, path: &Path, key: KeyPair) {
+        if self.key.is_some() {
+            eprintln!(
+                "warning: ignoring extra private key in {}, using the first one",
+                path.display()
+            );
+            return;
+        }
+        self.key = Some(key);
+    }
+
+    // 与私钥匹配的证书的下标
+    fn leaf_index(&self) -> Option<usize> {
+        let key = self.key.as_ref()?;
+        self.certs.iter().position(|der| {
+            x509_parser::parse_x509_certificate(der).is_ok_and(|(_, cert)| {
+                cert.public_key().subject_public_key.data.as_ref() == key.der_bytes()
+            })
+        })
+    }
+
+    // 与私钥匹配的证书放到最前面，作为叶子证书
+    fn sort_leaf_first(&mut self) {
+        if let Some(index) = self.leaf_index() {
+            let leaf = self.certs.remove(index);
+            self.certs.insert(0, leaf);
+        }
+    }
+}
+
+struct ConvertOutput {
+    data: Vec<u8>,
+    has_key: bool,
+}
+
+fn convert(
+    bundle: &Bundle,
+    opts: &ConvertOpts,
+    password: &str,
+) -> Result<ConvertOutput, CertError> {
+    let key = bundle
+        .key
+        .as_ref()
+        .filter(|_| !opts.no_key && opts.key_out.is_none());
+    if opts.key_only {
+        let key = bundle.key.as_ref().ok_or(CertError::NoKey)?;
+        let data = match opts.to {
+            ConvertFormat::Pem => key.serialize_pem().into_bytes(),
+            ConvertFormat::Der => key.serialize_der(),
+            ConvertFormat::P12 => return Err(CertError::KeyOnlyP12),
+        };
+        return Ok(ConvertOutput {
+            data,
+            has_key: true,
+        });
+    }
+    match opts.to {
+        // 私钥在前，随后是叶子证书和其余证书
+        ConvertFormat::Pem => {
+            let mut text = key.map(KeyPair::serialize_pem).unwrap_or_default();
+            for der in &bundle.certs {
+                text.push_str(&pki::encode_pem("CERTIFICATE", der));
+            }
+            Ok(ConvertOutput {
+                data: text.into_bytes(),
+                has_key: key.is_some(),
+            })
+        }
+        // DER 只能保存一个对象，取叶子证书
+        ConvertFormat::Der => {
+            let Some(leaf) = bundle.certs.first() else {
+                return Err(CertError::NoCertificate(opts.inputs[0].clone()));
+            };
+            if bundle.certs.len() > 1 {
+                eprintln!(
+                    "warning: DER holds a single certificate, dropping {} more",
+                    bundle.certs.len() - 1
+                );
+            }
+            if key.is_some() {
+                eprintln!("warning: DER output omits the private key, use --key-out or --key-only to extract it");
+            }
+            Ok(ConvertOutput {
+                data: leaf.clone(),
+                has_key: false,
+            })
+        }
+        ConvertFormat::P12 => Ok(ConvertOutput {
+            data: write_pkcs12(&bundle.certs, key, opts, password)?,
+            has_key: key.is_some(),
+        }),
+    }
+}
+
+// 有私钥时生成一个私钥条目（叶子证书 + 证书链），否则每张证书一个受信任证书条目
+fn write_pkcs12(
+    certs: &[Vec<u8>],
+    key: Option<&KeyPair>,
+    opts: &ConvertOpts,
+    password: &str,
+) -> Result<Vec<u8>, CertError> {
+    let to_p12 = |der: &Vec<u8>| {
+        Certificate::from_der(der).map_err(|e| CertError::Pkcs12Write(e.to_string()))
+    };
+    let mut store = KeyStore::new();
+    match key {
+        Some(key) => {
+            let leaf = certs.first().ok_or(CertError::KeyMismatch)?;
+            let (_, cert) = x509_parser::parse_x509_certificate(leaf)
+                .map_err(|e| CertError::Pkcs12Write(e.to_string()))?;
+            if cert.public_key().subject_public_key.data.as_ref() != key.der_bytes() {
+                return Err(CertError::KeyMismatch);
+            }
+            let chain = certs.iter().map(to_p12).collect::<Result<Vec<_>, _>>()?;
+            // localKeyId 关联私钥和叶子证书，取证书 DER 的 SHA-256
+            let local_key_id = Sha256::digest(leaf).to_vec();
+            let alias = opts.name.clone().unwrap_or_else(|| alias_for(leaf, 1));
+            let entry = PrivateKeyChain::new(key.serialize_der(), local_key_id, chain);
+            store.add_entry(&alias, KeyStoreEntry::PrivateKeyChain(entry));
+        }
+        None => {
+            if certs.is_empty() {
+                return Err(CertError::NoCertificate(opts.inputs[0].clone()));
+            }
+            for (index, der) in certs.iter().enumerate() {
+                // 别名是条目的唯一键，重名时追加序号
+                let mut alias = match &opts.name {
+                    Some(name) if index == 0 => name.clone(),
+                    Some(name) => format!("{name}-{}", index + 1),
+                    None => alias_for(der, index + 1),
+                };
+                if store.entry(&alias).is_some() {
+                    alias = format!("{alias}-{}", index + 1);
+                }
+                store.add_entry(&alias, KeyStoreEntry::Certificate(to_p12(der)?));
+            }
+        }
+    }
+    let mut writer = store.writer(password);
+    if opts.legacy {
+        writer = writer
+            .encryption_algorithm(EncryptionAlgorithm::PbeWithShaAnd3KeyTripleDesCbc)
+            .mac_algorithm(MacAlgorithm::HmacSha1);
+    }
+    writer
+        .write()
+        .map_err(|e| CertError::Pkcs12Write(e.to_string()))
+}
+
+// 证书主题的 CN，没有 CN 时用序号
+fn alias_for(der: &[u8], index: usize) -> String {
+    x509_parser::parse_x509_certificate(der)
+        .ok()
+        .and_then(|(_, cert)| {
+            cert.subject()
+                .iter_common_name()
+                .next()
+                .and_then(|cn| cn.as_str().ok())
+                .map(str::to_string)
+        })
+        .unwrap_or_else(|| index.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rcgen::{
+        BasicConstraints, CertificateParams, DistinguishedName, DnType, ExtendedKeyUsagePurpose,
+        IsCa, Issuer, KeyPair, KeyUsagePurpose,
+    };
+
+    fn opts(hostname: &str, purpose: Purpose) -> VerifyOpts {
+        VerifyOpts {
+            cert: PathBuf::new(),
+            chain: Vec::new(),
+            hostname: Some(hostname.to_string()),
+            purpose,
+            min_days: 30,
+        }
+    }
+
+    fn failed(report: &Report) -> Vec<&str> {
+        report
+            .checks
+            .iter()
+            .filter(|check| !check.passed)
+            .map(|check| check.name)
+            .collect()
+    }
+
+    #[test]
+    fn test_verify_leaf_against_local_ca() {
+        let ca_key = KeyPair::generate().unwrap();
+        let mut ca_params = CertificateParams::default();
+        let mut name = DistinguishedName::new();
+        name.push(DnType::CommonName, "Test Root");
+        ca_params.distinguished_name = name;
+        ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        ca_params.key_usages = vec![KeyUsagePurpose::KeyCertSign];
+        let ca = ca_params.self_signed(&ca_key).unwrap();
+        let issuer = Issuer::new(ca_params, ca_key);
+
+        let leaf_key = KeyPair::generate().unwrap();
+        let mut leaf_params =
+            CertificateParams::new(vec!["*.example.com".to_string(), "10.0.0.1".to_string()])
+                .unwrap();
+        leaf_params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ServerAuth];
+        let leaf = leaf_params.signed_by(&leaf_key, &issuer).unwrap();
+
+        let pool = vec![(ca.der().to_vec(), true)];
+        let now = chrono::Utc::now().timestamp();
+        let report = verify(
+            leaf.der(),
+            &pool,
+            &opts("www.example.com", Purpose::Server),
+            now,
+        );
+        assert!(failed(&report).is_empty(), "{:?}", report.checks);
+        assert_eq!(report.chain.len(), 2);
+        assert!(report.chain[1].trusted);
+        let report = verify(leaf.der(), &pool, &opts("10.0.0.1", Purpose::Server), now);
+        assert!(failed(&report).is_empty(), "{:?}", report.checks);
+
+        // 通配符不覆盖裸域名，也不匹配多级子域名
+        let report = verify(
+            leaf.der(),
+            &pool,
+            &opts("example.com", Purpose::Server),
+            now,
+        );
+        assert_eq!(failed(&report), ["hostname"]);
+        let report = verify(
+            leaf.der(),
+            &pool,
+            &opts("a.b.example.com", Purpose::Client),
+            now,
+        );
+        assert_eq!(failed(&report), ["hostname", "key usage"]);
+
+        // 根证书只在叶子文件中出现时不算信任锚；缺少签发者时链不完整
+        let untrusted = vec![(ca.der().to_vec(), false)];
+        let report = verify(
+            leaf.der(),
+            &untrusted,
+            &opts("www.example.com", Purpose::Any),
+            now,
+        );
+        assert_eq!(failed(&report), ["chain"]);
+        let report = verify(leaf.der(), &[], &opts("www.example.com", Purpose::Any), now);
+        assert_eq!(failed(&report), ["chain"]);
+        assert!(report.checks[1].detail.contains("not found"));
+
+        // rcgen 默认有效期到 4096 年，按 4124 年计算即已过期
+        let report = verify(
+            leaf.der(),
+            &pool,
+            &opts("www.example.com", Purpose::Any),
+            68_000_000_000,
+        );
+        assert_eq!(failed(&report), ["expiry"]);
+    }
+
+    #[test]
+    fn test_convert_pem_to_pkcs12_and_back() {
+        let dir = std::env::temp_dir().join(format!("rtoolkit-cert-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let ca_key = KeyPair::generate().unwrap();
+        let mut ca_params = CertificateParams::new(vec!["ca.test".to_string()]).unwrap();
+        ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let ca = ca_params.self_signed(&ca_key).unwrap();
+        let issuer = Issuer::new(ca_params, ca_key);
+        let key = KeyPair::generate().unwrap();
+        let mut params = CertificateParams::new(vec!["www.example.com".to_string()]).unwrap();
+        params
+            .distinguished_name
+            .push(DnType::CommonName, "www.example.com");
+        let leaf = params.signed_by(&key, &issuer).unwrap();
+
+        // 证书链顺序打乱也能按私钥找到叶子证书
+        let pem_path = dir.join("bundle.pem");
+        fs::write(
+            &pem_path,
+            format!("{}{}{}", ca.pem(), key.serialize_pem(), leaf.pem()),
+        )
+        .unwrap();
+        let mut bundle = Bundle::default();
+        bundle.load(&pem_path, "").unwrap();
+        bundle.sort_leaf_first();
+        assert_eq!(bundle.certs, [leaf.der().to_vec(), ca.der().to_vec()]);
+
+        let mut opts = ConvertOpts {
+            inputs: vec![pem_path],
+            to: ConvertFormat::P12,
+            password: None,
+            out: None,
+            key_out: None,
+            no_key: false,
+            key_only: false,
+            name: None,
+            legacy: true,
+        };
+        let p12 = convert(&bundle, &opts, "secret").unwrap();
+        assert!(p12.has_key);
+        let store = KeyStore::from_pkcs12(&p12.data, "secret").unwrap();
+        let (alias, _) = store.private_key_chain().unwrap();
+        assert_eq!(alias, "www.example.com");
+
+        let p12_path = dir.join("bundle.p12");
+        fs::write(&p12_path, &p12.data).unwrap();
+        let mut reloaded = Bundle::default();
+        reloaded.load(&p12_path, "secret").unwrap();
+        assert_eq!(reloaded.certs, bundle.certs);
+        assert_eq!(reloaded.key.unwrap().serialize_der(), key.serialize_der());
+        assert!(matches!(
+            Bundle::default().load(&p12_path, "wrong"),
+            Err(CertError::Pkcs12 { .. })
+        ));
+
+        opts.to = ConvertFormat::Der;
+        opts.key_only = true;
+        assert_eq!(
+            convert(&bundle, &opts, "").unwrap().data,
+            key.serialize_der()
+        );
+        opts.key_only = false;
+        opts.no_key = true;
+        opts.to = ConvertFormat::Pem;
+        let pem = convert(&bundle, &opts, "").unwrap();
+        assert!(!pem.has_key);
+        assert_eq!(
+            String::from_utf8(pem.data).unwrap(),
+            format!("{}{}", leaf.pem(), ca.pem())
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,314 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+use rcgen::KeyPair;
+use time::{Duration, OffsetDateTime};
+
+use crate::utils::pki::{self, EcCurve, PkiError, Subject};
+
+// 新建本地 CA 时的文件名，放在输出目录下，下次用 --ca-cert / --ca-key 复用
+const CA_NAME: &str = "rtoolkit-ca";
+
+#[derive(clap::Args)]
+pub struct CertgenOpts {
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "证书主题 CN，同时作为第一个 SAN，例如 dev.local"
+    )]
+    cn: String,
+
+    #[arg(
+        long = "san",
+        value_name = "NAME",
+        value_delimiter = ',',
+        help = "额外的主题备用名称，可重复或逗号分隔：域名（支持 *.example.com）、IP 地址或邮箱"
+    )]
+    sans: Vec<String>,
+
+    #[arg(
+        short = 'O',
+        long = "org",
+        value_name = "NAME",
+        help = "主题 O（组织名）"
+    )]
+    org: Option<String>,
+
+    #[arg(long, value_name = "N", default_value_t = 365, help = "证书有效天数")]
+    days: u32,
+
+    #[arg(
+        value_enum,
+        long = "key-type",
+        default_value_t = KeyType::Ec,
+        help = "私钥类型：ec 为 P-256，rsa 为 2048 位"
+    )]
+    key_type: KeyType,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["ca_cert", "ca_key"],
+        help = "同时生成本地 CA（rtoolkit-ca.crt / rtoolkit-ca.key），由它签发证书；信任这个 CA 后浏览器不再告警"
+    )]
+    ca: bool,
+
+    #[arg(
+        long = "ca-cert",
+        value_name = "FILE",
+        requires = "ca_key",
+        help = "用已有的 CA 证书签发，通常是之前 --ca 生成的 rtoolkit-ca.crt"
+    )]
+    ca_cert: Option<PathBuf>,
+
+    #[arg(
+        long = "ca-key",
+        value_name = "FILE",
+        requires = "ca_cert",
+        help = "--ca-cert 对应的 CA 私钥（PKCS#8 或 PKCS#1 PEM）"
+    )]
+    ca_key: Option<PathBuf>,
+
+    #[arg(
+        long = "ca-days",
+        value_name = "N",
+        default_value_t = 3650,
+        help = "新建 CA 的有效天数"
+    )]
+    ca_days: u32,
+
+    #[arg(long, help = "同时为私钥生成 CSR（NAME.csr），用于向正式 CA 申请证书")]
+    csr: bool,
+
+    #[arg(
+        short = 'o',
+        long = "out-dir",
+        value_name = "DIR",
+        default_value = ".",
+        help = "输出目录，不存在时自动创建"
+    )]
+    out_dir: PathBuf,
+
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "输出文件名（不含扩展名），默认取 CN，通配符 * 写作 _wildcard"
+    )]
+    name: Option<String>,
+
+    #[arg(short = 'f', long, help = "覆盖已存在的文件")]
+    force: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum KeyType {
+    Ec,
+    Rsa,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum CertgenError {
+    #[error(transparent)]
+    Pki(#[from] PkiError),
+    #[error("{0} already exists, use --force to overwrite")]
+    Exists(PathBuf),
+    #[error("cannot read {path}: {source}")]
+    Open {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+// 生成的一组文件，key 为私钥路径，cert 为证书路径
+struct Generated {
+    key: PathBuf,
+    cert: PathBuf,
+    csr: Option<PathBuf>,
+    ca: Option<PathBuf>,
+}
+
+pub fn run_certgen(opts: CertgenOpts) -> Result<(), CertgenError> {
+    let generated = generate(&opts)?;
+    let sans = opts.all_sans();
+    let expires = (OffsetDateTime::now_utc() + Duration::days(opts.days.into())).date();
+
+    println!("Private key: {}", generated.key.display());
+    println!("Certificate: {}", generated.cert.display());
+    println!("  Subject:   CN={}", opts.cn);
+    println!("  SAN:       {}", sans.join(", "));
+    println!("  Expires:   {}", expires);
+    if let Some(csr) = &generated.csr {
+        println!("CSR:         {}", csr.display());
+    }
+    match (&generated.ca, &opts.ca_cert) {
+        (Some(ca), _) => {
+            println!("CA:          {}", ca.display());
+            eprintln!(
+                "Add {} to the system or browser trust store so the certificate is accepted; keep {} private",
+                ca.display(),
+                ca.with_extension("key").display()
+            );
+        }
+        (None, Some(ca)) => println!("Issuer:      {}", ca.display()),
+        (None, None) => eprintln!(
+            "The certificate is self-signed; clients need to trust it explicitly or skip verification (e.g. curl -k)"
+        ),
+    }
+    Ok(())
+}
+
+fn generate(opts: &CertgenOpts) -> Result<Generated, CertgenError> {
+    let name = opts
+        .name
+        .clone()
+        .unwrap_or_else(|| opts.cn.replace('*', "_wildcard"));
+    let key_path = opts.out_dir.join(format!("{}.key", name));
+    let cert_path = opts.out_dir.join(format!("{}.crt", name));
+    let csr_path = opts.csr.then(|| opts.out_dir.join(format!("{}.csr", name)));
+    let ca_paths = opts.ca.then(|| {
+        (
+            opts.out_dir.join(format!("{}.key", CA_NAME)),
+            opts.out_dir.join(format!("{}.crt", CA_NAME)),
+        )
+    });
+    // 先检查全部目标文件，避免写了一半才发现冲突
+    if !opts.force {
+        let mut targets = vec![&key_path, &cert_path];
+        targets.extend(csr_path.as_ref());
+        if let Some((ca_key, ca_cert)) = &ca_paths {
+            targets.extend([ca_key, ca_cert]);
+        }
+        if let Some(path) = targets.into_iter().find(|path| path.exists()) {
+            return Err(CertgenError::Exists(path.clone()));
+        }
+    }
+    fs::create_dir_all(&opts.out_dir)?;
+
+    let subject = Subject {
+        common_name: Some(opts.cn.clone()),
+        organization: opts.org.clone(),
+        sans: opts.all_sans(),
+    };
+    let key = new_key(opts.key_type)?;
+
+    let cert = match (&ca_paths, &opts.ca_cert, &opts.ca_key) {
+        (Some((ca_key_path, ca_cert_path)), _, _) => {
+            let ca_key = new_key(opts.key_type)?;
+            let ca_subject = Subject {
+                common_name: Some("rtoolkit local development CA".to_string()),
+                organization: opts.org.clone(),
+                sans: Vec::new(),
+            };
+            let ca_cert = ca_subject.self_signed_pem(&ca_key, opts.ca_days, true)?;
+            pki::write_private_file(ca_key_path, ca_key.serialize_pem().as_bytes())?;
+            fs::write(ca_cert_path, &ca_cert)?;
+            subject.signed_pem(&key, opts.days, &ca_cert, &ca_key)?
+        }
+        (None, Some(ca_cert_path), Some(ca_key_path)) => {
+            let ca_cert = read_text(ca_cert_path)?;
+            let ca_key = pki::load_key_pair(&read_text(ca_key_path)?)?;
+            subject.signed_pem(&key, opts.days, &ca_cert, &ca_key)?
+        }
+        _ => subject.self_signed_pem(&key, opts.days, false)?,
+    };
+    pki::write_private_file(&key_path, key.serialize_pem().as_bytes())?;
+    fs::write(&cert_path, cert)?;
+    if let Some(path) = &csr_path {
+        fs::write(path, subject.csr_pem(&key)?)?;
+    }
+    Ok(Generated {
+        key: key_path,
+        cert: cert_path,
+        csr: csr_path,
+        ca: ca_paths.map(|(_, cert)| cert),
+    })
+}
+
+impl CertgenOpts {
+    // CN 总是作为第一个 SAN，--san 只需要写额外的名称
+    fn all_sans(&self) -> Vec<String> {
+        let mut sans = vec![self.cn.clone()];
+        sans.extend(self.sans.iter().filter(|san| **san != self.cn).cloned());
+        sans
+    }
+}
+
+fn new_key(key_type: KeyType) -> Result<KeyPair, PkiError> {
+    match key_type {
+        KeyType::Ec => pki::generate_ec(EcCurve::P256),
+        KeyType::Rsa => pki::generate_rsa(2048),
+    }
+}
+
+fn read_text(path: &Path) -> Result<String, CertgenError> {
+    fs::read_to_string(path).map_err(|source| CertgenError::Open {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use x509_parser::pem::parse_x509_pem;
+
+    #[test]
+    fn test_ca_signed_leaf() {
+        let dir = std::env::temp_dir().join(format!("rtoolkit-certgen-{}", std::process::id()));
+        let opts = CertgenOpts {
+            cn: "*.dev.local".into(),
+            sans: vec!["127.0.0.1".into()],
+            org: None,
+            days: 30,
+            key_type: KeyType::Ec,
+            ca: true,
+            ca_cert: None,
+            ca_key: None,
+            ca_days: 365,
+            csr: false,
+            out_dir: dir.clone(),
+            name: None,
+            force: false,
+        };
+        let generated = generate(&opts).unwrap();
+        assert_eq!(generated.cert, dir.join("_wildcard.dev.local.crt"));
+        assert!(matches!(generate(&opts), Err(CertgenError::Exists(_))));
+
+        let ca_pem = fs::read_to_string(generated.ca.unwrap()).unwrap();
+        let leaf_pem = fs::read_to_string(&generated.cert).unwrap();
+        let (_, ca_pem) = parse_x509_pem(ca_pem.as_bytes()).unwrap();
+        let (_, leaf_pem) = parse_x509_pem(leaf_pem.as_bytes()).unwrap();
+        let ca = ca_pem.parse_x509().unwrap();
+        let leaf = leaf_pem.parse_x509().unwrap();
+        assert!(ca.is_ca());
+        assert!(!leaf.is_ca());
+        assert_eq!(leaf.issuer(), ca.subject());
+        leaf.verify_signature(Some(ca.public_key())).unwrap();
+        let sans = leaf.subject_alternative_name().unwrap().unwrap();
+        assert_eq!(sans.value.general_names.len(), 2);
+
+        // 复用已有 CA 签发第二张证书
+        let reuse = CertgenOpts {
+            cn: "api.dev.local".into(),
+            sans: Vec::new(),
+            ca: false,
+            ca_cert: Some(dir.join("rtoolkit-ca.crt")),
+            ca_key: Some(dir.join("rtoolkit-ca.key")),
+            csr: true,
+            ..opts
+        };
+        let second = generate(&reuse).unwrap();
+        assert!(second.csr.unwrap().exists());
+        let pem = fs::read_to_string(&second.cert).unwrap();
+        let (_, pem) = parse_x509_pem(pem.as_bytes()).unwrap();
+        pem.parse_x509()
+            .unwrap()
+            .verify_signature(Some(ca.public_key()))
+            .unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,424 @@
+use std::io::{self, BufRead};
+use std::net::{IpAddr, Ipv4Addr};
+
+use clap::Subcommand;
+use ipnet::{IpNet, Ipv4Net, Ipv4Subnets, Ipv6Subnets};
+use serde::Serialize;
+
+use crate::commands::ipinfo::special_range;
+use crate::utils::output::RecordOutput;
+
+// split 最多拆出 2^16 个子网，防止 /8 拆成 /32 之类的误操作刷屏
+const MAX_SPLIT_BITS: u8 = 16;
+
+#[derive(clap::Args)]
+pub struct CidrOpts {
+    #[command(subcommand)]
+    command: Option<CidrCommand>,
+
+    #[arg(
+        value_name = "CIDR",
+        help = "网段，例如 10.0.0.0/22、192.168.1.10/255.255.255.0、2001:db8::/48，不带前缀时视为单个地址"
+    )]
+    network: Option<String>,
+
+    #[arg(long, help = "以 JSON 输出网段信息")]
+    json: bool,
+
+    // 全局 --where / --fields / --rename，由命令行入口注入
+    #[arg(skip)]
+    pub(crate) record_output: RecordOutput,
+}
+
+impl CidrOpts {
+    // 只有网段信息（不带子命令）支持全局 --where / --fields / --rename
+    pub(crate) fn record_output(&mut self) -> Option<&mut RecordOutput> {
+        self.command.is_none().then_some(&mut self.record_output)
+    }
+}
+
+#[derive(Subcommand)]
+enum CidrCommand {
+    #[command(about = "把网段拆分为更小的子网")]
+    Split(SplitOpts),
+    #[command(about = "检查 IP 或子网是否属于网段，有不属于的时以非零状态退出")]
+    Contains(ContainsOpts),
+    #[command(about = "把 IP、网段和地址范围合并为最少的 CIDR 列表")]
+    Summarize(SummarizeOpts),
+}
+
+#[derive(clap::Args)]
+struct SplitOpts {
+    #[arg(value_name = "CIDR", help = "要拆分的网段")]
+    network: String,
+
+    #[arg(
+        long,
+        value_name = "PREFIX",
+        required_unless_present = "count",
+        conflicts_with = "count",
+        help = "子网前缀长度，例如 /24 或 24"
+    )]
+    into: Option<String>,
+
+    #[arg(
+        short = 'n',
+        long,
+        value_name = "N",
+        help = "至少拆分为 N 个等大的子网，前缀长度自动计算"
+    )]
+    count: Option<u64>,
+}
+
+#[derive(clap::Args)]
+struct ContainsOpts {
+    #[arg(value_name = "CIDR", help = "网段")]
+    network: String,
+
+    #[arg(value_name = "IP|CIDR", required = true, help = "要检查的 IP 或子网")]
+    targets: Vec<String>,
+}
+
+#[derive(clap::Args)]
+struct SummarizeOpts {
+    #[arg(
+        value_name = "ITEM",
+        help = "IP、CIDR 或 起始IP-结束IP 范围，省略或为 - 时从 stdin 读取（空白或逗号分隔，# 开头为注释）"
+    )]
+    items: Vec<String>,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum CidrError {
+    #[error("invalid network '{0}', expected IP, IP/PREFIX or IP/NETMASK")]
+    InvalidNetwork(String),
+    #[error("invalid range '{0}', expected START-END of the same address family")]
+    InvalidRange(String),
+    #[error("invalid prefix '{0}' for {1}, expected a longer prefix within the address family")]
+    InvalidPrefix(String, IpNet),
+    #[error("splitting {network} into /{prefix} yields more than 65536 subnets")]
+    TooManySubnets { network: IpNet, prefix: u8 },
+    #[error("{0} of the given addresses are not in the network")]
+    NotContained(usize),
+    #[error("a network is required, e.g. rtoolkit cidr 10.0.0.0/22")]
+    MissingNetwork,
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("json serialize failed: {0}")]
+    Serialize(#[from] serde_json::Error),
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct CidrInfo {
+    pub address: String,
+    pub network: String,
+    pub prefix: u8,
+    pub netmask: String,
+    pub wildcard: String,
+    // IPv6 没有广播地址，IPv4 的 /31、/32 也没有
+    pub broadcast: Option<String>,
+    pub first_host: String,
+    pub last_host: String,
+    // 十进制字符串：IPv6 的 ::/0 有 2^128 个地址，超出 u128 和 JSON 数值的精度
+    pub hosts: String,
+    // 整个网段所在的私有或保留地址段，例如 "10.0.0.0/8 private-use (RFC 1918)"
+    pub range: Option<String>,
+}
+
+pub fn run_cidr(opts: CidrOpts) -> Result<(), CidrError> {
+    match opts.command {
+        Some(CidrCommand::Split(split)) => return run_split(split),
+        Some(CidrCommand::Contains(contains)) => return run_contains(contains),
+        Some(CidrCommand::Summarize(summarize)) => return run_summarize(summarize),
+        None => {}
+    }
+    let raw = opts.network.ok_or(CidrError::MissingNetwork)?;
+    let info = cidr_info(parse_network(&raw)?);
+    if !opts.record_output.keep(&info)? {
+        return Ok(());
+    }
+    if opts.json {
+        opts.record_output
+            .write_report(&info, None, io::stdout().lock())?;
+        return Ok(());
+    }
+    let netmask = format!("{} = {}", info.netmask, info.prefix);
+    let fields = [
+        ("Address", Some(&info.address)),
+        ("Network", Some(&info.network)),
+        ("Netmask", Some(&netmask)),
+        ("Wildcard", Some(&info.wildcard)),
+        ("Broadcast", info.broadcast.as_ref()),
+        ("HostMin", Some(&info.first_host)),
+        ("HostMax", Some(&info.last_host)),
+        ("Hosts", Some(&info.hosts)),
+        ("Type", info.range.as_ref()),
+    ];
+    for (name, value) in fields {
+        if let Some(value) = value {
+            println!("{:<11}{}", format!("{}:", name), value);
+        }
+    }
+    Ok(())
+}
+
+fn run_split(opts: SplitOpts) -> Result<(), CidrError> {
+    let network = parse_network(&opts.network)?.trunc();
+    let prefix = match (&opts.into, opts.count) {
+        (Some(into), _) => into
+            .trim_start_matches('/')
+            .parse::<u8>()
+            .ok()
+            .filter(|prefix| (network.prefix_len()..=network.max_prefix_len()).contains(prefix))
+            .ok_or_else(|| CidrError::InvalidPrefix(into.clone(), network))?,
+        (None, Some(count)) => {
+            // 向上取到 2 的幂
+            let bits = count.max(1).next_power_of_two().trailing_zeros() as u8;
+            network
+                .prefix_len()
+                .checked_add(bits)
+                .filter(|prefix| *prefix <= network.max_prefix_len())
+                .ok_or_else(|| CidrError::InvalidPrefix(count.to_string(), network))?
+        }
+        (None, None) => unreachable!("clap requires --into or --count"),
+    };
+    if prefix - network.prefix_len() > MAX_SPLIT_BITS {
+        return Err(CidrError::TooManySubnets { network, prefix });
+    }
+    let subnets = network
+        .subnets(prefix)
+        .map_err(|_| CidrError::InvalidPrefix(prefix.to_string(), network))?;
+    for subnet in subnets {
+        println!("{}", subnet);
+    }
+    Ok(())
+}
+
+fn run_contains(opts: ContainsOpts) -> Result<(), CidrError> {
+    let network = parse_network(&opts.network)?.trunc();
+    let mut missing = 0;
+    for raw in &opts.targets {
+        let target = parse_network(raw)?;
+        // 单个地址按原样输出，不带 /32
+        let shown = if target.prefix_len() == target.max_prefix_len() {
+            target.addr().to_string()
+        } else {
+            target.trunc().to_string()
+        };
+        if network.contains(&target) {
+            println!("{} in {}", shown, network);
+        } else {
+            missing += 1;
+            println!("{} not in {}", shown, network);
+        }
+    }
+    if missing > 0 {
+        return Err(CidrError::NotContained(missing));
+    }
+    Ok(())
+}
+
+fn run_summarize(opts: SummarizeOpts) -> Result<(), CidrError> {
+    let mut items = Vec::new();
+    if !opts.items.is_empty() && opts.items.iter().all(|item| item != "-") {
+        items = opts.items;
+    } else {
+        for line in io::stdin().lock().lines() {
+            let line = line?;
+            let line = line.split('#').next().unwrap_or_default();
+            items.extend(
+                line.split(|c: char| c.is_whitespace() || c == ',')
+                    .filter(|item| !item.is_empty())
+                    .map(str::to_string),
+            );
+        }
+    }
+    let mut networks = Vec::new();
+    for item in &items {
+        networks.extend(parse_item(item)?);
+    }
+    for network in summarize(&networks) {
+        println!("{}", network);
+    }
+    Ok(())
+}
+
+// 支持 IP、IP/PREFIX 和 IPv4 的 IP/NETMASK；保留主机位，由调用方决定是否取网络地址
+pub fn parse_network(raw: &str) -> Result<IpNet, CidrError> {
+    let invalid = || CidrError::InvalidNetwork(raw.to_string());
+    let raw = raw.trim();
+    let Some((addr, suffix)) = raw.split_once('/') else {
+        return raw
+            .parse::<IpAddr>()
+            .map(IpNet::from)
+            .map_err(|_| invalid());
+    };
+    if let Ok(network) = raw.parse::<IpNet>() {
+        return Ok(network);
+    }
+    let addr = addr.parse().map_err(|_| invalid())?;
+    let mask = suffix.parse().map_err(|_| invalid())?;
+    Ipv4Net::with_netmask(addr, mask)
+        .map(IpNet::V4)
+        .map_err(|_| invalid())
+}
+
+// 地址范围按最少的 CIDR 展开
+fn parse_item(raw: &str) -> Result<Vec<IpNet>, CidrError> {
+    let Some((start, end)) = raw.split_once('-') else {
+        return Ok(vec![parse_network(raw)?.trunc()]);
+    };
+    let invalid = || CidrError::InvalidRange(raw.to_string());
+    let start = start.trim().parse::<IpAddr>().map_err(|_| invalid())?;
+    let end = end.trim().parse::<IpAddr>().map_err(|_| invalid())?;
+    match (start, end) {
+        (IpAddr::V4(start), IpAddr::V4(end)) if start <= end => {
+            Ok(Ipv4Subnets::new(start, end, 0).map(IpNet::V4).collect())
+        }
+        (IpAddr::V6(start), IpAddr::V6(end)) if start <= end => {
+            Ok(Ipv6Subnets::new(start, end, 0).map(IpNet::V6).collect())
+        }
+        _ => Err(invalid()),
+    }
+}
+
+// 合并重叠和相邻的网段，IPv4 排在 IPv6 前面
+pub fn summarize(networks: &[IpNet]) -> Vec<IpNet> {
+    IpNet::aggregate(&networks.to_vec())
+}
+
+pub fn cidr_info(net: IpNet) -> CidrInfo {
+    let network = net.network();
+    let last = net.broadcast();
+    let host_bits = net.max_prefix_len() - net.prefix_len();
+    // IPv4 网段去掉网络地址和广播地址；/31 两个地址都可用（RFC 3021）
+    let (first_host, last_host, hosts, broadcast) = match net {
+        IpNet::V4(v4) if host_bits >= 2 => (
+            Ipv4Addr::from(u32::from(v4.network()) + 1).into(),
+            Ipv4Addr::from(u32::from(v4.broadcast()) - 1).into(),
+            ((1u64 << host_bits) - 2).to_string(),
+            Some(last.to_string()),
+        ),
+        _ => match host_bits {
+            128 => (
+                network,
+                last,
+                "340282366920938463463374607431768211456".to_string(),
+                None,
+            ),
+            bits => (network, last, (1u128 << bits).to_string(), None),
+        },
+    };
+    CidrInfo {
+        address: net.addr().to_string(),
+        network: net.trunc().to_string(),
+        prefix: net.prefix_len(),
+        netmask: net.netmask().to_string(),
+        wildcard: net.hostmask().to_string(),
+        broadcast,
+        first_host: first_host.to_string(),
+        last_host: last_host.to_string(),
+        hosts,
+        range: network_range(net),
+    }
+}
+
+// 网段的首尾地址落在同一个特殊地址段内才注明，只看首地址会把 0.0.0.0/0、::/0 算成保留地址
+fn network_range(net: IpNet) -> Option<String> {
+    let (range, name, private) = special_range(net.network())?;
+    if special_range(net.broadcast()).map(|(last, ..)| last) != Some(range) {
+        return None;
+    }
+    // “2000::/3 以外”不是连续的地址段，首尾都在其中时网段仍可能跨过 2000::/3
+    let global: IpNet = "2000::/3".parse().expect("valid network");
+    if net.contains(&global) {
+        return None;
+    }
+    let kind = if private { "private" } else { "bogon" };
+    Some(format!("{}, {} {}", kind, range, name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cidr_info() {
+        let info = cidr_info(parse_network("10.0.1.7/22").unwrap());
+        assert_eq!(info.address, "10.0.1.7");
+        assert_eq!(info.network, "10.0.0.0/22");
+        assert_eq!(info.netmask, "255.255.252.0");
+        assert_eq!(info.wildcard, "0.0.3.255");
+        assert_eq!(info.broadcast.as_deref(), Some("10.0.3.255"));
+        assert_eq!(info.first_host, "10.0.0.1");
+        assert_eq!(info.last_host, "10.0.3.254");
+        assert_eq!(info.hosts, "1022");
+        assert_eq!(
+            info.range.as_deref(),
+            Some("private, 10.0.0.0/8 private-use (RFC 1918)")
+        );
+
+        let info = cidr_info(parse_network("192.168.1.10/255.255.255.254").unwrap());
+        assert_eq!(info.network, "192.168.1.10/31");
+        assert_eq!(info.hosts, "2");
+        assert_eq!(info.broadcast, None);
+        assert_eq!(cidr_info(parse_network("8.8.8.8").unwrap()).hosts, "1");
+
+        let info = cidr_info(parse_network("2001:db8::/48").unwrap());
+        assert_eq!(info.last_host, "2001:db8:0:ffff:ffff:ffff:ffff:ffff");
+        assert_eq!(info.hosts, (1u128 << 80).to_string());
+        let info = cidr_info(parse_network("::/0").unwrap());
+        assert_eq!(info.hosts, "340282366920938463463374607431768211456");
+        assert_eq!(info.range, None);
+        assert_eq!(cidr_info(parse_network("0.0.0.0/0").unwrap()).range, None);
+        assert_eq!(cidr_info(parse_network("10.0.0.0/7").unwrap()).range, None);
+        assert_eq!(cidr_info(parse_network("::/1").unwrap()).range, None);
+        assert_eq!(
+            cidr_info(parse_network("4000::/3").unwrap())
+                .range
+                .as_deref(),
+            Some("bogon, ::/0 outside global unicast 2000::/3")
+        );
+        assert_eq!(
+            cidr_info(parse_network("0.0.0.0/8").unwrap())
+                .range
+                .as_deref(),
+            Some("bogon, 0.0.0.0/8 this network (RFC 791)")
+        );
+
+        assert!(parse_network("10.0.0.0/33").is_err());
+        assert!(parse_network("10.0.0.0/255.0.255.0").is_err());
+    }
+
+    #[test]
+    fn test_summarize_items() {
+        let mut networks = Vec::new();
+        for item in [
+            "10.0.1.0/24",
+            "10.0.0.0/24",
+            "10.0.2.5",
+            "192.168.0.10-192.168.0.17",
+            "2001:db8::/33",
+            "2001:db8:8000::/33",
+        ] {
+            networks.extend(parse_item(item).unwrap());
+        }
+        let summary: Vec<String> = summarize(&networks)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                "10.0.0.0/23",
+                "10.0.2.5/32",
+                "192.168.0.10/31",
+                "192.168.0.12/30",
+                "192.168.0.16/31",
+                "2001:db8::/32"
+            ]
+        );
+        assert!(parse_item("10.0.0.9-10.0.0.1").is_err());
+        assert!(parse_item("10.0.0.1-::1").is_err());
+    }
+}
@@ -0,0 +1,399 @@
+use std::collections::{BTreeSet, HashMap};
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::utils::dataset::{read_records, text_of, DatasetError, Record};
+use crate::utils::output::RecordOutput;
+
+#[derive(clap::Args)]
+pub struct DataDiffOpts {
+    #[arg(
+        value_name = "EXPECTED",
+        help = "期望数据：CSV（首行为表头）、JSON 数组（.json）或 JSON Lines（.jsonl）"
+    )]
+    expected: PathBuf,
+
+    #[arg(value_name = "ACTUAL", help = "实际数据，格式同上，两边可以不同")]
+    actual: PathBuf,
+
+    #[arg(
+        short = 'k',
+        long,
+        value_name = "FIELDS",
+        value_delimiter = ',',
+        required = true,
+        help = "主键字段，多个字段用逗号分隔组成联合主键"
+    )]
+    key: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "FIELDS",
+        value_delimiter = ',',
+        help = "比较时忽略的字段，例如时间戳、自增 ID"
+    )]
+    ignore: Vec<String>,
+
+    #[arg(
+        value_enum,
+        short = 'o',
+        long = "output",
+        value_name = "FMT",
+        default_value_t = DiffFormat::Plain,
+        help = "输出格式 plain | json"
+    )]
+    output: DiffFormat,
+
+    #[arg(short = 'q', long, help = "纯文本输出只打印汇总")]
+    quiet: bool,
+
+    // 全局 --where / --fields / --rename，由命令行入口注入
+    #[arg(skip)]
+    pub(crate) record_output: RecordOutput,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DiffFormat {
+    Plain,
+    Json,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum DataDiffError {
+    #[error(transparent)]
+    Dataset(#[from] DatasetError),
+    #[error("{file} record {index} has no key field '{field}'")]
+    MissingKey {
+        file: String,
+        index: usize,
+        field: String,
+    },
+    #[error("{file} has duplicate key {key}")]
+    DuplicateKey { file: String, key: String },
+    #[error("{0} differences found")]
+    Differences(usize),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct DiffReport {
+    pub summary: DiffSummary,
+    // 只在一侧出现的字段（列），不计入差异
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub missing_fields: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub extra_fields: Vec<String>,
+    pub missing: Vec<Map<String, Value>>,
+    pub extra: Vec<Map<String, Value>>,
+    pub changed: Vec<ChangedRow>,
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+pub struct DiffSummary {
+    pub expected: usize,
+    pub actual: usize,
+    pub matched: usize,
+    pub missing: usize,
+    pub extra: usize,
+    pub changed: usize,
+}
+
+impl DiffSummary {
+    fn differences(&self) -> usize {
+        self.missing + self.extra + self.changed
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ChangedRow {
+    pub key: Map<String, Value>,
+    pub fields: Vec<FieldDiff>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct FieldDiff {
+    pub field: String,
+    pub expected: Value,
+    pub actual: Value,
+}
+
+pub fn run_data_diff(opts: DataDiffOpts) -> Result<(), DataDiffError> {
+    let expected = read_records(&opts.expected)?;
+    let actual = read_records(&opts.actual)?;
+    let report = diff_records(
+        (&opts.expected.display().to_string(), &expected),
+        (&opts.actual.display().to_string(), &actual),
+        &opts.key,
+        &opts.ignore,
+    )?;
+
+    let mut out = BufWriter::new(io::stdout().lock());
+    // --where 不匹配时不输出报告，退出状态照常
+    if opts.record_output.keep(&report)? {
+        match opts.output {
+            DiffFormat::Json => {
+                opts.record_output.write_report(&report, None, &mut out)?;
+            }
+            DiffFormat::Plain => write_plain(&report, opts.quiet, &mut out)?,
+        }
+    }
+    out.flush()?;
+    // 存在差异时以非零状态退出，便于在 CI 中直接断言
+    match report.summary.differences() {
+        0 => Ok(()),
+        n => Err(DataDiffError::Differences(n)),
+    }
+}
+
+// 按主键对齐两份数据：missing / changed 按期望数据的顺序，extra 按实际数据的顺序。
+// 字段值按文本比较，因此 CSV 中的 "1" 与 JSON 中的 1 视为相同
+pub fn diff_records(
+    (expected_name, expected): (&str, &[Record]),
+    (actual_name, actual): (&str, &[Record]),
+    key: &[String],
+    ignore: &[String],
+) -> Result<DiffReport, DataDiffError> {
+    let actual_index = index_by_key(actual_name, actual, key)?;
+    let expected_index = index_by_key(expected_name, expected, key)?;
+
+    let expected_fields = field_names(expected);
+    let actual_fields = field_names(actual);
+    let compared: Vec<&String> = expected_fields
+        .iter()
+        .filter(|field| actual_fields.contains(*field))
+        .filter(|field| !key.contains(field) && !ignore.contains(field))
+        .collect();
+
+    let mut report = DiffReport {
+        summary: DiffSummary {
+            expected: expected.len(),
+            actual: actual.len(),
+            ..DiffSummary::default()
+        },
+        missing_fields: expected_fields
+            .iter()
+            .filter(|field| !actual_fields.contains(*field) && !ignore.contains(field))
+            .cloned()
+            .collect(),
+        extra_fields: actual_fields
+            .iter()
+            .filter(|field| !expected_fields.contains(*field) && !ignore.contains(field))
+            .cloned()
+            .collect(),
+        ..DiffReport::default()
+    };
+    for record in expected {
+        let record_key = key_of(record, key);
+        let Some(&other) = actual_index.get(&key_text(&record_key)) else {
+            report.missing.push(record.clone());
+            continue;
+        };
+        let fields: Vec<FieldDiff> = compared
+            .iter()
+            .filter_map(|field| {
+                let left = record.get(*field).unwrap_or(&Value::Null);
+                let right = actual[other].get(*field).unwrap_or(&Value::Null);
+                (text_of(left) != text_of(right)).then(|| FieldDiff {
+                    field: field.to_string(),
+                    expected: left.clone(),
+                    actual: right.clone(),
+                })
+            })
+            .collect();
+        if fields.is_empty() {
+            report.summary.matched += 1;
+        } else {
+            report.changed.push(ChangedRow {
+                key: record_key,
+                fields,
+            });
+        }
+    }
+    for record in actual {
+        if !expected_index.contains_key(&key_text(&key_of(record, key))) {
+            report.extra.push(record.clone());
+        }
+    }
+    report.summary.missing = report.missing.len();
+    report.summary.extra = report.extra.len();
+    report.summary.changed = report.changed.len();
+    Ok(report)
+}
+
+fn index_by_key(
+    file: &str,
+    records: &[Record],
+    key: &[String],
+) -> Result<HashMap<String, usize>, DataDiffError> {
+    let mut index = HashMap::with_capacity(records.len());
+    for (position, record) in records.iter().enumerate() {
+        if let Some(field) = key.iter().find(|field| !record.contains_key(*field)) {
+            return Err(DataDiffError::MissingKey {
+                file: file.to_string(),
+                index: position + 1,
+                field: field.clone(),
+            });
+        }
+        let text = key_text(&key_of(record, key));
+        if index.insert(text.clone(), position).is_some() {
+            return Err(DataDiffError::DuplicateKey {
+                file: file.to_string(),
+                key: text,
+            });
+        }
+    }
+    Ok(index)
+}
+
+fn key_of(record: &Record, key: &[String]) -> Map<String, Value> {
+    key.iter()
+        .map(|field| {
+            let value = record.get(field).cloned().unwrap_or(Value::Null);
+            (field.clone(), value)
+        })
+        .collect()
+}
+
+// 主键的文本形式，也用于纯文本输出，例如 id=3 或 region=110101,seq=7
+fn key_text(key: &Map<String, Value>) -> String {
+    key.iter()
+        .map(|(field, value)| format!("{}={}", field, text_of(value)))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+// 按首次出现的顺序收集字段名
+fn field_names(records: &[Record]) -> Vec<String> {
+    let mut seen = BTreeSet::new();
+    records
+        .iter()
+        .flat_map(|record| record.keys())
+        .filter(|field| seen.insert(field.as_str()))
+        .cloned()
+        .collect()
+}
+
+fn write_plain<W: Write>(report: &DiffReport, quiet: bool, out: &mut W) -> io::Result<()> {
+    if !quiet {
+        for field in &report.missing_fields {
+            writeln!(out, "field only in expected: {}", field)?;
+        }
+        for field in &report.extra_fields {
+            writeln!(out, "field only in actual: {}", field)?;
+        }
+        for record in &report.missing {
+            writeln!(out, "- missing  {}", Value::Object(record.clone()))?;
+        }
+        for record in &report.extra {
+            writeln!(out, "+ extra    {}", Value::Object(record.clone()))?;
+        }
+        for row in &report.changed {
+            writeln!(out, "~ changed  {}", key_text(&row.key))?;
+            for diff in &row.fields {
+                writeln!(
+                    out,
+                    "    {}: {} -> {}",
+                    diff.field, diff.expected, diff.actual
+                )?;
+            }
+        }
+        if report.summary.differences() > 0 {
+            writeln!(out)?;
+        }
+    }
+    let summary = &report.summary;
+    writeln!(
+        out,
+        "Expected: {}  Actual: {}  Matched: {}  Missing: {}  Extra: {}  Changed: {}",
+        summary.expected,
+        summary.actual,
+        summary.matched,
+        summary.missing,
+        summary.extra,
+        summary.changed
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn records(value: Value) -> Vec<Record> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_diff_records_by_key() {
+        let expected = records(json!([
+            {"id": "1", "name": "张三", "age": "30", "updated": "a"},
+            {"id": "2", "name": "李四", "age": "41", "updated": "a"},
+            {"id": "3", "name": "王五", "age": "25", "updated": "a"},
+        ]));
+        // 实际数据来自 JSON：数字 30 与 CSV 的 "30" 视为相同
+        let actual = records(json!([
+            {"id": 3, "name": "王五", "age": 26, "updated": "b", "note": "x"},
+            {"id": 1, "name": "张三", "age": 30, "updated": "b", "note": ""},
+            {"id": 4, "name": "赵六", "age": 33, "updated": "b", "note": ""},
+        ]));
+        let key = vec!["id".to_string()];
+        let report = diff_records(
+            ("expected.csv", &expected),
+            ("actual.json", &actual),
+            &key,
+            &["updated".to_string()],
+        )
+        .unwrap();
+        assert_eq!(
+            report.summary,
+            DiffSummary {
+                expected: 3,
+                actual: 3,
+                matched: 1,
+                missing: 1,
+                extra: 1,
+                changed: 1,
+            }
+        );
+        assert_eq!(report.missing[0]["id"], "2");
+        assert_eq!(report.extra[0]["id"], 4);
+        assert_eq!(key_text(&report.changed[0].key), "id=3");
+        assert_eq!(
+            report.changed[0].fields,
+            [FieldDiff {
+                field: "age".into(),
+                expected: json!("25"),
+                actual: json!(26),
+            }]
+        );
+        assert_eq!(report.extra_fields, ["note"]);
+
+        let mut plain = Vec::new();
+        write_plain(&report, false, &mut plain).unwrap();
+        let plain = String::from_utf8(plain).unwrap();
+        assert!(plain.contains("~ changed  id=3\n    age: \"25\" -> 26\n"));
+        assert!(plain.ends_with("Matched: 1  Missing: 1  Extra: 1  Changed: 1\n"));
+
+        // 不忽略 updated 时两条对上的记录都有变化
+        let report = diff_records(("e", &expected), ("a", &actual), &key, &[]).unwrap();
+        assert_eq!(report.summary.changed, 2);
+
+        let duplicated = records(json!([{"id": "1"}, {"id": "1"}]));
+        assert!(matches!(
+            diff_records(("e", &expected), ("a", &duplicated), &key, &[]),
+            Err(DataDiffError::DuplicateKey { key, .. }) if key == "id=1"
+        ));
+        let keyless = records(json!([{"name": "x"}]));
+        assert!(matches!(
+            diff_records(("e", &keyless), ("a", &actual), &key, &[]),
+            Err(DataDiffError::MissingKey { index: 1, .. })
+        ));
+    }
+}
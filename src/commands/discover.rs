@@ -0,0 +1,412 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader, IsTerminal, Write};
+use std::path::PathBuf;
+
+use clap::ValueEnum;
+use dialoguer::{MultiSelect, Select};
+use serde::Serialize;
+
+use crate::commands::portscan::{
+    self, parse_port_spec, parse_targets, read_target_list, remote_scan, PortScanError,
+    PortScanResult, PortState, ScanSettings,
+};
+use crate::utils::a11y;
+use crate::utils::output::RecordOutput;
+
+#[derive(clap::Args)]
+pub struct DiscoverOpts {
+    #[arg(
+        short = 't',
+        long = "target",
+        value_name = "HOST",
+        required_unless_present = "target_file",
+        conflicts_with = "target_file",
+        help = "目标主机，支持逗号分隔的多个主机或 CIDR，例如 192.168.1.0/24；为 - 时从 stdin 读取"
+    )]
+    target: Option<String>,
+    #[arg(
+        long = "target-file",
+        value_name = "FILE",
+        help = "从文件读取目标列表，每行取第一列，支持 # 注释"
+    )]
+    target_file: Option<PathBuf>,
+    #[arg(
+        long = "probe-ports",
+        value_name = "PORTS",
+        default_value = "22,80,443,445,3389",
+        help = "用于判断主机存活的 TCP 端口，任一端口连接成功或被拒绝即视为存活"
+    )]
+    probe_ports: String,
+    #[arg(
+        short = 'c',
+        long = "concurrency",
+        value_name = "N",
+        default_value_t = 200,
+        help = "并发数，范围 1-1000"
+    )]
+    concurrency: usize,
+    #[arg(
+        long = "timeout",
+        value_name = "MS",
+        default_value_t = 500,
+        help = "超时时间(毫秒)，范围 50-10000"
+    )]
+    time_out: u64,
+    #[arg(
+        value_enum,
+        short = 'o',
+        long = "output",
+        value_name = "FMT",
+        default_value_t = OutputFormat::Plain,
+        help = "输出格式 plain | json"
+    )]
+    output: OutputFormat,
+    #[arg(
+        long = "pick",
+        conflicts_with = "output",
+        help = "在终端列表中勾选存活主机，并直接对选中的主机发起端口扫描"
+    )]
+    pick: bool,
+    #[arg(
+        value_enum,
+        long = "preset",
+        value_name = "PRESET",
+        requires = "pick",
+        help = "后续端口扫描使用的端口预设 top | web | all，不指定时在交互中选择"
+    )]
+    preset: Option<ScanPreset>,
+
+    // 全局 --where / --fields / --rename，由命令行入口注入
+    #[arg(skip)]
+    pub(crate) record_output: RecordOutput,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    Plain,
+    Json,
+}
+
+// 交互扫描可选的端口预设
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ScanPreset {
+    Top,
+    Web,
+    All,
+}
+
+impl ScanPreset {
+    const ALL: [ScanPreset; 3] = [ScanPreset::Top, ScanPreset::Web, ScanPreset::All];
+
+    fn ports(self) -> &'static str {
+        match self {
+            ScanPreset::Top => {
+                "21,22,23,25,53,80,110,111,135,139,143,443,445,993,995,1433,1723,3306,3389,5432,5900,6379,8080,8443"
+            }
+            ScanPreset::Web => "80,443,8000,8008,8080,8081,8443,8888,9000,9443",
+            ScanPreset::All => "-",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            ScanPreset::Top => "top   常用服务端口",
+            ScanPreset::Web => "web   常见 HTTP(S) 端口",
+            ScanPreset::All => "all   全部 65535 个端口",
+        }
+    }
+}
+
+// 一台存活主机：记录最快响应的探测端口和耗时
+#[derive(Debug, Serialize, PartialEq)]
+struct AliveHost {
+    host: String,
+    port: u16,
+    latency_ms: Option<f64>,
+}
+
+pub fn run_discover(opts: DiscoverOpts) -> Result<(), DiscoverError> {
+    let (target, hosts) = match (&opts.target_file, opts.target.as_deref()) {
+        (Some(path), _) => {
+            let file = File::open(path).map_err(|source| PortScanError::TargetFile {
+                path: path.clone(),
+                source,
+            })?;
+            (
+                path.display().to_string(),
+                read_target_list(BufReader::new(file))?,
+            )
+        }
+        (None, Some("-")) => ("stdin".to_string(), read_target_list(io::stdin().lock())?),
+        (None, target) => {
+            let target = target.unwrap_or_default().to_string();
+            let hosts = parse_targets(&target)?;
+            (target, hosts)
+        }
+    };
+    if opts.pick && !(io::stdin().is_terminal() && io::stderr().is_terminal()) {
+        return Err(DiscoverError::NotInteractive);
+    }
+    let probe_ports = parse_port_spec(&opts.probe_ports)?;
+    let settings = ScanSettings {
+        concurrency: opts.concurrency.clamp(1, 1000),
+        timeout_ms: opts.time_out.clamp(50, 10_000),
+        ..ScanSettings::default()
+    };
+
+    let rt = tokio::runtime::Runtime::new()?;
+    let sweep = rt.block_on(remote_scan(
+        &target,
+        &hosts,
+        &opts.probe_ports,
+        &probe_ports,
+        &settings,
+    ))?;
+    let mut alive = alive_hosts(&sweep);
+    opts.record_output.retain(&mut alive)?;
+
+    if !opts.pick {
+        let mut out = io::stdout().lock();
+        match opts.output {
+            OutputFormat::Json => {
+                opts.record_output.write_json(&alive, &mut out)?;
+            }
+            OutputFormat::Plain => write_plain(&alive, hosts.len(), &opts.probe_ports, &mut out)?,
+        }
+        return Ok(());
+    }
+
+    eprintln!(
+        "Discovered {} of {} hosts alive (probe ports {})",
+        alive.len(),
+        hosts.len(),
+        opts.probe_ports
+    );
+    if alive.is_empty() {
+        return Ok(());
+    }
+    let labels: Vec<String> = alive.iter().map(host_label).collect();
+    let chosen = if a11y::is_enabled() {
+        a11y::pick_numbered("Hosts to scan", &labels, true)?
+    } else {
+        MultiSelect::new()
+            .with_prompt("Select hosts to scan (space toggles, enter confirms)")
+            .items(&labels)
+            .defaults(&vec![true; labels.len()])
+            .interact_opt()?
+    };
+    let Some(chosen) = chosen else {
+        return Ok(());
+    };
+    if chosen.is_empty() {
+        eprintln!("No hosts selected.");
+        return Ok(());
+    }
+    let preset = match opts.preset {
+        Some(preset) => preset,
+        None => {
+            let labels: Vec<String> = ScanPreset::ALL
+                .iter()
+                .map(|p| p.label().to_string())
+                .collect();
+            let index = if a11y::is_enabled() {
+                a11y::pick_numbered("Port preset", &labels, false)?.map(|chosen| chosen[0])
+            } else {
+                Select::new()
+                    .with_prompt("Port preset")
+                    .items(&labels)
+                    .default(0)
+                    .interact_opt()?
+            };
+            let Some(index) = index else {
+                return Ok(());
+            };
+            ScanPreset::ALL[index]
+        }
+    };
+
+    let selected: Vec<String> = chosen.into_iter().map(|i| alive[i].host.clone()).collect();
+    let port_spec = preset.ports();
+    let ports = parse_port_spec(port_spec)?;
+    let mut result = rt.block_on(remote_scan(
+        &selected.join(","),
+        &selected,
+        port_spec,
+        &ports,
+        &settings,
+    ))?;
+    // 预设可能覆盖全部端口，只列出开放的端口，汇总行仍按全部端口统计
+    result.ports.retain(|status| status.open);
+    write_scan(&result)?;
+    Ok(())
+}
+
+// 按扫描结果判断主机存活：任一探测端口返回 open 或 closed（RST）都说明主机在线
+fn alive_hosts(sweep: &PortScanResult) -> Vec<AliveHost> {
+    let mut fastest: HashMap<&str, (u16, Option<f64>)> = HashMap::new();
+    for status in &sweep.ports {
+        if !matches!(status.state, PortState::Open | PortState::Closed) {
+            continue;
+        }
+        let entry = fastest
+            .entry(status.host.as_str())
+            .or_insert((status.port, status.latency_ms));
+        if status.latency_ms.unwrap_or(f64::MAX) < entry.1.unwrap_or(f64::MAX) {
+            *entry = (status.port, status.latency_ms);
+        }
+    }
+    sweep
+        .hosts
+        .iter()
+        .filter_map(|summary| {
+            let (port, latency_ms) = fastest.get(summary.host.as_str())?;
+            Some(AliveHost {
+                host: summary.host.clone(),
+                port: *port,
+                latency_ms: *latency_ms,
+            })
+        })
+        .collect()
+}
+
+fn host_label(host: &AliveHost) -> String {
+    if a11y::is_enabled() {
+        let latency = host
+            .latency_ms
+            .map(|ms| format!("{:.2} ms", ms))
+            .unwrap_or_default();
+        return a11y::line(&[
+            ("host", &host.host),
+            ("latency", &latency),
+            ("probe port", &host.port),
+        ]);
+    }
+    match host.latency_ms {
+        Some(ms) => format!("{:<39} {:>8.2} ms  via port {}", host.host, ms, host.port),
+        None => format!("{:<39} via port {}", host.host, host.port),
+    }
+}
+
+fn write_plain<W: Write>(
+    alive: &[AliveHost],
+    total: usize,
+    probe_ports: &str,
+    out: &mut W,
+) -> io::Result<()> {
+    for host in alive {
+        writeln!(out, "[UP]  {}", host_label(host))?;
+    }
+    writeln!(
+        out,
+        "\nDiscovered {} of {} hosts alive (probe ports {})",
+        alive.len(),
+        total,
+        probe_ports
+    )
+}
+
+fn write_scan(result: &PortScanResult) -> Result<(), DiscoverError> {
+    let mut out = io::stdout().lock();
+    portscan::write_plain(result, &mut out)?;
+    Ok(())
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum DiscoverError {
+    #[error(transparent)]
+    PortScan(#[from] PortScanError),
+    #[error("--pick needs an interactive terminal")]
+    NotInteractive,
+    #[error("prompt failed: {0}")]
+    Prompt(#[from] dialoguer::Error),
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_refused_port_counts_as_alive() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let open = listener.local_addr().unwrap().port();
+            // 绑定后立即释放，得到一个大概率无人监听的端口
+            let closed = std::net::TcpListener::bind("127.0.0.1:0")
+                .unwrap()
+                .local_addr()
+                .unwrap()
+                .port();
+            let settings = ScanSettings {
+                concurrency: 10,
+                timeout_ms: 500,
+                ..ScanSettings::default()
+            };
+            let hosts = vec!["127.0.0.1".to_string()];
+
+            let sweep = remote_scan("127.0.0.1", &hosts, "", &[closed], &settings)
+                .await
+                .unwrap();
+            let alive = alive_hosts(&sweep);
+            assert_eq!(alive.len(), 1);
+            assert_eq!(alive[0].port, closed);
+
+            let sweep = remote_scan("127.0.0.1", &hosts, "", &[open, closed], &settings)
+                .await
+                .unwrap();
+            assert_eq!(alive_hosts(&sweep)[0].host, "127.0.0.1");
+        });
+    }
+
+    #[test]
+    fn test_filtered_hosts_are_not_alive() {
+        let mut sweep = tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(remote_scan(
+                "127.0.0.1",
+                &["127.0.0.1".to_string()],
+                "",
+                &[],
+                &ScanSettings {
+                    concurrency: 1,
+                    timeout_ms: 50,
+                    ..ScanSettings::default()
+                },
+            ))
+            .unwrap();
+        assert!(alive_hosts(&sweep).is_empty());
+        sweep.ports.push(portscan::PortStatus {
+            host: "127.0.0.1".into(),
+            hostname: None,
+            rdns: None,
+            port: 22,
+            open: false,
+            state: PortState::Filtered,
+            latency_ms: None,
+            service: None,
+            version: None,
+            banner: None,
+            tls: None,
+            http: None,
+            pid: None,
+            command: None,
+        });
+        assert!(alive_hosts(&sweep).is_empty());
+    }
+
+    #[test]
+    fn test_presets_parse() {
+        for preset in ScanPreset::ALL {
+            assert!(!parse_port_spec(preset.ports()).unwrap().is_empty());
+        }
+        assert_eq!(
+            parse_port_spec(ScanPreset::All.ports()).unwrap().len(),
+            65535
+        );
+    }
+}
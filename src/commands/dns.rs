@@ -0,0 +1,474 @@
+use std::io;
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
+use std::time::{Duration, Instant};
+
+use clap::ValueEnum;
+use hickory_resolver::config::{
+    NameServerConfig, NameServerConfigGroup, ResolveHosts, ResolverConfig, ResolverOpts,
+};
+use hickory_resolver::name_server::TokioConnectionProvider;
+use hickory_resolver::proto::op::ResponseCode;
+use hickory_resolver::proto::rr::{Record, RecordType};
+use hickory_resolver::proto::xfer::Protocol;
+use hickory_resolver::proto::ProtoErrorKind;
+use hickory_resolver::{Name, ResolveError, TokioResolver};
+use serde::Serialize;
+
+use crate::utils::network::{self, NetworkError};
+use crate::utils::output::RecordOutput;
+
+#[derive(clap::Args)]
+pub struct DnsOpts {
+    #[arg(
+        value_name = "NAME",
+        help = "要查询的域名；给出 IP 且未指定 --type 时做反向解析（PTR）"
+    )]
+    name: String,
+
+    #[arg(
+        value_enum,
+        short = 't',
+        long = "type",
+        value_name = "TYPE",
+        ignore_case = true,
+        help = "记录类型 A | AAAA | MX | TXT | CNAME | NS | SOA | PTR | SRV | CAA，默认 A"
+    )]
+    record_type: Option<QueryType>,
+
+    #[arg(
+        short = 's',
+        long = "server",
+        value_name = "SERVER",
+        help = "上游服务器，默认使用系统配置：1.1.1.1、1.1.1.1:5353、tcp://8.8.8.8、tls://1.1.1.1（DoT）或 https://cloudflare-dns.com/dns-query（DoH）"
+    )]
+    server: Option<String>,
+
+    #[arg(
+        long = "timeout",
+        default_value_t = 5000,
+        value_name = "MS",
+        help = "查询超时时间(毫秒)，范围 100-60000"
+    )]
+    time_out: u64,
+
+    #[arg(long, help = "只输出记录的值，每行一条，类似 dig +short")]
+    short: bool,
+
+    #[arg(long, help = "以 JSON 输出结果")]
+    json: bool,
+
+    // 全局 --where / --fields / --rename，由命令行入口注入，作用于应答记录
+    #[arg(skip)]
+    pub(crate) record_output: RecordOutput,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum DnsError {
+    #[error("invalid domain name {0}: {1}")]
+    InvalidName(String, String),
+    #[error("invalid server {0}: {1}")]
+    InvalidServer(String, String),
+    #[error("cannot resolve server {0}")]
+    UnresolvedServer(String),
+    #[error("query failed: {0}")]
+    Query(#[from] ResolveError),
+    #[error(transparent)]
+    Network(#[from] NetworkError),
+    #[error("tokio runtime error: {0}")]
+    Runtime(#[source] io::Error),
+    #[error("json serialize failed: {0}")]
+    Serialize(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum QueryType {
+    A,
+    Aaaa,
+    Mx,
+    Txt,
+    Cname,
+    Ns,
+    Soa,
+    Ptr,
+    Srv,
+    Caa,
+}
+
+impl QueryType {
+    fn record_type(self) -> RecordType {
+        match self {
+            QueryType::A => RecordType::A,
+            QueryType::Aaaa => RecordType::AAAA,
+            QueryType::Mx => RecordType::MX,
+            QueryType::Txt => RecordType::TXT,
+            QueryType::Cname => RecordType::CNAME,
+            QueryType::Ns => RecordType::NS,
+            QueryType::Soa => RecordType::SOA,
+            QueryType::Ptr => RecordType::PTR,
+            QueryType::Srv => RecordType::SRV,
+            QueryType::Caa => RecordType::CAA,
+        }
+    }
+}
+
+// 上游服务器：地址、传输方式，以及 DoT / DoH 校验证书用的名称和 DoH 的路径
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Upstream {
+    addr: SocketAddr,
+    protocol: Protocol,
+    tls_name: Option<String>,
+    endpoint: Option<String>,
+}
+
+impl Upstream {
+    fn parse(spec: &str) -> Result<Self, DnsError> {
+        let invalid = |reason: &str| DnsError::InvalidServer(spec.to_string(), reason.to_string());
+        let (scheme, rest) = spec.split_once("://").unwrap_or(("udp", spec));
+        let (protocol, default_port) = match scheme.to_ascii_lowercase().as_str() {
+            "udp" => (Protocol::Udp, 53),
+            "tcp" => (Protocol::Tcp, 53),
+            "tls" => (Protocol::Tls, 853),
+            "https" => (Protocol::Https, 443),
+            _ => return Err(invalid("expected udp://, tcp://, tls:// or https://")),
+        };
+        let (authority, endpoint) = match rest.split_once('/') {
+            Some((authority, path)) if protocol == Protocol::Https => {
+                (authority, Some(format!("/{}", path)))
+            }
+            Some(_) => return Err(invalid("only https:// servers take a path")),
+            None => (rest, None),
+        };
+        let (host, port) = split_host_port(authority).ok_or_else(|| invalid("bad host or port"))?;
+        let port = port.unwrap_or(default_port);
+        network::check_host("querying DNS server", host)?;
+        let addr = match host.parse::<IpAddr>() {
+            Ok(ip) => SocketAddr::new(ip, port),
+            // DoT / DoH 常用主机名给出，先用系统解析器拿到地址，证书按该主机名校验
+            Err(_) => (host, port)
+                .to_socket_addrs()
+                .ok()
+                .and_then(|mut addrs| addrs.next())
+                .ok_or_else(|| DnsError::UnresolvedServer(host.to_string()))?,
+        };
+        let encrypted = matches!(protocol, Protocol::Tls | Protocol::Https);
+        Ok(Self {
+            addr,
+            protocol,
+            tls_name: encrypted.then(|| host.to_string()),
+            endpoint,
+        })
+    }
+
+    // 明文 UDP 同时配置 TCP，应答被截断（TC）时改用 TCP 重新查询
+    fn config(&self) -> ResolverConfig {
+        let mut group = NameServerConfigGroup::new();
+        let mut server = NameServerConfig::new(self.addr, self.protocol);
+        server.tls_dns_name = self.tls_name.clone();
+        server.http_endpoint = self.endpoint.clone();
+        group.push(server);
+        if self.protocol == Protocol::Udp {
+            group.push(NameServerConfig::new(self.addr, Protocol::Tcp));
+        }
+        ResolverConfig::from_parts(None, vec![], group)
+    }
+}
+
+// host、host:port、[v6]:port 或不带方括号的 IPv6 地址
+pub(crate) fn split_host_port(authority: &str) -> Option<(&str, Option<u16>)> {
+    if let Some(rest) = authority.strip_prefix('[') {
+        let (host, tail) = rest.split_once(']')?;
+        let port = match tail.strip_prefix(':') {
+            Some(port) => Some(port.parse().ok()?),
+            None if tail.is_empty() => None,
+            None => return None,
+        };
+        return Some((host, port));
+    }
+    if authority.parse::<IpAddr>().is_ok() {
+        return Some((authority, None));
+    }
+    match authority.rsplit_once(':') {
+        Some((host, port)) if !host.is_empty() => Some((host, Some(port.parse().ok()?))),
+        Some(_) => None,
+        None if authority.is_empty() => None,
+        None => Some((authority, None)),
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct DnsAnswer {
+    pub name: String,
+    pub ttl: u32,
+    #[serde(rename = "type")]
+    pub record_type: String,
+    pub data: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DnsReport {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub record_type: String,
+    pub server: String,
+    pub protocol: String,
+    // NOERROR、NXDOMAIN 等响应码
+    pub status: String,
+    pub elapsed_ms: f64,
+    pub answers: Vec<DnsAnswer>,
+    // 否定应答中权威服务器给出的 SOA
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub authority: Option<DnsAnswer>,
+}
+
+pub fn run_dns(opts: DnsOpts) -> Result<(), DnsError> {
+    let upstream = opts.server.as_deref().map(Upstream::parse).transpose()?;
+    // IP 默认做反向解析；否则按 --type 查询
+    let reverse = opts.name.parse::<IpAddr>().ok();
+    let record_type = match (opts.record_type, reverse) {
+        (Some(query), _) => query.record_type(),
+        (None, Some(_)) => RecordType::PTR,
+        (None, None) => RecordType::A,
+    };
+    let name = match reverse {
+        Some(ip) if record_type == RecordType::PTR => Name::from(ip),
+        _ => Name::from_utf8(&opts.name)
+            .map_err(|e| DnsError::InvalidName(opts.name.clone(), e.to_string()))?,
+    };
+
+    let mut options = ResolverOpts::default();
+    options.timeout = Duration::from_millis(opts.time_out.clamp(100, 60_000));
+    options.attempts = 1;
+    options.cache_size = 0;
+    options.edns0 = true;
+    options.preserve_intermediates = true;
+    options.use_hosts_file = ResolveHosts::Never;
+    // 绝对名称不追加搜索域，与 dig 的行为一致
+    options.ndots = 0;
+
+    let rt = tokio::runtime::Runtime::new().map_err(DnsError::Runtime)?;
+    let mut report = rt.block_on(async {
+        let resolver = match &upstream {
+            Some(upstream) => TokioResolver::builder_with_config(
+                upstream.config(),
+                TokioConnectionProvider::default(),
+            ),
+            None => TokioResolver::builder_tokio()?,
+        }
+        .with_options(options)
+        .build();
+        // 系统配置的解析器可能是本机的 127.0.0.53 等，只有上游不在本机时才算联网
+        for server in resolver.config().name_servers() {
+            network::check_addr("DNS lookup", server.socket_addr.ip())?;
+        }
+        let (server, protocol) = match resolver.config().name_servers().first() {
+            Some(first) => (first.socket_addr.to_string(), first.protocol.to_string()),
+            None => ("-".to_string(), "-".to_string()),
+        };
+        let started = Instant::now();
+        let lookup = resolver.lookup(name.clone(), record_type).await;
+        let elapsed_ms = (started.elapsed().as_secs_f64() * 100_000.0).round() / 100.0;
+        let (status, answers, authority) = match lookup {
+            Ok(lookup) => (
+                ResponseCode::NoError,
+                lookup.records().iter().map(answer).collect(),
+                None,
+            ),
+            Err(error) => match error.proto().map(|proto| proto.kind()) {
+                Some(ProtoErrorKind::NoRecordsFound {
+                    response_code, soa, ..
+                }) => (
+                    *response_code,
+                    Vec::new(),
+                    soa.as_ref()
+                        .map(|soa| answer(&soa.clone().into_record_of_rdata())),
+                ),
+                _ => return Err(DnsError::Query(error)),
+            },
+        };
+        Ok::<_, DnsError>(DnsReport {
+            name: name.to_ascii(),
+            record_type: record_type.to_string(),
+            server,
+            protocol,
+            status: response_code_name(status),
+            elapsed_ms,
+            answers,
+            authority,
+        })
+    })?;
+
+    opts.record_output.retain(&mut report.answers)?;
+
+    if opts.json {
+        opts.record_output
+            .write_report(&report, Some("answers"), io::stdout().lock())?;
+    } else if opts.short {
+        for record in &report.answers {
+            println!("{}", record.data);
+        }
+    } else {
+        print_report(&report);
+    }
+    Ok(())
+}
+
+fn answer(record: &Record) -> DnsAnswer {
+    DnsAnswer {
+        name: record.name().to_ascii(),
+        ttl: record.ttl(),
+        record_type: record.record_type().to_string(),
+        data: record.data().to_string(),
+    }
+}
+
+// dig 风格的响应码名称
+pub(crate) fn response_code_name(code: ResponseCode) -> String {
+    match code {
+        ResponseCode::NoError => "NOERROR".to_string(),
+        ResponseCode::FormErr => "FORMERR".to_string(),
+        ResponseCode::ServFail => "SERVFAIL".to_string(),
+        ResponseCode::NXDomain => "NXDOMAIN".to_string(),
+        ResponseCode::NotImp => "NOTIMP".to_string(),
+        ResponseCode::Refused => "REFUSED".to_string(),
+        code => format!("RCODE{}", u16::from(code)),
+    }
+}
+
+fn print_report(report: &DnsReport) {
+    println!(
+        ";; {} IN {}  status: {}",
+        report.name, report.record_type, report.status
+    );
+    let width = report
+        .answers
+        .iter()
+        .chain(&report.authority)
+        .map(|record| record.name.len())
+        .max()
+        .unwrap_or(0);
+    let line = |record: &DnsAnswer| {
+        println!(
+            "{:<width$}  {:>6}  IN  {:<5}  {}",
+            record.name,
+            record.ttl,
+            record.record_type,
+            record.data,
+            width = width
+        )
+    };
+    if !report.answers.is_empty() {
+        println!("\n;; ANSWER");
+        report.answers.iter().for_each(line);
+    }
+    if let Some(authority) = &report.authority {
+        println!("\n;; AUTHORITY");
+        line(authority);
+    }
+    println!(
+        "\n;; Query time: {} ms\n;; Server: {} ({})",
+        report.elapsed_ms, report.server, report.protocol
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_upstream() {
+        let udp = Upstream::parse("127.0.0.1").unwrap();
+        assert_eq!(udp.addr, "127.0.0.1:53".parse().unwrap());
+        assert_eq!(udp.protocol, Protocol::Udp);
+        assert_eq!(udp.config().name_servers().len(), 2);
+
+        let tcp = Upstream::parse("tcp://[::1]:5353").unwrap();
+        assert_eq!(tcp.addr, "[::1]:5353".parse().unwrap());
+        assert_eq!(tcp.protocol, Protocol::Tcp);
+
+        let dot = Upstream::parse("tls://127.0.0.1").unwrap();
+        assert_eq!(dot.addr.port(), 853);
+        assert_eq!(dot.tls_name.as_deref(), Some("127.0.0.1"));
+
+        let doh = Upstream::parse("https://localhost:8443/resolve").unwrap();
+        assert_eq!(doh.addr.port(), 8443);
+        assert_eq!(doh.tls_name.as_deref(), Some("localhost"));
+        assert_eq!(doh.endpoint.as_deref(), Some("/resolve"));
+
+        for bad in [
+            "quic://1.1.1.1",
+            "1.1.1.1:99999",
+            "tls://1.1.1.1/path",
+            "[::1",
+        ] {
+            assert!(
+                matches!(Upstream::parse(bad), Err(DnsError::InvalidServer(..))),
+                "{} should be rejected",
+                bad
+            );
+        }
+    }
+
+    #[test]
+    fn test_lookup_against_local_server() {
+        use hickory_resolver::proto::op::{Message, MessageType};
+        use hickory_resolver::proto::rr::rdata::{A, MX};
+        use hickory_resolver::proto::rr::RData;
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let socket = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+            let addr = socket.local_addr().unwrap();
+            tokio::spawn(async move {
+                let mut buf = [0u8; 512];
+                while let Ok((len, peer)) = socket.recv_from(&mut buf).await {
+                    let query = Message::from_vec(&buf[..len]).unwrap();
+                    let question = query.queries()[0].clone();
+                    let mut reply = Message::new();
+                    reply
+                        .set_id(query.id())
+                        .set_message_type(MessageType::Response)
+                        .set_recursion_desired(true)
+                        .set_recursion_available(true)
+                        .add_query(question.clone());
+                    let name = question.name().clone();
+                    match question.query_type() {
+                        _ if name.to_ascii().starts_with("missing.") => {
+                            reply.set_response_code(ResponseCode::NXDomain);
+                        }
+                        RecordType::MX => {
+                            let mx = MX::new(10, Name::from_ascii("mail.example.test.").unwrap());
+                            reply.add_answer(Record::from_rdata(name, 300, RData::MX(mx)));
+                        }
+                        _ => {
+                            let a = A::new(192, 0, 2, 7);
+                            reply.add_answer(Record::from_rdata(name, 60, RData::A(a)));
+                        }
+                    }
+                    let _ = socket.send_to(&reply.to_vec().unwrap(), peer).await;
+                }
+            });
+
+            let upstream = Upstream::parse(&addr.to_string()).unwrap();
+            let resolver = TokioResolver::builder_with_config(
+                upstream.config(),
+                TokioConnectionProvider::default(),
+            )
+            .build();
+            let lookup = resolver
+                .lookup("example.test.", RecordType::MX)
+                .await
+                .unwrap();
+            let answers: Vec<DnsAnswer> = lookup.records().iter().map(answer).collect();
+            assert_eq!(answers.len(), 1);
+            assert_eq!(answers[0].record_type, "MX");
+            assert_eq!(answers[0].data, "10 mail.example.test.");
+            assert_eq!(answers[0].ttl, 300);
+
+            let error = resolver
+                .lookup("missing.example.test.", RecordType::A)
+                .await
+                .unwrap_err();
+            assert!(error.is_nx_domain());
+        });
+    }
+}
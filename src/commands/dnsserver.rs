@@ -0,0 +1,718 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use chrono::Local;
+use hickory_resolver::proto::op::{Message, MessageType, OpCode, Query, ResponseCode};
+use hickory_resolver::proto::rr::rdata::{A, AAAA, CNAME, TXT};
+use hickory_resolver::proto::rr::{RData, Record, RecordType};
+use hickory_resolver::Name;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream, UdpSocket};
+use tokio::time::timeout;
+
+use crate::commands::dns::response_code_name;
+use crate::utils::netif;
+use crate::utils::network::{self, NetworkError};
+
+// 没有 EDNS 时 UDP 应答的上限，超出时置 TC 位让客户端改用 TCP
+const UDP_LIMIT: usize = 512;
+// 本地 CNAME 链最多跟随的次数，防止配置成环
+const MAX_CNAME_CHAIN: usize = 8;
+
+#[derive(clap::Args)]
+pub struct DnsServerOpts {
+    #[arg(
+        short = 'z',
+        long = "zone",
+        value_name = "NAME=VALUE",
+        help = "静态记录，可重复：example.test=127.0.0.1（A）、example.test=::1（AAAA）、www.example.test=cname:example.test、example.test=txt:hello；名称支持 *.example.test 通配"
+    )]
+    zones: Vec<String>,
+
+    #[arg(
+        long = "hosts",
+        value_name = "FILE",
+        help = "从 hosts 格式的文件加载静态记录（每行 IP 后跟一个或多个名称）"
+    )]
+    hosts: Vec<PathBuf>,
+
+    #[arg(
+        short = 'l',
+        long,
+        value_name = "IP",
+        default_value = "127.0.0.1",
+        help = "监听地址，0.0.0.0 接受其他机器的查询"
+    )]
+    listen: IpAddr,
+
+    #[arg(
+        short = 'p',
+        long,
+        value_name = "PORT",
+        default_value_t = 53,
+        help = "监听端口（UDP 和 TCP）；1024 以下需要 root，测试时可用 5353 等"
+    )]
+    port: u16,
+
+    #[arg(
+        short = 'u',
+        long,
+        value_name = "SERVER",
+        conflicts_with = "no_forward",
+        help = "转发其余查询的上游服务器，例如 1.1.1.1 或 10.0.0.53:5353；默认使用系统的第一个 DNS 服务器"
+    )]
+    upstream: Option<String>,
+
+    #[arg(
+        long = "no-forward",
+        help = "不转发，静态记录以外的名称一律返回 NXDOMAIN"
+    )]
+    no_forward: bool,
+
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        default_value_t = 60,
+        help = "静态记录的 TTL（秒）"
+    )]
+    ttl: u32,
+
+    #[arg(
+        long = "timeout",
+        value_name = "MS",
+        default_value_t = 3000,
+        help = "等待上游应答的时间（毫秒），超时返回 SERVFAIL"
+    )]
+    time_out: u64,
+
+    #[arg(short = 'q', long, help = "不打印每条查询的日志")]
+    quiet: bool,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum DnsServerError {
+    #[error("invalid zone '{spec}': {reason}")]
+    InvalidZone { spec: String, reason: String },
+    #[error("failed to read {path}: {source}")]
+    Hosts { path: PathBuf, source: io::Error },
+    #[error("invalid upstream '{0}', expected an IP address with an optional port")]
+    InvalidUpstream(String),
+    #[error("no system DNS server found, specify one with --upstream or use --no-forward")]
+    NoUpstream,
+    #[error("upstream {0} is this server itself")]
+    SelfUpstream(SocketAddr),
+    #[error("cannot listen on {addr}: {source}")]
+    Bind {
+        addr: SocketAddr,
+        #[source]
+        source: io::Error,
+    },
+    #[error(transparent)]
+    Network(#[from] NetworkError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ZoneData {
+    A(Ipv4Addr),
+    Aaaa(Ipv6Addr),
+    Cname(Name),
+    Txt(String),
+}
+
+impl ZoneData {
+    fn record_type(&self) -> RecordType {
+        match self {
+            ZoneData::A(_) => RecordType::A,
+            ZoneData::Aaaa(_) => RecordType::AAAA,
+            ZoneData::Cname(_) => RecordType::CNAME,
+            ZoneData::Txt(_) => RecordType::TXT,
+        }
+    }
+
+    fn rdata(&self) -> RData {
+        match self {
+            ZoneData::A(ip) => RData::A(A(*ip)),
+            ZoneData::Aaaa(ip) => RData::AAAA(AAAA(*ip)),
+            ZoneData::Cname(name) => RData::CNAME(CNAME(name.clone())),
+            ZoneData::Txt(text) => RData::TXT(TXT::new(vec![text.clone()])),
+        }
+    }
+}
+
+impl fmt::Display for ZoneData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ZoneData::A(ip) => write!(f, "{}", ip),
+            ZoneData::Aaaa(ip) => write!(f, "{}", ip),
+            ZoneData::Cname(name) => write!(f, "CNAME {}", name),
+            ZoneData::Txt(text) => write!(f, "TXT \"{}\"", text),
+        }
+    }
+}
+
+// 命令行和 hosts 文件给出的静态记录，名称统一为小写的绝对名称
+#[derive(Debug, Default)]
+struct Zone {
+    records: Vec<(Name, ZoneData)>,
+}
+
+// 静态记录的查询结果；None 表示名称不在静态记录中，交给上游
+#[derive(Debug, PartialEq, Eq)]
+struct Answer {
+    records: Vec<(Name, ZoneData)>,
+}
+
+impl Zone {
+    fn add_spec(&mut self, spec: &str) -> Result<(), DnsServerError> {
+        let invalid = |reason: &str| DnsServerError::InvalidZone {
+            spec: spec.to_string(),
+            reason: reason.to_string(),
+        };
+        let (name, value) = spec
+            .split_once('=')
+            .ok_or_else(|| invalid("expected NAME=VALUE"))?;
+        let name = parse_name(name.trim()).ok_or_else(|| invalid("bad name"))?;
+        let value = value.trim();
+        let prefixed = |prefix: &str| {
+            value
+                .get(..prefix.len())
+                .filter(|head| head.eq_ignore_ascii_case(prefix))
+                .map(|_| &value[prefix.len()..])
+        };
+        let data = if let Ok(ip) = value.parse::<IpAddr>() {
+            match ip {
+                IpAddr::V4(ip) => ZoneData::A(ip),
+                IpAddr::V6(ip) => ZoneData::Aaaa(ip),
+            }
+        } else if let Some(target) = prefixed("cname:") {
+            ZoneData::Cname(parse_name(target.trim()).ok_or_else(|| invalid("bad CNAME target"))?)
+        } else if let Some(text) = prefixed("txt:") {
+            ZoneData::Txt(text.to_string())
+        } else {
+            return Err(invalid(
+                "expected an IP address, cname:NAME or txt:TEXT after '='",
+            ));
+        };
+        self.records.push((name, data));
+        Ok(())
+    }
+
+    // 127.0.0.1 example.test api.example.test # 注释
+    fn add_hosts(&mut self, text: &str) {
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or_default();
+            let mut fields = line.split_whitespace();
+            let Some(Ok(ip)) = fields.next().map(str::parse::<IpAddr>) else {
+                continue;
+            };
+            let data = match ip {
+                IpAddr::V4(ip) => ZoneData::A(ip),
+                IpAddr::V6(ip) => ZoneData::Aaaa(ip),
+            };
+            for name in fields.filter_map(parse_name) {
+                self.records.push((name, data.clone()));
+            }
+        }
+    }
+
+    // 精确名称优先，其次是最具体的通配符；名称存在但没有该类型的记录时返回空应答（NODATA）
+    fn lookup(&self, name: &Name, query_type: RecordType) -> Option<Answer> {
+        let mut current = name_key(name);
+        self.matching(&current)?;
+        let mut records = Vec::new();
+        for _ in 0..=MAX_CNAME_CHAIN {
+            let Some(matched) = self.matching(&current) else {
+                break;
+            };
+            let cname = matched.iter().find_map(|data| match data {
+                ZoneData::Cname(target) if query_type != RecordType::CNAME => Some(target.clone()),
+                _ => None,
+            });
+            if let Some(target) = cname {
+                records.push((current.clone(), ZoneData::Cname(target.clone())));
+                current = target;
+                continue;
+            }
+            records.extend(
+                matched
+                    .into_iter()
+                    .filter(|data| {
+                        query_type == RecordType::ANY || data.record_type() == query_type
+                    })
+                    .map(|data| (current.clone(), data.clone())),
+            );
+            break;
+        }
+        Some(Answer { records })
+    }
+
+    fn matching(&self, name: &Name) -> Option<Vec<&ZoneData>> {
+        let exact: Vec<&ZoneData> = self
+            .records
+            .iter()
+            .filter(|(owner, _)| owner == name)
+            .map(|(_, data)| data)
+            .collect();
+        if !exact.is_empty() {
+            return Some(exact);
+        }
+        // *.example.test 匹配 a.example.test 和 a.b.example.test，不匹配 example.test 本身
+        let best = self
+            .records
+            .iter()
+            .filter(|(owner, _)| owner.is_wildcard())
+            .map(|(owner, _)| owner.base_name())
+            .filter(|base| base.zone_of(name) && base.num_labels() < name.num_labels())
+            .max_by_key(|base| base.num_labels())?;
+        Some(
+            self.records
+                .iter()
+                .filter(|(owner, _)| owner.is_wildcard() && owner.base_name() == best)
+                .map(|(_, data)| data)
+                .collect(),
+        )
+    }
+}
+
+fn parse_name(raw: &str) -> Option<Name> {
+    if raw.is_empty() {
+        return None;
+    }
+    Name::from_utf8(raw).ok().map(|name| name_key(&name))
+}
+
+fn name_key(name: &Name) -> Name {
+    let mut name = name.to_lowercase();
+    name.set_fqdn(true);
+    name
+}
+
+// 各类应答的计数，退出时汇总
+#[derive(Default)]
+struct Stats {
+    local: AtomicU64,
+    forwarded: AtomicU64,
+    failed: AtomicU64,
+}
+
+struct Server {
+    zone: Zone,
+    upstream: Option<SocketAddr>,
+    ttl: u32,
+    timeout: Duration,
+    quiet: bool,
+    stats: Stats,
+}
+
+pub fn run_dns_server(opts: DnsServerOpts) -> Result<(), DnsServerError> {
+    let mut zone = Zone::default();
+    for spec in &opts.zones {
+        zone.add_spec(spec)?;
+    }
+    for path in &opts.hosts {
+        let text = fs::read_to_string(path).map_err(|source| DnsServerError::Hosts {
+            path: path.clone(),
+            source,
+        })?;
+        zone.add_hosts(&text);
+    }
+    let listen = SocketAddr::new(opts.listen, opts.port);
+    network::check_addr("dns-server listen", listen.ip())?;
+
+    let upstream = if opts.no_forward {
+        None
+    } else {
+        let upstream = match &opts.upstream {
+            Some(raw) => parse_upstream(raw)?,
+            None => netif::dns_servers()
+                .into_iter()
+                .map(|ip| SocketAddr::new(ip, 53))
+                .find(|addr| *addr != listen)
+                .ok_or(DnsServerError::NoUpstream)?,
+        };
+        if upstream == listen {
+            return Err(DnsServerError::SelfUpstream(upstream));
+        }
+        // 离线时静态记录照常应答，其余名称不转发
+        if upstream.ip().is_loopback() || !network::skip("forwarding to the upstream DNS server") {
+            Some(upstream)
+        } else {
+            None
+        }
+    };
+
+    let server = Arc::new(Server {
+        zone,
+        upstream,
+        ttl: opts.ttl,
+        timeout: Duration::from_millis(opts.time_out.max(1)),
+        quiet: opts.quiet,
+        stats: Stats::default(),
+    });
+
+    let rt = tokio::runtime::Runtime::new()?;
+    rt.block_on(async {
+        let bind_error = |source| DnsServerError::Bind {
+            addr: listen,
+            source,
+        };
+        let udp = Arc::new(UdpSocket::bind(listen).await.map_err(bind_error)?);
+        let local = udp.local_addr()?;
+        // 端口为 0 时 TCP 使用与 UDP 相同的随机端口
+        let tcp = TcpListener::bind(local).await.map_err(bind_error)?;
+
+        eprintln!(
+            "Serving DNS on {} (udp/tcp), {} static record{}, {}",
+            local,
+            server.zone.records.len(),
+            if server.zone.records.len() == 1 {
+                ""
+            } else {
+                "s"
+            },
+            match server.upstream {
+                Some(upstream) => format!("forwarding the rest to {}", upstream),
+                None => "answering NXDOMAIN for the rest".to_string(),
+            }
+        );
+        for (name, data) in &server.zone.records {
+            eprintln!("  {}  {}", name, data);
+        }
+
+        tokio::select! {
+            result = serve_udp(Arc::clone(&udp), Arc::clone(&server)) => result?,
+            result = serve_tcp(tcp, Arc::clone(&server)) => result?,
+            _ = tokio::signal::ctrl_c() => {}
+        }
+        let stats = &server.stats;
+        eprintln!(
+            "Answered {} locally, forwarded {}, {} failed",
+            stats.local.load(Ordering::Relaxed),
+            stats.forwarded.load(Ordering::Relaxed),
+            stats.failed.load(Ordering::Relaxed)
+        );
+        Ok(())
+    })
+}
+
+// IP 或 IP:端口，IPv6 带端口时需要方括号
+fn parse_upstream(raw: &str) -> Result<SocketAddr, DnsServerError> {
+    raw.parse::<SocketAddr>()
+        .or_else(|_| {
+            raw.trim_start_matches('[')
+                .trim_end_matches(']')
+                .parse::<IpAddr>()
+                .map(|ip| SocketAddr::new(ip, 53))
+        })
+        .map_err(|_| DnsServerError::InvalidUpstream(raw.to_string()))
+}
+
+async fn serve_udp(socket: Arc<UdpSocket>, server: Arc<Server>) -> io::Result<()> {
+    let mut buf = vec![0u8; 65535];
+    loop {
+        let (n, peer) = match socket.recv_from(&mut buf).await {
+            Ok(received) => received,
+            // Windows 上对端关闭的 ICMP 会让 recv_from 报错，忽略即可
+            Err(e) if e.kind() == io::ErrorKind::ConnectionReset => continue,
+            Err(e) => return Err(e),
+        };
+        let request = buf[..n].to_vec();
+        let socket = Arc::clone(&socket);
+        let server = Arc::clone(&server);
+        tokio::spawn(async move {
+            if let Some(response) = server.handle(&request, peer, false).await {
+                let _ = socket.send_to(&response, peer).await;
+            }
+        });
+    }
+}
+
+async fn serve_tcp(listener: TcpListener, server: Arc<Server>) -> io::Result<()> {
+    loop {
+        let (mut stream, peer) = listener.accept().await?;
+        let server = Arc::clone(&server);
+        tokio::spawn(async move {
+            // 同一连接上可以连续发送多个查询，空闲 10 秒后关闭
+            while let Ok(Ok(request)) =
+                timeout(Duration::from_secs(10), read_frame(&mut stream)).await
+            {
+                let Some(response) = server.handle(&request, peer, true).await else {
+                    break;
+                };
+                if write_frame(&mut stream, &response).await.is_err() {
+                    break;
+                }
+            }
+        });
+    }
+}
+
+// TCP 上的 DNS 消息前有两字节的长度
+async fn read_frame(stream: &mut TcpStream) -> io::Result<Vec<u8>> {
+    let len = stream.read_u16().await?;
+    let mut buf = vec![0u8; len as usize];
+    stream.read_exact(&mut buf).await?;
+    Ok(buf)
+}
+
+async fn write_frame(stream: &mut TcpStream, message: &[u8]) -> io::Result<()> {
+    let len = u16::try_from(message.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "DNS message too large"))?;
+    let mut frame = Vec::with_capacity(message.len() + 2);
+    frame.extend_from_slice(&len.to_be_bytes());
+    frame.extend_from_slice(message);
+    stream.write_all(&frame).await
+}
+
+impl Server {
+    // 返回要发回客户端的应答；无法解析的报文直接丢弃
+    async fn handle(&self, request: &[u8], peer: SocketAddr, tcp: bool) -> Option<Vec<u8>> {
+        let message = Message::from_vec(request).ok()?;
+        if message.message_type() != MessageType::Query {
+            return None;
+        }
+        let Some(query) = message.queries().first().cloned() else {
+            return self.reply(&message, ResponseCode::FormErr, Vec::new(), tcp);
+        };
+        if message.op_code() != OpCode::Query {
+            self.log(peer, &query, format_args!("NOTIMP"));
+            return self.reply(&message, ResponseCode::NotImp, Vec::new(), tcp);
+        }
+
+        if let Some(answer) = self.zone.lookup(query.name(), query.query_type()) {
+            self.stats.local.fetch_add(1, Ordering::Relaxed);
+            let summary = if answer.records.is_empty() {
+                "local NODATA".to_string()
+            } else {
+                let values: Vec<String> = answer
+                    .records
+                    .iter()
+                    .map(|(_, data)| data.to_string())
+                    .collect();
+                format!("local {}", values.join(", "))
+            };
+            self.log(peer, &query, format_args!("{}", summary));
+            let records = answer
+                .records
+                .into_iter()
+                .map(|(name, data)| Record::from_rdata(name, self.ttl, data.rdata()))
+                .collect();
+            return self.reply(&message, ResponseCode::NoError, records, tcp);
+        }
+
+        let Some(upstream) = self.upstream else {
+            self.stats.local.fetch_add(1, Ordering::Relaxed);
+            self.log(peer, &query, format_args!("NXDOMAIN"));
+            return self.reply(&message, ResponseCode::NXDomain, Vec::new(), tcp);
+        };
+        let started = Instant::now();
+        let forwarded = if tcp {
+            timeout(self.timeout, forward_tcp(upstream, request)).await
+        } else {
+            timeout(self.timeout, forward_udp(upstream, request, message.id())).await
+        };
+        match forwarded {
+            Ok(Ok(response)) => {
+                self.stats.forwarded.fetch_add(1, Ordering::Relaxed);
+                let (code, count) = Message::from_vec(&response)
+                    .map(|parsed| {
+                        (
+                            response_code_name(parsed.response_code()),
+                            parsed.answers().len(),
+                        )
+                    })
+                    .unwrap_or_else(|_| ("?".to_string(), 0));
+                self.log(
+                    peer,
+                    &query,
+                    format_args!(
+                        "upstream {} ({} answer{}, {:.1} ms)",
+                        code,
+                        count,
+                        if count == 1 { "" } else { "s" },
+                        started.elapsed().as_secs_f64() * 1000.0
+                    ),
+                );
+                Some(response)
+            }
+            failed => {
+                self.stats.failed.fetch_add(1, Ordering::Relaxed);
+                let reason = match failed {
+                    Ok(Err(e)) => e.to_string(),
+                    _ => "timed out".to_string(),
+                };
+                self.log(
+                    peer,
+                    &query,
+                    format_args!("SERVFAIL (upstream {}: {})", upstream, reason),
+                );
+                self.reply(&message, ResponseCode::ServFail, Vec::new(), tcp)
+            }
+        }
+    }
+
+    fn reply(
+        &self,
+        request: &Message,
+        code: ResponseCode,
+        answers: Vec<Record>,
+        tcp: bool,
+    ) -> Option<Vec<u8>> {
+        let mut response = Message::error_msg(request.id(), request.op_code(), code);
+        response
+            .set_recursion_desired(request.recursion_desired())
+            .set_recursion_available(self.upstream.is_some())
+            .set_authoritative(code != ResponseCode::ServFail)
+            .add_queries(request.queries().iter().cloned())
+            .add_answers(answers);
+        let limit = request.extensions().as_ref().map_or(UDP_LIMIT, |edns| {
+            usize::from(edns.max_payload()).max(UDP_LIMIT)
+        });
+        let bytes = response.to_vec().ok()?;
+        if !tcp && bytes.len() > limit {
+            return response.truncate().to_vec().ok();
+        }
+        Some(bytes)
+    }
+
+    fn log(&self, peer: SocketAddr, query: &Query, event: fmt::Arguments) {
+        if !self.quiet {
+            eprintln!(
+                "{}  {}  {} {}  -> {}",
+                Local::now().format("%Y-%m-%d %H:%M:%S%.3f"),
+                peer,
+                query.query_type(),
+                query.name(),
+                event
+            );
+        }
+    }
+}
+
+// 原样转发查询报文，只接受来自上游且 ID 匹配的应答
+async fn forward_udp(upstream: SocketAddr, request: &[u8], id: u16) -> io::Result<Vec<u8>> {
+    let local: SocketAddr = match upstream {
+        SocketAddr::V4(_) => (Ipv4Addr::UNSPECIFIED, 0).into(),
+        SocketAddr::V6(_) => (Ipv6Addr::UNSPECIFIED, 0).into(),
+    };
+    let socket = UdpSocket::bind(local).await?;
+    socket.connect(upstream).await?;
+    socket.send(request).await?;
+    let mut buf = vec![0u8; 65535];
+    loop {
+        let n = socket.recv(&mut buf).await?;
+        if n >= 2 && u16::from_be_bytes([buf[0], buf[1]]) == id {
+            buf.truncate(n);
+            return Ok(buf);
+        }
+    }
+}
+
+async fn forward_tcp(upstream: SocketAddr, request: &[u8]) -> io::Result<Vec<u8>> {
+    let mut stream = TcpStream::connect(upstream).await?;
+    write_frame(&mut stream, request).await?;
+    read_frame(&mut stream).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn name(raw: &str) -> Name {
+        parse_name(raw).unwrap()
+    }
+
+    #[test]
+    fn test_zone_lookup() {
+        let mut zone = Zone::default();
+        for spec in [
+            "example.test=127.0.0.1",
+            "Example.Test=::1",
+            "www.example.test=cname:example.test",
+            "*.apps.example.test=10.0.0.7",
+            "example.test=TXT:v=spf1 -all",
+        ] {
+            zone.add_spec(spec).unwrap();
+        }
+        zone.add_hosts(
+            "# comment\n192.168.1.5  nas.example.test nas   # trailing\nnot-an-ip foo\n",
+        );
+
+        let values = |answer: Option<Answer>| -> Vec<String> {
+            answer
+                .unwrap()
+                .records
+                .iter()
+                .map(|(_, data)| data.to_string())
+                .collect()
+        };
+        assert_eq!(
+            values(zone.lookup(&name("EXAMPLE.test"), RecordType::A)),
+            ["127.0.0.1"]
+        );
+        assert_eq!(
+            values(zone.lookup(&name("example.test"), RecordType::AAAA)),
+            ["::1"]
+        );
+        assert_eq!(
+            values(zone.lookup(&name("example.test"), RecordType::TXT)),
+            ["TXT \"v=spf1 -all\""]
+        );
+        // 存在的名称没有该类型记录时为 NODATA，而不是转发
+        assert_eq!(
+            zone.lookup(&name("nas"), RecordType::AAAA),
+            Some(Answer { records: vec![] })
+        );
+        assert_eq!(
+            values(zone.lookup(&name("nas.example.test"), RecordType::A)),
+            ["192.168.1.5"]
+        );
+
+        // CNAME 在本地跟随到目标记录
+        let answer = zone
+            .lookup(&name("www.example.test"), RecordType::A)
+            .unwrap();
+        assert_eq!(answer.records[0].0, name("www.example.test"));
+        assert_eq!(
+            answer.records[1],
+            (name("example.test"), ZoneData::A(Ipv4Addr::LOCALHOST))
+        );
+        assert_eq!(
+            values(zone.lookup(&name("www.example.test"), RecordType::CNAME)),
+            ["CNAME example.test."]
+        );
+
+        // 通配符匹配子域名，记录名称改写为查询的名称
+        let answer = zone
+            .lookup(&name("a.b.apps.example.test"), RecordType::A)
+            .unwrap();
+        assert_eq!(
+            answer.records,
+            [(
+                name("a.b.apps.example.test"),
+                ZoneData::A(Ipv4Addr::new(10, 0, 0, 7))
+            )]
+        );
+        assert_eq!(zone.lookup(&name("apps.example.test"), RecordType::A), None);
+        assert_eq!(zone.lookup(&name("other.test"), RecordType::A), None);
+
+        assert!(zone.add_spec("example.test").is_err());
+        assert!(zone.add_spec("example.test=mx:mail").is_err());
+        assert_eq!(
+            parse_upstream("[::1]:5353").unwrap(),
+            "[::1]:5353".parse().unwrap()
+        );
+        assert_eq!(
+            parse_upstream("1.1.1.1").unwrap(),
+            "1.1.1.1:53".parse().unwrap()
+        );
+    }
+}
@@ -0,0 +1,150 @@
+use std::io::{self, Write};
+
+use clap::ValueEnum;
+use fake::faker::address::raw::{CityName, StreetName, ZipCode};
+use fake::faker::company::raw::CompanyName;
+use fake::faker::internet::raw::{
+    FreeEmail, IPv4, IPv6, MACAddress, Password, UserAgent, Username,
+};
+use fake::faker::job::raw::Title;
+use fake::faker::lorem::raw::{Paragraph, Sentence, Word};
+use fake::faker::name::raw::{FirstName, LastName, Name};
+use fake::faker::phone_number::raw::{CellNumber, PhoneNumber};
+use fake::locales::{Data, EN, ZH_CN};
+use fake::{Dummy, Fake};
+
+use crate::utils::metrics;
+
+pub const MAX_FAKE_COUNT: u32 = 1_000_000;
+
+#[derive(clap::Args)]
+pub struct FakeOpts {
+    #[arg(value_enum, value_name = "KIND", help = "数据类型")]
+    kind: FakeKind,
+
+    #[arg(
+        value_enum,
+        short = 'l',
+        long = "locale",
+        default_value_t = FakeLocale::ZhCn,
+        help = "语言区域 zh_CN | en"
+    )]
+    locale: FakeLocale,
+
+    #[arg(short = 'n', long, default_value_t = 1, help = "生成数量")]
+    count: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum FakeKind {
+    Name,
+    FirstName,
+    LastName,
+    Email,
+    Username,
+    Password,
+    Phone,
+    CellPhone,
+    Ipv4,
+    Ipv6,
+    Mac,
+    UserAgent,
+    Job,
+    Company,
+    City,
+    Street,
+    ZipCode,
+    Word,
+    Sentence,
+    Paragraph,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum FakeLocale {
+    #[value(name = "zh_CN", alias = "zh-cn", alias = "zh")]
+    ZhCn,
+    #[value(name = "en")]
+    En,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum FakeError {
+    #[error("count must be between 1 and {0}")]
+    InvalidCount(u32),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+pub fn run_fake(opts: FakeOpts) -> Result<(), FakeError> {
+    if opts.count == 0 || opts.count > MAX_FAKE_COUNT {
+        return Err(FakeError::InvalidCount(MAX_FAKE_COUNT));
+    }
+
+    let stdout = io::stdout();
+    let mut out = io::BufWriter::new(stdout.lock());
+    for _ in 0..opts.count {
+        writeln!(out, "{}", fake_value(opts.kind, opts.locale))?;
+    }
+    metrics::records_generated("fake", opts.count as usize);
+    out.flush()?;
+    Ok(())
+}
+
+pub fn fake_value(kind: FakeKind, locale: FakeLocale) -> String {
+    match locale {
+        FakeLocale::ZhCn => fake_with_locale(kind, ZH_CN),
+        FakeLocale::En => fake_with_locale(kind, EN),
+    }
+}
+
+// CityName 依赖各语言区域自己的城市名规则，因此额外要求该语言区域实现了城市名生成
+fn fake_with_locale<L>(kind: FakeKind, locale: L) -> String
+where
+    L: Data + Copy,
+    String: Dummy<CityName<L>>,
+{
+    match kind {
+        FakeKind::Name => Name(locale).fake(),
+        FakeKind::FirstName => FirstName(locale).fake(),
+        FakeKind::LastName => LastName(locale).fake(),
+        FakeKind::Email => FreeEmail(locale).fake(),
+        FakeKind::Username => Username(locale).fake(),
+        FakeKind::Password => Password(locale, 12..20).fake(),
+        FakeKind::Phone => PhoneNumber(locale).fake(),
+        FakeKind::CellPhone => CellNumber(locale).fake(),
+        FakeKind::Ipv4 => IPv4(locale).fake(),
+        FakeKind::Ipv6 => IPv6(locale).fake(),
+        FakeKind::Mac => MACAddress(locale).fake(),
+        FakeKind::UserAgent => UserAgent(locale).fake(),
+        FakeKind::Job => Title(locale).fake(),
+        FakeKind::Company => CompanyName(locale).fake(),
+        FakeKind::City => CityName(locale).fake(),
+        FakeKind::Street => StreetName(locale).fake(),
+        FakeKind::ZipCode => ZipCode(locale).fake(),
+        FakeKind::Word => Word(locale).fake(),
+        FakeKind::Sentence => Sentence(locale, 4..10).fake(),
+        FakeKind::Paragraph => Paragraph(locale, 3..6).fake(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_kind_produces_a_value() {
+        for kind in FakeKind::value_variants() {
+            for locale in FakeLocale::value_variants() {
+                assert!(!fake_value(*kind, *locale).trim().is_empty(), "{:?}", kind);
+            }
+        }
+    }
+
+    #[test]
+    fn ipv4_values_parse() {
+        for _ in 0..20 {
+            let value = fake_value(FakeKind::Ipv4, FakeLocale::En);
+            assert!(value.parse::<std::net::Ipv4Addr>().is_ok(), "{}", value);
+        }
+    }
+}
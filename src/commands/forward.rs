@@ -0,0 +1,806 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Local;
+use rand::Rng;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream, UdpSocket};
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
+use tokio::time::Instant;
+
+use crate::utils::format::human_bytes;
+use crate::utils::network::{self, NetworkError};
+use crate::utils::ratelimit::RateLimiter;
+
+const CHUNK_SIZE: usize = 16 * 1024;
+// 每个方向允许排队等待延迟发出的数据块数，相当于在途数据上限 4 MiB
+const QUEUED_CHUNKS: usize = 256;
+const MAX_DATAGRAM: usize = 65536;
+// UDP 限速时排队超过这个时长的数据报直接丢弃，相当于路由器缓冲区溢出
+const UDP_QUEUE_LIMIT: Duration = Duration::from_secs(1);
+
+#[derive(clap::Args)]
+pub struct ForwardOpts {
+    #[arg(
+        short = 'l',
+        long,
+        value_name = "ADDR",
+        help = "本地监听地址，例如 0.0.0.0:8080 或 127.0.0.1:8080"
+    )]
+    listen: String,
+
+    #[arg(long, value_name = "HOST:PORT", help = "转发目标，例如 internal:80")]
+    to: String,
+
+    #[arg(
+        long,
+        value_name = "SPEC",
+        help = "注入网络故障，例如 latency=200ms±50,drop=1%,bandwidth=1mbps"
+    )]
+    chaos: Option<String>,
+
+    #[arg(
+        short = 'u',
+        long,
+        help = "转发 UDP 数据报而不是 TCP 连接，按客户端地址区分会话"
+    )]
+    udp: bool,
+
+    #[arg(
+        long = "udp-idle",
+        value_name = "SECS",
+        default_value_t = 60,
+        help = "UDP 会话空闲多少秒后回收"
+    )]
+    udp_idle: u64,
+
+    #[arg(
+        long,
+        value_name = "SECS",
+        default_value_t = 10,
+        help = "Ctrl-C 后等待现有连接结束的最长秒数，期间再按 Ctrl-C 立即退出"
+    )]
+    grace: u64,
+
+    #[arg(
+        short = 'q',
+        long,
+        help = "不输出每个连接的建立和关闭日志，错误仍会输出"
+    )]
+    quiet: bool,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ForwardError {
+    #[error("invalid --chaos '{spec}': {reason}")]
+    InvalidChaos { spec: String, reason: String },
+    #[error("invalid listen address '{0}', expected IP:PORT")]
+    InvalidListen(String),
+    #[error("invalid target '{0}', expected HOST:PORT")]
+    InvalidTarget(String),
+    #[error("failed to listen on {addr}: {source}")]
+    Bind { addr: SocketAddr, source: io::Error },
+    #[error(transparent)]
+    Network(#[from] NetworkError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+// 注入的故障：每个数据块延迟 latency±jitter 后发出（同一方向保持顺序），
+// 新连接按 drop 概率直接断开（UDP 按数据报丢弃），bandwidth 限制每个连接每个方向的字节速率
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Chaos {
+    pub latency: Duration,
+    pub jitter: Duration,
+    pub drop: f64,
+    // 字节/秒
+    pub bandwidth: Option<u32>,
+}
+
+impl Chaos {
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut chaos = Chaos::default();
+        for item in spec
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+        {
+            let (key, value) = item
+                .split_once('=')
+                .ok_or_else(|| format!("expected key=value, got '{}'", item))?;
+            match key.trim() {
+                "latency" => (chaos.latency, chaos.jitter) = parse_latency(value)?,
+                "drop" => chaos.drop = parse_probability(value)?,
+                "bandwidth" => chaos.bandwidth = Some(parse_bandwidth(value)?),
+                other => {
+                    return Err(format!(
+                        "unknown option '{}', expected latency, drop or bandwidth",
+                        other
+                    ))
+                }
+            }
+        }
+        if chaos == Chaos::default() {
+            return Err("no fault specified".to_string());
+        }
+        Ok(chaos)
+    }
+
+    fn delay(&self) -> Duration {
+        jittered(self.latency, self.jitter)
+    }
+
+    fn drops(&self) -> bool {
+        self.drop > 0.0 && rand::rng().random_bool(self.drop)
+    }
+
+    // 只有 drop 时数据原样转发，不经过延迟队列
+    fn shapes_traffic(&self) -> bool {
+        !self.latency.is_zero() || !self.jitter.is_zero() || self.bandwidth.is_some()
+    }
+}
+
+// 200ms±50 或 200ms+-50ms，返回基础延迟和抖动；mock-api 的 --latency 使用相同写法
+pub(crate) fn parse_latency(text: &str) -> Result<(Duration, Duration), String> {
+    let text = text.trim();
+    let (base, jitter) = match text.split_once('±') {
+        Some(pair) => pair,
+        None => text.split_once("+-").unwrap_or((text, "")),
+    };
+    let jitter = match jitter.trim() {
+        "" => Duration::ZERO,
+        jitter => parse_delay(jitter)?,
+    };
+    Ok((parse_delay(base)?, jitter))
+}
+
+// 在 latency±jitter 内均匀取值
+pub(crate) fn jittered(latency: Duration, jitter: Duration) -> Duration {
+    if jitter.is_zero() {
+        return latency;
+    }
+    rand::rng().random_range(latency.saturating_sub(jitter)..=latency + jitter)
+}
+
+// 200ms、0.5s 或不带单位的毫秒数
+fn parse_delay(text: &str) -> Result<Duration, String> {
+    let text = text.trim();
+    let invalid = || format!("invalid delay '{}', expected e.g. 200ms or 1.5s", text);
+    let seconds = if let Some(ms) = text.strip_suffix("ms") {
+        ms.trim().parse::<f64>().map_err(|_| invalid())? / 1000.0
+    } else if let Some(secs) = text.strip_suffix('s') {
+        secs.trim().parse::<f64>().map_err(|_| invalid())?
+    } else {
+        text.parse::<f64>().map_err(|_| invalid())? / 1000.0
+    };
+    Duration::try_from_secs_f64(seconds).map_err(|_| invalid())
+}
+
+// 1% 或 0.01
+pub(crate) fn parse_probability(text: &str) -> Result<f64, String> {
+    let text = text.trim();
+    let invalid = || format!("invalid probability '{}', expected 0-100% or 0-1", text);
+    let value = match text.strip_suffix('%') {
+        Some(percent) => percent.trim().parse::<f64>().map_err(|_| invalid())? / 100.0,
+        None => text.parse::<f64>().map_err(|_| invalid())?,
+    };
+    if !(0.0..=1.0).contains(&value) {
+        return Err(invalid());
+    }
+    Ok(value)
+}
+
+// 按比特计：bps、kbps、mbps、gbps（1kbps = 1000 bit/s），返回字节/秒
+fn parse_bandwidth(text: &str) -> Result<u32, String> {
+    let lower = text.trim().to_ascii_lowercase();
+    let invalid = || {
+        format!(
+            "invalid bandwidth '{}', expected e.g. 512kbps or 1mbps",
+            text
+        )
+    };
+    let (number, bits) = [("gbps", 1e9), ("mbps", 1e6), ("kbps", 1e3), ("bps", 1.0)]
+        .into_iter()
+        .find_map(|(unit, bits)| lower.strip_suffix(unit).map(|number| (number, bits)))
+        .ok_or_else(invalid)?;
+    let bytes = number.trim().parse::<f64>().map_err(|_| invalid())? * bits / 8.0;
+    if !(1.0..=f64::from(u32::MAX)).contains(&bytes) {
+        return Err(invalid());
+    }
+    Ok(bytes as u32)
+}
+
+pub fn run_forward(opts: ForwardOpts) -> Result<(), ForwardError> {
+    let chaos = match &opts.chaos {
+        Some(spec) => Chaos::parse(spec).map_err(|reason| ForwardError::InvalidChaos {
+            spec: spec.clone(),
+            reason,
+        })?,
+        None => Chaos::default(),
+    };
+    let listen: SocketAddr = opts
+        .listen
+        .parse()
+        .map_err(|_| ForwardError::InvalidListen(opts.listen.clone()))?;
+    if !opts
+        .to
+        .rsplit_once(':')
+        .is_some_and(|(host, port)| !host.is_empty() && port.parse::<u16>().is_ok())
+    {
+        return Err(ForwardError::InvalidTarget(opts.to));
+    }
+    network::check_addr("forward listen", listen.ip())?;
+    network::check_endpoint("forward", &opts.to)?;
+
+    let rt = tokio::runtime::Runtime::new()?;
+    rt.block_on(async {
+        let bind_error = |source| ForwardError::Bind {
+            addr: listen,
+            source,
+        };
+        let forwarder = Arc::new(Forwarder::new(opts.to.clone(), chaos, opts.quiet));
+        let shutdown = async {
+            let _ = tokio::signal::ctrl_c().await;
+        };
+        let (local, protocol) = if opts.udp {
+            let socket = UdpSocket::bind(listen).await.map_err(bind_error)?;
+            (socket.local_addr()?, Either::Udp(socket))
+        } else {
+            let listener = TcpListener::bind(listen).await.map_err(bind_error)?;
+            (listener.local_addr()?, Either::Tcp(listener))
+        };
+        let scheme = if opts.udp { "udp" } else { "tcp" };
+        match &opts.chaos {
+            Some(spec) => eprintln!(
+                "Forwarding {}://{} -> {} (chaos: {})",
+                scheme, local, opts.to, spec
+            ),
+            None => eprintln!("Forwarding {}://{} -> {}", scheme, local, opts.to),
+        }
+
+        match protocol {
+            Either::Tcp(listener) => {
+                let mut active = serve(listener, Arc::clone(&forwarder), shutdown).await?;
+                drain(&mut active, Duration::from_secs(opts.grace)).await;
+            }
+            Either::Udp(socket) => {
+                let target = tokio::net::lookup_host(&opts.to)
+                    .await?
+                    .next()
+                    .ok_or_else(|| ForwardError::InvalidTarget(opts.to.clone()))?;
+                let idle = Duration::from_secs(opts.udp_idle.max(1));
+                serve_udp(socket, target, Arc::clone(&forwarder), idle, shutdown).await?;
+            }
+        }
+        let unit = if opts.udp { "session" } else { "connection" };
+        let count = forwarder.next_id.load(Ordering::Relaxed);
+        eprintln!(
+            "Forwarded {} {}{}: {} up, {} down",
+            count,
+            unit,
+            if count == 1 { "" } else { "s" },
+            human_bytes(forwarder.total.up.load(Ordering::Relaxed)),
+            human_bytes(forwarder.total.down.load(Ordering::Relaxed))
+        );
+        Ok(())
+    })
+}
+
+enum Either {
+    Tcp(TcpListener),
+    Udp(UdpSocket),
+}
+
+// 已停止接受新连接，等现有连接自然结束，最多等 grace；期间再按 Ctrl-C 立即退出
+async fn drain(active: &mut JoinSet<()>, grace: Duration) {
+    if active.is_empty() {
+        return;
+    }
+    eprintln!(
+        "Shutting down, waiting up to {}s for {} active connection{} (Ctrl-C again to quit now)",
+        grace.as_secs(),
+        active.len(),
+        if active.len() == 1 { "" } else { "s" }
+    );
+    tokio::select! {
+        _ = async { while active.join_next().await.is_some() {} } => {}
+        _ = tokio::time::sleep(grace) => {}
+        _ = tokio::signal::ctrl_c() => {}
+    }
+    if !active.is_empty() {
+        eprintln!("Closing {} remaining connection(s)", active.len());
+        active.abort_all();
+    }
+}
+
+// 单个连接（或 UDP 会话）两个方向的字节数，up 为客户端发往目标
+#[derive(Default)]
+struct Traffic {
+    up: AtomicU64,
+    down: AtomicU64,
+}
+
+// 所有连接共享的转发设置、编号和累计流量
+struct Forwarder {
+    target: String,
+    chaos: Chaos,
+    quiet: bool,
+    next_id: AtomicU64,
+    total: Traffic,
+}
+
+impl Forwarder {
+    fn new(target: String, chaos: Chaos, quiet: bool) -> Self {
+        Self {
+            target,
+            chaos,
+            quiet,
+            next_id: AtomicU64::new(0),
+            total: Traffic::default(),
+        }
+    }
+
+    fn next_id(&self) -> u64 {
+        self.next_id.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    fn count(&self, traffic: &Traffic, up: bool, bytes: usize) {
+        let bytes = bytes as u64;
+        if up {
+            traffic.up.fetch_add(bytes, Ordering::Relaxed);
+            self.total.up.fetch_add(bytes, Ordering::Relaxed);
+        } else {
+            traffic.down.fetch_add(bytes, Ordering::Relaxed);
+            self.total.down.fetch_add(bytes, Ordering::Relaxed);
+        }
+    }
+
+    fn log(&self, id: u64, peer: SocketAddr, event: fmt::Arguments) {
+        if !self.quiet {
+            eprintln!(
+                "{}  #{} {}  {}",
+                Local::now().format("%Y-%m-%d %H:%M:%S%.3f"),
+                id,
+                peer,
+                event
+            );
+        }
+    }
+
+    fn closed(&self, id: u64, peer: SocketAddr, started: Instant, traffic: &Traffic, how: &str) {
+        self.log(
+            id,
+            peer,
+            format_args!(
+                "{} after {:.1}s ({} up, {} down)",
+                how,
+                started.elapsed().as_secs_f64(),
+                human_bytes(traffic.up.load(Ordering::Relaxed)),
+                human_bytes(traffic.down.load(Ordering::Relaxed))
+            ),
+        );
+    }
+}
+
+// 接受并转发 TCP 连接，shutdown 完成后停止接受，返回仍在转发的连接
+async fn serve(
+    listener: TcpListener,
+    forwarder: Arc<Forwarder>,
+    shutdown: impl Future<Output = ()>,
+) -> io::Result<JoinSet<()>> {
+    tokio::pin!(shutdown);
+    let mut active = JoinSet::new();
+    loop {
+        tokio::select! {
+            _ = &mut shutdown => return Ok(active),
+            Some(_) = active.join_next(), if !active.is_empty() => {}
+            accepted = listener.accept() => {
+                let (client, peer) = accepted?;
+                let id = forwarder.next_id();
+                if forwarder.chaos.drops() {
+                    forwarder.log(id, peer, format_args!("dropped by chaos"));
+                    continue;
+                }
+                forwarder.log(id, peer, format_args!("connected"));
+                let forwarder = Arc::clone(&forwarder);
+                active.spawn(async move {
+                    let started = Instant::now();
+                    let traffic = Traffic::default();
+                    match proxy(client, &forwarder, &traffic).await {
+                        Ok(()) => forwarder.closed(id, peer, started, &traffic, "closed"),
+                        // 出错时照常输出，不受 --quiet 影响
+                        Err(e) => eprintln!(
+                            "{}  #{} {} -> {}: {}",
+                            Local::now().format("%Y-%m-%d %H:%M:%S%.3f"),
+                            id,
+                            peer,
+                            forwarder.target,
+                            e
+                        ),
+                    }
+                });
+            }
+        }
+    }
+}
+
+async fn proxy(client: TcpStream, forwarder: &Forwarder, traffic: &Traffic) -> io::Result<()> {
+    let upstream = TcpStream::connect(&forwarder.target).await?;
+    // 小包不合并，注入的延迟才是唯一的额外延迟
+    client.set_nodelay(true)?;
+    upstream.set_nodelay(true)?;
+    let (client_read, client_write) = client.into_split();
+    let (upstream_read, upstream_write) = upstream.into_split();
+    let count_up = |n| forwarder.count(traffic, true, n);
+    let count_down = |n| forwarder.count(traffic, false, n);
+    tokio::try_join!(
+        pump(client_read, upstream_write, &forwarder.chaos, count_up),
+        pump(upstream_read, client_write, &forwarder.chaos, count_down)
+    )?;
+    Ok(())
+}
+
+// 单向转发，读到 EOF 后关闭对端的写方向，另一方向继续；每写出一块数据调用 written 计数
+async fn pump<R, W>(
+    mut reader: R,
+    mut writer: W,
+    chaos: &Chaos,
+    written: impl Fn(usize),
+) -> io::Result<()>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    if !chaos.shapes_traffic() {
+        let mut buf = vec![0u8; CHUNK_SIZE];
+        loop {
+            let n = reader.read(&mut buf).await?;
+            if n == 0 {
+                return writer.shutdown().await;
+            }
+            writer.write_all(&buf[..n]).await?;
+            written(n);
+        }
+    }
+    let limiter = chaos
+        .bandwidth
+        .map(|rate| (RateLimiter::new(rate, (rate / 20).max(1)), rate / 20));
+    let (tx, mut rx) = mpsc::channel::<(Instant, Vec<u8>)>(QUEUED_CHUNKS);
+    let read = async move {
+        let mut buf = vec![0u8; CHUNK_SIZE];
+        let mut due = Instant::now();
+        loop {
+            let n = reader.read(&mut buf).await?;
+            if n == 0 {
+                return Ok::<_, io::Error>(());
+            }
+            // 后读到的数据不早于前面的数据发出，抖动不会打乱字节流
+            due = due.max(Instant::now() + chaos.delay());
+            if tx.send((due, buf[..n].to_vec())).await.is_err() {
+                return Ok(());
+            }
+        }
+    };
+    let write = async {
+        while let Some((due, data)) = rx.recv().await {
+            tokio::time::sleep_until(due).await;
+            match &limiter {
+                // 按约 50ms 的流量切片发送，低带宽下也能平滑输出
+                Some((limiter, slice)) => {
+                    for piece in data.chunks((*slice as usize).max(1)) {
+                        limiter.acquire_n(piece.len() as u32).await;
+                        writer.write_all(piece).await?;
+                    }
+                }
+                None => writer.write_all(&data).await?,
+            }
+            written(data.len());
+        }
+        writer.shutdown().await
+    };
+    tokio::try_join!(read, write)?;
+    Ok(())
+}
+
+// 按客户端地址区分 UDP 会话，每个会话用一个独立的上游 socket，目标的回复原路发回客户端；
+// 会话空闲 idle 后回收，shutdown 完成后直接返回，UDP 没有需要等待结束的连接
+async fn serve_udp(
+    socket: UdpSocket,
+    target: SocketAddr,
+    forwarder: Arc<Forwarder>,
+    idle: Duration,
+    shutdown: impl Future<Output = ()>,
+) -> io::Result<()> {
+    tokio::pin!(shutdown);
+    let socket = Arc::new(socket);
+    let mut sessions: HashMap<SocketAddr, (u64, mpsc::Sender<Vec<u8>>)> = HashMap::new();
+    let (closed_tx, mut closed_rx) = mpsc::unbounded_channel::<(SocketAddr, u64)>();
+    let mut tasks = JoinSet::new();
+    let mut buf = vec![0u8; MAX_DATAGRAM];
+    loop {
+        tokio::select! {
+            _ = &mut shutdown => return Ok(()),
+            Some(_) = tasks.join_next(), if !tasks.is_empty() => {}
+            Some((peer, id)) = closed_rx.recv() => {
+                // 会话结束后同一客户端可能已经开始了新会话，只移除编号相同的
+                if sessions.get(&peer).is_some_and(|(current, _)| *current == id) {
+                    sessions.remove(&peer);
+                }
+            }
+            received = socket.recv_from(&mut buf) => {
+                // Windows 上之前发出的数据报触发的 ICMP 不可达会在这里报错，不影响其他客户端
+                let Ok((n, peer)) = received else { continue };
+                let queue = match sessions.get(&peer) {
+                    Some((_, queue)) if !queue.is_closed() => queue.clone(),
+                    _ => {
+                        let bind: SocketAddr = if target.is_ipv4() {
+                            ([0, 0, 0, 0], 0).into()
+                        } else {
+                            ([0u16; 8], 0).into()
+                        };
+                        let upstream = UdpSocket::bind(bind).await?;
+                        let id = forwarder.next_id();
+                        forwarder.log(id, peer, format_args!("session started"));
+                        let (queue, datagrams) = mpsc::channel(QUEUED_CHUNKS);
+                        tasks.spawn(udp_session(
+                            UdpSession {
+                                id,
+                                peer,
+                                target,
+                                socket: Arc::clone(&socket),
+                                upstream: Arc::new(upstream),
+                                forwarder: Arc::clone(&forwarder),
+                                idle,
+                            },
+                            datagrams,
+                            closed_tx.clone(),
+                        ));
+                        sessions.insert(peer, (id, queue.clone()));
+                        queue
+                    }
+                };
+                // 会话队列满时丢弃，和网络拥塞时一样
+                let _ = queue.try_send(buf[..n].to_vec());
+            }
+        }
+    }
+}
+
+struct UdpSession {
+    id: u64,
+    peer: SocketAddr,
+    target: SocketAddr,
+    // 监听 socket，用来把回复发回客户端
+    socket: Arc<UdpSocket>,
+    upstream: Arc<UdpSocket>,
+    forwarder: Arc<Forwarder>,
+    idle: Duration,
+}
+
+async fn udp_session(
+    session: UdpSession,
+    mut datagrams: mpsc::Receiver<Vec<u8>>,
+    closed: mpsc::UnboundedSender<(SocketAddr, u64)>,
+) {
+    let started = Instant::now();
+    let traffic = Traffic::default();
+    let chaos = &session.forwarder.chaos;
+    let limiters = chaos.bandwidth.map(|rate| {
+        (
+            RateLimiter::new(rate, (rate / 20).max(1)),
+            RateLimiter::new(rate, (rate / 20).max(1)),
+        )
+    });
+    let mut buf = vec![0u8; MAX_DATAGRAM];
+    loop {
+        tokio::select! {
+            datagram = datagrams.recv() => {
+                let Some(datagram) = datagram else { break };
+                let len = datagram.len();
+                let limiter = limiters.as_ref().map(|(up, _)| up);
+                if relay(chaos, limiter, &session.upstream, session.target, datagram) {
+                    session.forwarder.count(&traffic, true, len);
+                }
+            }
+            received = session.upstream.recv_from(&mut buf) => {
+                match received {
+                    // 上游 socket 只和目标通信，忽略其他来源的数据报
+                    Ok((n, from)) if from == session.target => {
+                        let limiter = limiters.as_ref().map(|(_, down)| down);
+                        let datagram = buf[..n].to_vec();
+                        if relay(chaos, limiter, &session.socket, session.peer, datagram) {
+                            session.forwarder.count(&traffic, false, n);
+                        }
+                    }
+                    Ok(_) | Err(_) => {}
+                }
+            }
+            _ = tokio::time::sleep(session.idle) => break,
+        }
+    }
+    session.forwarder.closed(
+        session.id,
+        session.peer,
+        started,
+        &traffic,
+        "session closed",
+    );
+    let _ = closed.send((session.peer, session.id));
+}
+
+// 按注入的故障发出一个数据报：drop 按数据报丢弃，有延迟时由单独的任务延后发出，
+// 延迟不同的数据报因此可能乱序，和真实网络一样；限速排队超过 UDP_QUEUE_LIMIT 的数据报也丢弃。
+// 返回数据报是否发出（或已排队等待发出）
+fn relay(
+    chaos: &Chaos,
+    limiter: Option<&RateLimiter>,
+    socket: &Arc<UdpSocket>,
+    to: SocketAddr,
+    datagram: Vec<u8>,
+) -> bool {
+    if chaos.drops() {
+        return false;
+    }
+    let queued = limiter.map_or(Duration::ZERO, |limiter| {
+        limiter.reserve_n(datagram.len() as u32)
+    });
+    if queued > UDP_QUEUE_LIMIT {
+        return false;
+    }
+    let delay = chaos.delay() + queued;
+    let socket = Arc::clone(socket);
+    tokio::spawn(async move {
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+        let _ = socket.send_to(&datagram, to).await;
+    });
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_chaos_spec() {
+        let chaos = Chaos::parse("latency=200ms±50,drop=1%,bandwidth=1mbps").unwrap();
+        assert_eq!(
+            chaos,
+            Chaos {
+                latency: Duration::from_millis(200),
+                jitter: Duration::from_millis(50),
+                drop: 0.01,
+                bandwidth: Some(125_000),
+            }
+        );
+        let chaos = Chaos::parse("latency=1s+-0.1s, drop=0.5").unwrap();
+        assert_eq!(chaos.jitter, Duration::from_millis(100));
+        assert_eq!(chaos.drop, 0.5);
+        for _ in 0..100 {
+            let delay = chaos.delay();
+            assert!(delay >= Duration::from_millis(900) && delay <= Duration::from_millis(1100));
+        }
+        assert_eq!(parse_bandwidth("512Kbps"), Ok(64_000));
+        for bad in [
+            "",
+            "latency",
+            "latency=fast",
+            "drop=150%",
+            "bandwidth=1mb",
+            "bandwidth=1bps",
+            "loss=1%",
+        ] {
+            assert!(Chaos::parse(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_forward_injects_latency_and_throttles() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let echo = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let target = echo.local_addr().unwrap().to_string();
+            tokio::spawn(async move {
+                loop {
+                    let (mut stream, _) = echo.accept().await.unwrap();
+                    tokio::spawn(async move {
+                        let (mut read, mut write) = stream.split();
+                        let _ = tokio::io::copy(&mut read, &mut write).await;
+                    });
+                }
+            });
+
+            // 20000 字节/秒、突发 1000 字节：去程 50ms 延迟后限速约 150ms 发完，
+            // 回程与去程流水线重叠，至少再加 50ms 延迟
+            let chaos = Chaos::parse("latency=50ms,bandwidth=160kbps").unwrap();
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            let forwarder = Arc::new(Forwarder::new(target.clone(), chaos, true));
+            tokio::spawn(serve(
+                listener,
+                Arc::clone(&forwarder),
+                std::future::pending(),
+            ));
+
+            let payload: Vec<u8> = (0..4000u32).map(|i| i as u8).collect();
+            let started = Instant::now();
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+            stream.write_all(&payload).await.unwrap();
+            stream.shutdown().await.unwrap();
+            let mut echoed = Vec::new();
+            stream.read_to_end(&mut echoed).await.unwrap();
+            assert_eq!(echoed, payload);
+            assert!(started.elapsed() >= Duration::from_millis(240));
+            assert_eq!(forwarder.total.up.load(Ordering::Relaxed), 4000);
+            assert_eq!(forwarder.total.down.load(Ordering::Relaxed), 4000);
+
+            // drop=100% 时连接被立即断开
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            let chaos = Chaos::parse("drop=100%").unwrap();
+            let forwarder = Arc::new(Forwarder::new(target, chaos, true));
+            tokio::spawn(serve(listener, forwarder, std::future::pending()));
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+            let mut buf = [0u8; 1];
+            assert!(!matches!(stream.read(&mut buf).await, Ok(n) if n > 0));
+        });
+    }
+
+    #[test]
+    fn test_forward_udp_sessions() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let echo = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+            let target = echo.local_addr().unwrap();
+            tokio::spawn(async move {
+                let mut buf = [0u8; 1500];
+                loop {
+                    let (n, from) = echo.recv_from(&mut buf).await.unwrap();
+                    echo.send_to(&buf[..n], from).await.unwrap();
+                }
+            });
+
+            let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+            let addr = socket.local_addr().unwrap();
+            let forwarder = Arc::new(Forwarder::new(target.to_string(), Chaos::default(), true));
+            let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+            let server = tokio::spawn(serve_udp(
+                socket,
+                target,
+                Arc::clone(&forwarder),
+                Duration::from_millis(200),
+                async {
+                    let _ = stopped.await;
+                },
+            ));
+
+            // 两个客户端各自对应一个会话，回复发回各自的地址
+            let mut buf = [0u8; 1500];
+            for payload in [&b"first"[..], b"second"] {
+                let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+                client.send_to(payload, addr).await.unwrap();
+                let (n, from) =
+                    tokio::time::timeout(Duration::from_secs(2), client.recv_from(&mut buf))
+                        .await
+                        .unwrap()
+                        .unwrap();
+                assert_eq!(&buf[..n], payload);
+                assert_eq!(from, addr);
+            }
+            assert_eq!(forwarder.next_id.load(Ordering::Relaxed), 2);
+            assert_eq!(forwarder.total.up.load(Ordering::Relaxed), 11);
+            assert_eq!(forwarder.total.down.load(Ordering::Relaxed), 11);
+
+            stop.send(()).unwrap();
+            server.await.unwrap().unwrap();
+        });
+    }
+}
@@ -0,0 +1,379 @@
+use std::fs::File;
+use std::io::{self, Write};
+use std::net::IpAddr;
+use std::path::PathBuf;
+
+use clap::ValueEnum;
+use maxminddb::{geoip2, MaxMindDBError, Reader};
+use serde::Serialize;
+
+use crate::utils::iplist;
+use crate::utils::output::RecordOutput;
+
+#[derive(clap::Args)]
+pub struct GeoIpOpts {
+    #[arg(
+        value_name = "IP",
+        help = "要查询的 IP，省略或为 - 时从 stdin 逐行读取"
+    )]
+    ips: Vec<String>,
+
+    #[arg(
+        short = 'd',
+        long = "db",
+        value_name = "MMDB",
+        required = true,
+        help = "MaxMind 数据库文件，可重复指定，例如 GeoLite2-City.mmdb 和 GeoLite2-ASN.mmdb"
+    )]
+    dbs: Vec<PathBuf>,
+
+    #[arg(
+        value_enum,
+        short = 'f',
+        long,
+        default_value_t = GeoIpFormat::Csv,
+        help = "输出格式 csv | jsonl"
+    )]
+    format: GeoIpFormat,
+
+    #[arg(
+        long,
+        default_value = "en",
+        value_name = "LANG",
+        help = "地名语言，例如 en、zh-CN，缺失时回退到英文"
+    )]
+    lang: String,
+
+    #[arg(
+        short = 'o',
+        long,
+        value_name = "FILE",
+        help = "输出文件，默认输出到 stdout"
+    )]
+    output: Option<PathBuf>,
+
+    // 全局 --where / --fields / --rename，由命令行入口注入
+    #[arg(skip)]
+    pub(crate) record_output: RecordOutput,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum GeoIpFormat {
+    Csv,
+    Jsonl,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum GeoIpError {
+    #[error("invalid ip: {0}")]
+    InvalidIp(String),
+    #[error("failed to open {path}: {source}")]
+    OpenDatabase {
+        path: PathBuf,
+        source: MaxMindDBError,
+    },
+    #[error("unsupported database type {0}, expected a City, Country or ASN database")]
+    UnsupportedDatabase(String),
+    #[error("lookup {ip} failed: {source}")]
+    Lookup { ip: IpAddr, source: MaxMindDBError },
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("csv write failed: {0}")]
+    Csv(#[from] csv::Error),
+    #[error("json serialize failed: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Default, Serialize, PartialEq)]
+pub struct GeoRecord {
+    pub ip: String,
+    pub country_code: Option<String>,
+    pub country: Option<String>,
+    pub region: Option<String>,
+    pub city: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub asn: Option<u32>,
+    pub as_org: Option<String>,
+}
+
+// 按 metadata.database_type 区分地理库和 ASN 库，同类库只保留第一个
+#[derive(Default)]
+pub struct GeoDatabases {
+    location: Option<Reader<Vec<u8>>>,
+    asn: Option<Reader<Vec<u8>>>,
+}
+
+impl GeoDatabases {
+    pub fn open(paths: &[PathBuf]) -> Result<Self, GeoIpError> {
+        let mut dbs = GeoDatabases::default();
+        for path in paths {
+            let reader =
+                Reader::open_readfile(path).map_err(|source| GeoIpError::OpenDatabase {
+                    path: path.clone(),
+                    source,
+                })?;
+            dbs.add(reader)?;
+        }
+        Ok(dbs)
+    }
+
+    fn add(&mut self, reader: Reader<Vec<u8>>) -> Result<(), GeoIpError> {
+        let db_type = reader.metadata.database_type.clone();
+        let slot = if db_type.contains("ASN") {
+            &mut self.asn
+        } else if db_type.contains("City") || db_type.contains("Country") {
+            &mut self.location
+        } else {
+            return Err(GeoIpError::UnsupportedDatabase(db_type));
+        };
+        if slot.is_none() {
+            *slot = Some(reader);
+        }
+        Ok(())
+    }
+
+    pub fn lookup(&self, ip: IpAddr, lang: &str) -> Result<GeoRecord, GeoIpError> {
+        let mut record = GeoRecord {
+            ip: ip.to_string(),
+            ..GeoRecord::default()
+        };
+
+        if let Some(city) = lookup_in::<geoip2::City>(self.location.as_ref(), ip)? {
+            if let Some(country) = city.country {
+                record.country_code = country.iso_code.map(str::to_string);
+                record.country = localized(country.names.as_ref(), lang);
+            }
+            record.region = city
+                .subdivisions
+                .as_ref()
+                .and_then(|subdivisions| subdivisions.first())
+                .and_then(|subdivision| localized(subdivision.names.as_ref(), lang));
+            record.city = city
+                .city
+                .and_then(|city| localized(city.names.as_ref(), lang));
+            if let Some(location) = city.location {
+                record.latitude = location.latitude;
+                record.longitude = location.longitude;
+            }
+        }
+
+        if let Some(asn) = lookup_in::<geoip2::Asn>(self.asn.as_ref(), ip)? {
+            record.asn = asn.autonomous_system_number;
+            record.as_org = asn.autonomous_system_organization.map(str::to_string);
+        }
+        Ok(record)
+    }
+}
+
+fn lookup_in<'a, T: serde::Deserialize<'a>>(
+    reader: Option<&'a Reader<Vec<u8>>>,
+    ip: IpAddr,
+) -> Result<Option<T>, GeoIpError> {
+    let Some(reader) = reader else {
+        return Ok(None);
+    };
+    // IPv4-only 库无法查询 IPv6 地址，直接视为无数据
+    if reader.metadata.ip_version == 4 && ip.is_ipv6() {
+        return Ok(None);
+    }
+    match reader.lookup::<T>(ip) {
+        Ok(value) => Ok(Some(value)),
+        Err(MaxMindDBError::AddressNotFoundError(_)) => Ok(None),
+        Err(source) => Err(GeoIpError::Lookup { ip, source }),
+    }
+}
+
+fn localized(names: Option<&std::collections::BTreeMap<&str, &str>>, lang: &str) -> Option<String> {
+    let names = names?;
+    names
+        .get(lang)
+        .or_else(|| names.get("en"))
+        .or_else(|| names.values().next())
+        .map(|name| name.to_string())
+}
+
+pub fn run_geoip(opts: GeoIpOpts) -> Result<(), GeoIpError> {
+    let dbs = GeoDatabases::open(&opts.dbs)?;
+    let ips = iplist::collect_ips(&opts.ips, GeoIpError::InvalidIp)?;
+
+    let mut out: Box<dyn Write> = match &opts.output {
+        Some(path) => Box::new(io::BufWriter::new(File::create(path)?)),
+        None => Box::new(io::BufWriter::new(io::stdout().lock())),
+    };
+
+    let mut written = 0;
+    match opts.format {
+        GeoIpFormat::Csv => {
+            let mut writer = opts.record_output.csv_stream(out);
+            for ip in &ips {
+                let record = dbs.lookup(*ip, &opts.lang)?;
+                if opts.record_output.keep(&record)? {
+                    writer.write(&opts.record_output, &record)?;
+                    written += 1;
+                }
+            }
+            writer.flush()?;
+        }
+        GeoIpFormat::Jsonl => {
+            for ip in &ips {
+                let record = dbs.lookup(*ip, &opts.lang)?;
+                if opts.record_output.keep(&record)? {
+                    opts.record_output.write_json_line(&record, &mut out)?;
+                    written += 1;
+                }
+            }
+            out.flush()?;
+        }
+    }
+
+    if let Some(path) = &opts.output {
+        eprintln!("Wrote {} records to {}", written, path.display());
+    }
+    Ok(())
+}
+
+// 命令行参数为空或为 - 时从 stdin 读取；每行取第一个可解析为 IP 的字段，
+// 无 IP 的行直接跳过，方便直接处理扫描结果或日志摘录
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 以下辅助函数按 MaxMind DB 格式手工拼出一个只包含 1.0.0.0/8 的 IPv4 测试库
+    fn string(s: &str) -> Vec<u8> {
+        // 长度 >= 29 时控制字节取 29，多出的部分写在下一个字节
+        let mut out = match s.len() {
+            len @ 0..=28 => vec![0x40 | len as u8],
+            len => vec![0x40 | 29, (len - 29) as u8],
+        };
+        out.extend_from_slice(s.as_bytes());
+        out
+    }
+
+    fn map(entries: Vec<(&str, Vec<u8>)>) -> Vec<u8> {
+        let mut out = vec![0xE0 | entries.len() as u8];
+        for (key, value) in entries {
+            out.extend(string(key));
+            out.extend(value);
+        }
+        out
+    }
+
+    fn uint16(value: u16) -> Vec<u8> {
+        let mut out = vec![0xA2];
+        out.extend_from_slice(&value.to_be_bytes());
+        out
+    }
+
+    fn uint32(value: u32) -> Vec<u8> {
+        let mut out = vec![0xC4];
+        out.extend_from_slice(&value.to_be_bytes());
+        out
+    }
+
+    fn double(value: f64) -> Vec<u8> {
+        let mut out = vec![0x68];
+        out.extend_from_slice(&value.to_be_bytes());
+        out
+    }
+
+    fn names(en: &str, zh: &str) -> Vec<u8> {
+        map(vec![("en", string(en)), ("zh-CN", string(zh))])
+    }
+
+    fn build_mmdb(database_type: &str, data: Vec<u8>) -> Reader<Vec<u8>> {
+        let node_count: u32 = 8;
+        let mut buf = Vec::new();
+        // 1.0.0.0/8 的前 8 位为 00000001，沿该路径建 8 个节点，另一侧指向空记录
+        for node in 0..node_count {
+            let bit = if node == 7 { 1 } else { 0 };
+            let next = if node == 7 { node_count + 16 } else { node + 1 };
+            let (left, right) = if bit == 0 {
+                (next, node_count)
+            } else {
+                (node_count, next)
+            };
+            buf.extend_from_slice(&left.to_be_bytes()[1..]);
+            buf.extend_from_slice(&right.to_be_bytes()[1..]);
+        }
+        buf.extend_from_slice(&[0u8; 16]);
+        buf.extend(data);
+        buf.extend_from_slice(b"\xab\xcd\xefMaxMind.com");
+        buf.extend(map(vec![
+            ("binary_format_major_version", uint16(2)),
+            ("binary_format_minor_version", uint16(0)),
+            ("build_epoch", {
+                let mut epoch = vec![0x04, 0x02];
+                epoch.extend_from_slice(&1_700_000_000u32.to_be_bytes());
+                epoch
+            }),
+            ("database_type", string(database_type)),
+            ("description", map(vec![("en", string("test"))])),
+            ("ip_version", uint16(4)),
+            ("languages", vec![0x01, 0x04, 0x42, b'e', b'n']),
+            ("node_count", uint32(node_count)),
+            ("record_size", uint16(24)),
+        ]));
+        Reader::from_source(buf).unwrap()
+    }
+
+    fn test_databases() -> GeoDatabases {
+        let city = map(vec![
+            ("city", map(vec![("names", names("Brisbane", "布里斯班"))])),
+            (
+                "country",
+                map(vec![
+                    ("iso_code", string("AU")),
+                    ("names", names("Australia", "澳大利亚")),
+                ]),
+            ),
+            (
+                "location",
+                map(vec![
+                    ("latitude", double(-27.47)),
+                    ("longitude", double(153.02)),
+                ]),
+            ),
+        ]);
+        let asn = map(vec![
+            ("autonomous_system_number", uint32(13335)),
+            ("autonomous_system_organization", string("CLOUDFLARENET")),
+        ]);
+
+        let mut dbs = GeoDatabases::default();
+        dbs.add(build_mmdb("GeoLite2-City", city)).unwrap();
+        dbs.add(build_mmdb("GeoLite2-ASN", asn)).unwrap();
+        dbs
+    }
+
+    #[test]
+    fn test_lookup_merges_city_and_asn() {
+        let dbs = test_databases();
+        let record = dbs.lookup("1.1.1.1".parse().unwrap(), "zh-CN").unwrap();
+        assert_eq!(record.country_code.as_deref(), Some("AU"));
+        assert_eq!(record.country.as_deref(), Some("澳大利亚"));
+        assert_eq!(record.city.as_deref(), Some("布里斯班"));
+        assert_eq!(record.region, None);
+        assert_eq!(record.latitude, Some(-27.47));
+        assert_eq!(record.asn, Some(13335));
+        assert_eq!(record.as_org.as_deref(), Some("CLOUDFLARENET"));
+
+        let english = dbs.lookup("1.2.3.4".parse().unwrap(), "ja").unwrap();
+        assert_eq!(english.country.as_deref(), Some("Australia"));
+    }
+
+    #[test]
+    fn test_lookup_missing_address_is_empty() {
+        let dbs = test_databases();
+        for ip in ["8.8.8.8", "2001:db8::1"] {
+            let record = dbs.lookup(ip.parse().unwrap(), "en").unwrap();
+            assert_eq!(
+                record,
+                GeoRecord {
+                    ip: ip.to_string(),
+                    ..GeoRecord::default()
+                }
+            );
+        }
+    }
+}
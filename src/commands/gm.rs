@@ -0,0 +1,483 @@
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use clap::{Subcommand, ValueEnum};
+use rand::{rng, Rng};
+
+use crate::utils::format::{decode_hex, encode_hex};
+use crate::utils::pki::write_private_file;
+use crate::utils::sm2::{self, CipherOrder, PrivateKey, PublicKey, Signature, Sm2Error};
+use crate::utils::sm4::{self, Sm4, Sm4Error, Sm4Mode};
+
+#[derive(clap::Args)]
+pub struct GmOpts {
+    #[command(subcommand)]
+    command: GmCommand,
+}
+
+#[derive(Subcommand)]
+enum GmCommand {
+    #[command(about = "生成 SM2 密钥对（PKCS#8 / SubjectPublicKeyInfo PEM 或十六进制）")]
+    Sm2Keygen(Sm2KeygenOpts),
+    #[command(about = "使用 SM2 私钥签名（SM3 杂凑，带用户标识）")]
+    Sm2Sign(Sm2SignOpts),
+    #[command(about = "使用 SM2 公钥验签")]
+    Sm2Verify(Sm2VerifyOpts),
+    #[command(about = "使用 SM2 公钥加密")]
+    Sm2Encrypt(Sm2CryptOpts),
+    #[command(about = "使用 SM2 私钥解密")]
+    Sm2Decrypt(Sm2CryptOpts),
+    #[command(about = "生成随机 SM4 密钥（32 位十六进制）")]
+    Sm4Keygen,
+    #[command(about = "SM4 加密（ECB / CBC，PKCS#7 填充）")]
+    Sm4Encrypt(Sm4Opts),
+    #[command(about = "SM4 解密")]
+    Sm4Decrypt(Sm4Opts),
+}
+
+#[derive(clap::Args)]
+struct InputArgs {
+    #[arg(value_name = "FILE", help = "输入文件，省略或为 - 时读取标准输入")]
+    file: Option<PathBuf>,
+
+    #[arg(
+        short = 't',
+        long,
+        value_name = "TEXT",
+        conflicts_with = "file",
+        help = "直接使用给定字符串作为输入"
+    )]
+    text: Option<String>,
+
+    #[arg(long, value_name = "FILE", help = "结果写入文件，默认输出到 stdout")]
+    out: Option<PathBuf>,
+
+    #[arg(
+        value_enum,
+        short = 'e',
+        long,
+        default_value_t = Encoding::Hex,
+        help = "密文和签名的编码 hex | base64"
+    )]
+    encoding: Encoding,
+}
+
+#[derive(clap::Args)]
+struct Sm2KeygenOpts {
+    #[arg(value_enum, long, default_value_t = KeyFormat::Pem, help = "密钥格式 pem | hex")]
+    format: KeyFormat,
+
+    #[arg(
+        long,
+        value_name = "PREFIX",
+        help = "写入 PREFIX.key（私钥）和 PREFIX.pub（公钥），默认输出到 stdout"
+    )]
+    out: Option<PathBuf>,
+}
+
+#[derive(clap::Args)]
+struct Sm2SignOpts {
+    #[command(flatten)]
+    input: InputArgs,
+
+    #[arg(
+        short = 'k',
+        long,
+        value_name = "KEY",
+        help = "SM2 私钥：PEM 文件路径，或 64 位十六进制私钥"
+    )]
+    key: String,
+
+    #[arg(
+        long,
+        value_name = "ID",
+        default_value = "1234567812345678",
+        help = "签名方用户标识"
+    )]
+    id: String,
+
+    #[arg(
+        value_enum,
+        long = "signature-format",
+        default_value_t = SignatureFormat::Der,
+        help = "签名格式 der | raw；raw 为 r || s 共 64 字节"
+    )]
+    signature_format: SignatureFormat,
+}
+
+#[derive(clap::Args)]
+struct Sm2VerifyOpts {
+    #[command(flatten)]
+    input: InputArgs,
+
+    #[arg(
+        short = 'k',
+        long,
+        value_name = "KEY",
+        help = "SM2 公钥：PEM 文件路径，或 04 开头的十六进制公钥；也可以给出私钥"
+    )]
+    key: String,
+
+    #[arg(
+        short = 's',
+        long,
+        value_name = "SIG",
+        help = "待验证的签名，按 --encoding 解码，DER 或 r || s 均可"
+    )]
+    signature: String,
+
+    #[arg(
+        long,
+        value_name = "ID",
+        default_value = "1234567812345678",
+        help = "签名方用户标识"
+    )]
+    id: String,
+}
+
+#[derive(clap::Args)]
+struct Sm2CryptOpts {
+    #[command(flatten)]
+    input: InputArgs,
+
+    #[arg(
+        short = 'k',
+        long,
+        value_name = "KEY",
+        help = "加密用公钥、解密用私钥：PEM 文件路径或十六进制"
+    )]
+    key: String,
+
+    #[arg(
+        value_enum,
+        long,
+        default_value_t = CipherLayout::C1c3c2,
+        help = "密文分量顺序 c1c3c2 | c1c2c3；GM/T 0003-2012 为 c1c3c2"
+    )]
+    order: CipherLayout,
+}
+
+#[derive(clap::Args)]
+struct Sm4Opts {
+    #[command(flatten)]
+    input: InputArgs,
+
+    #[arg(
+        short = 'k',
+        long,
+        value_name = "HEX",
+        help = "SM4 密钥，32 位十六进制"
+    )]
+    key: String,
+
+    #[arg(value_enum, long, default_value_t = BlockMode::Cbc, help = "工作模式 ecb | cbc")]
+    mode: BlockMode,
+
+    #[arg(long, value_name = "HEX", help = "CBC 模式的初始向量，32 位十六进制")]
+    iv: Option<String>,
+
+    #[arg(long, help = "不做 PKCS#7 填充，输入长度必须是 16 字节的整数倍")]
+    no_padding: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Encoding {
+    Hex,
+    Base64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum KeyFormat {
+    Pem,
+    Hex,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum SignatureFormat {
+    Der,
+    Raw,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum CipherLayout {
+    C1c3c2,
+    C1c2c3,
+}
+
+impl CipherLayout {
+    fn order(self) -> CipherOrder {
+        match self {
+            CipherLayout::C1c3c2 => CipherOrder::C1C3C2,
+            CipherLayout::C1c2c3 => CipherOrder::C1C2C3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum BlockMode {
+    Ecb,
+    Cbc,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum GmError {
+    #[error(transparent)]
+    Sm2(#[from] Sm2Error),
+    #[error(transparent)]
+    Sm4(#[from] Sm4Error),
+    #[error("invalid {0}: expected {1}")]
+    InvalidInput(&'static str, &'static str),
+    #[error("--mode cbc needs --iv")]
+    MissingIv,
+    #[error("signature verification failed")]
+    VerifyFailed,
+    #[error("cannot read {path}: {source}")]
+    Open {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+pub fn run_gm(opts: GmOpts) -> Result<(), GmError> {
+    match opts.command {
+        GmCommand::Sm2Keygen(opts) => sm2_keygen(opts),
+        GmCommand::Sm2Sign(opts) => {
+            let key = load_private_key(&opts.key)?;
+            let message = opts.input.read_input()?;
+            let signature = key.sign(&message, opts.id.as_bytes());
+            let bytes = match opts.signature_format {
+                SignatureFormat::Der => signature.to_der(),
+                SignatureFormat::Raw => signature.to_raw(),
+            };
+            opts.input.write_encoded(&bytes)
+        }
+        GmCommand::Sm2Verify(opts) => {
+            let key = load_public_key(&opts.key)?;
+            let message = opts.input.read_input()?;
+            let signature = opts.input.encoding.decode(&opts.signature, "signature")?;
+            let signature = Signature::from_bytes(&signature)?;
+            if !key.verify(&message, opts.id.as_bytes(), &signature) {
+                return Err(GmError::VerifyFailed);
+            }
+            println!("Signature OK");
+            Ok(())
+        }
+        GmCommand::Sm2Encrypt(opts) => {
+            let key = load_public_key(&opts.key)?;
+            let message = opts.input.read_input()?;
+            let cipher = key.encrypt(&message, opts.order.order());
+            opts.input.write_encoded(&cipher)
+        }
+        GmCommand::Sm2Decrypt(opts) => {
+            let key = load_private_key(&opts.key)?;
+            let cipher = opts.input.read_encoded()?;
+            let message = key.decrypt(&cipher, opts.order.order())?;
+            opts.input.write_raw(&message)
+        }
+        GmCommand::Sm4Keygen => {
+            let mut key = [0u8; sm4::KEY_LEN];
+            rng().fill(&mut key[..]);
+            println!("{}", encode_hex(&key, false));
+            Ok(())
+        }
+        GmCommand::Sm4Encrypt(opts) => {
+            let (sm4, mode) = opts.cipher()?;
+            let plain = opts.input.read_input()?;
+            let cipher = sm4.encrypt(mode, &plain, !opts.no_padding)?;
+            opts.input.write_encoded(&cipher)
+        }
+        GmCommand::Sm4Decrypt(opts) => {
+            let (sm4, mode) = opts.cipher()?;
+            let cipher = opts.input.read_encoded()?;
+            let plain = sm4.decrypt(mode, &cipher, !opts.no_padding)?;
+            opts.input.write_raw(&plain)
+        }
+    }
+}
+
+fn sm2_keygen(opts: Sm2KeygenOpts) -> Result<(), GmError> {
+    let key = PrivateKey::generate();
+    let (private, public) = match opts.format {
+        KeyFormat::Pem => (key.to_pem(), key.public_key().to_pem()),
+        KeyFormat::Hex => (
+            format!("{}\n", encode_hex(&key.to_bytes(), false)),
+            format!("{}\n", encode_hex(&key.public_key().to_bytes(), false)),
+        ),
+    };
+    match opts.out {
+        Some(prefix) => {
+            let private_path = prefix.with_extension("key");
+            let public_path = prefix.with_extension("pub");
+            write_private_file(&private_path, private.as_bytes())?;
+            fs::write(&public_path, public)?;
+            eprintln!(
+                "Wrote {} and {}",
+                private_path.display(),
+                public_path.display()
+            );
+        }
+        None => print!("{}{}", private, public),
+    }
+    Ok(())
+}
+
+impl Sm4Opts {
+    fn cipher(&self) -> Result<(Sm4, Sm4Mode), GmError> {
+        let key: [u8; sm4::KEY_LEN] = decode_hex(&self.key)
+            .and_then(|key| key.try_into().ok())
+            .ok_or(GmError::InvalidInput("SM4 key", "32 hex characters"))?;
+        let mode = match (self.mode, &self.iv) {
+            (BlockMode::Ecb, _) => Sm4Mode::Ecb,
+            (BlockMode::Cbc, None) => return Err(GmError::MissingIv),
+            (BlockMode::Cbc, Some(iv)) => Sm4Mode::Cbc(
+                decode_hex(iv)
+                    .and_then(|iv| iv.try_into().ok())
+                    .ok_or(GmError::InvalidInput("IV", "32 hex characters"))?,
+            ),
+        };
+        Ok((Sm4::new(&key), mode))
+    }
+}
+
+impl InputArgs {
+    fn read_input(&self) -> Result<Vec<u8>, GmError> {
+        if let Some(text) = &self.text {
+            return Ok(text.as_bytes().to_vec());
+        }
+        match self.file.as_ref().filter(|path| path.as_os_str() != "-") {
+            Some(path) => fs::read(path).map_err(|source| GmError::Open {
+                path: path.clone(),
+                source,
+            }),
+            None => {
+                let mut data = Vec::new();
+                io::stdin().lock().read_to_end(&mut data)?;
+                Ok(data)
+            }
+        }
+    }
+
+    // 读取按 --encoding 编码的密文，忽略其中的空白和换行
+    fn read_encoded(&self) -> Result<Vec<u8>, GmError> {
+        let data = self.read_input()?;
+        self.encoding
+            .decode(&String::from_utf8_lossy(&data), "ciphertext")
+    }
+
+    fn write_encoded(&self, data: &[u8]) -> Result<(), GmError> {
+        let text = match self.encoding {
+            Encoding::Hex => encode_hex(data, false),
+            Encoding::Base64 => STANDARD.encode(data),
+        };
+        self.write_raw(format!("{}\n", text).as_bytes())
+    }
+
+    fn write_raw(&self, data: &[u8]) -> Result<(), GmError> {
+        match &self.out {
+            Some(path) => fs::write(path, data)?,
+            None => {
+                let mut stdout = io::stdout().lock();
+                stdout.write_all(data)?;
+                stdout.flush()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Encoding {
+    fn decode(self, text: &str, what: &'static str) -> Result<Vec<u8>, GmError> {
+        let compact: String = text.split_whitespace().collect();
+        match self {
+            Encoding::Hex => decode_hex(&compact).ok_or(GmError::InvalidInput(what, "hex")),
+            Encoding::Base64 => STANDARD
+                .decode(compact)
+                .map_err(|_| GmError::InvalidInput(what, "base64")),
+        }
+    }
+}
+
+// 参数为已存在的文件时读取文件内容，否则按字面值处理
+fn key_text(raw: &str) -> Result<String, GmError> {
+    let path = Path::new(raw);
+    if path.is_file() {
+        return fs::read_to_string(path).map_err(|source| GmError::Open {
+            path: path.to_path_buf(),
+            source,
+        });
+    }
+    Ok(raw.to_string())
+}
+
+fn load_private_key(raw: &str) -> Result<PrivateKey, GmError> {
+    let text = key_text(raw)?;
+    if let Some((_, der)) = sm2::parse_pem(&text) {
+        return Ok(PrivateKey::from_der(&der)?);
+    }
+    let bytes = decode_hex(&text).ok_or(GmError::InvalidInput(
+        "SM2 private key",
+        "a PEM file or 64 hex characters",
+    ))?;
+    Ok(PrivateKey::from_bytes(&bytes)?)
+}
+
+// 验签和加密只需要公钥，给出私钥时从中取出公钥
+fn load_public_key(raw: &str) -> Result<PublicKey, GmError> {
+    let text = key_text(raw)?;
+    if let Some((label, der)) = sm2::parse_pem(&text) {
+        if label.contains("PRIVATE") {
+            return Ok(PrivateKey::from_der(&der)?.public_key().clone());
+        }
+        return Ok(PublicKey::from_der(&der)?);
+    }
+    let bytes = decode_hex(&text).ok_or(GmError::InvalidInput(
+        "SM2 public key",
+        "a PEM file or 130 hex characters starting with 04",
+    ))?;
+    if bytes.len() == 32 {
+        return Ok(PrivateKey::from_bytes(&bytes)?.public_key().clone());
+    }
+    Ok(PublicKey::from_bytes(&bytes)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_keys_from_hex_and_pem() {
+        let key = PrivateKey::generate();
+        let private_hex = encode_hex(&key.to_bytes(), false);
+        let public_hex = encode_hex(&key.public_key().to_bytes(), true);
+        assert_eq!(
+            load_private_key(&private_hex).unwrap().to_bytes(),
+            key.to_bytes()
+        );
+        assert_eq!(&load_public_key(&public_hex).unwrap(), key.public_key());
+        assert_eq!(&load_public_key(&private_hex).unwrap(), key.public_key());
+        assert_eq!(&load_public_key(&key.to_pem()).unwrap(), key.public_key());
+        assert_eq!(
+            &load_public_key(&key.public_key().to_pem()).unwrap(),
+            key.public_key()
+        );
+        assert!(matches!(
+            load_private_key("not a key"),
+            Err(GmError::InvalidInput(..))
+        ));
+
+        assert_eq!(
+            Encoding::Hex.decode("0a0b\n0c", "ciphertext").unwrap(),
+            [10, 11, 12]
+        );
+        assert_eq!(
+            Encoding::Base64.decode("AAEC", "ciphertext").unwrap(),
+            [0, 1, 2]
+        );
+    }
+}
@@ -0,0 +1,939 @@
+// gRPC 探测：标准健康检查协议（grpc.health.v1.Health/Check）和服务反射（grpc.reflection.v1，
+// 不支持时退回 v1alpha），不需要 .proto 文件。HTTP/2 交给 h2，protobuf 只手写了这两个服务用到的字段。
+
+use std::io;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+use clap::Subcommand;
+use h2::client::SendRequest;
+use http::header::{HeaderName, HeaderValue};
+use serde::Serialize;
+use tokio::net::TcpStream;
+use tokio_rustls::rustls::pki_types::ServerName;
+use tokio_rustls::TlsConnector;
+
+use crate::commands::dns::split_host_port;
+use crate::commands::http::parse_header;
+use crate::utils::http::HttpStream;
+use crate::utils::network::{self, NetworkError};
+use crate::utils::output::RecordOutput;
+use crate::utils::tls::{insecure_client_config, verified_client_config};
+
+const DEFAULT_PORT: u16 = 50051;
+const DEFAULT_TLS_PORT: u16 = 443;
+const HEALTH_CHECK: &str = "/grpc.health.v1.Health/Check";
+const REFLECTION_PATHS: [&str; 2] = [
+    "/grpc.reflection.v1.ServerReflection/ServerReflectionInfo",
+    "/grpc.reflection.v1alpha.ServerReflection/ServerReflectionInfo",
+];
+// 单次响应的上限，反射返回的描述文件一般只有几十 KB
+const MAX_RESPONSE: usize = 16 << 20;
+
+const STATUS_NOT_FOUND: u32 = 5;
+const STATUS_UNIMPLEMENTED: u32 = 12;
+
+#[derive(clap::Args)]
+pub struct GrpcOpts {
+    #[command(subcommand)]
+    command: GrpcCommand,
+}
+
+impl GrpcOpts {
+    pub(crate) fn record_output(&mut self) -> &mut RecordOutput {
+        match &mut self.command {
+            GrpcCommand::Health(opts) => &mut opts.connect.record_output,
+            GrpcCommand::List(opts) => &mut opts.connect.record_output,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum GrpcCommand {
+    #[command(
+        about = "调用标准健康检查接口 grpc.health.v1.Health/Check，不是 SERVING 时以非零状态退出"
+    )]
+    Health(HealthOpts),
+    #[command(about = "通过服务反射列出服务和方法")]
+    List(ListOpts),
+}
+
+#[derive(clap::Args)]
+struct ConnectOpts {
+    #[arg(
+        value_name = "HOST:PORT",
+        help = "gRPC 服务地址，省略端口时明文为 50051，TLS 为 443"
+    )]
+    target: String,
+
+    #[arg(long, help = "使用 TLS，按内置根证书校验服务端证书")]
+    tls: bool,
+
+    #[arg(short = 'k', long, help = "使用 TLS 但不校验证书，适合自签名证书")]
+    insecure: bool,
+
+    #[arg(
+        long,
+        value_name = "HOST",
+        help = ":authority 和 TLS SNI 使用的主机名，默认与目标主机相同"
+    )]
+    authority: Option<String>,
+
+    #[arg(
+        short = 'H',
+        long = "header",
+        value_name = "NAME: VALUE",
+        help = "附加的请求元数据，例如 authorization，可重复"
+    )]
+    headers: Vec<String>,
+
+    #[arg(
+        long,
+        default_value_t = 5000,
+        value_name = "MS",
+        help = "连接和每次调用的超时时间（毫秒）"
+    )]
+    timeout: u64,
+
+    #[arg(long, help = "以 JSON 输出结果")]
+    json: bool,
+
+    // 全局 --where / --fields / --rename，由命令行入口注入
+    #[arg(skip)]
+    pub(crate) record_output: RecordOutput,
+}
+
+#[derive(clap::Args)]
+struct HealthOpts {
+    #[command(flatten)]
+    connect: ConnectOpts,
+
+    #[arg(
+        short = 's',
+        long,
+        default_value = "",
+        value_name = "SERVICE",
+        help = "要检查的服务名，例如 helloworld.Greeter；默认检查整个服务器"
+    )]
+    service: String,
+}
+
+#[derive(clap::Args)]
+struct ListOpts {
+    #[command(flatten)]
+    connect: ConnectOpts,
+
+    #[arg(value_name = "SERVICE", help = "只列出该服务的方法")]
+    service: Option<String>,
+
+    #[arg(short = 'l', long, help = "同时列出每个服务的方法")]
+    long: bool,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum GrpcError {
+    #[error("invalid target '{0}', expected HOST:PORT")]
+    InvalidTarget(String),
+    #[error("invalid metadata '{0}', expected NAME: VALUE")]
+    InvalidHeader(String),
+    #[error("timed out after {0} ms")]
+    Timeout(u64),
+    #[error("failed to connect to {target}: {source}")]
+    Connect { target: String, source: io::Error },
+    #[error("TLS handshake failed: {0}")]
+    Tls(io::Error),
+    #[error("server did not negotiate HTTP/2 over TLS (ALPN h2)")]
+    NoAlpn,
+    #[error("HTTP/2 error: {0}")]
+    Http2(#[from] h2::Error),
+    #[error("server answered HTTP {0}, is this a gRPC endpoint?")]
+    HttpStatus(u16),
+    #[error("{}: {message}", status_name(*.code))]
+    Status { code: u32, message: String },
+    #[error("server does not implement {0}")]
+    Unimplemented(&'static str),
+    #[error("service '{0}' not found")]
+    UnknownService(String),
+    #[error("reflection error {code}: {message}")]
+    Reflection { code: u64, message: String },
+    #[error("invalid response: {0}")]
+    Protocol(&'static str),
+    #[error("health status is {0}")]
+    NotServing(&'static str),
+    #[error(transparent)]
+    Network(#[from] NetworkError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Serialize)]
+struct HealthReport {
+    target: String,
+    service: String,
+    status: &'static str,
+    time_ms: f64,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+struct Service {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    methods: Option<Vec<Method>>,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+struct Method {
+    name: String,
+    input: String,
+    output: String,
+    client_streaming: bool,
+    server_streaming: bool,
+}
+
+pub fn run_grpc(opts: GrpcOpts) -> Result<(), GrpcError> {
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(async {
+        match opts.command {
+            GrpcCommand::Health(opts) => run_health(opts).await,
+            GrpcCommand::List(opts) => run_list(opts).await,
+        }
+    })
+}
+
+async fn run_health(opts: HealthOpts) -> Result<(), GrpcError> {
+    let client = Client::connect(&opts.connect).await?;
+    let started = Instant::now();
+    let status = check_health(&client, &opts.service).await?;
+    let report = HealthReport {
+        target: opts.connect.target.clone(),
+        service: opts.service.clone(),
+        status,
+        time_ms: started.elapsed().as_secs_f64() * 1000.0,
+    };
+    // --where 不匹配时不输出，退出状态仍按健康状态
+    if opts.connect.record_output.keep(&report)? {
+        if opts.connect.json {
+            opts.connect
+                .record_output
+                .write_report(&report, None, io::stdout().lock())?;
+        } else {
+            let label = if report.service.is_empty() {
+                "server"
+            } else {
+                report.service.as_str()
+            };
+            println!("{}: {} ({:.1} ms)", label, report.status, report.time_ms);
+        }
+    }
+    if status != "SERVING" {
+        return Err(GrpcError::NotServing(status));
+    }
+    Ok(())
+}
+
+async fn run_list(opts: ListOpts) -> Result<(), GrpcError> {
+    let client = Client::connect(&opts.connect).await?;
+    let mut reflection = Reflection::new(&client);
+    let mut services = match &opts.service {
+        Some(name) => vec![Service {
+            name: name.clone(),
+            methods: None,
+        }],
+        None => {
+            let mut names = reflection.list_services().await?;
+            names.sort();
+            names
+                .into_iter()
+                .map(|name| Service {
+                    name,
+                    methods: None,
+                })
+                .collect()
+        }
+    };
+    if opts.long || opts.service.is_some() {
+        for service in &mut services {
+            service.methods = Some(reflection.describe(&service.name).await?);
+        }
+    }
+
+    opts.connect.record_output.retain(&mut services)?;
+    if opts.connect.json {
+        opts.connect
+            .record_output
+            .write_json(&services, io::stdout().lock())?;
+        return Ok(());
+    }
+    for service in &services {
+        println!("{}", service.name);
+        for method in service.methods.iter().flatten() {
+            println!(
+                "  rpc {}({}{}) returns ({}{})",
+                method.name,
+                if method.client_streaming {
+                    "stream "
+                } else {
+                    ""
+                },
+                method.input,
+                if method.server_streaming {
+                    "stream "
+                } else {
+                    ""
+                },
+                method.output
+            );
+        }
+    }
+    Ok(())
+}
+
+// 返回 SERVING、NOT_SERVING 等状态名；按规范未注册的服务返回 NOT_FOUND，视为 SERVICE_UNKNOWN
+async fn check_health(client: &Client, service: &str) -> Result<&'static str, GrpcError> {
+    let mut request = Vec::new();
+    if !service.is_empty() {
+        put_bytes(&mut request, 1, service.as_bytes());
+    }
+    let response = match client.call(HEALTH_CHECK, request).await {
+        Ok(response) => response,
+        Err(GrpcError::Status { code, .. }) if code == STATUS_NOT_FOUND => {
+            return Ok("SERVICE_UNKNOWN")
+        }
+        Err(GrpcError::Status { code, .. }) if code == STATUS_UNIMPLEMENTED => {
+            return Err(GrpcError::Unimplemented("grpc.health.v1.Health"))
+        }
+        Err(error) => return Err(error),
+    };
+    let status = decode(&response)?
+        .into_iter()
+        .find_map(|(field, value)| match (field, value) {
+            (1, Value::Varint(status)) => Some(status),
+            _ => None,
+        })
+        .unwrap_or(0);
+    Ok(match status {
+        1 => "SERVING",
+        2 => "NOT_SERVING",
+        3 => "SERVICE_UNKNOWN",
+        _ => "UNKNOWN",
+    })
+}
+
+struct Client {
+    send: SendRequest<Bytes>,
+    uri_prefix: String,
+    metadata: Vec<(HeaderName, HeaderValue)>,
+    timeout: Duration,
+    timeout_ms: u64,
+}
+
+impl Client {
+    async fn connect(opts: &ConnectOpts) -> Result<Self, GrpcError> {
+        let invalid_target = || GrpcError::InvalidTarget(opts.target.clone());
+        let (host, port) = split_host_port(&opts.target).ok_or_else(invalid_target)?;
+        let tls = opts.tls || opts.insecure;
+        let port = port.unwrap_or(if tls { DEFAULT_TLS_PORT } else { DEFAULT_PORT });
+        network::check_host("grpc", host)?;
+        let metadata = opts
+            .headers
+            .iter()
+            .map(|raw| {
+                let invalid = || GrpcError::InvalidHeader(raw.clone());
+                let (name, value) = parse_header(raw).map_err(|_| invalid())?;
+                // HTTP/2 的头名称必须小写
+                let name = HeaderName::from_bytes(name.to_ascii_lowercase().as_bytes())
+                    .map_err(|_| invalid())?;
+                let value = HeaderValue::from_str(&value).map_err(|_| invalid())?;
+                Ok((name, value))
+            })
+            .collect::<Result<Vec<_>, GrpcError>>()?;
+
+        let timeout = Duration::from_millis(opts.timeout.max(1));
+        let connect = async {
+            let tcp =
+                TcpStream::connect((host, port))
+                    .await
+                    .map_err(|source| GrpcError::Connect {
+                        target: opts.target.clone(),
+                        source,
+                    })?;
+            tcp.set_nodelay(true)?;
+            let authority = opts.authority.as_deref().unwrap_or(host);
+            let stream: Box<dyn HttpStream> = if tls {
+                let mut config = if opts.insecure {
+                    insecure_client_config()
+                } else {
+                    verified_client_config()
+                };
+                config.alpn_protocols = vec![b"h2".to_vec()];
+                let server_name =
+                    ServerName::try_from(authority.to_string()).map_err(|_| invalid_target())?;
+                let stream = TlsConnector::from(Arc::new(config))
+                    .connect(server_name, tcp)
+                    .await
+                    .map_err(GrpcError::Tls)?;
+                if stream.get_ref().1.alpn_protocol() != Some(b"h2") {
+                    return Err(GrpcError::NoAlpn);
+                }
+                Box::new(stream)
+            } else {
+                Box::new(tcp)
+            };
+            let (send, connection) = h2::client::handshake(stream).await?;
+            tokio::spawn(async move {
+                let _ = connection.await;
+            });
+            Ok(send)
+        };
+        let send = tokio::time::timeout(timeout, connect)
+            .await
+            .map_err(|_| GrpcError::Timeout(opts.timeout))??;
+
+        let authority = opts.authority.as_deref().unwrap_or(host);
+        let authority = if authority.contains(':') {
+            format!("[{}]:{}", authority, port)
+        } else {
+            format!("{}:{}", authority, port)
+        };
+        Ok(Self {
+            send,
+            uri_prefix: format!("{}://{}", if tls { "https" } else { "http" }, authority),
+            metadata,
+            timeout,
+            timeout_ms: opts.timeout,
+        })
+    }
+
+    // 发送一条消息并结束请求流，收集服务端返回的全部消息；一元调用和
+    // “一问一答”的双向流（如反射）都可以这样调用
+    async fn call(&self, path: &str, message: Vec<u8>) -> Result<Vec<u8>, GrpcError> {
+        tokio::time::timeout(self.timeout, self.exchange(path, message))
+            .await
+            .map_err(|_| GrpcError::Timeout(self.timeout_ms))?
+    }
+
+    async fn exchange(&self, path: &str, message: Vec<u8>) -> Result<Vec<u8>, GrpcError> {
+        let mut request = http::Request::builder()
+            .method("POST")
+            .uri(format!("{}{}", self.uri_prefix, path))
+            .header("content-type", "application/grpc")
+            .header("te", "trailers")
+            .header("grpc-timeout", format!("{}m", self.timeout_ms))
+            .header(
+                "user-agent",
+                format!("rtoolkit/{}", env!("CARGO_PKG_VERSION")),
+            )
+            .body(())
+            .map_err(|_| GrpcError::InvalidTarget(self.uri_prefix.clone()))?;
+        for (name, value) in &self.metadata {
+            request.headers_mut().append(name.clone(), value.clone());
+        }
+
+        let mut send = self.send.clone().ready().await?;
+        let (response, mut stream) = send.send_request(request, false)?;
+        stream.send_data(frame(&message), true)?;
+        let (parts, mut body) = response.await?.into_parts();
+        if parts.status != http::StatusCode::OK {
+            return Err(GrpcError::HttpStatus(parts.status.as_u16()));
+        }
+
+        let mut data = Vec::new();
+        while let Some(chunk) = body.data().await {
+            let chunk = chunk?;
+            let _ = body.flow_control().release_capacity(chunk.len());
+            data.extend_from_slice(&chunk);
+            if data.len() > MAX_RESPONSE {
+                return Err(GrpcError::Protocol("response is too large"));
+            }
+        }
+        // 出错时服务端可能只发响应头（Trailers-Only），状态在响应头里
+        let trailers = body.trailers().await?.unwrap_or_default();
+        let status_headers = if trailers.contains_key("grpc-status") {
+            &trailers
+        } else {
+            &parts.headers
+        };
+        let code = status_headers
+            .get("grpc-status")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<u32>().ok())
+            .ok_or(GrpcError::Protocol("missing grpc-status"))?;
+        if code != 0 {
+            let message = status_headers
+                .get("grpc-message")
+                .map(|value| percent_decode(value.as_bytes()))
+                .unwrap_or_default();
+            return Err(GrpcError::Status { code, message });
+        }
+        unframe(&data)
+    }
+}
+
+// 长度前缀消息：1 字节压缩标志 + 4 字节大端长度 + 消息，不压缩
+fn frame(message: &[u8]) -> Bytes {
+    let mut framed = Vec::with_capacity(5 + message.len());
+    framed.push(0);
+    framed.extend_from_slice(&(message.len() as u32).to_be_bytes());
+    framed.extend_from_slice(message);
+    framed.into()
+}
+
+// 这里的调用都只返回一条消息，多条时拼接在一起（protobuf 拼接即合并）
+fn unframe(mut data: &[u8]) -> Result<Vec<u8>, GrpcError> {
+    let mut message = Vec::new();
+    while !data.is_empty() {
+        if data.len() < 5 {
+            return Err(GrpcError::Protocol("truncated message"));
+        }
+        if data[0] != 0 {
+            return Err(GrpcError::Protocol("compressed messages are not supported"));
+        }
+        let len = u32::from_be_bytes([data[1], data[2], data[3], data[4]]) as usize;
+        let body = data
+            .get(5..5 + len)
+            .ok_or(GrpcError::Protocol("truncated message"))?;
+        message.extend_from_slice(body);
+        data = &data[5 + len..];
+    }
+    Ok(message)
+}
+
+// grpc-message 按 RFC 3986 百分号编码
+fn percent_decode(raw: &[u8]) -> String {
+    let mut out = Vec::with_capacity(raw.len());
+    let mut i = 0;
+    while i < raw.len() {
+        let hex = raw
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (raw[i], hex) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn status_name(code: u32) -> String {
+    const NAMES: [&str; 17] = [
+        "OK",
+        "CANCELLED",
+        "UNKNOWN",
+        "INVALID_ARGUMENT",
+        "DEADLINE_EXCEEDED",
+        "NOT_FOUND",
+        "ALREADY_EXISTS",
+        "PERMISSION_DENIED",
+        "RESOURCE_EXHAUSTED",
+        "FAILED_PRECONDITION",
+        "ABORTED",
+        "OUT_OF_RANGE",
+        "UNIMPLEMENTED",
+        "INTERNAL",
+        "UNAVAILABLE",
+        "DATA_LOSS",
+        "UNAUTHENTICATED",
+    ];
+    NAMES
+        .get(code as usize)
+        .map_or_else(|| format!("status {}", code), |name| name.to_string())
+}
+
+// 服务反射：每次请求单独开一个流，先用 v1，服务端未实现时退回 v1alpha
+struct Reflection<'a> {
+    client: &'a Client,
+    path: Option<&'static str>,
+}
+
+impl<'a> Reflection<'a> {
+    fn new(client: &'a Client) -> Self {
+        Self { client, path: None }
+    }
+
+    async fn request(&mut self, field: u32, value: &str) -> Result<Vec<u8>, GrpcError> {
+        let mut request = Vec::new();
+        put_bytes(&mut request, field, value.as_bytes());
+        let paths = match self.path {
+            Some(path) => vec![path],
+            None => REFLECTION_PATHS.to_vec(),
+        };
+        for path in paths {
+            match self.client.call(path, request.clone()).await {
+                Ok(response) => {
+                    self.path = Some(path);
+                    return Ok(response);
+                }
+                Err(GrpcError::Status { code, .. }) if code == STATUS_UNIMPLEMENTED => continue,
+                Err(error) => return Err(error),
+            }
+        }
+        Err(GrpcError::Unimplemented("server reflection"))
+    }
+
+    // ServerReflectionResponse 的 message_response：4 描述文件，6 服务列表，7 错误
+    async fn response(&mut self, field: u32, symbol: &str) -> Result<Vec<Vec<u8>>, GrpcError> {
+        let response = self.request(field, symbol).await?;
+        let mut files = Vec::new();
+        for (field, value) in decode(&response)? {
+            match (field, value) {
+                (4 | 6, Value::Bytes(body)) => files.push(body.to_vec()),
+                (7, Value::Bytes(body)) => {
+                    let (mut code, mut message) = (0, String::new());
+                    for (field, value) in decode(body)? {
+                        match (field, value) {
+                            (1, Value::Varint(value)) => code = value,
+                            (2, Value::Bytes(text)) => {
+                                message = String::from_utf8_lossy(text).into_owned()
+                            }
+                            _ => {}
+                        }
+                    }
+                    if code == STATUS_NOT_FOUND as u64 {
+                        return Err(GrpcError::UnknownService(symbol.to_string()));
+                    }
+                    return Err(GrpcError::Reflection { code, message });
+                }
+                _ => {}
+            }
+        }
+        Ok(files)
+    }
+
+    async fn list_services(&mut self) -> Result<Vec<String>, GrpcError> {
+        let mut names = Vec::new();
+        for body in self.response(7, "").await? {
+            for (field, value) in decode(&body)? {
+                if let (1, Value::Bytes(service)) = (field, value) {
+                    names.extend(strings(service, 1)?);
+                }
+            }
+        }
+        Ok(names)
+    }
+
+    // 取包含该服务的描述文件（可能连同依赖一起返回），在其中找到服务定义
+    async fn describe(&mut self, service: &str) -> Result<Vec<Method>, GrpcError> {
+        for body in self.response(4, service).await? {
+            for (field, value) in decode(&body)? {
+                let (1, Value::Bytes(file)) = (field, value) else {
+                    continue;
+                };
+                if let Some(methods) = find_service(file, service)? {
+                    return Ok(methods);
+                }
+            }
+        }
+        Err(GrpcError::UnknownService(service.to_string()))
+    }
+}
+
+// FileDescriptorProto：2 package，6 service；ServiceDescriptorProto：1 name，2 method
+fn find_service(file: &[u8], service: &str) -> Result<Option<Vec<Method>>, GrpcError> {
+    let fields = decode(file)?;
+    let package = fields
+        .iter()
+        .find_map(|(field, value)| match (field, value) {
+            (2, Value::Bytes(package)) => Some(String::from_utf8_lossy(package).into_owned()),
+            _ => None,
+        })
+        .unwrap_or_default();
+    for (field, value) in &fields {
+        let (6, Value::Bytes(descriptor)) = (field, value) else {
+            continue;
+        };
+        let descriptor = decode(descriptor)?;
+        let name = string_field(&descriptor, 1).unwrap_or_default();
+        let full_name = if package.is_empty() {
+            name
+        } else {
+            format!("{}.{}", package, name)
+        };
+        if full_name != service {
+            continue;
+        }
+        let methods = descriptor
+            .iter()
+            .filter_map(|(field, value)| match (field, value) {
+                (2, Value::Bytes(method)) => Some(parse_method(method)),
+                _ => None,
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        return Ok(Some(methods));
+    }
+    Ok(None)
+}
+
+// MethodDescriptorProto：1 name，2 input_type，3 output_type，5 / 6 是否流式
+fn parse_method(method: &[u8]) -> Result<Method, GrpcError> {
+    let fields = decode(method)?;
+    let flag = |number| {
+        fields
+            .iter()
+            .any(|(field, value)| *field == number && matches!(value, Value::Varint(1)))
+    };
+    // 类型名是以 . 开头的全名
+    let type_name = |number| {
+        string_field(&fields, number)
+            .map(|name| name.trim_start_matches('.').to_string())
+            .unwrap_or_default()
+    };
+    Ok(Method {
+        name: string_field(&fields, 1).unwrap_or_default(),
+        input: type_name(2),
+        output: type_name(3),
+        client_streaming: flag(5),
+        server_streaming: flag(6),
+    })
+}
+
+// protobuf 编码的最小子集：varint 和长度前缀字段，其余类型解码时跳过
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Value<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+}
+
+fn put_varint(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+fn put_bytes(buf: &mut Vec<u8>, field: u32, value: &[u8]) {
+    put_varint(buf, (field as u64) << 3 | 2);
+    put_varint(buf, value.len() as u64);
+    buf.extend_from_slice(value);
+}
+
+fn read_varint(data: &mut &[u8]) -> Result<u64, GrpcError> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = data
+            .split_first()
+            .ok_or(GrpcError::Protocol("truncated protobuf"))?;
+        *data = rest;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(GrpcError::Protocol("varint is too long"))
+}
+
+fn decode(mut data: &[u8]) -> Result<Vec<(u32, Value<'_>)>, GrpcError> {
+    let truncated = || GrpcError::Protocol("truncated protobuf");
+    let mut fields = Vec::new();
+    while !data.is_empty() {
+        let key = read_varint(&mut data)?;
+        let field = (key >> 3) as u32;
+        let skip = match key & 7 {
+            0 => {
+                fields.push((field, Value::Varint(read_varint(&mut data)?)));
+                0
+            }
+            1 => 8,
+            2 => {
+                let len = read_varint(&mut data)? as usize;
+                if len > data.len() {
+                    return Err(truncated());
+                }
+                let (value, rest) = data.split_at(len);
+                fields.push((field, Value::Bytes(value)));
+                data = rest;
+                0
+            }
+            5 => 4,
+            _ => return Err(GrpcError::Protocol("unsupported protobuf wire type")),
+        };
+        data = data.get(skip..).ok_or_else(truncated)?;
+    }
+    Ok(fields)
+}
+
+fn string_field(fields: &[(u32, Value<'_>)], number: u32) -> Option<String> {
+    fields.iter().find_map(|(field, value)| match value {
+        Value::Bytes(text) if *field == number => Some(String::from_utf8_lossy(text).into_owned()),
+        _ => None,
+    })
+}
+
+fn strings(data: &[u8], number: u32) -> Result<Vec<String>, GrpcError> {
+    Ok(decode(data)?
+        .into_iter()
+        .filter_map(|(field, value)| match value {
+            Value::Bytes(text) if field == number => {
+                Some(String::from_utf8_lossy(text).into_owned())
+            }
+            _ => None,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::HeaderMap;
+
+    fn message(fields: &[(u32, &[u8])]) -> Vec<u8> {
+        let mut buf = Vec::new();
+        for (field, value) in fields {
+            put_bytes(&mut buf, *field, value);
+        }
+        buf
+    }
+
+    // 只实现 v1alpha 反射和健康检查的假服务端，验证 v1 不可用时的回退
+    async fn fake_server(listener: tokio::net::TcpListener) {
+        let (tcp, _) = listener.accept().await.unwrap();
+        let mut connection = h2::server::handshake(tcp).await.unwrap();
+        // 连接需要持续被 accept 驱动，每个请求在单独的任务里处理
+        while let Some(Ok((request, respond))) = connection.accept().await {
+            tokio::spawn(fake_reply(request, respond));
+        }
+    }
+
+    async fn fake_reply(
+        request: http::Request<h2::RecvStream>,
+        mut respond: h2::server::SendResponse<Bytes>,
+    ) {
+        let path = request.uri().path().to_string();
+        let mut body = request.into_body();
+        let mut data = Vec::new();
+        while let Some(chunk) = body.data().await {
+            data.extend_from_slice(&chunk.unwrap());
+        }
+        let request = unframe(&data).unwrap();
+        let request = decode(&request).unwrap();
+        let (reply, status) = match path.as_str() {
+            HEALTH_CHECK => match request.first() {
+                None => (Some(vec![0x08, 1]), 0),
+                Some((1, Value::Bytes(b"helloworld.Greeter"))) => (Some(vec![0x08, 2]), 0),
+                Some(_) => (None, STATUS_NOT_FOUND),
+            },
+            "/grpc.reflection.v1alpha.ServerReflection/ServerReflectionInfo" => {
+                let reply = match request[0] {
+                    (7, _) => message(&[(
+                        6,
+                        &[
+                            message(&[(1, &message(&[(1, b"helloworld.Greeter")]))]),
+                            message(&[(1, &message(&[(1, b"grpc.health.v1.Health")]))]),
+                        ]
+                        .concat(),
+                    )]),
+                    (4, Value::Bytes(b"helloworld.Greeter")) => {
+                        let mut stream = message(&[
+                            (1, b"Chat"),
+                            (2, b".helloworld.HelloRequest"),
+                            (3, b".helloworld.HelloReply"),
+                        ]);
+                        stream.extend_from_slice(&[0x28, 1, 0x30, 1]);
+                        let service = message(&[
+                            (1, b"Greeter"),
+                            (
+                                2,
+                                &message(&[
+                                    (1, b"SayHello"),
+                                    (2, b".helloworld.HelloRequest"),
+                                    (3, b".helloworld.HelloReply"),
+                                ]),
+                            ),
+                            (2, &stream),
+                        ]);
+                        let file = message(&[(2, b"helloworld"), (6, &service)]);
+                        message(&[(4, &message(&[(1, &file)]))])
+                    }
+                    _ => message(&[(7, &[0x08, 5])]),
+                };
+                (Some(reply), 0)
+            }
+            _ => (None, STATUS_UNIMPLEMENTED),
+        };
+        let response = http::Response::builder()
+            .status(200)
+            .header("content-type", "application/grpc")
+            .body(())
+            .unwrap();
+        let mut send = respond.send_response(response, false).unwrap();
+        if let Some(reply) = reply {
+            send.send_data(frame(&reply), false).unwrap();
+        }
+        let mut trailers = HeaderMap::new();
+        trailers.insert("grpc-status", status.into());
+        send.send_trailers(trailers).unwrap();
+    }
+
+    #[test]
+    fn test_health_and_reflection() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let opts = ConnectOpts {
+                target: listener.local_addr().unwrap().to_string(),
+                tls: false,
+                insecure: false,
+                authority: None,
+                headers: vec!["Authorization: Bearer x".to_string()],
+                timeout: 5000,
+                json: false,
+                record_output: RecordOutput::default(),
+            };
+            tokio::spawn(fake_server(listener));
+            let client = Client::connect(&opts).await.unwrap();
+
+            assert_eq!(check_health(&client, "").await.unwrap(), "SERVING");
+            assert_eq!(
+                check_health(&client, "helloworld.Greeter").await.unwrap(),
+                "NOT_SERVING"
+            );
+            assert_eq!(
+                check_health(&client, "nope.Svc").await.unwrap(),
+                "SERVICE_UNKNOWN"
+            );
+
+            let mut reflection = Reflection::new(&client);
+            assert_eq!(
+                reflection.list_services().await.unwrap(),
+                ["helloworld.Greeter", "grpc.health.v1.Health"]
+            );
+            let methods = reflection.describe("helloworld.Greeter").await.unwrap();
+            assert_eq!(methods.len(), 2);
+            assert_eq!(
+                methods[0],
+                Method {
+                    name: "SayHello".to_string(),
+                    input: "helloworld.HelloRequest".to_string(),
+                    output: "helloworld.HelloReply".to_string(),
+                    client_streaming: false,
+                    server_streaming: false,
+                }
+            );
+            assert!(methods[1].client_streaming && methods[1].server_streaming);
+            assert!(matches!(
+                reflection.describe("nope.Svc").await,
+                Err(GrpcError::UnknownService(_))
+            ));
+        });
+    }
+
+    #[test]
+    fn test_status_message() {
+        assert_eq!(percent_decode(b"bad%20request%3A%20x%"), "bad request: x%");
+        let error = GrpcError::Status {
+            code: 14,
+            message: "upstream down".to_string(),
+        };
+        assert_eq!(error.to_string(), "UNAVAILABLE: upstream down");
+    }
+}
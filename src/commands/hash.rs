@@ -0,0 +1,252 @@
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::PathBuf;
+
+use clap::ValueEnum;
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256, Sha512};
+
+use crate::utils::format::{decode_hex, encode_hex};
+use crate::utils::sm3::{HmacSm3, Sm3};
+
+const READ_CHUNK: usize = 64 * 1024;
+
+#[derive(clap::Args)]
+pub struct HashOpts {
+    #[arg(
+        value_name = "FILE",
+        help = "输入文件，可指定多个；省略或为 - 时读取标准输入"
+    )]
+    files: Vec<PathBuf>,
+
+    #[arg(
+        short = 'a',
+        long,
+        value_enum,
+        default_value_t = HashAlgorithm::Sm3,
+        help = "摘要算法"
+    )]
+    algorithm: HashAlgorithm,
+
+    #[arg(
+        short = 't',
+        long,
+        value_name = "TEXT",
+        conflicts_with = "files",
+        help = "对给定字符串（UTF-8）计算摘要，代替文件输入"
+    )]
+    text: Option<String>,
+
+    #[arg(long, value_name = "KEY", help = "使用该密钥计算 HMAC，例如 HMAC-SM3")]
+    hmac: Option<String>,
+
+    #[arg(long, requires = "hmac", help = "HMAC 密钥按十六进制解析")]
+    hex_key: bool,
+
+    #[arg(
+        long,
+        value_name = "DIGEST",
+        help = "与期望的十六进制摘要比对，不一致时以非零状态退出"
+    )]
+    check: Option<String>,
+
+    #[arg(long, help = "十六进制摘要使用大写字母")]
+    upper: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum HashAlgorithm {
+    Sm3,
+    Sha256,
+    Sha512,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum HashError {
+    #[error("invalid hex key: {0}")]
+    InvalidKey(String),
+    #[error("--check expects a single input, got {0}")]
+    CheckInputs(usize),
+    #[error("digest mismatch for {name}: expected {expected}, got {actual}")]
+    Mismatch {
+        name: String,
+        expected: String,
+        actual: String,
+    },
+    #[error("cannot read {path}: {source}")]
+    Open {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+// 普通摘要和 HMAC 的统一计算状态
+enum Hasher {
+    Sm3(Sm3),
+    Sha256(Sha256),
+    Sha512(Sha512),
+    HmacSm3(HmacSm3),
+    HmacSha256(Hmac<Sha256>),
+    HmacSha512(Hmac<Sha512>),
+}
+
+impl Hasher {
+    fn new(algorithm: HashAlgorithm, key: Option<&[u8]>) -> Self {
+        const ANY_KEY: &str = "hmac accepts any key length";
+        match (algorithm, key) {
+            (HashAlgorithm::Sm3, None) => Hasher::Sm3(Sm3::new()),
+            (HashAlgorithm::Sha256, None) => Hasher::Sha256(Sha256::new()),
+            (HashAlgorithm::Sha512, None) => Hasher::Sha512(Sha512::new()),
+            (HashAlgorithm::Sm3, Some(key)) => Hasher::HmacSm3(HmacSm3::new(key)),
+            (HashAlgorithm::Sha256, Some(key)) => {
+                Hasher::HmacSha256(Hmac::new_from_slice(key).expect(ANY_KEY))
+            }
+            (HashAlgorithm::Sha512, Some(key)) => {
+                Hasher::HmacSha512(Hmac::new_from_slice(key).expect(ANY_KEY))
+            }
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Hasher::Sm3(hasher) => hasher.update(data),
+            Hasher::Sha256(hasher) => Digest::update(hasher, data),
+            Hasher::Sha512(hasher) => Digest::update(hasher, data),
+            Hasher::HmacSm3(mac) => mac.update(data),
+            Hasher::HmacSha256(mac) => mac.update(data),
+            Hasher::HmacSha512(mac) => mac.update(data),
+        }
+    }
+
+    fn finalize(self) -> Vec<u8> {
+        match self {
+            Hasher::Sm3(hasher) => hasher.finalize().to_vec(),
+            Hasher::Sha256(hasher) => hasher.finalize().to_vec(),
+            Hasher::Sha512(hasher) => hasher.finalize().to_vec(),
+            Hasher::HmacSm3(mac) => mac.finalize().to_vec(),
+            Hasher::HmacSha256(mac) => mac.finalize().into_bytes().to_vec(),
+            Hasher::HmacSha512(mac) => mac.finalize().into_bytes().to_vec(),
+        }
+    }
+}
+
+pub fn run_hash(opts: HashOpts) -> Result<(), HashError> {
+    let key = opts
+        .hmac
+        .as_deref()
+        .map(|key| {
+            if opts.hex_key {
+                decode_hex(key).ok_or_else(|| HashError::InvalidKey(key.to_string()))
+            } else {
+                Ok(key.as_bytes().to_vec())
+            }
+        })
+        .transpose()?;
+    let inputs = if opts.text.is_some() || opts.files.is_empty() {
+        1
+    } else {
+        opts.files.len()
+    };
+    if opts.check.is_some() && inputs != 1 {
+        return Err(HashError::CheckInputs(inputs));
+    }
+
+    let mut digests = Vec::new();
+    if let Some(text) = &opts.text {
+        let mut hasher = Hasher::new(opts.algorithm, key.as_deref());
+        hasher.update(text.as_bytes());
+        digests.push(("-".to_string(), hasher.finalize()));
+    } else if opts.files.is_empty() {
+        let digest = hash_reader(opts.algorithm, key.as_deref(), io::stdin().lock())?;
+        digests.push(("-".to_string(), digest));
+    } else {
+        for path in &opts.files {
+            let digest = if path.as_os_str() == "-" {
+                hash_reader(opts.algorithm, key.as_deref(), io::stdin().lock())?
+            } else {
+                let file = File::open(path).map_err(|source| HashError::Open {
+                    path: path.clone(),
+                    source,
+                })?;
+                hash_reader(opts.algorithm, key.as_deref(), file)?
+            };
+            digests.push((path.display().to_string(), digest));
+        }
+    }
+
+    for (name, digest) in &digests {
+        let hex = encode_hex(digest, opts.upper);
+        println!("{}  {}", hex, name);
+        if let Some(expected) = &opts.check {
+            if !expected.trim().eq_ignore_ascii_case(&hex) {
+                return Err(HashError::Mismatch {
+                    name: name.clone(),
+                    expected: expected.trim().to_string(),
+                    actual: hex,
+                });
+            }
+            eprintln!("{}: OK", name);
+        }
+    }
+    Ok(())
+}
+
+fn hash_reader(
+    algorithm: HashAlgorithm,
+    key: Option<&[u8]>,
+    mut reader: impl Read,
+) -> Result<Vec<u8>, HashError> {
+    let mut hasher = Hasher::new(algorithm, key);
+    let mut buf = vec![0u8; READ_CHUNK];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hasher.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hasher_digests_and_hmac() {
+        let digest = |algorithm, key: Option<&[u8]>, data: &[u8]| {
+            encode_hex(&hash_reader(algorithm, key, data).unwrap(), false)
+        };
+        assert_eq!(
+            digest(HashAlgorithm::Sm3, None, b"abc"),
+            "66c7f0f462eeedd9d1f2d46bdc10e4e24167c4875cf2f7a2297da02b8f4ba8e0"
+        );
+        assert_eq!(
+            digest(HashAlgorithm::Sha256, None, b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        // RFC 4231 用例 2
+        assert_eq!(
+            digest(
+                HashAlgorithm::Sha256,
+                Some(b"Jefe"),
+                b"what do ya want for nothing?"
+            ),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert_eq!(
+            digest(
+                HashAlgorithm::Sm3,
+                Some(&decode_hex("0x4a656665").unwrap()),
+                b"what do ya want for nothing?"
+            ),
+            "2e87f1d16862e6d964b50a5200bf2b10b764faa9680a296a2405f24bec39f882"
+        );
+        assert_eq!(decode_hex("abc"), None);
+        assert_eq!(decode_hex("zz"), None);
+        assert_eq!(encode_hex(&[0xab, 0x01], true), "AB01");
+    }
+}
@@ -0,0 +1,435 @@
+use std::fs;
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde_json::Value;
+
+use crate::utils::http::{HttpError, Request, Response, Url};
+use crate::utils::network::{self, NetworkError};
+use crate::utils::proxy::{Proxy, ProxyError};
+
+// 响应体上限，超出的部分丢弃
+const MAX_BODY_BYTES: usize = 256 << 20;
+
+#[derive(clap::Args)]
+pub struct HttpOpts {
+    #[arg(
+        value_name = "[METHOD] URL",
+        num_args = 1..=2,
+        required = true,
+        help = "请求方法（默认 GET，带请求体时为 POST）和 URL，省略协议时为 http://"
+    )]
+    target: Vec<String>,
+
+    #[arg(
+        short = 'H',
+        long = "header",
+        value_name = "NAME: VALUE",
+        help = "请求头，可重复"
+    )]
+    headers: Vec<String>,
+
+    #[arg(
+        short = 'd',
+        long,
+        value_name = "DATA",
+        conflicts_with = "json",
+        help = "请求体，@FILE 读取文件，@- 读取 stdin；默认 Content-Type 为 application/x-www-form-urlencoded"
+    )]
+    data: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "JSON",
+        help = "JSON 请求体（同样支持 @FILE），发送前校验格式并设置 Content-Type 和 Accept 为 application/json"
+    )]
+    json: Option<String>,
+
+    #[arg(short = 'u', long, value_name = "USER:PASS", help = "HTTP Basic 认证")]
+    user: Option<String>,
+
+    #[arg(short = 'L', long, help = "跟随 3xx 重定向")]
+    location: bool,
+
+    #[arg(
+        long = "max-redirs",
+        default_value_t = 10,
+        value_name = "N",
+        help = "最多跟随的重定向次数"
+    )]
+    max_redirs: u32,
+
+    #[arg(
+        short = 'x',
+        long,
+        value_name = "URL",
+        help = "经代理发送请求：socks5://、socks5h:// 或 http://[user:pass@]host[:port]（CONNECT 隧道）"
+    )]
+    proxy: Option<String>,
+
+    #[arg(short = 'k', long, help = "不校验 HTTPS 证书")]
+    insecure: bool,
+
+    #[arg(
+        long = "timeout",
+        default_value_t = 30000,
+        value_name = "MS",
+        help = "整个请求（含重定向）的超时时间(毫秒)"
+    )]
+    time_out: u64,
+
+    #[arg(short = 'b', long = "body-only", help = "只输出响应体")]
+    body_only: bool,
+
+    #[arg(long, help = "原样输出响应体，不格式化 JSON")]
+    raw: bool,
+
+    #[arg(
+        short = 'o',
+        long,
+        value_name = "FILE",
+        help = "把响应体写入文件，终端只显示状态和响应头"
+    )]
+    output: Option<PathBuf>,
+
+    #[arg(short = 'f', long, help = "响应状态为 4xx / 5xx 时以非零状态退出")]
+    fail: bool,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum HttpCmdError {
+    #[error("invalid header '{0}', expected NAME: VALUE")]
+    InvalidHeader(String),
+    #[error("invalid method '{0}'")]
+    InvalidMethod(String),
+    #[error("invalid JSON body: {0}")]
+    InvalidJson(#[source] serde_json::Error),
+    #[error("cannot read {0}: {1}")]
+    ReadBody(String, #[source] io::Error),
+    #[error("too many redirects (more than {0})")]
+    TooManyRedirects(u32),
+    #[error("request timed out after {0} ms")]
+    Timeout(u64),
+    #[error("server returned {0}")]
+    Status(u16),
+    #[error(transparent)]
+    Request(#[from] HttpError),
+    #[error(transparent)]
+    Proxy(#[from] ProxyError),
+    #[error(transparent)]
+    Network(#[from] NetworkError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+pub fn run_http(opts: HttpOpts) -> Result<(), HttpCmdError> {
+    let body_given = opts.data.is_some() || opts.json.is_some();
+    let (method, raw_url) = match opts.target.as_slice() {
+        [url] => (if body_given { "POST" } else { "GET" }.to_string(), url),
+        [method, url] => (method.to_ascii_uppercase(), url),
+        _ => unreachable!("clap limits URL arguments to 1..=2"),
+    };
+    if method.is_empty() || !method.bytes().all(|b| b.is_ascii_alphabetic()) {
+        return Err(HttpCmdError::InvalidMethod(method));
+    }
+    let mut url = if raw_url.contains("://") {
+        Url::parse(raw_url)?
+    } else {
+        Url::parse(&format!("http://{}", raw_url))?
+    };
+    let proxy = opts.proxy.as_deref().map(Proxy::parse).transpose()?;
+    if let Some(proxy) = &proxy {
+        network::check_host("http proxy", &proxy.host)?;
+    }
+
+    let mut headers = opts
+        .headers
+        .iter()
+        .map(|header| parse_header(header))
+        .collect::<Result<Vec<_>, _>>()?;
+    let has = |headers: &[(String, String)], name: &str| {
+        headers
+            .iter()
+            .any(|(key, _)| key.eq_ignore_ascii_case(name))
+    };
+    if let Some(user) = &opts.user {
+        if !has(&headers, "Authorization") {
+            let credentials = STANDARD.encode(user);
+            headers.push(("Authorization".into(), format!("Basic {}", credentials)));
+        }
+    }
+    let mut body = Vec::new();
+    if let Some(data) = &opts.data {
+        body = read_body(data)?;
+        if !has(&headers, "Content-Type") {
+            headers.push((
+                "Content-Type".into(),
+                "application/x-www-form-urlencoded".into(),
+            ));
+        }
+    }
+    if let Some(json) = &opts.json {
+        body = read_body(json)?;
+        serde_json::from_slice::<Value>(&body).map_err(HttpCmdError::InvalidJson)?;
+        for name in ["Content-Type", "Accept"] {
+            if !has(&headers, name) {
+                headers.push((name.into(), "application/json".into()));
+            }
+        }
+    }
+
+    let rt = tokio::runtime::Runtime::new()?;
+    let started = Instant::now();
+    let deadline = Duration::from_millis(opts.time_out.max(1));
+    let mut method = method;
+    let mut redirects = 0;
+    let response = loop {
+        if proxy.is_none() {
+            network::check_host("http", &url.host)?;
+        }
+        let request = Request {
+            method: &method,
+            url: &url,
+            headers: &headers,
+            body: &body,
+        };
+        let remaining = deadline.saturating_sub(started.elapsed());
+        let response = rt
+            .block_on(async {
+                tokio::time::timeout(
+                    remaining,
+                    request.send(proxy.as_ref(), opts.insecure, MAX_BODY_BYTES),
+                )
+                .await
+            })
+            .map_err(|_| HttpCmdError::Timeout(opts.time_out))??;
+        let location = response.header("Location").map(str::to_string);
+        let Some(location) = location.filter(|_| opts.location && is_redirect(response.status))
+        else {
+            break response;
+        };
+        redirects += 1;
+        if redirects > opts.max_redirs {
+            return Err(HttpCmdError::TooManyRedirects(opts.max_redirs));
+        }
+        let next = url.join(&location)?;
+        if !opts.body_only {
+            eprintln!("{} {} -> {}", response.status, url, next);
+        }
+        // 与 curl / 浏览器一致：303 以及 POST 的 301、302 改为不带请求体的 GET
+        if response.status == 303 || (matches!(response.status, 301 | 302) && method == "POST") {
+            if method != "HEAD" {
+                method = "GET".to_string();
+            }
+            body.clear();
+            headers.retain(|(key, _)| !key.eq_ignore_ascii_case("Content-Type"));
+        }
+        // 跳转到其他主机时不再携带认证信息
+        if next.host != url.host || next.port != url.port {
+            headers.retain(|(key, _)| {
+                !key.eq_ignore_ascii_case("Authorization") && !key.eq_ignore_ascii_case("Cookie")
+            });
+        }
+        url = next;
+    };
+    let elapsed_ms = (started.elapsed().as_secs_f64() * 100_000.0).round() / 100.0;
+
+    let color = io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+    let mut out = io::stdout().lock();
+    if !opts.body_only {
+        write_head(&mut out, &response, elapsed_ms, color)?;
+    }
+    match &opts.output {
+        Some(path) => {
+            fs::write(path, &response.body)?;
+            if !opts.body_only {
+                writeln!(
+                    out,
+                    "\n[{} bytes written to {}]",
+                    response.body.len(),
+                    path.display()
+                )?;
+            }
+        }
+        None => {
+            // 响应头和响应体之间空一行
+            if !opts.body_only && !response.body.is_empty() {
+                writeln!(out)?;
+            }
+            write_body(&mut out, &response, opts.raw, color)?
+        }
+    }
+    out.flush()?;
+    if opts.fail && response.status >= 400 {
+        return Err(HttpCmdError::Status(response.status));
+    }
+    Ok(())
+}
+
+fn is_redirect(status: u16) -> bool {
+    matches!(status, 301 | 302 | 303 | 307 | 308)
+}
+
+pub(crate) fn parse_header(raw: &str) -> Result<(String, String), HttpCmdError> {
+    let invalid = || HttpCmdError::InvalidHeader(raw.to_string());
+    let (name, value) = raw.split_once(':').ok_or_else(invalid)?;
+    let name = name.trim();
+    // 名称只能是 token 字符，值不能换行，避免拼出额外的请求头
+    let token = !name.is_empty()
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b));
+    if !token || value.contains(['\r', '\n']) {
+        return Err(invalid());
+    }
+    Ok((name.to_string(), value.trim().to_string()))
+}
+
+// 与 curl 一致：@FILE 读取文件内容，@- 读取 stdin，其余按字面发送
+pub(crate) fn read_body(data: &str) -> Result<Vec<u8>, HttpCmdError> {
+    match data.strip_prefix('@') {
+        Some("-") => {
+            let mut body = Vec::new();
+            io::stdin()
+                .read_to_end(&mut body)
+                .map_err(|e| HttpCmdError::ReadBody("stdin".into(), e))?;
+            Ok(body)
+        }
+        Some(path) => fs::read(path).map_err(|e| HttpCmdError::ReadBody(path.into(), e)),
+        None => Ok(data.as_bytes().to_vec()),
+    }
+}
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+const BLUE: &str = "\x1b[34m";
+const CYAN: &str = "\x1b[36m";
+
+fn paint(text: &str, style: &str, color: bool) -> String {
+    if color {
+        format!("{}{}{}", style, text, RESET)
+    } else {
+        text.to_string()
+    }
+}
+
+fn write_head(
+    out: &mut impl Write,
+    response: &Response,
+    elapsed_ms: f64,
+    color: bool,
+) -> io::Result<()> {
+    let style = match response.status {
+        200..=299 => GREEN,
+        300..=399 => YELLOW,
+        _ => RED,
+    };
+    let status = format!("HTTP {} {}", response.status, response.reason);
+    writeln!(
+        out,
+        "{}  ({} ms)",
+        paint(status.trim_end(), &format!("{}{}", BOLD, style), color),
+        elapsed_ms
+    )?;
+    for (key, value) in &response.headers {
+        writeln!(out, "{}: {}", paint(key, CYAN, color), value)?;
+    }
+    Ok(())
+}
+
+// JSON 响应格式化并着色；文本原样输出；终端上不直接输出二进制内容
+fn write_body(out: &mut impl Write, response: &Response, raw: bool, color: bool) -> io::Result<()> {
+    if response.body.is_empty() {
+        return Ok(());
+    }
+    let is_json = response
+        .header("Content-Type")
+        .is_some_and(|value| value.to_ascii_lowercase().contains("json"));
+    if is_json && !raw {
+        if let Ok(value) = serde_json::from_slice::<Value>(&response.body) {
+            let mut text = String::new();
+            write_json(&mut text, &value, 0, color);
+            return writeln!(out, "{}", text);
+        }
+    }
+    match std::str::from_utf8(&response.body) {
+        Ok(text) => {
+            write!(out, "{}", text)?;
+            if !text.ends_with('\n') {
+                writeln!(out)?;
+            }
+            Ok(())
+        }
+        Err(_) if io::stdout().is_terminal() => writeln!(
+            out,
+            "[binary body: {} bytes, use -o FILE to save it]",
+            response.body.len()
+        ),
+        Err(_) => out.write_all(&response.body),
+    }
+}
+
+// 两个空格缩进的 JSON：键为蓝色，字符串为绿色，数字为青色，布尔值和 null 为黄色
+fn write_json(out: &mut String, value: &Value, indent: usize, color: bool) {
+    let pad = "  ".repeat(indent + 1);
+    let close = "  ".repeat(indent);
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            out.push_str("{\n");
+            for (i, (key, item)) in map.iter().enumerate() {
+                let key = Value::String(key.clone()).to_string();
+                out.push_str(&format!("{}{}: ", pad, paint(&key, BLUE, color)));
+                write_json(out, item, indent + 1, color);
+                out.push_str(if i + 1 < map.len() { ",\n" } else { "\n" });
+            }
+            out.push_str(&format!("{}}}", close));
+        }
+        Value::Array(items) if !items.is_empty() => {
+            out.push_str("[\n");
+            for (i, item) in items.iter().enumerate() {
+                out.push_str(&pad);
+                write_json(out, item, indent + 1, color);
+                out.push_str(if i + 1 < items.len() { ",\n" } else { "\n" });
+            }
+            out.push_str(&format!("{}]", close));
+        }
+        Value::String(_) => out.push_str(&paint(&value.to_string(), GREEN, color)),
+        Value::Number(_) => out.push_str(&paint(&value.to_string(), CYAN, color)),
+        Value::Bool(_) | Value::Null => out.push_str(&paint(&value.to_string(), YELLOW, color)),
+        // 空对象和空数组
+        _ => out.push_str(&value.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_header_and_json() {
+        assert_eq!(
+            parse_header("X-Token:  abc ").unwrap(),
+            ("X-Token".to_string(), "abc".to_string())
+        );
+        assert_eq!(parse_header("Accept:").unwrap().1, "");
+        for bad in ["NoColon", ": v", "Bad Name: v", "X: a\r\nInjected: 1"] {
+            assert!(parse_header(bad).is_err(), "{}", bad);
+        }
+
+        let value: Value = serde_json::from_str(r#"{"a":[1,"x",null],"b":{},"c":true}"#).unwrap();
+        let mut plain = String::new();
+        write_json(&mut plain, &value, 0, false);
+        // 不着色时与 serde_json 的格式化结果一致
+        assert_eq!(plain, serde_json::to_string_pretty(&value).unwrap());
+        let mut colored = String::new();
+        write_json(&mut colored, &value, 0, true);
+        assert!(colored.contains("\x1b[34m\"a\"\x1b[0m"));
+        assert!(colored.contains("\x1b[32m\"x\"\x1b[0m"));
+    }
+}
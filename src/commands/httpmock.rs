@@ -0,0 +1,735 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use chrono::Local;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::commands::forward::{jittered, parse_latency};
+use crate::commands::mockapi::status_text;
+use crate::utils::http::content_type;
+use crate::utils::network::{self, NetworkError};
+use crate::web::url_decode;
+
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+// 请求体超过该大小时只读取前面的部分用于模板
+const MAX_BODY: u64 = 10 * 1024 * 1024;
+
+#[derive(clap::Args)]
+pub struct HttpMockOpts {
+    #[arg(
+        short,
+        long,
+        value_name = "FILE",
+        help = "路由定义文件（YAML 或 JSON），修改后自动重新加载"
+    )]
+    spec: PathBuf,
+
+    #[arg(long, default_value = "127.0.0.1", help = "监听地址")]
+    host: String,
+
+    #[arg(short, long, default_value_t = 3000, help = "监听端口")]
+    port: u16,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum HttpMockError {
+    #[error("failed to read {path}: {source}")]
+    Read { path: PathBuf, source: io::Error },
+    #[error("invalid spec: {0}")]
+    Parse(#[from] serde_yaml::Error),
+    #[error("spec defines no routes")]
+    NoRoutes,
+    #[error("invalid route '{route}': {reason}")]
+    InvalidRoute { route: String, reason: String },
+    #[error("failed to listen on {addr}: {source}")]
+    Bind { addr: String, source: io::Error },
+    #[error(transparent)]
+    Network(#[from] NetworkError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SpecFile {
+    // 所有响应都带上的头，路由中的同名头优先
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+    // 路由未指定 delay 时使用
+    delay: Option<String>,
+    #[serde(default)]
+    routes: Vec<RouteSpec>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RouteSpec {
+    #[serde(default = "any_method")]
+    method: String,
+    path: String,
+    #[serde(default = "ok_status")]
+    status: u16,
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+    delay: Option<String>,
+    body: Option<String>,
+    json: Option<Value>,
+    file: Option<PathBuf>,
+}
+
+fn any_method() -> String {
+    "*".to_string()
+}
+
+fn ok_status() -> u16 {
+    200
+}
+
+#[derive(Debug, PartialEq)]
+enum Segment {
+    Literal(String),
+    Param(String),
+    // 末尾的 *，匹配剩余的零个或多个路径段
+    Rest,
+}
+
+#[derive(Debug)]
+enum Body {
+    Empty,
+    Text(String),
+    Json(Value),
+    // 每次请求时读取，修改样例文件不需要重启；内容原样输出，不展开模板
+    File(PathBuf),
+}
+
+struct Route {
+    // None 表示匹配任意方法
+    method: Option<String>,
+    template: String,
+    segments: Vec<Segment>,
+    status: u16,
+    headers: Vec<(String, String)>,
+    delay: (Duration, Duration),
+    body: Body,
+}
+
+struct Mock {
+    routes: Vec<Route>,
+}
+
+#[derive(Debug, Default)]
+struct Request {
+    method: String,
+    path: String,
+    query: Vec<(String, String)>,
+    // 名称已转为小写
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+#[derive(Debug)]
+struct Response {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+    delay: Duration,
+}
+
+impl Response {
+    fn error(status: u16, message: String) -> Self {
+        Self {
+            status,
+            headers: vec![("Content-Type".to_string(), "application/json".to_string())],
+            body: json!({ "error": message }).to_string().into_bytes(),
+            delay: Duration::ZERO,
+        }
+    }
+}
+
+// 规格文件修改时间变化后，下一个请求到来时重新加载
+struct Server {
+    path: PathBuf,
+    state: Mutex<(Option<SystemTime>, Arc<Mock>)>,
+}
+
+impl Server {
+    fn current(&self) -> Arc<Mock> {
+        let mut state = self.state.lock().unwrap();
+        let modified = fs::metadata(&self.path)
+            .and_then(|meta| meta.modified())
+            .ok();
+        if modified.is_some() && modified != state.0 {
+            state.0 = modified;
+            match load(&self.path) {
+                Ok(mock) => {
+                    eprintln!(
+                        "Reloaded {} ({} routes)",
+                        self.path.display(),
+                        mock.routes.len()
+                    );
+                    state.1 = Arc::new(mock);
+                }
+                Err(error) => eprintln!(
+                    "failed to reload {}: {}, keeping previous routes",
+                    self.path.display(),
+                    error
+                ),
+            }
+        }
+        Arc::clone(&state.1)
+    }
+}
+
+pub fn run_httpmock(opts: HttpMockOpts) -> Result<(), HttpMockError> {
+    let modified = fs::metadata(&opts.spec)
+        .and_then(|meta| meta.modified())
+        .ok();
+    let mock = load(&opts.spec)?;
+
+    network::check_host("httpmock", &opts.host)?;
+    let addr = format!("{}:{}", opts.host, opts.port);
+    let listener = TcpListener::bind(&addr).map_err(|source| HttpMockError::Bind {
+        addr: addr.clone(),
+        source,
+    })?;
+    eprintln!(
+        "HTTP mock listening on http://{} ({} routes)",
+        listener.local_addr()?,
+        mock.routes.len()
+    );
+    for route in &mock.routes {
+        eprintln!(
+            "  {:<7} {} -> {}",
+            route.method.as_deref().unwrap_or("*"),
+            route.template,
+            route.status
+        );
+    }
+    let server = Arc::new(Server {
+        path: opts.spec,
+        state: Mutex::new((modified, Arc::new(mock))),
+    });
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(error) => {
+                eprintln!("connection failed: {}", error);
+                continue;
+            }
+        };
+        let server = Arc::clone(&server);
+        thread::spawn(move || {
+            if let Err(error) = handle_client(stream, &server) {
+                eprintln!("request failed: {}", error);
+            }
+        });
+    }
+    Ok(())
+}
+
+fn load(path: &Path) -> Result<Mock, HttpMockError> {
+    let text = fs::read_to_string(path).map_err(|source| HttpMockError::Read {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    // JSON 是 YAML 的子集，两种格式都用 YAML 解析
+    let spec: SpecFile = serde_yaml::from_str(&text)?;
+    Mock::new(spec, path.parent().unwrap_or(Path::new("")))
+}
+
+fn handle_client(mut stream: TcpStream, server: &Server) -> io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Ok(());
+    };
+    let mut request = Request {
+        method: method.to_ascii_uppercase(),
+        ..Request::default()
+    };
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    request.path = path.to_string();
+    request.query = parse_query(query);
+    let mut content_length = 0u64;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim_end().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            let name = name.trim().to_ascii_lowercase();
+            if name == "content-length" {
+                content_length = value.trim().parse().unwrap_or(0);
+            }
+            request.headers.push((name, value.trim().to_string()));
+        }
+    }
+    let mut body = reader.take(content_length);
+    (&mut body).take(MAX_BODY).read_to_end(&mut request.body)?;
+    io::copy(&mut body, &mut io::sink())?;
+
+    let started = Instant::now();
+    let response = server.current().respond(&request);
+    if !response.delay.is_zero() {
+        thread::sleep(response.delay);
+    }
+    write_response(&mut stream, &response, request.method == "HEAD")?;
+    eprintln!(
+        "{} {} -> {} ({}ms)",
+        method,
+        target,
+        response.status,
+        started.elapsed().as_millis()
+    );
+    Ok(())
+}
+
+fn write_response(stream: &mut TcpStream, response: &Response, head: bool) -> io::Result<()> {
+    let mut header = format!(
+        "HTTP/1.1 {} {}\r\n",
+        response.status,
+        status_text(response.status)
+    );
+    for (name, value) in &response.headers {
+        header.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    // 默认允许跨域，前端开发服务器可以直接请求；路由中设置了同名头时不再重复
+    for (name, value) in [
+        ("Access-Control-Allow-Origin", "*"),
+        ("Access-Control-Allow-Headers", "*"),
+        (
+            "Access-Control-Allow-Methods",
+            "GET, POST, PUT, PATCH, DELETE, OPTIONS",
+        ),
+    ] {
+        if !has_header(&response.headers, name) {
+            header.push_str(&format!("{}: {}\r\n", name, value));
+        }
+    }
+    header.push_str(&format!(
+        "Content-Length: {}\r\nConnection: close\r\n\r\n",
+        response.body.len()
+    ));
+    stream.write_all(header.as_bytes())?;
+    if !head {
+        stream.write_all(&response.body)?;
+    }
+    stream.flush()
+}
+
+fn has_header(headers: &[(String, String)], name: &str) -> bool {
+    headers
+        .iter()
+        .any(|(key, _)| key.eq_ignore_ascii_case(name))
+}
+
+fn parse_query(query: &str) -> Vec<(String, String)> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (url_decode(name), url_decode(value))
+        })
+        .collect()
+}
+
+impl Mock {
+    // base 是规格文件所在目录，路由中的相对 file 路径以它为准
+    fn new(spec: SpecFile, base: &Path) -> Result<Self, HttpMockError> {
+        if spec.routes.is_empty() {
+            return Err(HttpMockError::NoRoutes);
+        }
+        let default_delay = match &spec.delay {
+            Some(delay) => parse_latency(delay).map_err(|reason| HttpMockError::InvalidRoute {
+                route: "delay".to_string(),
+                reason,
+            })?,
+            None => (Duration::ZERO, Duration::ZERO),
+        };
+        let mut routes = Vec::new();
+        for route in spec.routes {
+            let method = route.method.trim().to_ascii_uppercase();
+            let invalid = |reason: String| HttpMockError::InvalidRoute {
+                route: format!("{} {}", method, route.path),
+                reason,
+            };
+            if !route.path.starts_with('/') {
+                return Err(invalid("path must start with '/'".to_string()));
+            }
+            if !(100..=999).contains(&route.status) {
+                return Err(invalid(format!("invalid status {}", route.status)));
+            }
+            let segments = parse_template(&route.path).map_err(invalid)?;
+            let delay = match &route.delay {
+                Some(delay) => parse_latency(delay).map_err(invalid)?,
+                None => default_delay,
+            };
+            let body = match (route.body, route.json, route.file) {
+                (None, None, None) => Body::Empty,
+                (Some(text), None, None) => Body::Text(text),
+                (None, Some(value), None) => Body::Json(value),
+                (None, None, Some(file)) => Body::File(base.join(file)),
+                _ => {
+                    return Err(invalid(
+                        "only one of 'body', 'json' and 'file' may be set".to_string(),
+                    ))
+                }
+            };
+            let mut headers: Vec<(String, String)> = route.headers.into_iter().collect();
+            for (name, value) in &spec.headers {
+                if !has_header(&headers, name) {
+                    headers.push((name.clone(), value.clone()));
+                }
+            }
+            routes.push(Route {
+                method: (method != "*").then_some(method),
+                template: route.path,
+                segments,
+                status: route.status,
+                headers,
+                delay,
+                body,
+            });
+        }
+        Ok(Self { routes })
+    }
+
+    // 按文件中的顺序取第一个匹配的路由
+    fn respond(&self, request: &Request) -> Response {
+        let mut path_matched = false;
+        for route in &self.routes {
+            let Some(params) = route.matches(&request.path) else {
+                continue;
+            };
+            path_matched = true;
+            let method_matches = match &route.method {
+                None => true,
+                // GET 路由同样应答 HEAD 请求
+                Some(method) => {
+                    *method == request.method || (method == "GET" && request.method == "HEAD")
+                }
+            };
+            if method_matches {
+                return route.respond(request, &params);
+            }
+        }
+        if request.method == "OPTIONS" {
+            // 没有定义 OPTIONS 路由时按 CORS 预检请求处理
+            return Response {
+                status: 204,
+                headers: Vec::new(),
+                body: Vec::new(),
+                delay: Duration::ZERO,
+            };
+        }
+        if path_matched {
+            Response::error(
+                405,
+                format!("method {} not allowed for {}", request.method, request.path),
+            )
+        } else {
+            Response::error(
+                404,
+                format!("no route matches {} {}", request.method, request.path),
+            )
+        }
+    }
+}
+
+impl Route {
+    // 匹配时返回路径参数（已解码）
+    fn matches(&self, path: &str) -> Option<Vec<(String, String)>> {
+        let actual: Vec<&str> = path_segments(path).collect();
+        let mut params = Vec::new();
+        for (index, segment) in self.segments.iter().enumerate() {
+            match segment {
+                Segment::Rest => {
+                    params.push(("*".to_string(), actual.get(index..)?.join("/")));
+                    return Some(params);
+                }
+                Segment::Literal(literal) => {
+                    if actual.get(index)? != literal {
+                        return None;
+                    }
+                }
+                Segment::Param(name) => {
+                    params.push((name.clone(), url_decode(actual.get(index)?)));
+                }
+            }
+        }
+        (actual.len() == self.segments.len()).then_some(params)
+    }
+
+    fn respond(&self, request: &Request, params: &[(String, String)]) -> Response {
+        let context = Context { request, params };
+        let mut headers: Vec<(String, String)> = self
+            .headers
+            .iter()
+            .map(|(name, value)| (name.clone(), context.render(value)))
+            .collect();
+        let (body, default_type) = match &self.body {
+            Body::Empty => (Vec::new(), None),
+            Body::Text(text) => {
+                let text = context.render(text);
+                let trimmed = text.trim_start();
+                let kind = if trimmed.starts_with('{') || trimmed.starts_with('[') {
+                    "application/json"
+                } else {
+                    "text/plain; charset=utf-8"
+                };
+                (text.into_bytes(), Some(kind))
+            }
+            Body::Json(value) => (
+                context.render_json(value).to_string().into_bytes(),
+                Some("application/json"),
+            ),
+            Body::File(path) => match fs::read(path) {
+                Ok(bytes) => (bytes, Some(content_type(&path.to_string_lossy()))),
+                Err(error) => {
+                    return Response::error(
+                        500,
+                        format!("failed to read {}: {}", path.display(), error),
+                    )
+                }
+            },
+        };
+        if let Some(kind) = default_type {
+            if !has_header(&headers, "Content-Type") {
+                headers.push(("Content-Type".to_string(), kind.to_string()));
+            }
+        }
+        Response {
+            status: self.status,
+            headers,
+            body,
+            delay: jittered(self.delay.0, self.delay.1),
+        }
+    }
+}
+
+// /users/:id、/users/{id} 中的参数段，以及末尾匹配剩余路径的 *
+fn parse_template(template: &str) -> Result<Vec<Segment>, String> {
+    let parts: Vec<&str> = path_segments(template).collect();
+    let mut segments = Vec::new();
+    for (index, part) in parts.iter().enumerate() {
+        let name = part.strip_prefix(':').or_else(|| {
+            part.strip_prefix('{')
+                .and_then(|rest| rest.strip_suffix('}'))
+        });
+        let segment = match name {
+            Some("") => return Err(format!("empty parameter name in '{}'", part)),
+            Some(name) => Segment::Param(name.to_string()),
+            None if *part == "*" => {
+                if index + 1 != parts.len() {
+                    return Err("'*' is only allowed as the last segment".to_string());
+                }
+                Segment::Rest
+            }
+            None => Segment::Literal(part.to_string()),
+        };
+        segments.push(segment);
+    }
+    Ok(segments)
+}
+
+fn path_segments(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/').filter(|segment| !segment.is_empty())
+}
+
+// 模板中可以引用的请求信息
+struct Context<'a> {
+    request: &'a Request,
+    params: &'a [(String, String)],
+}
+
+impl Context<'_> {
+    // 展开 {{params.id}}、{{query.page}}、{{headers.x-token}}、{{body.user.name}} 等占位符，
+    // 未知或缺失的值替换为空字符串
+    fn render(&self, template: &str) -> String {
+        let mut output = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(start) = rest.find("{{") {
+            let Some(end) = rest[start + 2..].find("}}") else {
+                break;
+            };
+            output.push_str(&rest[..start]);
+            match self.lookup(rest[start + 2..start + 2 + end].trim()) {
+                Some(Value::String(text)) => output.push_str(&text),
+                Some(Value::Null) | None => {}
+                Some(value) => output.push_str(&value.to_string()),
+            }
+            rest = &rest[start + 2 + end + 2..];
+        }
+        output.push_str(rest);
+        output
+    }
+
+    // JSON 响应体中只含一个占位符的字符串按原类型替换，{{body.count}} 仍输出数字
+    fn render_json(&self, value: &Value) -> Value {
+        match value {
+            Value::String(text) => {
+                let whole = text
+                    .strip_prefix("{{")
+                    .and_then(|rest| rest.strip_suffix("}}"))
+                    .filter(|inner| !inner.contains("{{") && !inner.contains("}}"));
+                match whole.and_then(|inner| self.lookup(inner.trim())) {
+                    Some(value) if !value.is_string() => value,
+                    _ => Value::String(self.render(text)),
+                }
+            }
+            Value::Array(items) => {
+                Value::Array(items.iter().map(|v| self.render_json(v)).collect())
+            }
+            Value::Object(map) => Value::Object(
+                map.iter()
+                    .map(|(key, v)| (key.clone(), self.render_json(v)))
+                    .collect(),
+            ),
+            other => other.clone(),
+        }
+    }
+
+    fn lookup(&self, expr: &str) -> Option<Value> {
+        let (root, key) = match expr.split_once('.') {
+            Some((root, key)) => (root, Some(key)),
+            None => (expr, None),
+        };
+        let find = |pairs: &[(String, String)], key: &str| {
+            pairs
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| Value::String(value.clone()))
+        };
+        match (root, key) {
+            ("method", None) => Some(Value::String(self.request.method.clone())),
+            ("path", None) => Some(Value::String(self.request.path.clone())),
+            ("now", None) => Some(Value::String(Local::now().to_rfc3339())),
+            ("timestamp", None) => Some(Value::from(Local::now().timestamp())),
+            ("body", None) => Some(Value::String(
+                String::from_utf8_lossy(&self.request.body).into_owned(),
+            )),
+            ("params", Some(key)) => find(self.params, key),
+            ("query", Some(key)) => find(&self.request.query, key),
+            ("headers", Some(key)) => find(&self.request.headers, &key.to_ascii_lowercase()),
+            // 请求体按 JSON 解析后按字段逐级取值，数组用下标
+            ("body", Some(key)) => {
+                let mut value: Value = serde_json::from_slice(&self.request.body).ok()?;
+                for field in key.split('.') {
+                    value = match value {
+                        Value::Object(mut map) => map.remove(field)?,
+                        Value::Array(mut items) => {
+                            let index: usize = field.parse().ok()?;
+                            (index < items.len()).then(|| items.swap_remove(index))?
+                        }
+                        _ => return None,
+                    };
+                }
+                Some(value)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mock(yaml: &str) -> Mock {
+        Mock::new(serde_yaml::from_str(yaml).unwrap(), Path::new("")).unwrap()
+    }
+
+    fn request(method: &str, path: &str) -> Request {
+        Request {
+            method: method.to_string(),
+            path: path.to_string(),
+            ..Request::default()
+        }
+    }
+
+    #[test]
+    fn test_route_matching() {
+        let mock = mock(
+            r#"
+routes:
+  - {method: GET, path: /users/me, body: me}
+  - {method: GET, path: "/users/:id", body: "user {{params.id}}"}
+  - {method: delete, path: "/users/{id}", status: 204}
+  - {path: "/static/*", body: "{{params.*}}"}
+"#,
+        );
+        let body = |method, path| {
+            let response = mock.respond(&request(method, path));
+            (response.status, String::from_utf8(response.body).unwrap())
+        };
+        assert_eq!(body("GET", "/users/me"), (200, "me".to_string()));
+        assert_eq!(body("GET", "/users/a%20b/"), (200, "user a b".to_string()));
+        assert_eq!(body("HEAD", "/users/7"), (200, "user 7".to_string()));
+        assert_eq!(body("DELETE", "/users/7").0, 204);
+        assert_eq!(body("POST", "/users/7").0, 405);
+        assert_eq!(body("GET", "/users/7/posts").0, 404);
+        assert_eq!(body("OPTIONS", "/users/7").0, 204);
+        assert_eq!(
+            body("PUT", "/static/js/app.js"),
+            (200, "js/app.js".to_string())
+        );
+        assert_eq!(body("GET", "/static"), (200, String::new()));
+
+        assert!(parse_template("/a/*/b").is_err());
+        assert!(Mock::new(
+            serde_yaml::from_str("routes: [{path: /a, body: x, json: {}}]").unwrap(),
+            Path::new("")
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_templates() {
+        let mock = mock(
+            r#"
+headers: {X-Mock: rtoolkit, X-Request: "{{headers.x-request-id}}"}
+routes:
+  - method: POST
+    path: /orders/:id
+    status: 201
+    headers: {X-Request: "req-{{headers.x-request-id}}"}
+    json:
+      id: "{{params.id}}"
+      page: "{{query.page}}"
+      count: "{{body.items.1.count}}"
+      label: "n={{body.items.0.count}}"
+      missing: "{{body.nope}}"
+"#,
+        );
+        let mut req = request("POST", "/orders/42");
+        req.query = parse_query("page=2&q=a+b");
+        req.headers = vec![("x-request-id".to_string(), "abc".to_string())];
+        req.body = br#"{"items": [{"count": 1}, {"count": 3}]}"#.to_vec();
+        let response = mock.respond(&req);
+        assert_eq!(response.status, 201);
+        assert_eq!(
+            response.headers,
+            vec![
+                ("X-Request".to_string(), "req-abc".to_string()),
+                ("X-Mock".to_string(), "rtoolkit".to_string()),
+                ("Content-Type".to_string(), "application/json".to_string()),
+            ]
+        );
+        let body: Value = serde_json::from_slice(&response.body).unwrap();
+        assert_eq!(
+            body,
+            json!({"id": "42", "page": "2", "count": 3, "label": "n=1", "missing": ""})
+        );
+    }
+}
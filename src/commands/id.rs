@@ -0,0 +1,599 @@
+use std::io::{self, BufRead};
+use std::thread;
+use std::time::Duration;
+
+use chrono::{DateTime, Local, NaiveDate, SecondsFormat, Utc};
+use clap::ValueEnum;
+use rand::Rng;
+use serde::Serialize;
+
+use crate::commands::uuid::now_ms;
+use crate::utils::output::RecordOutput;
+
+// Crockford Base32，去掉了容易混淆的 I、L、O、U
+const CROCKFORD: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+// NanoID 默认的 URL 安全字符表
+const NANOID_ALPHABET: &str = "useandom-26T198340PX75pxJACKVERYMINDBUSHWOLF_GQZbfghjklqvwyzrict";
+// Twitter 的纪元 2010-11-04T01:42:54.657Z
+const TWITTER_EPOCH: &str = "1288834974657";
+// Snowflake 共 63 位（最高位为 0），至少留给时间戳这么多位（约 49 天）
+const MIN_TIMESTAMP_BITS: u8 = 32;
+
+#[derive(clap::Args)]
+pub struct UniqueIdOpts {
+    #[arg(
+        value_enum,
+        short = 'k',
+        long,
+        help = "类型 ulid | nanoid | snowflake，生成时默认 ulid；解析时省略则按格式自动识别"
+    )]
+    kind: Option<IdKind>,
+
+    #[arg(short = 'n', long, default_value_t = 1, help = "生成数量")]
+    count: u32,
+
+    #[arg(short = 'l', long, default_value_t = 21, help = "NanoID 长度")]
+    length: usize,
+
+    #[arg(
+        long,
+        value_name = "CHARS",
+        help = "NanoID 字符表，默认为 A-Za-z0-9_-；例如 0123456789abcdef"
+    )]
+    alphabet: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "EPOCH",
+        default_value = TWITTER_EPOCH,
+        help = "Snowflake 纪元：Unix 毫秒数、2024-01-01 或 RFC 3339 时间，默认与 Twitter 相同"
+    )]
+    epoch: String,
+
+    #[arg(long, default_value_t = 0, help = "Snowflake 数据中心 ID")]
+    datacenter: u64,
+
+    #[arg(long, default_value_t = 0, help = "Snowflake 机器 ID")]
+    worker: u64,
+
+    #[arg(
+        long,
+        default_value_t = 5,
+        help = "Snowflake 数据中心 ID 的位数，可为 0"
+    )]
+    datacenter_bits: u8,
+
+    #[arg(long, default_value_t = 5, help = "Snowflake 机器 ID 的位数")]
+    worker_bits: u8,
+
+    #[arg(long, default_value_t = 12, help = "Snowflake 毫秒内序列号的位数")]
+    sequence_bits: u8,
+
+    #[arg(
+        short = 'd',
+        long,
+        help = "解析给出的 ULID / Snowflake，显示时间戳和其余各部分"
+    )]
+    decode: bool,
+
+    #[arg(
+        value_name = "ID",
+        requires = "decode",
+        help = "--decode 时要解析的 ID，省略或为 - 时从 stdin 逐行读取"
+    )]
+    ids: Vec<String>,
+
+    #[arg(long, requires = "decode", help = "以 JSON 输出解析结果")]
+    json: bool,
+
+    // 全局 --where / --fields / --rename，由命令行入口注入
+    #[arg(skip)]
+    pub(crate) record_output: RecordOutput,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum IdKind {
+    Ulid,
+    Nanoid,
+    Snowflake,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum UniqueIdError {
+    #[error("invalid {kind} '{value}'")]
+    InvalidId { kind: &'static str, value: String },
+    #[error("cannot tell whether '{0}' is a ULID or a Snowflake, use --kind")]
+    UnknownKind(String),
+    #[error("NanoID values are purely random and carry nothing to decode")]
+    NanoIdDecode,
+    #[error("invalid --alphabet: {0}")]
+    InvalidAlphabet(String),
+    #[error("--length must be at least 1")]
+    InvalidLength,
+    #[error("invalid --epoch '{0}', expected Unix milliseconds, 2024-01-01 or an RFC 3339 time")]
+    InvalidEpoch(String),
+    #[error("invalid Snowflake layout: {0}")]
+    InvalidLayout(String),
+    #[error("current time does not fit the Snowflake layout: {0}")]
+    ClockOutOfRange(String),
+    #[error("no IDs to decode")]
+    NoInput,
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("json serialize failed: {0}")]
+    Serialize(#[from] serde_json::Error),
+}
+
+// 48 位毫秒时间戳加 80 位随机数
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Ulid(pub u128);
+
+impl Ulid {
+    fn new(ms: u64, random: u128) -> Self {
+        Self((u128::from(ms) << 80) | (random & ((1 << 80) - 1)))
+    }
+
+    fn ms(&self) -> u64 {
+        (self.0 >> 80) as u64
+    }
+
+    fn random(&self) -> u128 {
+        self.0 & ((1 << 80) - 1)
+    }
+
+    // 不区分大小写，按 Crockford 规则把 I / L 当作 1、O 当作 0
+    pub fn parse(raw: &str) -> Option<Self> {
+        let raw = raw.trim();
+        if raw.len() != 26 {
+            return None;
+        }
+        let mut value: u128 = 0;
+        for (index, c) in raw.chars().enumerate() {
+            let c = match c.to_ascii_uppercase() {
+                'I' | 'L' => '1',
+                'O' => '0',
+                c => c,
+            };
+            let digit = CROCKFORD.iter().position(|&d| char::from(d) == c)? as u128;
+            // 26 个字符共 130 位，首字符只能用到 3 位
+            if index == 0 && digit > 7 {
+                return None;
+            }
+            value = (value << 5) | digit;
+        }
+        Some(Self(value))
+    }
+}
+
+impl std::fmt::Display for Ulid {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text: String = (0..26)
+            .rev()
+            .map(|index| char::from(CROCKFORD[((self.0 >> (index * 5)) & 0x1f) as usize]))
+            .collect();
+        f.write_str(&text)
+    }
+}
+
+// 同一毫秒内把随机部分加一，批量生成的 ULID 严格递增
+#[derive(Default)]
+struct UlidGenerator {
+    last: Option<Ulid>,
+}
+
+impl UlidGenerator {
+    fn next(&mut self, now_ms: u64) -> Ulid {
+        let ulid = match self.last {
+            // 时钟回拨时沿用上次的时间；随机部分用完时借用下一毫秒
+            Some(last) if now_ms <= last.ms() => match last.random().checked_add(1) {
+                Some(random) if random >> 80 == 0 => Ulid::new(last.ms(), random),
+                _ => Ulid::new(last.ms() + 1, rand::rng().random()),
+            },
+            _ => Ulid::new(now_ms, rand::rng().random()),
+        };
+        self.last = Some(ulid);
+        ulid
+    }
+}
+
+// 0 | 时间戳 | 数据中心 ID | 机器 ID | 序列号
+#[derive(Debug, Clone, Copy)]
+struct Layout {
+    datacenter_bits: u8,
+    worker_bits: u8,
+    sequence_bits: u8,
+}
+
+impl Layout {
+    fn new(datacenter_bits: u8, worker_bits: u8, sequence_bits: u8) -> Result<Self, UniqueIdError> {
+        let used = u32::from(datacenter_bits) + u32::from(worker_bits) + u32::from(sequence_bits);
+        if used + u32::from(MIN_TIMESTAMP_BITS) > 63 {
+            return Err(UniqueIdError::InvalidLayout(format!(
+                "datacenter, worker and sequence bits add up to {}, leaving fewer than {} bits for the timestamp",
+                used, MIN_TIMESTAMP_BITS
+            )));
+        }
+        Ok(Self {
+            datacenter_bits,
+            worker_bits,
+            sequence_bits,
+        })
+    }
+
+    fn timestamp_bits(&self) -> u8 {
+        63 - self.datacenter_bits - self.worker_bits - self.sequence_bits
+    }
+
+    fn max(bits: u8) -> u64 {
+        (1 << bits) - 1
+    }
+
+    fn compose(&self, elapsed: u64, datacenter: u64, worker: u64, sequence: u64) -> u64 {
+        let worker_shift = self.sequence_bits;
+        let datacenter_shift = worker_shift + self.worker_bits;
+        let timestamp_shift = datacenter_shift + self.datacenter_bits;
+        (elapsed << timestamp_shift)
+            | (datacenter << datacenter_shift)
+            | (worker << worker_shift)
+            | sequence
+    }
+
+    // (相对纪元的毫秒数, 数据中心 ID, 机器 ID, 序列号)
+    fn split(&self, id: u64) -> (u64, u64, u64, u64) {
+        let worker_shift = self.sequence_bits;
+        let datacenter_shift = worker_shift + self.worker_bits;
+        let timestamp_shift = datacenter_shift + self.datacenter_bits;
+        (
+            id >> timestamp_shift,
+            (id >> datacenter_shift) & Self::max(self.datacenter_bits),
+            (id >> worker_shift) & Self::max(self.worker_bits),
+            id & Self::max(self.sequence_bits),
+        )
+    }
+}
+
+struct SnowflakeGenerator {
+    layout: Layout,
+    epoch_ms: u64,
+    datacenter: u64,
+    worker: u64,
+    last_ms: Option<u64>,
+    sequence: u64,
+}
+
+impl SnowflakeGenerator {
+    // 当前毫秒的序列号用完时返回 Ok(None)，调用方等到下一毫秒再取
+    fn next(&mut self, now_ms: u64) -> Result<Option<u64>, UniqueIdError> {
+        // 时钟回拨时沿用上次的时间，保证不重复
+        let ms = self.last_ms.map_or(now_ms, |last| now_ms.max(last));
+        if self.last_ms == Some(ms) {
+            if self.sequence == Layout::max(self.layout.sequence_bits) {
+                return Ok(None);
+            }
+            self.sequence += 1;
+        } else {
+            self.last_ms = Some(ms);
+            self.sequence = 0;
+        }
+        let elapsed = ms.checked_sub(self.epoch_ms).ok_or_else(|| {
+            UniqueIdError::ClockOutOfRange("the current time is before --epoch".to_string())
+        })?;
+        if elapsed > Layout::max(self.layout.timestamp_bits()) {
+            return Err(UniqueIdError::ClockOutOfRange(format!(
+                "{} ms since --epoch do not fit in {} timestamp bits",
+                elapsed,
+                self.layout.timestamp_bits()
+            )));
+        }
+        Ok(Some(self.layout.compose(
+            elapsed,
+            self.datacenter,
+            self.worker,
+            self.sequence,
+        )))
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct DecodedId {
+    pub id: String,
+    pub kind: &'static str,
+    // RFC 3339（UTC）
+    pub timestamp: Option<String>,
+    pub unix_ms: Option<i64>,
+    // ULID 的 80 位随机部分（十六进制）
+    pub random: Option<String>,
+    pub datacenter: Option<u64>,
+    pub worker: Option<u64>,
+    pub sequence: Option<u64>,
+}
+
+pub fn run_unique_id(opts: UniqueIdOpts) -> Result<(), UniqueIdError> {
+    if opts.decode {
+        return run_decode(&opts);
+    }
+    match opts.kind.unwrap_or(IdKind::Ulid) {
+        IdKind::Ulid => {
+            let mut generator = UlidGenerator::default();
+            for _ in 0..opts.count {
+                println!("{}", generator.next(now_ms()));
+            }
+        }
+        IdKind::Nanoid => {
+            let alphabet = nanoid_alphabet(opts.alphabet.as_deref())?;
+            if opts.length == 0 {
+                return Err(UniqueIdError::InvalidLength);
+            }
+            for _ in 0..opts.count {
+                println!("{}", nanoid(&alphabet, opts.length));
+            }
+        }
+        IdKind::Snowflake => {
+            let mut generator = snowflake_generator(&opts)?;
+            for _ in 0..opts.count {
+                let id = loop {
+                    match generator.next(now_ms())? {
+                        Some(id) => break id,
+                        None => thread::sleep(Duration::from_micros(100)),
+                    }
+                };
+                println!("{}", id);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn snowflake_generator(opts: &UniqueIdOpts) -> Result<SnowflakeGenerator, UniqueIdError> {
+    let layout = Layout::new(opts.datacenter_bits, opts.worker_bits, opts.sequence_bits)?;
+    for (name, value, bits) in [
+        ("--datacenter", opts.datacenter, layout.datacenter_bits),
+        ("--worker", opts.worker, layout.worker_bits),
+    ] {
+        if value > Layout::max(bits) {
+            return Err(UniqueIdError::InvalidLayout(format!(
+                "{} {} does not fit in {} bits (max {})",
+                name,
+                value,
+                bits,
+                Layout::max(bits)
+            )));
+        }
+    }
+    Ok(SnowflakeGenerator {
+        layout,
+        epoch_ms: parse_epoch(&opts.epoch)?,
+        datacenter: opts.datacenter,
+        worker: opts.worker,
+        last_ms: None,
+        sequence: 0,
+    })
+}
+
+fn parse_epoch(raw: &str) -> Result<u64, UniqueIdError> {
+    let raw = raw.trim();
+    let invalid = || UniqueIdError::InvalidEpoch(raw.to_string());
+    if let Ok(ms) = raw.parse::<u64>() {
+        return Ok(ms);
+    }
+    let ms = if let Ok(date) = NaiveDate::parse_from_str(raw, "%Y-%m-%d") {
+        date.and_hms_opt(0, 0, 0)
+            .ok_or_else(invalid)?
+            .and_utc()
+            .timestamp_millis()
+    } else {
+        DateTime::parse_from_rfc3339(raw)
+            .map_err(|_| invalid())?
+            .timestamp_millis()
+    };
+    u64::try_from(ms).map_err(|_| invalid())
+}
+
+fn nanoid_alphabet(raw: Option<&str>) -> Result<Vec<char>, UniqueIdError> {
+    let alphabet: Vec<char> = raw.unwrap_or(NANOID_ALPHABET).chars().collect();
+    if alphabet.len() < 2 {
+        return Err(UniqueIdError::InvalidAlphabet(
+            "at least 2 characters are required".to_string(),
+        ));
+    }
+    if let Some(duplicate) = alphabet
+        .iter()
+        .enumerate()
+        .find_map(|(index, c)| alphabet[..index].contains(c).then_some(c))
+    {
+        return Err(UniqueIdError::InvalidAlphabet(format!(
+            "'{}' appears more than once",
+            duplicate
+        )));
+    }
+    Ok(alphabet)
+}
+
+// 每个字符均匀地从字符表中选取
+fn nanoid(alphabet: &[char], length: usize) -> String {
+    let mut rng = rand::rng();
+    (0..length)
+        .map(|_| alphabet[rng.random_range(0..alphabet.len())])
+        .collect()
+}
+
+fn run_decode(opts: &UniqueIdOpts) -> Result<(), UniqueIdError> {
+    let mut inputs = Vec::new();
+    if !opts.ids.is_empty() && opts.ids.iter().all(|arg| arg != "-") {
+        inputs.clone_from(&opts.ids);
+    } else {
+        for line in io::stdin().lock().lines() {
+            let line = line?;
+            if !line.trim().is_empty() {
+                inputs.push(line.trim().to_string());
+            }
+        }
+    }
+    if inputs.is_empty() {
+        return Err(UniqueIdError::NoInput);
+    }
+    let layout = Layout::new(opts.datacenter_bits, opts.worker_bits, opts.sequence_bits)?;
+    let epoch_ms = parse_epoch(&opts.epoch)?;
+
+    let mut records = Vec::new();
+    for raw in &inputs {
+        let kind = match opts.kind {
+            Some(kind) => kind,
+            None if Ulid::parse(raw).is_some() => IdKind::Ulid,
+            None if !raw.is_empty() && raw.bytes().all(|b| b.is_ascii_digit()) => IdKind::Snowflake,
+            None => return Err(UniqueIdError::UnknownKind(raw.clone())),
+        };
+        records.push(match kind {
+            IdKind::Ulid => decode_ulid(raw)?,
+            IdKind::Snowflake => decode_snowflake(raw, layout, epoch_ms)?,
+            IdKind::Nanoid => return Err(UniqueIdError::NanoIdDecode),
+        });
+    }
+
+    opts.record_output.retain(&mut records)?;
+    if opts.json {
+        opts.record_output
+            .write_json(&records, io::stdout().lock())?;
+        return Ok(());
+    }
+    for record in &records {
+        let mut fields = vec![record.kind.to_string()];
+        if let Some(ts) = record.unix_ms.and_then(DateTime::from_timestamp_millis) {
+            fields.push(format!(
+                "{} ({})",
+                record.timestamp.as_deref().unwrap_or_default(),
+                ts.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S%.3f %:z")
+            ));
+        }
+        if let Some(random) = &record.random {
+            fields.push(format!("random {}", random));
+        }
+        if let Some(datacenter) = record.datacenter {
+            fields.push(format!("datacenter {}", datacenter));
+        }
+        if let Some(worker) = record.worker {
+            fields.push(format!("worker {}", worker));
+        }
+        if let Some(sequence) = record.sequence {
+            fields.push(format!("sequence {}", sequence));
+        }
+        println!("{}  {}", record.id, fields.join("  "));
+    }
+    Ok(())
+}
+
+fn decode_ulid(raw: &str) -> Result<DecodedId, UniqueIdError> {
+    let ulid = Ulid::parse(raw).ok_or_else(|| UniqueIdError::InvalidId {
+        kind: "ULID",
+        value: raw.to_string(),
+    })?;
+    let timestamp = DateTime::from_timestamp_millis(ulid.ms() as i64);
+    Ok(DecodedId {
+        id: ulid.to_string(),
+        kind: "ulid",
+        timestamp: timestamp.map(rfc3339),
+        unix_ms: timestamp.map(|ts| ts.timestamp_millis()),
+        random: Some(format!("{:020x}", ulid.random())),
+        datacenter: None,
+        worker: None,
+        sequence: None,
+    })
+}
+
+fn decode_snowflake(raw: &str, layout: Layout, epoch_ms: u64) -> Result<DecodedId, UniqueIdError> {
+    let id = raw
+        .parse::<u64>()
+        .ok()
+        .filter(|id| id >> 63 == 0)
+        .ok_or_else(|| UniqueIdError::InvalidId {
+            kind: "Snowflake",
+            value: raw.to_string(),
+        })?;
+    let (elapsed, datacenter, worker, sequence) = layout.split(id);
+    let timestamp = i64::try_from(elapsed + epoch_ms)
+        .ok()
+        .and_then(DateTime::from_timestamp_millis);
+    Ok(DecodedId {
+        id: id.to_string(),
+        kind: "snowflake",
+        timestamp: timestamp.map(rfc3339),
+        unix_ms: timestamp.map(|ts| ts.timestamp_millis()),
+        random: None,
+        datacenter: (layout.datacenter_bits > 0).then_some(datacenter),
+        worker: (layout.worker_bits > 0).then_some(worker),
+        sequence: Some(sequence),
+    })
+}
+
+fn rfc3339(ts: DateTime<Utc>) -> String {
+    ts.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ulid() {
+        // ULID 规范中的示例
+        let ulid = Ulid::parse("01arz3ndektsv4rrffq69g5fav").unwrap();
+        assert_eq!(ulid.to_string(), "01ARZ3NDEKTSV4RRFFQ69G5FAV");
+        assert_eq!(ulid.ms(), 1_469_922_850_259);
+        assert_eq!(ulid.random(), 0xd6764c61efb99302bd5b);
+        assert_eq!(Ulid::parse("O1ARZ3NDEKTSV4RRFFQ69G5FAV"), Some(ulid));
+        // 首字符超过 7 会溢出 128 位
+        assert_eq!(Ulid::parse("81ARZ3NDEKTSV4RRFFQ69G5FAV"), None);
+        assert_eq!(Ulid::parse("01ARZ3NDEKTSV4RRFFQ69G5FAU"), None);
+
+        let mut generator = UlidGenerator::default();
+        let mut previous = generator.next(1_000);
+        for now in [1_000, 1_000, 999, 1_001] {
+            let next = generator.next(now);
+            assert!(next > previous);
+            assert_eq!(Ulid::parse(&next.to_string()), Some(next));
+            previous = next;
+        }
+
+        let hex = nanoid_alphabet(Some("0123456789abcdef")).unwrap();
+        let id = nanoid(&hex, 32);
+        assert_eq!(id.len(), 32);
+        assert!(id.chars().all(|c| c.is_ascii_hexdigit()));
+        assert!(nanoid_alphabet(Some("abca")).is_err());
+    }
+
+    #[test]
+    fn test_snowflake() {
+        let layout = Layout::new(5, 5, 12).unwrap();
+        let epoch = parse_epoch(TWITTER_EPOCH).unwrap();
+        let record = decode_snowflake("1541815603606036480", layout, epoch).unwrap();
+        assert_eq!(
+            record.timestamp.as_deref(),
+            Some("2022-06-28T16:07:40.105Z")
+        );
+        assert_eq!(
+            (record.datacenter, record.worker, record.sequence),
+            (Some(11), Some(26), Some(0))
+        );
+
+        let mut generator = SnowflakeGenerator {
+            layout: Layout::new(0, 10, 2).unwrap(),
+            epoch_ms: parse_epoch("2024-01-01").unwrap(),
+            datacenter: 0,
+            worker: 7,
+            last_ms: None,
+            sequence: 0,
+        };
+        let now = parse_epoch("2024-01-01T00:00:01Z").unwrap();
+        let ids: Vec<u64> = (0..4)
+            .map(|_| generator.next(now).unwrap().unwrap())
+            .collect();
+        // 2 位序列号在同一毫秒内只能生成 4 个
+        assert_eq!(generator.next(now).unwrap(), None);
+        assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(generator.layout.split(ids[3]), (1000, 0, 7, 3));
+        assert!(generator.next(now - 2_000_000).unwrap().is_none());
+
+        assert!(Layout::new(10, 10, 12).is_err());
+        assert!(decode_snowflake("9223372036854775808", layout, epoch).is_err());
+    }
+}
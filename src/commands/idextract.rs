@@ -0,0 +1,249 @@
+// 从任意文本（文档导出、日志）中找出疑似身份证号的 15 / 18 位数字串，逐个做地区码、出生日期和校验位检查，
+// 只输出校验通过的号码并去重，记录首次出现的位置和出现次数，用于排查泄露的证件号。
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::PathBuf;
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::utils::areas::{get_full_area_info, get_province_name};
+use crate::utils::idcard::{validate_id_number, RegionCheck};
+use crate::utils::output::RecordOutput;
+
+#[derive(clap::Args)]
+pub struct IdExtractOpts {
+    #[arg(
+        value_name = "FILE",
+        help = "要扫描的文本文件，省略或为 - 时从 stdin 读取"
+    )]
+    file: Option<PathBuf>,
+
+    #[arg(
+        value_enum,
+        short = 'f',
+        long,
+        default_value_t = ExtractFormat::Plain,
+        help = "输出格式 plain | json | csv"
+    )]
+    format: ExtractFormat,
+
+    #[arg(long, help = "地区码只校验省级代码，兼容已撤销的旧地区码")]
+    lenient_region: bool,
+
+    // 全局 --where / --fields / --rename，由命令行入口注入
+    #[arg(skip)]
+    pub(crate) record_output: RecordOutput,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ExtractFormat {
+    Plain,
+    Json,
+    Csv,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum IdExtractError {
+    #[error("failed to open {path}: {source}")]
+    Open { path: PathBuf, source: io::Error },
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("csv write failed: {0}")]
+    Csv(#[from] csv::Error),
+    #[error("json serialize failed: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+// 一个去重后的号码，位置为首次出现处：行号和列号从 1 开始按字符计，offset 为字节偏移
+#[derive(Debug, Serialize, PartialEq)]
+pub struct ExtractedId {
+    pub id_number: String,
+    pub line: usize,
+    pub column: usize,
+    pub offset: usize,
+    pub occurrences: usize,
+    pub region: String,
+    pub birthday: String,
+    pub gender: &'static str,
+    pub legacy: bool,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct ExtractSummary {
+    pub lines: usize,
+    // 长度和字符符合身份证号形式的数字串数
+    pub candidates: usize,
+    // 校验通过的出现次数（未去重）
+    pub valid: usize,
+}
+
+pub fn run_id_extract(opts: IdExtractOpts) -> Result<(), IdExtractError> {
+    let reader: Box<dyn BufRead> = match opts.file.as_ref().filter(|path| path.as_os_str() != "-") {
+        Some(path) => Box::new(BufReader::new(File::open(path).map_err(|source| {
+            IdExtractError::Open {
+                path: path.clone(),
+                source,
+            }
+        })?)),
+        None => Box::new(BufReader::new(io::stdin().lock())),
+    };
+    let region_check = if opts.lenient_region {
+        RegionCheck::Province
+    } else {
+        RegionCheck::Strict
+    };
+    let (mut ids, summary) = extract_ids(reader, region_check)?;
+    opts.record_output.retain(&mut ids)?;
+
+    let mut out = io::stdout().lock();
+    match opts.format {
+        ExtractFormat::Json => opts.record_output.write_json(&ids, &mut out)?,
+        ExtractFormat::Csv => opts.record_output.write_csv(&ids, &mut out)?,
+        ExtractFormat::Plain => {
+            for id in &ids {
+                writeln!(
+                    out,
+                    "{}:{}  {}  {}  {}  {}  x{}",
+                    id.line,
+                    id.column,
+                    id.id_number,
+                    id.region,
+                    id.birthday,
+                    id.gender,
+                    id.occurrences
+                )?;
+            }
+        }
+    }
+    eprintln!(
+        "Scanned {} lines: {} ID-like numbers, {} valid ({} unique)",
+        summary.lines,
+        summary.candidates,
+        summary.valid,
+        ids.len()
+    );
+    Ok(())
+}
+
+pub fn extract_ids<R: BufRead>(
+    mut reader: R,
+    region_check: RegionCheck,
+) -> Result<(Vec<ExtractedId>, ExtractSummary), io::Error> {
+    let mut ids: Vec<ExtractedId> = Vec::new();
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    let mut summary = ExtractSummary::default();
+    let mut offset = 0;
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        if reader.read_until(b'\n', &mut buf)? == 0 {
+            break;
+        }
+        summary.lines += 1;
+        for (start, end) in candidates(&buf) {
+            // 候选串只含 ASCII 数字和 X
+            let candidate = std::str::from_utf8(&buf[start..end]).unwrap_or_default();
+            summary.candidates += 1;
+            let Ok(parsed) = validate_id_number(candidate, region_check) else {
+                continue;
+            };
+            summary.valid += 1;
+            let id_number = candidate.to_ascii_uppercase();
+            if let Some(&index) = seen.get(&id_number) {
+                ids[index].occurrences += 1;
+                continue;
+            }
+            seen.insert(id_number.clone(), ids.len());
+            ids.push(ExtractedId {
+                id_number,
+                line: summary.lines,
+                // 非 UTF-8 的行按替换后的字符计列号
+                column: String::from_utf8_lossy(&buf[..start]).chars().count() + 1,
+                offset: offset + start,
+                occurrences: 1,
+                region: region_name(&parsed.region),
+                birthday: parsed.birthday.format("%Y-%m-%d").to_string(),
+                gender: parsed.gender,
+                legacy: parsed.legacy,
+            });
+        }
+        offset += buf.len();
+    }
+    Ok((ids, summary))
+}
+
+// 找出一行中形如身份证号的数字串：15 位数字、18 位数字或 17 位数字加 X。
+// 前后紧邻数字（更长的卡号、订单号）或 X 后紧跟字母时不算，避免从长串中截取
+fn candidates(bytes: &[u8]) -> Vec<(usize, usize)> {
+    let mut found = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if !bytes[i].is_ascii_digit() {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < bytes.len() && bytes[i].is_ascii_digit() {
+            i += 1;
+        }
+        let digits = i - start;
+        let check_x = digits == 17 && matches!(bytes.get(i), Some(b'X' | b'x'));
+        let end = if check_x { i + 1 } else { i };
+        let boundary = !bytes.get(end).is_some_and(u8::is_ascii_alphanumeric);
+        if boundary && (digits == 15 || digits == 18 || check_x) {
+            found.push((start, end));
+        }
+        i = end;
+    }
+    found
+}
+
+fn region_name(code: &str) -> String {
+    match get_full_area_info(code) {
+        // 直辖市的“市辖区”“县”不是地名，省略
+        Some((province, city, area)) if matches!(city.name.as_str(), "市辖区" | "县") => {
+            format!("{}{}", province.name, area.name)
+        }
+        Some((province, city, area)) => {
+            format!("{}{}{}", province.name, city.name, area.name)
+        }
+        None => get_province_name(&code[..2]).unwrap_or_else(|| code.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_ids_from_text() {
+        let text = "用户 张三，身份证号:11010519491231002x，电话 13800138000\n\
+                    订单号 811010519491231002X99 不算\n\
+                    旧证 110105491231001；重复 11010519491231002X，校验位错误 110105194912310021\n";
+        let (ids, summary) = extract_ids(text.as_bytes(), RegionCheck::Strict).unwrap();
+        assert_eq!(
+            summary,
+            ExtractSummary {
+                lines: 3,
+                candidates: 4,
+                valid: 3,
+            }
+        );
+        assert_eq!(ids.len(), 2);
+        assert_eq!(ids[0].id_number, "11010519491231002X");
+        assert_eq!((ids[0].line, ids[0].column), (1, 12));
+        assert_eq!(ids[0].offset, "用户 张三，身份证号:".len());
+        assert_eq!(ids[0].occurrences, 2);
+        assert_eq!(
+            (ids[0].birthday.as_str(), ids[0].gender),
+            ("1949-12-31", "female")
+        );
+        assert_eq!(ids[0].region, "北京市朝阳区");
+        assert_eq!(ids[1].id_number, "110105491231001");
+        assert!(ids[1].legacy);
+        assert_eq!(ids[1].line, 3);
+    }
+}
@@ -4,18 +4,30 @@ use fake::locales::*;
 use fake::Fake;
 use rand::{rng, Rng};
 
+use clap::Subcommand;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs::File;
-use std::io::Write;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::PathBuf;
 
 use crate::utils::areas::get_full_area_info_str;
 use crate::utils::areas::{random_area, random_region_by_code};
+use crate::utils::idcard::{
+    checksum_char, validate_id_number, InvalidId, InvalidReason, RegionCheck,
+};
+use crate::utils::metrics;
+use crate::utils::names::{self, parse_ratio, NameMix, NameMixError, NameStyle};
+use crate::utils::output::RecordOutput;
 
 pub const MAX_IDGEN_COUNT: u32 = 10_000_000;
 pub const MAX_EXCEL_ROWS: u32 = 1_048_575;
 
 #[derive(clap::Args)]
 pub struct IdOpts {
+    #[command(subcommand)]
+    command: Option<IdCommand>,
+
     #[arg(
         short = 'n',
         long,
@@ -33,7 +45,11 @@ pub struct IdOpts {
     )]
     region: Option<String>,
 
-    #[arg(short = 'b', long = "birth", help = "出生日期")]
+    #[arg(
+        short = 'b',
+        long = "birth",
+        help = "出生日期，支持 1995、1995-06 或 1995-06-15，只给年份或年月时其余部分随机"
+    )]
     birth: Option<String>,
 
     #[arg(long, default_value = "1970-01-01")]
@@ -45,26 +61,78 @@ pub struct IdOpts {
     #[arg(value_enum, short = 'g', long = "gender", default_value_t = Gender::Any, help = "性别")]
     gender: Gender,
 
+    #[arg(
+        long = "name-mix",
+        value_name = "SPEC",
+        help = "按权重混合姓名写法，例如 han=70,uyghur=10,tibetan=10,mongolian=10（默认全部为汉族姓名）"
+    )]
+    name_mix: Option<String>,
+
+    #[arg(
+        long = "long-names",
+        value_name = "RATIO",
+        value_parser = parse_ratio,
+        help = "使用超长写法（复姓、长音译名、带氏族名等）的比例，0-1 或百分比"
+    )]
+    long_names: Option<f64>,
+
     #[arg(short = 'o', long = "output", help = "输出文件")]
     output: Option<String>,
 
     #[arg(value_enum, short = 't', long = "type", default_value_t = OutputType::Text, help = "输出类型")]
     output_type: OutputType,
+
+    // 全局 --where / --fields / --rename，由命令行入口注入
+    #[arg(skip)]
+    pub(crate) record_output: RecordOutput,
+}
+
+#[derive(Subcommand)]
+enum IdCommand {
+    #[command(about = "批量校验身份证号")]
+    Validate(ValidateOpts),
+}
+
+#[derive(clap::Args)]
+struct ValidateOpts {
+    #[arg(
+        short = 'f',
+        long = "file",
+        value_name = "ids.txt",
+        help = "每行一个身份证号的输入文件，省略或为 - 时从 stdin 读取"
+    )]
+    file: Option<PathBuf>,
+
+    #[arg(long, value_name = "report.csv", help = "将无效记录写入 CSV 报告")]
+    report: Option<PathBuf>,
+
+    #[arg(long, help = "地区码只校验省级代码，兼容已撤销的旧地区码")]
+    lenient_region: bool,
+
+    #[arg(short, long, help = "不逐行输出无效记录，只输出汇总")]
+    quiet: bool,
 }
 
 pub fn run_gen_id(opts: IdOpts) -> Result<(), IdError> {
-    let records = generate_ids(IdGenerateRequest {
+    if let Some(IdCommand::Validate(validate)) = opts.command {
+        return run_validate(validate);
+    }
+
+    let mut records = generate_ids(IdGenerateRequest {
         count: Some(opts.count),
         region: opts.region,
         birth: opts.birth,
         min_birth: Some(opts.min_birth),
         max_birth: Some(opts.max_birth),
         gender: Some(opts.gender),
+        name_mix: opts.name_mix,
+        long_names: opts.long_names,
     })?;
+    opts.record_output.retain(&mut records)?;
 
     // 根据输出类型输出不同格式
     if let Some(output) = &opts.output {
-        write_to_file(&records, output, &opts.output_type)?;
+        write_to_file(&records, output, &opts.output_type, &opts.record_output)?;
     } else {
         print_console(&records);
     }
@@ -83,10 +151,109 @@ fn print_console(records: &[IdRecord]) {
     }
 }
 
+fn run_validate(opts: ValidateOpts) -> Result<(), IdError> {
+    let reader: Box<dyn BufRead> = match opts.file.as_ref().filter(|path| path.as_os_str() != "-") {
+        Some(path) => Box::new(BufReader::new(File::open(path)?)),
+        None => Box::new(BufReader::new(io::stdin().lock())),
+    };
+    let mut report = match &opts.report {
+        Some(path) => {
+            let mut writer = BufWriter::new(File::create(path)?);
+            writeln!(writer, "line,id_number,reason,detail")?;
+            Some(writer)
+        }
+        None => None,
+    };
+    let region_check = if opts.lenient_region {
+        RegionCheck::Province
+    } else {
+        RegionCheck::Strict
+    };
+
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    let mut total = 0u64;
+    let mut valid = 0u64;
+    let mut reasons: BTreeMap<InvalidReason, u64> = BTreeMap::new();
+    for (index, line) in reader.split(b'\n').enumerate() {
+        let line = line?;
+        // 无法按 UTF-8 解码的行记为格式错误，继续校验后续行
+        let (line, checked) = match String::from_utf8(line) {
+            Ok(line) => {
+                let checked = validate_id_number(line.trim(), region_check).map(|_| ());
+                (line, checked)
+            }
+            Err(error) => {
+                let line = String::from_utf8_lossy(error.as_bytes()).into_owned();
+                let invalid = InvalidId {
+                    reason: InvalidReason::Format,
+                    detail: "line is not valid UTF-8".to_string(),
+                };
+                (line, Err(invalid))
+            }
+        };
+        let id = line.trim();
+        if id.is_empty() {
+            continue;
+        }
+        total += 1;
+        match checked {
+            Ok(()) => valid += 1,
+            Err(invalid) => {
+                *reasons.entry(invalid.reason).or_default() += 1;
+                if !opts.quiet {
+                    writeln!(
+                        out,
+                        "line {}: {} [{}] {}",
+                        index + 1,
+                        id,
+                        invalid.reason.as_str(),
+                        invalid.detail
+                    )?;
+                }
+                if let Some(report) = report.as_mut() {
+                    writeln!(
+                        report,
+                        "{},{},{},{}",
+                        index + 1,
+                        csv_cell(id),
+                        invalid.reason.as_str(),
+                        csv_cell(&invalid.detail)
+                    )?;
+                }
+            }
+        }
+    }
+    if let Some(mut report) = report {
+        report.flush()?;
+    }
+
+    writeln!(
+        out,
+        "\nTotal: {}  Valid: {}  Invalid: {}",
+        total,
+        valid,
+        total - valid
+    )?;
+    for (reason, count) in &reasons {
+        writeln!(out, "  {:<9} {}", reason.as_str(), count)?;
+    }
+    if let Some(path) = &opts.report {
+        writeln!(out, "Report written to {}", path.display())?;
+    }
+    out.flush()?;
+    // 存在无效记录时以非零状态退出，便于脚本和 CI 判断
+    if valid < total {
+        return Err(IdError::InvalidIds(total - valid));
+    }
+    Ok(())
+}
+
 fn write_to_file(
     records: &[IdRecord],
     path: &str,
     output_type: &OutputType,
+    record_output: &RecordOutput,
 ) -> Result<(), IdError> {
     let mut file = File::create(path)?;
     match output_type {
@@ -106,6 +273,9 @@ fn write_to_file(
                 )?
             }
         }
+        OutputType::Csv if record_output.is_shaped() => record_output
+            .write_csv(records, file)
+            .map_err(io::Error::from)?,
         OutputType::Csv => {
             writeln!(file, "姓名,性别,身份证号,地址")?;
             for record in records {
@@ -120,8 +290,7 @@ fn write_to_file(
             }
         }
         OutputType::Json => {
-            let json = serde_json::to_string_pretty(records)?;
-            file.write_all(json.as_bytes())?;
+            record_output.write_json(records, file)?;
         }
         OutputType::Excel => {
             write_excel_file(records, path)?;
@@ -237,12 +406,16 @@ pub enum IdError {
     InvalidRegion,
     #[error("excel export supports at most 1048575 records")]
     ExcelRowLimit,
+    #[error("{0} invalid id numbers found")]
+    InvalidIds(u64),
     #[error(transparent)]
     Io(#[from] std::io::Error),
     #[error("Serialization error: {0}")]
     Serialization(#[from] serde_json::Error),
     #[error(transparent)]
     Excel(#[from] rust_xlsxwriter::XlsxError),
+    #[error(transparent)]
+    NameMix(#[from] NameMixError),
 }
 
 #[derive(Debug, Clone, Copy, clap::ValueEnum, Deserialize, Serialize)]
@@ -278,6 +451,10 @@ pub struct IdGenerateRequest {
     pub min_birth: Option<String>,
     pub max_birth: Option<String>,
     pub gender: Option<Gender>,
+    // 姓名写法权重，格式同 --name-mix
+    pub name_mix: Option<String>,
+    // 超长姓名的比例，0-1
+    pub long_names: Option<f64>,
 }
 
 #[derive(Debug, Serialize)]
@@ -291,6 +468,7 @@ pub struct IdRecord {
 }
 
 pub fn generate_ids(request: IdGenerateRequest) -> Result<Vec<IdRecord>, IdError> {
+    let (name_mix, long_names) = name_options(&request)?;
     let count = request.count.unwrap_or(1).clamp(1, MAX_IDGEN_COUNT);
     let min_birth = request
         .min_birth
@@ -298,11 +476,9 @@ pub fn generate_ids(request: IdGenerateRequest) -> Result<Vec<IdRecord>, IdError
     let max_birth = request
         .max_birth
         .unwrap_or_else(|| "2010-12-31".to_string());
-    let min_date = parse_date(&min_birth)?;
-    let max_date = parse_date(&max_birth)?;
-    let fixed_birth = match request.birth {
-        Some(b) if !b.trim().is_empty() => Some(parse_date(&b)?),
-        _ => None,
+    let (min_date, max_date) = match request.birth {
+        Some(b) if !b.trim().is_empty() => parse_birth_spec(&b)?,
+        _ => (parse_date(&min_birth)?, parse_date(&max_birth)?),
     };
     let region = request
         .region
@@ -313,14 +489,11 @@ pub fn generate_ids(request: IdGenerateRequest) -> Result<Vec<IdRecord>, IdError
     let mut records = Vec::with_capacity(count as usize);
     for _ in 0..count {
         records.push(generate_id(
-            region,
-            fixed_birth,
-            min_date,
-            max_date,
-            gender,
+            region, min_date, max_date, gender, &name_mix, long_names,
         )?);
     }
 
+    metrics::records_generated("idgen", records.len());
     Ok(records)
 }
 
@@ -331,6 +504,7 @@ pub fn write_generated_ids<W: Write>(
 ) -> Result<u32, IdError> {
     validate_id_download_request(&request, output_type)?;
 
+    let (name_mix, long_names) = name_options(&request)?;
     let count = request.count.unwrap_or(1).clamp(1, MAX_IDGEN_COUNT);
     let min_birth = request
         .min_birth
@@ -338,11 +512,9 @@ pub fn write_generated_ids<W: Write>(
     let max_birth = request
         .max_birth
         .unwrap_or_else(|| "2010-12-31".to_string());
-    let min_date = parse_date(&min_birth)?;
-    let max_date = parse_date(&max_birth)?;
-    let fixed_birth = match request.birth {
-        Some(b) if !b.trim().is_empty() => Some(parse_date(&b)?),
-        _ => None,
+    let (min_date, max_date) = match request.birth {
+        Some(b) if !b.trim().is_empty() => parse_birth_spec(&b)?,
+        _ => (parse_date(&min_birth)?, parse_date(&max_birth)?),
     };
     let region = request.region.filter(|value| !value.trim().is_empty());
     let gender = request.gender.unwrap_or(Gender::Any);
@@ -351,8 +523,14 @@ pub fn write_generated_ids<W: Write>(
         OutputType::Text => {
             writeln!(writer, "姓名\t性别\t身份证号\t生日\t地址")?;
             for _ in 0..count {
-                let record =
-                    generate_id(region.as_deref(), fixed_birth, min_date, max_date, gender)?;
+                let record = generate_id(
+                    region.as_deref(),
+                    min_date,
+                    max_date,
+                    gender,
+                    &name_mix,
+                    long_names,
+                )?;
                 writeln!(
                     writer,
                     "{}\t{}\t{}\t{}\t{}",
@@ -368,8 +546,14 @@ pub fn write_generated_ids<W: Write>(
             writer.write_all(b"\xEF\xBB\xBF")?;
             writeln!(writer, "姓名,性别,身份证号,生日,地址")?;
             for _ in 0..count {
-                let record =
-                    generate_id(region.as_deref(), fixed_birth, min_date, max_date, gender)?;
+                let record = generate_id(
+                    region.as_deref(),
+                    min_date,
+                    max_date,
+                    gender,
+                    &name_mix,
+                    long_names,
+                )?;
                 writeln!(
                     writer,
                     "{},{},{},{},{}",
@@ -384,8 +568,14 @@ pub fn write_generated_ids<W: Write>(
         OutputType::Json => {
             writer.write_all(b"[\n")?;
             for index in 0..count {
-                let record =
-                    generate_id(region.as_deref(), fixed_birth, min_date, max_date, gender)?;
+                let record = generate_id(
+                    region.as_deref(),
+                    min_date,
+                    max_date,
+                    gender,
+                    &name_mix,
+                    long_names,
+                )?;
                 if index > 0 {
                     writer.write_all(b",\n")?;
                 }
@@ -398,10 +588,11 @@ pub fn write_generated_ids<W: Write>(
             for _ in 0..count {
                 records.push(generate_id(
                     region.as_deref(),
-                    fixed_birth,
                     min_date,
                     max_date,
                     gender,
+                    &name_mix,
+                    long_names,
                 )?);
             }
             let path =
@@ -413,6 +604,7 @@ pub fn write_generated_ids<W: Write>(
         }
     }
 
+    metrics::records_generated("idgen", count as usize);
     Ok(count)
 }
 
@@ -435,7 +627,7 @@ pub fn validate_id_download_request(
         .as_deref()
         .filter(|value| !value.trim().is_empty())
     {
-        parse_date(birth)?;
+        parse_birth_spec(birth)?;
     }
 
     if let Some(region) = request
@@ -446,16 +638,30 @@ pub fn validate_id_download_request(
         validate_region(region)?;
         random_region_by_code(region).ok_or(IdError::InvalidRegion)?;
     }
+    name_options(request)?;
 
     Ok(())
 }
 
+fn name_options(request: &IdGenerateRequest) -> Result<(NameMix, f64), IdError> {
+    let name_mix = match request.name_mix.as_deref().map(str::trim) {
+        Some(spec) if !spec.is_empty() => NameMix::parse(spec)?,
+        _ => NameMix::default(),
+    };
+    let long_names = request.long_names.unwrap_or(0.0);
+    if !(0.0..=1.0).contains(&long_names) {
+        return Err(NameMixError::InvalidRatio(long_names.to_string()).into());
+    }
+    Ok((name_mix, long_names))
+}
+
 fn generate_id(
     region: Option<&str>,
-    birth: Option<NaiveDate>,
     min: NaiveDate,
     max: NaiveDate,
     gender: Gender,
+    name_mix: &NameMix,
+    long_names: f64,
 ) -> Result<IdRecord, IdError> {
     let code6 = match region {
         Some(r) => {
@@ -465,29 +671,31 @@ fn generate_id(
         None => random_area(),
     };
     let address = get_full_area_info_str(code6.as_str()).unwrap_or_else(|| "地址未知".to_string());
-    let birthday = birth.unwrap_or_else(|| random_date(min, max));
+    let birthday = random_date(min, max);
     let seq3 = random_seq(gender);
     let id17 = format!("{}{}{}", code6, birthday.format("%Y%m%d"), seq3);
     let check = checksum_char(&id17);
-    let name: String = Name(ZH_CN).fake();
+    let male = seq3
+        .chars()
+        .last()
+        .and_then(|c| c.to_digit(10))
+        .unwrap_or(0)
+        % 2
+        == 1;
+    let style = name_mix.pick();
+    let long = rng().random_bool(long_names);
+    let name: String = if style == NameStyle::Han && !long {
+        Name(ZH_CN).fake()
+    } else {
+        names::generate(style, male, None, long).full
+    };
 
     Ok(IdRecord {
         name,
         id_number: format!("{}{}", id17, check),
         region: code6,
         birthday: birthday.format("%Y-%m-%d").to_string(),
-        gender: if seq3
-            .chars()
-            .last()
-            .and_then(|c| c.to_digit(10))
-            .unwrap_or(0)
-            % 2
-            == 0
-        {
-            "female".to_string()
-        } else {
-            "male".to_string()
-        },
+        gender: if male { "male" } else { "female" }.to_string(),
         address,
     })
 }
@@ -518,6 +726,42 @@ fn parse_date(s: &str) -> Result<NaiveDate, IdError> {
     Err(IdError::InvalidDate(s.to_string()))
 }
 
+// 解析出生日期，只给年份或年月时返回该年/该月的首尾日期作为随机范围
+fn parse_birth_spec(s: &str) -> Result<(NaiveDate, NaiveDate), IdError> {
+    let value = s.trim();
+    let digits: String = value.chars().filter(|c| *c != '-').collect();
+    let parts = value.split('-').count();
+    let invalid = || IdError::InvalidDate(s.to_string());
+    if !digits.chars().all(|c| c.is_ascii_digit()) {
+        return Err(invalid());
+    }
+
+    match (digits.len(), parts) {
+        (4, 1) => {
+            let year = digits.parse().map_err(|_| invalid())?;
+            let first = NaiveDate::from_ymd_opt(year, 1, 1).ok_or_else(invalid)?;
+            let last = NaiveDate::from_ymd_opt(year, 12, 31).ok_or_else(invalid)?;
+            Ok((first, last))
+        }
+        (6, 1) | (6, 2) if parts == 1 || value.find('-') == Some(4) => {
+            let year = digits[..4].parse().map_err(|_| invalid())?;
+            let month = digits[4..].parse().map_err(|_| invalid())?;
+            let first = NaiveDate::from_ymd_opt(year, month, 1).ok_or_else(invalid)?;
+            let next_month = if month == 12 {
+                NaiveDate::from_ymd_opt(year + 1, 1, 1)
+            } else {
+                NaiveDate::from_ymd_opt(year, month + 1, 1)
+            }
+            .ok_or_else(invalid)?;
+            Ok((first, next_month - chrono::Duration::days(1)))
+        }
+        _ => {
+            let date = parse_date(value)?;
+            Ok((date, date))
+        }
+    }
+}
+
 fn random_seq(gender: Gender) -> String {
     let mut rng = rng();
     let mut n = rng.random_range(0..=999);
@@ -544,17 +788,6 @@ fn random_date(min: NaiveDate, max: NaiveDate) -> NaiveDate {
     min + chrono::Duration::days(offset)
 }
 
-fn checksum_char(id17: &str) -> char {
-    let weights = [7, 9, 10, 5, 8, 4, 2, 1, 6, 3, 7, 9, 10, 5, 8, 4, 2];
-    let mapping = ['1', '0', 'X', '9', '8', '7', '6', '5', '4', '3', '2'];
-    let sum: i32 = id17
-        .chars()
-        .zip(weights.iter())
-        .map(|(c, w)| (c.to_digit(10).unwrap_or(0) as i32) * (*w as i32))
-        .sum();
-    mapping[(sum % 11) as usize]
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -614,4 +847,33 @@ mod tests {
         assert_eq!(d1.year(), 1900);
         assert_eq!(d2.year(), 2099);
     }
+
+    #[test]
+    fn test_parse_birth_spec() {
+        let ymd = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+
+        assert_eq!(
+            parse_birth_spec("1995").unwrap(),
+            (ymd(1995, 1, 1), ymd(1995, 12, 31))
+        );
+        assert_eq!(
+            parse_birth_spec("1995-06").unwrap(),
+            (ymd(1995, 6, 1), ymd(1995, 6, 30))
+        );
+        assert_eq!(
+            parse_birth_spec("200002").unwrap(),
+            (ymd(2000, 2, 1), ymd(2000, 2, 29))
+        );
+        assert_eq!(
+            parse_birth_spec("1995-12").unwrap(),
+            (ymd(1995, 12, 1), ymd(1995, 12, 31))
+        );
+        assert_eq!(
+            parse_birth_spec("1995-06-15").unwrap(),
+            (ymd(1995, 6, 15), ymd(1995, 6, 15))
+        );
+        assert!(parse_birth_spec("1995-13").is_err());
+        assert!(parse_birth_spec("19-9506").is_err());
+        assert!(parse_birth_spec("abcd").is_err());
+    }
 }
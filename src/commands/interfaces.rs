@@ -0,0 +1,294 @@
+use std::io;
+use std::net::IpAddr;
+
+use serde::Serialize;
+
+use crate::commands::mac::MacAddr;
+use crate::utils::a11y;
+use crate::utils::netif::{self, Gateway, Interface};
+use crate::utils::output::RecordOutput;
+
+#[derive(clap::Args)]
+pub struct InterfacesOpts {
+    #[arg(
+        value_name = "NAME",
+        help = "只显示这些网卡，例如 eth0、en0；指定后停用和回环网卡也会显示"
+    )]
+    names: Vec<String>,
+
+    #[arg(short = 'a', long, help = "同时显示未启用的网卡和回环网卡")]
+    all: bool,
+
+    #[arg(
+        short = '4',
+        long = "ipv4",
+        conflicts_with = "ipv6",
+        help = "只显示 IPv4 地址"
+    )]
+    ipv4: bool,
+
+    #[arg(short = '6', long = "ipv6", help = "只显示 IPv6 地址")]
+    ipv6: bool,
+
+    #[arg(long, help = "以 JSON 输出结果")]
+    json: bool,
+
+    // 全局 --where / --fields / --rename，由命令行入口注入
+    #[arg(skip)]
+    pub(crate) record_output: RecordOutput,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum InterfacesError {
+    #[error("no interface named '{0}'")]
+    NotFound(String),
+    #[error("cannot list network interfaces: {0}")]
+    Io(#[from] io::Error),
+    #[error("json serialize failed: {0}")]
+    Serialize(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Serialize)]
+pub struct InterfaceRecord {
+    pub name: String,
+    pub up: bool,
+    pub loopback: bool,
+    pub mtu: Option<u32>,
+    pub mac: Option<String>,
+    // 带前缀长度，例如 192.168.1.23/24
+    pub addresses: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GatewayRecord {
+    pub address: IpAddr,
+    pub interface: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct InterfacesReport {
+    pub interfaces: Vec<InterfaceRecord>,
+    pub gateways: Vec<GatewayRecord>,
+    pub dns_servers: Vec<IpAddr>,
+    // 访问公网时系统选用的源地址，即通常所说的“本机局域网 IP”
+    pub outbound: Vec<IpAddr>,
+}
+
+pub fn run_interfaces(opts: InterfacesOpts) -> Result<(), InterfacesError> {
+    let mut list = netif::interfaces()?;
+    for name in &opts.names {
+        if !list.iter().any(|iface| &iface.name == name) {
+            return Err(InterfacesError::NotFound(name.clone()));
+        }
+    }
+    list.sort_by_key(|iface| (iface.loopback, !iface.up));
+    let keep_ip = |ip: &IpAddr| match (opts.ipv4, opts.ipv6) {
+        (true, _) => ip.is_ipv4(),
+        (_, true) => ip.is_ipv6(),
+        _ => true,
+    };
+    let mut report = build_report(
+        list,
+        netif::default_gateways(),
+        netif::dns_servers(),
+        [netif::outbound_addr(false), netif::outbound_addr(true)]
+            .into_iter()
+            .flatten()
+            .collect(),
+        |iface| {
+            if opts.names.is_empty() {
+                opts.all || (iface.up && !iface.loopback)
+            } else {
+                opts.names.contains(&iface.name)
+            }
+        },
+        keep_ip,
+    );
+    opts.record_output.retain(&mut report.interfaces)?;
+
+    if opts.json {
+        opts.record_output
+            .write_report(&report, Some("interfaces"), io::stdout().lock())?;
+        return Ok(());
+    }
+    print_report(&report);
+    Ok(())
+}
+
+fn build_report(
+    list: Vec<Interface>,
+    gateways: Vec<Gateway>,
+    dns_servers: Vec<IpAddr>,
+    outbound: Vec<IpAddr>,
+    keep_iface: impl Fn(&Interface) -> bool,
+    keep_ip: impl Fn(&IpAddr) -> bool,
+) -> InterfacesReport {
+    let interfaces: Vec<InterfaceRecord> = list
+        .into_iter()
+        .filter(|iface| keep_iface(iface))
+        .map(|iface| InterfaceRecord {
+            addresses: iface
+                .addrs
+                .iter()
+                .filter(|net| keep_ip(&net.addr()))
+                .map(|net| net.to_string())
+                .collect(),
+            mac: iface.mac.map(|mac| MacAddr(mac).to_string()),
+            name: iface.name,
+            up: iface.up,
+            loopback: iface.loopback,
+            mtu: iface.mtu,
+        })
+        .collect();
+    // 指定了网卡时网关也只保留这些网卡上的
+    let shown = |name: &Option<String>| {
+        name.as_ref()
+            .is_none_or(|name| interfaces.iter().any(|iface| &iface.name == name))
+    };
+    let gateways = gateways
+        .into_iter()
+        .filter(|gateway| keep_ip(&gateway.addr) && shown(&gateway.interface))
+        .map(|gateway| GatewayRecord {
+            address: gateway.addr,
+            interface: gateway.interface,
+        })
+        .collect();
+    InterfacesReport {
+        gateways,
+        dns_servers: dns_servers.into_iter().filter(|ip| keep_ip(ip)).collect(),
+        outbound: outbound.into_iter().filter(|ip| keep_ip(ip)).collect(),
+        interfaces,
+    }
+}
+
+fn print_report(report: &InterfacesReport) {
+    let state = |iface: &InterfaceRecord| if iface.up { "up" } else { "down" };
+    let mtu = |iface: &InterfaceRecord| iface.mtu.map(|mtu| mtu.to_string()).unwrap_or_default();
+    let mac = |iface: &InterfaceRecord| iface.mac.clone().unwrap_or_default();
+    if a11y::is_enabled() {
+        for iface in &report.interfaces {
+            let addresses = iface.addresses.join(", ");
+            println!(
+                "{}",
+                a11y::line(&[
+                    ("interface", &iface.name),
+                    ("state", &state(iface)),
+                    ("MTU", &mtu(iface)),
+                    ("MAC", &mac(iface)),
+                    ("addresses", &addresses),
+                ])
+            );
+        }
+    } else {
+        let name_width = report
+            .interfaces
+            .iter()
+            .map(|iface| iface.name.len())
+            .max()
+            .unwrap_or(9)
+            .max(9);
+        println!(
+            "{:<name_width$}  {:<5}  {:<5}  {:<17}  ADDRESS",
+            "INTERFACE", "STATE", "MTU", "MAC"
+        );
+        for iface in &report.interfaces {
+            let mut addresses = iface.addresses.iter();
+            println!(
+                "{:<name_width$}  {:<5}  {:<5}  {:<17}  {}",
+                iface.name,
+                state(iface),
+                mtu(iface),
+                mac(iface),
+                addresses.next().map(String::as_str).unwrap_or("-")
+            );
+            for address in addresses {
+                println!("{:<width$}  {}", "", address, width = name_width + 33);
+            }
+        }
+        println!();
+    }
+
+    let join = |ips: &[IpAddr]| {
+        if ips.is_empty() {
+            "(none)".to_string()
+        } else {
+            ips.iter()
+                .map(IpAddr::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        }
+    };
+    let gateways = if report.gateways.is_empty() {
+        "(none)".to_string()
+    } else {
+        report
+            .gateways
+            .iter()
+            .map(|gateway| match &gateway.interface {
+                Some(name) => format!("{} ({})", gateway.address, name),
+                None => gateway.address.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    println!("Default gateway: {}", gateways);
+    println!("DNS servers:     {}", join(&report.dns_servers));
+    println!("Outbound source: {}", join(&report.outbound));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_report_filters() {
+        let list = vec![
+            Interface {
+                name: "lo".into(),
+                up: true,
+                loopback: true,
+                mtu: Some(65536),
+                mac: None,
+                addrs: vec!["127.0.0.1/8".parse().unwrap()],
+            },
+            Interface {
+                name: "eth0".into(),
+                up: true,
+                loopback: false,
+                mtu: Some(1500),
+                mac: Some([0x52, 0x54, 0x00, 0x12, 0x34, 0x56]),
+                addrs: vec![
+                    "192.168.1.23/24".parse().unwrap(),
+                    "fe80::5054:ff:fe12:3456/64".parse().unwrap(),
+                ],
+            },
+        ];
+        let gateways = vec![
+            Gateway {
+                addr: "192.168.1.1".parse().unwrap(),
+                interface: Some("eth0".into()),
+            },
+            Gateway {
+                addr: "10.8.0.1".parse().unwrap(),
+                interface: Some("tun0".into()),
+            },
+        ];
+        let report = build_report(
+            list,
+            gateways,
+            vec!["192.168.1.1".parse().unwrap(), "fe80::1".parse().unwrap()],
+            Vec::new(),
+            |iface| !iface.loopback,
+            |ip| ip.is_ipv4(),
+        );
+        assert_eq!(report.interfaces.len(), 1);
+        let eth0 = &report.interfaces[0];
+        assert_eq!(eth0.mac.as_deref(), Some("52:54:00:12:34:56"));
+        assert_eq!(eth0.addresses, vec!["192.168.1.23/24"]);
+        assert_eq!(report.gateways.len(), 1);
+        assert_eq!(
+            report.dns_servers,
+            vec!["192.168.1.1".parse::<IpAddr>().unwrap()]
+        );
+    }
+}
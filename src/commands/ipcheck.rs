@@ -0,0 +1,560 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::Path;
+use std::sync::Arc;
+
+use futures::stream::{FuturesUnordered, StreamExt};
+use hickory_resolver::TokioResolver;
+use ipnet::IpNet;
+use serde::Serialize;
+use tokio::sync::Semaphore;
+use tokio::time::{timeout, Duration};
+
+use crate::utils::a11y;
+use crate::utils::iplist;
+use crate::utils::network::{self, NetworkError};
+use crate::utils::output::RecordOutput;
+
+// `--lists dnsbl` 展开的默认 DNSBL 区域
+const DEFAULT_DNSBL_ZONES: &[&str] = &[
+    "zen.spamhaus.org",
+    "bl.spamcop.net",
+    "b.barracudacentral.org",
+];
+
+#[derive(clap::Args)]
+pub struct IpCheckOpts {
+    #[arg(
+        value_name = "IP",
+        help = "要检查的 IP，省略或为 - 时从 stdin 逐行读取"
+    )]
+    ips: Vec<String>,
+
+    #[arg(
+        short = 'l',
+        long,
+        value_delimiter = ',',
+        default_value = "dnsbl",
+        value_name = "LIST",
+        help = "黑名单列表，逗号分隔：本地 IP/CIDR 文件路径、dnsbl（内置区域）或 dnsbl:<zone>"
+    )]
+    lists: Vec<String>,
+
+    #[arg(
+        long = "timeout",
+        default_value_t = 3000,
+        value_name = "MS",
+        help = "单次 DNSBL 查询超时时间(毫秒)"
+    )]
+    time_out: u64,
+
+    #[arg(
+        short = 'c',
+        long,
+        default_value_t = 32,
+        value_name = "N",
+        help = "DNSBL 查询并发数"
+    )]
+    concurrency: usize,
+
+    #[arg(long, help = "只输出命中黑名单的 IP")]
+    listed_only: bool,
+
+    #[arg(long, help = "以 JSON 输出结果")]
+    json: bool,
+
+    // 全局 --where / --fields / --rename，由命令行入口注入
+    #[arg(skip)]
+    pub(crate) record_output: RecordOutput,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum IpCheckError {
+    #[error("invalid ip address: {0}")]
+    InvalidIp(String),
+    #[error("{path}:{line}: invalid ip or cidr '{value}'")]
+    InvalidListEntry {
+        path: String,
+        line: usize,
+        value: String,
+    },
+    #[error("no ip addresses to check")]
+    NoInput,
+    #[error("tokio runtime error: {0}")]
+    RuntimeError(String),
+    #[error("cannot load system resolver config: {0}")]
+    Resolver(String),
+    #[error(transparent)]
+    Network(#[from] NetworkError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("json serialize failed: {0}")]
+    Serialize(#[from] serde_json::Error),
+}
+
+// 用户提供的本地黑名单
+pub struct LocalList {
+    pub name: String,
+    pub networks: Vec<IpNet>,
+}
+
+impl LocalList {
+    pub fn load(path: &Path) -> Result<Self, IpCheckError> {
+        let name = path.display().to_string();
+        let reader = BufReader::new(File::open(path)?);
+        let mut networks = Vec::new();
+        for (index, line) in reader.lines().enumerate() {
+            let line = line?;
+            // 支持 # 注释和行尾说明
+            let value = line.split('#').next().unwrap_or("").trim();
+            let Some(value) = value.split_whitespace().next() else {
+                continue;
+            };
+            let network = parse_network(value).ok_or_else(|| IpCheckError::InvalidListEntry {
+                path: name.clone(),
+                line: index + 1,
+                value: value.to_string(),
+            })?;
+            networks.push(network);
+        }
+        Ok(Self { name, networks })
+    }
+
+    pub fn find(&self, ip: IpAddr) -> Option<&IpNet> {
+        self.networks.iter().find(|network| network.contains(&ip))
+    }
+}
+
+fn parse_network(value: &str) -> Option<IpNet> {
+    value
+        .parse::<IpNet>()
+        .ok()
+        .or_else(|| value.parse::<IpAddr>().ok().map(IpNet::from))
+}
+
+enum ListSource {
+    Local(LocalList),
+    Dnsbl(String),
+}
+
+impl ListSource {
+    fn name(&self) -> &str {
+        match self {
+            ListSource::Local(list) => &list.name,
+            ListSource::Dnsbl(zone) => zone,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ListMatch {
+    pub list: String,
+    pub detail: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct IpCheckResult {
+    pub ip: String,
+    pub listed: bool,
+    pub matches: Vec<ListMatch>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<ListMatch>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct IpCheckSummary {
+    pub checked: usize,
+    pub listed: usize,
+    pub matches_by_list: BTreeMap<String, usize>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unavailable_lists: Vec<String>,
+    pub results: Vec<IpCheckResult>,
+}
+
+pub fn run_ip_check(opts: IpCheckOpts) -> Result<(), IpCheckError> {
+    let ips = iplist::collect_ips(&opts.ips, IpCheckError::InvalidIp)?;
+    if ips.is_empty() {
+        return Err(IpCheckError::NoInput);
+    }
+
+    let mut sources = Vec::new();
+    for entry in opts
+        .lists
+        .iter()
+        .map(|entry| entry.trim())
+        .filter(|e| !e.is_empty())
+    {
+        if entry.eq_ignore_ascii_case("dnsbl") {
+            sources.extend(
+                DEFAULT_DNSBL_ZONES
+                    .iter()
+                    .map(|zone| ListSource::Dnsbl(zone.to_string())),
+            );
+        } else if let Some(zone) = entry.strip_prefix("dnsbl:") {
+            sources.push(ListSource::Dnsbl(zone.trim_matches('.').to_string()));
+        } else {
+            sources.push(ListSource::Local(LocalList::load(Path::new(entry))?));
+        }
+    }
+
+    // 离线时 DNSBL 查询作为可选项跳过，只剩 DNSBL 可查时直接报错
+    if network::is_offline() {
+        let local = sources.iter().any(|s| matches!(s, ListSource::Local(_)));
+        if let Some(ListSource::Dnsbl(zone)) =
+            sources.iter().find(|s| matches!(s, ListSource::Dnsbl(_)))
+        {
+            if !local {
+                network::require("DNSBL lookup", zone)?;
+            }
+            network::skip("DNSBL lookups");
+            sources.retain(|s| matches!(s, ListSource::Local(_)));
+        }
+    }
+
+    let rt =
+        tokio::runtime::Runtime::new().map_err(|e| IpCheckError::RuntimeError(e.to_string()))?;
+    let mut summary = rt.block_on(check_ips(
+        ips,
+        sources,
+        opts.concurrency.clamp(1, 256),
+        opts.time_out,
+    ))?;
+    opts.record_output.retain(&mut summary.results)?;
+
+    if opts.json {
+        if opts.listed_only {
+            summary.results.retain(|result| result.listed);
+        }
+        opts.record_output
+            .write_report(&summary, Some("results"), io::stdout().lock())?;
+        return Ok(());
+    }
+
+    for result in &summary.results {
+        if opts.listed_only && !result.listed {
+            continue;
+        }
+        let status = if result.listed { "LISTED" } else { "clean" };
+        let detail: Vec<String> = result
+            .matches
+            .iter()
+            .map(|m| format!("{}({})", m.list, m.detail))
+            .collect();
+        if a11y::is_enabled() {
+            let status = if result.listed { "listed" } else { "clean" };
+            println!(
+                "{}",
+                a11y::line(&[
+                    ("IP", &result.ip),
+                    ("status", &status),
+                    ("matches", &detail.join("; ")),
+                ])
+            );
+        } else {
+            println!("{:<40} {:<7} {}", result.ip, status, detail.join(", "));
+        }
+        for error in &result.errors {
+            eprintln!("  warning: {} lookup failed: {}", error.list, error.detail);
+        }
+    }
+    println!();
+    println!(
+        "Checked {} IPs, {} listed.",
+        summary.checked, summary.listed
+    );
+    for (list, count) in &summary.matches_by_list {
+        if a11y::is_enabled() {
+            println!("  {}", a11y::line(&[("list", list), ("listed", count)]));
+        } else {
+            println!("  {:<32} {}", list, count);
+        }
+    }
+    for zone in &summary.unavailable_lists {
+        eprintln!(
+            "warning: {} did not answer its 127.0.0.2 test entry, skipped (resolver blocked or zone down)",
+            zone
+        );
+    }
+    Ok(())
+}
+
+async fn check_ips(
+    ips: Vec<IpAddr>,
+    sources: Vec<ListSource>,
+    concurrency: usize,
+    timeout_ms: u64,
+) -> Result<IpCheckSummary, IpCheckError> {
+    let resolver = if sources
+        .iter()
+        .any(|source| matches!(source, ListSource::Dnsbl(_)))
+    {
+        let builder =
+            TokioResolver::builder_tokio().map_err(|e| IpCheckError::Resolver(e.to_string()))?;
+        Some(builder.build())
+    } else {
+        None
+    };
+    let mut results: Vec<IpCheckResult> = ips
+        .iter()
+        .map(|ip| IpCheckResult {
+            ip: ip.to_string(),
+            listed: false,
+            matches: Vec::new(),
+            errors: Vec::new(),
+        })
+        .collect();
+
+    // RFC 5782 要求 DNSBL 必须列入 127.0.0.2；查不到说明区域不可用或本地 DNS 被拦截，
+    // 此时所有查询都会得到 NXDOMAIN，不能当作“未列入”
+    let mut unavailable_lists = Vec::new();
+    for source in &sources {
+        if let (ListSource::Dnsbl(zone), Some(resolver)) = (source, &resolver) {
+            let probe = dnsbl_query(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2)), zone);
+            if !matches!(
+                lookup_dnsbl(resolver, &probe, timeout_ms).await,
+                DnsblOutcome::Listed(_)
+            ) {
+                unavailable_lists.push(zone.clone());
+            }
+        }
+    }
+
+    let sem = Arc::new(Semaphore::new(concurrency));
+    let mut tasks = FuturesUnordered::new();
+    for (index, ip) in ips.iter().enumerate() {
+        for source in &sources {
+            match source {
+                ListSource::Local(list) => {
+                    if let Some(network) = list.find(*ip) {
+                        results[index].matches.push(ListMatch {
+                            list: list.name.clone(),
+                            detail: network.to_string(),
+                        });
+                    }
+                }
+                ListSource::Dnsbl(zone) if unavailable_lists.contains(zone) => {}
+                ListSource::Dnsbl(zone) => {
+                    let Some(resolver) = resolver.clone() else {
+                        continue;
+                    };
+                    let query = dnsbl_query(*ip, zone);
+                    let zone = zone.clone();
+                    let sem = sem.clone();
+                    tasks.push(async move {
+                        let _permit = sem.acquire_owned().await.ok();
+                        let outcome = lookup_dnsbl(&resolver, &query, timeout_ms).await;
+                        (index, zone, outcome)
+                    });
+                }
+            }
+        }
+    }
+
+    while let Some((index, zone, outcome)) = tasks.next().await {
+        match outcome {
+            DnsblOutcome::Listed(codes) => results[index].matches.push(ListMatch {
+                list: zone,
+                detail: codes.join(","),
+            }),
+            DnsblOutcome::NotListed => {}
+            DnsblOutcome::Error(detail) => {
+                results[index].errors.push(ListMatch { list: zone, detail })
+            }
+        }
+    }
+
+    // 保持 --lists 中的顺序
+    let order: Vec<&str> = sources.iter().map(ListSource::name).collect();
+    let mut matches_by_list = BTreeMap::new();
+    for result in &mut results {
+        result
+            .matches
+            .sort_by_key(|m| order.iter().position(|name| *name == m.list));
+        result.listed = !result.matches.is_empty();
+        for m in &result.matches {
+            *matches_by_list.entry(m.list.clone()).or_insert(0) += 1;
+        }
+    }
+
+    Ok(IpCheckSummary {
+        checked: results.len(),
+        listed: results.iter().filter(|r| r.listed).count(),
+        matches_by_list,
+        unavailable_lists,
+        results,
+    })
+}
+
+enum DnsblOutcome {
+    Listed(Vec<String>),
+    NotListed,
+    Error(String),
+}
+
+async fn lookup_dnsbl(resolver: &TokioResolver, query: &str, timeout_ms: u64) -> DnsblOutcome {
+    // 末尾加点按完整域名查询，避免拼接本机的 search 域
+    let lookup = resolver.ipv4_lookup(format!("{}.", query));
+    match timeout(Duration::from_millis(timeout_ms), lookup).await {
+        Err(_) => DnsblOutcome::Error("timeout".to_string()),
+        // 只有 NXDOMAIN 表示未列入；SERVFAIL、REFUSED 和网络错误都不能当作干净
+        Ok(Err(error)) if error.is_nx_domain() => DnsblOutcome::NotListed,
+        Ok(Err(error)) => DnsblOutcome::Error(error.to_string()),
+        Ok(Ok(answer)) => {
+            let mut codes: Vec<Ipv4Addr> = answer.iter().map(|record| record.0).collect();
+            codes.sort();
+            codes.dedup();
+            classify_dnsbl_codes(&codes)
+        }
+    }
+}
+
+// 127.0.0.0/8 内的应答才是有效命中；127.255.255.x 是 Spamhaus 等返回的查询错误（如公共 DNS 被拒绝）
+fn classify_dnsbl_codes(codes: &[Ipv4Addr]) -> DnsblOutcome {
+    if codes.is_empty() {
+        return DnsblOutcome::NotListed;
+    }
+    if let Some(code) = codes
+        .iter()
+        .find(|ip| ip.octets()[..3] == [127, 255, 255] || ip.octets()[0] != 127)
+    {
+        return DnsblOutcome::Error(format!("unexpected answer {}", code));
+    }
+    DnsblOutcome::Listed(codes.iter().map(|ip| ip.to_string()).collect())
+}
+
+// IPv4 按字节倒序，IPv6 按半字节倒序拼接到区域前
+pub fn dnsbl_query(ip: IpAddr, zone: &str) -> String {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, c, d] = ip.octets();
+            format!("{}.{}.{}.{}.{}", d, c, b, a, zone)
+        }
+        IpAddr::V6(ip) => {
+            let mut labels = Vec::with_capacity(33);
+            for byte in ip.octets().iter().rev() {
+                labels.push(format!("{:x}", byte & 0x0F));
+                labels.push(format!("{:x}", byte >> 4));
+            }
+            labels.push(zone.to_string());
+            labels.join(".")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_dnsbl_queries() {
+        assert_eq!(
+            dnsbl_query("1.2.3.4".parse().unwrap(), "zen.spamhaus.org"),
+            "4.3.2.1.zen.spamhaus.org"
+        );
+        let v6 = dnsbl_query("2001:db8::1".parse().unwrap(), "bl.example");
+        assert!(v6.starts_with("1.0.0.0.0.0.0.0"));
+        assert!(v6.ends_with("8.b.d.0.1.0.0.2.bl.example"));
+    }
+
+    #[test]
+    fn matches_local_cidr_lists() {
+        let list = LocalList {
+            name: "local.txt".into(),
+            networks: vec![
+                parse_network("10.0.0.0/8").unwrap(),
+                parse_network("192.0.2.7").unwrap(),
+                parse_network("2001:db8::/32").unwrap(),
+            ],
+        };
+        assert_eq!(
+            list.find("10.20.30.40".parse().unwrap())
+                .map(|n| n.to_string()),
+            Some("10.0.0.0/8".to_string())
+        );
+        assert!(list.find("192.0.2.7".parse().unwrap()).is_some());
+        assert!(list.find("192.0.2.8".parse().unwrap()).is_none());
+        assert!(list.find("2001:db8::42".parse().unwrap()).is_some());
+        assert!(parse_network("not-an-ip").is_none());
+    }
+
+    #[test]
+    fn classifies_dnsbl_answers() {
+        let ip = |s: &str| s.parse::<Ipv4Addr>().unwrap();
+        assert!(matches!(
+            classify_dnsbl_codes(&[ip("127.0.0.2"), ip("127.0.0.4")]),
+            DnsblOutcome::Listed(codes) if codes == ["127.0.0.2", "127.0.0.4"]
+        ));
+        assert!(matches!(
+            classify_dnsbl_codes(&[ip("127.255.255.254")]),
+            DnsblOutcome::Error(_)
+        ));
+        assert!(matches!(classify_dnsbl_codes(&[]), DnsblOutcome::NotListed));
+    }
+
+    // 极简 DNS 服务：名字含 nx 返回 NXDOMAIN，含 fail 返回 SERVFAIL，其余返回 127.0.0.2
+    async fn fake_dnsbl_server() -> u16 {
+        let socket = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let port = socket.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 512];
+            while let Ok((len, peer)) = socket.recv_from(&mut buf).await {
+                let query = &buf[..len];
+                let mut end = 12;
+                while query[end] != 0 {
+                    end += usize::from(query[end]) + 1;
+                }
+                let question = &query[12..end + 5];
+                let name = String::from_utf8_lossy(question);
+                let rcode = if name.contains("nx") {
+                    3
+                } else if name.contains("fail") {
+                    2
+                } else {
+                    0
+                };
+                let mut reply = query[..2].to_vec();
+                reply.extend_from_slice(&[0x81, 0x80 | rcode, 0, 1, 0, u8::from(rcode == 0)]);
+                reply.extend_from_slice(&[0, 0, 0, 0]);
+                reply.extend_from_slice(question);
+                if rcode == 0 {
+                    reply.extend_from_slice(&[0xC0, 0x0C, 0, 1, 0, 1, 0, 0, 0, 60, 0, 4]);
+                    reply.extend_from_slice(&[127, 0, 0, 2]);
+                }
+                let _ = socket.send_to(&reply, peer).await;
+            }
+        });
+        port
+    }
+
+    #[test]
+    fn only_nxdomain_counts_as_not_listed() {
+        use hickory_resolver::config::{NameServerConfigGroup, ResolverConfig};
+        use hickory_resolver::name_server::TokioConnectionProvider;
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let port = fake_dnsbl_server().await;
+            let servers =
+                NameServerConfigGroup::from_ips_clear(&[IpAddr::from([127, 0, 0, 1])], port, true);
+            let config = ResolverConfig::from_parts(None, vec![], servers);
+            let resolver =
+                TokioResolver::builder_with_config(config, TokioConnectionProvider::default())
+                    .build();
+
+            assert!(matches!(
+                lookup_dnsbl(&resolver, "hit.bl.test", 2000).await,
+                DnsblOutcome::Listed(codes) if codes == ["127.0.0.2"]
+            ));
+            assert!(matches!(
+                lookup_dnsbl(&resolver, "nx.bl.test", 2000).await,
+                DnsblOutcome::NotListed
+            ));
+            assert!(matches!(
+                lookup_dnsbl(&resolver, "fail.bl.test", 2000).await,
+                DnsblOutcome::Error(_)
+            ));
+        });
+    }
+}
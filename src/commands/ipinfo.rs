@@ -0,0 +1,491 @@
+use std::io;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use ipnet::IpNet;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::commands::geoip::{GeoDatabases, GeoIpError, GeoRecord};
+use crate::utils::http::{self, HttpError, Url};
+use crate::utils::network::{self, NetworkError};
+use crate::utils::output::RecordOutput;
+
+// 返回纯文本出口 IP 的服务，依次尝试，第一个成功的为准
+const EGRESS_SERVICES: [&str; 4] = [
+    "https://api.ipify.org",
+    "https://ifconfig.me/ip",
+    "https://icanhazip.com",
+    "https://checkip.amazonaws.com",
+];
+
+#[derive(Debug, Clone, Copy)]
+enum Provider {
+    Ipinfo,
+    Ipwhois,
+    IpApi,
+}
+
+// 在线归属地查询，依次尝试；ip-api.com 的免费接口只支持 HTTP
+const LOOKUP_PROVIDERS: [Provider; 3] = [Provider::Ipinfo, Provider::Ipwhois, Provider::IpApi];
+
+// 不会出现在公网上的地址段，private 为 true 的是可在内网自由使用的私有地址
+const SPECIAL_RANGES: [(&str, &str, bool); 25] = [
+    ("0.0.0.0/8", "this network (RFC 791)", false),
+    ("10.0.0.0/8", "private-use (RFC 1918)", true),
+    ("100.64.0.0/10", "CGNAT shared (RFC 6598)", true),
+    ("127.0.0.0/8", "loopback (RFC 1122)", false),
+    ("169.254.0.0/16", "link-local (RFC 3927)", false),
+    ("172.16.0.0/12", "private-use (RFC 1918)", true),
+    ("192.0.0.0/24", "IETF protocol (RFC 6890)", false),
+    ("192.0.2.0/24", "documentation TEST-NET-1 (RFC 5737)", false),
+    ("192.88.99.0/24", "6to4 relay (RFC 7526)", false),
+    ("192.168.0.0/16", "private-use (RFC 1918)", true),
+    ("198.18.0.0/15", "benchmarking (RFC 2544)", false),
+    (
+        "198.51.100.0/24",
+        "documentation TEST-NET-2 (RFC 5737)",
+        false,
+    ),
+    (
+        "203.0.113.0/24",
+        "documentation TEST-NET-3 (RFC 5737)",
+        false,
+    ),
+    ("224.0.0.0/4", "multicast (RFC 5771)", false),
+    ("240.0.0.0/4", "reserved (RFC 1112)", false),
+    ("::/128", "unspecified (RFC 4291)", false),
+    ("::1/128", "loopback (RFC 4291)", false),
+    ("::ffff:0:0/96", "IPv4-mapped (RFC 4291)", false),
+    ("64:ff9b:1::/48", "local NAT64 (RFC 8215)", true),
+    ("100::/64", "discard-only (RFC 6666)", false),
+    ("2001:db8::/32", "documentation (RFC 3849)", false),
+    ("fc00::/7", "unique local (RFC 4193)", true),
+    ("fe80::/10", "link-local (RFC 4291)", false),
+    ("fec0::/10", "site-local (RFC 3879)", false),
+    ("ff00::/8", "multicast (RFC 4291)", false),
+];
+
+#[derive(clap::Args)]
+pub struct IpInfoOpts {
+    #[arg(value_name = "IP", help = "要查询的 IP，省略时查询本机的出口公网 IP")]
+    ip: Option<String>,
+
+    #[arg(
+        short = 'd',
+        long = "db",
+        value_name = "MMDB",
+        help = "使用 MaxMind 离线库查询归属地和 ASN，可重复指定，不再访问在线服务"
+    )]
+    dbs: Vec<PathBuf>,
+
+    #[arg(
+        long,
+        default_value = "en",
+        value_name = "LANG",
+        requires = "dbs",
+        help = "离线库的地名语言，例如 en、zh-CN"
+    )]
+    lang: String,
+
+    #[arg(long = "no-lookup", help = "不查询归属地，只输出 IP 和地址类型")]
+    no_lookup: bool,
+
+    #[arg(long, help = "以 JSON 输出结果")]
+    json: bool,
+
+    #[arg(
+        long = "timeout",
+        default_value_t = 5000,
+        value_name = "MS",
+        help = "单个在线服务的请求超时时间(毫秒)"
+    )]
+    time_out: u64,
+
+    // 全局 --where / --fields / --rename，由命令行入口注入
+    #[arg(skip)]
+    pub(crate) record_output: RecordOutput,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum IpInfoError {
+    #[error("invalid ip address: {0}")]
+    InvalidIp(String),
+    #[error("cannot determine egress ip, all services failed:\n{0}")]
+    Egress(String),
+    #[error(transparent)]
+    GeoIp(#[from] GeoIpError),
+    #[error(transparent)]
+    Network(#[from] NetworkError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("json serialize failed: {0}")]
+    Serialize(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Serialize)]
+pub struct IpInfoReport {
+    #[serde(flatten)]
+    pub geo: GeoRecord,
+    pub version: u8,
+    pub private: bool,
+    pub bogon: bool,
+    // 命中的特殊地址段，例如 "10.0.0.0/8 private-use (RFC 1918)"
+    pub range: Option<String>,
+    pub timezone: Option<String>,
+    // 出口 IP 和归属地信息各自来自哪个服务（或离线库）
+    pub egress_source: Option<String>,
+    pub lookup_source: Option<String>,
+}
+
+pub fn run_ip_info(opts: IpInfoOpts) -> Result<(), IpInfoError> {
+    let timeout = Duration::from_millis(opts.time_out.max(1));
+    let rt = tokio::runtime::Runtime::new()?;
+    let (ip, egress_source) = match &opts.ip {
+        Some(raw) => {
+            let ip = raw
+                .trim()
+                .parse::<IpAddr>()
+                .map_err(|_| IpInfoError::InvalidIp(raw.clone()))?;
+            (ip, None)
+        }
+        None => {
+            network::require("ipinfo", "public egress ip services")?;
+            let (ip, service) = rt.block_on(egress_ip(timeout))?;
+            (ip, Some(service.to_string()))
+        }
+    };
+
+    let (range, private) = match special_range(ip) {
+        Some((network, name, private)) => (Some(format!("{} {}", network, name)), private),
+        None => (None, false),
+    };
+    let mut report = IpInfoReport {
+        geo: GeoRecord {
+            ip: ip.to_string(),
+            ..GeoRecord::default()
+        },
+        version: if ip.is_ipv4() { 4 } else { 6 },
+        private,
+        bogon: range.is_some(),
+        range,
+        timezone: None,
+        egress_source,
+        lookup_source: None,
+    };
+
+    // 私有和保留地址没有公网登记信息
+    if !opts.no_lookup && !report.bogon {
+        if !opts.dbs.is_empty() {
+            report.geo = GeoDatabases::open(&opts.dbs)?.lookup(ip, &opts.lang)?;
+            report.lookup_source = Some("maxmind".to_string());
+        } else if !network::skip("online ip geolocation lookup") {
+            match rt.block_on(lookup(ip, timeout)) {
+                Ok((provider, geo, timezone)) => {
+                    report.geo = geo;
+                    report.timezone = timezone;
+                    report.lookup_source = Some(provider.host().to_string());
+                }
+                Err(reasons) => {
+                    eprintln!("warning: geolocation lookup failed:\n{}", reasons)
+                }
+            }
+        }
+    }
+
+    if opts.record_output.keep(&report)? {
+        if opts.json {
+            opts.record_output
+                .write_report(&report, None, io::stdout().lock())?;
+        } else {
+            print_report(&report);
+        }
+    }
+    Ok(())
+}
+
+async fn fetch(url: &str, timeout: Duration) -> Result<String, String> {
+    let url = Url::parse(url).map_err(|err| err.to_string())?;
+    let response = tokio::time::timeout(timeout, http::get(&url, false))
+        .await
+        .map_err(|_| format!("timed out after {} ms", timeout.as_millis()))?
+        .map_err(|err: HttpError| err.to_string())?;
+    if response.status != 200 {
+        return Err(format!("HTTP {} {}", response.status, response.reason));
+    }
+    Ok(String::from_utf8_lossy(&response.body).into_owned())
+}
+
+async fn egress_ip(timeout: Duration) -> Result<(IpAddr, &'static str), IpInfoError> {
+    let mut failures = Vec::new();
+    for service in EGRESS_SERVICES {
+        match fetch(service, timeout).await {
+            Ok(body) => match body.trim().parse::<IpAddr>() {
+                Ok(ip) => return Ok((ip, service)),
+                Err(_) => failures.push(format!("  {}: unexpected response", service)),
+            },
+            Err(reason) => failures.push(format!("  {}: {}", service, reason)),
+        }
+    }
+    Err(IpInfoError::Egress(failures.join("\n")))
+}
+
+// 失败时返回每个服务的失败原因
+async fn lookup(
+    ip: IpAddr,
+    timeout: Duration,
+) -> Result<(Provider, GeoRecord, Option<String>), String> {
+    let mut failures = Vec::new();
+    for provider in LOOKUP_PROVIDERS {
+        let result = fetch(&provider.url(ip), timeout).await.and_then(|body| {
+            let value: Value =
+                serde_json::from_str(&body).map_err(|_| "response is not JSON".to_string())?;
+            provider.parse(ip, &value)
+        });
+        match result {
+            Ok((geo, timezone)) => return Ok((provider, geo, timezone)),
+            Err(reason) => failures.push(format!("  {}: {}", provider.host(), reason)),
+        }
+    }
+    Err(failures.join("\n"))
+}
+
+impl Provider {
+    fn host(self) -> &'static str {
+        match self {
+            Provider::Ipinfo => "ipinfo.io",
+            Provider::Ipwhois => "ipwho.is",
+            Provider::IpApi => "ip-api.com",
+        }
+    }
+
+    fn url(self, ip: IpAddr) -> String {
+        match self {
+            Provider::Ipinfo => format!("https://ipinfo.io/{}/json", ip),
+            Provider::Ipwhois => format!("https://ipwho.is/{}", ip),
+            Provider::IpApi => format!(
+                "http://ip-api.com/json/{}?fields=status,message,country,countryCode,regionName,city,lat,lon,timezone,as",
+                ip
+            ),
+        }
+    }
+
+    // 各服务的字段名不同，统一成 GeoRecord 和时区
+    fn parse(self, ip: IpAddr, value: &Value) -> Result<(GeoRecord, Option<String>), String> {
+        let text = |value: &Value, key: &str| {
+            value[key]
+                .as_str()
+                .filter(|text| !text.is_empty())
+                .map(str::to_string)
+        };
+        let mut geo = GeoRecord {
+            ip: ip.to_string(),
+            ..GeoRecord::default()
+        };
+        let timezone;
+        match self {
+            Provider::Ipinfo => {
+                if let Some(message) = value["error"]["message"].as_str() {
+                    return Err(message.to_string());
+                }
+                geo.country_code = text(value, "country");
+                geo.region = text(value, "region");
+                geo.city = text(value, "city");
+                if let Some((lat, lon)) = value["loc"].as_str().and_then(|loc| loc.split_once(','))
+                {
+                    geo.latitude = lat.trim().parse().ok();
+                    geo.longitude = lon.trim().parse().ok();
+                }
+                // org 形如 "AS15169 Google LLC"
+                if let Some(org) = text(value, "org") {
+                    (geo.asn, geo.as_org) = split_as(&org);
+                }
+                timezone = text(value, "timezone");
+            }
+            Provider::Ipwhois => {
+                if value["success"] == Value::Bool(false) {
+                    return Err(text(value, "message").unwrap_or_else(|| "lookup failed".into()));
+                }
+                geo.country_code = text(value, "country_code");
+                geo.country = text(value, "country");
+                geo.region = text(value, "region");
+                geo.city = text(value, "city");
+                geo.latitude = value["latitude"].as_f64();
+                geo.longitude = value["longitude"].as_f64();
+                geo.asn = value["connection"]["asn"]
+                    .as_u64()
+                    .and_then(|asn| u32::try_from(asn).ok());
+                geo.as_org = text(&value["connection"], "org");
+                timezone = text(&value["timezone"], "id");
+            }
+            Provider::IpApi => {
+                if value["status"].as_str() != Some("success") {
+                    return Err(text(value, "message").unwrap_or_else(|| "lookup failed".into()));
+                }
+                geo.country_code = text(value, "countryCode");
+                geo.country = text(value, "country");
+                geo.region = text(value, "regionName");
+                geo.city = text(value, "city");
+                geo.latitude = value["lat"].as_f64();
+                geo.longitude = value["lon"].as_f64();
+                if let Some(asn) = text(value, "as") {
+                    (geo.asn, geo.as_org) = split_as(&asn);
+                }
+                timezone = text(value, "timezone");
+            }
+        }
+        Ok((geo, timezone))
+    }
+}
+
+// "AS15169 Google LLC" -> (15169, "Google LLC")，没有 AS 前缀时整体作为组织名
+fn split_as(text: &str) -> (Option<u32>, Option<String>) {
+    let (first, rest) = text.split_once(' ').unwrap_or((text, ""));
+    match first.strip_prefix("AS").and_then(|asn| asn.parse().ok()) {
+        Some(asn) => (
+            Some(asn),
+            Some(rest.trim().to_string()).filter(|org| !org.is_empty()),
+        ),
+        None => (None, Some(text.trim().to_string())),
+    }
+}
+
+pub(crate) fn special_range(ip: IpAddr) -> Option<(IpNet, &'static str, bool)> {
+    // IPv4 映射地址按内嵌的 IPv4 地址归类
+    if let IpAddr::V6(v6) = ip {
+        if let Some(v4) = v6.to_ipv4_mapped() {
+            return special_range(IpAddr::V4(v4));
+        }
+    }
+    let found = SPECIAL_RANGES.iter().find_map(|(network, name, private)| {
+        let network: IpNet = network.parse().expect("valid special range");
+        network.contains(&ip).then_some((network, *name, *private))
+    });
+    if found.is_some() {
+        return found;
+    }
+    // 目前只有 2000::/3 分配为全球单播地址
+    match ip {
+        IpAddr::V6(v6) if v6.segments()[0] & 0xe000 != 0x2000 => {
+            let network: IpNet = "::/0".parse().expect("valid network");
+            Some((network, "outside global unicast 2000::/3", false))
+        }
+        _ => None,
+    }
+}
+
+fn print_report(report: &IpInfoReport) {
+    let geo = &report.geo;
+    let kind = match (&report.range, report.private) {
+        (None, _) => "public".to_string(),
+        (Some(range), true) => format!("private, {}", range),
+        (Some(range), false) => format!("bogon, {}", range),
+    };
+    let location = [&geo.city, &geo.region, &geo.country]
+        .into_iter()
+        .flatten()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .join(", ");
+    let location = match (&geo.country_code, location.is_empty()) {
+        (Some(code), true) => code.clone(),
+        (Some(code), false) if geo.country.is_none() => format!("{}, {}", location, code),
+        _ => location,
+    };
+    let asn = match (geo.asn, &geo.as_org) {
+        (Some(asn), Some(org)) => Some(format!("AS{} {}", asn, org)),
+        (Some(asn), None) => Some(format!("AS{}", asn)),
+        (None, org) => org.clone(),
+    };
+    let coordinates = geo
+        .latitude
+        .zip(geo.longitude)
+        .map(|(lat, lon)| format!("{}, {}", lat, lon));
+    let fields = [
+        ("IP", Some(format!("{} (IPv{})", geo.ip, report.version))),
+        ("Type", Some(kind)),
+        ("ASN", asn),
+        (
+            "Location",
+            Some(location).filter(|location| !location.is_empty()),
+        ),
+        ("Coordinates", coordinates),
+        ("Timezone", report.timezone.clone()),
+        ("Egress via", report.egress_source.clone()),
+        ("Lookup via", report.lookup_source.clone()),
+    ];
+    for (name, value) in fields {
+        if let Some(value) = value {
+            println!("{:<13}{}", format!("{}:", name), value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_special_range() {
+        let check = |ip: &str| {
+            special_range(ip.parse().unwrap())
+                .map(|(network, _, private)| (network.to_string(), private))
+        };
+        assert_eq!(check("10.1.2.3"), Some(("10.0.0.0/8".into(), true)));
+        assert_eq!(check("100.100.1.1"), Some(("100.64.0.0/10".into(), true)));
+        assert_eq!(check("192.0.2.7"), Some(("192.0.2.0/24".into(), false)));
+        assert_eq!(
+            check("::ffff:192.168.1.1"),
+            Some(("192.168.0.0/16".into(), true))
+        );
+        assert_eq!(check("fd00::1"), Some(("fc00::/7".into(), true)));
+        assert_eq!(check("2001:db8::1"), Some(("2001:db8::/32".into(), false)));
+        assert_eq!(check("4000::1"), Some(("::/0".into(), false)));
+        assert_eq!(check("8.8.8.8"), None);
+        assert_eq!(check("2606:4700:4700::1111"), None);
+    }
+
+    #[test]
+    fn test_parse_providers() {
+        let ip: IpAddr = "8.8.8.8".parse().unwrap();
+        let ipinfo = serde_json::json!({
+            "ip": "8.8.8.8", "city": "Mountain View", "region": "California", "country": "US",
+            "loc": "37.4056,-122.0775", "org": "AS15169 Google LLC", "timezone": "America/Los_Angeles"
+        });
+        let (geo, timezone) = Provider::Ipinfo.parse(ip, &ipinfo).unwrap();
+        assert_eq!(geo.asn, Some(15169));
+        assert_eq!(geo.as_org.as_deref(), Some("Google LLC"));
+        assert_eq!(geo.country_code.as_deref(), Some("US"));
+        assert_eq!(geo.latitude, Some(37.4056));
+        assert_eq!(timezone.as_deref(), Some("America/Los_Angeles"));
+
+        let ipwhois = serde_json::json!({
+            "success": true, "country": "United States", "country_code": "US",
+            "region": "California", "city": "Mountain View", "latitude": 37.4, "longitude": -122.1,
+            "connection": {"asn": 15169, "org": "Google LLC"}, "timezone": {"id": "America/Los_Angeles"}
+        });
+        let (geo, timezone) = Provider::Ipwhois.parse(ip, &ipwhois).unwrap();
+        assert_eq!(geo.asn, Some(15169));
+        assert_eq!(geo.country.as_deref(), Some("United States"));
+        assert_eq!(timezone.as_deref(), Some("America/Los_Angeles"));
+        let limited = serde_json::json!({"success": false, "message": "rate limited"});
+        assert_eq!(
+            Provider::Ipwhois.parse(ip, &limited).unwrap_err(),
+            "rate limited"
+        );
+
+        let ipapi = serde_json::json!({
+            "status": "success", "country": "United States", "countryCode": "US",
+            "regionName": "Virginia", "city": "Ashburn", "lat": 39.03, "lon": -77.5,
+            "timezone": "America/New_York", "as": "AS15169 Google LLC"
+        });
+        let (geo, _) = Provider::IpApi.parse(ip, &ipapi).unwrap();
+        assert_eq!(geo.region.as_deref(), Some("Virginia"));
+        assert_eq!(geo.asn, Some(15169));
+        assert_eq!(
+            split_as("Example Hosting"),
+            (None, Some("Example Hosting".into()))
+        );
+    }
+}
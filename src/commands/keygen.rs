@@ -0,0 +1,272 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use clap::Subcommand;
+use rcgen::KeyPair;
+
+use crate::utils::pki::{self, EcCurve, PkiError, Subject};
+
+#[derive(clap::Args)]
+pub struct KeygenOpts {
+    #[command(subcommand)]
+    command: KeygenCommand,
+}
+
+#[derive(Subcommand)]
+enum KeygenCommand {
+    #[command(about = "生成 RSA 私钥（PKCS#8 PEM）")]
+    Rsa(RsaOpts),
+    #[command(about = "生成 EC 私钥（PKCS#8 PEM），曲线 p256 | p384")]
+    Ec(EcOpts),
+    #[command(about = "生成证书签名请求（CSR），包含 CN 和 SAN")]
+    Csr(CertOpts),
+    #[command(about = "生成自签名证书，包含 CN 和 SAN")]
+    Cert(SelfSignedOpts),
+}
+
+#[derive(clap::Args)]
+struct KeyOutArgs {
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "私钥写入文件（权限 0600），默认输出到 stdout"
+    )]
+    out: Option<PathBuf>,
+
+    #[arg(
+        long = "pub-out",
+        value_name = "FILE",
+        help = "同时把公钥（SubjectPublicKeyInfo PEM）写入文件"
+    )]
+    pub_out: Option<PathBuf>,
+}
+
+#[derive(clap::Args)]
+struct RsaOpts {
+    #[arg(
+        value_name = "BITS",
+        default_value = "2048",
+        value_parser = ["2048", "3072", "4096"],
+        help = "密钥长度 2048 | 3072 | 4096"
+    )]
+    bits: String,
+
+    #[command(flatten)]
+    output: KeyOutArgs,
+}
+
+#[derive(clap::Args)]
+struct EcOpts {
+    #[arg(value_enum, value_name = "CURVE", default_value_t = EcCurve::P256, help = "曲线 p256 | p384")]
+    curve: EcCurve,
+
+    #[command(flatten)]
+    output: KeyOutArgs,
+}
+
+#[derive(clap::Args)]
+struct CertOpts {
+    #[arg(
+        short = 'k',
+        long,
+        value_name = "FILE",
+        help = "私钥 PEM 文件（PKCS#8 或 PKCS#1）；省略时生成新的 EC P-256 私钥"
+    )]
+    key: Option<PathBuf>,
+
+    #[arg(
+        long = "key-out",
+        value_name = "FILE",
+        conflicts_with = "key",
+        help = "新生成的私钥写入该文件，默认与结果一起输出到 stdout"
+    )]
+    key_out: Option<PathBuf>,
+
+    #[arg(long, value_name = "NAME", help = "主题 CN，默认取第一个 SAN")]
+    cn: Option<String>,
+
+    #[arg(
+        short = 'O',
+        long = "org",
+        value_name = "NAME",
+        help = "主题 O（组织名）"
+    )]
+    org: Option<String>,
+
+    #[arg(
+        long = "san",
+        value_name = "NAME",
+        value_delimiter = ',',
+        help = "主题备用名称，可重复或逗号分隔：域名（支持 *.example.com）、IP 地址或邮箱；默认为 CN"
+    )]
+    sans: Vec<String>,
+
+    #[arg(long, value_name = "FILE", help = "结果写入文件，默认输出到 stdout")]
+    out: Option<PathBuf>,
+}
+
+#[derive(clap::Args)]
+struct SelfSignedOpts {
+    #[command(flatten)]
+    cert: CertOpts,
+
+    #[arg(long, value_name = "N", default_value_t = 365, help = "有效天数")]
+    days: u32,
+
+    #[arg(
+        long,
+        help = "生成 CA 证书（basicConstraints CA:TRUE，可签发下级证书），默认生成服务端证书"
+    )]
+    ca: bool,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum KeygenError {
+    #[error(transparent)]
+    Pki(#[from] PkiError),
+    #[error("cannot read {path}: {source}")]
+    Open {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+pub fn run_keygen(opts: KeygenOpts) -> Result<(), KeygenError> {
+    match opts.command {
+        KeygenCommand::Rsa(opts) => {
+            let bits = opts.bits.parse().expect("validated by clap");
+            let key = pki::generate_rsa(bits)?;
+            write_key(&key, &opts.output)
+        }
+        KeygenCommand::Ec(opts) => {
+            let key = pki::generate_ec(opts.curve)?;
+            write_key(&key, &opts.output)
+        }
+        KeygenCommand::Csr(opts) => {
+            let key = opts.key_pair()?;
+            let csr = opts.subject().csr_pem(&key)?;
+            opts.write(&key, &csr)
+        }
+        KeygenCommand::Cert(opts) => {
+            let key = opts.cert.key_pair()?;
+            let cert = opts
+                .cert
+                .subject()
+                .self_signed_pem(&key, opts.days, opts.ca)?;
+            opts.cert.write(&key, &cert)
+        }
+    }
+}
+
+fn write_key(key: &KeyPair, output: &KeyOutArgs) -> Result<(), KeygenError> {
+    match &output.out {
+        Some(path) => {
+            pki::write_private_file(path, key.serialize_pem().as_bytes())?;
+            eprintln!("Wrote {}", path.display());
+        }
+        None => print!("{}", key.serialize_pem()),
+    }
+    if let Some(path) = &output.pub_out {
+        fs::write(path, key.public_key_pem())?;
+        eprintln!("Wrote {}", path.display());
+    }
+    Ok(())
+}
+
+impl CertOpts {
+    fn key_pair(&self) -> Result<KeyPair, KeygenError> {
+        match &self.key {
+            Some(path) => Ok(pki::load_key_pair(&read_text(path)?)?),
+            None => Ok(pki::generate_ec(EcCurve::P256)?),
+        }
+    }
+
+    fn subject(&self) -> Subject {
+        Subject {
+            common_name: self.cn.clone(),
+            organization: self.org.clone(),
+            sans: self.sans.clone(),
+        }
+    }
+
+    // 使用已有私钥时只输出结果；新生成的私钥写入 --key-out，否则放在结果前面一起输出
+    fn write(&self, key: &KeyPair, pem: &str) -> Result<(), KeygenError> {
+        let mut text = String::new();
+        if self.key.is_none() {
+            match &self.key_out {
+                Some(path) => {
+                    pki::write_private_file(path, key.serialize_pem().as_bytes())?;
+                    eprintln!("Wrote {}", path.display());
+                }
+                None => text.push_str(&key.serialize_pem()),
+            }
+        }
+        text.push_str(pem);
+        match &self.out {
+            Some(path) => {
+                if self.key.is_none() && self.key_out.is_none() {
+                    pki::write_private_file(path, text.as_bytes())?;
+                } else {
+                    fs::write(path, text)?;
+                }
+                eprintln!("Wrote {}", path.display());
+            }
+            None => print!("{}", text),
+        }
+        Ok(())
+    }
+}
+
+fn read_text(path: &Path) -> Result<String, KeygenError> {
+    fs::read_to_string(path).map_err(|source| KeygenError::Open {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated_key_is_reused_for_cert() {
+        let dir = std::env::temp_dir().join(format!("rtoolkit-keygen-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let key_path = dir.join("key.pem");
+        let cert_path = dir.join("cert.pem");
+        let opts = CertOpts {
+            key: None,
+            key_out: Some(key_path.clone()),
+            cn: None,
+            org: None,
+            sans: vec!["localhost".into(), "::1".into()],
+            out: Some(cert_path.clone()),
+        };
+        let key = opts.key_pair().unwrap();
+        let cert = opts.subject().self_signed_pem(&key, 7, false).unwrap();
+        opts.write(&key, &cert).unwrap();
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&key_path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        assert_eq!(fs::read_to_string(&cert_path).unwrap(), cert);
+        let reused = CertOpts {
+            key: Some(key_path),
+            key_out: None,
+            out: None,
+            ..opts
+        };
+        assert_eq!(
+            reused.key_pair().unwrap().public_key_pem(),
+            key.public_key_pem()
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,581 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Local, SecondsFormat};
+use clap::ValueEnum;
+use rand::seq::IndexedRandom;
+use rand::Rng;
+use serde::Serialize;
+
+use crate::commands::faker::{fake_value, FakeKind, FakeLocale};
+use crate::utils::duration::parse_duration;
+use crate::utils::metrics;
+use crate::utils::output::RecordOutput;
+
+#[derive(clap::Args)]
+pub struct LogGenOpts {
+    #[arg(
+        value_enum,
+        short = 'f',
+        long,
+        default_value_t = LogFormat::Nginx,
+        help = "日志格式 nginx（combined）| clf（Common Log Format）| json"
+    )]
+    format: LogFormat,
+
+    #[arg(
+        short = 'r',
+        long,
+        default_value = "10/s",
+        value_name = "RATE",
+        help = "平均速率，例如 1000/s、600/m，纯数字表示每秒"
+    )]
+    rate: String,
+
+    #[arg(
+        short = 'd',
+        long,
+        value_name = "DURATION",
+        help = "持续时间，例如 30s、2m、1h；与 -n 都不指定时一直运行到中断"
+    )]
+    duration: Option<String>,
+
+    #[arg(short = 'n', long, value_name = "N", help = "最多生成的日志条数")]
+    count: Option<u64>,
+
+    #[arg(
+        long,
+        default_value_t = 200,
+        value_name = "N",
+        help = "模拟的客户端数量，每个客户端固定 IP 和 UA"
+    )]
+    clients: usize,
+
+    #[arg(
+        long,
+        help = "不按速率等待，立即生成截至当前时间的历史日志，需配合 -d 或 -n"
+    )]
+    backfill: bool,
+
+    #[arg(
+        short = 'o',
+        long,
+        value_name = "FILE",
+        help = "输出文件，默认输出到 stdout"
+    )]
+    output: Option<PathBuf>,
+
+    // 全局 --where / --fields / --rename，由命令行入口注入
+    #[arg(skip)]
+    pub(crate) record_output: RecordOutput,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum LogFormat {
+    Nginx,
+    Clf,
+    Json,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum LogGenError {
+    #[error("invalid rate: {0}, expected e.g. 1000/s or 600/m")]
+    InvalidRate(String),
+    #[error("invalid duration: {0}, expected e.g. 30s, 2m or 1h")]
+    InvalidDuration(String),
+    #[error("clients must be at least 1")]
+    InvalidClients,
+    #[error("--backfill needs --duration or --count")]
+    UnboundedBackfill,
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("json serialize failed: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+// 解析速率，返回每秒条数
+pub fn parse_rate(s: &str) -> Result<f64, LogGenError> {
+    let (value, per_secs) = match s.trim().split_once('/') {
+        Some((value, unit)) => {
+            let per_secs = match unit.trim() {
+                "s" | "sec" => 1.0,
+                "m" | "min" => 60.0,
+                "h" => 3600.0,
+                _ => return Err(LogGenError::InvalidRate(s.into())),
+            };
+            (value, per_secs)
+        }
+        None => (s, 1.0),
+    };
+    match value.trim().parse::<f64>() {
+        Ok(value) if value > 0.0 && value.is_finite() => Ok(value / per_secs),
+        _ => Err(LogGenError::InvalidRate(s.into())),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PathKind {
+    Page,
+    Api,
+    Asset,
+    Probe,
+}
+
+struct Route {
+    method: &'static str,
+    path: &'static str,
+    kind: PathKind,
+    weight: u32,
+}
+
+const fn route(method: &'static str, path: &'static str, kind: PathKind, weight: u32) -> Route {
+    Route {
+        method,
+        path,
+        kind,
+        weight,
+    }
+}
+
+// 客户端主动发起的请求；{id}、{page}、{word}、{slug} 在生成时替换
+const ROUTES: &[Route] = &[
+    route("GET", "/", PathKind::Page, 300),
+    route("GET", "/products", PathKind::Page, 150),
+    route("GET", "/products/{id}", PathKind::Page, 200),
+    route("GET", "/search?q={word}", PathKind::Page, 80),
+    route("GET", "/blog/{slug}", PathKind::Page, 60),
+    route("GET", "/cart", PathKind::Page, 50),
+    route("GET", "/login", PathKind::Page, 40),
+    route("GET", "/about", PathKind::Page, 20),
+    route("GET", "/admin", PathKind::Page, 5),
+    route("GET", "/api/v1/products?page={page}", PathKind::Api, 100),
+    route("GET", "/api/v1/users/{id}", PathKind::Api, 60),
+    route("POST", "/api/v1/login", PathKind::Api, 30),
+    route("POST", "/api/v1/orders", PathKind::Api, 20),
+    route("GET", "/healthz", PathKind::Api, 30),
+    route("GET", "/wp-login.php", PathKind::Probe, 6),
+    route("GET", "/.env", PathKind::Probe, 4),
+    route("GET", "/phpmyadmin/index.php", PathKind::Probe, 3),
+];
+
+// 页面加载后紧跟着请求的静态资源
+const ASSETS: &[&str] = &[
+    "/static/css/app.3f9a1c.css",
+    "/static/js/app.8d2e7b.js",
+    "/static/js/vendor.c41f09.js",
+    "/static/img/logo.png",
+    "/static/img/products/{id}.jpg",
+    "/favicon.ico",
+];
+
+const WORDS: &[&str] = &[
+    "phone", "laptop", "shoes", "coffee", "keyboard", "monitor", "backpack", "tea",
+];
+
+const BOT_AGENTS: &[&str] = &[
+    "Mozilla/5.0 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)",
+    "Mozilla/5.0 (compatible; bingbot/2.0; +http://www.bing.com/bingbot.htm)",
+    "curl/8.5.0",
+    "python-requests/2.31.0",
+    "Go-http-client/1.1",
+];
+
+// 一个页面平均带出的静态资源请求数
+const MEAN_ASSETS_PER_PAGE: f64 = 2.5;
+
+struct Client {
+    ip: String,
+    user_agent: String,
+    protocol: &'static str,
+    last_page: Option<String>,
+    pending_assets: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LogEntry {
+    #[serde(skip)]
+    pub offset: Duration,
+    pub remote_addr: String,
+    pub method: &'static str,
+    pub path: String,
+    pub protocol: &'static str,
+    pub status: u16,
+    pub body_bytes_sent: u64,
+    pub http_referer: Option<String>,
+    pub http_user_agent: String,
+    pub request_time: f64,
+}
+
+// 每个客户端按“页面 → 静态资源 → 思考时间”的节奏访问，思考时间服从指数分布，
+// 平均值按客户端数量和目标速率反推，使总体速率接近 --rate
+pub struct Generator {
+    clients: Vec<Client>,
+    queue: BinaryHeap<Reverse<(u64, usize)>>,
+    mean_think_us: f64,
+}
+
+impl Generator {
+    pub fn new<R: Rng>(clients: usize, rate: f64, rng: &mut R) -> Self {
+        let total_weight: u32 = ROUTES.iter().map(|route| route.weight).sum();
+        let page_weight: u32 = ROUTES
+            .iter()
+            .filter(|route| route.kind == PathKind::Page)
+            .map(|route| route.weight)
+            .sum();
+        let requests_per_visit =
+            1.0 + MEAN_ASSETS_PER_PAGE * page_weight as f64 / total_weight as f64;
+        let mean_think_us = clients as f64 * requests_per_visit / rate * 1_000_000.0;
+
+        let clients: Vec<Client> = (0..clients)
+            .map(|_| Client {
+                ip: fake_value(FakeKind::Ipv4, FakeLocale::En),
+                user_agent: if rng.random_bool(0.05) {
+                    BOT_AGENTS.choose(rng).unwrap().to_string()
+                } else {
+                    fake_value(FakeKind::UserAgent, FakeLocale::En)
+                },
+                protocol: if rng.random_bool(0.6) {
+                    "HTTP/2.0"
+                } else {
+                    "HTTP/1.1"
+                },
+                last_page: None,
+                pending_assets: 0,
+            })
+            .collect();
+        let queue = (0..clients.len())
+            .map(|index| Reverse((think_time_us(mean_think_us, rng), index)))
+            .collect();
+        Self {
+            clients,
+            queue,
+            mean_think_us,
+        }
+    }
+
+    pub fn next_entry<R: Rng>(&mut self, rng: &mut R) -> LogEntry {
+        let Reverse((at_us, index)) = self.queue.pop().expect("client queue is never empty");
+        let client = &mut self.clients[index];
+
+        let (method, path, kind, referer) = if client.pending_assets > 0 {
+            client.pending_assets -= 1;
+            let path = fill_placeholders(ASSETS.choose(rng).unwrap(), rng);
+            ("GET", path, PathKind::Asset, client.last_page.clone())
+        } else {
+            let route = ROUTES
+                .choose_weighted(rng, |route| route.weight)
+                .expect("routes have positive weights");
+            let path = fill_placeholders(route.path, rng);
+            let referer = client.last_page.clone();
+            if route.kind == PathKind::Page {
+                client.pending_assets = rng.random_range(0..=(MEAN_ASSETS_PER_PAGE * 2.0) as u32);
+                client.last_page = Some(format!("https://shop.example.com{}", path));
+            }
+            (route.method, path, route.kind, referer)
+        };
+
+        let status = pick_status(kind, method, &path, rng);
+        let body_bytes_sent = body_size(kind, status, &path, rng);
+        let request_time = request_time(kind, status, rng);
+        let next_gap_us = if client.pending_assets > 0 {
+            rng.random_range(5_000..80_000)
+        } else {
+            think_time_us(self.mean_think_us, rng)
+        };
+        self.queue
+            .push(Reverse((at_us + next_gap_us.max(1), index)));
+
+        LogEntry {
+            offset: Duration::from_micros(at_us),
+            remote_addr: client.ip.clone(),
+            method,
+            path,
+            protocol: client.protocol,
+            status,
+            body_bytes_sent,
+            http_referer: referer,
+            http_user_agent: client.user_agent.clone(),
+            request_time,
+        }
+    }
+}
+
+// 指数分布的思考时间；首个请求也按它错开，避免启动时所有客户端集中发请求
+fn think_time_us<R: Rng>(mean_us: f64, rng: &mut R) -> u64 {
+    (-(1.0 - rng.random::<f64>()).ln() * mean_us) as u64
+}
+
+fn fill_placeholders<R: Rng>(template: &str, rng: &mut R) -> String {
+    let mut path = template.to_string();
+    if path.contains("{id}") {
+        path = path.replace("{id}", &rng.random_range(1..5000).to_string());
+    }
+    if path.contains("{page}") {
+        path = path.replace("{page}", &rng.random_range(1..20).to_string());
+    }
+    if path.contains("{word}") {
+        path = path.replace("{word}", WORDS.choose(rng).unwrap());
+    }
+    if path.contains("{slug}") {
+        let slug = format!(
+            "{}-{}",
+            WORDS.choose(rng).unwrap(),
+            rng.random_range(2020..2027)
+        );
+        path = path.replace("{slug}", &slug);
+    }
+    path
+}
+
+fn pick_status<R: Rng>(kind: PathKind, method: &str, path: &str, rng: &mut R) -> u16 {
+    let table: &[(u16, u32)] = match kind {
+        PathKind::Page if path == "/admin" => &[(302, 50), (401, 30), (403, 20)],
+        PathKind::Page => &[
+            (200, 940),
+            (304, 20),
+            (302, 10),
+            (404, 20),
+            (500, 5),
+            (502, 3),
+            (503, 2),
+        ],
+        PathKind::Api => &[
+            (200, 900),
+            (400, 30),
+            (401, 30),
+            (404, 15),
+            (429, 10),
+            (500, 10),
+            (504, 5),
+        ],
+        PathKind::Asset => &[(200, 700), (304, 290), (404, 10)],
+        PathKind::Probe => &[(404, 95), (403, 5)],
+    };
+    let status = table.choose_weighted(rng, |entry| entry.1).unwrap().0;
+    if status == 200 && method == "POST" {
+        201
+    } else {
+        status
+    }
+}
+
+fn body_size<R: Rng>(kind: PathKind, status: u16, path: &str, rng: &mut R) -> u64 {
+    match status {
+        304 => 0,
+        300..=599 => rng.random_range(150..700),
+        _ => match kind {
+            PathKind::Page => rng.random_range(2_000..60_000),
+            PathKind::Api => rng.random_range(80..8_000),
+            PathKind::Asset if path.ends_with(".js") => rng.random_range(20_000..400_000),
+            PathKind::Asset if path.ends_with(".css") => rng.random_range(5_000..80_000),
+            PathKind::Asset => rng.random_range(1_000..200_000),
+            PathKind::Probe => rng.random_range(150..700),
+        },
+    }
+}
+
+// 请求耗时（秒），5xx 和接口请求偏慢
+fn request_time<R: Rng>(kind: PathKind, status: u16, rng: &mut R) -> f64 {
+    let millis = match (kind, status) {
+        (_, 504) => rng.random_range(5_000..30_000),
+        (_, 500..=599) => rng.random_range(200..3_000),
+        (PathKind::Asset, _) => rng.random_range(0..30),
+        (PathKind::Api, _) => rng.random_range(5..800),
+        _ => rng.random_range(10..400),
+    };
+    millis as f64 / 1000.0
+}
+
+fn format_entry(
+    entry: &LogEntry,
+    time: DateTime<Local>,
+    format: LogFormat,
+    output: &RecordOutput,
+) -> Result<String, LogGenError> {
+    let time_local = time.format("%d/%b/%Y:%H:%M:%S %z");
+    let line = match format {
+        LogFormat::Nginx => format!(
+            "{} - - [{}] \"{} {} {}\" {} {} \"{}\" \"{}\"",
+            entry.remote_addr,
+            time_local,
+            entry.method,
+            entry.path,
+            entry.protocol,
+            entry.status,
+            entry.body_bytes_sent,
+            entry.http_referer.as_deref().unwrap_or("-"),
+            entry.http_user_agent
+        ),
+        LogFormat::Clf => format!(
+            "{} - - [{}] \"{} {} {}\" {} {}",
+            entry.remote_addr,
+            time_local,
+            entry.method,
+            entry.path,
+            entry.protocol,
+            entry.status,
+            match entry.body_bytes_sent {
+                0 => "-".to_string(),
+                bytes => bytes.to_string(),
+            }
+        ),
+        LogFormat::Json => {
+            let mut value = serde_json::to_value(entry)?;
+            value["time"] = time.to_rfc3339_opts(SecondsFormat::Millis, false).into();
+            serde_json::to_string(&output.shape(&value)?)?
+        }
+    };
+    Ok(line)
+}
+
+pub fn run_loggen(opts: LogGenOpts) -> Result<(), LogGenError> {
+    let rate = parse_rate(&opts.rate)?;
+    let duration = opts
+        .duration
+        .as_deref()
+        .map(|value| {
+            parse_duration(value).ok_or_else(|| LogGenError::InvalidDuration(value.into()))
+        })
+        .transpose()?;
+    if opts.clients == 0 {
+        return Err(LogGenError::InvalidClients);
+    }
+
+    let mut out: Box<dyn Write> = match &opts.output {
+        Some(path) => Box::new(io::BufWriter::new(File::create(path)?)),
+        None => Box::new(io::BufWriter::new(io::stdout().lock())),
+    };
+    match generate(&opts, rate, duration, &mut out) {
+        // 下游管道（例如 head）提前关闭时正常退出
+        Err(LogGenError::Io(e)) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        other => other,
+    }
+}
+
+fn generate(
+    opts: &LogGenOpts,
+    rate: f64,
+    duration: Option<Duration>,
+    out: &mut dyn Write,
+) -> Result<(), LogGenError> {
+    let mut rng = rand::rng();
+    let mut generator = Generator::new(opts.clients, rate, &mut rng);
+
+    let start_time = if opts.backfill {
+        let span = match (duration, opts.count) {
+            (Some(duration), _) => duration,
+            (None, Some(count)) => Duration::from_secs_f64(count as f64 / rate),
+            (None, None) => return Err(LogGenError::UnboundedBackfill),
+        };
+        Local::now() - span
+    } else {
+        Local::now()
+    };
+    let started = Instant::now();
+
+    let mut generated = 0u64;
+    while opts.count.is_none_or(|count| generated < count) {
+        let entry = generator.next_entry(&mut rng);
+        if duration.is_some_and(|duration| entry.offset >= duration) {
+            break;
+        }
+        if !opts.backfill {
+            let elapsed = started.elapsed();
+            // 领先超过 2ms 才休眠，避免高速率下频繁系统调用
+            if entry.offset > elapsed + Duration::from_millis(2) {
+                out.flush()?;
+                thread::sleep(entry.offset - elapsed);
+            }
+        }
+        generated += 1;
+        metrics::records_generated("loggen", 1);
+        // --where 只影响输出，-n 和速率仍按生成的全部请求计算
+        if !opts.record_output.keep(&entry)? {
+            continue;
+        }
+        let time = start_time + entry.offset;
+        writeln!(
+            out,
+            "{}",
+            format_entry(&entry, time, opts.format, &opts.record_output)?
+        )?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rate() {
+        assert_eq!(parse_rate("1000/s").unwrap(), 1000.0);
+        assert_eq!(parse_rate("600/m").unwrap(), 10.0);
+        assert_eq!(parse_rate("25").unwrap(), 25.0);
+        assert!(parse_rate("0/s").is_err());
+        assert!(parse_rate("10/d").is_err());
+    }
+
+    #[test]
+    fn test_generator_matches_rate_and_is_ordered() {
+        let mut rng = rand::rng();
+        let mut generator = Generator::new(50, 200.0, &mut rng);
+        let entries: Vec<LogEntry> = (0..20_000)
+            .map(|_| generator.next_entry(&mut rng))
+            .collect();
+
+        assert!(entries
+            .windows(2)
+            .all(|pair| pair[0].offset <= pair[1].offset));
+        // 20000 条 / 200 每秒 ≈ 100 秒，允许一定波动
+        let span = entries.last().unwrap().offset.as_secs_f64();
+        assert!((80.0..120.0).contains(&span), "span {}", span);
+
+        let assets = entries
+            .iter()
+            .filter(|entry| entry.path.starts_with("/static/") || entry.path == "/favicon.ico")
+            .count();
+        assert!(assets > 2_000, "assets {}", assets);
+        assert!(entries.iter().any(|entry| entry.status == 404));
+        assert!(entries
+            .iter()
+            .all(|entry| entry.status != 304 || entry.body_bytes_sent == 0));
+    }
+
+    #[test]
+    fn test_format_nginx_combined() {
+        let entry = LogEntry {
+            offset: Duration::ZERO,
+            remote_addr: "203.0.113.9".to_string(),
+            method: "GET",
+            path: "/products/42".to_string(),
+            protocol: "HTTP/1.1",
+            status: 200,
+            body_bytes_sent: 5120,
+            http_referer: None,
+            http_user_agent: "curl/8.5.0".to_string(),
+            request_time: 0.012,
+        };
+        let time = Local::now();
+        let line = format_entry(&entry, time, LogFormat::Nginx, &RecordOutput::default()).unwrap();
+        let expected = format!(
+            "203.0.113.9 - - [{}] \"GET /products/42 HTTP/1.1\" 200 5120 \"-\" \"curl/8.5.0\"",
+            time.format("%d/%b/%Y:%H:%M:%S %z")
+        );
+        assert_eq!(line, expected);
+
+        let json: serde_json::Value = serde_json::from_str(
+            &format_entry(&entry, time, LogFormat::Json, &RecordOutput::default()).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(json["status"], 200);
+        assert_eq!(json["http_referer"], serde_json::Value::Null);
+        assert!(json["time"].is_string());
+    }
+}
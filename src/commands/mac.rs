@@ -0,0 +1,381 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io::{self, BufRead};
+use std::path::{Path, PathBuf};
+
+use clap::{Subcommand, ValueEnum};
+use serde::Serialize;
+
+use crate::utils::oui;
+use crate::utils::output::RecordOutput;
+
+#[derive(clap::Args)]
+pub struct MacOpts {
+    #[command(subcommand)]
+    command: Option<MacCommand>,
+
+    #[arg(
+        value_name = "MAC",
+        help = "要查询的 MAC 地址，支持 00:1a:2b:3c:4d:5e、00-1A-2B-3C-4D-5E、001a.2b3c.4d5e 等写法；省略或为 - 时从 stdin 逐行读取"
+    )]
+    addresses: Vec<String>,
+
+    #[arg(
+        long = "oui-file",
+        value_name = "FILE",
+        help = "额外加载 IEEE 发布的 oui.txt 或 oui.csv，优先于内置的常见厂商表"
+    )]
+    oui_file: Option<PathBuf>,
+
+    #[arg(long, help = "以 JSON 输出结果")]
+    json: bool,
+
+    // 全局 --where / --fields / --rename，由命令行入口注入
+    #[arg(skip)]
+    pub(crate) record_output: RecordOutput,
+}
+
+impl MacOpts {
+    // 只有地址查询支持全局 --where / --fields / --rename，gen 输出的是纯文本地址
+    pub(crate) fn record_output(&mut self) -> Option<&mut RecordOutput> {
+        self.command.is_none().then_some(&mut self.record_output)
+    }
+}
+
+#[derive(Subcommand)]
+enum MacCommand {
+    #[command(about = "生成随机的本地管理单播 MAC 地址，用于实验环境")]
+    Gen(GenOpts),
+}
+
+#[derive(clap::Args)]
+struct GenOpts {
+    #[arg(short = 'n', long, default_value_t = 1, help = "生成数量")]
+    count: u32,
+
+    #[arg(
+        long,
+        value_name = "PREFIX",
+        help = "固定的前缀字节，例如 52:54:00，其余字节随机；不指定时首字节设置本地管理位并清除组播位"
+    )]
+    prefix: Option<String>,
+
+    #[arg(
+        value_enum,
+        short = 'f',
+        long,
+        default_value_t = MacFormat::Colon,
+        help = "输出格式 colon | hyphen | dot | bare"
+    )]
+    format: MacFormat,
+
+    #[arg(short = 'u', long, help = "输出大写十六进制")]
+    upper: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum MacFormat {
+    // 00:1a:2b:3c:4d:5e
+    Colon,
+    // 00-1a-2b-3c-4d-5e
+    Hyphen,
+    // 001a.2b3c.4d5e（Cisco 写法）
+    Dot,
+    // 001a2b3c4d5e
+    Bare,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum MacError {
+    #[error("invalid MAC address '{0}'")]
+    InvalidMac(String),
+    #[error("invalid prefix '{0}', expected 1 to 5 bytes such as 52:54:00")]
+    InvalidPrefix(String),
+    #[error("{path}: no OUI entries found, expected IEEE oui.txt or oui.csv")]
+    EmptyOuiFile { path: String },
+    #[error("no MAC addresses to look up")]
+    NoInput,
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("csv parse failed: {0}")]
+    Csv(#[from] csv::Error),
+    #[error("json serialize failed: {0}")]
+    Serialize(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MacAddr(pub [u8; 6]);
+
+impl MacAddr {
+    // 接受 : - 分隔的 6 组、Cisco 的 3 组 4 位和不带分隔符的 12 位十六进制
+    pub fn parse(raw: &str) -> Option<Self> {
+        let raw = raw.trim();
+        let groups: Vec<&str> = raw.split([':', '-', '.']).collect();
+        let valid = match groups.len() {
+            6 => groups.iter().all(|group| (1..=2).contains(&group.len())),
+            3 => raw.contains('.') && groups.iter().all(|group| group.len() == 4),
+            1 => raw.len() == 12,
+            _ => false,
+        };
+        if !valid {
+            return None;
+        }
+        let hex: String = if groups.len() == 6 {
+            groups
+                .iter()
+                .map(|group| format!("{:0>2}", group))
+                .collect()
+        } else {
+            groups.concat()
+        };
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let value = u64::from_str_radix(&hex, 16).ok()?;
+        let bytes = value.to_be_bytes();
+        Some(Self(bytes[2..].try_into().ok()?))
+    }
+
+    pub fn oui(&self) -> [u8; 3] {
+        [self.0[0], self.0[1], self.0[2]]
+    }
+
+    // 首字节最低位：组播 / 广播
+    pub fn is_multicast(&self) -> bool {
+        self.0[0] & 0x01 != 0
+    }
+
+    // 首字节次低位：本地管理（非厂商分配）
+    pub fn is_local(&self) -> bool {
+        self.0[0] & 0x02 != 0
+    }
+
+    fn format(&self, format: MacFormat, upper: bool) -> String {
+        let hex: Vec<String> = self
+            .0
+            .iter()
+            .map(|byte| {
+                if upper {
+                    format!("{:02X}", byte)
+                } else {
+                    format!("{:02x}", byte)
+                }
+            })
+            .collect();
+        match format {
+            MacFormat::Colon => hex.join(":"),
+            MacFormat::Hyphen => hex.join("-"),
+            MacFormat::Dot => hex
+                .chunks(2)
+                .map(|pair| pair.concat())
+                .collect::<Vec<_>>()
+                .join("."),
+            MacFormat::Bare => hex.concat(),
+        }
+    }
+}
+
+impl fmt::Display for MacAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.format(MacFormat::Colon, false))
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct MacRecord {
+    pub mac: String,
+    pub oui: String,
+    pub vendor: Option<String>,
+    pub multicast: bool,
+    pub broadcast: bool,
+    // 本地管理地址（U/L 位为 1），不是厂商分配的，一般查不到厂商
+    pub local: bool,
+}
+
+pub fn run_mac(opts: MacOpts) -> Result<(), MacError> {
+    if let Some(MacCommand::Gen(gen)) = opts.command {
+        return run_gen(gen);
+    }
+
+    let extra = opts.oui_file.as_deref().map(load_oui_file).transpose()?;
+    let mut addresses = Vec::new();
+    if !opts.addresses.is_empty() && opts.addresses.iter().all(|arg| arg != "-") {
+        for raw in &opts.addresses {
+            addresses.push(MacAddr::parse(raw).ok_or_else(|| MacError::InvalidMac(raw.clone()))?);
+        }
+    } else {
+        // stdin 中每行取第一个像 MAC 地址的字段，兼容 arp -a、ip neigh 的输出
+        for line in io::stdin().lock().lines() {
+            let line = line?;
+            if let Some(mac) = line
+                .split(|c: char| {
+                    c.is_whitespace() || matches!(c, ',' | ';' | '(' | ')' | '[' | ']')
+                })
+                .find_map(MacAddr::parse)
+            {
+                addresses.push(mac);
+            }
+        }
+    }
+    if addresses.is_empty() {
+        return Err(MacError::NoInput);
+    }
+
+    let mut records: Vec<MacRecord> = addresses
+        .iter()
+        .map(|mac| {
+            let vendor = extra
+                .as_ref()
+                .and_then(|extra| extra.get(&mac.oui()).map(String::as_str))
+                .or_else(|| oui::vendor(mac.oui()));
+            MacRecord {
+                mac: mac.to_string(),
+                oui: mac.to_string()[..8].to_string(),
+                vendor: vendor.map(str::to_string),
+                multicast: mac.is_multicast(),
+                broadcast: mac.0 == [0xff; 6],
+                local: mac.is_local(),
+            }
+        })
+        .collect();
+    opts.record_output.retain(&mut records)?;
+
+    if opts.json {
+        opts.record_output
+            .write_json(&records, io::stdout().lock())?;
+        return Ok(());
+    }
+    for record in &records {
+        let mut flags = Vec::new();
+        if record.broadcast {
+            flags.push("broadcast");
+        } else if record.multicast {
+            flags.push("multicast");
+        }
+        if record.local && !record.broadcast {
+            flags.push("locally administered");
+        }
+        let vendor = record.vendor.as_deref().unwrap_or("(unknown vendor)");
+        if flags.is_empty() {
+            println!("{}  {}", record.mac, vendor);
+        } else {
+            println!("{}  {}  [{}]", record.mac, vendor, flags.join(", "));
+        }
+    }
+    Ok(())
+}
+
+fn run_gen(opts: GenOpts) -> Result<(), MacError> {
+    let prefix = match &opts.prefix {
+        Some(raw) => parse_gen_prefix(raw).ok_or_else(|| MacError::InvalidPrefix(raw.clone()))?,
+        None => Vec::new(),
+    };
+    for _ in 0..opts.count {
+        let mac = random_mac(&prefix, rand::random());
+        println!("{}", mac.format(opts.format, opts.upper));
+    }
+    Ok(())
+}
+
+fn parse_gen_prefix(raw: &str) -> Option<Vec<u8>> {
+    let groups: Vec<&str> = raw.trim().split([':', '-']).collect();
+    if groups.is_empty() || groups.len() > 5 {
+        return None;
+    }
+    groups
+        .iter()
+        .map(|group| {
+            if group.is_empty() || group.len() > 2 {
+                return None;
+            }
+            u8::from_str_radix(group, 16).ok()
+        })
+        .collect()
+}
+
+// 没有前缀时首字节置本地管理位、清组播位，避免和厂商分配的地址冲突
+fn random_mac(prefix: &[u8], random: [u8; 6]) -> MacAddr {
+    let mut bytes = random;
+    if prefix.is_empty() {
+        bytes[0] = (bytes[0] & 0xfc) | 0x02;
+    }
+    bytes[..prefix.len()].copy_from_slice(prefix);
+    MacAddr(bytes)
+}
+
+// IEEE 的 oui.txt（"00-00-0C   (hex)\t\tCisco Systems, Inc"）或 oui.csv（Registry,Assignment,Organization Name,...）
+pub(crate) fn load_oui_file(path: &Path) -> Result<HashMap<[u8; 3], String>, MacError> {
+    let text = fs::read_to_string(path)?;
+    let mut vendors = HashMap::new();
+    if text.trim_start().starts_with("Registry,") {
+        let mut reader = csv::Reader::from_reader(text.as_bytes());
+        for record in reader.records() {
+            let record = record?;
+            if let (Some(prefix), Some(name)) =
+                (record.get(1).and_then(oui::parse_prefix), record.get(2))
+            {
+                vendors.insert(prefix, name.trim().to_string());
+            }
+        }
+    } else {
+        for line in text.lines() {
+            if let Some((prefix, name)) = line.split_once("(hex)") {
+                if let Some(prefix) = oui::parse_prefix(prefix) {
+                    vendors.insert(prefix, name.trim().to_string());
+                }
+            }
+        }
+    }
+    if vendors.is_empty() {
+        return Err(MacError::EmptyOuiFile {
+            path: path.display().to_string(),
+        });
+    }
+    Ok(vendors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_format() {
+        let expected = MacAddr([0x00, 0x1a, 0x2b, 0x3c, 0x4d, 0x5e]);
+        for raw in [
+            "00:1a:2b:3c:4d:5e",
+            "00-1A-2B-3C-4D-5E",
+            "001a.2b3c.4d5e",
+            "001A2B3C4D5E",
+            "0:1a:2b:3c:4d:5e",
+        ] {
+            assert_eq!(MacAddr::parse(raw), Some(expected), "{}", raw);
+        }
+        for raw in [
+            "00:1a:2b:3c:4d",
+            "00:1a:2b:3c:4d:5e:6f",
+            "001a2b3c4d5",
+            "zz:1a:2b:3c:4d:5e",
+        ] {
+            assert_eq!(MacAddr::parse(raw), None, "{}", raw);
+        }
+        assert_eq!(expected.format(MacFormat::Dot, false), "001a.2b3c.4d5e");
+        assert_eq!(
+            expected.format(MacFormat::Hyphen, true),
+            "00-1A-2B-3C-4D-5E"
+        );
+        assert!(MacAddr::parse("01:00:5e:00:00:fb").unwrap().is_multicast());
+        assert!(MacAddr::parse("52:54:00:12:34:56").unwrap().is_local());
+    }
+
+    #[test]
+    fn test_random_mac() {
+        let mac = random_mac(&[], [0xff; 6]);
+        assert!(mac.is_local());
+        assert!(!mac.is_multicast());
+        let mac = random_mac(&parse_gen_prefix("52:54:00").unwrap(), [0xab; 6]);
+        assert_eq!(mac.to_string(), "52:54:00:ab:ab:ab");
+        assert_eq!(parse_gen_prefix("52:54:00:01:02:03"), None);
+        assert_eq!(parse_gen_prefix("5254"), None);
+    }
+}
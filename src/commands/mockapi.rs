@@ -0,0 +1,878 @@
+use std::cmp::Reverse;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chrono::{Local, Utc};
+use rand::seq::IndexedRandom;
+use rand::{Rng, RngCore};
+use serde_json::{json, Map, Value};
+
+use crate::commands::faker::{fake_value, FakeKind, FakeLocale};
+use crate::commands::forward::{jittered, parse_latency, parse_probability};
+use crate::commands::idgen::{generate_ids, IdGenerateRequest, IdRecord};
+use crate::commands::person::random_mobile;
+use crate::utils::areas::random_province;
+use crate::utils::network::{self, NetworkError};
+
+// $ref 嵌套或自引用的 schema 展开到该深度后输出 null
+const MAX_DEPTH: usize = 8;
+// 未指定 maxItems 时数组最多生成的元素数
+const DEFAULT_MAX_ITEMS: u64 = 3;
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+const METHODS: [&str; 8] = [
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+#[derive(clap::Args)]
+pub struct MockApiOpts {
+    #[arg(
+        value_name = "SPEC",
+        help = "OpenAPI 3 或 Swagger 2 文档，YAML 或 JSON 格式"
+    )]
+    spec: PathBuf,
+
+    #[arg(long, default_value = "127.0.0.1", help = "监听地址")]
+    host: String,
+
+    #[arg(short, long, default_value_t = 8081, help = "监听端口")]
+    port: u16,
+
+    #[arg(
+        long,
+        value_name = "DELAY",
+        help = "每个响应的延迟，例如 200ms 或 200ms±50"
+    )]
+    latency: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "RATE",
+        help = "按概率返回 500 错误，例如 5% 或 0.05；文档中定义了 500 / default 响应时按其生成响应体"
+    )]
+    error_rate: Option<String>,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum MockApiError {
+    #[error("failed to read {path}: {source}")]
+    Read { path: PathBuf, source: io::Error },
+    #[error("invalid OpenAPI document: {0}")]
+    Parse(#[from] serde_yaml::Error),
+    #[error("OpenAPI document defines no operations under 'paths'")]
+    NoOperations,
+    #[error("invalid --latency '{value}': {reason}")]
+    InvalidLatency { value: String, reason: String },
+    #[error("invalid --error-rate '{value}': {reason}")]
+    InvalidErrorRate { value: String, reason: String },
+    #[error("failed to listen on {addr}: {source}")]
+    Bind { addr: String, source: io::Error },
+    #[error(transparent)]
+    Network(#[from] NetworkError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+// 注入到每个响应的延迟和错误
+#[derive(Debug, Default, Clone)]
+struct Injection {
+    latency: Duration,
+    jitter: Duration,
+    error_rate: f64,
+}
+
+pub struct MockApi {
+    spec: Value,
+    // servers[0].url 或 basePath 中的路径前缀，请求路径带不带前缀都能匹配
+    base_path: String,
+    routes: Vec<Route>,
+}
+
+struct Route {
+    method: String,
+    template: String,
+    // 路径参数为 None
+    segments: Vec<Option<String>>,
+    operation: Value,
+}
+
+impl Route {
+    fn matches(&self, segments: &[&str]) -> bool {
+        self.segments.len() == segments.len()
+            && self
+                .segments
+                .iter()
+                .zip(segments)
+                .all(|(expected, actual)| {
+                    expected.as_deref().is_none_or(|literal| literal == *actual)
+                })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct MockResponse {
+    pub status: u16,
+    pub content_type: String,
+    pub body: Option<Value>,
+}
+
+impl MockResponse {
+    fn error(status: u16, message: String) -> Self {
+        Self {
+            status,
+            content_type: "application/json".to_string(),
+            body: Some(json!({ "error": message })),
+        }
+    }
+}
+
+pub fn run_mock_api(opts: MockApiOpts) -> Result<(), MockApiError> {
+    let mut injection = Injection::default();
+    if let Some(value) = &opts.latency {
+        (injection.latency, injection.jitter) =
+            parse_latency(value).map_err(|reason| MockApiError::InvalidLatency {
+                value: value.clone(),
+                reason,
+            })?;
+    }
+    if let Some(value) = &opts.error_rate {
+        injection.error_rate =
+            parse_probability(value).map_err(|reason| MockApiError::InvalidErrorRate {
+                value: value.clone(),
+                reason,
+            })?;
+    }
+    let text = fs::read_to_string(&opts.spec).map_err(|source| MockApiError::Read {
+        path: opts.spec.clone(),
+        source,
+    })?;
+    // JSON 是 YAML 的子集，两种格式都用 YAML 解析
+    let api = MockApi::new(serde_yaml::from_str(&text)?)?;
+
+    network::check_host("mock-api", &opts.host)?;
+    let addr = format!("{}:{}", opts.host, opts.port);
+    let listener = TcpListener::bind(&addr).map_err(|source| MockApiError::Bind {
+        addr: addr.clone(),
+        source,
+    })?;
+    eprintln!(
+        "Mock API listening on http://{} ({} operations)",
+        listener.local_addr()?,
+        api.routes.len()
+    );
+    for route in &api.routes {
+        eprintln!("  {:<7} {}{}", route.method, api.base_path, route.template);
+    }
+    serve(listener, Arc::new(api), Arc::new(injection));
+    Ok(())
+}
+
+fn serve(listener: TcpListener, api: Arc<MockApi>, injection: Arc<Injection>) {
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(error) => {
+                eprintln!("connection failed: {}", error);
+                continue;
+            }
+        };
+        let api = Arc::clone(&api);
+        let injection = Arc::clone(&injection);
+        thread::spawn(move || {
+            if let Err(error) = handle_client(stream, &api, &injection) {
+                eprintln!("request failed: {}", error);
+            }
+        });
+    }
+}
+
+fn handle_client(mut stream: TcpStream, api: &MockApi, injection: &Injection) -> io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Ok(());
+    };
+    let mut content_length = 0u64;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim_end().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            }
+        }
+    }
+    // 请求体不参与匹配，读掉即可
+    io::copy(&mut reader.take(content_length), &mut io::sink())?;
+
+    let started = Instant::now();
+    let path = target.split('?').next().unwrap_or(target);
+    let inject_error = injection.error_rate > 0.0 && rand::rng().random_bool(injection.error_rate);
+    let response = api.respond(method, path, inject_error);
+    let delay = jittered(injection.latency, injection.jitter);
+    if !delay.is_zero() {
+        thread::sleep(delay);
+    }
+    write_response(&mut stream, &response)?;
+    eprintln!(
+        "{} {} -> {} ({}ms)",
+        method,
+        target,
+        response.status,
+        started.elapsed().as_millis()
+    );
+    Ok(())
+}
+
+fn write_response(stream: &mut TcpStream, response: &MockResponse) -> io::Result<()> {
+    let body = match &response.body {
+        // 非 JSON 的媒体类型（例如 text/plain）直接输出字符串
+        Some(Value::String(text)) if !response.content_type.contains("json") => {
+            text.clone().into_bytes()
+        }
+        Some(value) => serde_json::to_vec(value)?,
+        None => Vec::new(),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nAccess-Control-Allow-Origin: *\r\nAccess-Control-Allow-Headers: *\r\nAccess-Control-Allow-Methods: GET, POST, PUT, PATCH, DELETE, OPTIONS\r\nConnection: close\r\n\r\n",
+        response.status,
+        status_text(response.status),
+        response.content_type,
+        body.len()
+    )?;
+    stream.write_all(&body)?;
+    stream.flush()
+}
+
+pub(crate) fn status_text(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        202 => "Accepted",
+        204 => "No Content",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        422 => "Unprocessable Entity",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        _ => "",
+    }
+}
+
+impl MockApi {
+    pub fn new(spec: Value) -> Result<Self, MockApiError> {
+        let base_path = spec
+            .pointer("/servers/0/url")
+            .and_then(Value::as_str)
+            .map(url_path)
+            .or_else(|| spec.get("basePath").and_then(Value::as_str))
+            .unwrap_or_default()
+            .trim_end_matches('/')
+            .to_string();
+        let mut routes = Vec::new();
+        for (template, item) in spec
+            .get("paths")
+            .and_then(Value::as_object)
+            .into_iter()
+            .flatten()
+        {
+            for method in METHODS {
+                let Some(operation) = item.get(method) else {
+                    continue;
+                };
+                routes.push(Route {
+                    method: method.to_ascii_uppercase(),
+                    template: template.clone(),
+                    segments: path_segments(template)
+                        .map(|segment| {
+                            let parameter = segment.starts_with('{') && segment.ends_with('}');
+                            (!parameter).then(|| segment.to_string())
+                        })
+                        .collect(),
+                    operation: operation.clone(),
+                });
+            }
+        }
+        if routes.is_empty() {
+            return Err(MockApiError::NoOperations);
+        }
+        // 字面段多的路由优先，/users/me 先于 /users/{id}
+        routes.sort_by_key(|route| Reverse(route.segments.iter().flatten().count()));
+        Ok(Self {
+            spec,
+            base_path,
+            routes,
+        })
+    }
+
+    // inject_error 为 true 时返回 500，响应体取文档中的 500 / 5XX / default 响应
+    pub fn respond(&self, method: &str, path: &str, inject_error: bool) -> MockResponse {
+        let path = path
+            .strip_prefix(self.base_path.as_str())
+            .filter(|rest| !self.base_path.is_empty() && (rest.is_empty() || rest.starts_with('/')))
+            .unwrap_or(path);
+        let segments: Vec<&str> = path_segments(path).collect();
+        let matching: Vec<&Route> = self
+            .routes
+            .iter()
+            .filter(|route| route.matches(&segments))
+            .collect();
+        if matching.is_empty() {
+            return MockResponse::error(404, format!("no operation matches {}", path));
+        }
+        let Some(route) = matching.iter().find(|route| route.method == method) else {
+            // 文档没有定义 OPTIONS 时按 CORS 预检处理
+            if method == "OPTIONS" {
+                return MockResponse {
+                    status: 204,
+                    content_type: "text/plain".to_string(),
+                    body: None,
+                };
+            }
+            return MockResponse::error(405, format!("{} is not defined for {}", method, path));
+        };
+        let responses = route.operation.get("responses").and_then(Value::as_object);
+        let (status, response) = if inject_error {
+            let response = responses.and_then(|responses| {
+                ["500", "5XX", "default"]
+                    .into_iter()
+                    .find_map(|code| responses.get(code))
+            });
+            (500, response)
+        } else {
+            responses.map_or((200, None), success_response)
+        };
+        let mut reply = match response {
+            Some(response) => self.response_body(response, status),
+            None => MockResponse {
+                status,
+                content_type: "application/json".to_string(),
+                body: None,
+            },
+        };
+        if inject_error && reply.body.is_none() {
+            reply = MockResponse::error(500, "injected failure".to_string());
+        }
+        reply
+    }
+
+    fn response_body(&self, response: &Value, status: u16) -> MockResponse {
+        let mut reply = MockResponse {
+            status,
+            content_type: "application/json".to_string(),
+            body: None,
+        };
+        let Some(response) = self.resolve(response) else {
+            return reply;
+        };
+        // OpenAPI 3：content 下按媒体类型给出 schema / example，优先 JSON
+        if let Some(content) = response.get("content").and_then(Value::as_object) {
+            let Some((media_type, media)) = content
+                .iter()
+                .find(|(media_type, _)| media_type.contains("json"))
+                .or_else(|| content.iter().next())
+            else {
+                return reply;
+            };
+            reply.content_type = media_type.clone();
+            reply.body = if let Some(example) = media.get("example") {
+                Some(example.clone())
+            } else if let Some(example) = media
+                .get("examples")
+                .and_then(Value::as_object)
+                .and_then(|examples| examples.values().next())
+            {
+                self.resolve(example)
+                    .and_then(|example| example.get("value"))
+                    .cloned()
+            } else {
+                media
+                    .get("schema")
+                    .map(|schema| self.generate(schema, "", 0, &mut Persona::default()))
+            };
+            return reply;
+        }
+        // Swagger 2：schema 和 examples 直接挂在响应上
+        reply.body = match response
+            .get("examples")
+            .and_then(|examples| examples.get("application/json"))
+        {
+            Some(example) => Some(example.clone()),
+            None => response
+                .get("schema")
+                .map(|schema| self.generate(schema, "", 0, &mut Persona::default())),
+        };
+        reply
+    }
+
+    // 展开本文档内的 $ref（#/components/... 或 #/definitions/...），循环引用时返回 None
+    fn resolve<'a>(&'a self, mut value: &'a Value) -> Option<&'a Value> {
+        for _ in 0..MAX_DEPTH {
+            let Some(reference) = value.get("$ref").and_then(Value::as_str) else {
+                return Some(value);
+            };
+            value = self.spec.pointer(reference.strip_prefix('#')?)?;
+        }
+        None
+    }
+
+    fn generate(&self, schema: &Value, name: &str, depth: usize, persona: &mut Persona) -> Value {
+        let Some(schema) = self.resolve(schema).filter(|_| depth <= MAX_DEPTH) else {
+            return Value::Null;
+        };
+        let mut rng = rand::rng();
+        if let Some(example) = schema.get("example") {
+            return example.clone();
+        }
+        if let Some(value) = schema
+            .get("enum")
+            .and_then(Value::as_array)
+            .and_then(|values| values.choose(&mut rng))
+        {
+            return value.clone();
+        }
+        if let Some(parts) = schema.get("allOf").and_then(Value::as_array) {
+            let mut merged = Map::new();
+            for part in parts {
+                if let Value::Object(object) = self.generate(part, name, depth + 1, persona) {
+                    merged.extend(object);
+                }
+            }
+            return Value::Object(merged);
+        }
+        if let Some(choice) = ["oneOf", "anyOf"]
+            .into_iter()
+            .find_map(|key| schema.get(key))
+            .and_then(Value::as_array)
+            .and_then(|choices| choices.choose(&mut rng))
+        {
+            return self.generate(choice, name, depth + 1, persona);
+        }
+        // OpenAPI 3.1 的 type 可以是数组，例如 [string, "null"]
+        let schema_type = match schema.get("type") {
+            Some(Value::String(schema_type)) => schema_type.as_str(),
+            Some(Value::Array(types)) => types
+                .iter()
+                .filter_map(Value::as_str)
+                .find(|schema_type| *schema_type != "null")
+                .unwrap_or("null"),
+            _ if schema.get("properties").is_some() => "object",
+            _ if schema.get("items").is_some() => "array",
+            _ => "string",
+        };
+        match schema_type {
+            "object" => {
+                // 同一对象内的姓名、身份证号、出生日期、地址来自同一个人
+                let mut persona = Persona::default();
+                let object: Map<String, Value> = schema
+                    .get("properties")
+                    .and_then(Value::as_object)
+                    .into_iter()
+                    .flatten()
+                    .map(|(key, property)| {
+                        let value = self.generate(property, key, depth + 1, &mut persona);
+                        (key.clone(), value)
+                    })
+                    .collect();
+                Value::Object(object)
+            }
+            "array" => {
+                let min = schema.get("minItems").and_then(Value::as_u64).unwrap_or(1);
+                let max = schema
+                    .get("maxItems")
+                    .and_then(Value::as_u64)
+                    .unwrap_or(DEFAULT_MAX_ITEMS)
+                    .max(min);
+                let items = schema.get("items").unwrap_or(&Value::Null);
+                (0..rng.random_range(min..=max))
+                    .map(|_| self.generate(items, name, depth + 1, &mut Persona::default()))
+                    .collect()
+            }
+            "integer" => {
+                let (low, high) = default_range(name);
+                let min = schema.get("minimum").and_then(Value::as_i64).unwrap_or(low);
+                let max = schema
+                    .get("maximum")
+                    .and_then(Value::as_i64)
+                    .unwrap_or(high)
+                    .max(min);
+                json!(rng.random_range(min..=max))
+            }
+            "number" => {
+                let min = schema.get("minimum").and_then(Value::as_f64).unwrap_or(0.0);
+                let max = schema
+                    .get("maximum")
+                    .and_then(Value::as_f64)
+                    .unwrap_or(1000.0)
+                    .max(min);
+                json!((rng.random_range(min..=max) * 100.0).round() / 100.0)
+            }
+            "boolean" => Value::Bool(rng.random_bool(0.5)),
+            "null" => Value::Null,
+            _ => {
+                let text = string_value(schema, name, persona);
+                match schema.get("maxLength").and_then(Value::as_u64) {
+                    Some(max) => Value::String(text.chars().take(max as usize).collect()),
+                    None => Value::String(text),
+                }
+            }
+        }
+    }
+}
+
+// 同一对象中与身份相关的字段共用一条身份证记录，姓名、性别、出生日期、地址彼此一致
+#[derive(Default)]
+struct Persona(Option<IdRecord>);
+
+impl Persona {
+    fn field(&mut self, pick: fn(&IdRecord) -> &String, fallback: FakeKind) -> String {
+        if self.0.is_none() {
+            self.0 = generate_ids(IdGenerateRequest {
+                count: Some(1),
+                region: None,
+                birth: None,
+                min_birth: None,
+                max_birth: None,
+                gender: None,
+                name_mix: None,
+                long_names: None,
+            })
+            .ok()
+            .and_then(|mut records| records.pop());
+        }
+        match &self.0 {
+            Some(record) => pick(record).clone(),
+            None => fake_value(fallback, FakeLocale::ZhCn),
+        }
+    }
+}
+
+// 按 format 和字段名选择生成器，字段名忽略大小写和下划线，例如 id_number、idCard
+fn string_value(schema: &Value, name: &str, persona: &mut Persona) -> String {
+    let format = schema
+        .get("format")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let key: String = name
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .collect::<String>()
+        .to_ascii_lowercase();
+    let key = key.as_str();
+    match format.as_str() {
+        "id-number" | "id-card" | "idcard" => {
+            return persona.field(|p| &p.id_number, FakeKind::Word)
+        }
+        "date" if key.contains("birth") => return persona.field(|p| &p.birthday, FakeKind::Word),
+        "date" => return random_date(),
+        "date-time" => return random_datetime(),
+        "email" => return fake_value(FakeKind::Email, FakeLocale::En),
+        "uuid" => return random_uuid(),
+        "uri" | "url" => return random_url(),
+        "ipv4" => return fake_value(FakeKind::Ipv4, FakeLocale::En),
+        "ipv6" => return fake_value(FakeKind::Ipv6, FakeLocale::En),
+        "hostname" => return format!("{}.example.com", fake_value(FakeKind::Word, FakeLocale::En)),
+        "password" => return fake_value(FakeKind::Password, FakeLocale::En),
+        "phone" | "mobile" => return random_mobile(),
+        _ => {}
+    }
+    match key {
+        "idnumber" | "idcard" | "idcardno" | "idno" | "sfz" | "sfzh" | "identityno" | "certno" => {
+            persona.field(|p| &p.id_number, FakeKind::Word)
+        }
+        "name" | "fullname" | "realname" => persona.field(|p| &p.name, FakeKind::Name),
+        "username" | "login" | "account" => fake_value(FakeKind::Username, FakeLocale::En),
+        "gender" | "sex" => persona.field(|p| &p.gender, FakeKind::Word),
+        "province" => random_province()
+            .map(|province| province.name)
+            .unwrap_or_else(|| fake_value(FakeKind::City, FakeLocale::ZhCn)),
+        "ip" | "ipaddr" | "ipaddress" => fake_value(FakeKind::Ipv4, FakeLocale::En),
+        "uuid" | "guid" => random_uuid(),
+        _ if key.contains("birth") => persona.field(|p| &p.birthday, FakeKind::Word),
+        _ if key.contains("mobile") || key.contains("phone") || key == "tel" => random_mobile(),
+        _ if key.contains("email") => fake_value(FakeKind::Email, FakeLocale::En),
+        _ if key.contains("address") || key == "addr" => {
+            persona.field(|p| &p.address, FakeKind::Street)
+        }
+        _ if key.contains("city") => fake_value(FakeKind::City, FakeLocale::ZhCn),
+        _ if key.contains("company") => fake_value(FakeKind::Company, FakeLocale::ZhCn),
+        _ if key.contains("zip") || key.contains("postcode") => {
+            fake_value(FakeKind::ZipCode, FakeLocale::ZhCn)
+        }
+        _ if key.ends_with("url") || key.ends_with("uri") => random_url(),
+        _ if key.ends_with("name") && !key.ends_with("filename") && !key.ends_with("hostname") => {
+            persona.field(|p| &p.name, FakeKind::Name)
+        }
+        _ if key.contains("description") || key.contains("remark") || key.contains("content") => {
+            fake_value(FakeKind::Sentence, FakeLocale::ZhCn)
+        }
+        _ => fake_value(FakeKind::Word, FakeLocale::En),
+    }
+}
+
+// 整数字段按字段名给出合理的默认范围，文档里的 minimum / maximum 优先
+fn default_range(name: &str) -> (i64, i64) {
+    let key = name.to_ascii_lowercase();
+    match key.as_str() {
+        "age" => (18, 70),
+        "page" | "page_no" | "pageno" => (1, 10),
+        "size" | "page_size" | "pagesize" | "limit" => (10, 50),
+        _ if key == "id" || key.ends_with("_id") || key.ends_with("id") => (1, 100_000),
+        _ => (0, 1000),
+    }
+}
+
+fn random_date() -> String {
+    let days = rand::rng().random_range(0..1000);
+    (Local::now().date_naive() - chrono::Duration::days(days))
+        .format("%Y-%m-%d")
+        .to_string()
+}
+
+fn random_datetime() -> String {
+    let seconds = rand::rng().random_range(0..90 * 86_400);
+    (Utc::now() - chrono::Duration::seconds(seconds))
+        .format("%Y-%m-%dT%H:%M:%SZ")
+        .to_string()
+}
+
+fn random_uuid() -> String {
+    let mut bytes = [0u8; 16];
+    rand::rng().fill_bytes(&mut bytes);
+    // 版本 4、RFC 4122 变体
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+fn random_url() -> String {
+    format!(
+        "https://example.com/{}",
+        fake_value(FakeKind::Word, FakeLocale::En)
+    )
+}
+
+// 返回最小的 2xx 响应，其次 2XX、default；都没有时为 200 无响应体
+fn success_response(responses: &Map<String, Value>) -> (u16, Option<&Value>) {
+    let success = responses
+        .iter()
+        .filter_map(|(code, response)| Some((code.parse::<u16>().ok()?, response)))
+        .filter(|(code, _)| (200..300).contains(code))
+        .min_by_key(|(code, _)| *code);
+    match success {
+        Some((code, response)) => (code, Some(response)),
+        None => (
+            200,
+            responses.get("2XX").or_else(|| responses.get("default")),
+        ),
+    }
+}
+
+// servers[].url 可以是完整 URL 或相对路径，只取路径部分；带变量的 URL 不作前缀
+fn url_path(url: &str) -> &str {
+    if url.contains('{') {
+        return "";
+    }
+    match url.split_once("://") {
+        Some((_, rest)) => rest.find('/').map_or("", |index| &rest[index..]),
+        None => url,
+    }
+}
+
+fn path_segments(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/').filter(|segment| !segment.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::idcard::{validate_id_number, RegionCheck};
+
+    fn spec() -> Value {
+        serde_yaml::from_str(
+            r##"
+openapi: 3.0.3
+servers:
+  - url: https://api.example.com/v1
+paths:
+  /users:
+    get:
+      responses:
+        "200":
+          content:
+            application/json:
+              schema:
+                type: array
+                minItems: 2
+                maxItems: 2
+                items: { $ref: "#/components/schemas/User" }
+    post:
+      responses:
+        "201":
+          content:
+            application/json:
+              example: { id: 1 }
+        default:
+          content:
+            application/json:
+              schema: { $ref: "#/components/schemas/Error" }
+  /users/me:
+    get:
+      responses:
+        "200":
+          content:
+            text/plain:
+              schema: { type: string, example: me }
+  /users/{id}:
+    get:
+      responses:
+        "404": { description: not found }
+        "200":
+          content:
+            application/json:
+              schema: { $ref: "#/components/schemas/User" }
+    delete:
+      responses:
+        "204": { description: deleted }
+components:
+  schemas:
+    User:
+      type: object
+      properties:
+        id: { type: integer, minimum: 1, maximum: 9 }
+        name: { type: string }
+        id_number: { type: string }
+        birthday: { type: string, format: date }
+        mobile: { type: string }
+        email: { type: string, format: email }
+        status: { type: string, enum: [active, locked] }
+        score: { type: number, minimum: 1, maximum: 2 }
+        tags: { type: array, maxItems: 2, items: { type: string, maxLength: 3 } }
+        manager: { $ref: "#/components/schemas/User" }
+    Error:
+      type: object
+      properties:
+        code: { type: integer, example: 50001 }
+        message: { type: string, example: boom }
+"##,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_mock_responses_from_schema() {
+        let api = MockApi::new(spec()).unwrap();
+        assert_eq!(api.base_path, "/v1");
+
+        let response = api.respond("GET", "/v1/users/42", false);
+        assert_eq!(response.status, 200);
+        let user = response.body.unwrap();
+        assert!((1..=9).contains(&user["id"].as_i64().unwrap()));
+        assert!(!user["name"].as_str().unwrap().is_ascii());
+        let id_number = user["id_number"].as_str().unwrap();
+        assert!(validate_id_number(id_number, RegionCheck::Strict).is_ok());
+        // 同一对象的出生日期与身份证号一致
+        assert_eq!(
+            user["birthday"].as_str().unwrap().replace('-', ""),
+            id_number[6..14]
+        );
+        let mobile = user["mobile"].as_str().unwrap();
+        assert!(mobile.len() == 11 && mobile.starts_with('1'));
+        assert!(user["email"].as_str().unwrap().contains('@'));
+        assert!(["active", "locked"].contains(&user["status"].as_str().unwrap()));
+        assert!((1.0..=2.0).contains(&user["score"].as_f64().unwrap()));
+        assert!(user["tags"].as_array().unwrap().iter().all(|tag| tag
+            .as_str()
+            .unwrap()
+            .chars()
+            .count()
+            <= 3));
+        // 自引用的 schema 展开到一定深度后截止
+        assert!(user["manager"]["manager"].is_object());
+
+        // 不带前缀的路径同样匹配，字面路由优先于参数路由
+        let response = api.respond("GET", "/users", false);
+        assert_eq!(response.body.unwrap().as_array().unwrap().len(), 2);
+        assert_eq!(
+            api.respond("GET", "/v1/users/me", false),
+            MockResponse {
+                status: 200,
+                content_type: "text/plain".into(),
+                body: Some(json!("me")),
+            }
+        );
+        let response = api.respond("POST", "/v1/users", false);
+        assert_eq!(
+            (response.status, response.body),
+            (201, Some(json!({"id": 1})))
+        );
+        let response = api.respond("DELETE", "/v1/users/7", false);
+        assert_eq!((response.status, response.body), (204, None));
+
+        assert_eq!(api.respond("GET", "/v1/orders", false).status, 404);
+        assert_eq!(api.respond("PUT", "/v1/users/7", false).status, 405);
+        assert_eq!(api.respond("OPTIONS", "/v1/users/7", false).status, 204);
+
+        // 注入错误：有 default 响应时按其生成，没有时返回通用错误
+        let response = api.respond("POST", "/v1/users", true);
+        assert_eq!(
+            (response.status, response.body),
+            (500, Some(json!({"code": 50001, "message": "boom"})))
+        );
+        let response = api.respond("GET", "/v1/users/7", true);
+        assert_eq!(
+            (response.status, response.body),
+            (500, Some(json!({"error": "injected failure"})))
+        );
+    }
+
+    #[test]
+    fn test_serve_applies_latency() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let api = Arc::new(MockApi::new(spec()).unwrap());
+        let injection = Arc::new(Injection {
+            latency: Duration::from_millis(100),
+            ..Injection::default()
+        });
+        thread::spawn(move || serve(listener, api, injection));
+
+        let started = Instant::now();
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream
+            .write_all(b"POST /v1/users HTTP/1.1\r\nHost: x\r\nContent-Length: 2\r\n\r\n{}")
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(started.elapsed() >= Duration::from_millis(100));
+        assert!(response.starts_with("HTTP/1.1 201 Created\r\n"));
+        assert!(response.contains("Access-Control-Allow-Origin: *\r\n"));
+        assert!(response.ends_with("\r\n\r\n{\"id\":1}"));
+    }
+}
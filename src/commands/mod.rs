@@ -1,51 +1,551 @@
+use std::path::PathBuf;
+
 use anyhow::Result;
-use clap::{Parser, Subcommand};
+use clap::{error::ErrorKind, CommandFactory, Parser, Subcommand};
 
 use crate::commands::{
+    arpscan::{run_arp_scan, ArpScanOpts},
+    bench::{run_bench, BenchOpts},
+    cert::{run_cert, CertOpts},
+    certgen::{run_certgen, CertgenOpts},
+    cidr::{run_cidr, CidrOpts},
+    datadiff::{run_data_diff, DataDiffOpts},
+    discover::{run_discover, DiscoverOpts},
+    dns::{run_dns, DnsOpts},
+    dnsserver::{run_dns_server, DnsServerOpts},
+    faker::{run_fake, FakeOpts},
+    forward::{run_forward, ForwardOpts},
+    geoip::{run_geoip, GeoIpOpts},
+    gm::{run_gm, GmOpts},
+    grpc::{run_grpc, GrpcOpts},
+    hash::{run_hash, HashOpts},
+    http::{run_http, HttpOpts},
+    httpmock::{run_httpmock, HttpMockOpts},
+    id::{run_unique_id, UniqueIdOpts},
+    idextract::{run_id_extract, IdExtractOpts},
     idgen::{run_gen_id, IdOpts},
+    interfaces::{run_interfaces, InterfacesOpts},
+    ipcheck::{run_ip_check, IpCheckOpts},
+    ipinfo::{run_ip_info, IpInfoOpts},
     jsonfmt::{run_json_fmt, JsonFmtOpts},
+    keygen::{run_keygen, KeygenOpts},
+    loggen::{run_loggen, LogGenOpts},
+    mac::{run_mac, MacOpts},
+    mockapi::{run_mock_api, MockApiOpts},
+    mqtt::{run_mqtt, MqttOpts},
+    mtu::{run_mtu, MtuOpts},
+    mutate::{run_mutate, MutateOpts},
+    nc::{run_nc, NcOpts},
+    ntp::{run_ntp, NtpOpts},
+    passwd::{run_passwd, PasswdOpts},
+    pcap::{run_pcap, PcapOpts},
     pdf::{run_pdf, PdfOpts},
+    person::{run_person, PersonOpts},
+    ping::{run_ping, PingOpts},
+    pipe::{run_pipe, PipeOpts},
+    play::{run_play, PlayOpts},
     portscan::{run_port_scan, PortScanOpts},
+    serve::{run_serve, ServeOpts},
+    share::{run_share, ShareOpts},
+    smoketest::{run_smoke_test, SmokeTestOpts},
+    smtp::{run_smtp, SmtpOpts},
+    snmp::{run_snmp, SnmpOpts},
+    speedtest::{run_speed_test, SpeedTestOpts},
+    sshkey::{run_sshkey, SshkeyOpts},
+    syslog::{run_syslog, SyslogOpts},
+    tlsinfo::{run_tls_info, TlsInfoOpts},
+    trace::{run_trace, TraceOpts},
+    transfer::{run_recv, run_relay, run_send, RecvOpts, RelayOpts, SendOpts},
+    uuid::{run_uuid, UuidOpts},
+    whois::{run_whois, WhoisOpts},
+    wizard::{run_wizard, WizardOpts},
+    wol::{run_wol, WolOpts},
+    ws::{run_ws, WsOpts},
 };
+use crate::utils::filter::Filter;
+use crate::utils::output::RecordOutput;
+use crate::utils::script::MapScript;
+use crate::utils::version::write_version;
+use crate::utils::{a11y, metrics, network, record};
 use crate::web::{run_web, WebOpts};
 
 // 公共 Command trait + 注册函数
+pub mod arpscan;
+pub mod bench;
+pub mod cert;
+pub mod certgen;
+pub mod cidr;
+pub mod datadiff;
+pub mod discover;
+pub mod dns;
+pub mod dnsserver;
+pub mod faker;
+pub mod forward;
+pub mod geoip;
+pub mod gm;
+pub mod grpc;
+pub mod hash;
+pub mod http;
+pub mod httpmock;
+pub mod id;
+pub mod idextract;
 pub mod idgen;
 pub mod imagetool;
+pub mod interfaces;
+pub mod ipcheck;
+pub mod ipinfo;
 pub mod jsonfmt;
+pub mod keygen;
+pub mod loggen;
+pub mod mac;
+pub mod mockapi;
+pub mod mqtt;
+pub mod mtu;
+pub mod mutate;
+pub mod nc;
+pub mod ntp;
+pub mod passwd;
+pub mod pcap;
 pub mod pdf;
+pub mod person;
+pub mod ping;
+pub mod pipe;
+pub mod play;
 pub mod portscan;
+pub mod serve;
+pub mod share;
+pub mod smoketest;
+pub mod smtp;
+pub mod snmp;
+pub mod speedtest;
+pub mod sshkey;
+pub mod syslog;
+pub mod tlsinfo;
+pub mod trace;
+pub mod transfer;
+pub mod uuid;
+pub mod whois;
+pub mod wizard;
+pub mod wol;
+pub mod ws;
 
 #[derive(Parser)]
-#[command(name = "rtoolkit", version, about = "Rust Toolkit CLI")]
+#[command(
+    name = "rtoolkit",
+    version,
+    about = "Rust Toolkit CLI",
+    disable_version_flag = true,
+    arg_required_else_help = true
+)]
 struct Cli {
+    // 自行处理 --version，以便支持 --version --json
+    #[arg(short = 'V', long = "version", help = "打印版本信息")]
+    version: bool,
+
+    #[arg(
+        long = "json",
+        requires = "version",
+        help = "与 --version 一起使用，以 JSON 输出版本、git 提交、构建时间、feature 和内嵌数据版本"
+    )]
+    json: bool,
+
+    #[arg(
+        long = "offline",
+        global = true,
+        help = "禁止访问本机以外的网络：需要联网的命令直接报错，反向 DNS、DNSBL 等可选查询被跳过"
+    )]
+    offline: bool,
+
+    #[arg(
+        long = "a11y",
+        global = true,
+        help = "读屏友好的输出：不显示进度条、二维码和对齐表格，改为带标签的逐行文字，交互选择改为输入编号"
+    )]
+    a11y: bool,
+
+    #[arg(
+        long = "metrics-listen",
+        global = true,
+        value_name = "ADDR",
+        help = "在该地址提供 Prometheus /metrics 指标（探测数、开放端口、生成记录数等），例如 127.0.0.1:9898；只用于 port-scan、discover、loggen、syslog listen、web 等长时间运行的命令"
+    )]
+    metrics_listen: Option<String>,
+
+    #[arg(
+        long = "where",
+        global = true,
+        value_name = "EXPR",
+        help = "按表达式过滤结构化输出的记录，例如 'state == \"open\" && latency_ms < 50'"
+    )]
+    filter: Option<String>,
+
+    #[arg(
+        long = "fields",
+        global = true,
+        value_name = "LIST",
+        value_delimiter = ',',
+        help = "JSON / CSV 输出只保留这些字段并按此顺序排列，例如 id_number,name；嵌套字段用 a.b"
+    )]
+    fields: Option<Vec<String>>,
+
+    #[arg(
+        long = "rename",
+        global = true,
+        value_name = "OLD=NEW",
+        value_delimiter = ',',
+        value_parser = RecordOutput::parse_rename,
+        help = "重命名 JSON / CSV 输出的字段，例如 id_number=card_no，可重复或逗号分隔"
+    )]
+    rename: Vec<(String, String)>,
+
+    #[arg(
+        long = "map-script",
+        global = true,
+        value_name = "FILE",
+        help = "用 Rhai 脚本逐条处理结构化输出的记录：脚本中修改 record 即可改字段、加派生值，返回 false 丢弃记录"
+    )]
+    map_script: Option<PathBuf>,
+
+    #[arg(
+        long = "record",
+        global = true,
+        value_name = "FILE",
+        help = "在伪终端中运行命令并把终端会话录制为 asciicast 文件，可用 play 子命令或 asciinema 回放；只用于交互终端，输入或输出被重定向时报错"
+    )]
+    record: Option<PathBuf>,
+
     #[command(subcommand)]
-    command: Commands,
+    command: Option<Commands>,
 }
 
 #[derive(Subcommand)]
 enum Commands {
-    #[command(about = "生成中国身份证号")]
+    #[command(about = "交互式向导：逐步选择常用任务的参数，最后打印等价的完整命令并可直接运行")]
+    Wizard {
+        #[command(flatten)]
+        opts: WizardOpts,
+    },
+    #[command(about = "生成中国身份证号", args_conflicts_with_subcommands = true)]
     Idgen {
         /// 生成中国大陆 18 位身份证号（校验位符合 GB 11643 / MOD 11-2）
         #[command(flatten)]
         opts: IdOpts,
     },
-    #[command(about = "端口扫描")]
+    #[command(
+        name = "idextract",
+        about = "从任意文本中提取校验通过的身份证号，去重并给出位置"
+    )]
+    IdExtract {
+        #[command(flatten)]
+        opts: IdExtractOpts,
+    },
+    #[command(about = "生成通用测试数据（姓名、邮箱、IP、UA 等）")]
+    Fake {
+        #[command(flatten)]
+        opts: FakeOpts,
+    },
+    #[command(about = "生成 UUID（v4 / v7），或解析 UUID 中的版本和时间戳")]
+    Uuid {
+        #[command(flatten)]
+        opts: UuidOpts,
+    },
+    #[command(about = "生成 ULID / NanoID / Snowflake ID，或解析其中的时间戳和序列号")]
+    Id {
+        #[command(flatten)]
+        opts: UniqueIdOpts,
+    },
+    #[command(about = "生成随机密码或口令短语（英文 / 拼音词表），并估算熵")]
+    Passwd {
+        #[command(flatten)]
+        opts: PasswdOpts,
+    },
+    #[command(about = "生成关联一致的人员测试数据（可按家庭生成）")]
+    Person {
+        #[command(flatten)]
+        opts: PersonOpts,
+    },
+    #[command(about = "按规则变异已有记录，生成带校验标签的反例数据")]
+    Mutate {
+        #[command(flatten)]
+        opts: MutateOpts,
+    },
+    #[command(about = "按主键比较两份数据集，报告缺失、多余和变化的记录")]
+    Datadiff {
+        #[command(flatten)]
+        opts: DataDiffOpts,
+    },
+    #[command(about = "端口扫描", args_conflicts_with_subcommands = true)]
     PortScan {
         #[command(flatten)]
-        opts: PortScanOpts,
+        opts: Box<PortScanOpts>,
+    },
+    #[command(about = "发现网段内的存活主机，可交互选择后继续端口扫描")]
+    Discover {
+        #[command(flatten)]
+        opts: DiscoverOpts,
+    },
+    #[command(about = "类似 curl 的 HTTP 客户端，格式化输出状态、响应头和 JSON 响应体")]
+    Http {
+        #[command(flatten)]
+        opts: HttpOpts,
+    },
+    #[command(about = "WebSocket 客户端：交互式收发消息、一次性发送、ping 保活和耗时统计")]
+    Ws {
+        #[command(flatten)]
+        opts: WsOpts,
+    },
+    #[command(about = "MQTT 测试客户端：发布消息或订阅主题，支持认证、QoS 和 TLS")]
+    Mqtt {
+        #[command(flatten)]
+        opts: MqttOpts,
+    },
+    #[command(about = "gRPC 健康检查，通过服务反射列出服务和方法")]
+    Grpc {
+        #[command(flatten)]
+        opts: GrpcOpts,
+    },
+    #[command(
+        name = "speedtest",
+        about = "测量到测速服务器的延迟和下载、上传带宽，测速地址可自定义"
+    )]
+    SpeedTest {
+        #[command(flatten)]
+        opts: SpeedTestOpts,
+    },
+    #[command(about = "HTTP 压测，输出每秒请求数、延迟百分位和错误统计")]
+    Bench {
+        #[command(flatten)]
+        opts: BenchOpts,
+    },
+    #[command(about = "ICMP ping，无权限时回退为 TCP 连接，输出往返时间统计")]
+    Ping {
+        #[command(flatten)]
+        opts: PingOpts,
+    },
+    #[command(about = "逐跳追踪到目标主机的路由（UDP / ICMP traceroute）")]
+    Trace {
+        #[command(flatten)]
+        opts: TraceOpts,
+    },
+    #[command(about = "查询域名或 IP 的 whois 信息，跟随注册局转介，可解析注册商和到期日期")]
+    Whois {
+        #[command(flatten)]
+        opts: WhoisOpts,
+    },
+    #[command(about = "探测到目标主机的路径 MTU")]
+    Mtu {
+        #[command(flatten)]
+        opts: MtuOpts,
+    },
+    #[command(about = "DNS 查询，类似 dig，支持 DoT / DoH 上游")]
+    Dns {
+        #[command(flatten)]
+        opts: DnsOpts,
+    },
+    #[command(
+        name = "dns-server",
+        about = "本地测试用 DNS 服务器：按 --zone 应答静态记录，其余查询转发到上游"
+    )]
+    DnsServer {
+        #[command(flatten)]
+        opts: DnsServerOpts,
+    },
+    #[command(about = "查询 NTP 服务器，报告本机时钟偏差和往返延迟，偏差超限时以非零状态退出")]
+    Ntp {
+        #[command(flatten)]
+        opts: NtpOpts,
+    },
+    #[command(name = "ipcheck", about = "检查 IP 是否在 DNSBL 或本地黑名单中")]
+    IpCheck {
+        #[command(flatten)]
+        opts: IpCheckOpts,
+    },
+    #[command(
+        name = "ipinfo",
+        about = "查询本机出口公网 IP，或指定 IP 的 ASN、归属地以及是否为私有 / 保留地址"
+    )]
+    IpInfo {
+        #[command(flatten)]
+        opts: IpInfoOpts,
+    },
+    #[command(
+        about = "子网计算：网络地址、广播地址、可用主机范围，以及拆分、包含判断和合并",
+        args_conflicts_with_subcommands = true
+    )]
+    Cidr {
+        #[command(flatten)]
+        opts: CidrOpts,
+    },
+    #[command(
+        about = "查询 MAC 地址的厂商（内置常见厂商 OUI 表），或生成随机的本地管理 MAC",
+        args_conflicts_with_subcommands = true
+    )]
+    Mac {
+        #[command(flatten)]
+        opts: MacOpts,
+    },
+    #[command(about = "列出本机网卡的 IPv4 / IPv6 地址、MAC 和 MTU，以及默认网关和 DNS 服务器")]
+    Interfaces {
+        #[command(flatten)]
+        opts: InterfacesOpts,
+    },
+    #[command(
+        name = "arpscan",
+        about = "ARP 扫描：向本地网段广播 ARP 请求，列出在线主机的 IP、MAC 和厂商"
+    )]
+    ArpScan {
+        #[command(flatten)]
+        opts: ArpScanOpts,
+    },
+    #[command(about = "Wake-on-LAN：向 MAC 地址或配置中的主机发送魔术包，唤醒局域网内的机器")]
+    Wol {
+        #[command(flatten)]
+        opts: WolOpts,
+    },
+    #[command(name = "geoip", about = "使用 MaxMind 离线库批量查询 IP 归属地和 ASN")]
+    GeoIp {
+        #[command(flatten)]
+        opts: GeoIpOpts,
+    },
+    #[command(about = "SMTP 连通性测试：握手、STARTTLS、认证并列出服务器能力，可选发送测试邮件")]
+    Smtp {
+        #[command(flatten)]
+        opts: SmtpOpts,
+    },
+    #[command(about = "SNMP 查询（get / walk）")]
+    Snmp {
+        #[command(flatten)]
+        opts: SnmpOpts,
+    },
+    #[command(about = "发送测试 syslog 或监听接收")]
+    Syslog {
+        #[command(flatten)]
+        opts: SyslogOpts,
+    },
+    #[command(about = "生成模拟的 Web 访问日志")]
+    Loggen {
+        #[command(flatten)]
+        opts: LogGenOpts,
+    },
+    #[command(about = "离线分析 pcap / pcapng 抓包文件")]
+    Pcap {
+        #[command(flatten)]
+        opts: PcapOpts,
     },
     #[command(name = "jsonfmt", alias = "json-fmt", about = "JSON 格式化")]
     JsonFmt {
         #[command(flatten)]
         opts: JsonFmtOpts,
     },
+    #[command(
+        name = "mock-api",
+        about = "按 OpenAPI 文档启动模拟 HTTP 接口，响应体按 schema 生成测试数据"
+    )]
+    MockApi {
+        #[command(flatten)]
+        opts: MockApiOpts,
+    },
+    #[command(
+        name = "httpmock",
+        about = "按路由定义文件启动模拟 HTTP 服务，支持路径参数、延迟和响应模板"
+    )]
+    HttpMock {
+        #[command(flatten)]
+        opts: HttpMockOpts,
+    },
+    #[command(about = "TCP 端口转发，可注入延迟、丢弃连接和限速等网络故障")]
+    Forward {
+        #[command(flatten)]
+        opts: ForwardOpts,
+    },
     #[command(about = "PDF 处理工具")]
     Pdf {
         #[command(flatten)]
         opts: PdfOpts,
     },
+    #[command(about = "在两台机器之间通过加密认证的 TCP 通道传输 stdin/stdout")]
+    Pipe {
+        #[command(flatten)]
+        opts: PipeOpts,
+    },
+    #[command(about = "通过局域网发送文件，接收方凭口令经 mDNS 或中继找到发送方，支持断点续传")]
+    Send {
+        #[command(flatten)]
+        opts: SendOpts,
+    },
+    #[command(about = "凭口令接收 send 发送的文件")]
+    Recv {
+        #[command(flatten)]
+        opts: RecvOpts,
+    },
+    #[command(about = "运行 send / recv 的中继服务，转发不在同一局域网的两端")]
+    Relay {
+        #[command(flatten)]
+        opts: RelayOpts,
+    },
+    #[command(about = "简易 netcat：TCP / UDP 连接或监听，在 stdin/stdout 和网络之间收发数据")]
+    Nc {
+        #[command(flatten)]
+        opts: NcOpts,
+    },
+    #[command(about = "计算文件或字符串的 SM3 / SHA-2 摘要和 HMAC")]
+    Hash {
+        #[command(flatten)]
+        opts: HashOpts,
+    },
+    #[command(about = "国密 SM2 / SM4 密钥生成、签名验签和加解密")]
+    Gm {
+        #[command(flatten)]
+        opts: GmOpts,
+    },
+    #[command(about = "生成 RSA / EC 私钥、CSR 和带 SAN 的自签名证书")]
+    Keygen {
+        #[command(flatten)]
+        opts: KeygenOpts,
+    },
+    #[command(about = "一步生成开发用 HTTPS 的私钥和证书，可选同时生成本地 CA 并由它签发")]
+    Certgen {
+        #[command(flatten)]
+        opts: CertgenOpts,
+    },
+    #[command(about = "离线校验本地证书文件，或在 PEM、DER 和 PKCS#12 之间转换证书和私钥")]
+    Cert {
+        #[command(flatten)]
+        opts: CertOpts,
+    },
+    #[command(
+        name = "tlsinfo",
+        about = "查看服务端的 TLS 协议版本、加密套件和完整证书链，证书临近到期时以非零状态退出"
+    )]
+    TlsInfo {
+        #[command(flatten)]
+        opts: TlsInfoOpts,
+    },
+    #[command(about = "生成 OpenSSH 密钥对，检查 authorized_keys 中的弱密钥和重复密钥")]
+    Sshkey {
+        #[command(flatten)]
+        opts: SshkeyOpts,
+    },
+    #[command(about = "回放 asciicast 录制的终端会话")]
+    Play {
+        #[command(flatten)]
+        opts: PlayOpts,
+    },
+    #[command(about = "静态文件 HTTP 服务，支持目录列表、Basic 认证、CORS 和单页应用回退")]
+    Serve {
+        #[command(flatten)]
+        opts: ServeOpts,
+    },
+    #[command(about = "通过临时 HTTP 服务和终端二维码分享文件或文本")]
+    Share {
+        #[command(flatten)]
+        opts: ShareOpts,
+    },
+    #[command(about = "按清单并发检查部署环境（端口、HTTP、DNS、证书、数据库）")]
+    Smoketest {
+        #[command(flatten)]
+        opts: SmokeTestOpts,
+    },
     #[command(name = "imgtool", about = "图片处理工具")]
     Imagetool(imagetool::ImageTool),
     #[command(about = "启动本地 Web 工作台")]
@@ -55,13 +555,171 @@ enum Commands {
     },
 }
 
+impl Commands {
+    // 逐条输出结构化记录、可以应用 --where / --fields / --rename 的命令，返回输出层的注入位置
+    fn record_output(&mut self) -> Option<&mut RecordOutput> {
+        match self {
+            Commands::Idgen { opts } => Some(&mut opts.record_output),
+            Commands::IdExtract { opts } => Some(&mut opts.record_output),
+            Commands::Person { opts } => Some(&mut opts.record_output),
+            Commands::Mutate { opts } => Some(&mut opts.record_output),
+            Commands::PortScan { opts } => Some(&mut opts.record_output),
+            Commands::GeoIp { opts } => Some(&mut opts.record_output),
+            Commands::Snmp { opts } => Some(&mut opts.record_output),
+            Commands::Syslog { opts } => Some(&mut opts.record_output),
+            Commands::Loggen { opts } => Some(&mut opts.record_output),
+            Commands::Dns { opts } => Some(&mut opts.record_output),
+            Commands::Uuid { opts } => Some(&mut opts.record_output),
+            Commands::Id { opts } => Some(&mut opts.record_output),
+            Commands::Passwd { opts } => Some(&mut opts.record_output),
+            Commands::Datadiff { opts } => Some(&mut opts.record_output),
+            Commands::Discover { opts } => Some(&mut opts.record_output),
+            Commands::Mqtt { opts } => opts.record_output(),
+            Commands::Grpc { opts } => Some(opts.record_output()),
+            Commands::SpeedTest { opts } => Some(&mut opts.record_output),
+            Commands::Bench { opts } => Some(&mut opts.record_output),
+            Commands::Ping { opts } => Some(&mut opts.record_output),
+            Commands::Trace { opts } => Some(&mut opts.record_output),
+            Commands::Whois { opts } => Some(&mut opts.record_output),
+            Commands::Mtu { opts } => Some(&mut opts.record_output),
+            Commands::Ntp { opts } => Some(&mut opts.record_output),
+            Commands::IpCheck { opts } => Some(&mut opts.record_output),
+            Commands::IpInfo { opts } => Some(&mut opts.record_output),
+            Commands::Cidr { opts } => opts.record_output(),
+            Commands::Mac { opts } => opts.record_output(),
+            Commands::Interfaces { opts } => Some(&mut opts.record_output),
+            Commands::ArpScan { opts } => Some(&mut opts.record_output),
+            Commands::Pcap { opts } => Some(opts.record_output()),
+            Commands::TlsInfo { opts } => Some(&mut opts.record_output),
+            Commands::Sshkey { opts } => opts.record_output(),
+            Commands::Smoketest { opts } => Some(&mut opts.record_output),
+            _ => None,
+        }
+    }
+
+    // 长时间运行、会更新指标的命令；一次性命令结束时端点随进程退出，抓取不到数据
+    fn serves_metrics(&self) -> bool {
+        match self {
+            Commands::PortScan { .. }
+            | Commands::Discover { .. }
+            | Commands::Loggen { .. }
+            | Commands::Web { .. } => true,
+            Commands::Syslog { opts } => opts.is_listen(),
+            _ => false,
+        }
+    }
+}
+
 pub fn build_cli() -> Result<()> {
-    let cli = Cli::parse();
-    match cli.command {
+    let mut cli = Cli::parse();
+    if cli.version {
+        write_version(cli.json, std::io::stdout().lock())?;
+        return Ok(());
+    }
+    if let Some(path) = &cli.record {
+        // 去掉 --record 后在伪终端中重新运行自身，录制完成后以子进程的退出码退出
+        let args = record::strip_record_arg(std::env::args_os());
+        let mut child = std::process::Command::new(std::env::current_exe()?);
+        child.args(args.iter().skip(1));
+        let code = record::record(child, path)?;
+        eprintln!("Recorded session to {}", path.display());
+        std::process::exit(code);
+    }
+    network::set_offline(cli.offline);
+    a11y::set_enabled(cli.a11y);
+    let Some(mut command) = cli.command.take() else {
+        Cli::command()
+            .error(ErrorKind::MissingSubcommand, "a subcommand is required")
+            .exit();
+    };
+    if let Some(addr) = cli.metrics_listen.as_deref() {
+        if !command.serves_metrics() {
+            anyhow::bail!(
+                "--metrics-listen is only supported by long-running commands (port-scan, discover, loggen, syslog listen, web)"
+            );
+        }
+        network::check_endpoint("metrics", addr)?;
+        let local = metrics::serve(addr)?;
+        eprintln!("Serving metrics on http://{}/metrics", local);
+    }
+    if cli.filter.is_some()
+        || cli.fields.is_some()
+        || !cli.rename.is_empty()
+        || cli.map_script.is_some()
+    {
+        let Some(slot) = command.record_output() else {
+            anyhow::bail!(
+                "--where, --fields, --rename and --map-script are not supported by this command"
+            );
+        };
+        let filter = cli.filter.as_deref().map(Filter::parse).transpose()?;
+        let mut output =
+            RecordOutput::new(filter, cli.fields.take(), std::mem::take(&mut cli.rename))?;
+        if let Some(path) = &cli.map_script {
+            output = output.with_script(MapScript::load(path)?);
+        }
+        *slot = output;
+    }
+    match command {
+        Commands::Wizard { opts } => run_wizard(opts)?,
         Commands::Idgen { opts } => run_gen_id(opts)?,
-        Commands::PortScan { opts } => run_port_scan(opts)?,
+        Commands::IdExtract { opts } => run_id_extract(opts)?,
+        Commands::Fake { opts } => run_fake(opts)?,
+        Commands::Uuid { opts } => run_uuid(opts)?,
+        Commands::Id { opts } => run_unique_id(opts)?,
+        Commands::Passwd { opts } => run_passwd(opts)?,
+        Commands::Person { opts } => run_person(opts)?,
+        Commands::Mutate { opts } => run_mutate(opts)?,
+        Commands::Datadiff { opts } => run_data_diff(opts)?,
+        Commands::PortScan { opts } => run_port_scan(*opts)?,
+        Commands::Discover { opts } => run_discover(opts)?,
+        Commands::Http { opts } => run_http(opts)?,
+        Commands::Ws { opts } => run_ws(opts)?,
+        Commands::Mqtt { opts } => run_mqtt(opts)?,
+        Commands::Grpc { opts } => run_grpc(opts)?,
+        Commands::SpeedTest { opts } => run_speed_test(opts)?,
+        Commands::Bench { opts } => run_bench(opts)?,
+        Commands::Ping { opts } => run_ping(opts)?,
+        Commands::Trace { opts } => run_trace(opts)?,
+        Commands::Whois { opts } => run_whois(opts)?,
+        Commands::Mtu { opts } => run_mtu(opts)?,
+        Commands::Dns { opts } => run_dns(opts)?,
+        Commands::DnsServer { opts } => run_dns_server(opts)?,
+        Commands::Ntp { opts } => run_ntp(opts)?,
+        Commands::IpCheck { opts } => run_ip_check(opts)?,
+        Commands::IpInfo { opts } => run_ip_info(opts)?,
+        Commands::Cidr { opts } => run_cidr(opts)?,
+        Commands::Mac { opts } => run_mac(opts)?,
+        Commands::Interfaces { opts } => run_interfaces(opts)?,
+        Commands::ArpScan { opts } => run_arp_scan(opts)?,
+        Commands::Wol { opts } => run_wol(opts)?,
+        Commands::GeoIp { opts } => run_geoip(opts)?,
+        Commands::Smtp { opts } => run_smtp(opts)?,
+        Commands::Snmp { opts } => run_snmp(opts)?,
+        Commands::Syslog { opts } => run_syslog(opts)?,
+        Commands::Loggen { opts } => run_loggen(opts)?,
+        Commands::Pcap { opts } => run_pcap(opts)?,
         Commands::JsonFmt { opts } => run_json_fmt(opts)?,
         Commands::Pdf { opts } => run_pdf(opts)?,
+        Commands::Forward { opts } => run_forward(opts)?,
+        Commands::MockApi { opts } => run_mock_api(opts)?,
+        Commands::HttpMock { opts } => run_httpmock(opts)?,
+        Commands::Pipe { opts } => run_pipe(opts)?,
+        Commands::Send { opts } => run_send(opts)?,
+        Commands::Recv { opts } => run_recv(opts)?,
+        Commands::Relay { opts } => run_relay(opts)?,
+        Commands::Nc { opts } => run_nc(opts)?,
+        Commands::Hash { opts } => run_hash(opts)?,
+        Commands::Gm { opts } => run_gm(opts)?,
+        Commands::Keygen { opts } => run_keygen(opts)?,
+        Commands::Certgen { opts } => run_certgen(opts)?,
+        Commands::Cert { opts } => run_cert(opts)?,
+        Commands::TlsInfo { opts } => run_tls_info(opts)?,
+        Commands::Sshkey { opts } => run_sshkey(opts)?,
+        Commands::Play { opts } => run_play(opts)?,
+        Commands::Serve { opts } => run_serve(opts)?,
+        Commands::Share { opts } => run_share(opts)?,
+        Commands::Smoketest { opts } => run_smoke_test(opts)?,
         Commands::Imagetool(tool) => tool.run()?,
         Commands::Web { opts } => run_web(opts)?,
     };
@@ -70,7 +728,7 @@ pub fn build_cli() -> Result<()> {
 
 #[cfg(test)]
 mod tests {
-    use clap::CommandFactory;
+    use clap::{CommandFactory, Parser};
 
     use super::Cli;
 
@@ -78,4 +736,29 @@ mod tests {
     fn cli_definition_is_valid() {
         Cli::command().debug_assert();
     }
+
+    #[test]
+    fn test_record_output_follows_structured_output() {
+        let supported = |args: &[&str]| {
+            let mut cli = Cli::try_parse_from(args).unwrap();
+            cli.command.take().unwrap().record_output().is_some()
+        };
+        assert!(supported(&["rtoolkit", "whois", "example.com"]));
+        assert!(supported(&["rtoolkit", "mac", "00:1a:2b:3c:4d:5e"]));
+        assert!(supported(&["rtoolkit", "mqtt", "sub", "-t", "a/b"]));
+        assert!(!supported(&["rtoolkit", "mac", "gen"]));
+        assert!(!supported(&["rtoolkit", "mqtt", "pub", "-t", "a/b", "-m", "x"]));
+    }
+
+    #[test]
+    fn test_metrics_listen_only_for_long_running_commands() {
+        let serves = |args: &[&str]| {
+            let mut cli = Cli::try_parse_from(args).unwrap();
+            cli.command.take().unwrap().serves_metrics()
+        };
+        assert!(serves(&["rtoolkit", "--metrics-listen", "127.0.0.1:0", "syslog", "listen"]));
+        assert!(serves(&["rtoolkit", "loggen", "--metrics-listen", "127.0.0.1:0"]));
+        assert!(!serves(&["rtoolkit", "--metrics-listen", "127.0.0.1:0", "uuid"]));
+        assert!(!serves(&["rtoolkit", "syslog", "send", "x"]));
+    }
 }
@@ -0,0 +1,475 @@
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::utils::network::{self, NetworkError};
+use crate::utils::output::RecordOutput;
+
+const IPV4_UDP_OVERHEAD: u16 = 28;
+const IPV6_UDP_OVERHEAD: u16 = 48;
+const IPV4_TCP_OVERHEAD: u16 = 40;
+const IPV6_TCP_OVERHEAD: u16 = 60;
+
+#[derive(clap::Args)]
+pub struct MtuOpts {
+    #[arg(value_name = "HOST", help = "目标主机")]
+    host: String,
+
+    #[arg(
+        long,
+        default_value_t = 1500,
+        value_name = "BYTES",
+        help = "探测上限 MTU"
+    )]
+    max: u16,
+
+    #[arg(
+        long,
+        value_name = "BYTES",
+        help = "探测下限 MTU，默认 IPv4 为 576，IPv6 为 1280"
+    )]
+    min: Option<u16>,
+
+    #[arg(
+        short = 'p',
+        long,
+        default_value_t = 33434,
+        value_name = "PORT",
+        help = "UDP 探测端口"
+    )]
+    port: u16,
+
+    #[arg(
+        long = "tcp-port",
+        default_value_t = 443,
+        value_name = "PORT",
+        help = "TCP MSS 回退探测端口"
+    )]
+    tcp_port: u16,
+
+    #[arg(long, help = "跳过 DF 探测，直接使用 TCP MSS 估算")]
+    tcp: bool,
+
+    #[arg(
+        long = "timeout",
+        default_value_t = 1000,
+        value_name = "MS",
+        help = "单次探测超时时间(毫秒)"
+    )]
+    time_out: u64,
+
+    #[arg(long, help = "以 JSON 输出结果")]
+    json: bool,
+
+    // 全局 --where / --fields / --rename，由命令行入口注入
+    #[arg(skip)]
+    pub(crate) record_output: RecordOutput,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum MtuError {
+    #[error("cannot resolve host: {0}")]
+    Resolve(String),
+    #[error("invalid mtu range: {0}")]
+    InvalidRange(String),
+    #[error("path mtu could not be determined: {0}")]
+    Undetermined(String),
+    #[error(transparent)]
+    Network(#[from] NetworkError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("json serialize failed: {0}")]
+    Serialize(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MtuMethod {
+    DfProbe,
+    TcpMss,
+}
+
+// 单个 DF 探测包的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ProbeOutcome {
+    // 对端有回应（数据或 ICMP 端口不可达），说明包未分片到达
+    Reached,
+    // 本机或中间路由要求分片，附带内核记录的路径 MTU
+    TooBig(Option<u16>),
+    // 超时无回应
+    Silent,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProbeRecord {
+    pub size: u16,
+    pub outcome: ProbeOutcome,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MtuReport {
+    pub host: String,
+    pub addr: String,
+    pub method: MtuMethod,
+    pub mtu: u16,
+    pub max_udp_payload: u16,
+    pub probes: Vec<ProbeRecord>,
+}
+
+pub fn run_mtu(opts: MtuOpts) -> Result<(), MtuError> {
+    network::check_host("mtu", &opts.host)?;
+    let addr = resolve(&opts.host, opts.port)?;
+    let min = opts.min.unwrap_or(if addr.is_ipv4() { 576 } else { 1280 });
+    check_range(min, opts.max, udp_overhead(&addr))?;
+    let timeout = Duration::from_millis(opts.time_out.clamp(50, 10_000));
+
+    let mut probes = Vec::new();
+    let mut found = None;
+    if !opts.tcp {
+        match df_probe(addr, min, opts.max, timeout, &mut probes) {
+            Ok(mtu) => found = mtu.map(|mtu| (MtuMethod::DfProbe, mtu)),
+            Err(error) => eprintln!("DF probe unavailable ({}), falling back to TCP MSS", error),
+        }
+    }
+    if found.is_none() {
+        let tcp_addr = SocketAddr::new(addr.ip(), opts.tcp_port);
+        found = Some((MtuMethod::TcpMss, tcp_mss_mtu(tcp_addr, timeout)?));
+    }
+    let (method, mtu) = found.expect("mtu method resolved above");
+
+    let overhead = udp_overhead(&addr);
+    let report = MtuReport {
+        host: opts.host,
+        addr: addr.ip().to_string(),
+        method,
+        mtu,
+        max_udp_payload: mtu.saturating_sub(overhead),
+        probes,
+    };
+
+    if !opts.record_output.keep(&report)? {
+        return Ok(());
+    }
+    if opts.json {
+        opts.record_output
+            .write_report(&report, None, io::stdout().lock())?;
+        return Ok(());
+    }
+
+    println!("Path MTU to {} ({})", report.host, report.addr);
+    for probe in &report.probes {
+        let state = match probe.outcome {
+            ProbeOutcome::Reached => "ok".to_string(),
+            ProbeOutcome::TooBig(Some(pmtu)) => format!("too big (path mtu {})", pmtu),
+            ProbeOutcome::TooBig(None) => "too big".to_string(),
+            ProbeOutcome::Silent => "no reply".to_string(),
+        };
+        println!("  {:>5} bytes  {}", probe.size, state);
+    }
+    match report.method {
+        MtuMethod::DfProbe => println!("\nPath MTU: {} bytes (DF probe)", report.mtu),
+        MtuMethod::TcpMss => println!("\nPath MTU: ~{} bytes (estimated from TCP MSS)", report.mtu),
+    }
+    println!(
+        "Largest unfragmented UDP payload: {} bytes",
+        report.max_udp_payload
+    );
+    Ok(())
+}
+
+fn resolve(host: &str, port: u16) -> Result<SocketAddr, MtuError> {
+    (host, port)
+        .to_socket_addrs()
+        .map_err(|e| MtuError::Resolve(format!("{}: {}", host, e)))?
+        .next()
+        .ok_or_else(|| MtuError::Resolve(host.to_string()))
+}
+
+fn udp_overhead(addr: &SocketAddr) -> u16 {
+    if addr.is_ipv4() {
+        IPV4_UDP_OVERHEAD
+    } else {
+        IPV6_UDP_OVERHEAD
+    }
+}
+
+// 上限必须大于 IP/UDP 头部长度，否则探测包连空负载都放不下
+fn check_range(min: u16, max: u16, overhead: u16) -> Result<(), MtuError> {
+    if min > max {
+        return Err(MtuError::InvalidRange(format!("{}-{}", min, max)));
+    }
+    if max <= overhead {
+        return Err(MtuError::InvalidRange(format!(
+            "max {} must be larger than the {}-byte header overhead",
+            max, overhead
+        )));
+    }
+    Ok(())
+}
+
+// 在 [min, max] 区间内寻找能未分片到达的最大包长。
+//
+// 先直接尝试上限，再从下限确认对端会回应，之后二分；内核返回的路径 MTU 会直接收窄上界。
+// 对端始终无回应时返回 `None`，由调用方回退到其它估算方式。
+pub fn search_mtu<F>(min: u16, max: u16, mut probe: F) -> Option<u16>
+where
+    F: FnMut(u16) -> ProbeOutcome,
+{
+    let mut hi = max;
+    match probe(hi) {
+        ProbeOutcome::Reached => return Some(hi),
+        ProbeOutcome::TooBig(Some(pmtu)) if pmtu >= min && pmtu < hi => hi = pmtu,
+        _ => hi = hi.saturating_sub(1),
+    }
+    if hi < min {
+        return None;
+    }
+    if hi != max && probe(hi) == ProbeOutcome::Reached {
+        return Some(hi);
+    }
+    if probe(min) != ProbeOutcome::Reached {
+        return None;
+    }
+
+    let mut lo = min;
+    while lo < hi {
+        let mid = lo + (hi - lo).div_ceil(2);
+        match probe(mid) {
+            ProbeOutcome::Reached => lo = mid,
+            ProbeOutcome::TooBig(Some(pmtu)) if pmtu >= lo && pmtu < mid => hi = pmtu,
+            _ => hi = mid - 1,
+        }
+    }
+    Some(lo)
+}
+
+#[cfg(target_os = "linux")]
+fn df_probe(
+    addr: SocketAddr,
+    min: u16,
+    max: u16,
+    timeout: Duration,
+    records: &mut Vec<ProbeRecord>,
+) -> Result<Option<u16>, MtuError> {
+    use std::net::UdpSocket;
+
+    let bind = if addr.is_ipv4() {
+        "0.0.0.0:0"
+    } else {
+        "[::]:0"
+    };
+    let socket = UdpSocket::bind(bind)?;
+    socket.connect(addr)?;
+    socket.set_read_timeout(Some(timeout))?;
+    linux::set_dont_fragment(&socket, addr.is_ipv4())?;
+
+    let overhead = udp_overhead(&addr);
+    let mut buf = [0u8; 2048];
+    let mut fatal = None;
+    let mtu = search_mtu(min.max(overhead + 1), max, |size| {
+        if fatal.is_some() {
+            return ProbeOutcome::Silent;
+        }
+        let payload = vec![0u8; (size - overhead) as usize];
+        let outcome = match socket.send(&payload) {
+            Err(e) if e.raw_os_error() == Some(libc::EMSGSIZE) => {
+                ProbeOutcome::TooBig(linux::path_mtu(&socket, addr.is_ipv4()))
+            }
+            Err(e) => {
+                fatal = Some(e);
+                ProbeOutcome::Silent
+            }
+            Ok(_) => match socket.recv(&mut buf) {
+                Ok(_) => ProbeOutcome::Reached,
+                Err(e) if e.kind() == io::ErrorKind::ConnectionRefused => ProbeOutcome::Reached,
+                Err(e) if e.raw_os_error() == Some(libc::EMSGSIZE) => {
+                    ProbeOutcome::TooBig(linux::path_mtu(&socket, addr.is_ipv4()))
+                }
+                Err(_) => match linux::path_mtu(&socket, addr.is_ipv4()) {
+                    Some(pmtu) if pmtu < size => ProbeOutcome::TooBig(Some(pmtu)),
+                    _ => ProbeOutcome::Silent,
+                },
+            },
+        };
+        records.push(ProbeRecord { size, outcome });
+        outcome
+    });
+    if let Some(error) = fatal {
+        return Err(MtuError::Io(error));
+    }
+    if mtu.is_some() {
+        return Ok(mtu);
+    }
+
+    // 对端静默丢弃 UDP 时，仍可使用内核从 ICMP Fragmentation Needed 学到的路径 MTU
+    Ok(linux::path_mtu(&socket, addr.is_ipv4()).filter(|pmtu| *pmtu < max))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn df_probe(
+    _addr: SocketAddr,
+    _min: u16,
+    _max: u16,
+    _timeout: Duration,
+    _records: &mut Vec<ProbeRecord>,
+) -> Result<Option<u16>, MtuError> {
+    Err(MtuError::Io(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "DF probing is only supported on Linux",
+    )))
+}
+
+#[cfg(unix)]
+fn tcp_mss_mtu(addr: SocketAddr, timeout: Duration) -> Result<u16, MtuError> {
+    use socket2::{Domain, Protocol, Socket, Type};
+
+    let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
+    socket
+        .connect_timeout(&addr.into(), timeout)
+        .map_err(|e| MtuError::Undetermined(format!("tcp connect to {} failed: {}", addr, e)))?;
+    Ok(mss_to_mtu(socket.tcp_mss()?, addr.is_ipv4()))
+}
+
+// MSS 加上 IP + TCP 头即为 MTU；异常大的 MSS（例如环回接口）截到 u16 上限
+#[cfg(unix)]
+fn mss_to_mtu(mss: u32, ipv4: bool) -> u16 {
+    let overhead = if ipv4 {
+        IPV4_TCP_OVERHEAD
+    } else {
+        IPV6_TCP_OVERHEAD
+    };
+    u16::try_from(mss.saturating_add(u32::from(overhead))).unwrap_or(u16::MAX)
+}
+
+#[cfg(not(unix))]
+fn tcp_mss_mtu(_addr: SocketAddr, _timeout: Duration) -> Result<u16, MtuError> {
+    Err(MtuError::Undetermined(
+        "TCP MSS fallback is not supported on this platform".to_string(),
+    ))
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::io;
+    use std::net::UdpSocket;
+    use std::os::fd::AsRawFd;
+
+    pub fn set_dont_fragment(socket: &UdpSocket, ipv4: bool) -> io::Result<()> {
+        let (level, name, value) = if ipv4 {
+            (
+                libc::IPPROTO_IP,
+                libc::IP_MTU_DISCOVER,
+                libc::IP_PMTUDISC_DO,
+            )
+        } else {
+            (
+                libc::IPPROTO_IPV6,
+                libc::IPV6_MTU_DISCOVER,
+                libc::IPV6_PMTUDISC_DO,
+            )
+        };
+        let ret = unsafe {
+            libc::setsockopt(
+                socket.as_raw_fd(),
+                level,
+                name,
+                &value as *const libc::c_int as *const libc::c_void,
+                std::mem::size_of::<libc::c_int>() as libc::socklen_t,
+            )
+        };
+        if ret == 0 {
+            Ok(())
+        } else {
+            Err(io::Error::last_os_error())
+        }
+    }
+
+    pub fn path_mtu(socket: &UdpSocket, ipv4: bool) -> Option<u16> {
+        let (level, name) = if ipv4 {
+            (libc::IPPROTO_IP, libc::IP_MTU)
+        } else {
+            (libc::IPPROTO_IPV6, libc::IPV6_MTU)
+        };
+        let mut value: libc::c_int = 0;
+        let mut len = std::mem::size_of::<libc::c_int>() as libc::socklen_t;
+        let ret = unsafe {
+            libc::getsockopt(
+                socket.as_raw_fd(),
+                level,
+                name,
+                &mut value as *mut libc::c_int as *mut libc::c_void,
+                &mut len,
+            )
+        };
+        if ret == 0 && value > 0 {
+            u16::try_from(value).ok()
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path(limit: u16, icmp: bool) -> impl FnMut(u16) -> ProbeOutcome {
+        move |size| {
+            if size <= limit {
+                ProbeOutcome::Reached
+            } else if icmp {
+                ProbeOutcome::TooBig(Some(limit))
+            } else {
+                ProbeOutcome::Silent
+            }
+        }
+    }
+
+    #[test]
+    fn full_size_path_needs_one_probe() {
+        let mut count = 0;
+        let mut probe = path(1500, true);
+        let mtu = search_mtu(576, 1500, |size| {
+            count += 1;
+            probe(size)
+        });
+        assert_eq!(mtu, Some(1500));
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn icmp_feedback_narrows_to_path_mtu() {
+        assert_eq!(search_mtu(576, 1500, path(1400, true)), Some(1400));
+    }
+
+    #[test]
+    fn black_hole_path_is_found_by_bisection() {
+        assert_eq!(search_mtu(576, 1500, path(1420, false)), Some(1420));
+        assert_eq!(search_mtu(576, 1500, path(576, false)), Some(576));
+    }
+
+    #[test]
+    fn range_must_leave_room_for_headers() {
+        assert!(check_range(576, 1500, IPV4_UDP_OVERHEAD).is_ok());
+        assert!(check_range(20, 20, IPV4_UDP_OVERHEAD).is_err());
+        assert!(check_range(0, IPV6_UDP_OVERHEAD, IPV6_UDP_OVERHEAD).is_err());
+        assert!(check_range(1500, 576, IPV4_UDP_OVERHEAD).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn mss_to_mtu_saturates() {
+        assert_eq!(mss_to_mtu(1460, true), 1500);
+        assert_eq!(mss_to_mtu(1440, false), 1500);
+        assert_eq!(mss_to_mtu(65_495, true), u16::MAX);
+        assert_eq!(mss_to_mtu(u32::MAX, false), u16::MAX);
+    }
+
+    #[test]
+    fn silent_target_yields_none() {
+        assert_eq!(search_mtu(576, 1500, |_| ProbeOutcome::Silent), None);
+    }
+}
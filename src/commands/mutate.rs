@@ -0,0 +1,579 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use chrono::{Duration, Local, NaiveDate};
+use clap::ValueEnum;
+use rand::rngs::StdRng;
+use rand::seq::IndexedRandom;
+use rand::{Rng, SeedableRng};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::utils::dataset::{read_records, text_of, DatasetError};
+use crate::utils::idcard::{checksum_char, validate_id_number, RegionCheck};
+use crate::utils::metrics;
+use crate::utils::output::RecordOutput;
+
+pub const MAX_MUTATE_COUNT: u32 = 1_000_000;
+
+// 身份证校验位的全部取值，checksum 变异从中挑一个错误的
+const CHECK_CHARS: [char; 11] = ['1', '0', 'X', '9', '8', '7', '6', '5', '4', '3', '2'];
+
+#[derive(clap::Args)]
+pub struct MutateOpts {
+    #[arg(
+        value_name = "FILE",
+        help = "源数据文件：CSV（首行为表头）、JSON 数组（.json）或 JSON Lines（.jsonl）"
+    )]
+    input: PathBuf,
+
+    #[arg(long, value_name = "FILE", help = "变异规则文件（YAML）")]
+    rules: PathBuf,
+
+    #[arg(
+        short = 'n',
+        long,
+        default_value_t = 100,
+        help = "输出记录数，源记录依次循环使用"
+    )]
+    count: u32,
+
+    #[arg(
+        long,
+        value_name = "N",
+        help = "随机种子，指定后相同输入和规则得到相同输出"
+    )]
+    seed: Option<u64>,
+
+    #[arg(value_enum, short = 't', long = "type", default_value_t = MutateFormat::Csv, help = "输出类型")]
+    output_type: MutateFormat,
+
+    #[arg(short = 'o', long = "output", help = "输出文件，默认写到 stdout")]
+    output: Option<PathBuf>,
+
+    // 全局 --where / --fields / --rename，由命令行入口注入
+    #[arg(skip)]
+    pub(crate) record_output: RecordOutput,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum MutateFormat {
+    Csv,
+    Json,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum MutateError {
+    #[error("count must be between 1 and {0}")]
+    InvalidCount(u32),
+    #[error("failed to read {path}: {source}")]
+    Read { path: PathBuf, source: io::Error },
+    #[error("invalid rules file: {0}")]
+    Rules(#[from] serde_yaml::Error),
+    #[error("rate for {field}:{kind} must be between 0 and 1, got {rate}")]
+    InvalidRate {
+        field: String,
+        kind: &'static str,
+        rate: f64,
+    },
+    #[error("field '{0}' in the rules does not exist in the source data")]
+    UnknownField(String),
+    #[error("source data has no records")]
+    NoRecords,
+    #[error(transparent)]
+    Dataset(#[from] DatasetError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+    #[error(transparent)]
+    Csv(#[from] csv::Error),
+}
+
+// 规则文件：mutations 按顺序对每条输出记录逐条掷骰子，validate 指定用哪个校验器给字段打标签
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Rules {
+    mutations: Vec<MutationRule>,
+    #[serde(default)]
+    validate: BTreeMap<String, FieldKind>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MutationRule {
+    field: String,
+    kind: MutationKind,
+    // 每条记录应用该变异的概率
+    #[serde(default = "default_rate")]
+    rate: f64,
+}
+
+fn default_rate() -> f64 {
+    1.0
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum MutationKind {
+    // 删除、替换、重复或交换一个字符
+    Typo,
+    // 交换两个相邻的不同数字
+    SwapDigits,
+    // 改错校验位（身份证号末位，其它数字串的末位数字）
+    Checksum,
+    // 日期换成边界值：闰日、非法月日、1900 年前、未来日期等；身份证号改其中的出生日期并重算校验位
+    BoundaryDate,
+    // 截掉末尾 1-3 个字符
+    Truncate,
+    Empty,
+    // 首尾或中间插入空格
+    Whitespace,
+}
+
+impl MutationKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            MutationKind::Typo => "typo",
+            MutationKind::SwapDigits => "swap_digits",
+            MutationKind::Checksum => "checksum",
+            MutationKind::BoundaryDate => "boundary_date",
+            MutationKind::Truncate => "truncate",
+            MutationKind::Empty => "empty",
+            MutationKind::Whitespace => "whitespace",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum FieldKind {
+    // 18 / 15 位身份证号，地区码必须存在
+    IdNumber,
+    // 合法日期，YYYY-MM-DD 或 YYYYMMDD
+    Date,
+    // 合法日期且在 1900-01-01 到今天之间
+    Birthday,
+    // 11 位大陆手机号
+    Mobile,
+    Email,
+}
+
+pub fn run_mutate(opts: MutateOpts) -> Result<(), MutateError> {
+    if opts.count == 0 || opts.count > MAX_MUTATE_COUNT {
+        return Err(MutateError::InvalidCount(MAX_MUTATE_COUNT));
+    }
+    let rules_text = fs::read_to_string(&opts.rules).map_err(|source| MutateError::Read {
+        path: opts.rules.clone(),
+        source,
+    })?;
+    let rules: Rules = serde_yaml::from_str(&rules_text)?;
+    let sources = read_records(&opts.input)?;
+    if sources.is_empty() {
+        return Err(MutateError::NoRecords);
+    }
+    check_rules(&rules, &sources)?;
+
+    let mut rng = match opts.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_os_rng(),
+    };
+    let today = Local::now().date_naive();
+    let mut records: Vec<Value> = (0..opts.count as usize)
+        .map(|index| {
+            let source = sources[index % sources.len()].clone();
+            Value::Object(mutate_record(source, &rules, today, &mut rng))
+        })
+        .collect();
+    metrics::records_generated("mutate", records.len());
+    opts.record_output.retain(&mut records)?;
+
+    match &opts.output {
+        Some(path) => write_records(
+            &records,
+            opts.output_type,
+            &opts.record_output,
+            File::create(path)?,
+        ),
+        None => write_records(
+            &records,
+            opts.output_type,
+            &opts.record_output,
+            io::stdout().lock(),
+        ),
+    }
+}
+
+fn check_rules(rules: &Rules, sources: &[Map<String, Value>]) -> Result<(), MutateError> {
+    for rule in &rules.mutations {
+        if !(0.0..=1.0).contains(&rule.rate) {
+            return Err(MutateError::InvalidRate {
+                field: rule.field.clone(),
+                kind: rule.kind.as_str(),
+                rate: rule.rate,
+            });
+        }
+    }
+    let fields = rules
+        .mutations
+        .iter()
+        .map(|rule| &rule.field)
+        .chain(rules.validate.keys());
+    for field in fields {
+        if !sources[0].contains_key(field) {
+            return Err(MutateError::UnknownField(field.clone()));
+        }
+    }
+    Ok(())
+}
+
+// 对一条源记录按规则变异，并追加 mutations / valid / errors 三个标签字段
+fn mutate_record(
+    mut record: Map<String, Value>,
+    rules: &Rules,
+    today: NaiveDate,
+    rng: &mut impl Rng,
+) -> Map<String, Value> {
+    let mut applied = Vec::new();
+    for rule in &rules.mutations {
+        if !rng.random_bool(rule.rate) {
+            continue;
+        }
+        let Some(value) = record.get_mut(&rule.field) else {
+            continue;
+        };
+        let original = text_of(value);
+        // 不适用的变异（例如没有可交换的数字）不记录
+        if let Some(mutated) = mutate(&original, rule.kind, today, rng) {
+            if mutated != original {
+                *value = Value::String(mutated);
+                applied.push(Value::String(format!(
+                    "{}:{}",
+                    rule.field,
+                    rule.kind.as_str()
+                )));
+            }
+        }
+    }
+    let errors: Vec<Value> = rules
+        .validate
+        .iter()
+        .filter_map(|(field, kind)| {
+            let value = text_of(record.get(field).unwrap_or(&Value::Null));
+            check_field(&value, *kind, today)
+                .err()
+                .map(|reason| Value::String(format!("{}:{}", field, reason)))
+        })
+        .collect();
+    record.insert("mutations".into(), Value::Array(applied));
+    record.insert("valid".into(), Value::Bool(errors.is_empty()));
+    record.insert("errors".into(), Value::Array(errors));
+    record
+}
+
+fn mutate(value: &str, kind: MutationKind, today: NaiveDate, rng: &mut impl Rng) -> Option<String> {
+    let mut chars: Vec<char> = value.chars().collect();
+    match kind {
+        MutationKind::Typo => {
+            if chars.is_empty() {
+                return None;
+            }
+            let index = rng.random_range(0..chars.len());
+            match rng.random_range(0..4) {
+                0 if chars.len() > 1 => {
+                    chars.remove(index);
+                }
+                1 => chars.insert(index, chars[index]),
+                2 if index + 1 < chars.len() && chars[index] != chars[index + 1] => {
+                    chars.swap(index, index + 1)
+                }
+                _ => chars[index] = replace_char(chars[index], rng),
+            }
+        }
+        MutationKind::SwapDigits => {
+            let candidates: Vec<usize> = (0..chars.len().saturating_sub(1))
+                .filter(|&i| {
+                    chars[i].is_ascii_digit()
+                        && chars[i + 1].is_ascii_digit()
+                        && chars[i] != chars[i + 1]
+                })
+                .collect();
+            let index = *candidates.choose(rng)?;
+            chars.swap(index, index + 1);
+        }
+        MutationKind::Checksum => {
+            let last = chars.last_mut()?;
+            if is_id18(value) {
+                let wrong: Vec<char> = CHECK_CHARS
+                    .into_iter()
+                    .filter(|c| *c != last.to_ascii_uppercase())
+                    .collect();
+                *last = *wrong.choose(rng)?;
+            } else if last.is_ascii_digit() {
+                *last = replace_char(*last, rng);
+            } else {
+                return None;
+            }
+        }
+        MutationKind::BoundaryDate => {
+            let boundary = boundary_date(today, rng);
+            if is_id18(value) {
+                let id17 = format!(
+                    "{}{}{}",
+                    &value[..6],
+                    boundary.replace('-', ""),
+                    &value[14..17]
+                );
+                return Some(format!("{}{}", id17, checksum_char(&id17)));
+            }
+            parse_date(value)?;
+            return Some(if value.contains('-') {
+                boundary
+            } else {
+                boundary.replace('-', "")
+            });
+        }
+        MutationKind::Truncate => {
+            if chars.is_empty() {
+                return None;
+            }
+            let cut = rng.random_range(1..=chars.len().min(3));
+            chars.truncate(chars.len() - cut);
+        }
+        MutationKind::Empty => return Some(String::new()),
+        MutationKind::Whitespace => match rng.random_range(0..3) {
+            0 => chars.insert(0, ' '),
+            1 => chars.push(' '),
+            _ => chars.insert(rng.random_range(0..=chars.len()), ' '),
+        },
+    }
+    Some(chars.into_iter().collect())
+}
+
+// 同类字符替换：数字换数字、字母换同大小写字母，其它字符换成相邻码位
+fn replace_char(c: char, rng: &mut impl Rng) -> char {
+    let pool: &[u8] = if c.is_ascii_digit() {
+        b"0123456789"
+    } else if c.is_ascii_lowercase() {
+        b"abcdefghijklmnopqrstuvwxyz"
+    } else if c.is_ascii_uppercase() {
+        b"ABCDEFGHIJKLMNOPQRSTUVWXYZ"
+    } else {
+        return char::from_u32(c as u32 + 1).unwrap_or('?');
+    };
+    loop {
+        let replacement = char::from(pool[rng.random_range(0..pool.len())]);
+        if replacement != c {
+            return replacement;
+        }
+    }
+}
+
+fn boundary_date(today: NaiveDate, rng: &mut impl Rng) -> String {
+    let future = (today + Duration::days(1)).format("%Y-%m-%d").to_string();
+    let candidates = [
+        "2000-02-29".to_string(),
+        "2023-02-29".to_string(),
+        "1900-01-01".to_string(),
+        "1899-12-31".to_string(),
+        "1999-12-31".to_string(),
+        "2024-04-31".to_string(),
+        "2024-13-01".to_string(),
+        "2024-00-10".to_string(),
+        future,
+    ];
+    candidates.choose(rng).cloned().unwrap_or_default()
+}
+
+fn is_id18(value: &str) -> bool {
+    value.len() == 18 && value.is_ascii() && value[..17].bytes().all(|b| b.is_ascii_digit())
+}
+
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .or_else(|_| NaiveDate::parse_from_str(value, "%Y%m%d"))
+        .ok()
+}
+
+// 返回不合法的原因，用于 errors 标签
+fn check_field(value: &str, kind: FieldKind, today: NaiveDate) -> Result<(), &'static str> {
+    if value.is_empty() {
+        return Err("empty");
+    }
+    // 校验器本身会去掉首尾空白，这里把多余的空白单独标出来
+    if value.trim() != value {
+        return Err("whitespace");
+    }
+    match kind {
+        FieldKind::IdNumber => validate_id_number(value, RegionCheck::Strict)
+            .map(|_| ())
+            .map_err(|invalid| invalid.reason.as_str()),
+        FieldKind::Date => parse_date(value).map(|_| ()).ok_or("date"),
+        FieldKind::Birthday => match parse_date(value) {
+            Some(date) if date > today => Err("future"),
+            Some(date) if date < NaiveDate::from_ymd_opt(1900, 1, 1).unwrap_or(date) => {
+                Err("too_old")
+            }
+            Some(_) => Ok(()),
+            None => Err("date"),
+        },
+        FieldKind::Mobile => {
+            let bytes = value.as_bytes();
+            let ok = bytes.len() == 11
+                && bytes[0] == b'1'
+                && (b'3'..=b'9').contains(&bytes[1])
+                && bytes.iter().all(u8::is_ascii_digit);
+            ok.then_some(()).ok_or("format")
+        }
+        FieldKind::Email => {
+            let ok = value.split_once('@').is_some_and(|(local, domain)| {
+                !local.is_empty()
+                    && !domain.contains('@')
+                    && domain.contains('.')
+                    && !domain.starts_with('.')
+                    && !domain.ends_with('.')
+                    && !value.contains(char::is_whitespace)
+            });
+            ok.then_some(()).ok_or("format")
+        }
+    }
+}
+
+fn write_records<W: Write>(
+    records: &[Value],
+    format: MutateFormat,
+    output: &RecordOutput,
+    writer: W,
+) -> Result<(), MutateError> {
+    match format {
+        // 列为源数据字段加上标签字段，数组写成以 ; 分隔的单元格
+        MutateFormat::Csv => output.write_csv(records, writer)?,
+        MutateFormat::Json => output.write_json(records, writer)?,
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::idcard::compose_id_number;
+
+    fn rules(yaml: &str) -> Rules {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn test_mutations_are_labelled_by_validators() {
+        let today = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+        let birthday = NaiveDate::from_ymd_opt(1990, 5, 20).unwrap();
+        let id = compose_id_number("110101", birthday, 123);
+        let source: Map<String, Value> = serde_json::from_value(serde_json::json!({
+            "name": "张三",
+            "id_number": id,
+            "birthday": "1990-05-20",
+            "phone": "13800138000",
+        }))
+        .unwrap();
+        let mut rng = StdRng::seed_from_u64(7);
+
+        // 未变异的记录全部通过校验
+        let clean = rules(
+            "mutations: []\nvalidate:\n  id_number: id_number\n  birthday: birthday\n  phone: mobile\n",
+        );
+        let record = mutate_record(source.clone(), &clean, today, &mut rng);
+        assert_eq!(record["valid"], true);
+        assert_eq!(record["mutations"], serde_json::json!([]));
+
+        let checksum = rules(
+            "mutations:\n  - field: id_number\n    kind: checksum\nvalidate:\n  id_number: id_number\n",
+        );
+        for _ in 0..20 {
+            let record = mutate_record(source.clone(), &checksum, today, &mut rng);
+            assert_eq!(
+                record["mutations"],
+                serde_json::json!(["id_number:checksum"])
+            );
+            assert_eq!(record["errors"], serde_json::json!(["id_number:checksum"]));
+            assert_eq!(record["valid"], false);
+        }
+
+        // 身份证号里的出生日期换成边界值后重算校验位，只会因日期不合法报错
+        let boundary = rules(
+            "mutations:\n  - field: id_number\n    kind: boundary_date\n  - field: birthday\n    kind: boundary_date\nvalidate:\n  id_number: id_number\n  birthday: birthday\n",
+        );
+        for _ in 0..50 {
+            let record = mutate_record(source.clone(), &boundary, today, &mut rng);
+            let id = record["id_number"].as_str().unwrap();
+            let id_valid = validate_id_number(id, RegionCheck::Strict);
+            assert_ne!(
+                id_valid.as_ref().map_err(|e| e.reason.as_str()),
+                Err("checksum"),
+                "{}",
+                id
+            );
+            let errors = record["errors"].as_array().unwrap();
+            assert_eq!(errors.is_empty(), record["valid"] == true);
+        }
+
+        let never = rules("mutations:\n  - field: phone\n    kind: empty\n    rate: 0\n");
+        let record = mutate_record(source.clone(), &never, today, &mut rng);
+        assert_eq!(record["phone"], "13800138000");
+        assert!(matches!(
+            check_rules(
+                &rules("mutations:\n  - field: nope\n    kind: typo\n"),
+                std::slice::from_ref(&source)
+            ),
+            Err(MutateError::UnknownField(_))
+        ));
+        assert!(matches!(
+            check_rules(
+                &rules("mutations:\n  - field: phone\n    kind: typo\n    rate: 2\n"),
+                &[source]
+            ),
+            Err(MutateError::InvalidRate { .. })
+        ));
+    }
+
+    #[test]
+    fn test_single_mutations() {
+        let today = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..50 {
+            let typo = mutate("hello", MutationKind::Typo, today, &mut rng).unwrap();
+            assert_ne!(typo, "hello");
+            let swapped = mutate("1234", MutationKind::SwapDigits, today, &mut rng).unwrap();
+            let mut sorted: Vec<char> = swapped.chars().collect();
+            sorted.sort();
+            assert_eq!(sorted, ['1', '2', '3', '4']);
+            assert_ne!(swapped, "1234");
+            let truncated = mutate("abcdef", MutationKind::Truncate, today, &mut rng).unwrap();
+            assert!("abcdef".starts_with(&truncated) && truncated.len() >= 3);
+            let spaced = mutate("abc", MutationKind::Whitespace, today, &mut rng).unwrap();
+            assert!(check_field(&spaced, FieldKind::Email, today).is_err());
+        }
+        assert_eq!(
+            mutate("1111", MutationKind::SwapDigits, today, &mut rng),
+            None
+        );
+        assert_eq!(mutate("abc", MutationKind::Checksum, today, &mut rng), None);
+        assert_eq!(
+            mutate("n/a", MutationKind::BoundaryDate, today, &mut rng),
+            None
+        );
+        assert_eq!(
+            check_field("2024-06-02", FieldKind::Birthday, today),
+            Err("future")
+        );
+        assert_eq!(
+            check_field("2023-02-29", FieldKind::Date, today),
+            Err("date")
+        );
+        assert_eq!(check_field("a@b.cn", FieldKind::Email, today), Ok(()));
+        assert_eq!(
+            check_field("12800138000", FieldKind::Mobile, today),
+            Err("format")
+        );
+    }
+}
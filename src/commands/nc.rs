@@ -0,0 +1,475 @@
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream, UdpSocket};
+use tokio::time::Instant;
+
+use crate::utils::format::{hex_dump, human_bytes};
+use crate::utils::network::{self, NetworkError};
+
+const CHUNK_SIZE: usize = 16 * 1024;
+// 单个 UDP 数据报的上限
+const MAX_DATAGRAM: usize = 65_535;
+
+#[derive(clap::Args)]
+pub struct NcOpts {
+    #[arg(
+        value_name = "HOST PORT",
+        num_args = 0..=2,
+        help = "连接模式为目标主机和端口；监听模式可省略，或只给端口"
+    )]
+    target: Vec<String>,
+
+    #[arg(short = 'l', long, help = "监听模式：等待对端连接")]
+    listen: bool,
+
+    #[arg(
+        short = 'p',
+        long,
+        value_name = "PORT",
+        help = "监听端口，也可以作为监听模式的位置参数给出"
+    )]
+    port: Option<u16>,
+
+    #[arg(
+        short = 'b',
+        long,
+        default_value = "0.0.0.0",
+        value_name = "ADDR",
+        help = "监听地址"
+    )]
+    bind: IpAddr,
+
+    #[arg(short = 'u', long, help = "使用 UDP，默认 TCP")]
+    udp: bool,
+
+    #[arg(
+        short = 'k',
+        long = "keep-open",
+        requires = "listen",
+        help = "TCP 监听模式下对端断开后继续等待下一个连接（逐个处理）"
+    )]
+    keep_open: bool,
+
+    #[arg(
+        long = "send-file",
+        value_name = "FILE",
+        help = "发送文件内容代替 stdin，发送完毕后关闭写方向；-k 时每个连接都会重新发送"
+    )]
+    send_file: Option<PathBuf>,
+
+    #[arg(
+        long,
+        help = "在 stderr 以十六进制输出收发的数据，stdout 仍输出原始数据"
+    )]
+    hex: bool,
+
+    #[arg(
+        short = 'w',
+        long,
+        default_value_t = 5,
+        value_name = "SECS",
+        help = "TCP 连接超时；UDP 连接模式下输入结束后等待回复的时间"
+    )]
+    wait: u64,
+
+    #[arg(short = 'q', long, help = "不在 stderr 输出连接信息和传输统计")]
+    quiet: bool,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum NcError {
+    #[error("expected HOST PORT, e.g. rtoolkit nc example.com 80")]
+    MissingTarget,
+    #[error("listen mode needs a port, e.g. rtoolkit nc -l -p 9000")]
+    MissingPort,
+    #[error("invalid port '{0}'")]
+    InvalidPort(String),
+    #[error("cannot resolve {0}")]
+    Resolve(String),
+    #[error("connection to {addr} timed out after {secs} s")]
+    Timeout { addr: String, secs: u64 },
+    #[error("failed to connect to {addr}: {source}")]
+    Connect { addr: String, source: io::Error },
+    #[error("failed to bind {addr}: {source}")]
+    Bind { addr: SocketAddr, source: io::Error },
+    #[error("cannot open {path}: {source}")]
+    OpenFile { path: PathBuf, source: io::Error },
+    #[error(transparent)]
+    Network(#[from] NetworkError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+// 发送给对端的数据来源；stdin 只能读一次，-k 时只有第一个连接能收到
+enum Input {
+    Stdin,
+    File(PathBuf),
+    None,
+}
+
+impl Input {
+    async fn open(&self) -> Result<Option<Box<dyn AsyncRead + Unpin + Send>>, NcError> {
+        Ok(match self {
+            Input::Stdin => Some(Box::new(tokio::io::stdin())),
+            Input::File(path) => {
+                let file =
+                    tokio::fs::File::open(path)
+                        .await
+                        .map_err(|source| NcError::OpenFile {
+                            path: path.clone(),
+                            source,
+                        })?;
+                Some(Box::new(file))
+            }
+            Input::None => None,
+        })
+    }
+}
+
+// 两个方向的字节计数，--hex 时兼作偏移量
+#[derive(Default)]
+struct Traffic {
+    sent: AtomicU64,
+    received: AtomicU64,
+}
+
+impl Traffic {
+    fn record(&self, outgoing: bool, data: &[u8], hex: bool) {
+        let counter = if outgoing { &self.sent } else { &self.received };
+        let offset = counter.fetch_add(data.len() as u64, Ordering::Relaxed);
+        if hex {
+            let arrow = if outgoing { '>' } else { '<' };
+            eprint!("{} {} bytes\n{}", arrow, data.len(), hex_dump(data, offset));
+        }
+    }
+
+    fn summary(&self) -> String {
+        format!(
+            "Sent {}, received {}",
+            human_bytes(self.sent.load(Ordering::Relaxed)),
+            human_bytes(self.received.load(Ordering::Relaxed))
+        )
+    }
+}
+
+pub fn run_nc(opts: NcOpts) -> Result<(), NcError> {
+    let rt = tokio::runtime::Runtime::new()?;
+    let result = rt.block_on(nc(opts));
+    // 读 stdin 的阻塞线程可能还在等待输入，不等它结束
+    rt.shutdown_background();
+    result
+}
+
+async fn nc(opts: NcOpts) -> Result<(), NcError> {
+    let input = match &opts.send_file {
+        Some(path) => Input::File(path.clone()),
+        None => Input::Stdin,
+    };
+    if opts.listen {
+        let port = match (opts.port, opts.target.as_slice()) {
+            (Some(port), _) => port,
+            (None, [.., port]) => parse_port(port)?,
+            (None, []) => return Err(NcError::MissingPort),
+        };
+        network::check_addr("nc --listen", opts.bind)?;
+        let addr = SocketAddr::new(opts.bind, port);
+        return if opts.udp {
+            listen_udp(addr, input, &opts).await
+        } else {
+            listen_tcp(addr, input, &opts).await
+        };
+    }
+
+    let [host, port] = opts.target.as_slice() else {
+        return Err(NcError::MissingTarget);
+    };
+    let port = parse_port(port)?;
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    network::check_host("nc", host)?;
+    let addr = tokio::net::lookup_host((host, port))
+        .await
+        .ok()
+        .and_then(|mut addrs| addrs.next())
+        .ok_or_else(|| NcError::Resolve(host.to_string()))?;
+    if opts.udp {
+        connect_udp(addr, input, &opts).await
+    } else {
+        let wait = Duration::from_secs(opts.wait.max(1));
+        let stream = tokio::time::timeout(wait, TcpStream::connect(addr))
+            .await
+            .map_err(|_| NcError::Timeout {
+                addr: addr.to_string(),
+                secs: opts.wait.max(1),
+            })?
+            .map_err(|source| NcError::Connect {
+                addr: addr.to_string(),
+                source,
+            })?;
+        if !opts.quiet {
+            eprintln!("Connected to {}", addr);
+        }
+        let traffic = relay_tcp(stream, &input, opts.hex).await?;
+        if !opts.quiet {
+            eprintln!("{}", traffic.summary());
+        }
+        Ok(())
+    }
+}
+
+fn parse_port(raw: &str) -> Result<u16, NcError> {
+    raw.parse::<u16>()
+        .ok()
+        .filter(|port| *port != 0)
+        .ok_or_else(|| NcError::InvalidPort(raw.to_string()))
+}
+
+async fn listen_tcp(addr: SocketAddr, mut input: Input, opts: &NcOpts) -> Result<(), NcError> {
+    let listener = TcpListener::bind(addr)
+        .await
+        .map_err(|source| NcError::Bind { addr, source })?;
+    if !opts.quiet {
+        eprintln!("Listening on {}", listener.local_addr()?);
+    }
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        if !opts.quiet {
+            eprintln!("Connection from {}", peer);
+        }
+        let result = relay_tcp(stream, &input, opts.hex).await;
+        if !opts.keep_open {
+            let traffic = result?;
+            if !opts.quiet {
+                eprintln!("{}", traffic.summary());
+            }
+            return Ok(());
+        }
+        // -k 时单个连接出错不影响后续连接
+        match result {
+            Ok(traffic) if !opts.quiet => eprintln!("{}", traffic.summary()),
+            Ok(_) => {}
+            Err(error) => eprintln!("Connection from {} failed: {}", peer, error),
+        }
+        if matches!(input, Input::Stdin) {
+            input = Input::None;
+        }
+    }
+}
+
+// stdin（或文件）发往对端，结束后半关闭写方向；对端关闭连接时结束
+async fn relay_tcp(stream: TcpStream, input: &Input, hex: bool) -> Result<Arc<Traffic>, NcError> {
+    let traffic = Arc::new(Traffic::default());
+    let (mut reader, mut writer) = stream.into_split();
+    let source = input.open().await?;
+    let sender = {
+        let traffic = traffic.clone();
+        tokio::spawn(async move {
+            let Some(mut source) = source else {
+                // 没有输入时保持写方向打开，直到对端关闭
+                std::future::pending::<()>().await;
+                return Ok(());
+            };
+            copy_out(&mut source, &mut writer, &traffic, hex).await?;
+            writer.shutdown().await
+        })
+    };
+
+    let mut stdout = tokio::io::stdout();
+    let mut buf = vec![0u8; CHUNK_SIZE];
+    let received = loop {
+        let n = match reader.read(&mut buf).await {
+            Ok(0) => break Ok(()),
+            Ok(n) => n,
+            Err(error) => break Err(error),
+        };
+        traffic.record(false, &buf[..n], hex);
+        stdout.write_all(&buf[..n]).await?;
+        stdout.flush().await?;
+    };
+    // 对端已关闭，仍在发送的数据没有意义
+    if sender.is_finished() {
+        if let Ok(Err(error)) = sender.await {
+            if error.kind() != io::ErrorKind::BrokenPipe {
+                return Err(error.into());
+            }
+        }
+    } else {
+        sender.abort();
+    }
+    received?;
+    Ok(traffic)
+}
+
+async fn copy_out<R, W>(
+    source: &mut R,
+    sink: &mut W,
+    traffic: &Traffic,
+    hex: bool,
+) -> io::Result<()>
+where
+    R: AsyncRead + Unpin + ?Sized,
+    W: AsyncWrite + Unpin,
+{
+    let mut buf = vec![0u8; CHUNK_SIZE];
+    loop {
+        let n = source.read(&mut buf).await?;
+        if n == 0 {
+            return Ok(());
+        }
+        traffic.record(true, &buf[..n], hex);
+        sink.write_all(&buf[..n]).await?;
+    }
+}
+
+// 每次读到的输入作为一个数据报发送；输入结束后再等待 wait 秒的回复，期间有回复则重新计时
+async fn connect_udp(addr: SocketAddr, input: Input, opts: &NcOpts) -> Result<(), NcError> {
+    let local: IpAddr = if addr.is_ipv4() {
+        Ipv4Addr::UNSPECIFIED.into()
+    } else {
+        Ipv6Addr::UNSPECIFIED.into()
+    };
+    let socket = UdpSocket::bind((local, 0)).await?;
+    socket
+        .connect(addr)
+        .await
+        .map_err(|source| NcError::Connect {
+            addr: addr.to_string(),
+            source,
+        })?;
+    if !opts.quiet {
+        eprintln!("Sending to {} (udp)", addr);
+    }
+    let traffic = Traffic::default();
+    let wait = Duration::from_secs(opts.wait);
+    let mut source = input.open().await?;
+    let mut deadline: Option<Instant> = None;
+    let mut out_buf = vec![0u8; MAX_DATAGRAM];
+    let mut in_buf = vec![0u8; MAX_DATAGRAM];
+    let mut stdout = tokio::io::stdout();
+    loop {
+        tokio::select! {
+            read = async { source.as_mut().expect("guarded by select").read(&mut out_buf).await }, if source.is_some() => {
+                let n = read?;
+                if n == 0 {
+                    source = None;
+                    deadline = Some(Instant::now() + wait);
+                    continue;
+                }
+                traffic.record(true, &out_buf[..n], opts.hex);
+                socket.send(&out_buf[..n]).await?;
+            }
+            received = socket.recv(&mut in_buf) => {
+                let n = match received {
+                    Ok(n) => n,
+                    // 对端端口不可达时内核返回 ICMP 错误，提示后继续
+                    Err(error) if error.kind() == io::ErrorKind::ConnectionRefused => {
+                        if !opts.quiet {
+                            eprintln!("{}: port unreachable", addr);
+                        }
+                        continue;
+                    }
+                    Err(error) => return Err(error.into()),
+                };
+                traffic.record(false, &in_buf[..n], opts.hex);
+                stdout.write_all(&in_buf[..n]).await?;
+                stdout.flush().await?;
+                if deadline.is_some() {
+                    deadline = Some(Instant::now() + wait);
+                }
+            }
+            _ = async { tokio::time::sleep_until(deadline.expect("guarded by select")).await }, if deadline.is_some() => break,
+        }
+    }
+    if !opts.quiet {
+        eprintln!("{}", traffic.summary());
+    }
+    Ok(())
+}
+
+// 第一个发来数据报的地址作为对端，之后只接收它的数据，输入也发给它；按 Ctrl-C 结束
+async fn listen_udp(addr: SocketAddr, input: Input, opts: &NcOpts) -> Result<(), NcError> {
+    let socket = UdpSocket::bind(addr)
+        .await
+        .map_err(|source| NcError::Bind { addr, source })?;
+    if !opts.quiet {
+        eprintln!("Listening on {} (udp)", socket.local_addr()?);
+    }
+    let traffic = Traffic::default();
+    let mut source = input.open().await?;
+    let mut peer: Option<SocketAddr> = None;
+    let mut out_buf = vec![0u8; MAX_DATAGRAM];
+    let mut in_buf = vec![0u8; MAX_DATAGRAM];
+    let mut stdout = tokio::io::stdout();
+    loop {
+        tokio::select! {
+            read = async { source.as_mut().expect("guarded by select").read(&mut out_buf).await }, if source.is_some() && peer.is_some() => {
+                let n = read?;
+                if n == 0 {
+                    source = None;
+                    continue;
+                }
+                traffic.record(true, &out_buf[..n], opts.hex);
+                socket.send_to(&out_buf[..n], peer.expect("guarded by select")).await?;
+            }
+            received = socket.recv_from(&mut in_buf) => {
+                let (n, from) = match received {
+                    Ok(received) => received,
+                    Err(error) if error.kind() == io::ErrorKind::ConnectionReset => continue,
+                    Err(error) => return Err(error.into()),
+                };
+                match peer {
+                    None => {
+                        if !opts.quiet {
+                            eprintln!("Connection from {}", from);
+                        }
+                        peer = Some(from);
+                    }
+                    Some(peer) if peer != from => continue,
+                    Some(_) => {}
+                }
+                traffic.record(false, &in_buf[..n], opts.hex);
+                stdout.write_all(&in_buf[..n]).await?;
+                stdout.flush().await?;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tcp_relay_round_trip() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            let server = tokio::spawn(async move {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = Vec::new();
+                stream.read_to_end(&mut request).await.unwrap();
+                stream.write_all(b"pong").await.unwrap();
+                request
+            });
+
+            let path = std::env::temp_dir().join(format!("rtoolkit-nc-{}.txt", std::process::id()));
+            std::fs::write(&path, b"ping").unwrap();
+            let stream = TcpStream::connect(addr).await.unwrap();
+            let traffic = relay_tcp(stream, &Input::File(path.clone()), false)
+                .await
+                .unwrap();
+            std::fs::remove_file(&path).unwrap();
+
+            // 发送完文件后半关闭，服务端才能读到 EOF 并回复
+            assert_eq!(server.await.unwrap(), b"ping");
+            assert_eq!(traffic.sent.load(Ordering::Relaxed), 4);
+            assert_eq!(traffic.received.load(Ordering::Relaxed), 4);
+        });
+    }
+}
@@ -0,0 +1,476 @@
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use chrono::{DateTime, Local};
+use serde::Serialize;
+
+use crate::utils::a11y;
+use crate::utils::network::{self, NetworkError};
+use crate::utils::output::RecordOutput;
+
+const DEFAULT_SERVER: &str = "pool.ntp.org";
+const NTP_PORT: u16 = 123;
+const PACKET_LEN: usize = 48;
+// 1900-01-01 到 1970-01-01 的秒数
+const UNIX_OFFSET: u64 = 2_208_988_800;
+const VERSION: u8 = 4;
+const MODE_CLIENT: u8 = 3;
+const MODE_SERVER: u8 = 4;
+
+#[derive(clap::Args)]
+pub struct NtpOpts {
+    #[arg(
+        value_name = "SERVER",
+        default_value = DEFAULT_SERVER,
+        help = "NTP 服务器，可带端口，例如 ntp.aliyun.com、time.apple.com、192.168.1.1:123"
+    )]
+    server: String,
+
+    #[arg(
+        short = 'n',
+        long,
+        value_name = "N",
+        default_value_t = 1,
+        value_parser = clap::value_parser!(u32).range(1..=16),
+        help = "查询次数，间隔 1 秒，取往返延迟最小的一次计算偏差"
+    )]
+    samples: u32,
+
+    #[arg(
+        long = "max-offset",
+        value_name = "MS",
+        help = "允许的最大时钟偏差（毫秒），超过时以非零状态退出，适合监控脚本"
+    )]
+    max_offset: Option<f64>,
+
+    #[arg(
+        long = "timeout",
+        value_name = "MS",
+        default_value_t = 3000,
+        help = "每次查询等待应答的时间（毫秒）"
+    )]
+    time_out: u64,
+
+    #[arg(long, help = "以 JSON 输出结果")]
+    json: bool,
+
+    // 全局 --where / --fields / --rename，由命令行入口注入
+    #[arg(skip)]
+    pub(crate) record_output: RecordOutput,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum NtpError {
+    #[error("invalid server '{0}'")]
+    InvalidServer(String),
+    #[error("cannot resolve {0}")]
+    Resolve(String),
+    #[error("no reply from {server} within {timeout_ms} ms")]
+    Timeout { server: SocketAddr, timeout_ms: u64 },
+    #[error("{0} refused the query, no NTP server is listening on that port")]
+    Refused(SocketAddr),
+    #[error("invalid reply from {server}: {reason}")]
+    InvalidReply { server: SocketAddr, reason: String },
+    #[error("{server} sent kiss-o'-death {code}, try again later or use another server")]
+    KissOfDeath { server: SocketAddr, code: String },
+    #[error("{0} is not synchronized (leap indicator alarm)")]
+    Unsynchronized(SocketAddr),
+    #[error("clock offset {offset} exceeds --max-offset {max} ms")]
+    OffsetExceeded { offset: String, max: f64 },
+    #[error(transparent)]
+    Network(#[from] NetworkError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("json serialize failed: {0}")]
+    Serialize(#[from] serde_json::Error),
+}
+
+// 服务器应答中用到的字段，时间戳为 NTP 的 32.32 定点格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct NtpPacket {
+    leap: u8,
+    version: u8,
+    mode: u8,
+    stratum: u8,
+    precision: i8,
+    root_delay: u32,
+    root_dispersion: u32,
+    reference_id: [u8; 4],
+    origin: u64,
+    receive: u64,
+    transmit: u64,
+}
+
+// 一次查询：offset 为服务器时间减本机时间，正值表示本机时钟偏慢
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Sample {
+    offset: f64,
+    delay: f64,
+    packet: NtpPacket,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NtpReport {
+    pub server: String,
+    pub address: String,
+    pub stratum: u8,
+    pub reference: String,
+    pub leap: &'static str,
+    pub version: u8,
+    pub precision_us: f64,
+    pub root_delay_ms: f64,
+    pub root_dispersion_ms: f64,
+    pub offset_ms: f64,
+    pub delay_ms: f64,
+    pub samples: u32,
+    pub server_time: String,
+}
+
+pub fn run_ntp(opts: NtpOpts) -> Result<(), NtpError> {
+    let (host, port) = split_server(&opts.server)?;
+    network::check_host("ntp", host)?;
+    let addr = (host, port)
+        .to_socket_addrs()
+        .map_err(|_| NtpError::Resolve(host.to_string()))?
+        .next()
+        .ok_or_else(|| NtpError::Resolve(host.to_string()))?;
+    let timeout = Duration::from_millis(opts.time_out.max(1));
+
+    let mut best: Option<(Sample, SystemTime)> = None;
+    for round in 0..opts.samples {
+        if round > 0 {
+            std::thread::sleep(Duration::from_secs(1));
+        }
+        let (sample, received) = query(addr, timeout)?;
+        if best.is_none_or(|(current, _)| sample.delay < current.delay) {
+            best = Some((sample, received));
+        }
+    }
+    let (sample, received) = best.expect("at least one sample");
+    let report = build_report(&opts.server, addr, &sample, received, opts.samples);
+
+    // --where 不匹配时不输出报告，退出状态照常
+    if opts.record_output.keep(&report)? {
+        if opts.json {
+            opts.record_output
+                .write_report(&report, None, io::stdout().lock())?;
+        } else {
+            print_report(&report, sample.offset);
+        }
+    }
+    if let Some(max) = opts.max_offset {
+        if report.offset_ms.abs() > max {
+            return Err(NtpError::OffsetExceeded {
+                offset: format_offset(sample.offset),
+                max,
+            });
+        }
+    }
+    Ok(())
+}
+
+// host、host:port 或 [v6]:port；不带方括号的 IPv6 地址视为没有端口
+fn split_server(raw: &str) -> Result<(&str, u16), NtpError> {
+    let invalid = || NtpError::InvalidServer(raw.to_string());
+    if let Some(rest) = raw.strip_prefix('[') {
+        let (host, tail) = rest.split_once(']').ok_or_else(invalid)?;
+        let port = match tail.strip_prefix(':') {
+            Some(port) => port.parse().map_err(|_| invalid())?,
+            None if tail.is_empty() => NTP_PORT,
+            None => return Err(invalid()),
+        };
+        return Ok((host, port));
+    }
+    if raw.parse::<std::net::Ipv6Addr>().is_ok() {
+        return Ok((raw, NTP_PORT));
+    }
+    match raw.rsplit_once(':') {
+        Some((host, port)) if !host.is_empty() => Ok((host, port.parse().map_err(|_| invalid())?)),
+        Some(_) => Err(invalid()),
+        None if raw.is_empty() => Err(invalid()),
+        None => Ok((raw, NTP_PORT)),
+    }
+}
+
+// 发送一个客户端请求，只接受源地址和原始时间戳都匹配的应答
+fn query(addr: SocketAddr, timeout: Duration) -> Result<(Sample, SystemTime), NtpError> {
+    let local: SocketAddr = match addr {
+        SocketAddr::V4(_) => (Ipv4Addr::UNSPECIFIED, 0).into(),
+        SocketAddr::V6(_) => (std::net::Ipv6Addr::UNSPECIFIED, 0).into(),
+    };
+    let socket = UdpSocket::bind(local)?;
+    socket.connect(addr)?;
+
+    let sent = SystemTime::now();
+    let t1 = to_ntp(sent);
+    socket.send(&request(t1))?;
+
+    let deadline = Instant::now() + timeout;
+    let mut buf = [0u8; 512];
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(NtpError::Timeout {
+                server: addr,
+                timeout_ms: timeout.as_millis() as u64,
+            });
+        }
+        socket.set_read_timeout(Some(remaining))?;
+        let n = match socket.recv(&mut buf) {
+            Ok(n) => n,
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                continue
+            }
+            Err(e) if e.kind() == io::ErrorKind::ConnectionRefused => {
+                return Err(NtpError::Refused(addr))
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let received = SystemTime::now();
+        let Some(packet) = parse_packet(&buf[..n]) else {
+            continue;
+        };
+        if packet.mode != MODE_SERVER || packet.origin != t1 {
+            continue;
+        }
+        let invalid = |reason: &str| NtpError::InvalidReply {
+            server: addr,
+            reason: reason.to_string(),
+        };
+        if packet.stratum == 0 {
+            return Err(NtpError::KissOfDeath {
+                server: addr,
+                code: String::from_utf8_lossy(&packet.reference_id)
+                    .trim_end_matches('\0')
+                    .to_string(),
+            });
+        }
+        if packet.leap == 3 {
+            return Err(NtpError::Unsynchronized(addr));
+        }
+        if packet.transmit == 0 || packet.stratum > 15 {
+            return Err(invalid(
+                "missing transmit timestamp or stratum out of range",
+            ));
+        }
+        return Ok((sample(t1, to_ntp(received), packet), received));
+    }
+}
+
+fn request(transmit: u64) -> [u8; PACKET_LEN] {
+    let mut packet = [0u8; PACKET_LEN];
+    packet[0] = (VERSION << 3) | MODE_CLIENT;
+    packet[40..48].copy_from_slice(&transmit.to_be_bytes());
+    packet
+}
+
+fn parse_packet(buf: &[u8]) -> Option<NtpPacket> {
+    if buf.len() < PACKET_LEN {
+        return None;
+    }
+    let u32_at = |offset: usize| u32::from_be_bytes(buf[offset..offset + 4].try_into().unwrap());
+    let u64_at = |offset: usize| u64::from_be_bytes(buf[offset..offset + 8].try_into().unwrap());
+    Some(NtpPacket {
+        leap: buf[0] >> 6,
+        version: (buf[0] >> 3) & 0x07,
+        mode: buf[0] & 0x07,
+        stratum: buf[1],
+        precision: buf[3] as i8,
+        root_delay: u32_at(4),
+        root_dispersion: u32_at(8),
+        reference_id: buf[12..16].try_into().ok()?,
+        origin: u64_at(24),
+        receive: u64_at(32),
+        transmit: u64_at(40),
+    })
+}
+
+// RFC 5905：offset = ((T2 - T1) + (T3 - T4)) / 2，delay = (T4 - T1) - (T3 - T2)
+fn sample(t1: u64, t4: u64, packet: NtpPacket) -> Sample {
+    let (t2, t3) = (packet.receive, packet.transmit);
+    Sample {
+        offset: (diff(t2, t1) + diff(t3, t4)) / 2.0,
+        delay: (diff(t4, t1) - diff(t3, t2)).max(0.0),
+        packet,
+    }
+}
+
+// 两个时间戳之差（秒），按 64 位回绕相减，跨越 2036 年的纪元切换也成立
+fn diff(a: u64, b: u64) -> f64 {
+    a.wrapping_sub(b) as i64 as f64 / 4_294_967_296.0
+}
+
+fn to_ntp(time: SystemTime) -> u64 {
+    let since = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since.as_secs().wrapping_add(UNIX_OFFSET);
+    let frac = (u64::from(since.subsec_nanos()) << 32) / 1_000_000_000;
+    (secs << 32) | frac
+}
+
+// 16.16 定点格式的秒数
+fn short_to_ms(value: u32) -> f64 {
+    f64::from(value) / 65_536.0 * 1000.0
+}
+
+fn round(value: f64, digits: i32) -> f64 {
+    let scale = 10f64.powi(digits);
+    (value * scale).round() / scale
+}
+
+fn build_report(
+    server: &str,
+    addr: SocketAddr,
+    sample: &Sample,
+    received: SystemTime,
+    samples: u32,
+) -> NtpReport {
+    let packet = &sample.packet;
+    // 一级服务器的参考标识是 GPS、PPS 等 ASCII 代码，其余为上游服务器的 IPv4 地址（IPv6 上游为地址摘要）
+    let reference = if packet.stratum == 1 {
+        String::from_utf8_lossy(&packet.reference_id)
+            .trim_end_matches('\0')
+            .to_string()
+    } else {
+        Ipv4Addr::from(packet.reference_id).to_string()
+    };
+    let leap = match packet.leap {
+        0 => "none",
+        1 => "insert second",
+        2 => "delete second",
+        _ => "unsynchronized",
+    };
+    let offset = Duration::from_secs_f64(sample.offset.abs());
+    let server_time = if sample.offset >= 0.0 {
+        received + offset
+    } else {
+        received - offset
+    };
+    NtpReport {
+        server: server.to_string(),
+        address: addr.to_string(),
+        stratum: packet.stratum,
+        reference,
+        leap,
+        version: packet.version,
+        precision_us: round(2f64.powi(packet.precision.into()) * 1_000_000.0, 3),
+        root_delay_ms: round(short_to_ms(packet.root_delay), 3),
+        root_dispersion_ms: round(short_to_ms(packet.root_dispersion), 3),
+        offset_ms: round(sample.offset * 1000.0, 3),
+        delay_ms: round(sample.delay * 1000.0, 3),
+        samples,
+        server_time: DateTime::<Local>::from(server_time)
+            .format("%Y-%m-%d %H:%M:%S%.3f %:z")
+            .to_string(),
+    }
+}
+
+// 小于 1 秒时以毫秒显示，始终带符号
+fn format_offset(secs: f64) -> String {
+    if secs.abs() < 1.0 {
+        format!("{:+.3} ms", secs * 1000.0)
+    } else {
+        format!("{:+.3} s", secs)
+    }
+}
+
+fn print_report(report: &NtpReport, offset: f64) {
+    let direction = if offset > 0.0 {
+        "local clock is behind"
+    } else if offset < 0.0 {
+        "local clock is ahead"
+    } else {
+        "in sync"
+    };
+    // 直接给出 IP 时不再重复显示地址
+    let server = if report.server == report.address {
+        report.server.clone()
+    } else {
+        format!("{} ({})", report.server, report.address)
+    };
+    let offset_text = format!("{} ({})", format_offset(offset), direction);
+    let delay = format!("{:.3} ms", report.delay_ms);
+    let stratum = format!("{} (reference {})", report.stratum, report.reference);
+    let root = format!(
+        "delay {:.3} ms, dispersion {:.3} ms",
+        report.root_delay_ms, report.root_dispersion_ms
+    );
+    if a11y::is_enabled() {
+        println!(
+            "{}",
+            a11y::line(&[
+                ("Server", &server),
+                ("Offset", &offset_text),
+                ("Delay", &delay),
+                ("Stratum", &stratum),
+                ("Leap", &report.leap),
+                ("Server time", &report.server_time),
+            ])
+        );
+        return;
+    }
+    println!("Server:      {}", server);
+    println!("Offset:      {}", offset_text);
+    if report.samples > 1 {
+        println!(
+            "Delay:       {} (best of {} samples)",
+            delay, report.samples
+        );
+    } else {
+        println!("Delay:       {}", delay);
+    }
+    println!("Stratum:     {}", stratum);
+    println!("Root:        {}", root);
+    println!("Leap:        {}", report.leap);
+    println!("Server time: {}", report.server_time);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offset_and_delay() {
+        let t1 = to_ntp(UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+        let secs = |s: f64| (s * 4_294_967_296.0) as u64;
+        // 服务器比本机快 0.5 秒，单程 10 毫秒，服务器处理 1 毫秒
+        let mut reply = [0u8; PACKET_LEN];
+        reply[0] = (VERSION << 3) | MODE_SERVER;
+        reply[1] = 2;
+        reply[3] = (-20i8) as u8;
+        reply[12..16].copy_from_slice(&[192, 168, 1, 1]);
+        reply[24..32].copy_from_slice(&t1.to_be_bytes());
+        reply[32..40].copy_from_slice(&(t1 + secs(0.510)).to_be_bytes());
+        reply[40..48].copy_from_slice(&(t1 + secs(0.511)).to_be_bytes());
+        let packet = parse_packet(&reply).unwrap();
+        assert_eq!(
+            (packet.mode, packet.stratum, packet.precision),
+            (MODE_SERVER, 2, -20)
+        );
+
+        let sample = sample(t1, t1 + secs(0.021), packet);
+        assert!((sample.offset - 0.5).abs() < 1e-6);
+        assert!((sample.delay - 0.020).abs() < 1e-6);
+        assert_eq!(format_offset(sample.offset), "+500.000 ms");
+        assert_eq!(format_offset(-1.5), "-1.500 s");
+
+        // 时间戳跨越 2036 年纪元回绕
+        assert!((diff(secs(0.25), u64::MAX - secs(0.25) + 1) - 0.5).abs() < 1e-6);
+        assert_eq!(parse_packet(&reply[..40]), None);
+    }
+
+    #[test]
+    fn test_split_server() {
+        assert_eq!(split_server("pool.ntp.org").unwrap(), ("pool.ntp.org", 123));
+        assert_eq!(split_server("10.0.0.1:1123").unwrap(), ("10.0.0.1", 1123));
+        assert_eq!(split_server("[::1]:123").unwrap(), ("::1", 123));
+        assert_eq!(split_server("fe80::1").unwrap(), ("fe80::1", 123));
+        assert!(split_server(":123").is_err());
+        assert!(split_server("host:port").is_err());
+    }
+}
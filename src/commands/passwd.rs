@@ -0,0 +1,353 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::path::PathBuf;
+
+use rand::seq::IndexedRandom;
+use rand::Rng;
+use serde::Serialize;
+
+use crate::utils::output::RecordOutput;
+
+const WORDS_EN: &str = include_str!("../../data/words_en.txt");
+const WORDS_PINYIN: &str = include_str!("../../data/words_pinyin.txt");
+
+const LOWER: &str = "abcdefghijklmnopqrstuvwxyz";
+const UPPER: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const DIGITS: &str = "0123456789";
+// 不含引号、反引号和反斜杠，写进 shell 命令或配置文件时不需要转义
+const SYMBOLS: &str = "!#$%&()*+,-./:;<=>?@[]^_{|}~";
+// 在常见字体中容易看错的字符
+const AMBIGUOUS: &str = "0Oo1lI|";
+
+#[derive(clap::Args)]
+pub struct PasswdOpts {
+    #[arg(
+        short = 'l',
+        long,
+        default_value_t = 16,
+        conflicts_with = "passphrase",
+        help = "密码长度"
+    )]
+    length: usize,
+
+    #[arg(short = 'n', long, default_value_t = 1, help = "生成数量")]
+    count: u32,
+
+    #[arg(short = 's', long, help = "包含符号 !#$%&()*+,-./:;<=>?@[]^_{|}~")]
+    symbols: bool,
+
+    #[arg(long, help = "不含数字")]
+    no_digits: bool,
+
+    #[arg(long, help = "不含大写字母")]
+    no_upper: bool,
+
+    #[arg(long, help = "去掉容易看错的字符 0 O o 1 l I |")]
+    no_ambiguous: bool,
+
+    #[arg(
+        short = 'p',
+        long,
+        help = "生成由随机单词组成的口令短语，便于记忆和输入"
+    )]
+    passphrase: bool,
+
+    #[arg(
+        short = 'w',
+        long,
+        default_value_t = 6,
+        requires = "passphrase",
+        help = "口令短语的单词数"
+    )]
+    words: usize,
+
+    #[arg(
+        long,
+        value_name = "LIST",
+        default_value = "en",
+        requires = "passphrase",
+        help = "词表：en（BIP39 英文 2048 词）、pinyin（常用拼音词 1296 个），或每行一个词的文件（兼容 diceware 的 “11111 词” 格式）"
+    )]
+    wordlist: String,
+
+    #[arg(
+        long,
+        default_value = "-",
+        requires = "passphrase",
+        help = "单词之间的分隔符"
+    )]
+    separator: String,
+
+    #[arg(long, requires = "passphrase", help = "每个单词首字母大写")]
+    capitalize: bool,
+
+    #[arg(long, help = "以 JSON 输出，包含每个值的熵")]
+    json: bool,
+
+    // 全局 --where / --fields / --rename，由命令行入口注入
+    #[arg(skip)]
+    pub(crate) record_output: RecordOutput,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum PasswdError {
+    #[error("--length {length} is too short to include all {classes} character classes")]
+    TooShort { length: usize, classes: usize },
+    #[error("--words must be at least 1")]
+    NoWords,
+    #[error("failed to read wordlist {path}: {source}")]
+    Wordlist { path: PathBuf, source: io::Error },
+    #[error("wordlist {0} needs at least 2 distinct words")]
+    EmptyWordlist(String),
+    #[error("json serialize failed: {0}")]
+    Serialize(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Serialize)]
+pub struct Secret {
+    pub value: String,
+    // 生成方式的熵（位），即攻击者知道生成参数时需要穷举的空间
+    pub entropy_bits: f64,
+    pub strength: &'static str,
+}
+
+// 随机密码的字符集，每一类至少出现一次
+struct Charset {
+    classes: Vec<Vec<char>>,
+}
+
+impl Charset {
+    fn new(opts: &PasswdOpts) -> Self {
+        let mut classes = vec![LOWER];
+        if !opts.no_upper {
+            classes.push(UPPER);
+        }
+        if !opts.no_digits {
+            classes.push(DIGITS);
+        }
+        if opts.symbols {
+            classes.push(SYMBOLS);
+        }
+        let classes = classes
+            .into_iter()
+            .map(|class| {
+                class
+                    .chars()
+                    .filter(|c| !opts.no_ambiguous || !AMBIGUOUS.contains(*c))
+                    .collect()
+            })
+            .collect();
+        Self { classes }
+    }
+
+    fn pool(&self) -> Vec<char> {
+        self.classes.concat()
+    }
+
+    // 整体均匀抽取，缺少某一类时重抽，结果在所有满足条件的密码中均匀分布
+    fn generate(&self, length: usize) -> String {
+        let pool = self.pool();
+        let mut rng = rand::rng();
+        loop {
+            let password: Vec<char> = (0..length)
+                .map(|_| pool[rng.random_range(0..pool.len())])
+                .collect();
+            if self
+                .classes
+                .iter()
+                .all(|class| password.iter().any(|c| class.contains(c)))
+            {
+                return password.into_iter().collect();
+            }
+        }
+    }
+
+    // log2(满足条件的密码数)：总数 pool^length 按容斥原理扣掉缺少某些类的部分
+    fn entropy(&self, length: usize) -> f64 {
+        let pool = self.pool().len() as f64;
+        let n = self.classes.len();
+        let mut ratio = 0.0;
+        for mask in 0..1u32 << n {
+            let missing: usize = (0..n)
+                .filter(|i| mask & (1 << i) != 0)
+                .map(|i| self.classes[i].len())
+                .sum();
+            let term = ((pool - missing as f64) / pool).powi(length as i32);
+            ratio += if mask.count_ones() % 2 == 0 {
+                term
+            } else {
+                -term
+            };
+        }
+        length as f64 * pool.log2() + ratio.log2()
+    }
+}
+
+pub fn run_passwd(opts: PasswdOpts) -> Result<(), PasswdError> {
+    let mut secrets = Vec::new();
+    if opts.passphrase {
+        if opts.words == 0 {
+            return Err(PasswdError::NoWords);
+        }
+        let words = load_wordlist(&opts.wordlist)?;
+        let entropy = opts.words as f64 * (words.len() as f64).log2();
+        for _ in 0..opts.count {
+            let value = passphrase(&words, opts.words, &opts.separator, opts.capitalize);
+            secrets.push(secret(value, entropy));
+        }
+    } else {
+        let charset = Charset::new(&opts);
+        if opts.length < charset.classes.len() {
+            return Err(PasswdError::TooShort {
+                length: opts.length,
+                classes: charset.classes.len(),
+            });
+        }
+        let entropy = charset.entropy(opts.length);
+        for _ in 0..opts.count {
+            secrets.push(secret(charset.generate(opts.length), entropy));
+        }
+    }
+
+    opts.record_output.retain(&mut secrets)?;
+    if opts.json {
+        opts.record_output
+            .write_json(&secrets, io::stdout().lock())?;
+        return Ok(());
+    }
+    // 输出到管道时只写值，方便直接使用；终端上附带熵的估计
+    let annotate = io::stdout().is_terminal();
+    for secret in &secrets {
+        if annotate {
+            println!(
+                "{}  ({:.1} bits, {})",
+                secret.value, secret.entropy_bits, secret.strength
+            );
+        } else {
+            println!("{}", secret.value);
+        }
+    }
+    Ok(())
+}
+
+fn secret(value: String, entropy_bits: f64) -> Secret {
+    let strength = match entropy_bits {
+        bits if bits < 40.0 => "weak",
+        bits if bits < 60.0 => "fair",
+        bits if bits < 80.0 => "strong",
+        _ => "very strong",
+    };
+    Secret {
+        value,
+        entropy_bits: (entropy_bits * 10.0).round() / 10.0,
+        strength,
+    }
+}
+
+// 内置词表或文件；重复的词只算一次，否则会高估熵
+fn load_wordlist(name: &str) -> Result<Vec<String>, PasswdError> {
+    let text = match name {
+        "en" => WORDS_EN.to_string(),
+        "pinyin" => WORDS_PINYIN.to_string(),
+        path => fs::read_to_string(path).map_err(|source| PasswdError::Wordlist {
+            path: PathBuf::from(path),
+            source,
+        })?,
+    };
+    let words = parse_wordlist(&text);
+    if words.len() < 2 {
+        return Err(PasswdError::EmptyWordlist(name.to_string()));
+    }
+    Ok(words)
+}
+
+// 每行取最后一个字段，diceware 词表行首的骰子点数被忽略；# 开头的行为注释
+fn parse_wordlist(text: &str) -> Vec<String> {
+    let mut seen = HashSet::new();
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_whitespace().last())
+        .filter(|word| seen.insert(word.to_string()))
+        .map(str::to_string)
+        .collect()
+}
+
+fn passphrase(words: &[String], count: usize, separator: &str, capitalize: bool) -> String {
+    let mut rng = rand::rng();
+    (0..count)
+        .map(|_| {
+            let word = words.choose(&mut rng).map(String::as_str).unwrap_or("");
+            if capitalize {
+                let mut chars = word.chars();
+                chars
+                    .next()
+                    .map(|first| first.to_uppercase().chain(chars).collect())
+                    .unwrap_or_default()
+            } else {
+                word.to_string()
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(separator)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::{CommandFactory, Parser};
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        opts: PasswdOpts,
+    }
+
+    fn opts(args: &[&str]) -> PasswdOpts {
+        Cli::parse_from(std::iter::once("passwd").chain(args.iter().copied())).opts
+    }
+
+    #[test]
+    fn test_charset() {
+        let charset = Charset::new(&opts(&["--symbols", "--no-ambiguous"]));
+        let pool = charset.pool();
+        assert!(pool.iter().all(|c| !AMBIGUOUS.contains(*c)));
+        assert_eq!(pool.len(), 26 + 26 + 10 + SYMBOLS.len() - AMBIGUOUS.len());
+        for _ in 0..50 {
+            let password = charset.generate(4);
+            assert_eq!(password.chars().count(), 4);
+            for class in &charset.classes {
+                assert!(password.chars().any(|c| class.contains(&c)));
+            }
+        }
+
+        // 帮助中列出的正是实际使用的符号
+        let command = Cli::command();
+        let symbols = command
+            .get_arguments()
+            .find(|arg| arg.get_id() == "symbols")
+            .unwrap();
+        assert!(symbols.get_help().unwrap().to_string().contains(SYMBOLS));
+
+        // 只有小写字母时没有约束，熵为 length * log2(26)
+        let lower = Charset::new(&opts(&["--no-upper", "--no-digits"]));
+        assert!((lower.entropy(10) - 10.0 * 26f64.log2()).abs() < 1e-9);
+        // 两个字符、两类：满足条件的只有 26 * 26 * 2 种
+        let two = Charset::new(&opts(&["--no-digits"]));
+        assert!((two.entropy(2) - (26.0 * 26.0 * 2.0f64).log2()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_wordlists() {
+        assert_eq!(load_wordlist("en").unwrap().len(), 2048);
+        assert_eq!(load_wordlist("pinyin").unwrap().len(), 1296);
+        let words = parse_wordlist("# EFF\n11111\tabacus\n11112\tabdomen\n\n11113 abacus\n");
+        assert_eq!(words, ["abacus", "abdomen"]);
+        let phrase = passphrase(&words, 4, " ", true);
+        assert_eq!(phrase.split(' ').count(), 4);
+        assert!(phrase
+            .split(' ')
+            .all(|word| word == "Abacus" || word == "Abdomen"));
+    }
+}
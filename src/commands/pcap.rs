@@ -0,0 +1,1332 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::path::PathBuf;
+
+use clap::Subcommand;
+use serde::Serialize;
+
+use crate::utils::a11y;
+use crate::utils::format::human_bytes;
+use crate::utils::output::RecordOutput;
+
+const PCAPNG_SHB: u32 = 0x0A0D_0D0A;
+const PCAPNG_BYTE_ORDER: u32 = 0x1A2B_3C4D;
+// pcapng 单个块的长度上限，防止损坏文件导致超大分配
+const MAX_BLOCK_LEN: usize = 64 * 1024 * 1024;
+
+const LINKTYPE_NULL: u32 = 0;
+const LINKTYPE_ETHERNET: u32 = 1;
+const LINKTYPE_RAW: u32 = 101;
+const LINKTYPE_LOOP: u32 = 108;
+const LINKTYPE_LINUX_SLL: u32 = 113;
+const LINKTYPE_IPV4: u32 = 228;
+const LINKTYPE_IPV6: u32 = 229;
+const LINKTYPE_LINUX_SLL2: u32 = 276;
+
+const ETHERTYPE_IPV4: u16 = 0x0800;
+const ETHERTYPE_ARP: u16 = 0x0806;
+const ETHERTYPE_IPV6: u16 = 0x86DD;
+
+const TCP_SYN: u8 = 0x02;
+const TCP_RST: u8 = 0x04;
+const TCP_ACK: u8 = 0x10;
+
+#[derive(clap::Args)]
+pub struct PcapOpts {
+    #[command(subcommand)]
+    command: PcapCommand,
+}
+
+impl PcapOpts {
+    pub(crate) fn record_output(&mut self) -> &mut RecordOutput {
+        match &mut self.command {
+            PcapCommand::Summary(opts) => &mut opts.record_output,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum PcapCommand {
+    #[command(about = "离线分析抓包文件并输出摘要")]
+    Summary(SummaryOpts),
+}
+
+#[derive(clap::Args)]
+struct SummaryOpts {
+    #[arg(value_name = "FILE", help = "pcap 或 pcapng 文件")]
+    file: PathBuf,
+
+    #[arg(long, value_name = "IP", help = "只统计与该主机相关的数据包")]
+    host: Option<IpAddr>,
+
+    #[arg(
+        long,
+        value_name = "PORT",
+        help = "只统计源或目的端口为该端口的 TCP/UDP 数据包"
+    )]
+    port: Option<u16>,
+
+    #[arg(
+        long,
+        default_value_t = 10,
+        value_name = "N",
+        help = "各排行榜显示条数"
+    )]
+    top: usize,
+
+    #[arg(long, help = "以 JSON 输出结果")]
+    json: bool,
+
+    // 全局 --where / --fields / --rename，由命令行入口注入
+    #[arg(skip)]
+    pub(crate) record_output: RecordOutput,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum PcapError {
+    #[error("not a pcap or pcapng file")]
+    UnknownFormat,
+    #[error("corrupt capture: {0}")]
+    Corrupt(String),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("json serialize failed: {0}")]
+    Serialize(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CaptureFormat {
+    Pcap,
+    Pcapng,
+}
+
+pub struct Packet {
+    pub timestamp: f64,
+    pub link_type: u32,
+    pub orig_len: u32,
+    pub data: Vec<u8>,
+}
+
+struct Interface {
+    link_type: u32,
+    ts_units_per_sec: f64,
+}
+
+// 顺序读取 pcap / pcapng 中的数据包
+pub struct CaptureReader<R: Read> {
+    reader: R,
+    format: CaptureFormat,
+    big_endian: bool,
+    // pcap: 全局链路类型与时间精度
+    link_type: u32,
+    nanos: bool,
+    // pcapng: 当前 section 内的接口列表
+    interfaces: Vec<Interface>,
+}
+
+impl<R: Read> CaptureReader<R> {
+    pub fn new(mut reader: R) -> Result<Self, PcapError> {
+        let mut magic = [0u8; 4];
+        reader
+            .read_exact(&mut magic)
+            .map_err(|_| PcapError::UnknownFormat)?;
+
+        if u32::from_le_bytes(magic) == PCAPNG_SHB {
+            let mut capture = Self {
+                reader,
+                format: CaptureFormat::Pcapng,
+                big_endian: false,
+                link_type: 0,
+                nanos: false,
+                interfaces: Vec::new(),
+            };
+            capture.read_section_header()?;
+            return Ok(capture);
+        }
+
+        let (big_endian, nanos) = match magic {
+            [0xD4, 0xC3, 0xB2, 0xA1] => (false, false),
+            [0xA1, 0xB2, 0xC3, 0xD4] => (true, false),
+            [0x4D, 0x3C, 0xB2, 0xA1] => (false, true),
+            [0xA1, 0xB2, 0x3C, 0x4D] => (true, true),
+            _ => return Err(PcapError::UnknownFormat),
+        };
+        let mut header = [0u8; 20];
+        reader.read_exact(&mut header)?;
+        let mut capture = Self {
+            reader,
+            format: CaptureFormat::Pcap,
+            big_endian,
+            link_type: 0,
+            nanos,
+            interfaces: Vec::new(),
+        };
+        // 高 16 位可能带 FCS 标志，只取低 16 位
+        capture.link_type = capture.u32_at(&header, 16) & 0xFFFF;
+        Ok(capture)
+    }
+
+    pub fn format(&self) -> CaptureFormat {
+        self.format
+    }
+
+    pub fn next_packet(&mut self) -> Result<Option<Packet>, PcapError> {
+        match self.format {
+            CaptureFormat::Pcap => self.next_pcap_packet(),
+            CaptureFormat::Pcapng => self.next_pcapng_packet(),
+        }
+    }
+
+    fn next_pcap_packet(&mut self) -> Result<Option<Packet>, PcapError> {
+        let mut header = [0u8; 16];
+        if !read_exact_or_eof(&mut self.reader, &mut header)? {
+            return Ok(None);
+        }
+        let ts_sec = self.u32_at(&header, 0);
+        let ts_frac = self.u32_at(&header, 4);
+        let incl_len = self.u32_at(&header, 8) as usize;
+        let orig_len = self.u32_at(&header, 12);
+        if incl_len > MAX_BLOCK_LEN {
+            return Err(PcapError::Corrupt(format!(
+                "packet length {} too large",
+                incl_len
+            )));
+        }
+        let mut data = vec![0u8; incl_len];
+        self.reader.read_exact(&mut data)?;
+        let divisor = if self.nanos { 1e9 } else { 1e6 };
+        Ok(Some(Packet {
+            timestamp: f64::from(ts_sec) + f64::from(ts_frac) / divisor,
+            link_type: self.link_type,
+            orig_len,
+            data,
+        }))
+    }
+
+    fn next_pcapng_packet(&mut self) -> Result<Option<Packet>, PcapError> {
+        loop {
+            let mut header = [0u8; 8];
+            if !read_exact_or_eof(&mut self.reader, &mut header)? {
+                return Ok(None);
+            }
+            if u32::from_le_bytes([header[0], header[1], header[2], header[3]]) == PCAPNG_SHB {
+                // 新的 section：长度字段的字节序要等读到 byte-order magic 才能确定
+                self.read_section_header_after_type(&header[4..8])?;
+                continue;
+            }
+
+            let block_type = self.u32_at(&header, 0);
+            let body = self.read_block_body(self.u32_at(&header, 4))?;
+            match block_type {
+                // Interface Description Block
+                1 => self.add_interface(&body)?,
+                // Enhanced Packet Block
+                6 => {
+                    if body.len() < 20 {
+                        return Err(PcapError::Corrupt("short enhanced packet block".into()));
+                    }
+                    let interface = self.u32_at(&body, 0) as usize;
+                    let ts =
+                        (u64::from(self.u32_at(&body, 4)) << 32) | u64::from(self.u32_at(&body, 8));
+                    let cap_len = self.u32_at(&body, 12) as usize;
+                    let orig_len = self.u32_at(&body, 16);
+                    let data = body
+                        .get(20..20 + cap_len)
+                        .ok_or_else(|| PcapError::Corrupt("packet exceeds block".into()))?;
+                    let iface = self.interfaces.get(interface).ok_or_else(|| {
+                        PcapError::Corrupt(format!("unknown interface {}", interface))
+                    })?;
+                    return Ok(Some(Packet {
+                        timestamp: ts as f64 / iface.ts_units_per_sec,
+                        link_type: iface.link_type,
+                        orig_len,
+                        data: data.to_vec(),
+                    }));
+                }
+                // Simple Packet Block，没有时间戳
+                3 => {
+                    if body.len() < 4 {
+                        return Err(PcapError::Corrupt("short simple packet block".into()));
+                    }
+                    let orig_len = self.u32_at(&body, 0);
+                    let iface = self
+                        .interfaces
+                        .first()
+                        .ok_or_else(|| PcapError::Corrupt("missing interface".into()))?;
+                    let cap_len = (orig_len as usize).min(body.len() - 4);
+                    return Ok(Some(Packet {
+                        timestamp: 0.0,
+                        link_type: iface.link_type,
+                        orig_len,
+                        data: body[4..4 + cap_len].to_vec(),
+                    }));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn read_section_header(&mut self) -> Result<(), PcapError> {
+        let mut len = [0u8; 4];
+        self.reader.read_exact(&mut len)?;
+        self.read_section_header_after_type(&len)
+    }
+
+    fn read_section_header_after_type(&mut self, len_bytes: &[u8]) -> Result<(), PcapError> {
+        let mut magic = [0u8; 4];
+        self.reader.read_exact(&mut magic)?;
+        self.big_endian = match u32::from_le_bytes(magic) {
+            PCAPNG_BYTE_ORDER => false,
+            m if m.swap_bytes() == PCAPNG_BYTE_ORDER => true,
+            _ => return Err(PcapError::Corrupt("bad section byte-order magic".into())),
+        };
+        let total_len = self.u32_at(len_bytes, 0) as usize;
+        if !(28..=MAX_BLOCK_LEN).contains(&total_len) {
+            return Err(PcapError::Corrupt("bad section header length".into()));
+        }
+        // 已读 type、length、magic 共 12 字节
+        let mut rest = vec![0u8; total_len - 12];
+        self.reader.read_exact(&mut rest)?;
+        self.interfaces.clear();
+        Ok(())
+    }
+
+    fn read_block_body(&mut self, total_len: u32) -> Result<Vec<u8>, PcapError> {
+        let total_len = total_len as usize;
+        if !(12..=MAX_BLOCK_LEN).contains(&total_len) || !total_len.is_multiple_of(4) {
+            return Err(PcapError::Corrupt(format!(
+                "bad block length {}",
+                total_len
+            )));
+        }
+        let mut body = vec![0u8; total_len - 8];
+        self.reader.read_exact(&mut body)?;
+        // 去掉结尾重复的长度字段
+        body.truncate(total_len - 12);
+        Ok(body)
+    }
+
+    fn add_interface(&mut self, body: &[u8]) -> Result<(), PcapError> {
+        if body.len() < 8 {
+            return Err(PcapError::Corrupt("short interface block".into()));
+        }
+        let link_type = u32::from(self.u16_at(body, 0));
+        let mut ts_units_per_sec = 1e6;
+
+        let mut offset = 8;
+        while offset + 4 <= body.len() {
+            let code = self.u16_at(body, offset);
+            let len = self.u16_at(body, offset + 2) as usize;
+            if code == 0 {
+                break;
+            }
+            let Some(value) = body.get(offset + 4..offset + 4 + len) else {
+                return Err(PcapError::Corrupt(
+                    "interface option runs past block".into(),
+                ));
+            };
+            match code {
+                // if_tsresol：最高位为 0 表示 10 的负幂，为 1 表示 2 的负幂
+                9 if len == 1 => {
+                    let exp = value[0] & 0x7F;
+                    ts_units_per_sec = if value[0] & 0x80 == 0 {
+                        10f64.powi(i32::from(exp))
+                    } else {
+                        2f64.powi(i32::from(exp))
+                    };
+                }
+                _ => {}
+            }
+            offset += 4 + len.div_ceil(4) * 4;
+        }
+
+        self.interfaces.push(Interface {
+            link_type,
+            ts_units_per_sec,
+        });
+        Ok(())
+    }
+
+    fn u32_at(&self, buf: &[u8], offset: usize) -> u32 {
+        let bytes = [
+            buf[offset],
+            buf[offset + 1],
+            buf[offset + 2],
+            buf[offset + 3],
+        ];
+        if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        }
+    }
+
+    fn u16_at(&self, buf: &[u8], offset: usize) -> u16 {
+        let bytes = [buf[offset], buf[offset + 1]];
+        if self.big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        }
+    }
+}
+
+// 在记录边界处遇到 EOF 返回 false，记录中途截断视为错误
+fn read_exact_or_eof<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<bool, PcapError> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) if filled == 0 => return Ok(false),
+            Ok(0) => return Err(PcapError::Corrupt("truncated record".into())),
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(PcapError::Io(e)),
+        }
+    }
+    Ok(true)
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Transport<'a> {
+    Tcp {
+        src_port: u16,
+        dst_port: u16,
+        flags: u8,
+    },
+    Udp {
+        src_port: u16,
+        dst_port: u16,
+        payload: &'a [u8],
+    },
+    Icmp,
+    Icmpv6,
+    Other(u8),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Decoded<'a> {
+    Ip {
+        src: IpAddr,
+        dst: IpAddr,
+        transport: Transport<'a>,
+    },
+    Arp,
+    Other(&'static str),
+}
+
+pub fn decode_packet(link_type: u32, data: &[u8]) -> Decoded<'_> {
+    let (ethertype, payload) = match link_type {
+        LINKTYPE_ETHERNET => {
+            let mut offset = 12;
+            let mut ethertype = read_u16(data, offset);
+            // 跳过 802.1Q / 802.1ad VLAN 标签
+            while matches!(ethertype, Some(0x8100) | Some(0x88A8)) {
+                offset += 4;
+                ethertype = read_u16(data, offset);
+            }
+            (ethertype, data.get(offset + 2..))
+        }
+        LINKTYPE_LINUX_SLL => (read_u16(data, 14), data.get(16..)),
+        LINKTYPE_LINUX_SLL2 => (read_u16(data, 0), data.get(20..)),
+        LINKTYPE_NULL | LINKTYPE_LOOP => (None, data.get(4..)),
+        LINKTYPE_RAW | LINKTYPE_IPV4 | LINKTYPE_IPV6 | 12 | 14 => (None, Some(data)),
+        _ => return Decoded::Other("unknown-link"),
+    };
+    let Some(payload) = payload else {
+        return Decoded::Other("truncated");
+    };
+
+    // 没有 ethertype 的链路层按 IP 版本号判断
+    let ethertype = ethertype.unwrap_or(match payload.first().map(|b| b >> 4) {
+        Some(4) => ETHERTYPE_IPV4,
+        Some(6) => ETHERTYPE_IPV6,
+        _ => 0,
+    });
+    match ethertype {
+        ETHERTYPE_IPV4 => decode_ipv4(payload).unwrap_or(Decoded::Other("truncated")),
+        ETHERTYPE_IPV6 => decode_ipv6(payload).unwrap_or(Decoded::Other("truncated")),
+        ETHERTYPE_ARP => Decoded::Arp,
+        _ => Decoded::Other("other-l2"),
+    }
+}
+
+fn decode_ipv4(data: &[u8]) -> Option<Decoded<'_>> {
+    let ihl = usize::from(*data.first()? & 0x0F) * 4;
+    if ihl < 20 || data.len() < ihl {
+        return None;
+    }
+    let protocol = data[9];
+    let src = IpAddr::V4(Ipv4Addr::new(data[12], data[13], data[14], data[15]));
+    let dst = IpAddr::V4(Ipv4Addr::new(data[16], data[17], data[18], data[19]));
+    let fragment_offset = read_u16(data, 6)? & 0x1FFF;
+    let total_len = usize::from(read_u16(data, 2)?).clamp(ihl, data.len());
+    let transport = if fragment_offset != 0 {
+        Transport::Other(protocol)
+    } else {
+        decode_transport(protocol, &data[ihl..total_len])
+    };
+    Some(Decoded::Ip {
+        src,
+        dst,
+        transport,
+    })
+}
+
+fn decode_ipv6(data: &[u8]) -> Option<Decoded<'_>> {
+    if data.len() < 40 {
+        return None;
+    }
+    let src = IpAddr::V6(Ipv6Addr::from(<[u8; 16]>::try_from(&data[8..24]).ok()?));
+    let dst = IpAddr::V6(Ipv6Addr::from(<[u8; 16]>::try_from(&data[24..40]).ok()?));
+    let mut next_header = data[6];
+    let mut offset = 40;
+    // 跳过常见扩展头
+    loop {
+        match next_header {
+            0 | 43 | 60 => {
+                let len = (usize::from(*data.get(offset + 1)?) + 1) * 8;
+                next_header = data[offset];
+                offset += len;
+            }
+            44 => {
+                let fragment_offset = read_u16(data, offset + 2)? >> 3;
+                next_header = data[offset];
+                offset += 8;
+                if fragment_offset != 0 {
+                    return Some(Decoded::Ip {
+                        src,
+                        dst,
+                        transport: Transport::Other(next_header),
+                    });
+                }
+            }
+            _ => break,
+        }
+    }
+    let transport = decode_transport(next_header, data.get(offset..)?);
+    Some(Decoded::Ip {
+        src,
+        dst,
+        transport,
+    })
+}
+
+fn decode_transport(protocol: u8, data: &[u8]) -> Transport<'_> {
+    match protocol {
+        6 if data.len() >= 14 => Transport::Tcp {
+            src_port: u16::from_be_bytes([data[0], data[1]]),
+            dst_port: u16::from_be_bytes([data[2], data[3]]),
+            flags: data[13],
+        },
+        17 if data.len() >= 8 => Transport::Udp {
+            src_port: u16::from_be_bytes([data[0], data[1]]),
+            dst_port: u16::from_be_bytes([data[2], data[3]]),
+            payload: &data[8..],
+        },
+        1 => Transport::Icmp,
+        58 => Transport::Icmpv6,
+        other => Transport::Other(other),
+    }
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_be_bytes([
+        *data.get(offset)?,
+        *data.get(offset + 1)?,
+    ]))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DnsMessage {
+    pub response: bool,
+    pub rcode: u8,
+    pub name: String,
+    pub qtype: u16,
+}
+
+pub fn parse_dns(payload: &[u8]) -> Option<DnsMessage> {
+    if payload.len() < 12 {
+        return None;
+    }
+    let flags = u16::from_be_bytes([payload[2], payload[3]]);
+    let qdcount = u16::from_be_bytes([payload[4], payload[5]]);
+    if qdcount == 0 {
+        return None;
+    }
+
+    let mut labels = Vec::new();
+    let mut offset = 12;
+    loop {
+        let len = usize::from(*payload.get(offset)?);
+        if len == 0 {
+            offset += 1;
+            break;
+        }
+        // 问题区一般不使用压缩指针，遇到则视为异常报文
+        if len & 0xC0 != 0 || labels.len() > 127 {
+            return None;
+        }
+        let label = payload.get(offset + 1..offset + 1 + len)?;
+        labels.push(String::from_utf8_lossy(label).to_ascii_lowercase());
+        offset += 1 + len;
+    }
+    let qtype = read_u16(payload, offset)?;
+
+    Some(DnsMessage {
+        response: flags & 0x8000 != 0,
+        rcode: (flags & 0x000F) as u8,
+        name: if labels.is_empty() {
+            ".".to_string()
+        } else {
+            labels.join(".")
+        },
+        qtype,
+    })
+}
+
+fn qtype_name(qtype: u16) -> String {
+    match qtype {
+        1 => "A".to_string(),
+        2 => "NS".to_string(),
+        5 => "CNAME".to_string(),
+        6 => "SOA".to_string(),
+        12 => "PTR".to_string(),
+        15 => "MX".to_string(),
+        16 => "TXT".to_string(),
+        28 => "AAAA".to_string(),
+        33 => "SRV".to_string(),
+        64 => "SVCB".to_string(),
+        65 => "HTTPS".to_string(),
+        255 => "ANY".to_string(),
+        other => format!("TYPE{}", other),
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ProtocolStat {
+    pub protocol: String,
+    pub packets: u64,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct TalkerStat {
+    pub address: String,
+    pub packets: u64,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct HandshakeFailure {
+    pub server: String,
+    pub refused: u64,
+    pub no_response: u64,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct HandshakeSummary {
+    pub attempts: u64,
+    pub established: u64,
+    pub refused: u64,
+    pub no_response: u64,
+    pub failures: Vec<HandshakeFailure>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct DnsQueryStat {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub qtype: String,
+    pub queries: u64,
+    pub nxdomain: u64,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct DnsSummary {
+    pub queries: u64,
+    pub responses: u64,
+    pub nxdomain: u64,
+    pub unique_names: usize,
+    pub top_queries: Vec<DnsQueryStat>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PcapSummary {
+    pub file: String,
+    pub format: CaptureFormat,
+    pub total_packets: u64,
+    pub matched_packets: u64,
+    pub bytes: u64,
+    pub duration_secs: f64,
+    pub protocols: Vec<ProtocolStat>,
+    pub top_talkers: Vec<TalkerStat>,
+    pub tcp_handshakes: HandshakeSummary,
+    pub dns: DnsSummary,
+}
+
+#[derive(Default)]
+struct Handshake {
+    syn_ack: bool,
+    rst: bool,
+}
+
+type FlowKey = (IpAddr, u16, IpAddr, u16);
+
+pub struct Filter {
+    pub host: Option<IpAddr>,
+    pub port: Option<u16>,
+}
+
+#[derive(Default)]
+struct Collector {
+    total_packets: u64,
+    matched_packets: u64,
+    bytes: u64,
+    first_ts: Option<f64>,
+    last_ts: Option<f64>,
+    protocols: HashMap<&'static str, (u64, u64)>,
+    talkers: HashMap<IpAddr, TalkerStat>,
+    handshakes: HashMap<FlowKey, Handshake>,
+    dns_queries: HashMap<(String, u16), DnsQueryStat>,
+    dns_responses: u64,
+    dns_nxdomain: u64,
+}
+
+impl Collector {
+    fn add(&mut self, packet: &Packet, filter: &Filter) {
+        self.total_packets += 1;
+        let decoded = decode_packet(packet.link_type, &packet.data);
+        if !matches_filter(&decoded, filter) {
+            return;
+        }
+
+        let len = u64::from(packet.orig_len);
+        self.matched_packets += 1;
+        self.bytes += len;
+        if packet.timestamp > 0.0 {
+            self.first_ts = Some(
+                self.first_ts
+                    .map_or(packet.timestamp, |t| t.min(packet.timestamp)),
+            );
+            self.last_ts = Some(
+                self.last_ts
+                    .map_or(packet.timestamp, |t| t.max(packet.timestamp)),
+            );
+        }
+
+        let protocol = match &decoded {
+            Decoded::Ip { transport, .. } => match transport {
+                Transport::Tcp { .. } => "TCP",
+                Transport::Udp { .. } => "UDP",
+                Transport::Icmp => "ICMP",
+                Transport::Icmpv6 => "ICMPv6",
+                Transport::Other(_) => "IP-other",
+            },
+            Decoded::Arp => "ARP",
+            Decoded::Other(kind) => kind,
+        };
+        let entry = self.protocols.entry(protocol).or_default();
+        entry.0 += 1;
+        entry.1 += len;
+
+        let Decoded::Ip {
+            src,
+            dst,
+            transport,
+        } = decoded
+        else {
+            return;
+        };
+
+        let sender = self.talkers.entry(src).or_default();
+        sender.packets += 1;
+        sender.bytes_sent += len;
+        let receiver = self.talkers.entry(dst).or_default();
+        receiver.packets += 1;
+        receiver.bytes_received += len;
+
+        match transport {
+            Transport::Tcp {
+                src_port,
+                dst_port,
+                flags,
+            } => self.track_handshake(src, src_port, dst, dst_port, flags),
+            Transport::Udp {
+                src_port,
+                dst_port,
+                payload,
+            } if src_port == 53 || dst_port == 53 || src_port == 5353 || dst_port == 5353 => {
+                if let Some(dns) = parse_dns(payload) {
+                    self.track_dns(dns);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn track_handshake(&mut self, src: IpAddr, sport: u16, dst: IpAddr, dport: u16, flags: u8) {
+        let syn = flags & TCP_SYN != 0;
+        let ack = flags & TCP_ACK != 0;
+        if syn && !ack {
+            // 重传的 SYN 合并为一次尝试
+            self.handshakes.entry((src, sport, dst, dport)).or_default();
+        } else if let Some(state) = self.handshakes.get_mut(&(dst, dport, src, sport)) {
+            if syn && ack {
+                state.syn_ack = true;
+            } else if flags & TCP_RST != 0 && !state.syn_ack {
+                state.rst = true;
+            }
+        }
+    }
+
+    fn track_dns(&mut self, dns: DnsMessage) {
+        let key = (dns.name.clone(), dns.qtype);
+        if dns.response {
+            self.dns_responses += 1;
+            if dns.rcode == 3 {
+                self.dns_nxdomain += 1;
+                if let Some(stat) = self.dns_queries.get_mut(&key) {
+                    stat.nxdomain += 1;
+                }
+            }
+        } else {
+            let stat = self.dns_queries.entry(key).or_insert_with(|| DnsQueryStat {
+                name: dns.name,
+                qtype: qtype_name(dns.qtype),
+                ..Default::default()
+            });
+            stat.queries += 1;
+        }
+    }
+
+    fn finish(self, file: String, format: CaptureFormat, top: usize) -> PcapSummary {
+        let mut protocols: Vec<ProtocolStat> = self
+            .protocols
+            .into_iter()
+            .map(|(protocol, (packets, bytes))| ProtocolStat {
+                protocol: protocol.to_string(),
+                packets,
+                bytes,
+            })
+            .collect();
+        protocols.sort_by(|a, b| b.packets.cmp(&a.packets).then(a.protocol.cmp(&b.protocol)));
+
+        let mut top_talkers: Vec<TalkerStat> = self
+            .talkers
+            .into_iter()
+            .map(|(address, mut stat)| {
+                stat.address = address.to_string();
+                stat
+            })
+            .collect();
+        top_talkers.sort_by(|a, b| {
+            (b.bytes_sent + b.bytes_received)
+                .cmp(&(a.bytes_sent + a.bytes_received))
+                .then(a.address.cmp(&b.address))
+        });
+        top_talkers.truncate(top);
+
+        let mut handshakes = HandshakeSummary::default();
+        let mut failures: HashMap<String, HandshakeFailure> = HashMap::new();
+        for ((_, _, server, port), state) in self.handshakes {
+            handshakes.attempts += 1;
+            if state.syn_ack {
+                handshakes.established += 1;
+                continue;
+            }
+            let server = endpoint(server, port);
+            let failure = failures
+                .entry(server.clone())
+                .or_insert_with(|| HandshakeFailure {
+                    server,
+                    ..Default::default()
+                });
+            if state.rst {
+                handshakes.refused += 1;
+                failure.refused += 1;
+            } else {
+                handshakes.no_response += 1;
+                failure.no_response += 1;
+            }
+        }
+        handshakes.failures = failures.into_values().collect();
+        handshakes.failures.sort_by(|a, b| {
+            (b.refused + b.no_response)
+                .cmp(&(a.refused + a.no_response))
+                .then(a.server.cmp(&b.server))
+        });
+        handshakes.failures.truncate(top);
+
+        let unique_names = self.dns_queries.len();
+        let mut top_queries: Vec<DnsQueryStat> = self.dns_queries.into_values().collect();
+        let queries = top_queries.iter().map(|q| q.queries).sum();
+        top_queries.sort_by(|a, b| b.queries.cmp(&a.queries).then(a.name.cmp(&b.name)));
+        top_queries.truncate(top);
+
+        PcapSummary {
+            file,
+            format,
+            total_packets: self.total_packets,
+            matched_packets: self.matched_packets,
+            bytes: self.bytes,
+            duration_secs: match (self.first_ts, self.last_ts) {
+                (Some(first), Some(last)) => last - first,
+                _ => 0.0,
+            },
+            protocols,
+            top_talkers,
+            tcp_handshakes: handshakes,
+            dns: DnsSummary {
+                queries,
+                responses: self.dns_responses,
+                nxdomain: self.dns_nxdomain,
+                unique_names,
+                top_queries,
+            },
+        }
+    }
+}
+
+fn endpoint(ip: IpAddr, port: u16) -> String {
+    match ip {
+        IpAddr::V4(ip) => format!("{}:{}", ip, port),
+        IpAddr::V6(ip) => format!("[{}]:{}", ip, port),
+    }
+}
+
+fn matches_filter(decoded: &Decoded<'_>, filter: &Filter) -> bool {
+    if filter.host.is_none() && filter.port.is_none() {
+        return true;
+    }
+    let Decoded::Ip {
+        src,
+        dst,
+        transport,
+    } = decoded
+    else {
+        return false;
+    };
+    if let Some(host) = filter.host {
+        if *src != host && *dst != host {
+            return false;
+        }
+    }
+    if let Some(port) = filter.port {
+        let ports = match transport {
+            Transport::Tcp {
+                src_port, dst_port, ..
+            }
+            | Transport::Udp {
+                src_port, dst_port, ..
+            } => Some((*src_port, *dst_port)),
+            _ => None,
+        };
+        if !ports.is_some_and(|(s, d)| s == port || d == port) {
+            return false;
+        }
+    }
+    true
+}
+
+pub fn summarize<R: Read>(
+    reader: R,
+    file: String,
+    filter: &Filter,
+    top: usize,
+) -> Result<PcapSummary, PcapError> {
+    let mut capture = CaptureReader::new(reader)?;
+    let mut collector = Collector::default();
+    while let Some(packet) = capture.next_packet()? {
+        collector.add(&packet, filter);
+    }
+    Ok(collector.finish(file, capture.format(), top))
+}
+
+pub fn run_pcap(opts: PcapOpts) -> Result<(), PcapError> {
+    match opts.command {
+        PcapCommand::Summary(summary) => run_summary(summary),
+    }
+}
+
+fn run_summary(opts: SummaryOpts) -> Result<(), PcapError> {
+    let file = File::open(&opts.file)?;
+    let filter = Filter {
+        host: opts.host,
+        port: opts.port,
+    };
+    let summary = summarize(
+        BufReader::new(file),
+        opts.file.display().to_string(),
+        &filter,
+        opts.top,
+    )?;
+    if !opts.record_output.keep(&summary)? {
+        return Ok(());
+    }
+
+    if opts.json {
+        opts.record_output
+            .write_report(&summary, None, io::stdout().lock())?;
+    } else {
+        print_summary(&summary);
+    }
+    Ok(())
+}
+
+fn print_summary(summary: &PcapSummary) {
+    // --a11y 时表格的每一行改为带标签的一行
+    let labeled = a11y::is_enabled();
+    println!(
+        "File: {} ({})",
+        summary.file,
+        match summary.format {
+            CaptureFormat::Pcap => "pcap",
+            CaptureFormat::Pcapng => "pcapng",
+        }
+    );
+    println!(
+        "Packets: {} (matched {}), Bytes: {}, Duration: {:.3}s",
+        summary.total_packets,
+        summary.matched_packets,
+        human_bytes(summary.bytes),
+        summary.duration_secs
+    );
+
+    println!();
+    println!("Protocols:");
+    for stat in &summary.protocols {
+        let share = if summary.matched_packets == 0 {
+            0.0
+        } else {
+            stat.packets as f64 * 100.0 / summary.matched_packets as f64
+        };
+        if labeled {
+            println!(
+                "  {}",
+                a11y::line(&[
+                    ("protocol", &stat.protocol),
+                    ("packets", &stat.packets),
+                    ("share", &format!("{:.1} percent", share)),
+                    ("bytes", &human_bytes(stat.bytes)),
+                ])
+            );
+            continue;
+        }
+        println!(
+            "  {:<10} {:>10} {:>6.1}% {:>10}",
+            stat.protocol,
+            stat.packets,
+            share,
+            human_bytes(stat.bytes)
+        );
+    }
+
+    println!();
+    println!("Top talkers:");
+    if !labeled {
+        println!(
+            "  {:<40} {:>10} {:>10} {:>10}",
+            "ADDRESS", "PACKETS", "SENT", "RECEIVED"
+        );
+    }
+    for talker in &summary.top_talkers {
+        if labeled {
+            println!(
+                "  {}",
+                a11y::line(&[
+                    ("address", &talker.address),
+                    ("packets", &talker.packets),
+                    ("sent", &human_bytes(talker.bytes_sent)),
+                    ("received", &human_bytes(talker.bytes_received)),
+                ])
+            );
+            continue;
+        }
+        println!(
+            "  {:<40} {:>10} {:>10} {:>10}",
+            talker.address,
+            talker.packets,
+            human_bytes(talker.bytes_sent),
+            human_bytes(talker.bytes_received)
+        );
+    }
+
+    let handshakes = &summary.tcp_handshakes;
+    println!();
+    println!(
+        "TCP handshakes: {} attempts, {} established, {} refused, {} no response",
+        handshakes.attempts, handshakes.established, handshakes.refused, handshakes.no_response
+    );
+    if labeled {
+        for failure in &handshakes.failures {
+            println!(
+                "  {}",
+                a11y::line(&[
+                    ("server", &failure.server),
+                    ("refused", &failure.refused),
+                    ("no response", &failure.no_response),
+                ])
+            );
+        }
+    } else if !handshakes.failures.is_empty() {
+        println!("  {:<46} {:>8} {:>12}", "SERVER", "REFUSED", "NO RESPONSE");
+        for failure in &handshakes.failures {
+            println!(
+                "  {:<46} {:>8} {:>12}",
+                failure.server, failure.refused, failure.no_response
+            );
+        }
+    }
+
+    let dns = &summary.dns;
+    println!();
+    println!(
+        "DNS: {} queries ({} unique), {} responses, {} NXDOMAIN",
+        dns.queries, dns.unique_names, dns.responses, dns.nxdomain
+    );
+    if labeled {
+        for query in &dns.top_queries {
+            println!(
+                "  {}",
+                a11y::line(&[
+                    ("name", &query.name),
+                    ("type", &query.qtype),
+                    ("queries", &query.queries),
+                    ("NXDOMAIN", &query.nxdomain),
+                ])
+            );
+        }
+    } else if !dns.top_queries.is_empty() {
+        println!(
+            "  {:<48} {:<6} {:>8} {:>9}",
+            "NAME", "TYPE", "QUERIES", "NXDOMAIN"
+        );
+        for query in &dns.top_queries {
+            println!(
+                "  {:<48} {:<6} {:>8} {:>9}",
+                query.name, query.qtype, query.queries, query.nxdomain
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ipv4_packet(src: [u8; 4], dst: [u8; 4], protocol: u8, transport: &[u8]) -> Vec<u8> {
+        let mut frame = vec![0u8; 12];
+        frame.extend_from_slice(&ETHERTYPE_IPV4.to_be_bytes());
+        let total_len = (20 + transport.len()) as u16;
+        frame.extend_from_slice(&[0x45, 0]);
+        frame.extend_from_slice(&total_len.to_be_bytes());
+        frame.extend_from_slice(&[0, 0, 0x40, 0, 64, protocol, 0, 0]);
+        frame.extend_from_slice(&src);
+        frame.extend_from_slice(&dst);
+        frame.extend_from_slice(transport);
+        frame
+    }
+
+    fn tcp(sport: u16, dport: u16, flags: u8) -> Vec<u8> {
+        let mut segment = Vec::new();
+        segment.extend_from_slice(&sport.to_be_bytes());
+        segment.extend_from_slice(&dport.to_be_bytes());
+        segment.extend_from_slice(&[0; 8]);
+        segment.extend_from_slice(&[0x50, flags, 0xFF, 0xFF, 0, 0, 0, 0]);
+        segment
+    }
+
+    fn dns_udp(sport: u16, dport: u16, response: bool, rcode: u8, name: &str) -> Vec<u8> {
+        let mut dns = vec![0x12, 0x34];
+        let flags: u16 = if response {
+            0x8180 | u16::from(rcode)
+        } else {
+            0x0100
+        };
+        dns.extend_from_slice(&flags.to_be_bytes());
+        dns.extend_from_slice(&[0, 1, 0, 0, 0, 0, 0, 0]);
+        for label in name.split('.') {
+            dns.push(label.len() as u8);
+            dns.extend_from_slice(label.as_bytes());
+        }
+        dns.extend_from_slice(&[0, 0, 1, 0, 1]);
+
+        let mut datagram = Vec::new();
+        datagram.extend_from_slice(&sport.to_be_bytes());
+        datagram.extend_from_slice(&dport.to_be_bytes());
+        datagram.extend_from_slice(&((8 + dns.len()) as u16).to_be_bytes());
+        datagram.extend_from_slice(&[0, 0]);
+        datagram.extend(dns);
+        datagram
+    }
+
+    fn pcap_file(frames: &[Vec<u8>]) -> Vec<u8> {
+        let mut file = vec![0xD4, 0xC3, 0xB2, 0xA1, 2, 0, 4, 0];
+        file.extend_from_slice(&[0; 8]);
+        file.extend_from_slice(&65535u32.to_le_bytes());
+        file.extend_from_slice(&LINKTYPE_ETHERNET.to_le_bytes());
+        for (index, frame) in frames.iter().enumerate() {
+            file.extend_from_slice(&(1_700_000_000u32 + index as u32).to_le_bytes());
+            file.extend_from_slice(&0u32.to_le_bytes());
+            file.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+            file.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+            file.extend_from_slice(frame);
+        }
+        file
+    }
+
+    fn sample_frames() -> Vec<Vec<u8>> {
+        let client = [10, 0, 0, 2];
+        let web = [10, 0, 0, 80];
+        let dns = [10, 0, 0, 53];
+        vec![
+            // 成功握手
+            ipv4_packet(client, web, 6, &tcp(40000, 443, TCP_SYN)),
+            ipv4_packet(web, client, 6, &tcp(443, 40000, TCP_SYN | TCP_ACK)),
+            ipv4_packet(client, web, 6, &tcp(40000, 443, TCP_ACK)),
+            // 被拒绝
+            ipv4_packet(client, web, 6, &tcp(40001, 8080, TCP_SYN)),
+            ipv4_packet(web, client, 6, &tcp(8080, 40001, TCP_RST | TCP_ACK)),
+            // 无响应（含一次重传）
+            ipv4_packet(client, web, 6, &tcp(40002, 22, TCP_SYN)),
+            ipv4_packet(client, web, 6, &tcp(40002, 22, TCP_SYN)),
+            // DNS
+            ipv4_packet(client, dns, 17, &dns_udp(5000, 53, false, 0, "Example.com")),
+            ipv4_packet(dns, client, 17, &dns_udp(53, 5000, true, 0, "example.com")),
+            ipv4_packet(
+                client,
+                dns,
+                17,
+                &dns_udp(5001, 53, false, 0, "nope.invalid"),
+            ),
+            ipv4_packet(dns, client, 17, &dns_udp(53, 5001, true, 3, "nope.invalid")),
+        ]
+    }
+
+    #[test]
+    fn summarizes_pcap_file() {
+        let data = pcap_file(&sample_frames());
+        let filter = Filter {
+            host: None,
+            port: None,
+        };
+        let summary = summarize(data.as_slice(), "test.pcap".into(), &filter, 10).unwrap();
+
+        assert_eq!(summary.format, CaptureFormat::Pcap);
+        assert_eq!(summary.total_packets, 11);
+        assert_eq!(summary.duration_secs, 10.0);
+        assert_eq!(summary.protocols[0].protocol, "TCP");
+        assert_eq!(summary.protocols[0].packets, 7);
+        assert_eq!(summary.top_talkers[0].address, "10.0.0.2");
+
+        let handshakes = &summary.tcp_handshakes;
+        assert_eq!(handshakes.attempts, 3);
+        assert_eq!(handshakes.established, 1);
+        assert_eq!(handshakes.refused, 1);
+        assert_eq!(handshakes.no_response, 1);
+
+        assert_eq!(summary.dns.queries, 2);
+        assert_eq!(summary.dns.nxdomain, 1);
+        let nope = summary
+            .dns
+            .top_queries
+            .iter()
+            .find(|q| q.name == "nope.invalid")
+            .unwrap();
+        assert_eq!(nope.qtype, "A");
+        assert_eq!(nope.nxdomain, 1);
+    }
+
+    #[test]
+    fn filters_by_host_and_port() {
+        let data = pcap_file(&sample_frames());
+        let filter = Filter {
+            host: Some("10.0.0.80".parse().unwrap()),
+            port: Some(8080),
+        };
+        let summary = summarize(data.as_slice(), "test.pcap".into(), &filter, 10).unwrap();
+        assert_eq!(summary.matched_packets, 2);
+        assert_eq!(summary.tcp_handshakes.refused, 1);
+        assert_eq!(summary.dns.queries, 0);
+    }
+
+    #[test]
+    fn reads_pcapng_blocks() {
+        let frame = ipv4_packet(
+            [1, 1, 1, 1],
+            [2, 2, 2, 2],
+            17,
+            &dns_udp(1, 53, false, 0, "a.b"),
+        );
+        let mut file = Vec::new();
+        // Section Header Block
+        file.extend_from_slice(&PCAPNG_SHB.to_le_bytes());
+        file.extend_from_slice(&28u32.to_le_bytes());
+        file.extend_from_slice(&PCAPNG_BYTE_ORDER.to_le_bytes());
+        file.extend_from_slice(&[1, 0, 0, 0]);
+        file.extend_from_slice(&u64::MAX.to_le_bytes());
+        file.extend_from_slice(&28u32.to_le_bytes());
+        // Interface Description Block，tsresol = 10^-9
+        file.extend_from_slice(&1u32.to_le_bytes());
+        file.extend_from_slice(&28u32.to_le_bytes());
+        file.extend_from_slice(&(LINKTYPE_ETHERNET as u16).to_le_bytes());
+        file.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+        file.extend_from_slice(&[9, 0, 1, 0, 9, 0, 0, 0]);
+        file.extend_from_slice(&28u32.to_le_bytes());
+        // Enhanced Packet Block
+        let padded = frame.len().div_ceil(4) * 4;
+        let total = (32 + padded) as u32;
+        file.extend_from_slice(&6u32.to_le_bytes());
+        file.extend_from_slice(&total.to_le_bytes());
+        file.extend_from_slice(&0u32.to_le_bytes());
+        let ts: u64 = 1_500_000_000_000_000_000;
+        file.extend_from_slice(&((ts >> 32) as u32).to_le_bytes());
+        file.extend_from_slice(&(ts as u32).to_le_bytes());
+        file.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        file.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        file.extend_from_slice(&frame);
+        file.resize(file.len() + padded - frame.len(), 0);
+        file.extend_from_slice(&total.to_le_bytes());
+
+        let mut reader = CaptureReader::new(file.as_slice()).unwrap();
+        let packet = reader.next_packet().unwrap().unwrap();
+        assert_eq!(reader.format(), CaptureFormat::Pcapng);
+        assert_eq!(packet.data, frame);
+        assert!((packet.timestamp - 1_500_000_000.0).abs() < 1e-3);
+        assert!(reader.next_packet().unwrap().is_none());
+    }
+
+    #[test]
+    fn rejects_truncated_interface_options() {
+        let mut file = Vec::new();
+        file.extend_from_slice(&PCAPNG_SHB.to_le_bytes());
+        file.extend_from_slice(&28u32.to_le_bytes());
+        file.extend_from_slice(&PCAPNG_BYTE_ORDER.to_le_bytes());
+        file.extend_from_slice(&[1, 0, 0, 0]);
+        file.extend_from_slice(&u64::MAX.to_le_bytes());
+        file.extend_from_slice(&28u32.to_le_bytes());
+        // if_tsresol 声明 1 字节，但块在选项头之后就结束了
+        file.extend_from_slice(&1u32.to_le_bytes());
+        file.extend_from_slice(&24u32.to_le_bytes());
+        file.extend_from_slice(&(LINKTYPE_ETHERNET as u16).to_le_bytes());
+        file.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+        file.extend_from_slice(&[9, 0, 1, 0]);
+        file.extend_from_slice(&24u32.to_le_bytes());
+
+        let result =
+            CaptureReader::new(file.as_slice()).and_then(|mut reader| reader.next_packet());
+        assert!(matches!(result, Err(PcapError::Corrupt(_))));
+    }
+
+    #[test]
+    fn rejects_unknown_files() {
+        assert!(matches!(
+            CaptureReader::new(&b"hello world, not a capture"[..]),
+            Err(PcapError::UnknownFormat)
+        ));
+    }
+}
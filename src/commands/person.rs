@@ -0,0 +1,817 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::{Datelike, Local, NaiveDate};
+use clap::ValueEnum;
+use fake::faker::internet::raw::FreeEmailProvider;
+use fake::locales::EN;
+use fake::Fake;
+use rand::seq::IndexedRandom;
+use rand::{rng, Rng};
+use serde::Serialize;
+
+use crate::utils::areas::{
+    get_full_area_info, get_full_area_info_str, random_area, random_region_by_code,
+    random_street_in_region,
+};
+use crate::utils::idcard::compose_id_number;
+use crate::utils::metrics;
+use crate::utils::names::{self, parse_ratio, NameMix, NameStyle};
+use crate::utils::output::RecordOutput;
+
+pub const MAX_PERSON_COUNT: u32 = 1_000_000;
+
+// 户主与配偶的最小年龄，子女与父母的最小年龄差
+const MIN_PARENT_AGE: u32 = 22;
+const MIN_PARENT_GAP: u32 = 20;
+// 有雇佣关系的年龄范围和受雇比例
+const WORKING_AGES: std::ops::RangeInclusive<u32> = 18..=60;
+const EMPLOYMENT_RATE: f64 = 0.8;
+
+const MOBILE_PREFIXES: &[&str] = &[
+    "130", "131", "132", "133", "135", "136", "137", "138", "139", "150", "151", "152", "155",
+    "156", "157", "158", "159", "166", "177", "180", "181", "182", "185", "186", "187", "188",
+    "189", "198", "199",
+];
+// 公司名：地名 + 字号 + 行业 + 有限公司
+const COMPANY_BRAND_CHARS: &[&str] = &[
+    "华", "鑫", "恒", "瑞", "泰", "达", "盛", "源", "信", "诚", "安", "联", "创", "宏", "远", "嘉",
+    "博", "易", "捷", "汇", "金", "天", "海", "正",
+];
+const COMPANY_INDUSTRIES: &[&str] = &[
+    "科技",
+    "信息技术",
+    "贸易",
+    "建设工程",
+    "餐饮管理",
+    "物流",
+    "文化传媒",
+    "电子商务",
+    "医药",
+    "机械制造",
+    "网络科技",
+    "商贸",
+];
+const EMAIL_PREFIXES: &[&str] = &["user", "mail", "test", "demo", "cn", "hello"];
+
+#[derive(clap::Args)]
+pub struct PersonOpts {
+    #[arg(
+        short = 'n',
+        long,
+        default_value_t = 1,
+        help = "生成数量，--family 时为家庭户数"
+    )]
+    count: u32,
+
+    #[arg(short = 'r', long = "region", help = "地区, 例如 11、1101 或 110101")]
+    region: Option<String>,
+
+    #[arg(long, help = "按家庭生成：户主、配偶和子女共享户籍地址")]
+    family: bool,
+
+    #[arg(long, default_value_t = 18, help = "最小年龄（家庭模式下作用于户主）")]
+    min_age: u32,
+
+    #[arg(long, default_value_t = 65, help = "最大年龄（家庭模式下作用于户主）")]
+    max_age: u32,
+
+    #[arg(
+        long = "name-mix",
+        value_name = "SPEC",
+        value_parser = NameMix::parse,
+        help = "按权重混合姓名写法，例如 han=70,uyghur=10,tibetan=10,mongolian=10（默认全部为汉族姓名）"
+    )]
+    name_mix: Option<NameMix>,
+
+    #[arg(
+        long = "long-names",
+        value_name = "RATIO",
+        value_parser = parse_ratio,
+        default_value_t = 0.0,
+        help = "使用超长写法（复姓、长音译名、带氏族名等）的比例，0-1 或百分比"
+    )]
+    long_names: f64,
+
+    #[arg(value_enum, short = 't', long = "type", default_value_t = PersonFormat::Text, help = "输出类型")]
+    output_type: PersonFormat,
+
+    #[arg(short = 'o', long = "output", help = "输出文件")]
+    output: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "把人员之间的关系（配偶、父母子女、雇佣）写入单独的边文件，扩展名为 .json 时输出 JSON，否则输出 CSV"
+    )]
+    edges: Option<PathBuf>,
+
+    #[arg(
+        long,
+        default_value_t = 0,
+        requires = "company_output",
+        help = "同时生成的公司数量，18-60 岁的人员有八成随机受雇于其中一家"
+    )]
+    companies: u32,
+
+    #[arg(
+        long = "company-output",
+        value_name = "FILE",
+        requires = "edges",
+        help = "公司数据的输出文件，格式按扩展名与 --edges 相同"
+    )]
+    company_output: Option<PathBuf>,
+
+    // 全局 --where / --fields / --rename，由命令行入口注入
+    #[arg(skip)]
+    pub(crate) record_output: RecordOutput,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum PersonFormat {
+    Text,
+    Csv,
+    Json,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum PersonError {
+    #[error("count must be between 1 and {0}")]
+    InvalidCount(u32),
+    #[error("invalid age range {0}-{1}")]
+    InvalidAgeRange(u32, u32),
+    #[error("family mode requires max age of at least {0}")]
+    FamilyAgeTooLow(u32),
+    #[error("region must be 2, 4, or 6 digits and exist in the area table")]
+    InvalidRegion,
+    #[error("companies must be at most {0}")]
+    InvalidCompanyCount(u32),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+    #[error(transparent)]
+    Csv(#[from] csv::Error),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Relation {
+    Head,
+    Spouse,
+    Child,
+}
+
+impl Relation {
+    fn display_name(self) -> &'static str {
+        match self {
+            Relation::Head => "户主",
+            Relation::Spouse => "配偶",
+            Relation::Child => "子女",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Sex {
+    Male,
+    Female,
+}
+
+impl Sex {
+    fn random() -> Self {
+        if rng().random_bool(0.5) {
+            Sex::Male
+        } else {
+            Sex::Female
+        }
+    }
+
+    fn opposite(self) -> Self {
+        match self {
+            Sex::Male => Sex::Female,
+            Sex::Female => Sex::Male,
+        }
+    }
+
+    fn display_name(self) -> &'static str {
+        match self {
+            Sex::Male => "男",
+            Sex::Female => "女",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PersonRecord {
+    pub person_id: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub household_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub relation: Option<Relation>,
+    pub name: String,
+    pub gender: Sex,
+    pub age: u32,
+    pub birthday: String,
+    pub id_number: String,
+    pub phone: String,
+    pub email: String,
+    pub region: String,
+    pub address: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CompanyRecord {
+    pub company_id: u64,
+    pub name: String,
+    pub region: String,
+    pub address: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EdgeRelation {
+    // 户主指向配偶
+    Spouse,
+    // 父母指向子女
+    Parent,
+    // 人员指向公司
+    EmployedBy,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeType {
+    Person,
+    Company,
+}
+
+// 边文件的一行，source_id / target_id 分别对应 person_id 或 company_id
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Edge {
+    pub source_type: NodeType,
+    pub source_id: u64,
+    pub target_type: NodeType,
+    pub target_id: u64,
+    pub relation: EdgeRelation,
+}
+
+// 同一户共享的户籍信息
+struct Household {
+    region: String,
+    address: String,
+}
+
+pub fn run_person(opts: PersonOpts) -> Result<(), PersonError> {
+    if opts.companies > MAX_PERSON_COUNT {
+        return Err(PersonError::InvalidCompanyCount(MAX_PERSON_COUNT));
+    }
+    let mut records = generate_people(&opts)?;
+    let companies = generate_companies(opts.companies, opts.region.as_deref());
+    // 关系按过滤前的全部人员生成，再去掉指向已过滤人员的边
+    let mut edges = link_records(&records, &companies);
+    opts.record_output.retain(&mut records)?;
+    if let Some(path) = &opts.edges {
+        let kept: HashSet<u64> = records.iter().map(|record| record.person_id).collect();
+        let kept = |node: NodeType, id: u64| node == NodeType::Company || kept.contains(&id);
+        edges.retain(|edge| {
+            kept(edge.source_type, edge.source_id) && kept(edge.target_type, edge.target_id)
+        });
+        write_table(path, &edges)?;
+    }
+    if let Some(path) = &opts.company_output {
+        write_table(path, &companies)?;
+    }
+
+    match &opts.output {
+        Some(path) => write_records(
+            &records,
+            opts.output_type,
+            &opts.record_output,
+            File::create(path)?,
+        ),
+        None => write_records(
+            &records,
+            opts.output_type,
+            &opts.record_output,
+            io::stdout().lock(),
+        ),
+    }
+}
+
+fn generate_people(opts: &PersonOpts) -> Result<Vec<PersonRecord>, PersonError> {
+    if opts.count == 0 || opts.count > MAX_PERSON_COUNT {
+        return Err(PersonError::InvalidCount(MAX_PERSON_COUNT));
+    }
+    if opts.min_age > opts.max_age || opts.max_age > 120 {
+        return Err(PersonError::InvalidAgeRange(opts.min_age, opts.max_age));
+    }
+    if opts.family && opts.max_age < MIN_PARENT_AGE {
+        return Err(PersonError::FamilyAgeTooLow(MIN_PARENT_AGE));
+    }
+    let region = opts
+        .region
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty());
+    if let Some(code) = region {
+        if !matches!(code.len(), 2 | 4 | 6)
+            || !code.chars().all(|c| c.is_ascii_digit())
+            || random_region_by_code(code).is_none()
+        {
+            return Err(PersonError::InvalidRegion);
+        }
+    }
+
+    let today = Local::now().date_naive();
+    let mut generator = Generator {
+        today,
+        next_person_id: 1,
+        records: Vec::new(),
+        long_names: opts.long_names,
+    };
+    let name_mix = opts.name_mix.clone().unwrap_or_default();
+
+    for household_id in 1..=u64::from(opts.count) {
+        let household = random_household(region);
+        if !opts.family {
+            let age = rng().random_range(opts.min_age..=opts.max_age);
+            generator.push(&household, None, Sex::random(), name_mix.pick(), None, age);
+            continue;
+        }
+
+        let head_age = rng().random_range(opts.min_age.max(MIN_PARENT_AGE)..=opts.max_age);
+        let head_sex = Sex::random();
+        // 一户人家使用同一种姓名写法
+        let style = name_mix.pick();
+        let head_surname = generator.push(
+            &household,
+            Some((household_id, Relation::Head)),
+            head_sex,
+            style,
+            None,
+            head_age,
+        );
+
+        let spouse_age = (head_age as i64 + rng().random_range(-5..=5)).clamp(
+            i64::from(MIN_PARENT_AGE),
+            i64::from(opts.max_age.max(head_age)),
+        ) as u32;
+        let spouse_surname = generator.push(
+            &household,
+            Some((household_id, Relation::Spouse)),
+            head_sex.opposite(),
+            style,
+            None,
+            spouse_age,
+        );
+
+        // 子女随父姓（维吾尔族姓名中为父亲的本名），年龄至少比父母中较年轻的一方小 20 岁
+        let father_surname = match head_sex {
+            Sex::Male => head_surname,
+            Sex::Female => spouse_surname,
+        };
+        let youngest_parent = head_age.min(spouse_age);
+        if youngest_parent >= MIN_PARENT_GAP {
+            let max_child_age = youngest_parent - MIN_PARENT_GAP;
+            for _ in 0..rng().random_range(0..=3) {
+                let age = rng().random_range(0..=max_child_age);
+                generator.push(
+                    &household,
+                    Some((household_id, Relation::Child)),
+                    Sex::random(),
+                    style,
+                    Some(&father_surname),
+                    age,
+                );
+            }
+        }
+    }
+
+    metrics::records_generated("person", generator.records.len());
+    Ok(generator.records)
+}
+
+struct Generator {
+    today: NaiveDate,
+    next_person_id: u64,
+    records: Vec<PersonRecord>,
+    long_names: f64,
+}
+
+impl Generator {
+    // 生成一条记录并返回子女可继承的姓氏部分
+    fn push(
+        &mut self,
+        household: &Household,
+        relation: Option<(u64, Relation)>,
+        sex: Sex,
+        style: NameStyle,
+        surname: Option<&str>,
+        age: u32,
+    ) -> String {
+        let birthday = birthday_for_age(self.today, age);
+        let long = rng().random_bool(self.long_names);
+        let name = names::generate(style, sex == Sex::Male, surname, long);
+        let id_number = compose_id_number(&household.region, birthday, random_seq(sex));
+        let email_prefix = EMAIL_PREFIXES.choose(&mut rng()).copied().unwrap_or("user");
+        let email_domain: String = FreeEmailProvider(EN).fake();
+
+        self.records.push(PersonRecord {
+            person_id: self.next_person_id,
+            household_id: relation.map(|(id, _)| id),
+            relation: relation.map(|(_, relation)| relation),
+            name: name.full,
+            gender: sex,
+            age,
+            birthday: birthday.format("%Y-%m-%d").to_string(),
+            email: format!(
+                "{}{}@{}",
+                email_prefix,
+                &id_number[id_number.len() - 8..],
+                email_domain
+            )
+            .to_lowercase(),
+            id_number,
+            phone: random_mobile(),
+            region: household.region.clone(),
+            address: household.address.clone(),
+        });
+        self.next_person_id += 1;
+        name.inherited
+    }
+}
+
+fn generate_companies(count: u32, region: Option<&str>) -> Vec<CompanyRecord> {
+    let region = region.map(str::trim).filter(|value| !value.is_empty());
+    (1..=u64::from(count))
+        .map(|company_id| {
+            let site = random_household(region);
+            CompanyRecord {
+                company_id,
+                name: company_name(&site.region),
+                region: site.region,
+                address: site.address,
+            }
+        })
+        .collect()
+}
+
+fn company_name(region: &str) -> String {
+    // 直辖市的“市辖区”“县”不是地名，改用省级名称
+    let place = match get_full_area_info(region) {
+        Some((province, city, _)) if matches!(city.name.as_str(), "市辖区" | "县") => {
+            province.name
+        }
+        Some((_, city, _)) => city.name,
+        None => String::new(),
+    };
+    let place = place.trim_end_matches(['市', '省']);
+    let mut rng = rng();
+    let brand: String = (0..2)
+        .map(|_| {
+            COMPANY_BRAND_CHARS
+                .choose(&mut rng)
+                .copied()
+                .unwrap_or("华")
+        })
+        .collect();
+    let industry = COMPANY_INDUSTRIES
+        .choose(&mut rng)
+        .copied()
+        .unwrap_or("科技");
+    format!("{}{}{}有限公司", place, brand, industry)
+}
+
+// 按户内身份生成配偶、父母子女关系，并为工作年龄的人员随机指定雇主
+fn link_records(records: &[PersonRecord], companies: &[CompanyRecord]) -> Vec<Edge> {
+    let person_edge = |source_id, target_id, relation| Edge {
+        source_type: NodeType::Person,
+        source_id,
+        target_type: NodeType::Person,
+        target_id,
+        relation,
+    };
+    let mut households: HashMap<u64, Vec<&PersonRecord>> = HashMap::new();
+    for record in records {
+        if let Some(household_id) = record.household_id {
+            households.entry(household_id).or_default().push(record);
+        }
+    }
+    let mut household_ids: Vec<u64> = households.keys().copied().collect();
+    household_ids.sort_unstable();
+
+    let mut edges = Vec::new();
+    for household_id in household_ids {
+        let members = &households[&household_id];
+        let with = |relation| members.iter().filter(move |m| m.relation == Some(relation));
+        let parents: Vec<u64> = with(Relation::Head)
+            .chain(with(Relation::Spouse))
+            .map(|m| m.person_id)
+            .collect();
+        for spouse in with(Relation::Spouse) {
+            for head in with(Relation::Head) {
+                edges.push(person_edge(
+                    head.person_id,
+                    spouse.person_id,
+                    EdgeRelation::Spouse,
+                ));
+            }
+        }
+        for child in with(Relation::Child) {
+            for &parent in &parents {
+                edges.push(person_edge(parent, child.person_id, EdgeRelation::Parent));
+            }
+        }
+    }
+
+    if !companies.is_empty() {
+        let mut rng = rng();
+        for record in records {
+            if WORKING_AGES.contains(&record.age) && rng.random_bool(EMPLOYMENT_RATE) {
+                if let Some(company) = companies.choose(&mut rng) {
+                    edges.push(Edge {
+                        source_type: NodeType::Person,
+                        source_id: record.person_id,
+                        target_type: NodeType::Company,
+                        target_id: company.company_id,
+                        relation: EdgeRelation::EmployedBy,
+                    });
+                }
+            }
+        }
+    }
+    edges
+}
+
+// 扩展名为 .json 时写 JSON 数组，否则写带表头的 CSV
+fn write_table<T: Serialize>(path: &Path, rows: &[T]) -> Result<(), PersonError> {
+    let json = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+    if json {
+        let mut writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(&mut writer, rows)?;
+        writeln!(writer)?;
+        writer.flush()?;
+    } else {
+        let mut writer = csv::Writer::from_path(path)?;
+        for row in rows {
+            writer.serialize(row)?;
+        }
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+fn random_household(region: Option<&str>) -> Household {
+    let code6 = region
+        .and_then(random_region_by_code)
+        .unwrap_or_else(random_area);
+    let area = get_full_area_info_str(&code6).unwrap_or_else(|| "地址未知".to_string());
+    let street = random_street_in_region(&code6)
+        .map(|street| street.name)
+        .unwrap_or_default();
+    let mut rng = rng();
+    let address = format!(
+        "{}{}{}号{}单元{}室",
+        area,
+        street,
+        rng.random_range(1..=300),
+        rng.random_range(1..=6),
+        rng.random_range(1..=30) * 100 + rng.random_range(1..=4)
+    );
+    Household {
+        region: code6,
+        address,
+    }
+}
+
+pub(crate) fn random_mobile() -> String {
+    let mut rng = rng();
+    let prefix = MOBILE_PREFIXES.choose(&mut rng).copied().unwrap_or("138");
+    format!("{}{:08}", prefix, rng.random_range(0..100_000_000))
+}
+
+// 顺序码末位奇数为男、偶数为女
+fn random_seq(sex: Sex) -> u16 {
+    let n: u16 = rng().random_range(0..500) * 2;
+    match sex {
+        Sex::Male => n + 1,
+        Sex::Female => n,
+    }
+}
+
+// 在满足“今天正好 age 岁”的出生日期区间内随机取一天
+fn birthday_for_age(today: NaiveDate, age: u32) -> NaiveDate {
+    let latest = years_before(today, age);
+    let earliest = years_before(today, age + 1) + chrono::Duration::days(1);
+    let days = (latest - earliest).num_days().max(0);
+    earliest + chrono::Duration::days(rng().random_range(0..=days))
+}
+
+fn years_before(date: NaiveDate, years: u32) -> NaiveDate {
+    let year = date.year() - years as i32;
+    NaiveDate::from_ymd_opt(year, date.month(), date.day())
+        // 2 月 29 日回退到 2 月 28 日
+        .or_else(|| NaiveDate::from_ymd_opt(year, date.month(), date.day() - 1))
+        .unwrap_or(date)
+}
+
+fn write_records<W: Write>(
+    records: &[PersonRecord],
+    format: PersonFormat,
+    output: &RecordOutput,
+    mut writer: W,
+) -> Result<(), PersonError> {
+    match format {
+        PersonFormat::Text => {
+            for record in records {
+                let household = record
+                    .household_id
+                    .zip(record.relation)
+                    .map(|(id, relation)| format!("户 {}\t{}\t", id, relation.display_name()))
+                    .unwrap_or_default();
+                writeln!(
+                    writer,
+                    "{}姓名: {}\t 性别: {}\t 年龄: {}\t 身份证号: {}\t 手机: {}\t 地址: {}",
+                    household,
+                    record.name,
+                    record.gender.display_name(),
+                    record.age,
+                    record.id_number,
+                    record.phone,
+                    record.address
+                )?;
+            }
+        }
+        PersonFormat::Csv if output.is_shaped() => output.write_csv(records, writer)?,
+        PersonFormat::Csv => {
+            let mut csv_writer = csv::Writer::from_writer(writer);
+            csv_writer.write_record([
+                "person_id",
+                "household_id",
+                "relation",
+                "name",
+                "gender",
+                "age",
+                "birthday",
+                "id_number",
+                "phone",
+                "email",
+                "region",
+                "address",
+            ])?;
+            for record in records {
+                csv_writer.write_record([
+                    record.person_id.to_string(),
+                    record
+                        .household_id
+                        .map(|id| id.to_string())
+                        .unwrap_or_default(),
+                    record
+                        .relation
+                        .map(|relation| format!("{:?}", relation).to_lowercase())
+                        .unwrap_or_default(),
+                    record.name.clone(),
+                    format!("{:?}", record.gender).to_lowercase(),
+                    record.age.to_string(),
+                    record.birthday.clone(),
+                    record.id_number.clone(),
+                    record.phone.clone(),
+                    record.email.clone(),
+                    record.region.clone(),
+                    record.address.clone(),
+                ])?;
+            }
+            csv_writer.flush()?;
+        }
+        PersonFormat::Json => {
+            output.write_json(records, writer)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::idcard::{validate_id_number, RegionCheck};
+
+    fn age_on(birthday: NaiveDate, today: NaiveDate) -> u32 {
+        let mut age = today.year() - birthday.year();
+        if (today.month(), today.day()) < (birthday.month(), birthday.day()) {
+            age -= 1;
+        }
+        age.max(0) as u32
+    }
+
+    fn opts(family: bool, count: u32) -> PersonOpts {
+        PersonOpts {
+            count,
+            region: Some("3301".to_string()),
+            family,
+            min_age: 18,
+            max_age: 65,
+            name_mix: None,
+            long_names: 0.0,
+            output_type: PersonFormat::Json,
+            output: None,
+            edges: None,
+            companies: 0,
+            company_output: None,
+            record_output: RecordOutput::default(),
+        }
+    }
+
+    #[test]
+    fn records_are_internally_consistent() {
+        let today = Local::now().date_naive();
+        for record in generate_people(&opts(false, 50)).unwrap() {
+            let parsed = validate_id_number(&record.id_number, RegionCheck::Strict).unwrap();
+            assert_eq!(parsed.region, record.region);
+            assert!(record.region.starts_with("3301"));
+            assert_eq!(
+                parsed.birthday.format("%Y-%m-%d").to_string(),
+                record.birthday
+            );
+            assert_eq!(age_on(parsed.birthday, today), record.age);
+            let expected_gender = match record.gender {
+                Sex::Male => "male",
+                Sex::Female => "female",
+            };
+            assert_eq!(parsed.gender, expected_gender);
+            assert!((18..=65).contains(&record.age));
+        }
+    }
+
+    #[test]
+    fn family_members_share_household() {
+        let records = generate_people(&opts(true, 20)).unwrap();
+        for household_id in 1..=20 {
+            let members: Vec<_> = records
+                .iter()
+                .filter(|r| r.household_id == Some(household_id))
+                .collect();
+            let head = members[0];
+            let spouse = members[1];
+            assert_eq!(head.relation, Some(Relation::Head));
+            assert_eq!(spouse.relation, Some(Relation::Spouse));
+            assert_ne!(head.gender, spouse.gender);
+            let youngest_parent = head.age.min(spouse.age);
+            for member in &members {
+                assert_eq!(member.region, head.region);
+                assert_eq!(member.address, head.address);
+                if member.relation == Some(Relation::Child) {
+                    assert!(member.age + MIN_PARENT_GAP <= youngest_parent);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn edges_link_families_and_employers() {
+        let records = generate_people(&opts(true, 10)).unwrap();
+        let companies = generate_companies(3, Some("3301"));
+        assert!(companies.iter().all(|c| c.region.starts_with("3301")));
+        let edges = link_records(&records, &companies);
+        let by_id: HashMap<u64, &PersonRecord> = records.iter().map(|r| (r.person_id, r)).collect();
+        let children = records
+            .iter()
+            .filter(|r| r.relation == Some(Relation::Child))
+            .count();
+        let count = |relation| edges.iter().filter(|e| e.relation == relation).count();
+        assert_eq!(count(EdgeRelation::Spouse), 10);
+        assert_eq!(count(EdgeRelation::Parent), children * 2);
+        for edge in &edges {
+            let source = by_id[&edge.source_id];
+            match edge.relation {
+                EdgeRelation::Spouse | EdgeRelation::Parent => {
+                    let target = by_id[&edge.target_id];
+                    assert_eq!(source.household_id, target.household_id);
+                    assert_eq!(edge.target_type, NodeType::Person);
+                }
+                EdgeRelation::EmployedBy => {
+                    assert!(WORKING_AGES.contains(&source.age));
+                    assert!((1..=3).contains(&edge.target_id));
+                }
+            }
+        }
+        assert!(link_records(&records, &[])
+            .iter()
+            .all(|e| e.relation != EdgeRelation::EmployedBy));
+    }
+
+    #[test]
+    fn birthday_matches_requested_age() {
+        let today = NaiveDate::from_ymd_opt(2024, 2, 29).unwrap();
+        for age in [0, 1, 30, 99] {
+            for _ in 0..20 {
+                assert_eq!(age_on(birthday_for_age(today, age), today), age);
+            }
+        }
+    }
+}
@@ -0,0 +1,324 @@
+use std::io;
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::utils::icmp::EchoSocket;
+use crate::utils::network::{self, NetworkError};
+use crate::utils::output::RecordOutput;
+
+#[derive(clap::Args)]
+pub struct PingOpts {
+    #[arg(value_name = "HOST", help = "目标主机")]
+    host: String,
+
+    #[arg(
+        short = 'c',
+        long,
+        default_value_t = 4,
+        value_name = "N",
+        value_parser = clap::value_parser!(u32).range(1..),
+        help = "发送的探测次数"
+    )]
+    count: u32,
+
+    #[arg(
+        short = 'i',
+        long,
+        default_value_t = 1000,
+        value_name = "MS",
+        help = "两次探测的间隔(毫秒)，最小 10"
+    )]
+    interval: u64,
+
+    #[arg(
+        long = "timeout",
+        default_value_t = 1000,
+        value_name = "MS",
+        help = "单次探测等待回应的超时时间(毫秒)"
+    )]
+    time_out: u64,
+
+    #[arg(
+        short = 's',
+        long,
+        default_value_t = 56,
+        value_name = "BYTES",
+        value_parser = clap::value_parser!(u16).range(0..=65000),
+        help = "ICMP 负载字节数"
+    )]
+    size: u16,
+
+    #[arg(long, help = "不使用 ICMP，直接用 TCP 连接测量往返时间")]
+    tcp: bool,
+
+    #[arg(
+        short = 'p',
+        long,
+        default_value_t = 80,
+        value_name = "PORT",
+        help = "TCP 方式连接的端口；无权限发送 ICMP 时也会回退到该方式"
+    )]
+    port: u16,
+
+    #[arg(long, help = "以 JSON 输出结果")]
+    json: bool,
+
+    // 全局 --where / --fields / --rename，由命令行入口注入
+    #[arg(skip)]
+    pub(crate) record_output: RecordOutput,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum PingError {
+    #[error("cannot resolve host: {0}")]
+    Resolve(String),
+    #[error("no reply from {0}")]
+    NoReply(String),
+    #[error(transparent)]
+    Network(#[from] NetworkError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("json serialize failed: {0}")]
+    Serialize(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PingMethod {
+    Icmp,
+    Tcp,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PingReply {
+    pub seq: u32,
+    // 未收到回应时为 None
+    pub rtt_ms: Option<f64>,
+}
+
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct PingStats {
+    pub transmitted: u32,
+    pub received: u32,
+    pub loss_percent: f64,
+    pub min_ms: Option<f64>,
+    pub avg_ms: Option<f64>,
+    pub max_ms: Option<f64>,
+    pub stddev_ms: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PingReport {
+    pub host: String,
+    pub addr: String,
+    pub method: PingMethod,
+    // TCP 方式连接的端口
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    pub replies: Vec<PingReply>,
+    pub stats: PingStats,
+}
+
+enum Prober {
+    Icmp { socket: EchoSocket, id: u16 },
+    Tcp(SocketAddr),
+}
+
+impl Prober {
+    // 返回往返时间；超时为 None。TCP 方式下对端拒绝连接同样说明主机可达
+    fn probe(&self, seq: u32, size: usize, timeout: Duration) -> io::Result<Option<Duration>> {
+        let started = Instant::now();
+        match self {
+            Prober::Icmp { socket, id } => {
+                let seq = seq as u16;
+                socket.send(*id, seq, size)?;
+                Ok(socket.recv(*id, seq, timeout)?.map(|_| started.elapsed()))
+            }
+            Prober::Tcp(addr) => match TcpStream::connect_timeout(addr, timeout) {
+                Ok(_) => Ok(Some(started.elapsed())),
+                Err(e) if e.kind() == io::ErrorKind::ConnectionRefused => {
+                    Ok(Some(started.elapsed()))
+                }
+                Err(e) if e.kind() == io::ErrorKind::TimedOut => Ok(None),
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => Ok(None),
+                Err(e) => Err(e),
+            },
+        }
+    }
+}
+
+pub fn run_ping(opts: PingOpts) -> Result<(), PingError> {
+    network::check_host("ping", &opts.host)?;
+    let addr = (opts.host.as_str(), opts.port)
+        .to_socket_addrs()
+        .map_err(|e| PingError::Resolve(format!("{}: {}", opts.host, e)))?
+        .next()
+        .ok_or_else(|| PingError::Resolve(opts.host.clone()))?;
+    let prober = if opts.tcp {
+        Prober::Tcp(addr)
+    } else {
+        match EchoSocket::open(addr) {
+            Ok(socket) => Prober::Icmp {
+                socket,
+                id: std::process::id() as u16,
+            },
+            Err(e) => {
+                eprintln!(
+                    "warning: cannot send ICMP ({}), falling back to TCP connect on port {}",
+                    e, opts.port
+                );
+                Prober::Tcp(addr)
+            }
+        }
+    };
+    let (method, port) = match prober {
+        Prober::Icmp { .. } => (PingMethod::Icmp, None),
+        Prober::Tcp(addr) => (PingMethod::Tcp, Some(addr.port())),
+    };
+    let timeout = Duration::from_millis(opts.time_out.clamp(10, 60_000));
+    let interval = Duration::from_millis(opts.interval.max(10));
+
+    if !opts.json {
+        match port {
+            None => println!(
+                "PING {} ({}): {} bytes of data",
+                opts.host,
+                addr.ip(),
+                opts.size
+            ),
+            Some(port) => println!(
+                "PING {} ({}): TCP connect to port {}",
+                opts.host,
+                addr.ip(),
+                port
+            ),
+        }
+    }
+    let mut replies = Vec::with_capacity(opts.count as usize);
+    for seq in 1..=opts.count {
+        let started = Instant::now();
+        let rtt = prober
+            .probe(seq, opts.size as usize, timeout)?
+            .map(|rtt| (rtt.as_secs_f64() * 100_000.0).round() / 100.0);
+        let reply = PingReply { seq, rtt_ms: rtt };
+        if !opts.json && opts.record_output.keep(&reply)? {
+            match rtt {
+                Some(rtt) => println!("reply from {}: seq={} time={} ms", addr.ip(), seq, rtt),
+                None => println!("request timeout for seq={}", seq),
+            }
+        }
+        replies.push(reply);
+        if seq < opts.count {
+            thread::sleep(interval.saturating_sub(started.elapsed()));
+        }
+    }
+
+    // 统计按全部探测计算，--where 只筛选输出的应答
+    let stats = summarize(&replies);
+    opts.record_output.retain(&mut replies)?;
+    let received = stats.received;
+    let report = PingReport {
+        host: opts.host,
+        addr: addr.ip().to_string(),
+        method,
+        port,
+        replies,
+        stats,
+    };
+    if opts.json {
+        opts.record_output
+            .write_report(&report, Some("replies"), io::stdout().lock())?;
+    } else {
+        print_stats(&report);
+    }
+    if received == 0 {
+        return Err(PingError::NoReply(report.host));
+    }
+    Ok(())
+}
+
+// 与 ping 的 mdev 一致，stddev 为总体标准差
+pub fn summarize(replies: &[PingReply]) -> PingStats {
+    let rtts: Vec<f64> = replies.iter().filter_map(|reply| reply.rtt_ms).collect();
+    let transmitted = replies.len() as u32;
+    let received = rtts.len() as u32;
+    let round = |value: f64| (value * 100.0).round() / 100.0;
+    let loss_percent = if transmitted == 0 {
+        0.0
+    } else {
+        round(f64::from(transmitted - received) * 100.0 / f64::from(transmitted))
+    };
+    let mut stats = PingStats {
+        transmitted,
+        received,
+        loss_percent,
+        ..PingStats::default()
+    };
+    if rtts.is_empty() {
+        return stats;
+    }
+    let avg = rtts.iter().sum::<f64>() / rtts.len() as f64;
+    let variance = rtts.iter().map(|rtt| (rtt - avg).powi(2)).sum::<f64>() / rtts.len() as f64;
+    stats.min_ms = rtts.iter().copied().reduce(f64::min);
+    stats.max_ms = rtts.iter().copied().reduce(f64::max);
+    stats.avg_ms = Some(round(avg));
+    stats.stddev_ms = Some(round(variance.sqrt()));
+    stats
+}
+
+fn print_stats(report: &PingReport) {
+    let stats = &report.stats;
+    println!("\n--- {} ping statistics ---", report.host);
+    println!(
+        "{} packets transmitted, {} received, {}% packet loss",
+        stats.transmitted, stats.received, stats.loss_percent
+    );
+    if let (Some(min), Some(avg), Some(max), Some(stddev)) =
+        (stats.min_ms, stats.avg_ms, stats.max_ms, stats.stddev_ms)
+    {
+        println!(
+            "rtt min/avg/max/stddev = {}/{}/{}/{} ms",
+            min, avg, max, stddev
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summarize() {
+        let replies: Vec<PingReply> = [Some(10.0), None, Some(20.0), Some(30.0)]
+            .into_iter()
+            .zip(1..)
+            .map(|(rtt_ms, seq)| PingReply { seq, rtt_ms })
+            .collect();
+        let stats = summarize(&replies);
+        assert_eq!(stats.transmitted, 4);
+        assert_eq!(stats.received, 3);
+        assert_eq!(stats.loss_percent, 25.0);
+        assert_eq!(stats.min_ms, Some(10.0));
+        assert_eq!(stats.avg_ms, Some(20.0));
+        assert_eq!(stats.max_ms, Some(30.0));
+        assert_eq!(stats.stddev_ms, Some(8.16));
+
+        let lost = summarize(&[PingReply {
+            seq: 1,
+            rtt_ms: None,
+        }]);
+        assert_eq!(lost.loss_percent, 100.0);
+        assert_eq!(lost.avg_ms, None);
+    }
+
+    #[test]
+    fn test_tcp_ping_localhost() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let prober = Prober::Tcp(listener.local_addr().unwrap());
+        let rtt = prober.probe(1, 0, Duration::from_secs(1)).unwrap();
+        assert!(rtt.is_some());
+    }
+}
@@ -0,0 +1,473 @@
+use std::env;
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use hmac::{Hmac, Mac};
+use rand::{Rng, RngCore};
+use sha2::Sha256;
+
+use crate::utils::format::human_bytes;
+use crate::utils::network::{self, NetworkError};
+
+type HmacSha256 = Hmac<Sha256>;
+
+const MAGIC: &[u8; 8] = b"RTKPIPE2";
+const NONCE_LEN: usize = 16;
+pub(crate) const SALT_LEN: usize = 16;
+#[cfg(not(test))]
+const KDF_ROUNDS: u32 = 200_000;
+// 未优化的测试构建中 PBKDF2 很慢，测试只验证流程
+#[cfg(test)]
+const KDF_ROUNDS: u32 = 1_000;
+const MIN_KEY_LEN: usize = 12;
+const MAC_LEN: usize = 32;
+const CHUNK_SIZE: usize = 64 * 1024;
+const TAG_LEN: usize = 16;
+const KEY_ENV: &str = "RTOOLKIT_PIPE_KEY";
+
+const STATUS_OK: u8 = 0;
+const STATUS_AUTH_FAILED: u8 = 1;
+const STATUS_DIRECTION_MISMATCH: u8 = 2;
+
+#[derive(clap::Args)]
+pub struct PipeOpts {
+    #[arg(
+        short = 'l',
+        long,
+        conflicts_with = "to",
+        required_unless_present = "to",
+        help = "监听并等待对端连接，默认把收到的数据写到 stdout"
+    )]
+    listen: bool,
+
+    #[arg(
+        short = 't',
+        long,
+        value_name = "HOST[:PORT]",
+        help = "连接到正在监听的对端，默认把 stdin 发送过去"
+    )]
+    to: Option<String>,
+
+    #[arg(
+        short = 'b',
+        long,
+        default_value = "0.0.0.0",
+        value_name = "ADDR",
+        help = "监听地址"
+    )]
+    bind: String,
+
+    #[arg(
+        short = 'p',
+        long,
+        default_value_t = 7878,
+        help = "监听端口；--to 未写端口时也使用该端口"
+    )]
+    port: u16,
+
+    #[arg(
+        short = 'k',
+        long,
+        value_name = "KEY",
+        help = "共享密钥，也可通过环境变量 RTOOLKIT_PIPE_KEY 传入；监听端省略时自动生成并打印"
+    )]
+    key: Option<String>,
+
+    #[arg(
+        long,
+        help = "反转方向：监听端发送 stdin，连接端输出到 stdout，两端需同时指定"
+    )]
+    reverse: bool,
+
+    #[arg(
+        long,
+        default_value_t = 10,
+        value_name = "SECS",
+        help = "连接和握手超时时间（秒）"
+    )]
+    timeout: u64,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum PipeError {
+    #[error("a shared key is required, pass --key or set {KEY_ENV}")]
+    MissingKey,
+    #[error("shared key must be at least {MIN_KEY_LEN} characters, omit --key on the listener to generate one")]
+    WeakKey,
+    #[error("invalid address: {0}")]
+    InvalidAddress(String),
+    #[error("failed to bind {addr}: {source}")]
+    Bind { addr: String, source: io::Error },
+    #[error("authentication failed, check that both sides use the same key")]
+    AuthFailed,
+    #[error("direction mismatch, use --reverse on both sides or on neither")]
+    DirectionMismatch,
+    #[error("peer is not an rtoolkit pipe")]
+    BadHandshake,
+    #[error("frame failed integrity check")]
+    Decrypt,
+    #[error("stream ended before the sender finished")]
+    Truncated,
+    #[error(transparent)]
+    Network(#[from] NetworkError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+pub fn run_pipe(opts: PipeOpts) -> Result<(), PipeError> {
+    let key = opts
+        .key
+        .clone()
+        .or_else(|| env::var(KEY_ENV).ok())
+        .filter(|key| !key.is_empty());
+    if key
+        .as_ref()
+        .is_some_and(|key| key.chars().count() < MIN_KEY_LEN)
+    {
+        return Err(PipeError::WeakKey);
+    }
+    // 监听端发送数据当且仅当指定了 --reverse
+    let listener_sends = opts.reverse;
+    let timeout = Duration::from_secs(opts.timeout.max(1));
+
+    let (mut stream, session, sending) = if opts.listen {
+        let key = key.unwrap_or_else(|| {
+            let key = generate_key();
+            eprintln!("Generated key: {}", key);
+            key
+        });
+        network::check_host("pipe listen", &opts.bind)?;
+        let addr = format!("{}:{}", opts.bind, opts.port);
+        let listener = TcpListener::bind(&addr).map_err(|source| PipeError::Bind {
+            addr: addr.clone(),
+            source,
+        })?;
+        // 每次监听随机生成盐，抓包得到的握手无法复用预计算结果
+        let mut salt = [0u8; SALT_LEN];
+        rand::rng().fill_bytes(&mut salt);
+        let psk = derive_psk(&key, &salt);
+        eprintln!("Listening on {}", listener.local_addr()?);
+        // 认证失败的连接直接丢弃，继续等待，避免被端口扫描打断
+        loop {
+            let (mut stream, peer) = listener.accept()?;
+            stream.set_read_timeout(Some(timeout))?;
+            match server_handshake(&mut stream, &psk, &salt, listener_sends) {
+                Ok(session) => {
+                    eprintln!("Connected from {}", peer);
+                    break (stream, session, listener_sends);
+                }
+                Err(error) => eprintln!("Rejected {}: {}", peer, error),
+            }
+        }
+    } else {
+        let key = key.ok_or(PipeError::MissingKey)?;
+        let target = opts.to.as_deref().unwrap_or_default();
+        let target = if target.contains(':') && !target.ends_with(']') {
+            target.to_string()
+        } else {
+            format!("{}:{}", target, opts.port)
+        };
+        network::check_endpoint("pipe", &target)?;
+        let addr = target
+            .to_socket_addrs()
+            .map_err(|_| PipeError::InvalidAddress(target.clone()))?
+            .next()
+            .ok_or_else(|| PipeError::InvalidAddress(target.clone()))?;
+        let mut stream = TcpStream::connect_timeout(&addr, timeout)?;
+        stream.set_read_timeout(Some(timeout))?;
+        let session = client_handshake(&mut stream, &key, listener_sends)?;
+        eprintln!("Connected to {}", addr);
+        (stream, session, !listener_sends)
+    };
+
+    // 握手完成后数据流可能长时间空闲，取消读超时
+    stream.set_read_timeout(None)?;
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(&session));
+    if sending {
+        let sent = send_stream(io::stdin().lock(), &mut stream, &cipher)?;
+        eprintln!("Sent {}", human_bytes(sent));
+    } else {
+        let stdout = io::stdout();
+        let received = recv_stream(&mut stream, &mut stdout.lock(), &cipher)?;
+        eprintln!("Received {}", human_bytes(received));
+    }
+    Ok(())
+}
+
+fn generate_key() -> String {
+    const CHARSET: &[u8] = b"abcdefghijkmnpqrstuvwxyz23456789";
+    let mut rng = rand::rng();
+    (0..20)
+        .map(|_| CHARSET[rng.random_range(0..CHARSET.len())] as char)
+        .collect()
+}
+
+// PBKDF2 拉长密钥，抓到握手报文的人离线暴力猜测密钥的代价随轮数线性增加
+pub(crate) fn derive_psk(key: &str, salt: &[u8]) -> [u8; 32] {
+    let mut psk = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(key.as_bytes(), salt, KDF_ROUNDS, &mut psk);
+    psk
+}
+
+fn hmac(psk: &[u8; 32], label: &[u8], parts: &[&[u8]]) -> HmacSha256 {
+    let mut mac = <HmacSha256 as Mac>::new_from_slice(psk).expect("hmac accepts any key length");
+    mac.update(label);
+    for part in parts {
+        mac.update(part);
+    }
+    mac
+}
+
+fn session_key(psk: &[u8; 32], server_nonce: &[u8], client_nonce: &[u8]) -> [u8; 32] {
+    hmac(psk, b"session", &[server_nonce, client_nonce])
+        .finalize()
+        .into_bytes()
+        .into()
+}
+
+// 握手流程（双向认证，密钥本身不上线）：
+// 1. 监听端 -> 连接端：MAGIC + salt + server_nonce，双方用 PBKDF2(key, salt) 作为 psk
+// 2. 连接端 -> 监听端：MAGIC + client_nonce + 方向 + HMAC("client", nonces, 方向)
+// 3. 监听端 -> 连接端：状态 + HMAC("server", nonces, 方向)
+// 之后用 HMAC("session", nonces) 作为 ChaCha20-Poly1305 会话密钥
+pub(crate) fn server_handshake<S: Read + Write>(
+    stream: &mut S,
+    psk: &[u8; 32],
+    salt: &[u8; SALT_LEN],
+    listener_sends: bool,
+) -> Result<[u8; 32], PipeError> {
+    let mut server_nonce = [0u8; NONCE_LEN];
+    rand::rng().fill_bytes(&mut server_nonce);
+    stream.write_all(MAGIC)?;
+    stream.write_all(salt)?;
+    stream.write_all(&server_nonce)?;
+    stream.flush()?;
+
+    let mut hello = [0u8; 8 + NONCE_LEN + 1 + MAC_LEN];
+    stream
+        .read_exact(&mut hello)
+        .map_err(|_| PipeError::BadHandshake)?;
+    if &hello[..8] != MAGIC {
+        return Err(PipeError::BadHandshake);
+    }
+    let client_nonce = &hello[8..8 + NONCE_LEN];
+    let direction = &hello[8 + NONCE_LEN..8 + NONCE_LEN + 1];
+    let client_mac = &hello[8 + NONCE_LEN + 1..];
+
+    if hmac(psk, b"client", &[&server_nonce, client_nonce, direction])
+        .verify_slice(client_mac)
+        .is_err()
+    {
+        let _ = stream.write_all(&[STATUS_AUTH_FAILED]);
+        return Err(PipeError::AuthFailed);
+    }
+    if direction[0] != listener_sends as u8 {
+        let _ = stream.write_all(&[STATUS_DIRECTION_MISMATCH]);
+        return Err(PipeError::DirectionMismatch);
+    }
+
+    let server_mac = hmac(psk, b"server", &[&server_nonce, client_nonce, direction]).finalize();
+    stream.write_all(&[STATUS_OK])?;
+    stream.write_all(&server_mac.into_bytes())?;
+    stream.flush()?;
+    Ok(session_key(psk, &server_nonce, client_nonce))
+}
+
+pub(crate) fn client_handshake<S: Read + Write>(
+    stream: &mut S,
+    key: &str,
+    listener_sends: bool,
+) -> Result<[u8; 32], PipeError> {
+    let mut greeting = [0u8; 8 + SALT_LEN + NONCE_LEN];
+    stream
+        .read_exact(&mut greeting)
+        .map_err(|_| PipeError::BadHandshake)?;
+    if &greeting[..8] != MAGIC {
+        return Err(PipeError::BadHandshake);
+    }
+    let psk = &derive_psk(key, &greeting[8..8 + SALT_LEN]);
+    let server_nonce = &greeting[8 + SALT_LEN..];
+
+    let mut client_nonce = [0u8; NONCE_LEN];
+    rand::rng().fill_bytes(&mut client_nonce);
+    let direction = [listener_sends as u8];
+    let client_mac = hmac(psk, b"client", &[server_nonce, &client_nonce, &direction]).finalize();
+    stream.write_all(MAGIC)?;
+    stream.write_all(&client_nonce)?;
+    stream.write_all(&direction)?;
+    stream.write_all(&client_mac.into_bytes())?;
+    stream.flush()?;
+
+    let mut status = [0u8; 1];
+    stream
+        .read_exact(&mut status)
+        .map_err(|_| PipeError::AuthFailed)?;
+    match status[0] {
+        STATUS_OK => {}
+        STATUS_DIRECTION_MISMATCH => return Err(PipeError::DirectionMismatch),
+        _ => return Err(PipeError::AuthFailed),
+    }
+    // 校验监听端同样持有密钥，防止把数据发给冒充者
+    let mut server_mac = [0u8; MAC_LEN];
+    stream.read_exact(&mut server_mac)?;
+    hmac(psk, b"server", &[server_nonce, &client_nonce, &direction])
+        .verify_slice(&server_mac)
+        .map_err(|_| PipeError::AuthFailed)?;
+    Ok(session_key(psk, server_nonce, &client_nonce))
+}
+
+fn frame_nonce(seq: u64) -> Nonce {
+    let mut nonce = [0u8; 12];
+    nonce[4..].copy_from_slice(&seq.to_be_bytes());
+    *Nonce::from_slice(&nonce)
+}
+
+// 帧格式：4 字节大端长度 + 密文（含 16 字节认证标签），空明文帧表示发送结束，
+// 接收端据此区分正常结束和连接被截断
+fn send_stream<R: Read, W: Write>(
+    mut input: R,
+    conn: &mut W,
+    cipher: &ChaCha20Poly1305,
+) -> Result<u64, PipeError> {
+    let mut buf = vec![0u8; CHUNK_SIZE];
+    let mut seq = 0u64;
+    let mut total = 0u64;
+    loop {
+        let n = match input.read(&mut buf) {
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        };
+        let frame = cipher
+            .encrypt(&frame_nonce(seq), &buf[..n])
+            .expect("encryption of in-memory buffer cannot fail");
+        conn.write_all(&(frame.len() as u32).to_be_bytes())?;
+        conn.write_all(&frame)?;
+        seq += 1;
+        total += n as u64;
+        if n == 0 {
+            conn.flush()?;
+            return Ok(total);
+        }
+    }
+}
+
+fn recv_stream<R: Read, W: Write>(
+    conn: &mut R,
+    out: &mut W,
+    cipher: &ChaCha20Poly1305,
+) -> Result<u64, PipeError> {
+    let mut seq = 0u64;
+    let mut total = 0u64;
+    let mut frame = Vec::with_capacity(CHUNK_SIZE + TAG_LEN);
+    loop {
+        let mut len = [0u8; 4];
+        conn.read_exact(&mut len)
+            .map_err(|_| PipeError::Truncated)?;
+        let len = u32::from_be_bytes(len) as usize;
+        if !(TAG_LEN..=CHUNK_SIZE + TAG_LEN).contains(&len) {
+            return Err(PipeError::Decrypt);
+        }
+        frame.resize(len, 0);
+        conn.read_exact(&mut frame)
+            .map_err(|_| PipeError::Truncated)?;
+        let plain = cipher
+            .decrypt(&frame_nonce(seq), frame.as_slice())
+            .map_err(|_| PipeError::Decrypt)?;
+        seq += 1;
+        if plain.is_empty() {
+            out.flush()?;
+            return Ok(total);
+        }
+        out.write_all(&plain)?;
+        // 逐帧刷新，让下游管道尽快看到数据
+        out.flush()?;
+        total += plain.len() as u64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    fn connected_pair() -> (TcpStream, TcpStream) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (server, _) = listener.accept().unwrap();
+        (server, client)
+    }
+
+    #[test]
+    fn test_transfer_round_trip() {
+        let (mut server, mut client) = connected_pair();
+        let payload: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+        let expected = payload.clone();
+
+        let receiver = thread::spawn(move || {
+            let salt = [3u8; SALT_LEN];
+            let psk = derive_psk("correct horse", &salt);
+            let session = server_handshake(&mut server, &psk, &salt, false).unwrap();
+            let cipher = ChaCha20Poly1305::new(Key::from_slice(&session));
+            let mut out = Vec::new();
+            recv_stream(&mut server, &mut out, &cipher).unwrap();
+            out
+        });
+
+        let session = client_handshake(&mut client, "correct horse", false).unwrap();
+        let cipher = ChaCha20Poly1305::new(Key::from_slice(&session));
+        let sent = send_stream(io::Cursor::new(payload), &mut client, &cipher).unwrap();
+        assert_eq!(sent, expected.len() as u64);
+        assert_eq!(receiver.join().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_handshake_rejects_wrong_key_and_direction() {
+        let salt = [9u8; SALT_LEN];
+        let psk = derive_psk("right key 123", &salt);
+
+        let (mut server, mut client) = connected_pair();
+        let handle = thread::spawn(move || server_handshake(&mut server, &psk, &salt, false));
+        assert!(matches!(
+            client_handshake(&mut client, "wrong key 123", false),
+            Err(PipeError::AuthFailed)
+        ));
+        assert!(matches!(handle.join().unwrap(), Err(PipeError::AuthFailed)));
+
+        let (mut server, mut client) = connected_pair();
+        let handle = thread::spawn(move || server_handshake(&mut server, &psk, &salt, true));
+        assert!(matches!(
+            client_handshake(&mut client, "right key 123", false),
+            Err(PipeError::DirectionMismatch)
+        ));
+        assert!(handle.join().unwrap().is_err());
+    }
+
+    #[test]
+    fn test_truncated_stream_is_detected() {
+        let cipher = ChaCha20Poly1305::new(Key::from_slice(&[7u8; 32]));
+        let mut wire = Vec::new();
+        send_stream(io::Cursor::new(b"hello".to_vec()), &mut wire, &cipher).unwrap();
+
+        let mut out = Vec::new();
+        let received = recv_stream(&mut io::Cursor::new(wire.clone()), &mut out, &cipher).unwrap();
+        assert_eq!((received, out.as_slice()), (5, &b"hello"[..]));
+
+        // 去掉结束帧，模拟连接中途断开
+        let first_frame = 4 + 5 + TAG_LEN;
+        let result = recv_stream(
+            &mut io::Cursor::new(wire[..first_frame].to_vec()),
+            &mut Vec::new(),
+            &cipher,
+        );
+        assert!(matches!(result, Err(PipeError::Truncated)));
+
+        let mut tampered = wire;
+        tampered[6] ^= 1;
+        let result = recv_stream(&mut io::Cursor::new(tampered), &mut Vec::new(), &cipher);
+        assert!(matches!(result, Err(PipeError::Decrypt)));
+    }
+}
@@ -0,0 +1,122 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+use crate::utils::asciicast::{self, CastError, Event};
+
+#[derive(clap::Args)]
+pub struct PlayOpts {
+    #[arg(
+        value_name = "FILE",
+        help = "asciicast 录制文件（--record 生成，也兼容 asciinema 的 v1 / v2 格式）"
+    )]
+    file: PathBuf,
+
+    #[arg(
+        short = 's',
+        long,
+        value_name = "FACTOR",
+        default_value_t = 1.0,
+        help = "回放速度倍数，例如 2 为两倍速"
+    )]
+    speed: f64,
+
+    #[arg(
+        short = 'i',
+        long = "idle-limit",
+        value_name = "SECS",
+        help = "两帧之间最长等待的秒数，跳过录制时的长时间停顿"
+    )]
+    idle_limit: Option<f64>,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum PlayError {
+    #[error("failed to read {path}: {source}")]
+    Read { path: PathBuf, source: io::Error },
+    #[error("{path}: {source}")]
+    Cast { path: PathBuf, source: CastError },
+    #[error("--speed must be a positive number")]
+    InvalidSpeed,
+    #[error("--idle-limit must be a positive number of seconds")]
+    InvalidIdleLimit,
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+pub fn run_play(opts: PlayOpts) -> Result<(), PlayError> {
+    if !(opts.speed.is_finite() && opts.speed > 0.0) {
+        return Err(PlayError::InvalidSpeed);
+    }
+    if opts
+        .idle_limit
+        .is_some_and(|limit| !(limit.is_finite() && limit > 0.0))
+    {
+        return Err(PlayError::InvalidIdleLimit);
+    }
+    let text = fs::read_to_string(&opts.file).map_err(|source| PlayError::Read {
+        path: opts.file.clone(),
+        source,
+    })?;
+    let (_, events) = asciicast::parse(&text).map_err(|source| PlayError::Cast {
+        path: opts.file.clone(),
+        source,
+    })?;
+
+    let mut out = io::stdout().lock();
+    for (delay, data) in schedule(&events, opts.speed, opts.idle_limit) {
+        if !delay.is_zero() {
+            thread::sleep(delay);
+        }
+        out.write_all(data.as_bytes())?;
+        out.flush()?;
+    }
+    Ok(())
+}
+
+// 每一帧输出前需要等待的时长：按录制时的间隔，停顿不超过 idle_limit，再按速度缩放
+fn schedule(events: &[Event], speed: f64, idle_limit: Option<f64>) -> Vec<(Duration, &str)> {
+    let mut previous = 0.0;
+    events
+        .iter()
+        .map(|event| {
+            let mut gap = (event.time - previous).max(0.0);
+            previous = event.time;
+            if let Some(limit) = idle_limit {
+                gap = gap.min(limit);
+            }
+            (Duration::from_secs_f64(gap / speed), event.data.as_str())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schedule_scales_and_caps_gaps() {
+        let events = [(0.5, "a"), (1.0, "b"), (11.0, "c"), (10.5, "d")].map(|(time, data)| Event {
+            time,
+            data: data.to_string(),
+        });
+        let delays: Vec<Duration> = schedule(&events, 2.0, Some(2.0))
+            .into_iter()
+            .map(|(delay, _)| delay)
+            .collect();
+        // 10 秒的停顿被压缩到 2 秒，时间倒退的帧立即输出，然后整体两倍速
+        assert_eq!(
+            delays,
+            [
+                Duration::from_millis(250),
+                Duration::from_millis(250),
+                Duration::from_secs(1),
+                Duration::ZERO,
+            ]
+        );
+        let (delay, data) = schedule(&events, 1.0, None)[2];
+        assert_eq!((delay, data), (Duration::from_secs(10), "c"));
+    }
+}
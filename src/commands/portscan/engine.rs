@@ -0,0 +1,1373 @@
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::future::Future;
+use std::io;
+use std::net::IpAddr;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{ready, Context, Poll};
+use std::time::Instant;
+
+use futures::stream::{FuturesUnordered, Stream, StreamExt};
+use hickory_resolver::TokioResolver;
+use rand::seq::SliceRandom;
+use serde::Serialize;
+use tokio::sync::{mpsc, OwnedSemaphorePermit, Semaphore};
+use tokio::task::JoinHandle;
+use tokio::time::{timeout, Duration};
+
+use crate::utils::fdlimit;
+use crate::utils::metrics;
+use crate::utils::network;
+use crate::utils::progress::Progress;
+use crate::utils::proxy::{Proxy, ProxyError};
+use crate::utils::services;
+use crate::utils::tls;
+
+use super::local::{is_local_target, local_process_commands, local_tcp_listen_pids};
+use super::probe::{
+    grab_banner, guess_os, identify_service, probe_http, probe_proxy, probe_tcp, probe_tls,
+    probe_udp, SourceBind,
+};
+use super::syn::syn_scan;
+use super::targets::{parse_port_spec, parse_targets, target_addr};
+use super::{
+    AddressFamily, HostSummary, LatencyStats, PortScanError, PortScanRequest, PortScanResult,
+    PortState, PortStatus, ScanProtocol, ScanSettings, ScanType,
+};
+
+pub async fn scan_ports(request: PortScanRequest) -> Result<PortScanResult, PortScanError> {
+    ScanPlan::new(request, WEB_MAX_PORTS)?.run().await
+}
+
+// --retries 的上限，重试过多只会成倍拉长扫描时间
+pub(super) const MAX_RETRIES: u8 = 5;
+
+// --adaptive-timeout 的采样范围：前 8 个主机各自的前 3 个端口
+const RTT_SAMPLE_HOSTS: usize = 8;
+const RTT_SAMPLE_PORTS: usize = 3;
+// 超时取采样最大 RTT 的倍数，留出抖动余量
+const RTT_TIMEOUT_FACTOR: f64 = 4.0;
+// 局域网 RTT 往往不到 1ms，超时不低于该值，避免偶发延迟被误判为 filtered
+const MIN_ADAPTIVE_TIMEOUT_MS: u64 = 100;
+
+// 探测数达到该值才显示进度条，小扫描转瞬即完，不必闪一下
+pub(super) const PROGRESS_MIN_PROBES: usize = 1000;
+
+// 留给标准输入输出、DNS 解析、状态文件等的文件描述符，不计入探测并发
+const RESERVED_FDS: usize = 64;
+// 本机资源耗尽时同一探测最多重新排队的次数，每次等待时间递增
+const MAX_EXHAUSTED_RETRIES: u32 = 10;
+const EXHAUSTED_BACKOFF: Duration = Duration::from_millis(50);
+// 同一批在途探测往往一起失败，窗口在这段时间内只减半一次
+const BACKOFF_COOLDOWN: Duration = Duration::from_millis(200);
+// 失败率（拒绝、超时、不可达）按最近这么多个探测统计，比长期基线高出 ERROR_SPIKE 时
+// 目标多半开始限速或被 IDS 拦截，与本机资源耗尽一样减半并发
+const RATE_WINDOW: usize = 64;
+const ERROR_SPIKE: f64 = 0.3;
+// 基线按指数移动平均缓慢跟随，整段端口都关闭这类持续的高失败率不会反复触发
+const BASELINE_SMOOTHING: f64 = 256.0;
+
+// Web 接口单次扫描的主机 × 端口上限，CLI 不受此限制
+const WEB_MAX_PORTS: usize = 4096;
+// --os-guess 同时运行的 ping 进程数上限
+pub(super) const OS_GUESS_CONCURRENCY: usize = 32;
+
+// 后台任务不占用 HTTP 处理，放宽到 /16 网段扫描 16 个端口
+pub const JOB_MAX_PORTS: usize = 65_536 * 16;
+
+// 校验过的 Web 扫描请求；后台任务在提交时校验，执行时才真正扫描
+pub struct ScanPlan {
+    config: ScanConfig,
+}
+
+impl ScanPlan {
+    pub fn new(request: PortScanRequest, max_ports: usize) -> Result<Self, PortScanError> {
+        let target = request
+            .target
+            .filter(|value| !value.trim().is_empty())
+            .unwrap_or_else(|| "127.0.0.1".to_string());
+        let port = request
+            .port
+            .filter(|value| !value.trim().is_empty())
+            .unwrap_or_else(|| "80".to_string());
+        let mut config = ScanConfig::new(&target, &port)?;
+        config.settings = ScanSettings {
+            concurrency: request.concurrency.unwrap_or(100).clamp(1, 1000),
+            timeout_ms: request.timeout_ms.unwrap_or(1000).clamp(50, 10_000),
+            retries: request.retries.unwrap_or(0).min(MAX_RETRIES),
+            protocol: request.protocol.unwrap_or(ScanProtocol::Tcp),
+            ..ScanSettings::default()
+        };
+        if config.hosts.len().saturating_mul(config.ports.len()) > max_ports {
+            return Err(PortScanError::TooManyPorts(max_ports));
+        }
+        Ok(Self { config })
+    }
+
+    pub fn with_progress(mut self, progress: Arc<Progress>) -> Self {
+        self.config.settings.progress = Some(progress);
+        self
+    }
+
+    pub async fn run(self) -> Result<PortScanResult, PortScanError> {
+        scan(self.config).finish().await
+    }
+}
+
+// 库接口的扫描参数。target / port_range 只原样写入结果，实际扫描 hosts × ports
+#[derive(Debug, Clone)]
+pub struct ScanConfig {
+    pub target: String,
+    pub port_range: String,
+    pub hosts: Vec<String>,
+    pub ports: Vec<u16>,
+    pub settings: ScanSettings,
+}
+
+impl ScanConfig {
+    // 目标和端口的写法与 CLI 的 --target / --port 相同，其余参数取默认值
+    pub fn new(target: &str, ports: &str) -> Result<Self, PortScanError> {
+        Ok(Self {
+            target: target.to_string(),
+            port_range: ports.to_string(),
+            hosts: parse_targets(target)?,
+            ports: parse_port_spec(ports)?,
+            settings: ScanSettings::default(),
+        })
+    }
+}
+
+// 流中的单个端口结果；扫描整体失败时错误作为最后一项给出
+pub type PortResult = Result<PortStatus, PortScanError>;
+
+// 在当前 tokio 运行时中启动扫描，端口结果按完成顺序逐个产出，
+// 服务识别、TLS 探测和反向解析等补充信息已经填好。
+// 读完后可以用 finish() 取按主机和端口排序的汇总；丢弃流会中止扫描
+pub fn scan(config: ScanConfig) -> ScanStream {
+    let (sink, results) = mpsc::unbounded_channel();
+    ScanStream {
+        results,
+        task: Some(tokio::spawn(run_scan(config, sink))),
+        summary: None,
+    }
+}
+
+pub struct ScanStream {
+    results: mpsc::UnboundedReceiver<PortStatus>,
+    task: Option<JoinHandle<Result<PortScanResult, PortScanError>>>,
+    summary: Option<PortScanResult>,
+}
+
+impl ScanStream {
+    // 读完剩余的端口结果并返回扫描汇总
+    pub async fn finish(mut self) -> Result<PortScanResult, PortScanError> {
+        while let Some(result) = self.next().await {
+            result?;
+        }
+        self.summary.take().ok_or(PortScanError::NoSummary)
+    }
+}
+
+impl Stream for ScanStream {
+    type Item = PortResult;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<PortResult>> {
+        if let Some(status) = ready!(self.results.poll_recv(cx)) {
+            return Poll::Ready(Some(Ok(status)));
+        }
+        // 结果通道关闭说明扫描任务已经结束，再取出汇总或错误
+        let Some(task) = self.task.as_mut() else {
+            return Poll::Ready(None);
+        };
+        let outcome = ready!(Pin::new(task).poll(cx));
+        self.task = None;
+        match outcome {
+            Ok(Ok(result)) => {
+                self.summary = Some(result);
+                Poll::Ready(None)
+            }
+            Ok(Err(e)) => Poll::Ready(Some(Err(e))),
+            Err(e) => Poll::Ready(Some(Err(PortScanError::JoinError(e.to_string())))),
+        }
+    }
+}
+
+impl Drop for ScanStream {
+    fn drop(&mut self) {
+        if let Some(task) = &self.task {
+            task.abort();
+        }
+    }
+}
+
+// 一次性取完整结果，供 --watch、discover 等不需要逐个处理端口的调用方使用
+pub async fn remote_scan(
+    target_spec: &str,
+    hosts: &[String],
+    port_spec: &str,
+    ports: &[u16],
+    settings: &ScanSettings,
+) -> Result<PortScanResult, PortScanError> {
+    scan(ScanConfig {
+        target: target_spec.to_string(),
+        port_range: port_spec.to_string(),
+        hosts: hosts.to_vec(),
+        ports: ports.to_vec(),
+        settings: settings.clone(),
+    })
+    .finish()
+    .await
+}
+
+// 开放端口的补充信息需要建立连接，与后续端口的探测并行进行
+struct PortEnricher {
+    hostnames: HashMap<String, String>,
+    rdns: HashMap<String, String>,
+    // 本机监听端口对应的进程号和命令行
+    local: Option<(HashMap<u16, u32>, HashMap<u32, String>)>,
+    service_names: bool,
+    probe: ProbeOptions,
+    sem: Arc<Semaphore>,
+}
+
+// 开放端口补充探测的参数，每个探测任务持有一份
+#[derive(Clone)]
+struct ProbeOptions {
+    service_detect: bool,
+    tls: Option<tokio_rustls::TlsConnector>,
+    // HTTP 探测改用 HTTPS 重试时使用的连接器
+    http: Option<tokio_rustls::TlsConnector>,
+    wait: Duration,
+    proxy: Option<Proxy>,
+    source: SourceBind,
+}
+
+impl PortEnricher {
+    fn label(&self, status: &mut PortStatus) {
+        status.hostname = self.hostnames.get(&status.host).cloned();
+        status.rdns = self.rdns.get(&status.host).cloned();
+        if !self.service_names {
+            status.service = None;
+        }
+        if let Some((pids, commands)) = &self.local {
+            if status.open && is_local_target(&status.host) {
+                status.pid = pids.get(&status.port).copied();
+                status.command = status.pid.and_then(|pid| commands.get(&pid).cloned());
+            }
+        }
+    }
+
+    fn needs_probe(&self, status: &PortStatus) -> bool {
+        let probe = &self.probe;
+        status.open && (probe.service_detect || probe.tls.is_some() || probe.http.is_some())
+    }
+
+    // 先读 banner 识别服务，识别结果覆盖按端口号猜测的服务名，再做 TLS 握手读取证书，最后发 HTTP 请求
+    async fn probe(mut status: PortStatus, opts: ProbeOptions) -> PortStatus {
+        let (wait, proxy, source) = (opts.wait, opts.proxy.as_ref(), &opts.source);
+        if opts.service_detect {
+            if let Some(data) = grab_banner(&status.host, status.port, wait, proxy, source).await {
+                let info = identify_service(&data);
+                if let Some(name) = info.name {
+                    status.service = Some(name.to_string());
+                }
+                status.version = info.version;
+                status.banner = info.banner;
+            }
+        }
+        if let Some(connector) = &opts.tls {
+            status.tls = probe_tls(connector, &status.host, status.port, wait, proxy, source).await;
+        }
+        if let Some(connector) = &opts.http {
+            let host = status.hostname.as_deref().unwrap_or(&status.host);
+            status.http = probe_http(
+                connector,
+                host,
+                &status.host,
+                status.port,
+                wait,
+                proxy,
+                source,
+            )
+            .await;
+        }
+        status
+    }
+}
+
+async fn run_scan(
+    config: ScanConfig,
+    sink: mpsc::UnboundedSender<PortStatus>,
+) -> Result<PortScanResult, PortScanError> {
+    let ScanConfig {
+        target,
+        port_range,
+        hosts,
+        ports,
+        mut settings,
+    } = config;
+    let protocol = settings.protocol;
+    for host in &hosts {
+        network::check_host("scanning", host)?;
+    }
+    if let Some(proxy) = &settings.proxy {
+        if protocol != ScanProtocol::Tcp
+            || settings.scan_type == ScanType::Syn
+            || settings.os_guess
+            || settings.adaptive_timeout
+        {
+            return Err(PortScanError::ProxyRequiresConnect);
+        }
+        network::check_host("connecting to proxy", &proxy.host)?;
+        // 先确认代理可用、认证通过，否则每个端口都会因代理故障被判为 filtered
+        timeout(PROXY_HANDSHAKE_TIMEOUT, proxy.handshake())
+            .await
+            .unwrap_or_else(|_| {
+                Err(ProxyError::Connect(
+                    proxy.to_string(),
+                    io::ErrorKind::TimedOut.into(),
+                ))
+            })?;
+    }
+    // 源地址决定了能探测的地址族，未指定 -4/-6 时按源地址选择
+    settings.source.check()?;
+    if let Some(ip) = settings.source.ip {
+        let family = AddressFamily::of(&ip);
+        if !settings.family.accepts(&ip) {
+            return Err(PortScanError::SourceFamily(ip, settings.family.label()));
+        }
+        settings.family = family;
+    }
+    if settings.scan_type == ScanType::Connect {
+        settings.concurrency = fd_budget(settings.concurrency, settings.enriches());
+    }
+    // IP 字面量不经过解析，地址族不符时直接报错，而不是静默跳过
+    if let Some(host) = hosts
+        .iter()
+        .find(|host| target_addr(host, 0).is_some_and(|addr| !settings.family.accepts(&addr.ip())))
+    {
+        return Err(PortScanError::WrongFamily(
+            host.clone(),
+            settings.family.label(),
+        ));
+    }
+    // 主机名由代理解析时不在本地解析，避免经 Tor 扫描时泄露 DNS 查询
+    let resolution = match &settings.proxy {
+        Some(proxy) if proxy.resolves_remotely() => Resolution {
+            hosts: hosts.clone(),
+            hostnames: HashMap::new(),
+            resolved: Vec::new(),
+            unresolved: Vec::new(),
+        },
+        _ => resolve_targets(&hosts, settings.resolve_all, settings.family).await,
+    };
+    if resolution.hosts.is_empty() {
+        return Err(PortScanError::Unresolved(resolution.unresolved.join(", ")));
+    }
+    let hosts = resolution.hosts.as_slice();
+    let ports = ports.as_slice();
+    let adaptive_timeout = if settings.adaptive_timeout && protocol == ScanProtocol::Tcp {
+        estimate_timeout(hosts, ports, settings.timeout_ms, &settings.source).await
+    } else {
+        None
+    };
+    let settings = match adaptive_timeout {
+        Some(timeout_ms) => ScanSettings {
+            timeout_ms,
+            ..settings
+        },
+        None => settings,
+    };
+    let settings = &settings;
+    if let Some(progress) = &settings.progress {
+        progress.set_total((hosts.len() * ports.len()) as u64);
+    }
+
+    // 反向解析和本机监听表与端口无关，在探测前准备好，每个端口结果产出时即可填上
+    let rdns = if settings.reverse_dns && !network::skip("reverse DNS") {
+        reverse_lookup(hosts, settings).await
+    } else {
+        HashMap::new()
+    };
+    // 本机进程匹配基于 TCP 监听表，UDP 扫描不做匹配；经代理时 localhost 指的是代理所在的主机
+    let local = (protocol == ScanProtocol::Tcp
+        && settings.proxy.is_none()
+        && hosts.iter().any(|host| is_local_target(host)))
+    .then(|| (local_tcp_listen_pids(), local_process_commands()));
+    // 服务识别需要建立完整连接，仅对 TCP 开放端口执行
+    let service_detect = settings.service_detect && protocol == ScanProtocol::Tcp;
+    let tls_probe = settings.tls_probe && protocol == ScanProtocol::Tcp;
+    let http_probe = settings.http_probe && protocol == ScanProtocol::Tcp;
+    let insecure = || tokio_rustls::TlsConnector::from(Arc::new(tls::insecure_client_config()));
+    let enricher = PortEnricher {
+        hostnames: resolution.hostnames.clone(),
+        rdns: rdns.clone(),
+        local,
+        service_names: settings.service_names,
+        probe: ProbeOptions {
+            service_detect,
+            tls: tls_probe.then(insecure),
+            http: http_probe.then(insecure),
+            wait: Duration::from_millis(settings.timeout_ms.clamp(500, 3000)),
+            proxy: settings.proxy.clone(),
+            source: settings.source.clone(),
+        },
+        sem: Arc::new(Semaphore::new(settings.concurrency)),
+    };
+
+    let (probe_tx, mut probe_rx) = mpsc::unbounded_channel();
+    let probing = async {
+        match settings.scan_type {
+            ScanType::Syn => match syn_scan(hosts, ports, settings).await? {
+                Some(results) => {
+                    for result in results {
+                        let _ = probe_tx.send(result);
+                    }
+                    Ok(ScanType::Syn)
+                }
+                None => {
+                    connect_scan(hosts, ports, settings, probe_tx).await?;
+                    Ok(ScanType::Connect)
+                }
+            },
+            ScanType::Connect | ScanType::Local => {
+                connect_scan(hosts, ports, settings, probe_tx).await?;
+                Ok::<_, PortScanError>(ScanType::Connect)
+            }
+        }
+    };
+    let enriching = async {
+        let mut probes = FuturesUnordered::new();
+        let mut results = Vec::new();
+        let mut emit = |(host_index, status): (usize, PortStatus)| {
+            // 调用方丢弃了流时只是不再推送，汇总仍然完整
+            let _ = sink.send(status.clone());
+            results.push((host_index, status));
+        };
+        loop {
+            tokio::select! {
+                Some((host_index, mut status)) = probe_rx.recv() => {
+                    enricher.label(&mut status);
+                    if !enricher.needs_probe(&status) {
+                        emit((host_index, status));
+                        continue;
+                    }
+                    let sem = enricher.sem.clone();
+                    let opts = enricher.probe.clone();
+                    probes.push(tokio::spawn(async move {
+                        let _permit = sem.acquire_owned().await.expect("semaphore acquire failed");
+                        let status = PortEnricher::probe(status, opts).await;
+                        (host_index, status)
+                    }));
+                }
+                Some(join_res) = probes.next() => {
+                    emit(join_res.map_err(|e| PortScanError::JoinError(e.to_string()))?);
+                }
+                else => break,
+            }
+        }
+        Ok::<_, PortScanError>(results)
+    };
+    let (scan_type, results) = tokio::join!(probing, enriching);
+    let scan_type = scan_type?;
+    let mut results = results?;
+    results.sort_by_key(|(host_index, status)| (*host_index, status.port));
+    let ports: Vec<PortStatus> = results.into_iter().map(|(_, status)| status).collect();
+    metrics::open_ports_found(ports.iter().filter(|status| status.open).count());
+
+    let mut host_summaries: Vec<HostSummary> = hosts
+        .iter()
+        .map(|host| {
+            let host_ports: Vec<&PortStatus> =
+                ports.iter().filter(|status| &status.host == host).collect();
+            let open_ports: Vec<u16> = host_ports
+                .iter()
+                .filter(|status| status.open)
+                .map(|status| status.port)
+                .collect();
+            let count = |state| {
+                host_ports
+                    .iter()
+                    .filter(|status| status.state == state)
+                    .count()
+            };
+            HostSummary {
+                host: host.clone(),
+                hostname: resolution.hostnames.get(host).cloned(),
+                rdns: rdns.get(host).cloned(),
+                open_count: open_ports.len(),
+                closed_count: count(PortState::Closed),
+                filtered_count: count(PortState::Filtered),
+                open_ports,
+                latency: LatencyStats::of_open(host_ports.iter().copied()),
+                os_guess: None,
+            }
+        })
+        .collect();
+    if settings.os_guess {
+        guess_os(&mut host_summaries, protocol, settings).await;
+    }
+
+    let open_ports: Vec<u16> = ports
+        .iter()
+        .filter(|status| status.open)
+        .map(|status| status.port)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    let total = ports.len();
+    let count = |state| ports.iter().filter(|status| status.state == state).count();
+    let open_count = ports.iter().filter(|status| status.open).count();
+    let closed_count = count(PortState::Closed);
+    let filtered_count = count(PortState::Filtered);
+    let open_filtered_count = count(PortState::OpenFiltered);
+
+    Ok(PortScanResult {
+        target,
+        port_range,
+        protocol,
+        scan_type,
+        service_detect,
+        tls_probe,
+        http_probe,
+        reverse_dns: settings.reverse_dns,
+        resolved: resolution.resolved,
+        unresolved: resolution.unresolved,
+        concurrency: settings.concurrency,
+        timeout_ms: settings.timeout_ms,
+        adaptive_timeout: adaptive_timeout.is_some(),
+        rate: settings.rate,
+        proxy: settings.proxy.as_ref().map(Proxy::to_string),
+        source: settings
+            .source
+            .is_set()
+            .then(|| settings.source.to_string()),
+        retries: settings.retries,
+        total,
+        open_count,
+        closed_count,
+        filtered_count,
+        open_filtered_count,
+        open_ports,
+        latency: LatencyStats::of_open(&ports),
+        hosts: host_summaries,
+        ports,
+    })
+}
+
+// 主机名的解析结果：全部 A/AAAA 记录以及实际扫描的地址
+#[derive(Debug, Clone, Serialize)]
+pub struct ResolvedHost {
+    pub hostname: String,
+    pub addresses: Vec<IpAddr>,
+    pub scanned: Vec<IpAddr>,
+}
+
+// 扫描目标的解析汇总，hosts 为去重后实际扫描的主机（IP 字面量或解析出的地址）
+struct Resolution {
+    hosts: Vec<String>,
+    hostnames: HashMap<String, String>,
+    resolved: Vec<ResolvedHost>,
+    unresolved: Vec<String>,
+}
+
+const RESOLVE_TIMEOUT: Duration = Duration::from_secs(5);
+// 每个连接与代理之间的 TCP 连接和 SOCKS5 协商的超时，不计入 --timeout
+pub(super) const PROXY_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+// 扫描前统一解析主机名，避免每次连接各自解析落到不同地址上；
+// 默认只扫描第一个地址（有 IPv4 时优先 IPv4），resolve_all 时扫描全部地址；
+// 指定地址族时只扫描该地址族的地址，没有对应记录的主机名视为无法解析
+async fn resolve_targets(hosts: &[String], resolve_all: bool, family: AddressFamily) -> Resolution {
+    let lookups = futures::future::join_all(hosts.iter().map(|host| async move {
+        if target_addr(host, 0).is_some() {
+            return None;
+        }
+        let addrs = timeout(RESOLVE_TIMEOUT, tokio::net::lookup_host((host.as_str(), 0)))
+            .await
+            .ok()
+            .and_then(Result::ok)
+            .map(|addrs| addrs.map(|addr| addr.ip()).collect::<Vec<_>>())
+            .unwrap_or_default();
+        Some(addrs)
+    }))
+    .await;
+
+    let mut seen = HashSet::new();
+    let mut resolution = Resolution {
+        hosts: Vec::new(),
+        hostnames: HashMap::new(),
+        resolved: Vec::new(),
+        unresolved: Vec::new(),
+    };
+    for (host, lookup) in hosts.iter().zip(lookups) {
+        let Some(lookup) = lookup else {
+            if seen.insert(host.clone()) {
+                resolution.hosts.push(host.clone());
+            }
+            continue;
+        };
+        let mut addresses = Vec::new();
+        for addr in lookup {
+            if !addresses.contains(&addr) {
+                addresses.push(addr);
+            }
+        }
+        let usable: Vec<IpAddr> = addresses
+            .iter()
+            .copied()
+            .filter(|addr| family.accepts(addr))
+            .collect();
+        let Some(&first) = usable.iter().find(|addr| addr.is_ipv4()).or(usable.first()) else {
+            resolution.unresolved.push(host.clone());
+            continue;
+        };
+        let scanned = if resolve_all { usable } else { vec![first] };
+        for addr in &scanned {
+            let key = addr.to_string();
+            resolution
+                .hostnames
+                .entry(key.clone())
+                .or_insert_with(|| host.clone());
+            if seen.insert(key.clone()) {
+                resolution.hosts.push(key);
+            }
+        }
+        resolution.resolved.push(ResolvedHost {
+            hostname: host.clone(),
+            addresses,
+            scanned,
+        });
+    }
+    resolution
+}
+
+// 反向解析扫描地址的 PTR 记录，查询失败或超时的地址不出现在结果中
+async fn reverse_lookup(hosts: &[String], settings: &ScanSettings) -> HashMap<String, String> {
+    let Ok(builder) = TokioResolver::builder_tokio() else {
+        eprintln!("warning: cannot load system resolver config, reverse DNS skipped");
+        return HashMap::new();
+    };
+    let resolver = builder.build();
+    let wait = Duration::from_millis(settings.timeout_ms).max(RESOLVE_TIMEOUT);
+    let resolver = &resolver;
+    // 先把查询收集成 Vec：扫描在 tokio::spawn 中运行，流里带着闭包时编译器无法证明 future 满足 Send
+    let lookups: Vec<_> = hosts
+        .iter()
+        .map(|host| async move {
+            let ip: IpAddr = host.parse().ok()?;
+            let lookup = timeout(wait, resolver.reverse_lookup(ip))
+                .await
+                .ok()?
+                .ok()?;
+            let name = lookup.iter().next()?.to_utf8();
+            Some((host.clone(), name.trim_end_matches('.').to_string()))
+        })
+        .collect();
+    futures::stream::iter(lookups)
+        .buffer_unordered(settings.concurrency)
+        .collect::<Vec<_>>()
+        .await
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+// 逐个端口建立连接（TCP）或发送探测报文（UDP），每完成一个探测就把（主机序号, 结果）送入 results；
+// 所有主机共用同一个信号量，并发数是全局上限而不是每台主机的上限
+async fn connect_scan(
+    hosts: &[String],
+    ports: &[u16],
+    settings: &ScanSettings,
+    results: mpsc::UnboundedSender<(usize, PortStatus)>,
+) -> Result<(), PortScanError> {
+    let protocol = settings.protocol;
+    let window = Arc::new(ConcurrencyWindow::new(settings.concurrency));
+    let rate = settings.rate_limiter();
+    let mut tasks = FuturesUnordered::new();
+
+    for (host_index, port) in probe_order(hosts.len(), ports, settings.randomize) {
+        let host = &hosts[host_index];
+        // 状态文件中已有结果的探测直接沿用
+        if let Some((state, latency_ms)) = settings
+            .resume
+            .as_ref()
+            .and_then(|resume| resume.get(host, port))
+        {
+            if let Some(progress) = &settings.progress {
+                progress.advance(1);
+                if state == PortState::Open {
+                    progress.add_found(1);
+                }
+            }
+            let _ = results.send((
+                host_index,
+                port_status(host.clone(), port, state, latency_ms, protocol),
+            ));
+            continue;
+        }
+        let permit = window.acquire().await;
+        if settings
+            .progress
+            .as_ref()
+            .is_some_and(|progress| progress.is_cancelled())
+        {
+            return Err(PortScanError::Cancelled);
+        }
+        let host = host.clone();
+        let to = Duration::from_millis(settings.timeout_ms);
+        let retries = settings.retries;
+        let rate = rate.clone();
+        let progress = settings.progress.clone();
+        let resume = settings.resume.clone();
+        let proxy = settings.proxy.clone();
+        let source = settings.source.clone();
+        let window = Arc::clone(&window);
+        let results = results.clone();
+
+        tasks.push(tokio::spawn(async move {
+            let _permit = permit;
+            let mut attempt = 0;
+            let mut exhausted = 0;
+            let (state, started) = loop {
+                if let Some(rate) = &rate {
+                    rate.acquire().await;
+                }
+                let started = Instant::now();
+                // 经代理时延迟从请求代理连接目标时算起，不含与代理的握手
+                let (probed, started) = match (protocol, &proxy) {
+                    (ScanProtocol::Tcp, Some(proxy)) => {
+                        let (state, started) = probe_proxy(proxy, &host, port, to).await;
+                        (Ok(state), started)
+                    }
+                    (ScanProtocol::Tcp, None) => {
+                        (probe_tcp(&host, port, to, &source).await, started)
+                    }
+                    (ScanProtocol::Udp, _) => (probe_udp(&host, port, to, &source).await, started),
+                };
+                // 本机资源耗尽时探测并未发出：缩小并发窗口后重新探测，不计入 --retries
+                let state = match probed {
+                    Ok(state) => {
+                        window.success();
+                        window.observe(state != PortState::Open);
+                        state
+                    }
+                    Err(_) if exhausted < MAX_EXHAUSTED_RETRIES => {
+                        exhausted += 1;
+                        window.backoff();
+                        tokio::time::sleep(EXHAUSTED_BACKOFF * exhausted).await;
+                        continue;
+                    }
+                    Err(_) => PortState::Filtered,
+                };
+                // 只重试没有明确响应的端口，收到 RST / 回包 / ICMP 不可达的结果已经确定
+                if attempt >= retries || !state.unanswered() {
+                    break (state, started);
+                }
+                attempt += 1;
+            };
+            let latency_ms = matches!(state, PortState::Open | PortState::Closed)
+                .then(|| (started.elapsed().as_secs_f64() * 100_000.0).round() / 100.0);
+            if let Some(progress) = progress {
+                progress.advance(1);
+                if state == PortState::Open {
+                    progress.add_found(1);
+                }
+            }
+            if let Some(resume) = resume {
+                resume.record(&host, port, state, latency_ms)?;
+            }
+            let _ = results.send((
+                host_index,
+                port_status(host, port, state, latency_ms, protocol),
+            ));
+            Ok::<_, PortScanError>(())
+        }));
+    }
+
+    while let Some(join_res) = tasks.next().await {
+        match join_res {
+            Ok(result) => result?,
+            Err(e) => return Err(PortScanError::JoinError(e.to_string())),
+        }
+    }
+    let lowest = window.lowest();
+    if lowest < settings.concurrency {
+        let cause = if window.lock().throttled {
+            "error and timeout rate spiked (rate limiting or IDS on the path?)"
+        } else {
+            "ran out of local resources (open files, buffers or ephemeral ports)"
+        };
+        eprintln!(
+            "warning: {}, concurrency dropped to {} at the lowest",
+            cause, lowest
+        );
+    }
+    Ok(())
+}
+
+// 按进程的文件描述符上限限制并发：先尝试把软限制提高到所需数量，仍不够时降低 -c。
+// 服务识别、TLS / HTTP 探测与扫描同时进行，每个并发可能占用两个描述符
+fn fd_budget(concurrency: usize, enriches: bool) -> usize {
+    let per_probe = if enriches { 2 } else { 1 };
+    let wanted = concurrency * per_probe + RESERVED_FDS;
+    let Some(limit) = fdlimit::raise_open_files(wanted as u64) else {
+        return concurrency;
+    };
+    let budget = usize::try_from(limit)
+        .unwrap_or(usize::MAX)
+        .saturating_sub(RESERVED_FDS)
+        / per_probe;
+    if budget >= concurrency {
+        return concurrency;
+    }
+    let budget = budget.max(1);
+    eprintln!(
+        "warning: open file limit is {}, lowering concurrency from {} to {} (raise it with ulimit -n)",
+        limit, concurrency, budget
+    );
+    budget
+}
+
+// 自适应并发窗口：本机资源耗尽（EMFILE、ENOBUFS、临时端口用完等）或失败率突增时减半，
+// 之后每完成一个窗口大小的正常探测增加 10%，直到回到 -c 指定的上限。
+// 信号量按上限创建，缩小窗口时记下欠数，之后归还的许可直接作废来偿还
+struct ConcurrencyWindow {
+    sem: Arc<Semaphore>,
+    max: usize,
+    state: Mutex<WindowState>,
+}
+
+struct WindowState {
+    limit: usize,
+    lowest: usize,
+    debt: usize,
+    successes: usize,
+    last_backoff: Option<Instant>,
+    // 最近 RATE_WINDOW 个探测是否失败，以及其中失败的个数
+    recent: VecDeque<bool>,
+    recent_failures: usize,
+    // 长期失败率，第一次攒满窗口时取窗口的失败率
+    baseline: Option<f64>,
+    // 是否因失败率突增缩小过窗口，用于结束时的提示
+    throttled: bool,
+}
+
+// 归还时先偿还窗口缩小欠下的许可
+struct WindowPermit {
+    permit: Option<OwnedSemaphorePermit>,
+    window: Arc<ConcurrencyWindow>,
+}
+
+impl Drop for WindowPermit {
+    fn drop(&mut self) {
+        let mut state = self.window.lock();
+        if state.debt > 0 {
+            state.debt -= 1;
+            if let Some(permit) = self.permit.take() {
+                permit.forget();
+            }
+        }
+    }
+}
+
+impl ConcurrencyWindow {
+    fn new(max: usize) -> Self {
+        let max = max.max(1);
+        Self {
+            sem: Arc::new(Semaphore::new(max)),
+            max,
+            state: Mutex::new(WindowState {
+                limit: max,
+                lowest: max,
+                debt: 0,
+                successes: 0,
+                last_backoff: None,
+                recent: VecDeque::with_capacity(RATE_WINDOW + 1),
+                recent_failures: 0,
+                baseline: None,
+                throttled: false,
+            }),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, WindowState> {
+        self.state
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    async fn acquire(self: &Arc<Self>) -> WindowPermit {
+        let permit = Arc::clone(&self.sem)
+            .acquire_owned()
+            .await
+            .expect("semaphore acquire failed");
+        WindowPermit {
+            permit: Some(permit),
+            window: Arc::clone(self),
+        }
+    }
+
+    fn backoff(&self) {
+        Self::shrink(&mut self.lock());
+    }
+
+    fn shrink(state: &mut WindowState) {
+        if state
+            .last_backoff
+            .is_some_and(|last| last.elapsed() < BACKOFF_COOLDOWN)
+        {
+            return;
+        }
+        let limit = (state.limit / 2).max(1);
+        state.debt += state.limit - limit;
+        state.limit = limit;
+        state.lowest = state.lowest.min(limit);
+        state.successes = 0;
+        state.last_backoff = Some(Instant::now());
+    }
+
+    // 记录一次探测的结果：拒绝连接、超时和不可达都算失败
+    fn observe(&self, failed: bool) {
+        let mut state = self.lock();
+        state.recent.push_back(failed);
+        state.recent_failures += usize::from(failed);
+        if state.recent.len() > RATE_WINDOW && state.recent.pop_front() == Some(true) {
+            state.recent_failures -= 1;
+        }
+        if state.recent.len() < RATE_WINDOW {
+            return;
+        }
+        let rate = state.recent_failures as f64 / RATE_WINDOW as f64;
+        let Some(baseline) = state.baseline else {
+            state.baseline = Some(rate);
+            return;
+        };
+        if rate - baseline >= ERROR_SPIKE {
+            // 清空窗口，缩小后的并发重新攒满一个窗口再判断
+            state.recent.clear();
+            state.recent_failures = 0;
+            state.throttled = true;
+            Self::shrink(&mut state);
+            return;
+        }
+        state.baseline = Some(baseline + (rate - baseline) / BASELINE_SMOOTHING);
+    }
+
+    fn success(&self) {
+        let mut state = self.lock();
+        if state.limit >= self.max {
+            return;
+        }
+        state.successes += 1;
+        if state.successes < state.limit {
+            return;
+        }
+        let grown = (state.limit + state.limit.div_ceil(10)).min(self.max);
+        let mut added = grown - state.limit;
+        state.limit = grown;
+        state.successes = 0;
+        let repaid = added.min(state.debt);
+        state.debt -= repaid;
+        added -= repaid;
+        self.sem.add_permits(added);
+    }
+
+    fn lowest(&self) -> usize {
+        self.lock().lowest
+    }
+}
+
+// 探测顺序：默认逐个主机按端口升序；randomize 时把所有 (主机, 端口) 组合整体打乱，
+// 相邻的探测通常落在不同主机的不同端口上。结果最终仍按主机和端口排序输出
+fn probe_order(host_count: usize, ports: &[u16], randomize: bool) -> Vec<(usize, u16)> {
+    let mut order: Vec<(usize, u16)> = (0..host_count)
+        .flat_map(|host_index| ports.iter().map(move |&port| (host_index, port)))
+        .collect();
+    if randomize {
+        order.shuffle(&mut rand::rng());
+    }
+    order
+}
+
+// 先用配置的超时对前几个主机的前几个端口做连接采样，收到 SYN/ACK 或 RST 的探测给出 RTT，
+// 超时取最大 RTT 的若干倍，不超过配置值；采样全部无响应时返回 None，沿用配置的超时
+async fn estimate_timeout(
+    hosts: &[String],
+    ports: &[u16],
+    timeout_ms: u64,
+    source: &SourceBind,
+) -> Option<u64> {
+    let to = Duration::from_millis(timeout_ms);
+    let samples = hosts.iter().take(RTT_SAMPLE_HOSTS).flat_map(|host| {
+        ports
+            .iter()
+            .take(RTT_SAMPLE_PORTS)
+            .map(move |&port| async move {
+                let started = Instant::now();
+                let state = probe_tcp(host, port, to, source).await.ok()?;
+                (!state.unanswered()).then(|| started.elapsed())
+            })
+    });
+    let rtt = futures::future::join_all(samples)
+        .await
+        .into_iter()
+        .flatten()
+        .max()?;
+    let estimate = (rtt.as_secs_f64() * 1000.0 * RTT_TIMEOUT_FACTOR).ceil() as u64;
+    Some(estimate.clamp(MIN_ADAPTIVE_TIMEOUT_MS.min(timeout_ms), timeout_ms))
+}
+
+pub(super) fn port_status(
+    host: String,
+    port: u16,
+    state: PortState,
+    latency_ms: Option<f64>,
+    protocol: ScanProtocol,
+) -> PortStatus {
+    PortStatus {
+        host,
+        hostname: None,
+        rdns: None,
+        port,
+        open: state == PortState::Open,
+        state,
+        latency_ms,
+        service: services::service_name(port, protocol == ScanProtocol::Udp).map(str::to_string),
+        version: None,
+        banner: None,
+        tls: None,
+        http: None,
+        pid: None,
+        command: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::portscan::write_plain;
+    use tokio::io::AsyncWriteExt;
+
+    #[test]
+    fn test_resolve_targets_pins_hostnames() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let hosts = vec![
+                "localhost".to_string(),
+                "127.0.0.1".to_string(),
+                "no-such-host.invalid".to_string(),
+            ];
+            let resolution = resolve_targets(&hosts, false, AddressFamily::Any).await;
+            // localhost 优先取 IPv4 地址，与字面量目标去重
+            assert_eq!(resolution.hosts, vec!["127.0.0.1"]);
+            assert_eq!(resolution.hostnames["127.0.0.1"], "localhost");
+            assert_eq!(resolution.unresolved, vec!["no-such-host.invalid"]);
+            assert_eq!(resolution.resolved[0].scanned.len(), 1);
+
+            let resolution = resolve_targets(&hosts[..1], true, AddressFamily::Any).await;
+            assert_eq!(
+                resolution.resolved[0].scanned,
+                resolution.resolved[0].addresses
+            );
+            assert_eq!(
+                resolution.hosts.len(),
+                resolution.resolved[0].addresses.len()
+            );
+
+            let err = remote_scan("x", &hosts[2..], "80", &[80], &ScanSettings::default())
+                .await
+                .unwrap_err();
+            assert!(
+                matches!(err, PortScanError::Unresolved(host) if host == "no-such-host.invalid")
+            );
+        });
+    }
+
+    #[test]
+    fn test_udp_scan_states() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            // 回显服务：收到任意报文原样返回
+            let server = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+            let open_port = server.local_addr().unwrap().port();
+            tokio::spawn(async move {
+                let mut buf = [0u8; 1500];
+                while let Ok((n, peer)) = server.recv_from(&mut buf).await {
+                    let _ = server.send_to(&buf[..n], peer).await;
+                }
+            });
+            // 静默服务：只收不回
+            let silent = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+            let silent_port = silent.local_addr().unwrap().port();
+            // 绑定后立即释放，端口上没有监听者
+            let closed_port = std::net::UdpSocket::bind("127.0.0.1:0")
+                .unwrap()
+                .local_addr()
+                .unwrap()
+                .port();
+
+            let to = Duration::from_millis(300);
+            assert_eq!(
+                probe_udp("127.0.0.1", open_port, to, &SourceBind::default())
+                    .await
+                    .unwrap(),
+                PortState::Open
+            );
+            assert_eq!(
+                probe_udp("127.0.0.1", silent_port, to, &SourceBind::default())
+                    .await
+                    .unwrap(),
+                PortState::OpenFiltered
+            );
+            assert_eq!(
+                probe_udp("127.0.0.1", closed_port, to, &SourceBind::default())
+                    .await
+                    .unwrap(),
+                PortState::Closed
+            );
+
+            // 汇总中的 closed 只统计真正关闭的端口
+            let settings = ScanSettings {
+                concurrency: 10,
+                timeout_ms: 300,
+                protocol: ScanProtocol::Udp,
+                ..ScanSettings::default()
+            };
+            let hosts = vec!["127.0.0.1".to_string()];
+            let ports = [open_port, silent_port, closed_port];
+            let result = remote_scan("127.0.0.1", &hosts, "", &ports, &settings)
+                .await
+                .unwrap();
+            assert_eq!(result.total, 3);
+            assert_eq!(result.open_count, 1);
+            assert_eq!(result.closed_count, 1);
+            assert_eq!(result.open_filtered_count, 1);
+            assert_eq!(result.hosts[0].closed_count, 1);
+
+            // 只列出部分端口时，纯文本汇总注明统计是过滤前的
+            let mut result = result;
+            result.ports.retain(|status| status.open);
+            let mut plain = Vec::new();
+            write_plain(&result, &mut plain).unwrap();
+            let plain = String::from_utf8(plain).unwrap();
+            assert!(plain.contains("Listed above: 1 of 3 (filtered)"));
+            assert!(plain.contains("Open ports: 1  Closed ports: 1"));
+            drop(silent);
+        });
+    }
+
+    #[test]
+    fn test_adaptive_timeout_from_sampled_rtt() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let port = listener.local_addr().unwrap().port();
+            let hosts = vec!["127.0.0.1".to_string()];
+            // 本机 RTT 远低于下限，取下限；配置的超时更小时不会被调高
+            assert_eq!(
+                estimate_timeout(&hosts, &[port], 2000, &SourceBind::default()).await,
+                Some(MIN_ADAPTIVE_TIMEOUT_MS)
+            );
+            assert_eq!(
+                estimate_timeout(&hosts, &[port], 60, &SourceBind::default()).await,
+                Some(60)
+            );
+
+            let settings = ScanSettings {
+                timeout_ms: 2000,
+                adaptive_timeout: true,
+                ..ScanSettings::default()
+            };
+            let result = remote_scan("127.0.0.1", &hosts, "", &[port], &settings)
+                .await
+                .unwrap();
+            assert!(result.adaptive_timeout);
+            assert_eq!(result.timeout_ms, MIN_ADAPTIVE_TIMEOUT_MS);
+            assert_eq!(result.open_ports, [port]);
+            let mut plain = Vec::new();
+            write_plain(&result, &mut plain).unwrap();
+            assert!(String::from_utf8(plain)
+                .unwrap()
+                .contains("timeout=100ms adaptive)"));
+            drop(listener);
+        });
+    }
+
+    #[test]
+    fn test_rate_limits_connect_scan() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let settings = ScanSettings {
+                rate: Some(100),
+                ..ScanSettings::default()
+            };
+            let hosts = vec!["127.0.0.1".to_string()];
+            let ports: Vec<u16> = (1..=11).collect();
+            let started = Instant::now();
+            let result = remote_scan("127.0.0.1", &hosts, "1-11", &ports, &settings)
+                .await
+                .unwrap();
+            // 并发足够时也按每 10ms 一个探测发出
+            assert!(started.elapsed() >= Duration::from_millis(95));
+            assert_eq!(result.total, 11);
+            assert_eq!(result.rate, Some(100));
+            let mut plain = Vec::new();
+            write_plain(&result, &mut plain).unwrap();
+            assert!(String::from_utf8(plain)
+                .unwrap()
+                .contains("timeout=1000ms, rate=100/s)"));
+        });
+    }
+
+    #[test]
+    fn test_concurrency_window_backs_off_and_recovers() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let window = Arc::new(ConcurrencyWindow::new(8));
+            let permits = futures::future::join_all((0..8).map(|_| window.acquire())).await;
+            window.backoff();
+            // 同一批在途探测一起失败时只减半一次
+            window.backoff();
+            assert_eq!(window.lowest(), 4);
+            // 归还的 8 个许可中有 4 个用来偿还缩小窗口的欠数
+            drop(permits);
+            assert_eq!(window.sem.available_permits(), 4);
+            for _ in 0..4 {
+                window.success();
+            }
+            assert_eq!(window.lock().limit, 5);
+            assert_eq!(window.sem.available_permits(), 5);
+        });
+    }
+
+    #[test]
+    fn test_concurrency_window_backs_off_on_error_storm() {
+        // 整段端口都拒绝连接是正常的扫描结果，不缩小窗口
+        let window = ConcurrencyWindow::new(64);
+        for _ in 0..RATE_WINDOW * 10 {
+            window.observe(true);
+        }
+        assert_eq!(window.lowest(), 64);
+
+        // 大部分探测成功之后突然连续失败，模拟目标开始限速或 IDS 拦截
+        let window = ConcurrencyWindow::new(64);
+        for i in 0..RATE_WINDOW * 4 {
+            window.observe(i % 10 == 0);
+        }
+        assert_eq!(window.lowest(), 64);
+        for _ in 0..RATE_WINDOW / 2 {
+            window.observe(true);
+        }
+        assert_eq!(window.lowest(), 32);
+        assert!(window.lock().throttled);
+        assert!(window.lock().recent.len() < RATE_WINDOW);
+    }
+
+    #[test]
+    fn test_retries_reprobe_unanswered_ports() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            // 模拟丢包：丢弃第一个报文，之后的报文原样返回
+            let server = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+            let port = server.local_addr().unwrap().port();
+            tokio::spawn(async move {
+                let mut buf = [0u8; 1500];
+                let mut received = 0;
+                while let Ok((n, peer)) = server.recv_from(&mut buf).await {
+                    received += 1;
+                    if received > 1 {
+                        let _ = server.send_to(&buf[..n], peer).await;
+                    }
+                }
+            });
+            let hosts = vec!["127.0.0.1".to_string()];
+            let mut settings = ScanSettings {
+                timeout_ms: 200,
+                protocol: ScanProtocol::Udp,
+                ..ScanSettings::default()
+            };
+            let result = remote_scan("127.0.0.1", &hosts, "", &[port], &settings)
+                .await
+                .unwrap();
+            assert_eq!(result.ports[0].state, PortState::OpenFiltered);
+
+            settings.retries = 2;
+            let result = remote_scan("127.0.0.1", &hosts, "", &[port], &settings)
+                .await
+                .unwrap();
+            assert_eq!(result.ports[0].state, PortState::Open);
+            assert_eq!(result.retries, 2);
+            let mut plain = Vec::new();
+            write_plain(&result, &mut plain).unwrap();
+            assert!(String::from_utf8(plain)
+                .unwrap()
+                .contains("timeout=200ms, retries=2)"));
+        });
+    }
+
+    #[test]
+    fn test_randomized_probe_order() {
+        let ports: Vec<u16> = (1..=500).collect();
+        let ordered = probe_order(3, &ports, false);
+        assert_eq!(ordered[..2], [(0, 1), (0, 2)]);
+        assert_eq!(ordered[500], (1, 1));
+        let mut shuffled = probe_order(3, &ports, true);
+        assert_ne!(shuffled, ordered);
+        // 只改变顺序，不增减探测
+        shuffled.sort();
+        assert_eq!(shuffled, ordered);
+    }
+
+    #[test]
+    fn test_scan_stream_yields_enriched_results() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let ssh = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let open = ssh.local_addr().unwrap().port();
+            tokio::spawn(async move {
+                while let Ok((mut conn, _)) = ssh.accept().await {
+                    let _ = conn.write_all(b"SSH-2.0-dropbear_2022.83\r\n").await;
+                }
+            });
+            let closed = {
+                let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+                listener.local_addr().unwrap().port()
+            };
+
+            let mut config = ScanConfig::new("127.0.0.1", &format!("{},{}", closed, open)).unwrap();
+            config.settings.service_detect = true;
+            let mut stream = scan(config);
+            let mut seen = Vec::new();
+            while let Some(status) = stream.next().await {
+                seen.push(status.unwrap());
+            }
+            assert_eq!(seen.len(), 2);
+            // 流中的开放端口已经带上服务识别结果
+            let status = seen.iter().find(|status| status.open).unwrap();
+            assert_eq!(status.port, open);
+            assert_eq!(status.version.as_deref(), Some("dropbear_2022.83"));
+
+            let result = stream.finish().await.unwrap();
+            assert_eq!(result.open_ports, vec![open]);
+            let mut expected = vec![closed, open];
+            expected.sort();
+            let ports: Vec<u16> = result.ports.iter().map(|status| status.port).collect();
+            assert_eq!(ports, expected);
+
+            // 扫描失败时错误作为流的最后一项
+            let mut config = ScanConfig::new("127.0.0.1", "80").unwrap();
+            config.settings.family = AddressFamily::V6;
+            let mut stream = scan(config);
+            assert!(matches!(
+                stream.next().await,
+                Some(Err(PortScanError::WrongFamily(..)))
+            ));
+            assert!(stream.next().await.is_none());
+        });
+        assert!(matches!(
+            ScanConfig::new("127.0.0.1", "0"),
+            Err(PortScanError::InvalidPort(_) | PortScanError::InvalidPortRange(_))
+        ));
+    }
+}
@@ -0,0 +1,296 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use crate::utils::sockets::{self, Transport};
+
+use super::engine::port_status;
+use super::{
+    HostSummary, PortScanError, PortScanResult, PortState, PortStatus, ScanProtocol, ScanSettings,
+    ScanType,
+};
+
+// --local：用套接字表代替扫描，每个监听地址作为一个主机，监听的端口都视为开放
+pub(super) fn local_listening(
+    port_spec: &str,
+    ports: &[u16],
+    settings: &ScanSettings,
+) -> Result<PortScanResult, PortScanError> {
+    let transport = match settings.protocol {
+        ScanProtocol::Tcp => Transport::Tcp,
+        ScanProtocol::Udp => Transport::Udp,
+    };
+    let wanted: HashSet<u16> = ports.iter().copied().collect();
+    let mut sockets: Vec<_> = sockets::listening_sockets(transport)
+        .map_err(PortScanError::SocketTable)?
+        .into_iter()
+        .filter(|socket| wanted.contains(&socket.port) && settings.family.accepts(&socket.addr))
+        .collect();
+    // SO_REUSEPORT 和多进程预派生会让同一地址端口出现多次，保留第一个能确定进程的
+    sockets.sort_by_key(|socket| (socket.addr, socket.port, socket.pid.is_none()));
+    sockets.dedup_by_key(|socket| (socket.addr, socket.port));
+    let commands = local_process_commands();
+    let mut ports: Vec<PortStatus> = sockets
+        .into_iter()
+        .map(|socket| {
+            let mut status = port_status(
+                socket.addr.to_string(),
+                socket.port,
+                PortState::Open,
+                None,
+                settings.protocol,
+            );
+            if !settings.service_names {
+                status.service = None;
+            }
+            status.pid = socket.pid;
+            status.command = socket.pid.and_then(|pid| commands.get(&pid).cloned());
+            status
+        })
+        .collect();
+    ports.sort_by_key(|status| status.port);
+
+    let mut hosts: BTreeMap<&str, Vec<u16>> = BTreeMap::new();
+    for status in &ports {
+        hosts.entry(&status.host).or_default().push(status.port);
+    }
+    let hosts: Vec<HostSummary> = hosts
+        .into_iter()
+        .map(|(host, open_ports)| HostSummary {
+            host: host.to_string(),
+            hostname: None,
+            rdns: None,
+            open_count: open_ports.len(),
+            closed_count: 0,
+            filtered_count: 0,
+            open_ports,
+            latency: None,
+            os_guess: None,
+        })
+        .collect();
+    let open_ports: Vec<u16> = ports
+        .iter()
+        .map(|status| status.port)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    Ok(PortScanResult {
+        target: "localhost".to_string(),
+        port_range: port_spec.to_string(),
+        protocol: settings.protocol,
+        scan_type: ScanType::Local,
+        service_detect: false,
+        tls_probe: false,
+        http_probe: false,
+        reverse_dns: false,
+        resolved: Vec::new(),
+        unresolved: Vec::new(),
+        concurrency: 0,
+        timeout_ms: 0,
+        adaptive_timeout: false,
+        rate: None,
+        proxy: None,
+        source: None,
+        retries: 0,
+        total: ports.len(),
+        open_count: ports.len(),
+        closed_count: 0,
+        filtered_count: 0,
+        open_filtered_count: 0,
+        open_ports,
+        latency: None,
+        hosts,
+        ports,
+    })
+}
+
+pub(super) fn is_local_target(target: &str) -> bool {
+    matches!(
+        target.trim().to_ascii_lowercase().as_str(),
+        "127.0.0.1" | "localhost" | "::1" | "0.0.0.0"
+    )
+}
+
+pub(super) fn local_tcp_listen_pids() -> HashMap<u16, u32> {
+    #[cfg(windows)]
+    {
+        windows_tcp_listen_pids()
+    }
+
+    #[cfg(not(windows))]
+    {
+        let mut pids = HashMap::new();
+        for socket in sockets::listening_sockets(Transport::Tcp).unwrap_or_default() {
+            if let Some(pid) = socket.pid {
+                pids.entry(socket.port).or_insert(pid);
+            }
+        }
+        pids
+    }
+}
+
+pub(super) fn local_process_commands() -> HashMap<u32, String> {
+    #[cfg(windows)]
+    {
+        windows_process_commands()
+    }
+
+    #[cfg(not(windows))]
+    {
+        sockets::process_commands()
+    }
+}
+
+#[cfg(windows)]
+fn windows_tcp_listen_pids() -> HashMap<u16, u32> {
+    let mut pids = HashMap::new();
+    let output = Command::new("netstat").args(["-ano", "-p", "tcp"]).output();
+    let Ok(output) = output else {
+        return pids;
+    };
+
+    let text = String::from_utf8_lossy(&output.stdout);
+    for line in text.lines() {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if parts.len() < 5 || !parts[0].eq_ignore_ascii_case("TCP") {
+            continue;
+        }
+        if !parts[3].eq_ignore_ascii_case("LISTENING") {
+            continue;
+        }
+        let Some(port) = parse_addr_port(parts[1]) else {
+            continue;
+        };
+        let Ok(pid) = parts[4].parse::<u32>() else {
+            continue;
+        };
+        pids.entry(port).or_insert(pid);
+    }
+    pids
+}
+
+#[cfg(windows)]
+fn parse_addr_port(addr: &str) -> Option<u16> {
+    addr.rsplit_once(':')
+        .and_then(|(_, port)| port.parse::<u16>().ok())
+}
+
+#[cfg(windows)]
+fn windows_process_commands() -> HashMap<u32, String> {
+    let mut commands = HashMap::new();
+    let script = "Get-CimInstance Win32_Process | ForEach-Object { \"$($_.ProcessId)`t$($_.Name)`t$($_.CommandLine)\" }";
+    let output = Command::new("powershell")
+        .args(["-NoProfile", "-Command", script])
+        .output();
+    let Ok(output) = output else {
+        return commands;
+    };
+
+    let text = String::from_utf8_lossy(&output.stdout);
+    for line in text.lines() {
+        let mut parts = line.splitn(3, '\t');
+        let Some(pid_text) = parts.next() else {
+            continue;
+        };
+        let Ok(pid) = pid_text.parse::<u32>() else {
+            continue;
+        };
+        let name = parts.next().unwrap_or("").trim();
+        let command_line = parts.next().unwrap_or("").trim();
+        let command = if command_line.is_empty() {
+            name
+        } else {
+            command_line
+        };
+        if !command.is_empty() {
+            commands.insert(pid, command.to_string());
+        }
+    }
+
+    for (pid, name) in windows_tasklist_names() {
+        commands.entry(pid).or_insert(name);
+    }
+    for (pid, name) in windows_get_process_names() {
+        commands.entry(pid).or_insert(name);
+    }
+    commands
+}
+
+#[cfg(windows)]
+fn windows_tasklist_names() -> HashMap<u32, String> {
+    let mut names = HashMap::new();
+    let output = Command::new("tasklist")
+        .args(["/FO", "CSV", "/NH"])
+        .output();
+    let Ok(output) = output else {
+        return names;
+    };
+
+    let text = String::from_utf8_lossy(&output.stdout);
+    for line in text.lines() {
+        let fields = parse_csv_line(line);
+        if fields.len() < 2 {
+            continue;
+        }
+        let Ok(pid) = fields[1].parse::<u32>() else {
+            continue;
+        };
+        if !fields[0].trim().is_empty() {
+            names.insert(pid, fields[0].trim().to_string());
+        }
+    }
+    names
+}
+
+#[cfg(windows)]
+fn parse_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut current = String::new();
+    let mut chars = line.chars().peekable();
+    let mut in_quotes = false;
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                current.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => {
+                fields.push(current);
+                current = String::new();
+            }
+            _ => current.push(ch),
+        }
+    }
+    fields.push(current);
+    fields
+}
+
+#[cfg(windows)]
+fn windows_get_process_names() -> HashMap<u32, String> {
+    let mut names = HashMap::new();
+    let script = "Get-Process | ForEach-Object { \"$($_.Id)`t$($_.ProcessName)`t$($_.Path)\" }";
+    let output = Command::new("powershell")
+        .args(["-NoProfile", "-Command", script])
+        .output();
+    let Ok(output) = output else {
+        return names;
+    };
+
+    let text = String::from_utf8_lossy(&output.stdout);
+    for line in text.lines() {
+        let mut parts = line.splitn(3, '\t');
+        let Some(pid_text) = parts.next() else {
+            continue;
+        };
+        let Ok(pid) = pid_text.parse::<u32>() else {
+            continue;
+        };
+        let name = parts.next().unwrap_or("").trim();
+        let path = parts.next().unwrap_or("").trim();
+        let command = if path.is_empty() { name } else { path };
+        if !command.is_empty() {
+            names.insert(pid, command.to_string());
+        }
+    }
+    names
+}
@@ -0,0 +1,853 @@
+use std::io::{self, Write};
+use std::net::IpAddr;
+
+use chrono::{DateTime, Local};
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::utils::a11y;
+use crate::utils::output::RecordOutput;
+use crate::utils::tls::TlsCertInfo;
+
+use super::probe::HttpInfo;
+use super::targets::host_port;
+use super::{
+    HostSummary, PortScanError, PortScanResult, PortState, PortStatus, ScanProtocol, ScanType,
+};
+
+// 扫描的命令行和起止时间，nmap 格式的头尾需要
+pub(super) struct ScanRun {
+    pub(super) args: String,
+    pub(super) started: DateTime<Local>,
+    pub(super) finished: DateTime<Local>,
+}
+
+pub(super) fn write_result<W: Write>(
+    result: &PortScanResult,
+    run: &ScanRun,
+    format: OutputFormat,
+    output: &RecordOutput,
+    out: &mut W,
+) -> Result<(), PortScanError> {
+    let records = || -> Vec<PortRecord> {
+        result
+            .ports
+            .iter()
+            .map(|status| PortRecord::new(status, result.protocol))
+            .collect()
+    };
+    match format {
+        OutputFormat::Json => output.write_json(&records(), &mut *out)?,
+        OutputFormat::Csv if output.is_shaped() => output
+            .write_csv(&records(), &mut *out)
+            .map_err(io::Error::from)?,
+        OutputFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(&mut *out);
+            let mut header = vec!["host"];
+            if !result.resolved.is_empty() {
+                header.push("hostname");
+            }
+            if result.reverse_dns {
+                header.push("rdns");
+            }
+            header.extend(["port", "protocol", "state", "latency_ms", "service"]);
+            if result.service_detect {
+                header.extend(["version", "banner"]);
+            }
+            if result.tls_probe {
+                header.extend(["tls_subject", "tls_issuer", "tls_sans", "tls_not_after"]);
+            }
+            if result.http_probe {
+                header.extend(["http_scheme", "http_status", "http_server", "http_title"]);
+            }
+            let local = result.scan_type == ScanType::Local;
+            if local {
+                header.extend(["pid", "command"]);
+            }
+            writer.write_record(&header).map_err(io::Error::from)?;
+            for status in &result.ports {
+                let mut record = vec![status.host.clone()];
+                if !result.resolved.is_empty() {
+                    record.push(status.hostname.clone().unwrap_or_default());
+                }
+                if result.reverse_dns {
+                    record.push(status.rdns.clone().unwrap_or_default());
+                }
+                record.extend([
+                    status.port.to_string(),
+                    result.protocol.as_str().to_string(),
+                    status.state.as_str().to_string(),
+                    status
+                        .latency_ms
+                        .map(|ms| ms.to_string())
+                        .unwrap_or_default(),
+                    status.service.clone().unwrap_or_default(),
+                ]);
+                if result.service_detect {
+                    record.push(status.version.clone().unwrap_or_default());
+                    record.push(status.banner.clone().unwrap_or_default());
+                }
+                if result.tls_probe {
+                    let tls = status.tls.as_ref();
+                    record.push(tls.map(|tls| tls.subject.clone()).unwrap_or_default());
+                    record.push(tls.map(|tls| tls.issuer.clone()).unwrap_or_default());
+                    record.push(tls.map(|tls| tls.sans.join(";")).unwrap_or_default());
+                    record.push(tls.map(|tls| tls.not_after.clone()).unwrap_or_default());
+                }
+                if result.http_probe {
+                    let http = status.http.as_ref();
+                    record.push(http.map(|http| http.scheme.to_string()).unwrap_or_default());
+                    record.push(http.map(|http| http.status.to_string()).unwrap_or_default());
+                    record.push(
+                        http.and_then(|http| http.server.clone())
+                            .unwrap_or_default(),
+                    );
+                    record.push(http.and_then(|http| http.title.clone()).unwrap_or_default());
+                }
+                if local {
+                    record.push(status.pid.map(|pid| pid.to_string()).unwrap_or_default());
+                    record.push(status.command.clone().unwrap_or_default());
+                }
+                writer.write_record(&record).map_err(io::Error::from)?;
+            }
+            writer.flush()?;
+        }
+        OutputFormat::Plain => write_plain(result, out)?,
+        OutputFormat::NmapXml => write_nmap_xml(result, run, out)?,
+        OutputFormat::Grepable => write_grepable(result, run, out)?,
+    }
+    Ok(())
+}
+
+pub(crate) fn write_plain<W: Write>(result: &PortScanResult, out: &mut W) -> io::Result<()> {
+    let mode = match result.scan_type {
+        ScanType::Syn => "syn scan, ",
+        ScanType::Connect => "",
+        ScanType::Local => {
+            writeln!(
+                out,
+                "Listening {} sockets on this host, ports {} (from the OS socket table)",
+                result.protocol.as_str(),
+                result.port_range
+            )?;
+            for host in &result.hosts {
+                writeln!(out, "\nAddress {}", host.host)?;
+                for port in result.ports.iter().filter(|port| port.host == host.host) {
+                    write_port_line(port, out)?;
+                }
+            }
+            writeln!(out, "\nListening sockets: {}", result.total)?;
+            if result.ports.len() < result.total {
+                writeln!(out, "Listed above: {} (filtered)", result.ports.len())?;
+            }
+            if !result.open_ports.is_empty() {
+                writeln!(out, "Listening port list: {:?}", result.open_ports)?;
+            }
+            return Ok(());
+        }
+    };
+    let adaptive = if result.adaptive_timeout {
+        " adaptive"
+    } else {
+        ""
+    };
+    let rate = match result.rate {
+        Some(rate) => format!(", rate={}/s", rate),
+        None => String::new(),
+    };
+    let retries = match result.retries {
+        0 => String::new(),
+        n => format!(", retries={}", n),
+    };
+    let proxy = match (&result.proxy, &result.source) {
+        (Some(proxy), _) => format!(", via {}", proxy),
+        (None, Some(source)) => format!(", from {}", source),
+        (None, None) => String::new(),
+    };
+    if result.hosts.len() == 1 {
+        writeln!(
+            out,
+            "Scanning {} ports {} on {} ({}concurrency={}, timeout={}ms{}{}{}{})",
+            result.target,
+            result.port_range,
+            result.target,
+            mode,
+            result.concurrency,
+            result.timeout_ms,
+            adaptive,
+            rate,
+            retries,
+            proxy
+        )?;
+    } else {
+        writeln!(
+            out,
+            "Scanning ports {} on {} hosts from {} ({}concurrency={}, timeout={}ms{}{}{}{})",
+            result.port_range,
+            result.hosts.len(),
+            result.target,
+            mode,
+            result.concurrency,
+            result.timeout_ms,
+            adaptive,
+            rate,
+            retries,
+            proxy
+        )?;
+    }
+    for resolved in &result.resolved {
+        let addresses: Vec<String> = resolved.addresses.iter().map(IpAddr::to_string).collect();
+        let scanned = if resolved.scanned.len() == resolved.addresses.len() {
+            "all".to_string()
+        } else {
+            let scanned: Vec<String> = resolved.scanned.iter().map(IpAddr::to_string).collect();
+            scanned.join(", ")
+        };
+        writeln!(
+            out,
+            "Resolved {} to {} (scanning {})",
+            resolved.hostname,
+            addresses.join(", "),
+            scanned
+        )?;
+    }
+    if !result.unresolved.is_empty() {
+        writeln!(
+            out,
+            "Could not resolve, skipped: {}",
+            result.unresolved.join(", ")
+        )?;
+    }
+    if result.hosts.len() == 1 {
+        if let Some(rdns) = &result.hosts[0].rdns {
+            writeln!(out, "Reverse DNS: {}", rdns)?;
+        }
+        if let Some(guess) = &result.hosts[0].os_guess {
+            writeln!(out, "OS guess (heuristic): {}", guess.describe())?;
+        }
+        for port in &result.ports {
+            write_port_line(port, out)?;
+        }
+    } else {
+        // 多主机时按主机分组输出
+        for host in &result.hosts {
+            writeln!(out, "\nHost {}", host_label(host))?;
+            if let Some(guess) = &host.os_guess {
+                writeln!(out, "OS guess (heuristic): {}", guess.describe())?;
+            }
+            for port in result.ports.iter().filter(|port| port.host == host.host) {
+                write_port_line(port, out)?;
+            }
+        }
+    }
+    writeln!(out, "\nScan finished.")?;
+    if result.hosts.len() > 1 {
+        writeln!(out, "Hosts scanned: {}", result.hosts.len())?;
+    }
+    writeln!(out, "Total ports scanned: {}", result.total)?;
+    // --show / --where 只筛选上面列出的端口，下面的统计仍覆盖全部扫描结果
+    if result.ports.len() < result.total {
+        writeln!(
+            out,
+            "Listed above: {} of {} (filtered); counts below are before filtering",
+            result.ports.len(),
+            result.total
+        )?;
+    }
+    if result.protocol == ScanProtocol::Udp {
+        writeln!(
+            out,
+            "Open ports: {}  Closed ports: {}  Filtered ports: {}  Open|filtered ports: {}",
+            result.open_count,
+            result.closed_count,
+            result.filtered_count,
+            result.open_filtered_count
+        )?;
+    } else {
+        writeln!(
+            out,
+            "Open ports: {}  Closed ports: {}  Filtered ports: {}",
+            result.open_count, result.closed_count, result.filtered_count
+        )?;
+    }
+    if let Some(latency) = &result.latency {
+        writeln!(
+            out,
+            "Open port latency: min {}  avg {}  max {}",
+            format_ms(latency.min_ms),
+            format_ms(latency.avg_ms),
+            format_ms(latency.max_ms)
+        )?;
+    }
+    if result.hosts.len() > 1 {
+        for host in result.hosts.iter().filter(|host| host.open_count > 0) {
+            if a11y::is_enabled() {
+                let open: Vec<String> = host.open_ports.iter().map(u16::to_string).collect();
+                let line = a11y::line(&[("host", &host_label(host)), ("open", &open.join(", "))]);
+                writeln!(out, "  {}", line)?;
+            } else {
+                writeln!(out, "  {:<39} open {:?}", host_label(host), host.open_ports)?;
+            }
+        }
+    } else if !result.open_ports.is_empty() {
+        if a11y::is_enabled() {
+            let open: Vec<String> = result.open_ports.iter().map(u16::to_string).collect();
+            writeln!(out, "Open port list: {}", open.join(", "))?;
+        } else {
+            writeln!(out, "Open port list: {:?}", result.open_ports)?;
+        }
+    }
+    Ok(())
+}
+
+fn format_ms(ms: f64) -> String {
+    format!("{:.2}ms", ms)
+}
+
+// 多主机输出中的主机标题：地址后附上目标主机名和 PTR 名称
+fn host_label(host: &HostSummary) -> String {
+    let names: Vec<&str> = [host.hostname.as_deref(), host.rdns.as_deref()]
+        .into_iter()
+        .flatten()
+        .collect();
+    if names.is_empty() {
+        host.host.clone()
+    } else {
+        format!("{} ({})", host.host, names.join(", "))
+    }
+}
+
+fn write_port_line<W: Write>(port: &PortStatus, out: &mut W) -> io::Result<()> {
+    // 有服务名时显示为 80/http
+    let label = match port.service.as_deref() {
+        Some(name) => format!("{:>5}/{}", port.port, name),
+        None => format!("{:>5}", port.port),
+    };
+    // 识别出版本时只显示版本，否则显示原始 banner
+    let detail = port
+        .version
+        .as_deref()
+        .or(port.banner.as_deref())
+        .map(|detail| format!(" {}", detail))
+        .unwrap_or_default();
+    match port.state {
+        PortState::Open => {
+            let mut extra = Vec::new();
+            if let Some(ms) = port.latency_ms {
+                extra.push(format!("latency={}", format_ms(ms)));
+            }
+            if let Some(pid) = port.pid {
+                extra.push(format!("pid={}", pid));
+                if let Some(command) = &port.command {
+                    extra.push(format!("command={}", command));
+                }
+            }
+            if extra.is_empty() {
+                writeln!(out, "[OPEN]  Port {} is open{}", label, detail)
+            } else {
+                writeln!(
+                    out,
+                    "[OPEN]  Port {} is open{} ({})",
+                    label,
+                    detail,
+                    extra.join(", ")
+                )
+            }
+        }
+        PortState::Closed => writeln!(out, "[CLOSED] Port {} is closed", label),
+        PortState::OpenFiltered => {
+            writeln!(out, "[OPEN|FILTERED] Port {} no response{}", label, detail)
+        }
+        PortState::Filtered => writeln!(out, "[FILTERED] Port {} did not respond", label),
+    }?;
+    if let Some(tls) = &port.tls {
+        writeln!(out, "        {}  subject: {}", tls.version, tls.subject)?;
+        writeln!(out, "        issuer: {}", tls.issuer)?;
+        if !tls.sans.is_empty() {
+            writeln!(out, "        SAN: {}", tls.sans.join(", "))?;
+        }
+        let expiry = if tls.days_left < 0 {
+            format!("expired {} days ago", -tls.days_left)
+        } else {
+            format!("{} days left", tls.days_left)
+        };
+        writeln!(out, "        expires: {} ({})", tls.not_after, expiry)?;
+    }
+    if let Some(http) = &port.http {
+        let mut line = format!("        {} {}", http.scheme, http.status);
+        if let Some(server) = &http.server {
+            line.push_str(&format!("  server: {}", server));
+        }
+        if let Some(title) = &http.title {
+            line.push_str(&format!("  title: {}", title));
+        }
+        if let Some(location) = &http.location {
+            line.push_str(&format!("  -> {}", location));
+        }
+        writeln!(out, "{}", line)?;
+    }
+    Ok(())
+}
+
+// nmap 的 -oX 格式，只包含 Metasploit db_import、EyeWitness 等工具实际读取的元素。
+// 不做主机发现，扫描的主机一律标记为 up（相当于 nmap -Pn）
+pub(super) fn write_nmap_xml<W: Write>(
+    result: &PortScanResult,
+    run: &ScanRun,
+    out: &mut W,
+) -> io::Result<()> {
+    let scan_type = match (result.protocol, result.scan_type) {
+        (ScanProtocol::Udp, _) => "udp",
+        (_, scan_type) => scan_type.as_str(),
+    };
+    writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(out, "<!DOCTYPE nmaprun>")?;
+    writeln!(
+        out,
+        r#"<nmaprun scanner="rtoolkit" args="{}" start="{}" startstr="{}" version="{}" xmloutputversion="1.05">"#,
+        xml_escape(&run.args),
+        run.started.timestamp(),
+        nmap_time(&run.started),
+        env!("CARGO_PKG_VERSION")
+    )?;
+    writeln!(
+        out,
+        r#"<scaninfo type="{}" protocol="{}" numservices="{}" services="{}"/>"#,
+        scan_type,
+        result.protocol.as_str(),
+        result.total / result.hosts.len().max(1),
+        xml_escape(&result.port_range)
+    )?;
+    for host in &result.hosts {
+        let ports: Vec<&PortStatus> = result
+            .ports
+            .iter()
+            .filter(|port| port.host == host.host)
+            .collect();
+        writeln!(
+            out,
+            r#"<host starttime="{}" endtime="{}"><status state="up" reason="user-set" reason_ttl="0"/>"#,
+            run.started.timestamp(),
+            run.finished.timestamp()
+        )?;
+        let addrtype = if host.host.contains(':') {
+            "ipv6"
+        } else {
+            "ipv4"
+        };
+        writeln!(
+            out,
+            r#"<address addr="{}" addrtype="{}"/>"#,
+            xml_escape(&host.host),
+            addrtype
+        )?;
+        writeln!(out, "<hostnames>")?;
+        if let Some(name) = &host.hostname {
+            writeln!(
+                out,
+                r#"<hostname name="{}" type="user"/>"#,
+                xml_escape(name)
+            )?;
+        }
+        if let Some(name) = &host.rdns {
+            writeln!(out, r#"<hostname name="{}" type="PTR"/>"#, xml_escape(name))?;
+        }
+        writeln!(out, "</hostnames>")?;
+        writeln!(out, "<ports>")?;
+        let ignored = ignored_closed(host, &ports);
+        if ignored > 0 {
+            let reason = port_reason(PortState::Closed, result);
+            writeln!(
+                out,
+                r#"<extraports state="closed" count="{}"><extrareasons reason="{}" count="{}"/></extraports>"#,
+                ignored, reason, ignored
+            )?;
+        }
+        for port in ports {
+            write!(
+                out,
+                r#"<port protocol="{}" portid="{}"><state state="{}" reason="{}" reason_ttl="0"/>"#,
+                result.protocol.as_str(),
+                port.port,
+                port.state.as_str(),
+                port_reason(port.state, result)
+            )?;
+            if let Some(service) = &port.service {
+                // 识别出版本的服务为探测得出，否则只是按端口号查表
+                match &port.version {
+                    Some(version) => write!(
+                        out,
+                        r#"<service name="{}" product="{}" method="probed" conf="10"/>"#,
+                        xml_escape(service),
+                        xml_escape(version)
+                    )?,
+                    None => write!(
+                        out,
+                        r#"<service name="{}" method="table" conf="3"/>"#,
+                        xml_escape(service)
+                    )?,
+                }
+            }
+            writeln!(out, "</port>")?;
+        }
+        writeln!(out, "</ports>")?;
+        // 启发式猜测的可信度低，accuracy 固定取较低的值
+        if let Some(guess) = &host.os_guess {
+            writeln!(
+                out,
+                r#"<os><osmatch name="{}" accuracy="50" line="0"/></os>"#,
+                xml_escape(guess.os)
+            )?;
+        }
+        // nmap 的 times 以微秒为单位：srtt 取开放端口的平均耗时，rttvar 取最大值与平均值之差
+        if let Some(latency) = &host.latency {
+            let micros = |ms: f64| (ms * 1000.0).round() as u64;
+            writeln!(
+                out,
+                r#"<times srtt="{}" rttvar="{}" to="{}"/>"#,
+                micros(latency.avg_ms),
+                micros(latency.max_ms - latency.avg_ms),
+                result.timeout_ms * 1000
+            )?;
+        }
+        writeln!(out, "</host>")?;
+    }
+    let elapsed = elapsed_secs(run);
+    writeln!(
+        out,
+        r#"<runstats><finished time="{}" timestr="{}" elapsed="{:.2}" summary="{}" exit="success"/><hosts up="{}" down="0" total="{}"/></runstats>"#,
+        run.finished.timestamp(),
+        nmap_time(&run.finished),
+        elapsed,
+        xml_escape(&format!(
+            "rtoolkit done at {}; {} scanned in {:.2} seconds",
+            nmap_time(&run.finished),
+            host_count(result.hosts.len()),
+            elapsed
+        )),
+        result.hosts.len(),
+        result.hosts.len()
+    )?;
+    writeln!(out, "</nmaprun>")
+}
+
+// nmap 的 -oG 格式：每个主机一行 Status、一行 Ports，端口字段为
+// 端口/状态/协议/属主/服务/RPC/版本/，字段内的 / 按 nmap 的习惯替换为 |
+fn write_grepable<W: Write>(result: &PortScanResult, run: &ScanRun, out: &mut W) -> io::Result<()> {
+    writeln!(
+        out,
+        "# rtoolkit {} scan initiated {} as: {}",
+        env!("CARGO_PKG_VERSION"),
+        nmap_time(&run.started),
+        run.args
+    )?;
+    for host in &result.hosts {
+        let name = host
+            .hostname
+            .as_deref()
+            .or(host.rdns.as_deref())
+            .unwrap_or_default();
+        writeln!(out, "Host: {} ({})\tStatus: Up", host.host, name)?;
+        let ports: Vec<&PortStatus> = result
+            .ports
+            .iter()
+            .filter(|port| port.host == host.host)
+            .collect();
+        let ignored = ignored_closed(host, &ports);
+        if ports.is_empty() && ignored == 0 {
+            continue;
+        }
+        let fields: Vec<String> = ports
+            .iter()
+            .map(|port| {
+                format!(
+                    "{}/{}/{}//{}//{}/",
+                    port.port,
+                    port.state.as_str(),
+                    result.protocol.as_str(),
+                    port.service
+                        .as_deref()
+                        .unwrap_or_default()
+                        .replace('/', "|"),
+                    port.version
+                        .as_deref()
+                        .unwrap_or_default()
+                        .replace('/', "|")
+                )
+            })
+            .collect();
+        write!(
+            out,
+            "Host: {} ({})\tPorts: {}",
+            host.host,
+            name,
+            fields.join(", ")
+        )?;
+        if ignored > 0 {
+            write!(out, "\tIgnored State: closed ({})", ignored)?;
+        }
+        if let Some(guess) = &host.os_guess {
+            write!(out, "\tOS: {} (guess)", guess.os)?;
+        }
+        // nmap 的端口字段没有耗时，单独附加一节，按名称取字段的解析工具会忽略它
+        let latencies: Vec<String> = ports
+            .iter()
+            .filter(|port| port.open)
+            .filter_map(|port| Some(format!("{}={}", port.port, format_ms(port.latency_ms?))))
+            .collect();
+        if !latencies.is_empty() {
+            write!(out, "\tLatency: {}", latencies.join(", "))?;
+        }
+        writeln!(out)?;
+    }
+    writeln!(
+        out,
+        "# rtoolkit done at {} -- {} scanned in {:.2} seconds",
+        nmap_time(&run.finished),
+        host_count(result.hosts.len()),
+        elapsed_secs(run)
+    )
+}
+
+// 主机的关闭端口中没有逐个列出的数量，对应 nmap 的 extraports / Ignored State
+fn ignored_closed(host: &HostSummary, listed: &[&PortStatus]) -> usize {
+    let listed_closed = listed
+        .iter()
+        .filter(|port| port.state == PortState::Closed)
+        .count();
+    host.closed_count.saturating_sub(listed_closed)
+}
+
+// 与 nmap 的 reason 字段取值一致
+fn port_reason(state: PortState, result: &PortScanResult) -> &'static str {
+    match (state, result.protocol, result.scan_type) {
+        (PortState::Open, ScanProtocol::Udp, _) => "udp-response",
+        (PortState::Open, _, ScanType::Local) => "localhost-response",
+        (PortState::Open, _, _) => "syn-ack",
+        (PortState::Closed, ScanProtocol::Udp, _) => "port-unreach",
+        (PortState::Closed, _, ScanType::Syn) => "reset",
+        (PortState::Closed, _, ScanType::Connect | ScanType::Local) => "conn-refused",
+        (PortState::Filtered, ScanProtocol::Udp, _) => "host-unreach",
+        (PortState::Filtered | PortState::OpenFiltered, _, _) => "no-response",
+    }
+}
+
+pub(super) fn host_count(hosts: usize) -> String {
+    match hosts {
+        1 => "1 IP address (1 host up)".to_string(),
+        n => format!("{} IP addresses ({} hosts up)", n, n),
+    }
+}
+
+fn nmap_time(time: &DateTime<Local>) -> String {
+    time.format("%a %b %e %H:%M:%S %Y").to_string()
+}
+
+fn elapsed_secs(run: &ScanRun) -> f64 {
+    (run.finished - run.started).num_milliseconds() as f64 / 1000.0
+}
+
+fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // XML 1.0 不允许大部分控制字符，banner 里偶尔会出现
+            ch if ch.is_control() && !matches!(ch, '\t' | '\n' | '\r') => escaped.push('?'),
+            ch => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub(super) enum OutputFormat {
+    Plain,
+    Json,
+    Csv,
+    NmapXml,
+    Grepable,
+}
+
+impl OutputFormat {
+    // 未指定 --show 时不逐个列出关闭的端口，nmap 格式与 nmap 一样只给出数量
+    pub(super) fn omits_closed(self) -> bool {
+        matches!(
+            self,
+            OutputFormat::Plain | OutputFormat::NmapXml | OutputFormat::Grepable
+        )
+    }
+}
+
+// CLI 结构化输出的单条记录
+#[derive(Debug, Serialize)]
+pub(super) struct PortRecord<'a> {
+    host: &'a str,
+    // 可直接用于连接的 host:port，IPv6 带方括号
+    pub(super) address: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hostname: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rdns: Option<&'a str>,
+    port: u16,
+    pub(super) protocol: ScanProtocol,
+    state: PortState,
+    latency_ms: Option<f64>,
+    pub(super) service: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    banner: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tls: Option<&'a TlsCertInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    http: Option<&'a HttpInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pid: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    command: Option<&'a str>,
+}
+
+impl<'a> PortRecord<'a> {
+    pub(super) fn new(status: &'a PortStatus, protocol: ScanProtocol) -> Self {
+        Self {
+            host: &status.host,
+            address: host_port(&status.host, status.port),
+            hostname: status.hostname.as_deref(),
+            rdns: status.rdns.as_deref(),
+            port: status.port,
+            protocol,
+            state: status.state,
+            latency_ms: status.latency_ms,
+            service: status.service.as_deref(),
+            version: status.version.as_deref(),
+            banner: status.banner.as_deref(),
+            tls: status.tls.as_ref(),
+            http: status.http.as_ref(),
+            pid: status.pid,
+            command: status.command.as_deref(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::portscan::engine::port_status;
+    use crate::commands::portscan::{remote_scan, LatencyStats, ScanSettings};
+
+    #[test]
+    fn test_port_lines_show_service_names() {
+        let line = |status: &PortStatus| {
+            let mut out = Vec::new();
+            write_port_line(status, &mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        let mut status = port_status(
+            "10.0.0.1".into(),
+            3306,
+            PortState::Open,
+            None,
+            ScanProtocol::Tcp,
+        );
+        assert_eq!(status.service.as_deref(), Some("mysql"));
+        assert_eq!(line(&status), "[OPEN]  Port  3306/mysql is open\n");
+        status.service = None;
+        assert_eq!(line(&status), "[OPEN]  Port  3306 is open\n");
+        status.latency_ms = Some(1.5);
+        status.pid = Some(42);
+        assert_eq!(
+            line(&status),
+            "[OPEN]  Port  3306 is open (latency=1.50ms, pid=42)\n"
+        );
+
+        let sample = |state, latency_ms| {
+            port_status("10.0.0.1".into(), 80, state, latency_ms, ScanProtocol::Tcp)
+        };
+        let fast = sample(PortState::Open, Some(0.25));
+        let closed = sample(PortState::Closed, Some(0.01));
+        assert_eq!(
+            LatencyStats::of_open([&status, &fast, &closed]),
+            Some(LatencyStats {
+                min_ms: 0.25,
+                avg_ms: 0.88,
+                max_ms: 1.5,
+            })
+        );
+        assert_eq!(LatencyStats::of_open([&closed]), None);
+
+        let status = port_status(
+            "10.0.0.1".into(),
+            123,
+            PortState::OpenFiltered,
+            None,
+            ScanProtocol::Udp,
+        );
+        assert_eq!(
+            line(&status),
+            "[OPEN|FILTERED] Port   123/ntp no response\n"
+        );
+    }
+
+    #[test]
+    fn test_nmap_xml_and_grepable_output() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let open = listener.local_addr().unwrap().port();
+        let closed = {
+            let probe = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            probe.local_addr().unwrap().port()
+        };
+        let hosts = vec!["127.0.0.1".to_string()];
+        let mut result = rt
+            .block_on(remote_scan(
+                "127.0.0.1",
+                &hosts,
+                "custom",
+                &[open, closed],
+                &ScanSettings::default(),
+            ))
+            .unwrap();
+        // 与 CLI 默认一致：关闭的端口只计数
+        result.ports.retain(|port| port.state != PortState::Closed);
+        let started = Local::now();
+        let run = ScanRun {
+            args: "rtoolkit port-scan -o nmap-xml --where 'a<b'".into(),
+            started,
+            finished: started + chrono::Duration::milliseconds(1500),
+        };
+
+        let mut xml = Vec::new();
+        write_nmap_xml(&result, &run, &mut xml).unwrap();
+        let xml = String::from_utf8(xml).unwrap();
+        assert!(xml.contains(r#"args="rtoolkit port-scan -o nmap-xml --where &apos;a&lt;b&apos;""#));
+        assert!(xml.contains(
+            r#"<scaninfo type="connect" protocol="tcp" numservices="2" services="custom"/>"#
+        ));
+        assert!(xml.contains(r#"<address addr="127.0.0.1" addrtype="ipv4"/>"#));
+        assert!(xml.contains(r#"<extraports state="closed" count="1"><extrareasons reason="conn-refused" count="1"/></extraports>"#));
+        assert!(xml.contains(&format!(
+            r#"<port protocol="tcp" portid="{}"><state state="open" reason="syn-ack" reason_ttl="0"/>"#,
+            open
+        )));
+        assert!(xml.contains(r#"<times srtt=""#));
+        assert!(xml.contains(r#"elapsed="1.50""#));
+        assert!(xml.trim_end().ends_with("</nmaprun>"));
+
+        let mut grepable = Vec::new();
+        write_grepable(&result, &run, &mut grepable).unwrap();
+        let grepable = String::from_utf8(grepable).unwrap();
+        let lines: Vec<&str> = grepable.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[1], "Host: 127.0.0.1 ()\tStatus: Up");
+        assert!(lines[2].starts_with(&format!("Host: 127.0.0.1 ()\tPorts: {}/open/tcp//", open)));
+        assert!(lines[2].contains(&format!("\tIgnored State: closed (1)\tLatency: {}=", open)));
+        assert!(lines[2].ends_with("ms"));
+        assert!(lines[3].ends_with("1 IP address (1 host up) scanned in 1.50 seconds"));
+        drop(listener);
+    }
+}
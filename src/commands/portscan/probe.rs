@@ -0,0 +1,818 @@
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Instant;
+
+use futures::stream::StreamExt;
+use serde::Serialize;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::time::{timeout, Duration};
+use tokio_rustls::rustls::pki_types::ServerName;
+
+use crate::commands::snmp::{encode_request, Oid, SnmpVersion, PDU_GET};
+use crate::utils::fdlimit;
+use crate::utils::http::{self, Url};
+use crate::utils::metrics;
+use crate::utils::osguess::{self, OsGuess};
+use crate::utils::proxy::{Proxy, ProxyError, TargetError};
+use crate::utils::services;
+use crate::utils::tls::{self, TlsCertInfo};
+
+use super::engine::{OS_GUESS_CONCURRENCY, PROXY_HANDSHAKE_TIMEOUT};
+use super::targets::target_addr;
+use super::{AddressFamily, HostSummary, PortScanError, PortState, ScanProtocol, ScanSettings};
+
+// 多网卡或实验环境下指定探测流量的出口：--source-ip 绑定源地址，--interface 绑定网卡（SO_BINDTODEVICE）
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceBind {
+    pub ip: Option<IpAddr>,
+    pub interface: Option<String>,
+}
+
+impl SourceBind {
+    pub fn is_set(&self) -> bool {
+        self.ip.is_some() || self.interface.is_some()
+    }
+
+    // 扫描前确认源地址属于本机、网卡存在且有权限绑定，否则每个端口都会被误判为 filtered
+    pub(super) fn check(&self) -> Result<(), PortScanError> {
+        if let Some(ip) = self.ip {
+            std::net::UdpSocket::bind((ip, 0))
+                .map_err(|err| PortScanError::SourceAddress(ip, err))?;
+        }
+        let Some(name) = &self.interface else {
+            return Ok(());
+        };
+        if !cfg!(target_os = "linux") {
+            return Err(PortScanError::InterfaceUnsupported);
+        }
+        let socket = std::net::UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))?;
+        bind_device(&socket, Some(name)).map_err(|err| match err.kind() {
+            io::ErrorKind::PermissionDenied => PortScanError::InterfacePermission(name.clone()),
+            _ => PortScanError::Interface(name.clone(), err),
+        })
+    }
+
+    // 主机名解析出多个地址时选与源地址同族的一个
+    fn pick_addr(&self, addrs: impl Iterator<Item = SocketAddr>) -> io::Result<SocketAddr> {
+        let family = self
+            .ip
+            .as_ref()
+            .map_or(AddressFamily::Any, AddressFamily::of);
+        addrs
+            .into_iter()
+            .find(|addr| family.accepts(&addr.ip()))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "host has no address"))
+    }
+
+    fn tcp_socket(&self, target: SocketAddr) -> io::Result<tokio::net::TcpSocket> {
+        let socket = if target.is_ipv4() {
+            tokio::net::TcpSocket::new_v4()?
+        } else {
+            tokio::net::TcpSocket::new_v6()?
+        };
+        bind_device(&socket, self.interface.as_deref())?;
+        if let Some(ip) = self.ip {
+            socket.bind((ip, 0).into())?;
+        }
+        Ok(socket)
+    }
+
+    async fn udp_socket(&self, target: SocketAddr) -> io::Result<tokio::net::UdpSocket> {
+        let ip = self.ip.unwrap_or(match target {
+            SocketAddr::V4(_) => Ipv4Addr::UNSPECIFIED.into(),
+            SocketAddr::V6(_) => Ipv6Addr::UNSPECIFIED.into(),
+        });
+        let socket = tokio::net::UdpSocket::bind((ip, 0)).await?;
+        bind_device(&socket, self.interface.as_deref())?;
+        Ok(socket)
+    }
+
+    // --os-guess 调用系统 ping 时同样从指定的网卡或地址发出
+    fn ping_args(&self) -> Vec<String> {
+        let flag = if cfg!(target_os = "linux") {
+            "-I"
+        } else {
+            "-S"
+        };
+        match (&self.interface, self.ip) {
+            (Some(name), _) if cfg!(target_os = "linux") => vec![flag.to_string(), name.clone()],
+            (_, Some(ip)) => vec![flag.to_string(), ip.to_string()],
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl std::fmt::Display for SourceBind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.ip, &self.interface) {
+            (Some(ip), Some(name)) => write!(f, "{} on {}", ip, name),
+            (Some(ip), None) => write!(f, "{}", ip),
+            (None, Some(name)) => write!(f, "{}", name),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub(super) fn bind_device<S: std::os::fd::AsFd>(
+    socket: &S,
+    interface: Option<&str>,
+) -> io::Result<()> {
+    match interface {
+        Some(name) => socket2::SockRef::from(socket).bind_device(Some(name.as_bytes())),
+        None => Ok(()),
+    }
+}
+
+// 其他平台在 SourceBind::check 中已拒绝 --interface
+#[cfg(not(target_os = "linux"))]
+fn bind_device<S>(_socket: &S, _interface: Option<&str>) -> io::Result<()> {
+    Ok(())
+}
+
+// 连接扫描目标：IP 字面量（包括带区域 ID 的 IPv6）直接连接，主机名交给系统解析；
+// 指定代理时经代理连接，指定源地址或网卡时先绑定再连接
+async fn connect_target(
+    host: &str,
+    port: u16,
+    proxy: Option<&Proxy>,
+    source: &SourceBind,
+) -> io::Result<tokio::net::TcpStream> {
+    if let Some(proxy) = proxy {
+        return proxy.connect(host, port).await.map_err(io::Error::other);
+    }
+    if !source.is_set() {
+        return match target_addr(host, port) {
+            Some(addr) => tokio::net::TcpStream::connect(addr).await,
+            None => tokio::net::TcpStream::connect((host, port)).await,
+        };
+    }
+    let addr = match target_addr(host, port) {
+        Some(addr) => addr,
+        None => source.pick_addr(tokio::net::lookup_host((host, port)).await?)?,
+    };
+    source.tcp_socket(addr)?.connect(addr).await
+}
+
+// 返回 Err 表示本机资源耗尽（EMFILE 等），探测没有发出，由调用方降低并发后重试
+pub(super) async fn probe_tcp(
+    host: &str,
+    port: u16,
+    to: Duration,
+    source: &SourceBind,
+) -> io::Result<PortState> {
+    metrics::probe_sent(false);
+    match timeout(to, connect_target(host, port, None, source)).await {
+        Ok(Ok(_)) => Ok(PortState::Open),
+        Ok(Err(e)) if e.kind() == io::ErrorKind::ConnectionRefused => Ok(PortState::Closed),
+        Ok(Err(e)) if fdlimit::is_exhausted(&e) => Err(e),
+        _ => Ok(PortState::Filtered),
+    }
+}
+
+// 与代理的握手单独限时，to 只用于代理连接目标的阶段，返回结果和发出连接请求的时刻。
+// 代理握手失败算作没有响应，可由 --retries 重试；目标拒绝连接为 closed，其余失败为 filtered
+pub(super) async fn probe_proxy(
+    proxy: &Proxy,
+    host: &str,
+    port: u16,
+    to: Duration,
+) -> (PortState, Instant) {
+    metrics::probe_sent(false);
+    let stream = match timeout(PROXY_HANDSHAKE_TIMEOUT, proxy.handshake()).await {
+        Ok(Ok(stream)) => stream,
+        _ => return (PortState::Filtered, Instant::now()),
+    };
+    let started = Instant::now();
+    let state = match timeout(to, proxy.request(stream, host, port)).await {
+        Ok(Ok(_)) => PortState::Open,
+        Ok(Err(ProxyError::Target(TargetError::Refused))) => PortState::Closed,
+        _ => PortState::Filtered,
+    };
+    (state, started)
+}
+
+// UDP 无握手：发送探测报文后收到任何回包即为 open；收到 ICMP 端口不可达时
+// 已连接的 socket 会返回 ConnectionRefused，即 closed；主机/网络不可达视为 filtered；
+// 超时没有任何响应只能判为 open|filtered
+pub(super) async fn probe_udp(
+    host: &str,
+    port: u16,
+    to: Duration,
+    source: &SourceBind,
+) -> io::Result<PortState> {
+    metrics::probe_sent(true);
+    let probe = async {
+        let addr = match target_addr(host, port) {
+            Some(addr) => addr,
+            None => source.pick_addr(tokio::net::lookup_host((host, port)).await?)?,
+        };
+        let socket = source.udp_socket(addr).await?;
+        socket.connect(addr).await?;
+        socket.send(&udp_payload(port)).await?;
+        let mut buf = [0u8; 1500];
+        socket.recv(&mut buf).await
+    };
+    match timeout(to, probe).await {
+        Ok(Ok(_)) => Ok(PortState::Open),
+        Ok(Err(e)) if e.kind() == io::ErrorKind::ConnectionRefused => Ok(PortState::Closed),
+        Ok(Err(e)) if fdlimit::is_exhausted(&e) => Err(e),
+        Ok(Err(_)) => Ok(PortState::Filtered),
+        Err(_) => Ok(PortState::OpenFiltered),
+    }
+}
+
+// 常见 UDP 服务只会回应合法请求，空报文大多被直接丢弃，因此按端口构造协议探测包
+fn udp_payload(port: u16) -> Vec<u8> {
+    match port {
+        // DNS：查询根域 NS 记录
+        53 | 5353 => vec![
+            0x12, 0x34, 0x01, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x02, 0x00, 0x01,
+        ],
+        // NTP：v3 客户端模式请求
+        123 => {
+            let mut packet = vec![0u8; 48];
+            packet[0] = 0x1b;
+            packet
+        }
+        // NetBIOS：NBSTAT 通配名称查询
+        137 => {
+            let mut packet = vec![
+                0x80, 0xf0, 0x00, 0x10, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x20, 0x43,
+                0x4b,
+            ];
+            packet.extend(std::iter::repeat_n(0x41, 30));
+            packet.extend([0x00, 0x00, 0x21, 0x00, 0x01]);
+            packet
+        }
+        // SNMP：v2c public 团体名读取 sysDescr.0
+        161 => encode_request(
+            SnmpVersion::V2c,
+            "public",
+            PDU_GET,
+            0x5343,
+            0,
+            0,
+            &[Oid(vec![1, 3, 6, 1, 2, 1, 1, 1, 0])],
+        ),
+        // SSDP：M-SEARCH 发现请求
+        1900 => b"M-SEARCH * HTTP/1.1\r\nHOST: 239.255.255.250:1900\r\nMAN: \"ssdp:discover\"\r\nMX: 1\r\nST: ssdp:all\r\n\r\n".to_vec(),
+        // memcached UDP 帧头 + stats
+        11211 => b"\x00\x01\x00\x00\x00\x01\x00\x00stats\r\n".to_vec(),
+        _ => Vec::new(),
+    }
+}
+
+// 服务识别时单次读取的最大字节数
+const BANNER_READ_LIMIT: usize = 2048;
+// banner 展示时的最大字符数
+const BANNER_MAX_CHARS: usize = 120;
+
+// 每个主机 ping 一次取 TTL；TCP 扫描时再连接第一个开放端口，读取握手中对端的窗口
+pub(super) async fn guess_os(
+    hosts: &mut [HostSummary],
+    protocol: ScanProtocol,
+    settings: &ScanSettings,
+) {
+    let wait = Duration::from_millis(settings.timeout_ms.clamp(1000, 3000));
+    let source = &settings.source;
+    let ping_args = &source.ping_args();
+    // 与 reverse_lookup 相同，先收集成 Vec 再交给 buffered
+    let probes: Vec<_> = hosts
+        .iter()
+        .map(|host| {
+            let port = host
+                .open_ports
+                .first()
+                .copied()
+                .filter(|_| protocol == ScanProtocol::Tcp);
+            async move {
+                let window = async {
+                    let stream =
+                        tokio::time::timeout(wait, connect_target(&host.host, port?, None, source))
+                            .await
+                            .ok()?
+                            .ok()?;
+                    osguess::peer_window(&stream)
+                };
+                let (ttl, window) =
+                    tokio::join!(osguess::ping_ttl(&host.host, wait, ping_args), window);
+                osguess::guess(ttl, window)
+            }
+        })
+        .collect();
+    let guesses: Vec<Option<OsGuess>> = futures::stream::iter(probes)
+        .buffered(settings.concurrency.min(OS_GUESS_CONCURRENCY))
+        .collect()
+        .await;
+    for (host, guess) in hosts.iter_mut().zip(guesses) {
+        host.os_guess = guess;
+    }
+}
+
+// 先被动等待服务端主动发送的 banner（SSH、SMTP、FTP、MySQL 等），
+// 没有数据时再按端口发送最小探测请求
+pub(super) async fn grab_banner(
+    host: &str,
+    port: u16,
+    wait: Duration,
+    proxy: Option<&Proxy>,
+    source: &SourceBind,
+) -> Option<Vec<u8>> {
+    let mut stream = timeout(wait, connect_target(host, port, proxy, source))
+        .await
+        .ok()?
+        .ok()?;
+    let mut buf = vec![0u8; BANNER_READ_LIMIT];
+    match timeout(wait, stream.read(&mut buf)).await {
+        Ok(Ok(0)) | Ok(Err(_)) => return None,
+        Ok(Ok(n)) => {
+            buf.truncate(n);
+            return Some(buf);
+        }
+        Err(_) => {}
+    }
+    stream.write_all(service_probe(port)).await.ok()?;
+    match timeout(wait, stream.read(&mut buf)).await {
+        Ok(Ok(n)) if n > 0 => {
+            buf.truncate(n);
+            Some(buf)
+        }
+        _ => None,
+    }
+}
+
+fn service_probe(port: u16) -> &'static [u8] {
+    match port {
+        6379 | 6380 => b"PING\r\n",
+        // 其它端口默认按 HTTP 探测，非 HTTP 服务通常也会回一条错误信息
+        _ => b"HEAD / HTTP/1.0\r\n\r\n",
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(super) struct ServiceInfo {
+    pub(super) name: Option<&'static str>,
+    pub(super) version: Option<String>,
+    pub(super) banner: Option<String>,
+}
+
+pub(super) fn identify_service(data: &[u8]) -> ServiceInfo {
+    let text = String::from_utf8_lossy(data);
+    let first_line = clean_banner(text.lines().next().unwrap_or_default());
+    let mut info = ServiceInfo {
+        banner: first_line.clone(),
+        ..ServiceInfo::default()
+    };
+
+    if let Some(rest) = text.strip_prefix("SSH-") {
+        // SSH-2.0-OpenSSH_8.9p1 Ubuntu-3ubuntu0.6
+        info.name = Some("ssh");
+        info.version = rest
+            .split_once('-')
+            .and_then(|(_, software)| software.split_whitespace().next())
+            .and_then(clean_banner);
+    } else if text.starts_with("HTTP/") {
+        info.name = Some("http");
+        info.version = text
+            .lines()
+            .take_while(|line| !line.is_empty())
+            .find_map(|line| {
+                let (key, value) = line.split_once(':')?;
+                key.eq_ignore_ascii_case("server")
+                    .then(|| clean_banner(value))
+                    .flatten()
+            });
+    } else if text.starts_with("220") && text.to_ascii_uppercase().contains("SMTP") {
+        // 220 mx.example.com ESMTP Postfix (Ubuntu)
+        info.name = Some("smtp");
+        let line = first_line.as_deref().unwrap_or_default();
+        info.version = line
+            .split_once("ESMTP ")
+            .or_else(|| line.split_once("SMTP "))
+            .and_then(|(_, software)| clean_banner(software));
+    } else if text.starts_with("220") && text.to_ascii_uppercase().contains("FTP") {
+        // 220 (vsFTPd 3.0.5)
+        info.name = Some("ftp");
+        let line = first_line.as_deref().unwrap_or_default();
+        info.version = line
+            .split_once('(')
+            .and_then(|(_, rest)| rest.split_once(')'))
+            .and_then(|(software, _)| clean_banner(software));
+    } else if text.starts_with("+PONG")
+        || text.starts_with("-NOAUTH")
+        || text.starts_with("-DENIED")
+        || text.starts_with("-ERR unknown command")
+    {
+        info.name = Some("redis");
+    } else if is_mysql_handshake(data) {
+        // MySQL 握手包：3 字节长度 + 序号 + 协议版本 10 + 以 0 结尾的服务端版本
+        let version: Vec<u8> = data[5..].iter().copied().take_while(|&b| b != 0).collect();
+        if let Some(version) = clean_banner(&String::from_utf8_lossy(&version)) {
+            info.name = Some("mysql");
+            info.banner = Some(version.clone());
+            info.version = Some(version);
+        }
+    }
+    info
+}
+
+// 包头的小端长度必须与实际负载一致且序号为 0，避免把第 5 个字节恰好是换行的文本误判为 MySQL
+pub(crate) fn is_mysql_handshake(data: &[u8]) -> bool {
+    if data.len() <= 5 || data[3] != 0 || data[4] != 0x0a {
+        return false;
+    }
+    let len = usize::from(data[0]) | usize::from(data[1]) << 8 | usize::from(data[2]) << 16;
+    len == data.len() - 4
+}
+
+// 去掉不可打印字符并截断，纯二进制数据返回 None
+fn clean_banner(raw: &str) -> Option<String> {
+    let cleaned: String = raw
+        .chars()
+        .filter(|c| !c.is_control())
+        .take(BANNER_MAX_CHARS)
+        .collect();
+    let cleaned = cleaned.trim();
+    let printable = cleaned
+        .chars()
+        .filter(|c| c.is_alphanumeric() || c.is_ascii_punctuation() || *c == ' ')
+        .count();
+    (!cleaned.is_empty() && printable * 2 >= cleaned.chars().count()).then(|| cleaned.to_string())
+}
+
+// 以 IP 作为 ServerName，rustls 不会发送 SNI，拿到的是服务端的默认证书；
+// 经代理且由代理解析主机名时只有主机名可用，此时会以主机名发送 SNI
+pub(super) async fn probe_tls(
+    connector: &tokio_rustls::TlsConnector,
+    host: &str,
+    port: u16,
+    wait: Duration,
+    proxy: Option<&Proxy>,
+    source: &SourceBind,
+) -> Option<TlsCertInfo> {
+    let handshake = async {
+        let server_name = match target_addr(host, port) {
+            Some(addr) => ServerName::IpAddress(addr.ip().into()),
+            None => ServerName::try_from(host.to_string()).ok()?,
+        };
+        let stream = connect_target(host, port, proxy, source).await.ok()?;
+        tls::peer_certificate(connector, server_name, stream).await
+    };
+    timeout(wait, handshake).await.ok()?
+}
+
+// --http-probe 的结果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HttpInfo {
+    // 实际得到响应的协议，http 或 https
+    pub scheme: &'static str,
+    pub status: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub server: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    // 3xx 响应的跳转地址
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+}
+
+impl HttpInfo {
+    fn new(scheme: &'static str, response: &http::Response) -> Self {
+        Self {
+            scheme,
+            status: response.status,
+            server: response.header("Server").and_then(clean_banner),
+            title: html_title(&response.body),
+            location: (300..400)
+                .contains(&response.status)
+                .then(|| response.header("Location").and_then(clean_banner))
+                .flatten(),
+        }
+    }
+}
+
+// 标题一般在页面开头，读取的响应上限（含响应头）
+const HTTP_READ_LIMIT: usize = 64 * 1024;
+
+// host 用于 Host 头和 SNI，目标以主机名给出时为主机名，以便命中虚拟主机；连接仍然发往 addr。
+// 按端口号注册为 https 的端口先走 TLS，其余端口先发明文请求，得不到 HTTP 响应时再换另一种，
+// 每次尝试各自限时 wait
+pub(super) async fn probe_http(
+    connector: &tokio_rustls::TlsConnector,
+    host: &str,
+    addr: &str,
+    port: u16,
+    wait: Duration,
+    proxy: Option<&Proxy>,
+    source: &SourceBind,
+) -> Option<HttpInfo> {
+    let fetch = |https: bool| async move {
+        let url = Url {
+            https,
+            host: host.to_string(),
+            port,
+            path: "/".to_string(),
+        };
+        let stream = connect_target(addr, port, proxy, source).await.ok()?;
+        let response = if https {
+            let server_name = ServerName::try_from(host.to_string()).ok()?;
+            let stream = connector.connect(server_name, stream).await.ok()?;
+            http::get_on(stream, &url, HTTP_READ_LIMIT).await
+        } else {
+            http::get_on(stream, &url, HTTP_READ_LIMIT).await
+        };
+        let scheme = if https { "https" } else { "http" };
+        response
+            .ok()
+            .map(|response| HttpInfo::new(scheme, &response))
+    };
+    let tls_first =
+        services::service_name(port, false).is_some_and(|name| name.starts_with("https"));
+    for https in [tls_first, !tls_first] {
+        if let Ok(Some(info)) = timeout(wait, fetch(https)).await {
+            return Some(info);
+        }
+    }
+    None
+}
+
+// 取 <title> 的内容，解码常见的实体并合并空白
+fn html_title(body: &[u8]) -> Option<String> {
+    let text = String::from_utf8_lossy(body);
+    // ASCII 小写不改变字节偏移，可以直接用来定位原文
+    let lower = text.to_ascii_lowercase();
+    let open = lower.find("<title")?;
+    let start = open + lower[open..].find('>')? + 1;
+    let end = start + lower[start..].find("</title")?;
+    let title = text[start..end]
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&");
+    clean_banner(&title.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::portscan::remote_scan;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_source_bind() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let mut settings = ScanSettings::default();
+            settings.source.ip = Some("192.0.2.1".parse().unwrap());
+            let err = remote_scan("x", &["127.0.0.1".into()], "80", &[80], &settings)
+                .await
+                .unwrap_err();
+            assert!(matches!(err, PortScanError::SourceAddress(..)));
+
+            settings.source.ip = Some(Ipv6Addr::LOCALHOST.into());
+            settings.family = AddressFamily::V4;
+            let err = remote_scan("x", &["127.0.0.1".into()], "80", &[80], &settings)
+                .await
+                .unwrap_err();
+            assert!(matches!(err, PortScanError::SourceFamily(_, "IPv4")));
+
+            // Linux 整个 127.0.0.0/8 都在回环网卡上，可以验证连接确实从指定地址发出
+            #[cfg(target_os = "linux")]
+            {
+                let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+                let port = listener.local_addr().unwrap().port();
+                let accepted = tokio::spawn(async move {
+                    let (_, peer) = listener.accept().await.unwrap();
+                    peer.ip()
+                });
+                settings.source.ip = Some(Ipv4Addr::new(127, 0, 0, 2).into());
+                let result = remote_scan("x", &["127.0.0.1".into()], "", &[port], &settings)
+                    .await
+                    .unwrap();
+                assert_eq!(result.ports[0].state, PortState::Open);
+                assert_eq!(result.source.as_deref(), Some("127.0.0.2"));
+                assert_eq!(accepted.await.unwrap(), Ipv4Addr::new(127, 0, 0, 2));
+            }
+        });
+    }
+
+    #[test]
+    fn test_identify_service_banners() {
+        let cases: [(&[u8], Option<&str>, Option<&str>); 9] = [
+            (
+                b"SSH-2.0-OpenSSH_8.9p1 Ubuntu-3ubuntu0.6\r\n",
+                Some("ssh"),
+                Some("OpenSSH_8.9p1"),
+            ),
+            (
+                b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nServer: nginx/1.24.0\r\n\r\n",
+                Some("http"),
+                Some("nginx/1.24.0"),
+            ),
+            (
+                b"220 mx.example.com ESMTP Postfix (Ubuntu)\r\n",
+                Some("smtp"),
+                Some("Postfix (Ubuntu)"),
+            ),
+            (b"220 (vsFTPd 3.0.5)\r\n", Some("ftp"), Some("vsFTPd 3.0.5")),
+            (b"-NOAUTH Authentication required.\r\n", Some("redis"), None),
+            (
+                b"\x0c\x00\x00\x00\x0a8.0.36\x00\x08\x00\x00\x00",
+                Some("mysql"),
+                Some("8.0.36"),
+            ),
+            (b"ABCD\nsome text", None, None),
+            (
+                b"\x0c\x00\x00\x01\x0a8.0.36\x00\x08\x00\x00\x00",
+                None,
+                None,
+            ),
+            (b"\x00\x01\xff\xfe\x02", None, None),
+        ];
+        for (data, name, version) in cases {
+            let info = identify_service(data);
+            assert_eq!(info.name, name, "{:?}", String::from_utf8_lossy(data));
+            assert_eq!(info.version.as_deref(), version);
+        }
+        assert_eq!(
+            identify_service(b"* OK IMAP4rev1 ready\r\n")
+                .banner
+                .as_deref(),
+            Some("* OK IMAP4rev1 ready")
+        );
+    }
+
+    #[test]
+    fn test_grab_banner_passive_and_probe() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            // 连接后主动发送 banner 的服务
+            let ssh = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let ssh_port = ssh.local_addr().unwrap().port();
+            tokio::spawn(async move {
+                let (mut conn, _) = ssh.accept().await.unwrap();
+                conn.write_all(b"SSH-2.0-dropbear_2022.83\r\n")
+                    .await
+                    .unwrap();
+            });
+            // 只在收到请求后才响应的服务
+            let http = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let http_port = http.local_addr().unwrap().port();
+            tokio::spawn(async move {
+                let (mut conn, _) = http.accept().await.unwrap();
+                let mut buf = [0u8; 256];
+                let _ = conn.read(&mut buf).await.unwrap();
+                conn.write_all(b"HTTP/1.0 404 Not Found\r\nServer: test/1.0\r\n\r\n")
+                    .await
+                    .unwrap();
+            });
+
+            let wait = Duration::from_millis(500);
+            let banner = grab_banner("127.0.0.1", ssh_port, wait, None, &SourceBind::default())
+                .await
+                .unwrap();
+            assert_eq!(
+                identify_service(&banner).version.as_deref(),
+                Some("dropbear_2022.83")
+            );
+            let banner = grab_banner("127.0.0.1", http_port, wait, None, &SourceBind::default())
+                .await
+                .unwrap();
+            let info = identify_service(&banner);
+            assert_eq!(
+                (info.name, info.version.as_deref()),
+                (Some("http"), Some("test/1.0"))
+            );
+        });
+    }
+
+    #[test]
+    fn test_tls_probe_reads_certificate() {
+        use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer};
+        use tokio_rustls::rustls::{crypto::ring, ServerConfig};
+
+        let certified = rcgen::generate_simple_self_signed(vec![
+            "scan.test".to_string(),
+            "127.0.0.1".to_string(),
+        ])
+        .unwrap();
+        let cert = CertificateDer::from(certified.cert.der().to_vec());
+        let key = PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(
+            certified.signing_key.serialize_der(),
+        ));
+        let config = ServerConfig::builder_with_provider(Arc::new(ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_no_client_auth()
+            .with_single_cert(vec![cert], key)
+            .unwrap();
+        let acceptor = tokio_rustls::TlsAcceptor::from(Arc::new(config));
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let port = listener.local_addr().unwrap().port();
+            tokio::spawn(async move {
+                while let Ok((conn, _)) = listener.accept().await {
+                    let acceptor = acceptor.clone();
+                    tokio::spawn(async move {
+                        let _ = acceptor.accept(conn).await;
+                    });
+                }
+            });
+
+            let connector =
+                tokio_rustls::TlsConnector::from(Arc::new(tls::insecure_client_config()));
+            let info = probe_tls(
+                &connector,
+                "127.0.0.1",
+                port,
+                Duration::from_secs(2),
+                None,
+                &SourceBind::default(),
+            )
+            .await
+            .unwrap();
+            assert_eq!(info.version, "TLS 1.3");
+            assert_eq!(info.sans, vec!["scan.test", "127.0.0.1"]);
+            assert!(info.self_signed);
+            assert!(info.days_left > 365);
+
+            // 非 TLS 端口握手失败时不产生结果
+            let plain = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let plain_port = plain.local_addr().unwrap().port();
+            tokio::spawn(async move {
+                let (mut conn, _) = plain.accept().await.unwrap();
+                let _ = conn.write_all(b"SSH-2.0-test\r\n").await;
+            });
+            assert!(probe_tls(
+                &connector,
+                "127.0.0.1",
+                plain_port,
+                Duration::from_secs(2),
+                None,
+                &SourceBind::default()
+            )
+            .await
+            .is_none());
+        });
+    }
+
+    #[test]
+    fn test_http_probe_reads_status_server_and_title() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let port = listener.local_addr().unwrap().port();
+            tokio::spawn(async move {
+                let (mut conn, _) = listener.accept().await.unwrap();
+                let mut buf = vec![0u8; 1024];
+                let n = conn.read(&mut buf).await.unwrap();
+                let request = String::from_utf8_lossy(&buf[..n]).to_string();
+                assert!(request.starts_with("GET / HTTP/1.1\r\n"));
+                assert!(request.contains(&format!("Host: web.test:{}\r\n", port)));
+                let body = "<html><head><TITLE>\n  Tom &amp; Jerry\n</TITLE></head></html>";
+                let response = format!(
+                    "HTTP/1.1 302 Found\r\nServer: nginx/1.24.0\r\nLocation: /login\r\nContent-Length: {}\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                conn.write_all(response.as_bytes()).await.unwrap();
+            });
+            let connector =
+                tokio_rustls::TlsConnector::from(Arc::new(tls::insecure_client_config()));
+            let wait = Duration::from_secs(2);
+            let info = probe_http(&connector, "web.test", "127.0.0.1", port, wait, None, &SourceBind::default())
+                .await
+                .unwrap();
+            assert_eq!(
+                info,
+                HttpInfo {
+                    scheme: "http",
+                    status: 302,
+                    server: Some("nginx/1.24.0".into()),
+                    title: Some("Tom & Jerry".into()),
+                    location: Some("/login".into()),
+                }
+            );
+
+            // 明文和 TLS 都得不到 HTTP 响应时不产生结果
+            let ssh = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let ssh_port = ssh.local_addr().unwrap().port();
+            tokio::spawn(async move {
+                while let Ok((mut conn, _)) = ssh.accept().await {
+                    let _ = conn.write_all(b"SSH-2.0-test\r\n").await;
+                }
+            });
+            assert!(
+                probe_http(&connector, "127.0.0.1", "127.0.0.1", ssh_port, wait, None, &SourceBind::default())
+                    .await
+                    .is_none()
+            );
+        });
+    }
+}
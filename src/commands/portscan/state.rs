@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use super::{PortScanError, PortState, ScanProtocol};
+
+// --resume 状态文件（JSON Lines）：首行记录目标、端口和协议，之后每完成一个探测追加一行结果。
+// 扫描中断后用同一文件重新运行时沿用已完成的结果，只探测剩下的部分；首行不一致时拒绝继续
+#[derive(Debug)]
+pub struct ScanState {
+    path: PathBuf,
+    done: HashMap<(String, u16), (PortState, Option<f64>)>,
+    file: Mutex<File>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct StateHeader {
+    target: String,
+    ports: String,
+    protocol: ScanProtocol,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StateEntry {
+    host: String,
+    port: u16,
+    state: PortState,
+    latency_ms: Option<f64>,
+}
+
+impl ScanState {
+    pub fn open(
+        path: &Path,
+        target: &str,
+        ports: &str,
+        protocol: ScanProtocol,
+    ) -> Result<Self, PortScanError> {
+        let state_error = |source| PortScanError::StateFile {
+            path: path.to_path_buf(),
+            source,
+        };
+        let header = StateHeader {
+            target: target.to_string(),
+            ports: ports.to_string(),
+            protocol,
+        };
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(state_error(e)),
+        };
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(state_error)?;
+        let mut done = HashMap::new();
+        let mut lines = text.lines();
+        match lines.next() {
+            None => {
+                let line = serde_json::to_string(&header)?;
+                writeln!(file, "{}", line).map_err(state_error)?;
+            }
+            Some(first) => {
+                if serde_json::from_str::<StateHeader>(first).ok().as_ref() != Some(&header) {
+                    return Err(PortScanError::StateMismatch(path.to_path_buf()));
+                }
+                // 中断时最后一行可能只写了一半，解析失败的行跳过，对应的探测重新进行
+                for entry in lines.filter_map(|line| serde_json::from_str::<StateEntry>(line).ok())
+                {
+                    done.insert((entry.host, entry.port), (entry.state, entry.latency_ms));
+                }
+                if !text.ends_with('\n') {
+                    writeln!(file).map_err(state_error)?;
+                }
+            }
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            done,
+            file: Mutex::new(file),
+        })
+    }
+
+    pub fn done(&self) -> usize {
+        self.done.len()
+    }
+
+    pub(super) fn get(&self, host: &str, port: u16) -> Option<(PortState, Option<f64>)> {
+        self.done.get(&(host.to_string(), port)).copied()
+    }
+
+    // 每个结果单独写入一行，进程被终止时最多丢失正在写的一行
+    pub(super) fn record(
+        &self,
+        host: &str,
+        port: u16,
+        state: PortState,
+        latency_ms: Option<f64>,
+    ) -> Result<(), PortScanError> {
+        let mut line = serde_json::to_vec(&StateEntry {
+            host: host.to_string(),
+            port,
+            state,
+            latency_ms,
+        })?;
+        line.push(b'\n');
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        file.write_all(&line)
+            .map_err(|source| PortScanError::StateFile {
+                path: self.path.clone(),
+                source,
+            })
+    }
+
+    // 扫描完成后删除状态文件
+    pub fn remove(&self) -> Result<(), PortScanError> {
+        fs::remove_file(&self.path).map_err(|source| PortScanError::StateFile {
+            path: self.path.clone(),
+            source,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::portscan::{remote_scan, ScanSettings};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_resume_skips_recorded_probes() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let open = listener.local_addr().unwrap().port();
+            let closed = std::net::TcpListener::bind("127.0.0.1:0")
+                .unwrap()
+                .local_addr()
+                .unwrap()
+                .port();
+            let path = std::env::temp_dir().join(format!(
+                "rtoolkit-resume-{}.state",
+                std::process::id()
+            ));
+            // 模拟中断：closed 端口已记录（记为 filtered 以便区分是否重新探测），最后一行只写了一半
+            fs::write(
+                &path,
+                format!(
+                    "{{\"target\":\"127.0.0.1\",\"ports\":\"test\",\"protocol\":\"tcp\"}}\n\
+                     {{\"host\":\"127.0.0.1\",\"port\":{},\"state\":\"filtered\",\"latency_ms\":null}}\n\
+                     {{\"host\":\"127.0.0.1\",\"po",
+                    closed
+                ),
+            )
+            .unwrap();
+            let state = ScanState::open(&path, "127.0.0.1", "test", ScanProtocol::Tcp).unwrap();
+            assert_eq!(state.done(), 1);
+            let settings = ScanSettings {
+                timeout_ms: 500,
+                resume: Some(Arc::new(state)),
+                ..ScanSettings::default()
+            };
+            let hosts = vec!["127.0.0.1".to_string()];
+            let result = remote_scan("127.0.0.1", &hosts, "test", &[open, closed], &settings)
+                .await
+                .unwrap();
+            let states: HashMap<u16, PortState> = result
+                .ports
+                .iter()
+                .map(|status| (status.port, status.state))
+                .collect();
+            assert_eq!(states[&open], PortState::Open);
+            assert_eq!(states[&closed], PortState::Filtered);
+
+            // 新的结果追加在半行之后的新行上，再次打开时两个探测都已完成
+            let state = ScanState::open(&path, "127.0.0.1", "test", ScanProtocol::Tcp).unwrap();
+            assert_eq!(state.done(), 2);
+            assert!(matches!(
+                ScanState::open(&path, "127.0.0.1", "1-1024", ScanProtocol::Tcp),
+                Err(PortScanError::StateMismatch(_))
+            ));
+            state.remove().unwrap();
+            assert!(!path.exists());
+        });
+    }
+}
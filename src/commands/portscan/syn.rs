@@ -0,0 +1,475 @@
+use std::collections::BTreeSet;
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+
+use tokio::time::Duration;
+
+use crate::utils::ratelimit::RateLimiter;
+
+use super::engine::port_status;
+use super::probe::{bind_device, SourceBind};
+use super::targets::target_addr;
+use super::{PortScanError, PortState, PortStatus, ScanProtocol, ScanSettings};
+
+// SYN 收发循环的节奏参数
+struct SynPacing {
+    // 同时在途的探测数
+    window: usize,
+    timeout: Duration,
+    // 每个端口最多发送的探测次数，包括重发
+    max_attempts: u8,
+    rate: Option<Arc<RateLimiter>>,
+    randomize: bool,
+}
+
+// SYN 扫描：只支持 IPv4 目标和 Linux（其它系统的原始套接字收不到 TCP 回包）。
+// 无法执行时打印原因并返回 None，由调用方回退到 connect 扫描
+pub(super) async fn syn_scan(
+    hosts: &[String],
+    ports: &[u16],
+    settings: &ScanSettings,
+) -> Result<Option<Vec<(usize, PortStatus)>>, PortScanError> {
+    let mut addrs = Vec::with_capacity(hosts.len());
+    for host in hosts {
+        let addr = match target_addr(host, 0) {
+            Some(addr) => Some(addr),
+            None => tokio::net::lookup_host((host.as_str(), 0))
+                .await
+                .ok()
+                .and_then(|mut addrs| addrs.find(SocketAddr::is_ipv4)),
+        };
+        match addr {
+            Some(SocketAddr::V4(addr)) => addrs.push(*addr.ip()),
+            _ => {
+                eprintln!(
+                    "SYN scan needs an IPv4 address, {} has none; falling back to connect scan",
+                    host
+                );
+                return Ok(None);
+            }
+        }
+    }
+    let socket = match sys::open_socket(settings.source.interface.as_deref()) {
+        Ok(socket) => socket,
+        Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
+            eprintln!("SYN scan requires root or CAP_NET_RAW; falling back to connect scan");
+            return Ok(None);
+        }
+        Err(e) => {
+            eprintln!("SYN scan unavailable ({}); falling back to connect scan", e);
+            return Ok(None);
+        }
+    };
+
+    // 不同主机名可能解析到同一地址，只探测一次
+    let unique: Vec<Ipv4Addr> = addrs
+        .iter()
+        .copied()
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    // 路由查找失败（例如没有到目标的路由）时同样回退到 connect 扫描
+    let sources = match sys::source_ips(&unique, &settings.source) {
+        Ok(sources) => sources,
+        Err(e) => {
+            eprintln!(
+                "SYN scan cannot pick a source address ({}); falling back to connect scan",
+                e
+            );
+            return Ok(None);
+        }
+    };
+    let probe_ports = ports.to_vec();
+    let pacing = SynPacing {
+        window: settings.concurrency,
+        timeout: Duration::from_millis(settings.timeout_ms),
+        // SYN 探测默认就会重发一次，--retries 更大时按其重发
+        max_attempts: settings.retries.max(1) + 1,
+        rate: settings.rate_limiter(),
+        randomize: settings.randomize,
+    };
+    let states = tokio::task::spawn_blocking(move || {
+        sys::scan(&socket, &unique, &sources, &probe_ports, &pacing)
+    })
+    .await
+    .map_err(|e| PortScanError::JoinError(e.to_string()))??;
+
+    // 原始套接字扫描在阻塞线程中一次完成，进度只能在结束时补齐
+    if let Some(progress) = &settings.progress {
+        progress.finish();
+    }
+    let mut results = Vec::with_capacity(hosts.len() * ports.len());
+    for (host_index, (host, addr)) in hosts.iter().zip(&addrs).enumerate() {
+        for &port in ports {
+            let (state, latency_ms) = states
+                .get(&(*addr, port))
+                .copied()
+                .unwrap_or((PortState::Filtered, None));
+            results.push((
+                host_index,
+                port_status(host.clone(), port, state, latency_ms, ScanProtocol::Tcp),
+            ));
+        }
+    }
+    Ok(Some(results))
+}
+
+const TCP_FLAG_SYN: u8 = 0x02;
+const TCP_FLAG_RST: u8 = 0x04;
+const TCP_FLAG_ACK: u8 = 0x10;
+
+// SYN 探测包：20 字节 TCP 头 + MSS 选项，没有 MSS 的 SYN 容易被部分设备丢弃
+fn build_syn(src: Ipv4Addr, dst: Ipv4Addr, src_port: u16, dst_port: u16, seq: u32) -> [u8; 24] {
+    let mut segment = [0u8; 24];
+    segment[0..2].copy_from_slice(&src_port.to_be_bytes());
+    segment[2..4].copy_from_slice(&dst_port.to_be_bytes());
+    segment[4..8].copy_from_slice(&seq.to_be_bytes());
+    segment[12] = 6 << 4;
+    segment[13] = TCP_FLAG_SYN;
+    segment[14..16].copy_from_slice(&1024u16.to_be_bytes());
+    segment[20..24].copy_from_slice(&[2, 4, 0x05, 0xb4]);
+    let checksum = tcp_checksum(src, dst, &segment);
+    segment[16..18].copy_from_slice(&checksum.to_be_bytes());
+    segment
+}
+
+// TCP 校验和覆盖伪首部（源/目的地址、协议号、TCP 长度）和整个报文段
+fn tcp_checksum(src: Ipv4Addr, dst: Ipv4Addr, segment: &[u8]) -> u16 {
+    let mut sum: u32 = 0;
+    let mut add = |bytes: &[u8]| {
+        for chunk in bytes.chunks(2) {
+            let word = match chunk {
+                [hi, lo] => u16::from_be_bytes([*hi, *lo]),
+                [hi] => u16::from_be_bytes([*hi, 0]),
+                _ => 0,
+            };
+            sum += u32::from(word);
+        }
+    };
+    add(&src.octets());
+    add(&dst.octets());
+    add(&[0, 6]);
+    add(&(segment.len() as u16).to_be_bytes());
+    add(segment);
+    while sum > 0xffff {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+// 每个探测的初始序列号由随机密钥和目标派生，回包的 ACK 必须等于它加一，
+// 用来过滤不属于本次扫描的报文
+fn probe_seq(secret: u32, dst: Ipv4Addr, port: u16) -> u32 {
+    secret ^ u32::from(dst).rotate_left(13) ^ u32::from(port).wrapping_mul(0x9e37_79b1)
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct SynReply {
+    addr: Ipv4Addr,
+    port: u16,
+    ack: u32,
+    state: PortState,
+}
+
+// 解析原始套接字收到的 IPv4 报文，只接受发往本次扫描源端口的 SYN/ACK 或 RST
+fn parse_syn_reply(packet: &[u8], src_port: u16) -> Option<SynReply> {
+    if packet.len() < 20 || packet[0] >> 4 != 4 || packet[9] != 6 {
+        return None;
+    }
+    let header_len = usize::from(packet[0] & 0x0f) * 4;
+    let segment = packet.get(header_len..)?;
+    if segment.len() < 20 || u16::from_be_bytes([segment[2], segment[3]]) != src_port {
+        return None;
+    }
+    let flags = segment[13];
+    let state = if flags & (TCP_FLAG_SYN | TCP_FLAG_ACK) == TCP_FLAG_SYN | TCP_FLAG_ACK {
+        PortState::Open
+    } else if flags & TCP_FLAG_RST != 0 {
+        PortState::Closed
+    } else {
+        return None;
+    };
+    Some(SynReply {
+        addr: Ipv4Addr::new(packet[12], packet[13], packet[14], packet[15]),
+        port: u16::from_be_bytes([segment[0], segment[1]]),
+        ack: u32::from_be_bytes([segment[8], segment[9], segment[10], segment[11]]),
+        state,
+    })
+}
+
+#[cfg(target_os = "linux")]
+mod sys {
+    use std::collections::HashMap;
+    use std::io::{self, Read};
+    use std::net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket};
+    use std::time::{Duration, Instant};
+
+    use rand::seq::SliceRandom;
+    use rand::Rng;
+    use socket2::{Domain, Protocol, SockAddr, Socket, Type};
+
+    use super::{
+        bind_device, build_syn, parse_syn_reply, probe_seq, PortState, SourceBind, SynPacing,
+    };
+
+    const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+    struct Probe {
+        sent: Instant,
+        attempts: u8,
+    }
+
+    pub type SynStates = HashMap<(Ipv4Addr, u16), (PortState, Option<f64>)>;
+
+    // 原始 TCP 套接字：发送时由内核补 IP 头，接收时拿到包含 IP 头的完整报文
+    pub fn open_socket(interface: Option<&str>) -> io::Result<Socket> {
+        let socket = Socket::new(Domain::IPV4, Type::RAW, Some(Protocol::TCP))?;
+        bind_device(&socket, interface)?;
+        socket.set_read_timeout(Some(POLL_INTERVAL))?;
+        // 大范围扫描时回包密集，放大接收缓冲区减少丢包
+        let _ = socket.set_recv_buffer_size(4 << 20);
+        Ok(socket)
+    }
+
+    // 单线程收发：在途探测数不超过 window，收到回包或超时后再发下一个。
+    // 超时未响应的探测重发，最多共发送 max_attempts 次，避免单个丢包被误判为 filtered。
+    // 设置了速率时每个探测包（包括重发）发送前都要取得令牌。
+    // 对端回 SYN/ACK 后内核会因为没有对应连接自动回 RST，连接不会建立
+    pub fn scan(
+        socket: &Socket,
+        addrs: &[Ipv4Addr],
+        sources: &HashMap<Ipv4Addr, Ipv4Addr>,
+        ports: &[u16],
+        pacing: &SynPacing,
+    ) -> io::Result<SynStates> {
+        let mut rng = rand::rng();
+        let src_port: u16 = rng.random_range(40_000..60_000);
+        let secret: u32 = rng.random();
+
+        let mut order: Vec<(Ipv4Addr, u16)> = addrs
+            .iter()
+            .flat_map(|&addr| ports.iter().map(move |&port| (addr, port)))
+            .collect();
+        if pacing.randomize {
+            order.shuffle(&mut rng);
+        }
+        let mut queue = order.into_iter();
+        let mut pending: HashMap<(Ipv4Addr, u16), Probe> = HashMap::new();
+        let mut states = SynStates::with_capacity(addrs.len() * ports.len());
+        let mut buf = [0u8; 1500];
+        let mut last_sweep = Instant::now();
+        let send = |addr: Ipv4Addr, port: u16| {
+            let packet = build_syn(
+                sources[&addr],
+                addr,
+                src_port,
+                port,
+                probe_seq(secret, addr, port),
+            );
+            if let Some(rate) = &pacing.rate {
+                rate.acquire_blocking();
+            }
+            crate::utils::metrics::probe_sent(false);
+            send_packet(socket, &packet, addr)
+        };
+
+        loop {
+            while pending.len() < pacing.window {
+                let Some((addr, port)) = queue.next() else {
+                    break;
+                };
+                send(addr, port)?;
+                pending.insert(
+                    (addr, port),
+                    Probe {
+                        sent: Instant::now(),
+                        attempts: 1,
+                    },
+                );
+            }
+            if pending.is_empty() {
+                break;
+            }
+
+            match (&*socket).read(&mut buf) {
+                Ok(n) => {
+                    if let Some(reply) = parse_syn_reply(&buf[..n], src_port) {
+                        let key = (reply.addr, reply.port);
+                        let expected = probe_seq(secret, reply.addr, reply.port).wrapping_add(1);
+                        if reply.ack == expected {
+                            if let Some(probe) = pending.remove(&key) {
+                                let latency_ms = (probe.sent.elapsed().as_secs_f64() * 100_000.0)
+                                    .round()
+                                    / 100.0;
+                                states.insert(key, (reply.state, Some(latency_ms)));
+                            }
+                        }
+                    }
+                }
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        io::ErrorKind::WouldBlock
+                            | io::ErrorKind::TimedOut
+                            | io::ErrorKind::Interrupted
+                    ) => {}
+                Err(e) => return Err(e),
+            }
+
+            if last_sweep.elapsed() >= POLL_INTERVAL {
+                let now = Instant::now();
+                let expired: Vec<(Ipv4Addr, u16)> = pending
+                    .iter()
+                    .filter(|(_, probe)| now.duration_since(probe.sent) >= pacing.timeout)
+                    .map(|(key, _)| *key)
+                    .collect();
+                for key in expired {
+                    let probe = pending.get_mut(&key).expect("expired probe is pending");
+                    if probe.attempts < pacing.max_attempts {
+                        send(key.0, key.1)?;
+                        probe.sent = Instant::now();
+                        probe.attempts += 1;
+                    } else {
+                        pending.remove(&key);
+                        states.insert(key, (PortState::Filtered, None));
+                    }
+                }
+                last_sweep = now;
+            }
+        }
+        Ok(states)
+    }
+
+    fn send_packet(socket: &Socket, packet: &[u8], addr: Ipv4Addr) -> io::Result<()> {
+        let dest = SockAddr::from(SocketAddr::from((addr, 0)));
+        loop {
+            match socket.send_to(packet, &dest) {
+                Ok(_) => return Ok(()),
+                // 发送队列满时稍等再试
+                Err(e) if e.raw_os_error() == Some(libc::ENOBUFS) => {
+                    std::thread::sleep(Duration::from_millis(1));
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    // 每个目标对应的本机源地址，用于计算 TCP 校验和；指定了 --source-ip 时统一使用它
+    pub fn source_ips(
+        addrs: &[Ipv4Addr],
+        source: &SourceBind,
+    ) -> io::Result<HashMap<Ipv4Addr, Ipv4Addr>> {
+        addrs
+            .iter()
+            .map(|&addr| match source.ip {
+                Some(IpAddr::V4(ip)) => Ok((addr, ip)),
+                _ => Ok((addr, source_ip(addr, source.interface.as_deref())?)),
+            })
+            .collect()
+    }
+
+    // 借助 UDP connect 让内核按路由表（绑定网卡时按该网卡）选出发往目标的源地址
+    fn source_ip(addr: Ipv4Addr, interface: Option<&str>) -> io::Result<Ipv4Addr> {
+        let socket = UdpSocket::bind("0.0.0.0:0")?;
+        bind_device(&socket, interface)?;
+        socket.connect((addr, 9))?;
+        match socket.local_addr()? {
+            SocketAddr::V4(local) => Ok(*local.ip()),
+            SocketAddr::V6(_) => Err(io::Error::new(
+                io::ErrorKind::AddrNotAvailable,
+                "no IPv4 source address",
+            )),
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod sys {
+    use std::collections::HashMap;
+    use std::io;
+    use std::net::Ipv4Addr;
+    use std::time::Duration;
+
+    use super::{PortState, SourceBind, SynPacing};
+
+    pub type SynStates = HashMap<(Ipv4Addr, u16), (PortState, Option<f64>)>;
+
+    pub struct RawSocket;
+
+    pub fn open_socket(_interface: Option<&str>) -> io::Result<RawSocket> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "SYN scan is only supported on Linux",
+        ))
+    }
+
+    pub fn source_ips(
+        _addrs: &[Ipv4Addr],
+        _source: &SourceBind,
+    ) -> io::Result<HashMap<Ipv4Addr, Ipv4Addr>> {
+        Ok(HashMap::new())
+    }
+
+    pub fn scan(
+        _socket: &RawSocket,
+        _addrs: &[Ipv4Addr],
+        _sources: &HashMap<Ipv4Addr, Ipv4Addr>,
+        _ports: &[u16],
+        _pacing: &SynPacing,
+    ) -> io::Result<SynStates> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "SYN scan is only supported on Linux",
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_syn_packet_and_reply_parsing() {
+        let src = Ipv4Addr::new(192, 168, 1, 10);
+        let dst = Ipv4Addr::new(192, 168, 1, 20);
+        let seq = probe_seq(0xdead_beef, dst, 443);
+        let syn = build_syn(src, dst, 45_000, 443, seq);
+        assert_eq!(syn[13], TCP_FLAG_SYN);
+        // 带上校验和后重新计算应为 0
+        assert_eq!(tcp_checksum(src, dst, &syn), 0);
+
+        let reply = |flags: u8, dst_port: u16| {
+            let mut packet = vec![0x45, 0, 0, 40, 0, 0, 0, 0, 64, 6, 0, 0];
+            packet.extend(dst.octets());
+            packet.extend(src.octets());
+            packet.extend(443u16.to_be_bytes());
+            packet.extend(dst_port.to_be_bytes());
+            packet.extend(7u32.to_be_bytes());
+            packet.extend(seq.wrapping_add(1).to_be_bytes());
+            packet.extend([5 << 4, flags, 0xff, 0xff, 0, 0, 0, 0]);
+            packet
+        };
+        assert_eq!(
+            parse_syn_reply(&reply(TCP_FLAG_SYN | TCP_FLAG_ACK, 45_000), 45_000),
+            Some(SynReply {
+                addr: dst,
+                port: 443,
+                ack: seq.wrapping_add(1),
+                state: PortState::Open,
+            })
+        );
+        assert_eq!(
+            parse_syn_reply(&reply(TCP_FLAG_RST | TCP_FLAG_ACK, 45_000), 45_000)
+                .map(|reply| reply.state),
+            Some(PortState::Closed)
+        );
+        // 发往其它端口的报文和我们自己发出的 SYN 都应忽略
+        assert_eq!(
+            parse_syn_reply(&reply(TCP_FLAG_SYN | TCP_FLAG_ACK, 45_001), 45_000),
+            None
+        );
+        assert_eq!(parse_syn_reply(&reply(TCP_FLAG_SYN, 45_000), 45_000), None);
+    }
+}
@@ -0,0 +1,431 @@
+use std::collections::{BTreeSet, HashSet};
+use std::io::BufRead;
+use std::net::{IpAddr, Ipv6Addr, SocketAddr, SocketAddrV6};
+
+use ipnet::IpNet;
+
+use crate::utils::services;
+
+use super::PortScanError;
+
+// 单次扫描展开后的主机数上限，相当于一个 /16
+const MAX_TARGET_HOSTS: usize = 65_536;
+
+// 解析目标列表：逗号分隔的主机名、IP 或 CIDR，CIDR 展开为其中的可用主机地址，
+// 重复的主机只保留第一次出现的位置
+pub fn parse_targets(s: &str) -> Result<Vec<String>, PortScanError> {
+    let mut seen = HashSet::new();
+    let mut hosts = Vec::new();
+    for item in s.split(',').map(str::trim) {
+        if item.is_empty() {
+            return Err(PortScanError::InvalidTarget(s.into()));
+        }
+        if item.contains('/') {
+            let net: IpNet = item
+                .parse()
+                .map_err(|_| PortScanError::InvalidTarget(item.into()))?;
+            // /31、/32（以及 IPv6 /127、/128）没有网络地址和广播地址之分，hosts() 会保留全部地址
+            for addr in net.hosts() {
+                if seen.insert(addr.to_string()) {
+                    hosts.push(addr.to_string());
+                }
+                if hosts.len() > MAX_TARGET_HOSTS {
+                    return Err(PortScanError::TooManyHosts(MAX_TARGET_HOSTS));
+                }
+            }
+        } else {
+            // IPv6 字面量去掉方括号，方便与 (host, port) 组合连接
+            let host = normalize_host(item.trim_start_matches('[').trim_end_matches(']'))?;
+            if seen.insert(host.clone()) {
+                hosts.push(host);
+            }
+            if hosts.len() > MAX_TARGET_HOSTS {
+                return Err(PortScanError::TooManyHosts(MAX_TARGET_HOSTS));
+            }
+        }
+    }
+    Ok(hosts)
+}
+
+// IP 字面量规范化为标准写法；链路本地 IPv6 地址必须带区域 ID（fe80::1%eth0），
+// 否则系统不知道从哪个网卡发出
+fn normalize_host(host: &str) -> Result<String, PortScanError> {
+    if let Ok(addr) = host.parse::<IpAddr>() {
+        if matches!(addr, IpAddr::V6(v6) if v6.is_unicast_link_local()) {
+            return Err(PortScanError::MissingZone(host.into()));
+        }
+        return Ok(addr.to_string());
+    }
+    match host.split_once('%') {
+        Some((ip, zone)) => {
+            let ip: Ipv6Addr = ip
+                .parse()
+                .map_err(|_| PortScanError::InvalidTarget(host.into()))?;
+            if zone_index(zone).is_none() {
+                return Err(PortScanError::InvalidZone(zone.into()));
+            }
+            Ok(format!("{}%{}", ip, zone))
+        }
+        None => Ok(host.to_string()),
+    }
+}
+
+// 扫描目标是 IP 字面量时直接得到套接字地址，IPv6 区域 ID 转换为 scope id；主机名返回 None
+pub fn target_addr(host: &str, port: u16) -> Option<SocketAddr> {
+    if let Ok(ip) = host.parse::<IpAddr>() {
+        return Some(SocketAddr::new(ip, port));
+    }
+    let (ip, zone) = host.split_once('%')?;
+    let ip: Ipv6Addr = ip.parse().ok()?;
+    Some(SocketAddr::V6(SocketAddrV6::new(
+        ip,
+        port,
+        0,
+        zone_index(zone)?,
+    )))
+}
+
+// 区域 ID 可以是网卡序号，也可以是网卡名（仅类 Unix 系统）
+fn zone_index(zone: &str) -> Option<u32> {
+    if let Ok(index) = zone.parse::<u32>() {
+        return Some(index);
+    }
+    #[cfg(unix)]
+    {
+        let name = std::ffi::CString::new(zone).ok()?;
+        let index = unsafe { libc::if_nametoindex(name.as_ptr()) };
+        (index != 0).then_some(index)
+    }
+    #[cfg(not(unix))]
+    {
+        None
+    }
+}
+
+// 主机和端口组合成地址字符串，IPv6 加方括号，例如 [::1]:22、[fe80::1%eth0]:22
+pub fn host_port(host: &str, port: u16) -> String {
+    if host.contains(':') {
+        format!("[{}]:{}", host, port)
+    } else {
+        format!("{}:{}", host, port)
+    }
+}
+
+// 从主机清单读取目标：每行取第一列（兼容 "IP 主机名" 形式），# 之后为注释，
+// 每个条目仍可以是逗号分隔的列表或 CIDR
+pub fn read_target_list<R: BufRead>(reader: R) -> Result<Vec<String>, PortScanError> {
+    let mut items = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.split('#').next().unwrap_or_default();
+        if let Some(field) = line.split_whitespace().next() {
+            let field = field.trim_matches(',');
+            if !field.is_empty() {
+                items.push(field.to_string());
+            }
+        }
+    }
+    if items.is_empty() {
+        return Err(PortScanError::NoTargets);
+    }
+    parse_targets(&items.join(","))
+}
+
+// nmap-services 中按出现频率排名前 100 / 前 1000 的 TCP 端口（即 nmap --top-ports），
+// 按端口号升序书写，前 100 是前 1000 的子集
+const TOP_100_TCP: &str = concat!(
+    "7,9,13,21-23,25-26,37,53,79-81,88,106,110-111,113,119,135,139,143-144,179,199,389,427,",
+    "443-445,465,513-515,543-544,548,554,587,631,646,873,990,993,995,1025-1029,1110,1433,1720,",
+    "1723,1755,1900,2000-2001,2049,2121,2717,3000,3128,3306,3389,3986,4899,5000,5009,5051,",
+    "5060,5101,5190,5357,5432,5631,5666,5800,5900,6000-6001,6646,7070,8000,8008-8009,",
+    "8080-8081,8443,8888,9100,9999-10000,32768,49152-49157",
+);
+const TOP_1000_TCP: &str = concat!(
+    "1,3-4,6-7,9,13,17,19-26,30,32-33,37,42-43,49,53,70,79-85,88-90,99-100,106,109-111,113,",
+    "119,125,135,139,143-144,146,161,163,179,199,211-212,222,254-256,259,264,280,301,306,311,",
+    "340,366,389,406-407,416-417,425,427,443-445,458,464-465,481,497,500,512-515,524,541,",
+    "543-545,548,554-555,563,587,593,616-617,625,631,636,646,648,666-668,683,687,691,700,705,",
+    "711,714,720,722,726,749,765,777,783,787,800-801,808,843,873,880,888,898,900-903,911-912,",
+    "981,987,990,992-993,995,999-1002,1007,1009-1011,1021-1100,1102,1104-1108,1110-1114,1117,",
+    "1119,1121-1124,1126,1130-1132,1137-1138,1141,1145,1147-1149,1151-1152,1154,1163-1166,",
+    "1169,1174-1175,1183,1185-1187,1192,1198-1199,1201,1213,1216-1218,1233-1234,1236,1244,",
+    "1247-1248,1259,1271-1272,1277,1287,1296,1300-1301,1309-1311,1322,1328,1334,1352,1417,",
+    "1433-1434,1443,1455,1461,1494,1500-1501,1503,1521,1524,1533,1556,1580,1583,1594,1600,",
+    "1641,1658,1666,1687-1688,1700,1717-1721,1723,1755,1761,1782-1783,1801,1805,1812,",
+    "1839-1840,1862-1864,1875,1900,1914,1935,1947,1971-1972,1974,1984,1998-2010,2013,",
+    "2020-2022,2030,2033-2035,2038,2040-2043,2045-2049,2065,2068,2099-2100,2103,2105-2107,",
+    "2111,2119,2121,2126,2135,2144,2160-2161,2170,2179,2190-2191,2196,2200,2222,2251,2260,",
+    "2288,2301,2323,2366,2381-2383,2393-2394,2399,2401,2492,2500,2522,2525,2557,2601-2602,",
+    "2604-2605,2607-2608,2638,2701-2702,2710,2717-2718,2725,2800,2809,2811,2869,2875,",
+    "2909-2910,2920,2967-2968,2998,3000-3001,3003,3005-3007,3011,3013,3017,3030-3031,3052,",
+    "3071,3077,3128,3168,3211,3221,3260-3261,3268-3269,3283,3300-3301,3306,3322-3325,3333,",
+    "3351,3367,3369-3372,3389-3390,3404,3476,3493,3517,3527,3546,3551,3580,3659,3689-3690,",
+    "3703,3737,3766,3784,3800-3801,3809,3814,3826-3828,3851,3869,3871,3878,3880,3889,3905,",
+    "3914,3918,3920,3945,3971,3986,3995,3998,4000-4006,4045,4111,4125-4126,4129,4224,4242,",
+    "4279,4321,4343,4443-4446,4449,4550,4567,4662,4848,4899-4900,4998,5000-5004,5009,5030,",
+    "5033,5050-5051,5054,5060-5061,5080,5087,5100-5102,5120,5190,5200,5214,5221-5222,",
+    "5225-5226,5269,5280,5298,5357,5405,5414,5431-5432,5440,5500,5510,5544,5550,5555,5560,",
+    "5566,5631,5633,5666,5678-5679,5718,5730,5800-5802,5810-5811,5815,5822,5825,5850,5859,",
+    "5862,5877,5900-5904,5906-5907,5910-5911,5915,5922,5925,5950,5952,5959-5963,5987-5989,",
+    "5998-6007,6009,6025,6059,6100-6101,6106,6112,6123,6129,6156,6346,6389,6502,6510,6543,",
+    "6547,6565-6567,6580,6646,6666-6669,6689,6692,6699,6779,6788-6789,6792,6839,6881,6901,",
+    "6969,7000-7002,7004,7007,7019,7025,7070,7100,7103,7106,7200-7201,7402,7435,7443,7496,",
+    "7512,7625,7627,7676,7741,7777-7778,7800,7911,7920-7921,7937-7938,7999-8002,8007-8011,",
+    "8021-8022,8031,8042,8045,8080-8090,8093,8099-8100,8180-8181,8192-8194,8200,8222,8254,",
+    "8290-8292,8300,8333,8383,8400,8402,8443,8500,8600,8649,8651-8652,8654,8701,8800,8873,",
+    "8888,8899,8994,9000-9003,9009-9011,9040,9050,9071,9080-9081,9090-9091,9099-9103,",
+    "9110-9111,9200,9207,9220,9290,9415,9418,9485,9500,9502-9503,9535,9575,9593-9595,9618,",
+    "9666,9876-9878,9898,9900,9917,9929,9943-9944,9968,9998-10004,10009-10010,10012,",
+    "10024-10025,10082,10180,10215,10243,10566,10616-10617,10621,10626,10628-10629,10778,",
+    "11110-11111,11967,12000,12174,12265,12345,13456,13722,13782-13783,14000,14238,",
+    "14441-14442,15000,15002-15004,15660,15742,16000-16001,16012,16016,16018,16080,16113,",
+    "16992-16993,17877,17988,18040,18101,18988,19101,19283,19315,19350,19780,19801,19842,",
+    "20000,20005,20031,20221-20222,20828,21571,22939,23502,24444,24800,25734-25735,26214,",
+    "27000,27352-27353,27355-27356,27715,28201,30000,30718,30951,31038,31337,32768-32785,",
+    "33354,33899,34571-34573,35500,38292,40193,40911,41511,42510,44176,44442-44443,44501,",
+    "45100,48080,49152-49161,49163,49165,49167,49175-49176,49400,49999-50003,50006,50300,",
+    "50389,50500,50636,50800,51103,51493,52673,52822,52848,52869,54045,54328,55055-55056,",
+    "55555,55600,56737-56738,57294,57797,58080,60020,60443,61532,61900,62078,63331,64623,",
+    "64680,65000,65129,65389",
+);
+
+// --top-ports 只接受 100 和 1000，由 clap 保证
+pub(super) fn top_ports(count: &str) -> Vec<u16> {
+    let table = if count == "100" {
+        TOP_100_TCP
+    } else {
+        TOP_1000_TCP
+    };
+    parse_port_spec(table).expect("embedded top-ports table is valid")
+}
+
+// 解析端口列表：逗号分隔的单个端口或区间，例如 22,80,443,8000-8100；
+// 单独的 - 表示全部端口 1-65535。结果去重并升序排列
+pub fn parse_port_spec(s: &str) -> Result<Vec<u16>, PortScanError> {
+    let s = s.trim();
+    if s == "-" {
+        return Ok((1..=u16::MAX).collect());
+    }
+
+    let mut ports = BTreeSet::new();
+    for item in s.split(',').map(str::trim) {
+        if item.is_empty() {
+            return Err(PortScanError::InvalidPortRange(s.into()));
+        }
+        // 服务名本身可能带 -（如 ms-wbt-server），先整体按服务名查找，再按范围拆分
+        let named = (!item.starts_with(|c: char| c.is_ascii_digit()))
+            .then(|| lookup_service(item))
+            .flatten();
+        let (start, end) = match item.split_once('-').filter(|_| named.is_none()) {
+            Some((a, b)) => {
+                // no-such-service 这类未知的服务名整体报告，而不是只报告 - 前的一段
+                let whole = |err| match err {
+                    PortScanError::UnknownService(_) => PortScanError::UnknownService(item.into()),
+                    err => err,
+                };
+                let start = parse_port(a).map_err(whole)?;
+                let end = parse_port(b).map_err(whole)?;
+                if start > end {
+                    return Err(PortScanError::InvalidPortRange(item.into()));
+                }
+                (start, end)
+            }
+            None => {
+                let port = match named {
+                    Some(port) => port,
+                    None => parse_port(item)?,
+                };
+                (port, port)
+            }
+        };
+        ports.extend(start..=end);
+    }
+    Ok(ports.into_iter().collect())
+}
+
+// 在展开后的端口列表中去掉排除的端口
+pub(super) fn exclude_ports(ports: Vec<u16>, exclude: &str) -> Result<Vec<u16>, PortScanError> {
+    let excluded: BTreeSet<u16> = parse_port_spec(exclude)?.into_iter().collect();
+    let ports: Vec<u16> = ports
+        .into_iter()
+        .filter(|port| !excluded.contains(port))
+        .collect();
+    if ports.is_empty() {
+        return Err(PortScanError::AllPortsExcluded);
+    }
+    Ok(ports)
+}
+
+// 端口号或内置服务表中的服务名，例如 443、https；范围的两端同样可以用服务名
+fn parse_port(raw: &str) -> Result<u16, PortScanError> {
+    let raw = raw.trim();
+    match raw.parse::<u16>() {
+        Ok(0) => Err(PortScanError::InvalidPort(raw.into())),
+        Ok(port) => Ok(port),
+        Err(_) if raw.starts_with(|c: char| c.is_ascii_alphabetic()) => {
+            lookup_service(raw).ok_or_else(|| PortScanError::UnknownService(raw.into()))
+        }
+        Err(_) => Err(PortScanError::InvalidPort(raw.into())),
+    }
+}
+
+// 服务名不区分协议：先查 TCP 表，只注册了 UDP 的服务（如 tftp）再查 UDP 表
+fn lookup_service(name: &str) -> Option<u16> {
+    services::service_port(name, false).or_else(|| services::service_port(name, true))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::portscan::output::PortRecord;
+    use crate::commands::portscan::{
+        remote_scan, AddressFamily, PortState, ScanProtocol, ScanSettings,
+    };
+    use std::io;
+
+    #[test]
+    fn test_parse_port_spec_mixed() {
+        let ports = parse_port_spec("443, 22,80,8000-8002,80").unwrap();
+        assert_eq!(ports, vec![22, 80, 443, 8000, 8001, 8002]);
+        assert_eq!(parse_port_spec("-").unwrap().len(), 65535);
+        assert_eq!(parse_port_spec("65535").unwrap(), vec![65535]);
+        assert_eq!(
+            parse_port_spec("HTTPS, http,ssh,8000-8001,tftp").unwrap(),
+            vec![22, 69, 80, 443, 8000, 8001]
+        );
+        // 带 - 的服务名不会被当作范围，范围两端也可以是服务名
+        assert_eq!(parse_port_spec("ms-wbt-server").unwrap(), vec![3389]);
+        assert_eq!(parse_port_spec("ssh-smtp").unwrap(), vec![22, 23, 24, 25]);
+    }
+
+    #[test]
+    fn test_exclude_ports_after_expansion() {
+        let ports = parse_port_spec("20-25,3380-3390").unwrap();
+        let ports = exclude_ports(ports, "22,3385-3400,8080").unwrap();
+        assert_eq!(ports, [20, 21, 23, 24, 25, 3380, 3381, 3382, 3383, 3384]);
+        assert!(matches!(
+            exclude_ports(vec![22], "1-1024"),
+            Err(PortScanError::AllPortsExcluded)
+        ));
+        assert!(matches!(
+            exclude_ports(vec![22], "ssh"),
+            Err(PortScanError::AllPortsExcluded)
+        ));
+    }
+
+    #[test]
+    fn test_parse_targets_expands_cidr_and_lists() {
+        let hosts = parse_targets("192.168.1.0/30, example.com,192.168.1.2,[::1]").unwrap();
+        assert_eq!(
+            hosts,
+            vec!["192.168.1.1", "192.168.1.2", "example.com", "::1"]
+        );
+        assert_eq!(parse_targets("10.0.0.7/32").unwrap(), vec!["10.0.0.7"]);
+        assert_eq!(parse_targets("10.0.0.0/24").unwrap().len(), 254);
+        assert!(matches!(
+            parse_targets("10.0.0.0/8"),
+            Err(PortScanError::TooManyHosts(_))
+        ));
+        assert!(parse_targets("10.0.0.0/33").is_err());
+        assert!(parse_targets("a,,b").is_err());
+    }
+
+    #[test]
+    fn test_ipv6_targets_and_zones() {
+        assert_eq!(
+            parse_targets("[fe80::0001%1],2001:db8::1").unwrap(),
+            vec!["fe80::1%1", "2001:db8::1"]
+        );
+        assert!(matches!(
+            parse_targets("fe80::1"),
+            Err(PortScanError::MissingZone(_))
+        ));
+        assert!(matches!(
+            parse_targets("fe80::1%no-such-if0"),
+            Err(PortScanError::InvalidZone(zone)) if zone == "no-such-if0"
+        ));
+        assert_eq!(
+            target_addr("fe80::1%3", 22),
+            Some("[fe80::1%3]:22".parse().unwrap())
+        );
+        assert_eq!(target_addr("example.com", 22), None);
+        assert_eq!(host_port("::1", 22), "[::1]:22");
+        assert_eq!(host_port("fe80::1%eth0", 22), "[fe80::1%eth0]:22");
+        assert_eq!(host_port("10.0.0.1", 22), "10.0.0.1:22");
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let settings = ScanSettings {
+                family: AddressFamily::V6,
+                ..ScanSettings::default()
+            };
+            let err = remote_scan("x", &["127.0.0.1".into()], "80", &[80], &settings)
+                .await
+                .unwrap_err();
+            assert!(matches!(err, PortScanError::WrongFamily(host, "IPv6") if host == "127.0.0.1"));
+
+            // 环境没有 IPv6 回环时跳过实际连接
+            let Ok(listener) = tokio::net::TcpListener::bind("[::1]:0").await else {
+                return;
+            };
+            let port = listener.local_addr().unwrap().port();
+            let result = remote_scan("::1", &["::1".into()], "", &[port], &settings)
+                .await
+                .unwrap();
+            assert_eq!(result.ports[0].state, PortState::Open);
+            let record = PortRecord::new(&result.ports[0], ScanProtocol::Tcp);
+            assert_eq!(record.address, format!("[::1]:{}", port));
+        });
+    }
+
+    #[test]
+    fn test_read_target_list() {
+        let inventory =
+            "# web servers\n10.0.0.5 web1\n\n  db.internal   # primary\n10.0.1.0/30,10.0.0.5\n";
+        let hosts = read_target_list(io::Cursor::new(inventory)).unwrap();
+        assert_eq!(
+            hosts,
+            vec!["10.0.0.5", "db.internal", "10.0.1.1", "10.0.1.2"]
+        );
+        assert!(matches!(
+            read_target_list(io::Cursor::new("# nothing\n\n")),
+            Err(PortScanError::NoTargets)
+        ));
+    }
+
+    #[test]
+    fn test_top_ports_tables() {
+        let top100 = top_ports("100");
+        let top1000 = top_ports("1000");
+        assert_eq!(top100.len(), 100);
+        assert_eq!(top1000.len(), 1000);
+        assert!(top100.iter().all(|port| top1000.contains(port)));
+        for port in [21, 22, 23, 25, 80, 443, 445, 3306, 3389, 8080] {
+            assert!(top100.contains(&port), "{}", port);
+        }
+    }
+
+    #[test]
+    fn test_parse_port_spec_rejects_invalid() {
+        for spec in [
+            "65536",
+            "0",
+            "100-80",
+            "22,,80",
+            "80-",
+            "http2x",
+            "8o",
+            "https-ssh",
+        ] {
+            assert!(
+                parse_port_spec(spec).is_err(),
+                "{} should be rejected",
+                spec
+            );
+        }
+        assert!(matches!(
+            parse_port_spec("80,no-such-service"),
+            Err(PortScanError::UnknownService(name)) if name == "no-such-service"
+        ));
+    }
+}
@@ -0,0 +1,481 @@
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use tokio::time::{timeout, Duration};
+
+use crate::utils::duration::parse_duration;
+use crate::utils::http::{self, Url};
+use crate::utils::output::RecordOutput;
+
+use super::engine::remote_scan;
+use super::output::{OutputFormat, PortRecord, ScanRun};
+use super::targets::host_port;
+use super::{PortScanError, PortScanResult, ScanProtocol, ScanSettings};
+
+// Slack 等 webhook 消息中最多逐行列出的端口数
+const NOTIFY_MAX_LINES: usize = 20;
+const NOTIFY_TIMEOUT: Duration = Duration::from_secs(10);
+
+// --notify-url 的消息体：text 供 webhook 直接显示，其余字段便于脚本处理
+#[derive(Debug, Serialize)]
+pub(super) struct ScanNotification<'a> {
+    event: &'static str,
+    text: String,
+    target: &'a str,
+    port_range: &'a str,
+    protocol: ScanProtocol,
+    started: String,
+    finished: String,
+    hosts: usize,
+    total: usize,
+    open_count: usize,
+    closed_count: usize,
+    filtered_count: usize,
+    open: Vec<PortRecord<'a>>,
+}
+
+impl<'a> ScanNotification<'a> {
+    // 只列出 --show / --where 筛选后仍保留的开放端口，计数覆盖全部扫描结果
+    pub(super) fn new(result: &'a PortScanResult, run: &ScanRun) -> Self {
+        let open: Vec<PortRecord> = result
+            .ports
+            .iter()
+            .filter(|status| status.open)
+            .map(|status| PortRecord::new(status, result.protocol))
+            .collect();
+        let mut lines = vec![format!(
+            "port-scan {} finished: {} open of {} ports scanned",
+            result.target, result.open_count, result.total
+        )];
+        lines.extend(open.iter().take(NOTIFY_MAX_LINES).map(|record| {
+            let service = record.service.map(|name| format!(" {}", name));
+            format!(
+                "{}/{}{}",
+                record.address,
+                record.protocol.as_str(),
+                service.unwrap_or_default()
+            )
+        }));
+        if open.len() > NOTIFY_MAX_LINES {
+            lines.push(format!("... and {} more", open.len() - NOTIFY_MAX_LINES));
+        }
+        let time =
+            |time: &DateTime<Local>| time.to_rfc3339_opts(chrono::SecondsFormat::Secs, false);
+        Self {
+            event: "scan_finished",
+            text: lines.join("\n"),
+            target: &result.target,
+            port_range: &result.port_range,
+            protocol: result.protocol,
+            started: time(&run.started),
+            finished: time(&run.finished),
+            hosts: result.hosts.len(),
+            total: result.total,
+            open_count: result.open_count,
+            closed_count: result.closed_count,
+            filtered_count: result.filtered_count,
+            open,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct ChangeNotification<'a> {
+    event: &'static str,
+    text: String,
+    target: &'a str,
+    time: String,
+    changes: &'a [PortChangeEvent],
+}
+
+impl<'a> ChangeNotification<'a> {
+    fn new(target: &'a str, time: &DateTime<Local>, changes: &'a [PortChangeEvent]) -> Self {
+        let mut text = format!("port-scan watch on {}: {} changes", target, changes.len());
+        for event in changes.iter().take(NOTIFY_MAX_LINES) {
+            let mut line = Vec::new();
+            // 写入内存缓冲区不会失败
+            let _ = write_change(event, OutputFormat::Plain, &mut line);
+            text.push('\n');
+            text.push_str(String::from_utf8_lossy(&line).trim_end());
+        }
+        if changes.len() > NOTIFY_MAX_LINES {
+            text.push_str(&format!(
+                "\n... and {} more",
+                changes.len() - NOTIFY_MAX_LINES
+            ));
+        }
+        Self {
+            event: "ports_changed",
+            text,
+            target,
+            time: time.to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+            changes,
+        }
+    }
+}
+
+// 通知失败只打印警告，不影响扫描结果和退出码，--watch 也继续运行
+pub(super) async fn notify(url: &Url, payload: &impl Serialize) {
+    let sent = async {
+        let body = serde_json::to_vec(payload).map_err(|e| e.to_string())?;
+        let response = timeout(NOTIFY_TIMEOUT, http::post_json(url, &body, false))
+            .await
+            .map_err(|_| "timed out".to_string())?
+            .map_err(|e| e.to_string())?;
+        match response.status {
+            200..=299 => Ok(()),
+            status => Err(format!("HTTP {}", status)),
+        }
+    };
+    if let Err(error) = sent.await {
+        eprintln!("warning: notification to {} failed: {}", url.host, error);
+    }
+}
+
+pub(super) fn parse_interval(raw: &str) -> Result<Duration, String> {
+    parse_duration(raw)
+        .ok_or_else(|| format!("invalid interval '{}', expected e.g. 30s, 5m, 1h", raw))
+}
+
+// --watch 模式：第一轮作为基线，之后每轮只输出相对上一轮的变化
+pub(super) struct Watch<'a> {
+    pub(super) interval: Duration,
+    pub(super) format: OutputFormat,
+    // 变化事件追加写入的文件，默认输出到 stdout
+    pub(super) out: Option<&'a Path>,
+    pub(super) record_output: &'a RecordOutput,
+    pub(super) notify_url: Option<&'a Url>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(super) enum PortChange {
+    Opened,
+    Closed,
+}
+
+impl PortChange {
+    fn as_str(self) -> &'static str {
+        match self {
+            PortChange::Opened => "opened",
+            PortChange::Closed => "closed",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            PortChange::Opened => "[OPENED]",
+            PortChange::Closed => "[CLOSED]",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(super) struct WatchedPort {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) hostname: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) service: Option<String>,
+}
+
+pub(super) const CHANGE_CSV_HEADER: &str = "time,change,host,port,protocol,hostname,service";
+
+// 一轮扫描中开放的端口，键为 (地址, 端口)
+pub(super) type OpenPorts = BTreeMap<(String, u16), WatchedPort>;
+
+#[derive(Debug, Serialize)]
+pub(super) struct PortChangeEvent {
+    time: String,
+    pub(super) change: PortChange,
+    host: String,
+    pub(super) port: u16,
+    protocol: ScanProtocol,
+    #[serde(flatten)]
+    detail: WatchedPort,
+}
+
+impl Watch<'_> {
+    pub(super) async fn run(
+        &self,
+        target: &str,
+        hosts: &[String],
+        port_spec: &str,
+        ports: &[u16],
+        settings: &ScanSettings,
+    ) -> Result<(), PortScanError> {
+        if matches!(self.format, OutputFormat::NmapXml | OutputFormat::Grepable) {
+            return Err(PortScanError::WatchFormat);
+        }
+        let mut out: Box<dyn Write> = match self.out {
+            Some(path) => {
+                let empty = fs::metadata(path).map_or(true, |meta| meta.len() == 0);
+                let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+                if empty && self.format == OutputFormat::Csv {
+                    writeln!(file, "{}", CHANGE_CSV_HEADER)?;
+                }
+                Box::new(file)
+            }
+            None => {
+                let mut stdout = io::stdout();
+                if self.format == OutputFormat::Csv {
+                    writeln!(stdout, "{}", CHANGE_CSV_HEADER)?;
+                }
+                Box::new(stdout)
+            }
+        };
+
+        let mut previous: Option<OpenPorts> = None;
+        let mut round = 0usize;
+        loop {
+            round += 1;
+            // 收到 Ctrl-C 后不再等待本轮扫描结束
+            let scanned = tokio::select! {
+                result = remote_scan(target, hosts, port_spec, ports, settings) => result,
+                _ = tokio::signal::ctrl_c() => break,
+            };
+            let now = Local::now();
+            match scanned {
+                Ok(result) => {
+                    let protocol = result.protocol;
+                    let current = self.open_ports(result)?;
+                    match &previous {
+                        None => eprintln!(
+                            "[{}] Baseline: {} open ports, rescanning every {}s",
+                            now.format("%Y-%m-%d %H:%M:%S"),
+                            current.len(),
+                            self.interval.as_secs()
+                        ),
+                        Some(previous) => {
+                            let events = diff_open_ports(previous, &current, protocol, &now);
+                            for event in &events {
+                                write_change(event, self.format, &mut out)?;
+                            }
+                            out.flush()?;
+                            if let (Some(url), false) = (self.notify_url, events.is_empty()) {
+                                notify(url, &ChangeNotification::new(target, &now, &events)).await;
+                            }
+                            eprintln!(
+                                "[{}] Scan #{}: {} open ports, {} changes",
+                                now.format("%Y-%m-%d %H:%M:%S"),
+                                round,
+                                current.len(),
+                                events.len()
+                            );
+                        }
+                    }
+                    previous = Some(current);
+                }
+                // 基线之后的单轮失败（例如临时断网）不中断监控，下一轮再与上一次成功的结果比较
+                Err(error) if previous.is_some() => {
+                    eprintln!("Scan #{} failed: {}", round, error);
+                }
+                Err(error) => return Err(error),
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(self.interval) => {}
+                _ = tokio::signal::ctrl_c() => break,
+            }
+        }
+        eprintln!("Stopped watching after {} scans", round);
+        Ok(())
+    }
+
+    // 只监控 --where 保留下来的开放端口
+    fn open_ports(&self, result: PortScanResult) -> Result<OpenPorts, PortScanError> {
+        let mut open = OpenPorts::new();
+        for status in result.ports {
+            if !status.open
+                || !self
+                    .record_output
+                    .keep(&PortRecord::new(&status, result.protocol))?
+            {
+                continue;
+            }
+            open.insert(
+                (status.host, status.port),
+                WatchedPort {
+                    hostname: status.hostname,
+                    service: status.service,
+                },
+            );
+        }
+        Ok(open)
+    }
+}
+
+pub(super) fn diff_open_ports(
+    previous: &OpenPorts,
+    current: &OpenPorts,
+    protocol: ScanProtocol,
+    time: &DateTime<Local>,
+) -> Vec<PortChangeEvent> {
+    let event = |change, (host, port): &(String, u16), detail: &WatchedPort| PortChangeEvent {
+        time: time.to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+        change,
+        host: host.clone(),
+        port: *port,
+        protocol,
+        detail: detail.clone(),
+    };
+    let opened = current
+        .iter()
+        .filter(|(key, _)| !previous.contains_key(*key))
+        .map(|(key, detail)| event(PortChange::Opened, key, detail));
+    let closed = previous
+        .iter()
+        .filter(|(key, _)| !current.contains_key(*key))
+        .map(|(key, detail)| event(PortChange::Closed, key, detail));
+    opened.chain(closed).collect()
+}
+
+pub(super) fn write_change<W: Write + ?Sized>(
+    event: &PortChangeEvent,
+    format: OutputFormat,
+    out: &mut W,
+) -> Result<(), PortScanError> {
+    match format {
+        OutputFormat::Json => {
+            serde_json::to_writer(&mut *out, event)?;
+            writeln!(out)?;
+        }
+        OutputFormat::Csv => {
+            let mut writer = csv::WriterBuilder::new()
+                .has_headers(false)
+                .from_writer(&mut *out);
+            writer
+                .write_record([
+                    event.time.as_str(),
+                    event.change.as_str(),
+                    &event.host,
+                    &event.port.to_string(),
+                    event.protocol.as_str(),
+                    event.detail.hostname.as_deref().unwrap_or_default(),
+                    event.detail.service.as_deref().unwrap_or_default(),
+                ])
+                .map_err(io::Error::from)?;
+            writer.flush()?;
+        }
+        _ => {
+            let mut line = format!(
+                "{} {} {}/{}",
+                event.time,
+                event.change.label(),
+                host_port(&event.host, event.port),
+                event.protocol.as_str()
+            );
+            if let Some(service) = &event.detail.service {
+                line.push_str(&format!(" {}", service));
+            }
+            if let Some(hostname) = &event.detail.hostname {
+                line.push_str(&format!(" ({})", hostname));
+            }
+            writeln!(out, "{}", line)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[test]
+    fn test_watch_reports_opened_and_closed_ports() {
+        let port = |service: Option<&str>| WatchedPort {
+            hostname: None,
+            service: service.map(str::to_string),
+        };
+        let previous = OpenPorts::from([
+            (("10.0.0.1".to_string(), 22), port(Some("ssh"))),
+            (("10.0.0.1".to_string(), 80), port(Some("http"))),
+        ]);
+        let current = OpenPorts::from([
+            (("10.0.0.1".to_string(), 22), port(Some("ssh"))),
+            (("fe80::1".to_string(), 8080), port(None)),
+        ]);
+        let time = Local::now();
+        let events = diff_open_ports(&previous, &current, ScanProtocol::Tcp, &time);
+        let summary: Vec<_> = events.iter().map(|e| (e.change, e.port)).collect();
+        assert_eq!(
+            summary,
+            [(PortChange::Opened, 8080), (PortChange::Closed, 80)]
+        );
+        assert!(diff_open_ports(&current, &current, ScanProtocol::Tcp, &time).is_empty());
+
+        let mut plain = Vec::new();
+        write_change(&events[0], OutputFormat::Plain, &mut plain).unwrap();
+        assert!(String::from_utf8(plain)
+            .unwrap()
+            .ends_with(" [OPENED] [fe80::1]:8080/tcp\n"));
+        let mut csv = Vec::new();
+        write_change(&events[1], OutputFormat::Csv, &mut csv).unwrap();
+        assert!(String::from_utf8(csv)
+            .unwrap()
+            .ends_with(",closed,10.0.0.1,80,tcp,,http\n"));
+        let mut json = Vec::new();
+        write_change(&events[1], OutputFormat::Json, &mut json).unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(value["change"], "closed");
+        assert_eq!(value["service"], "http");
+    }
+
+    #[test]
+    fn test_notify_posts_change_summary() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let url = Url::parse(&format!(
+                "http://{}/hooks/scan",
+                listener.local_addr().unwrap()
+            ))
+            .unwrap();
+            let server = tokio::spawn(async move {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = Vec::new();
+                let mut buf = [0u8; 4096];
+                while !request.ends_with(b"}") {
+                    let n = stream.read(&mut buf).await.unwrap();
+                    request.extend_from_slice(&buf[..n]);
+                }
+                stream
+                    .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok")
+                    .await
+                    .unwrap();
+                String::from_utf8(request).unwrap()
+            });
+
+            let time = Local::now();
+            let previous = OpenPorts::new();
+            let current = OpenPorts::from([(
+                ("10.0.0.12".to_string(), 6379),
+                WatchedPort {
+                    hostname: None,
+                    service: Some("redis".to_string()),
+                },
+            )]);
+            let events = diff_open_ports(&previous, &current, ScanProtocol::Tcp, &time);
+            notify(
+                &url,
+                &ChangeNotification::new("10.0.0.0/24", &time, &events),
+            )
+            .await;
+
+            let request = server.await.unwrap();
+            assert!(request.starts_with("POST /hooks/scan HTTP/1.1\r\n"));
+            assert!(request.contains("Content-Type: application/json\r\n"));
+            let body = request.split("\r\n\r\n").nth(1).unwrap();
+            let value: serde_json::Value = serde_json::from_str(body).unwrap();
+            assert_eq!(value["event"], "ports_changed");
+            assert!(value["text"]
+                .as_str()
+                .unwrap()
+                .ends_with("[OPENED] 10.0.0.12:6379/tcp redis"));
+            assert_eq!(value["changes"][0]["port"], 6379);
+        });
+    }
+}
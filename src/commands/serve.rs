@@ -0,0 +1,588 @@
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::{DateTime, Local, Utc};
+
+use crate::utils::format::human_bytes;
+use crate::utils::http::{content_type, percent_encode};
+use crate::utils::network::{self, NetworkError};
+
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_HEADER_BYTES: u64 = 16 * 1024;
+const INDEX_FILE: &str = "index.html";
+// 同时处理的连接数上限，超出时直接回 503，避免大量连接耗尽线程
+const MAX_CLIENTS: usize = 64;
+
+#[derive(clap::Args)]
+pub struct ServeOpts {
+    #[arg(value_name = "DIR", default_value = ".", help = "要提供访问的目录")]
+    dir: PathBuf,
+
+    #[arg(
+        short = 'b',
+        long,
+        default_value = "127.0.0.1",
+        value_name = "ADDR",
+        help = "监听地址，局域网访问时使用 0.0.0.0"
+    )]
+    bind: String,
+
+    #[arg(short, long, default_value_t = 8080, help = "监听端口，0 表示随机端口")]
+    port: u16,
+
+    #[arg(long = "no-list", help = "不列出没有 index.html 的目录内容")]
+    no_list: bool,
+
+    #[arg(long, value_name = "USER:PASS", help = "要求 HTTP Basic 认证")]
+    auth: Option<String>,
+
+    #[arg(long, help = "添加 CORS 响应头，允许任意来源访问并应答预检请求")]
+    cors: bool,
+
+    #[arg(
+        long,
+        help = "单页应用模式：不存在且没有扩展名的路径返回根目录的 index.html"
+    )]
+    spa: bool,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ServeError {
+    #[error("{0} is not a directory")]
+    NotADirectory(PathBuf),
+    #[error("invalid --auth '{0}', expected USER:PASS")]
+    InvalidAuth(String),
+    #[error("--spa requires {0}")]
+    MissingIndex(PathBuf),
+    #[error("failed to listen on {addr}: {source}")]
+    Bind { addr: String, source: io::Error },
+    #[error(transparent)]
+    Network(#[from] NetworkError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+struct Site {
+    root: PathBuf,
+    listing: bool,
+    // 期望的 Authorization 请求头
+    auth: Option<String>,
+    cors: bool,
+    spa: bool,
+}
+
+struct Request {
+    method: String,
+    target: String,
+    authorization: Option<String>,
+    if_modified_since: Option<String>,
+}
+
+pub fn run_serve(opts: ServeOpts) -> Result<(), ServeError> {
+    if !opts.dir.is_dir() {
+        return Err(ServeError::NotADirectory(opts.dir));
+    }
+    let root = opts.dir.canonicalize()?;
+    let auth = match &opts.auth {
+        Some(credentials) if credentials.contains(':') => {
+            Some(format!("Basic {}", STANDARD.encode(credentials)))
+        }
+        Some(credentials) => return Err(ServeError::InvalidAuth(credentials.clone())),
+        None => None,
+    };
+    if opts.spa && !root.join(INDEX_FILE).is_file() {
+        return Err(ServeError::MissingIndex(root.join(INDEX_FILE)));
+    }
+
+    network::check_host("serve", &opts.bind)?;
+    let addr = format!("{}:{}", opts.bind, opts.port);
+    let listener = TcpListener::bind(&addr).map_err(|source| ServeError::Bind {
+        addr: addr.clone(),
+        source,
+    })?;
+    let local = listener.local_addr()?;
+    let host = if local.is_ipv6() {
+        format!("[{}]", local.ip())
+    } else {
+        local.ip().to_string()
+    };
+    eprintln!(
+        "Serving {} on http://{}:{}",
+        root.display(),
+        host,
+        local.port()
+    );
+    let mut features = Vec::new();
+    if auth.is_some() {
+        features.push("basic auth");
+    }
+    if opts.cors {
+        features.push("CORS");
+    }
+    if opts.spa {
+        features.push("SPA fallback");
+    }
+    if opts.no_list {
+        features.push("no directory listing");
+    }
+    if !features.is_empty() {
+        eprintln!("  {}", features.join(", "));
+    }
+    serve(
+        listener,
+        Arc::new(Site {
+            root,
+            listing: !opts.no_list,
+            auth,
+            cors: opts.cors,
+            spa: opts.spa,
+        }),
+    );
+    Ok(())
+}
+
+fn serve(listener: TcpListener, site: Arc<Site>) {
+    let active = Arc::new(AtomicUsize::new(0));
+    for stream in listener.incoming() {
+        let mut stream = match stream {
+            Ok(stream) => stream,
+            Err(error) => {
+                eprintln!("connection failed: {}", error);
+                continue;
+            }
+        };
+        if active.load(Ordering::SeqCst) >= MAX_CLIENTS {
+            let _ = stream.set_write_timeout(Some(Duration::from_secs(1)));
+            let _ = write_status(&mut stream, &site, 503, "Service Unavailable", &[]);
+            continue;
+        }
+        active.fetch_add(1, Ordering::SeqCst);
+        let site = Arc::clone(&site);
+        let active = Arc::clone(&active);
+        thread::spawn(move || {
+            if let Err(error) = handle_client(stream, &site) {
+                eprintln!("request failed: {}", error);
+            }
+            active.fetch_sub(1, Ordering::SeqCst);
+        });
+    }
+}
+
+fn handle_client(mut stream: TcpStream, site: &Site) -> io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let started = Instant::now();
+    let peer = stream.peer_addr()?;
+    let Some(request) = read_request(&stream)? else {
+        return write_status(&mut stream, site, 400, "Bad Request", &[]);
+    };
+    let (status, bytes) = respond(&mut stream, site, &request)?;
+    eprintln!(
+        "{} {} {} -> {} {} ({}ms)",
+        peer.ip(),
+        request.method,
+        request.target,
+        status,
+        human_bytes(bytes),
+        started.elapsed().as_millis()
+    );
+    Ok(())
+}
+
+// 请求头超过上限或请求行不完整时为 None
+fn read_request(stream: &TcpStream) -> io::Result<Option<Request>> {
+    let mut reader = BufReader::new(stream.take(MAX_HEADER_BYTES));
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Ok(None);
+    };
+    let mut request = Request {
+        method: method.to_string(),
+        target: target.to_string(),
+        authorization: None,
+        if_modified_since: None,
+    };
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        if line.trim_end().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            let value = Some(value.trim().to_string());
+            match name.trim().to_ascii_lowercase().as_str() {
+                "authorization" => request.authorization = value,
+                "if-modified-since" => request.if_modified_since = value,
+                _ => {}
+            }
+        }
+    }
+    Ok(Some(request))
+}
+
+// 返回状态码和发送的响应体字节数
+fn respond(stream: &mut TcpStream, site: &Site, request: &Request) -> io::Result<(u16, u64)> {
+    // 浏览器的预检请求不带认证信息，先于认证处理
+    if request.method == "OPTIONS" && site.cors {
+        write_head(stream, site, 204, "No Content", &[("Content-Length", "0")])?;
+        return Ok((204, 0));
+    }
+    if site.auth.is_some() && request.authorization != site.auth {
+        let challenge = [("WWW-Authenticate", "Basic realm=\"rtoolkit\"")];
+        write_status(stream, site, 401, "Unauthorized", &challenge)?;
+        return Ok((401, 0));
+    }
+    let head_only = match request.method.as_str() {
+        "GET" => false,
+        "HEAD" => true,
+        _ => {
+            let methods = if site.cors {
+                "GET, HEAD, OPTIONS"
+            } else {
+                "GET, HEAD"
+            };
+            let allow = [("Allow", methods)];
+            write_status(stream, site, 405, "Method Not Allowed", &allow)?;
+            return Ok((405, 0));
+        }
+    };
+    let raw_path = request.target.split(['?', '#']).next().unwrap_or("/");
+    let Some((url_path, path)) = decode_path(raw_path).and_then(|url_path| {
+        let path = resolve(&site.root, &url_path)?;
+        Some((url_path, path))
+    }) else {
+        write_status(stream, site, 400, "Bad Request", &[])?;
+        return Ok((400, 0));
+    };
+
+    if path.is_dir() {
+        // 目录地址补上结尾的 /，页面中的相对链接才能指向目录内
+        if !url_path.ends_with('/') {
+            let location = format!("{}/", raw_path);
+            write_status(
+                stream,
+                site,
+                301,
+                "Moved Permanently",
+                &[("Location", &location)],
+            )?;
+            return Ok((301, 0));
+        }
+        let index = path.join(INDEX_FILE);
+        if index.is_file() {
+            return send_file(stream, site, request, &index, head_only);
+        }
+        if !site.listing {
+            write_status(stream, site, 403, "Forbidden", &[])?;
+            return Ok((403, 0));
+        }
+        let page = listing(&path, &url_path)?;
+        let length = page.len().to_string();
+        write_head(
+            stream,
+            site,
+            200,
+            "OK",
+            &[
+                ("Content-Type", "text/html; charset=utf-8"),
+                ("Content-Length", &length),
+            ],
+        )?;
+        if head_only {
+            return Ok((200, 0));
+        }
+        stream.write_all(page.as_bytes())?;
+        return Ok((200, page.len() as u64));
+    }
+    if path.is_file() {
+        return send_file(stream, site, request, &path, head_only);
+    }
+    // 前端路由的地址交给 index.html 处理，缺失的静态资源仍返回 404
+    let last = url_path.rsplit('/').next().unwrap_or_default();
+    if site.spa && !last.contains('.') {
+        return send_file(
+            stream,
+            site,
+            request,
+            &site.root.join(INDEX_FILE),
+            head_only,
+        );
+    }
+    write_status(stream, site, 404, "Not Found", &[])?;
+    Ok((404, 0))
+}
+
+fn send_file(
+    stream: &mut TcpStream,
+    site: &Site,
+    request: &Request,
+    path: &Path,
+    head_only: bool,
+) -> io::Result<(u16, u64)> {
+    let mut file = File::open(path)?;
+    let metadata = file.metadata()?;
+    let modified = metadata.modified().ok().map(http_date);
+    if modified.is_some() && request.if_modified_since == modified {
+        write_head(stream, site, 304, "Not Modified", &[])?;
+        return Ok((304, 0));
+    }
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let length = metadata.len().to_string();
+    let mut headers = vec![
+        ("Content-Type", content_type(&name)),
+        ("Content-Length", length.as_str()),
+        ("Cache-Control", "no-cache"),
+    ];
+    if let Some(modified) = &modified {
+        headers.push(("Last-Modified", modified));
+    }
+    write_head(stream, site, 200, "OK", &headers)?;
+    if head_only {
+        return Ok((200, 0));
+    }
+    let sent = io::copy(&mut file, stream)?;
+    stream.flush()?;
+    Ok((200, sent))
+}
+
+fn write_head(
+    stream: &mut TcpStream,
+    site: &Site,
+    status: u16,
+    reason: &str,
+    headers: &[(&str, &str)],
+) -> io::Result<()> {
+    let mut head = format!("HTTP/1.1 {} {}\r\n", status, reason);
+    for (name, value) in headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    if site.cors {
+        head.push_str(
+            "Access-Control-Allow-Origin: *\r\nAccess-Control-Allow-Methods: GET, HEAD, OPTIONS\r\nAccess-Control-Allow-Headers: *\r\n",
+        );
+    }
+    head.push_str("Connection: close\r\n\r\n");
+    stream.write_all(head.as_bytes())
+}
+
+// 错误状态附带纯文本的原因短语作为响应体
+fn write_status(
+    stream: &mut TcpStream,
+    site: &Site,
+    status: u16,
+    reason: &str,
+    extra: &[(&str, &str)],
+) -> io::Result<()> {
+    let length = reason.len().to_string();
+    let mut headers = vec![
+        ("Content-Type", "text/plain; charset=utf-8"),
+        ("Content-Length", length.as_str()),
+    ];
+    headers.extend_from_slice(extra);
+    write_head(stream, site, status, reason, &headers)?;
+    stream.write_all(reason.as_bytes())
+}
+
+// 百分号解码 URL 路径，解码后不是 UTF-8 时为 None
+fn decode_path(raw: &str) -> Option<String> {
+    let bytes = raw.as_bytes();
+    let mut output = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'%' {
+            let hex = raw.get(index + 1..index + 3)?;
+            output.push(u8::from_str_radix(hex, 16).ok()?);
+            index += 3;
+        } else {
+            output.push(bytes[index]);
+            index += 1;
+        }
+    }
+    String::from_utf8(output).ok()
+}
+
+// 把 URL 路径映射到根目录下的文件；含 .. 、反斜杠或 NUL 的路径拒绝。
+// 根目录内的符号链接只在指向根目录之内时跟随，指向外面的同样拒绝，不会越出根目录
+fn resolve(root: &Path, url_path: &str) -> Option<PathBuf> {
+    let mut path = root.to_path_buf();
+    for segment in url_path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => return None,
+            _ if segment.contains(['\\', '\0']) => return None,
+            _ => path.push(segment),
+        }
+    }
+    // 不存在的路径留给调用方返回 404 或交给 index.html
+    match path.canonicalize() {
+        Ok(real) if !real.starts_with(root) => None,
+        _ => Some(path),
+    }
+}
+
+// 目录在前、文件在后，各自按名称排序
+fn listing(dir: &Path, url_path: &str) -> io::Result<String> {
+    let mut entries: Vec<(bool, String, u64, Option<SystemTime>)> = fs::read_dir(dir)?
+        .filter_map(Result::ok)
+        .map(|entry| {
+            let metadata = entry.metadata().ok();
+            (
+                entry.path().is_dir(),
+                entry.file_name().to_string_lossy().into_owned(),
+                metadata.as_ref().map_or(0, |metadata| metadata.len()),
+                metadata.and_then(|metadata| metadata.modified().ok()),
+            )
+        })
+        .collect();
+    entries.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+
+    let title = format!("Index of {}", escape_html(url_path));
+    let mut page = format!(
+        "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>{0}</title>\n<style>body{{font-family:sans-serif;margin:2em}}td{{padding:2px 16px 2px 0}}td.size{{text-align:right}}</style></head>\n<body>\n<h1>{0}</h1>\n<table>\n",
+        title
+    );
+    if url_path != "/" {
+        page.push_str("<tr><td><a href=\"../\">../</a></td><td></td><td></td></tr>\n");
+    }
+    for (is_dir, name, size, modified) in entries {
+        let (href, label, size) = if is_dir {
+            (
+                format!("{}/", percent_encode(&name)),
+                format!("{}/", name),
+                "-".to_string(),
+            )
+        } else {
+            (percent_encode(&name), name, human_bytes(size))
+        };
+        let modified = modified
+            .map(|time| {
+                DateTime::<Local>::from(time)
+                    .format("%Y-%m-%d %H:%M")
+                    .to_string()
+            })
+            .unwrap_or_default();
+        page.push_str(&format!(
+            "<tr><td><a href=\"{}\">{}</a></td><td class=\"size\">{}</td><td>{}</td></tr>\n",
+            href,
+            escape_html(&label),
+            size,
+            modified
+        ));
+    }
+    page.push_str("</table>\n</body>\n</html>\n");
+    Ok(page)
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// RFC 7231 的 HTTP 日期，例如 Sun, 06 Nov 1994 08:49:37 GMT
+fn http_date(time: SystemTime) -> String {
+    DateTime::<Utc>::from(time)
+        .format("%a, %d %b %Y %H:%M:%S GMT")
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_and_resolve() {
+        let root = Path::new("/srv/www");
+        assert_eq!(decode_path("/a%20b/%E6%96%87.txt").unwrap(), "/a b/文.txt");
+        assert_eq!(decode_path("/bad%2"), None);
+        assert_eq!(decode_path("/%FF"), None);
+        assert_eq!(
+            resolve(root, "/assets/./app.js").unwrap(),
+            Path::new("/srv/www/assets/app.js")
+        );
+        assert_eq!(resolve(root, "/").unwrap(), root);
+        assert_eq!(resolve(root, "/../etc/passwd"), None);
+        assert_eq!(resolve(root, "/a\\..\\b"), None);
+    }
+
+    #[test]
+    fn test_serve_directory() {
+        let root = std::env::temp_dir().join(format!("rtoolkit-serve-{}", std::process::id()));
+        fs::create_dir_all(root.join("docs")).unwrap();
+        fs::write(root.join(INDEX_FILE), "<p>app</p>").unwrap();
+        fs::write(root.join("docs/a&b.css"), "body{}").unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let site = Arc::new(Site {
+            root: root.canonicalize().unwrap(),
+            listing: true,
+            auth: Some(format!("Basic {}", STANDARD.encode("u:p"))),
+            cors: true,
+            spa: true,
+        });
+        thread::spawn(move || serve(listener, site));
+
+        let request = |method: &str, path: &str, auth: bool| {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            let authorization = if auth {
+                "Authorization: Basic dTpw\r\n"
+            } else {
+                ""
+            };
+            write!(
+                stream,
+                "{} {} HTTP/1.1\r\nHost: test\r\n{}\r\n",
+                method, path, authorization
+            )
+            .unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        };
+        let response = request("GET", "/docs/a%26b.css", true);
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+        assert!(response.contains("Content-Type: text/css; charset=utf-8\r\n"));
+        assert!(response.contains("Access-Control-Allow-Origin: *\r\n"));
+        assert!(response.ends_with("\r\n\r\nbody{}"));
+
+        let response = request("GET", "/docs/", true);
+        assert!(
+            response.contains("<a href=\"a%26b.css\">a&amp;b.css</a>"),
+            "{}",
+            response
+        );
+        assert!(request("GET", "/docs", true).contains("Location: /docs/\r\n"));
+        assert!(request("GET", "/users/42", true).ends_with("<p>app</p>"));
+        assert!(request("GET", "/missing.js", true).starts_with("HTTP/1.1 404"));
+        assert!(request("GET", "/", false).starts_with("HTTP/1.1 401"));
+        assert!(request("OPTIONS", "/", false).starts_with("HTTP/1.1 204"));
+        assert!(request("DELETE", "/", true).starts_with("HTTP/1.1 405"));
+        // 指向根目录外的符号链接不跟随
+        #[cfg(unix)]
+        {
+            let outside = root.with_extension("outside");
+            fs::write(&outside, "secret").unwrap();
+            std::os::unix::fs::symlink(&outside, root.join("leak")).unwrap();
+            std::os::unix::fs::symlink(root.join("docs"), root.join("inside")).unwrap();
+            assert!(request("GET", "/leak", true).starts_with("HTTP/1.1 400"));
+            assert!(request("GET", "/inside/a%26b.css", true).ends_with("body{}"));
+            fs::remove_file(outside).unwrap();
+        }
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,498 @@
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{IpAddr, SocketAddr, TcpListener, TcpStream, UdpSocket};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use qrcode::render::unicode::Dense1x2;
+use qrcode::types::QrError;
+use qrcode::QrCode;
+use rand::Rng;
+
+use crate::utils::a11y;
+use crate::utils::duration::parse_duration;
+use crate::utils::format::human_bytes;
+use crate::utils::http::{content_type, percent_encode};
+use crate::utils::network::{self, NetworkError};
+
+const MAX_CLIENTS: usize = 16;
+const MAX_HEADER_BYTES: u64 = 8 * 1024;
+
+#[derive(clap::Args)]
+pub struct ShareOpts {
+    #[arg(
+        value_name = "FILE",
+        required_unless_present = "text",
+        conflicts_with = "text",
+        help = "要分享的文件"
+    )]
+    file: Option<PathBuf>,
+
+    #[arg(
+        short = 't',
+        long,
+        value_name = "TEXT",
+        help = "直接分享一段文本，为 - 时从 stdin 读取"
+    )]
+    text: Option<String>,
+
+    #[arg(
+        short = 'b',
+        long,
+        default_value = "0.0.0.0",
+        value_name = "ADDR",
+        help = "监听地址"
+    )]
+    bind: String,
+
+    #[arg(
+        short = 'p',
+        long,
+        default_value_t = 0,
+        help = "监听端口，0 表示随机端口"
+    )]
+    port: u16,
+
+    #[arg(
+        long,
+        value_name = "HOST",
+        help = "链接中使用的主机名或 IP，默认自动探测局域网 IP"
+    )]
+    host: Option<String>,
+
+    #[arg(
+        short = 'e',
+        long,
+        default_value = "10m",
+        value_name = "DURATION",
+        help = "分享有效期，例如 30s、10m、1h"
+    )]
+    expire: String,
+
+    #[arg(long, help = "成功下载一次后立即停止分享")]
+    once: bool,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ShareError {
+    #[error("{0} is not a regular file")]
+    NotAFile(PathBuf),
+    #[error("invalid expire duration: {0}, expected e.g. 30s, 10m or 1h")]
+    InvalidExpire(String),
+    #[error("failed to bind {addr}: {source}")]
+    Bind { addr: String, source: io::Error },
+    #[error("qr code generation failed: {0}")]
+    Qr(#[from] QrError),
+    #[error(transparent)]
+    Network(#[from] NetworkError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+enum Payload {
+    File {
+        path: PathBuf,
+        name: String,
+        size: u64,
+    },
+    Text(String),
+}
+
+impl Payload {
+    fn name(&self) -> &str {
+        match self {
+            Payload::File { name, .. } => name,
+            Payload::Text(_) => "text.txt",
+        }
+    }
+
+    fn size(&self) -> u64 {
+        match self {
+            Payload::File { size, .. } => *size,
+            Payload::Text(text) => text.len() as u64,
+        }
+    }
+}
+
+struct Share {
+    payload: Payload,
+    token: String,
+    once: bool,
+    // once 模式下标记是否已有下载在进行或已完成，避免并发请求重复拿到内容
+    claimed: AtomicBool,
+}
+
+pub fn run_share(opts: ShareOpts) -> Result<(), ShareError> {
+    let deadline = parse_duration(&opts.expire)
+        .and_then(|expire| Instant::now().checked_add(expire))
+        .ok_or_else(|| ShareError::InvalidExpire(opts.expire.clone()))?;
+    let payload = match (&opts.file, opts.text.as_deref()) {
+        (Some(path), _) => {
+            let metadata = fs::metadata(path)?;
+            if !metadata.is_file() {
+                return Err(ShareError::NotAFile(path.clone()));
+            }
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| "download".to_string());
+            Payload::File {
+                path: path.clone(),
+                name,
+                size: metadata.len(),
+            }
+        }
+        (None, Some("-")) => {
+            let mut text = String::new();
+            io::stdin().read_to_string(&mut text)?;
+            Payload::Text(text)
+        }
+        (None, Some(text)) => Payload::Text(text.to_string()),
+        (None, None) => unreachable!("clap requires FILE or --text"),
+    };
+
+    network::check_host("share", &opts.bind)?;
+    let addr = format!("{}:{}", opts.bind, opts.port);
+    let listener = TcpListener::bind(&addr).map_err(|source| ShareError::Bind {
+        addr: addr.clone(),
+        source,
+    })?;
+    let local = listener.local_addr()?;
+    let host = opts
+        .host
+        .clone()
+        .unwrap_or_else(|| advertised_host(local.ip()).to_string());
+
+    let share = Arc::new(Share {
+        token: random_token(),
+        payload,
+        once: opts.once,
+        claimed: AtomicBool::new(false),
+    });
+    let url = share_url(&host, local.port(), &share.token, share.payload.name());
+
+    println!(
+        "Sharing {} ({})",
+        share.payload.name(),
+        human_bytes(share.payload.size())
+    );
+    // 读屏软件无法读出二维码，--a11y 时只给出链接
+    if !a11y::is_enabled() {
+        println!("{}", render_qr(&url)?);
+    }
+    println!("URL: {}", url);
+    println!(
+        "Expires in {}{}. Press Ctrl+C to stop.",
+        opts.expire,
+        if opts.once { ", one download only" } else { "" }
+    );
+
+    serve(listener, share, deadline)
+}
+
+fn serve(listener: TcpListener, share: Arc<Share>, deadline: Instant) -> Result<(), ShareError> {
+    // 非阻塞 accept 轮询，便于按时检查有效期和 once 模式的完成信号
+    listener.set_nonblocking(true)?;
+    let (done_tx, done_rx) = mpsc::channel::<SocketAddr>();
+    let active = Arc::new(AtomicUsize::new(0));
+    let mut downloads = 0usize;
+
+    loop {
+        while let Ok(peer) = done_rx.try_recv() {
+            downloads += 1;
+            eprintln!("{} downloaded {}", peer.ip(), share.payload.name());
+            if share.once {
+                println!("Downloaded once, sharing stopped.");
+                return Ok(());
+            }
+        }
+        if Instant::now() >= deadline {
+            println!("Share expired after {} download(s).", downloads);
+            return Ok(());
+        }
+
+        match listener.accept() {
+            Ok((mut stream, peer)) => {
+                // 限制同时处理的连接数，避免局域网内的大量连接耗尽线程
+                if active.load(Ordering::SeqCst) >= MAX_CLIENTS {
+                    let _ = stream.set_nonblocking(false);
+                    let _ = stream.set_write_timeout(Some(Duration::from_secs(1)));
+                    let _ = write_status(&mut stream, 503, "Service Unavailable");
+                    continue;
+                }
+                active.fetch_add(1, Ordering::SeqCst);
+                let share = Arc::clone(&share);
+                let done_tx = done_tx.clone();
+                let active = Arc::clone(&active);
+                thread::spawn(move || {
+                    match handle_client(stream, &share) {
+                        Ok(true) => {
+                            let _ = done_tx.send(peer);
+                        }
+                        Ok(false) => {}
+                        Err(error) => eprintln!("{} request failed: {}", peer.ip(), error),
+                    }
+                    active.fetch_sub(1, Ordering::SeqCst);
+                });
+            }
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                thread::sleep(Duration::from_millis(50));
+            }
+            Err(error) => eprintln!("connection failed: {}", error),
+        }
+    }
+}
+
+// 返回 true 表示完整发送了一次内容
+fn handle_client(mut stream: TcpStream, share: &Share) -> io::Result<bool> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+    // 请求行和请求头合计最多读取 MAX_HEADER_BYTES 字节
+    let mut reader = BufReader::new(stream.try_clone()?.take(MAX_HEADER_BYTES));
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim_end().is_empty() {
+            break;
+        }
+    }
+    if reader.get_ref().limit() == 0 {
+        write_status(&mut stream, 431, "Request Header Fields Too Large")?;
+        return Ok(false);
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let target = parts.next().unwrap_or_default();
+    let prefix = format!("/s/{}", share.token);
+    let matched = target == prefix || target.starts_with(&format!("{}/", prefix));
+    if !matched {
+        write_status(&mut stream, 404, "Not Found")?;
+        return Ok(false);
+    }
+    let head_only = match method {
+        "GET" => false,
+        "HEAD" => true,
+        _ => {
+            write_status(&mut stream, 405, "Method Not Allowed")?;
+            return Ok(false);
+        }
+    };
+
+    if share.once && !head_only && share.claimed.swap(true, Ordering::SeqCst) {
+        write_status(&mut stream, 410, "Gone")?;
+        return Ok(false);
+    }
+    let result = send_payload(&mut stream, &share.payload, head_only);
+    if share.once && !head_only && !matches!(result, Ok(true)) {
+        // 下载中断时释放占用，允许重新下载
+        share.claimed.store(false, Ordering::SeqCst);
+    }
+    result
+}
+
+fn send_payload(stream: &mut TcpStream, payload: &Payload, head_only: bool) -> io::Result<bool> {
+    let (content_type, disposition) = match payload {
+        Payload::File { name, .. } => (content_type(name), content_disposition(name)),
+        Payload::Text(_) => (
+            "text/plain; charset=utf-8",
+            "inline; filename=\"text.txt\"".to_string(),
+        ),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nContent-Disposition: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n",
+        content_type,
+        payload.size(),
+        disposition
+    )?;
+    if head_only {
+        return Ok(false);
+    }
+
+    let sent = match payload {
+        Payload::File { path, .. } => io::copy(&mut File::open(path)?, stream)?,
+        Payload::Text(text) => {
+            stream.write_all(text.as_bytes())?;
+            text.len() as u64
+        }
+    };
+    stream.flush()?;
+    Ok(sent == payload.size())
+}
+
+fn write_status(stream: &mut TcpStream, code: u16, reason: &str) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        code,
+        reason,
+        reason.len(),
+        reason
+    )
+}
+
+// 同时给出 ASCII 回退文件名和 RFC 5987 编码的 UTF-8 文件名，兼容中文文件名
+fn content_disposition(name: &str) -> String {
+    let fallback: String = name
+        .chars()
+        .map(|c| {
+            if (c.is_ascii_graphic() && c != '"' && c != '\\') || c == ' ' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!(
+        "attachment; filename=\"{}\"; filename*=UTF-8''{}",
+        fallback,
+        percent_encode(name)
+    )
+}
+
+fn share_url(host: &str, port: u16, token: &str, name: &str) -> String {
+    let host = if host.contains(':') && !host.starts_with('[') {
+        format!("[{}]", host)
+    } else {
+        host.to_string()
+    };
+    format!(
+        "http://{}:{}/s/{}/{}",
+        host,
+        port,
+        token,
+        percent_encode(name)
+    )
+}
+
+// 监听在通配地址时，通过 UDP connect 找出默认路由使用的本机地址（不会真正发包）
+fn advertised_host(bind_ip: IpAddr) -> IpAddr {
+    if !bind_ip.is_unspecified() {
+        return bind_ip;
+    }
+    UdpSocket::bind("0.0.0.0:0")
+        .and_then(|socket| {
+            socket.connect("8.8.8.8:80")?;
+            socket.local_addr()
+        })
+        .map(|addr| addr.ip())
+        .ok()
+        .filter(|ip| !ip.is_unspecified())
+        .unwrap_or(IpAddr::from([127, 0, 0, 1]))
+}
+
+fn random_token() -> String {
+    const CHARSET: &[u8] = b"abcdefghijkmnpqrstuvwxyz23456789";
+    let mut rng = rand::rng();
+    (0..10)
+        .map(|_| CHARSET[rng.random_range(0..CHARSET.len())] as char)
+        .collect()
+}
+
+// 终端多为深色背景，反转深浅色块让二维码在深色背景上可被扫描
+fn render_qr(data: &str) -> Result<String, ShareError> {
+    let code = QrCode::new(data.as_bytes())?;
+    Ok(code
+        .render::<Dense1x2>()
+        .dark_color(Dense1x2::Light)
+        .light_color(Dense1x2::Dark)
+        .build())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get(addr: SocketAddr, path: &str) -> String {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(stream, "GET {} HTTP/1.1\r\nHost: test\r\n\r\n", path).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    fn start(share: Share) -> (SocketAddr, Arc<Share>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let share = Arc::new(share);
+        let served = Arc::clone(&share);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let _ = handle_client(stream.unwrap(), &served);
+            }
+        });
+        (addr, share)
+    }
+
+    #[test]
+    fn test_serves_text_only_with_token() {
+        let (addr, _) = start(Share {
+            payload: Payload::Text("你好 phone".to_string()),
+            token: "abc123".to_string(),
+            once: false,
+            claimed: AtomicBool::new(false),
+        });
+
+        let response = get(addr, "/s/abc123/text.txt");
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+        assert!(response.contains("Content-Length: 12\r\n"));
+        assert!(response.ends_with("\r\n\r\n你好 phone"));
+        assert!(get(addr, "/s/abc123").starts_with("HTTP/1.1 200 OK"));
+        assert!(get(addr, "/s/abc1234").starts_with("HTTP/1.1 404"));
+        assert!(get(addr, "/").starts_with("HTTP/1.1 404"));
+    }
+
+    #[test]
+    fn test_once_mode_rejects_second_download() {
+        let (addr, share) = start(Share {
+            payload: Payload::Text("secret".to_string()),
+            token: "t0k".to_string(),
+            once: true,
+            claimed: AtomicBool::new(false),
+        });
+
+        assert!(get(addr, "/s/t0k/x").ends_with("secret"));
+        assert!(share.claimed.load(Ordering::SeqCst));
+        assert!(get(addr, "/s/t0k/x").starts_with("HTTP/1.1 410 Gone"));
+    }
+
+    #[test]
+    fn test_rejects_oversized_headers() {
+        let (addr, _) = start(Share {
+            payload: Payload::Text("data".to_string()),
+            token: "tok".to_string(),
+            once: false,
+            claimed: AtomicBool::new(false),
+        });
+
+        // 请求头恰好填满上限且没有结束空行，服务端读完全部数据后返回 431
+        let mut stream = TcpStream::connect(addr).unwrap();
+        let mut request = "GET /s/tok HTTP/1.1\r\nX-Pad: ".to_string();
+        request.push_str(&"a".repeat(MAX_HEADER_BYTES as usize - request.len()));
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 431"), "{}", response);
+    }
+
+    #[test]
+    fn test_url_and_disposition_encoding() {
+        assert_eq!(
+            share_url("192.168.1.5", 8000, "tok", "报告 v1.pdf"),
+            "http://192.168.1.5:8000/s/tok/%E6%8A%A5%E5%91%8A%20v1.pdf"
+        );
+        assert_eq!(
+            share_url("fe80::1", 80, "tok", "a.txt"),
+            "http://[fe80::1]:80/s/tok/a.txt"
+        );
+        assert_eq!(
+            content_disposition("报告.pdf"),
+            "attachment; filename=\"__.pdf\"; filename*=UTF-8''%E6%8A%A5%E5%91%8A.pdf"
+        );
+    }
+}
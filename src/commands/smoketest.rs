@@ -0,0 +1,601 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use clap::ValueEnum;
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+use tokio_rustls::rustls::pki_types::ServerName;
+use tokio_rustls::TlsConnector;
+
+use crate::commands::portscan::is_mysql_handshake;
+use crate::utils::duration::parse_duration;
+use crate::utils::network;
+use crate::utils::output::RecordOutput;
+use crate::utils::{a11y, http, tls};
+
+#[derive(clap::Args)]
+pub struct SmokeTestOpts {
+    #[arg(
+        value_name = "FILE",
+        help = "检查清单（YAML），声明 TCP、HTTP、DNS、证书和数据库检查"
+    )]
+    file: PathBuf,
+
+    #[arg(
+        long,
+        value_name = "DURATION",
+        help = "单项检查的默认超时，例如 5s；清单中的 timeout 优先，默认 5s"
+    )]
+    timeout: Option<String>,
+
+    #[arg(
+        short = 'c',
+        long,
+        default_value_t = 16,
+        value_parser = clap::value_parser!(u16).range(1..=256),
+        help = "同时执行的检查数，范围 1-256"
+    )]
+    concurrency: u16,
+
+    #[arg(
+        long,
+        value_name = "NAMES",
+        value_delimiter = ',',
+        help = "只执行指定名称的检查，多个名称用逗号分隔"
+    )]
+    only: Vec<String>,
+
+    #[arg(
+        value_enum,
+        short = 'o',
+        long = "output",
+        value_name = "FMT",
+        default_value_t = SmokeFormat::Plain,
+        help = "输出格式 plain | json"
+    )]
+    output: SmokeFormat,
+
+    // 全局 --where / --fields / --rename，由命令行入口注入
+    #[arg(skip)]
+    pub(crate) record_output: RecordOutput,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SmokeFormat {
+    Plain,
+    Json,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum SmokeTestError {
+    #[error("failed to read {path}: {source}")]
+    Read { path: PathBuf, source: io::Error },
+    #[error("invalid checks file: {0}")]
+    Checks(#[from] serde_yaml::Error),
+    #[error("invalid timeout '{0}', e.g. 5s or 1m")]
+    InvalidTimeout(String),
+    #[error("no checks to run")]
+    NoChecks,
+    #[error("no check named '{0}'")]
+    UnknownCheck(String),
+    #[error("{failed} of {total} checks failed")]
+    Failed { failed: usize, total: usize },
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ChecksFile {
+    // 所有检查的默认超时
+    timeout: Option<String>,
+    checks: Vec<Check>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Check {
+    // 省略时用目标作为名称
+    name: Option<String>,
+    timeout: Option<String>,
+    #[serde(flatten)]
+    kind: CheckKind,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+enum CheckKind {
+    // 端口可以建立 TCP 连接
+    Tcp {
+        target: String,
+    },
+    // GET 请求返回期望的状态码，可选要求响应体包含指定文本
+    Http {
+        url: String,
+        #[serde(default = "default_status")]
+        status: u16,
+        contains: Option<String>,
+        // 不校验 HTTPS 证书
+        #[serde(default)]
+        insecure: bool,
+    },
+    // 域名可以解析，可选要求解析结果包含指定地址
+    Dns {
+        host: String,
+        #[serde(default)]
+        expect: Vec<IpAddr>,
+    },
+    // 证书剩余有效天数不少于 min_days
+    Cert {
+        target: String,
+        #[serde(default = "default_min_days")]
+        min_days: i64,
+    },
+    // 数据库端口可以连接并完成协议握手的第一步，不做认证
+    Db {
+        engine: DbEngine,
+        target: String,
+    },
+}
+
+fn default_status() -> u16 {
+    200
+}
+
+fn default_min_days() -> i64 {
+    14
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum DbEngine {
+    Mysql,
+    Postgres,
+    Redis,
+}
+
+impl CheckKind {
+    fn label(&self) -> &'static str {
+        match self {
+            CheckKind::Tcp { .. } => "tcp",
+            CheckKind::Http { .. } => "http",
+            CheckKind::Dns { .. } => "dns",
+            CheckKind::Cert { .. } => "cert",
+            CheckKind::Db { .. } => "db",
+        }
+    }
+
+    fn target(&self) -> &str {
+        match self {
+            CheckKind::Tcp { target }
+            | CheckKind::Cert { target, .. }
+            | CheckKind::Db { target, .. } => target,
+            CheckKind::Http { url, .. } => url,
+            CheckKind::Dns { host, .. } => host,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct CheckResult {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub target: String,
+    pub passed: bool,
+    pub elapsed_ms: u64,
+    pub detail: String,
+}
+
+#[derive(Debug, Serialize)]
+struct SmokeReport<'a> {
+    passed: usize,
+    failed: usize,
+    checks: &'a [CheckResult],
+}
+
+pub fn run_smoke_test(opts: SmokeTestOpts) -> Result<(), SmokeTestError> {
+    let text = fs::read_to_string(&opts.file).map_err(|source| SmokeTestError::Read {
+        path: opts.file.clone(),
+        source,
+    })?;
+    let mut file: ChecksFile = serde_yaml::from_str(&text)?;
+    let fallback = opts.timeout.as_deref().or(file.timeout.as_deref());
+    let default_timeout = fallback
+        .map(parse_timeout)
+        .transpose()?
+        .unwrap_or(Duration::from_secs(5));
+    for name in &opts.only {
+        if !file.checks.iter().any(|check| check_name(check) == name) {
+            return Err(SmokeTestError::UnknownCheck(name.clone()));
+        }
+    }
+    if !opts.only.is_empty() {
+        file.checks
+            .retain(|check| opts.only.iter().any(|name| name == check_name(check)));
+    }
+    if file.checks.is_empty() {
+        return Err(SmokeTestError::NoChecks);
+    }
+    let mut checks = Vec::with_capacity(file.checks.len());
+    for check in file.checks {
+        let wait = check
+            .timeout
+            .as_deref()
+            .map(parse_timeout)
+            .transpose()?
+            .unwrap_or(default_timeout);
+        checks.push((check, wait));
+    }
+
+    let rt = tokio::runtime::Runtime::new()?;
+    let mut results = rt.block_on(run_checks(checks, usize::from(opts.concurrency)));
+
+    let failed = results.iter().filter(|result| !result.passed).count();
+    let total = results.len();
+    // --where 只影响输出，汇总和退出状态仍按全部检查计算
+    opts.record_output.retain(&mut results)?;
+    let mut out = BufWriter::new(io::stdout().lock());
+    match opts.output {
+        SmokeFormat::Json => {
+            let report = SmokeReport {
+                passed: total - failed,
+                failed,
+                checks: &results,
+            };
+            opts.record_output
+                .write_report(&report, Some("checks"), &mut out)?;
+        }
+        SmokeFormat::Plain if a11y::is_enabled() => write_labeled(&results, &mut out)?,
+        SmokeFormat::Plain => write_matrix(&results, &mut out)?,
+    }
+    out.flush()?;
+    // 有任何检查失败时以非零状态退出，可以直接作为部署后的环境闸门
+    if failed > 0 {
+        return Err(SmokeTestError::Failed { failed, total });
+    }
+    Ok(())
+}
+
+fn parse_timeout(raw: &str) -> Result<Duration, SmokeTestError> {
+    parse_duration(raw).ok_or_else(|| SmokeTestError::InvalidTimeout(raw.to_string()))
+}
+
+fn check_name(check: &Check) -> &str {
+    check.name.as_deref().unwrap_or_else(|| check.kind.target())
+}
+
+// 并发执行，结果保持清单中的顺序
+async fn run_checks(checks: Vec<(Check, Duration)>, concurrency: usize) -> Vec<CheckResult> {
+    stream::iter(checks)
+        .map(|(check, wait)| async move {
+            let started = Instant::now();
+            let outcome = match timeout(wait, run_check(&check.kind)).await {
+                Ok(outcome) => outcome,
+                Err(_) => Err(format!("timed out after {}s", wait.as_secs())),
+            };
+            let (passed, detail) = match outcome {
+                Ok(detail) => (true, detail),
+                Err(detail) => (false, detail),
+            };
+            CheckResult {
+                name: check_name(&check).to_string(),
+                kind: check.kind.label(),
+                target: check.kind.target().to_string(),
+                passed,
+                elapsed_ms: started.elapsed().as_millis() as u64,
+                detail,
+            }
+        })
+        .buffered(concurrency)
+        .collect()
+        .await
+}
+
+// 成功和失败都返回一句说明，显示在结果矩阵的 DETAIL 列
+async fn run_check(kind: &CheckKind) -> Result<String, String> {
+    match kind {
+        CheckKind::Tcp { target } => {
+            connect(target).await?;
+            Ok("connected".to_string())
+        }
+        CheckKind::Http {
+            url,
+            status,
+            contains,
+            insecure,
+        } => {
+            let url = http::Url::parse(url).map_err(|e| e.to_string())?;
+            network::check_host("smoketest", &url.host).map_err(|e| e.to_string())?;
+            let response = http::get(&url, *insecure)
+                .await
+                .map_err(|e| e.to_string())?;
+            if response.status != *status {
+                return Err(format!("status {}, expected {}", response.status, status));
+            }
+            if let Some(text) = contains {
+                if !String::from_utf8_lossy(&response.body).contains(text.as_str()) {
+                    return Err(format!(
+                        "status {}, body does not contain {:?}",
+                        status, text
+                    ));
+                }
+            }
+            Ok(format!("status {}", response.status))
+        }
+        CheckKind::Dns { host, expect } => {
+            network::check_host("smoketest", host).map_err(|e| e.to_string())?;
+            let addrs: BTreeSet<IpAddr> = tokio::net::lookup_host((host.as_str(), 0))
+                .await
+                .map_err(|e| e.to_string())?
+                .map(|addr| addr.ip())
+                .collect();
+            let listed = addrs
+                .iter()
+                .map(IpAddr::to_string)
+                .collect::<Vec<_>>()
+                .join(", ");
+            if let Some(missing) = expect.iter().find(|addr| !addrs.contains(addr)) {
+                return Err(format!("resolved to {}, missing {}", listed, missing));
+            }
+            Ok(listed)
+        }
+        CheckKind::Cert { target, min_days } => {
+            let stream = connect(target).await?;
+            let host = split_host(target);
+            // 主机名作为 SNI 发送，取到的是该域名实际使用的证书
+            let server_name = match host.parse::<IpAddr>() {
+                Ok(ip) => ServerName::IpAddress(ip.into()),
+                Err(_) => ServerName::try_from(host.to_string()).map_err(|e| e.to_string())?,
+            };
+            let connector = TlsConnector::from(Arc::new(tls::insecure_client_config()));
+            let info = tls::peer_certificate(&connector, server_name, stream)
+                .await
+                .ok_or("TLS handshake failed")?;
+            let detail = format!("expires in {} days ({})", info.days_left, info.not_after);
+            if info.days_left < *min_days {
+                return Err(format!("{}, minimum {}", detail, min_days));
+            }
+            Ok(detail)
+        }
+        CheckKind::Db { engine, target } => {
+            let mut stream = connect(target).await?;
+            db_handshake(*engine, &mut stream).await
+        }
+    }
+}
+
+async fn connect(target: &str) -> Result<TcpStream, String> {
+    network::check_endpoint("smoketest", target).map_err(|e| e.to_string())?;
+    TcpStream::connect(target).await.map_err(|e| e.to_string())
+}
+
+// host:port 中的主机部分，IPv6 去掉方括号
+fn split_host(target: &str) -> &str {
+    let host = target.rsplit_once(':').map_or(target, |(host, _)| host);
+    host.trim_start_matches('[').trim_end_matches(']')
+}
+
+// 只验证对端确实是期望的数据库服务，认证失败之类的应答同样说明服务可达
+async fn db_handshake(engine: DbEngine, stream: &mut TcpStream) -> Result<String, String> {
+    let mut buf = [0u8; 512];
+    match engine {
+        DbEngine::Mysql => {
+            let n = stream.read(&mut buf).await.map_err(|e| e.to_string())?;
+            if is_mysql_handshake(&buf[..n]) {
+                let version = buf[5..n].split(|&b| b == 0).next().unwrap_or_default();
+                return Ok(format!("mysql {}", String::from_utf8_lossy(version)));
+            }
+            // 0xff 为错误包，例如主机不在允许列表中
+            if n > 7 && buf[4] == 0xff {
+                return Err(format!(
+                    "mysql error: {}",
+                    String::from_utf8_lossy(&buf[7..n])
+                ));
+            }
+            Err("not a MySQL handshake".to_string())
+        }
+        DbEngine::Postgres => {
+            // SSLRequest：服务端回复单个字节 S 或 N
+            stream
+                .write_all(&[0, 0, 0, 8, 0x04, 0xd2, 0x16, 0x2f])
+                .await
+                .map_err(|e| e.to_string())?;
+            let n = stream.read(&mut buf).await.map_err(|e| e.to_string())?;
+            match buf[..n] {
+                [b'S'] => Ok("postgres (ssl available)".to_string()),
+                [b'N'] => Ok("postgres (no ssl)".to_string()),
+                _ => Err("not a PostgreSQL server".to_string()),
+            }
+        }
+        DbEngine::Redis => {
+            stream
+                .write_all(b"PING\r\n")
+                .await
+                .map_err(|e| e.to_string())?;
+            let n = stream.read(&mut buf).await.map_err(|e| e.to_string())?;
+            let reply = String::from_utf8_lossy(&buf[..n]);
+            if reply.starts_with("+PONG") {
+                Ok("redis PONG".to_string())
+            } else if reply.starts_with("-NOAUTH") {
+                Ok("redis (authentication required)".to_string())
+            } else {
+                Err(format!("unexpected reply {:?}", reply.trim_end()))
+            }
+        }
+    }
+}
+
+fn write_matrix<W: Write>(results: &[CheckResult], out: &mut W) -> io::Result<()> {
+    let width = |header: &str, column: fn(&CheckResult) -> &str| {
+        results
+            .iter()
+            .map(|result| column(result).chars().count())
+            .chain([header.len()])
+            .max()
+            .unwrap_or_default()
+    };
+    let name_width = width("NAME", |result| &result.name);
+    let target_width = width("TARGET", |result| &result.target);
+    writeln!(
+        out,
+        "STATUS  {:<name_width$}  TYPE  {:<target_width$}  {:>7}  DETAIL",
+        "NAME", "TARGET", "TIME"
+    )?;
+    for result in results {
+        writeln!(
+            out,
+            "{:<6}  {:<name_width$}  {:<4}  {:<target_width$}  {:>5}ms  {}",
+            if result.passed { "PASS" } else { "FAIL" },
+            result.name,
+            result.kind,
+            result.target,
+            result.elapsed_ms,
+            result.detail
+        )?;
+    }
+    write_totals(results, out)
+}
+
+// --a11y：每项检查一行，状态写成完整的单词放在行首
+fn write_labeled<W: Write>(results: &[CheckResult], out: &mut W) -> io::Result<()> {
+    for result in results {
+        let status = if result.passed { "passed" } else { "failed" };
+        writeln!(
+            out,
+            "{}",
+            a11y::line(&[
+                ("status", &status),
+                ("name", &result.name),
+                ("type", &result.kind),
+                ("target", &result.target),
+                ("time", &format!("{} ms", result.elapsed_ms)),
+                ("detail", &result.detail),
+            ])
+        )?;
+    }
+    write_totals(results, out)
+}
+
+fn write_totals<W: Write>(results: &[CheckResult], out: &mut W) -> io::Result<()> {
+    let failed = results.iter().filter(|result| !result.passed).count();
+    writeln!(
+        out,
+        "\nPassed: {}  Failed: {}  Total: {}",
+        results.len() - failed,
+        failed,
+        results.len()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checks_file_rejects_unknown_fields() {
+        let file: ChecksFile = serde_yaml::from_str(
+            "timeout: 3s\nchecks:\n  - type: http\n    url: http://a.test/\n  - name: db\n    type: db\n    engine: redis\n    target: a.test:6379\n",
+        )
+        .unwrap();
+        assert!(matches!(
+            &file.checks[0].kind,
+            CheckKind::Http { status: 200, .. }
+        ));
+        assert_eq!(check_name(&file.checks[0]), "http://a.test/");
+        assert_eq!(check_name(&file.checks[1]), "db");
+        assert!(serde_yaml::from_str::<ChecksFile>(
+            "checks:\n  - type: tcp\n    taget: a.test:80\n"
+        )
+        .is_err());
+        assert!(serde_yaml::from_str::<ChecksFile>("checks:\n  - type: ftp\n").is_err());
+        assert_eq!(split_host("[::1]:443"), "::1");
+    }
+
+    #[test]
+    fn test_run_checks_matrix() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            // 同一个端口既回应 HTTP 又回应 Redis PING，按收到的第一行区分
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let port = listener.local_addr().unwrap().port();
+            tokio::spawn(async move {
+                while let Ok((mut conn, _)) = listener.accept().await {
+                    tokio::spawn(async move {
+                        let mut buf = [0u8; 1024];
+                        let n = conn.read(&mut buf).await.unwrap_or(0);
+                        let reply: &[u8] = if buf[..n].starts_with(b"PING") {
+                            b"+PONG\r\n"
+                        } else {
+                            b"HTTP/1.1 200 OK\r\nContent-Length: 9\r\n\r\nstatus:ok"
+                        };
+                        let _ = conn.write_all(reply).await;
+                    });
+                }
+            });
+            let closed = std::net::TcpListener::bind("127.0.0.1:0")
+                .unwrap()
+                .local_addr()
+                .unwrap()
+                .port();
+            let yaml = format!(
+                "checks:
+  - {{name: tcp, type: tcp, target: '127.0.0.1:{port}'}}
+  - {{name: http, type: http, url: 'http://127.0.0.1:{port}/health', contains: ok}}
+  - {{name: wrong-status, type: http, url: 'http://127.0.0.1:{port}/', status: 204}}
+  - {{name: redis, type: db, engine: redis, target: '127.0.0.1:{port}'}}
+  - {{name: postgres, type: db, engine: postgres, target: '127.0.0.1:{port}'}}
+  - {{name: closed, type: tcp, target: '127.0.0.1:{closed}'}}
+  - {{name: dns, type: dns, host: localhost, expect: [127.0.0.1]}}
+"
+            );
+            let file: ChecksFile = serde_yaml::from_str(&yaml).unwrap();
+            let checks = file
+                .checks
+                .into_iter()
+                .map(|check| (check, Duration::from_secs(2)))
+                .collect();
+            let results = run_checks(checks, 4).await;
+            let passed: Vec<(&str, bool)> = results
+                .iter()
+                .map(|result| (result.name.as_str(), result.passed))
+                .collect();
+            assert_eq!(
+                passed,
+                [
+                    ("tcp", true),
+                    ("http", true),
+                    ("wrong-status", false),
+                    ("redis", true),
+                    ("postgres", false),
+                    ("closed", false),
+                    ("dns", true),
+                ]
+            );
+            assert_eq!(results[2].detail, "status 200, expected 204");
+            assert_eq!(results[3].detail, "redis PONG");
+
+            let mut plain = Vec::new();
+            write_matrix(&results, &mut plain).unwrap();
+            let plain = String::from_utf8(plain).unwrap();
+            assert!(plain.starts_with("STATUS  NAME "));
+            assert!(plain.contains("\nFAIL    wrong-status  http  "));
+            assert!(plain.ends_with("Passed: 4  Failed: 3  Total: 7\n"));
+
+            let mut labeled = Vec::new();
+            write_labeled(&results, &mut labeled).unwrap();
+            let labeled = String::from_utf8(labeled).unwrap();
+            assert!(labeled.contains("\nstatus: failed, name: wrong-status, type: http, target: "));
+            assert!(labeled.contains(", detail: status 200, expected 204\n"));
+        });
+    }
+}
@@ -0,0 +1,846 @@
+use std::env;
+use std::fmt;
+use std::io::{self, Read};
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chrono::{DateTime, Local};
+use clap::{Subcommand, ValueEnum};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+use tokio_rustls::rustls::pki_types::ServerName;
+use tokio_rustls::TlsConnector;
+
+use crate::utils::format::human_bytes;
+use crate::utils::http::HttpStream;
+use crate::utils::network::{self, NetworkError};
+use crate::utils::tls::{self, TlsCertInfo};
+
+const PASSWORD_ENV: &str = "RTOOLKIT_SMTP_PASSWORD";
+
+// 单行应答的上限，RFC 5321 规定 512 字节，留足余量
+const MAX_LINE_BYTES: usize = 8192;
+
+#[derive(clap::Args)]
+pub struct SmtpOpts {
+    #[arg(
+        short = 's',
+        long,
+        value_name = "HOST[:PORT]",
+        help = "SMTP 服务器，端口默认 25，--starttls 时默认 587，--tls 时默认 465"
+    )]
+    server: String,
+
+    #[arg(
+        long,
+        conflicts_with = "tls",
+        help = "发送 STARTTLS 升级为加密连接，服务器不支持时报错"
+    )]
+    starttls: bool,
+
+    #[arg(long, help = "连接后立即进行 TLS 握手（SMTPS，通常为 465 端口）")]
+    tls: bool,
+
+    #[arg(
+        short = 'u',
+        long,
+        value_name = "USER",
+        help = "认证用户名，指定后在握手完成后进行 AUTH"
+    )]
+    user: Option<String>,
+
+    #[arg(
+        short = 'p',
+        long,
+        value_name = "PASSWORD",
+        requires = "user",
+        help = "认证密码；也可通过环境变量 RTOOLKIT_SMTP_PASSWORD 传入，避免出现在 shell 历史中"
+    )]
+    password: Option<String>,
+
+    #[arg(
+        value_enum,
+        long,
+        value_name = "MECH",
+        help = "认证方式，默认优先 PLAIN，服务器不支持时用 LOGIN"
+    )]
+    auth: Option<AuthMechanism>,
+
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "EHLO 时报告的本机名称，默认为本地 IP 地址字面量，例如 [192.168.1.23]"
+    )]
+    helo: Option<String>,
+
+    #[arg(
+        long = "timeout",
+        value_name = "MS",
+        default_value_t = 10000,
+        help = "连接和等待每个应答的超时时间（毫秒）"
+    )]
+    time_out: u64,
+
+    #[arg(short = 'k', long, help = "不校验服务器证书，用于自签名证书的测试环境")]
+    insecure: bool,
+
+    #[arg(
+        short = 'v',
+        long,
+        help = "在 stderr 显示完整的会话记录，认证信息以 * 代替"
+    )]
+    verbose: bool,
+
+    #[command(subcommand)]
+    command: Option<SmtpCommand>,
+}
+
+#[derive(Subcommand)]
+enum SmtpCommand {
+    #[command(about = "握手和认证通过后发送一封测试邮件")]
+    Send(SendOpts),
+}
+
+#[derive(clap::Args)]
+struct SendOpts {
+    #[arg(
+        long,
+        value_name = "ADDR",
+        help = "发件人地址，默认使用 --user（需为邮箱地址）"
+    )]
+    from: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "ADDR",
+        required = true,
+        value_delimiter = ',',
+        help = "收件人地址，可重复或逗号分隔"
+    )]
+    to: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "TEXT",
+        default_value = "rtoolkit SMTP test",
+        help = "邮件主题"
+    )]
+    subject: String,
+
+    #[arg(
+        long,
+        value_name = "TEXT",
+        conflicts_with = "body_file",
+        help = "邮件正文，默认为包含发送时间和服务器的说明文字"
+    )]
+    body: Option<String>,
+
+    #[arg(
+        long = "body-file",
+        value_name = "FILE",
+        help = "以文件内容作为正文，- 表示 stdin"
+    )]
+    body_file: Option<PathBuf>,
+
+    #[arg(
+        short = 'H',
+        long = "header",
+        value_name = "NAME: VALUE",
+        help = "添加或覆盖邮件头，可重复，例如 -H 'Reply-To: ops@example.com'"
+    )]
+    headers: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum AuthMechanism {
+    Plain,
+    Login,
+}
+
+impl AuthMechanism {
+    fn name(self) -> &'static str {
+        match self {
+            AuthMechanism::Plain => "PLAIN",
+            AuthMechanism::Login => "LOGIN",
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum SmtpError {
+    #[error("invalid server '{0}', expected host[:port]")]
+    InvalidServer(String),
+    #[error("{0} timed out")]
+    Timeout(String),
+    #[error("{command} rejected: {reply}")]
+    Rejected { command: String, reply: Reply },
+    #[error("server does not support STARTTLS")]
+    NoStartTls,
+    #[error("server does not offer AUTH; many servers only allow it after --starttls or --tls")]
+    NoAuth,
+    #[error("server offers no supported AUTH mechanism (PLAIN, LOGIN): {0}")]
+    UnsupportedAuth(String),
+    #[error("--from is required unless --user is an email address")]
+    MissingFrom,
+    #[error("invalid header '{0}', expected 'Name: value'")]
+    InvalidHeader(String),
+    #[error("all recipients were rejected")]
+    NoRecipients,
+    #[error("invalid SMTP reply: {0}")]
+    Protocol(String),
+    #[error("TLS handshake failed: {0} (use -k to accept self-signed certificates)")]
+    Tls(io::Error),
+    #[error(transparent)]
+    Network(#[from] NetworkError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+// 服务器应答：三位状态码和一行或多行文本（多行应答的 250-xxx 续行）
+#[derive(Debug, Clone)]
+pub struct Reply {
+    pub code: u16,
+    pub lines: Vec<String>,
+}
+
+impl Reply {
+    fn text(&self) -> String {
+        self.lines.join(" ")
+    }
+}
+
+impl fmt::Display for Reply {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.code, self.text())
+    }
+}
+
+pub fn run_smtp(opts: SmtpOpts) -> Result<(), SmtpError> {
+    let default_port = match (opts.tls, opts.starttls) {
+        (true, _) => 465,
+        (_, true) => 587,
+        _ => 25,
+    };
+    let (host, port) = parse_server(&opts.server, default_port)
+        .ok_or_else(|| SmtpError::InvalidServer(opts.server.clone()))?;
+    network::check_host("smtp", &host)?;
+    let password = match &opts.user {
+        Some(_) => Some(
+            opts.password
+                .clone()
+                .or_else(|| env::var(PASSWORD_ENV).ok())
+                .unwrap_or_default(),
+        ),
+        None => None,
+    };
+    // 发送前先准备好邮件，正文来自 stdin 时不必等连接建立
+    let message = match &opts.command {
+        Some(SmtpCommand::Send(send)) => Some(prepare(send, &opts, &host)?),
+        None => None,
+    };
+    let rt = tokio::runtime::Runtime::new()?;
+    rt.block_on(session(&opts, &host, port, password.as_deref(), message))
+}
+
+// 待发送的邮件：信封地址和已编码的正文
+struct Outgoing {
+    from: String,
+    to: Vec<String>,
+    data: String,
+}
+
+fn prepare(send: &SendOpts, opts: &SmtpOpts, host: &str) -> Result<Outgoing, SmtpError> {
+    let from = match (&send.from, &opts.user) {
+        (Some(from), _) => from.clone(),
+        (None, Some(user)) if user.contains('@') => user.clone(),
+        _ => return Err(SmtpError::MissingFrom),
+    };
+    let headers = send
+        .headers
+        .iter()
+        .map(|raw| {
+            raw.split_once(':')
+                .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+                .filter(|(name, _)| !name.is_empty() && !name.contains(' '))
+                .ok_or_else(|| SmtpError::InvalidHeader(raw.clone()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let now = Local::now();
+    let body = match (&send.body, &send.body_file) {
+        (Some(body), _) => body.clone(),
+        (None, Some(path)) if path.as_os_str() == "-" => {
+            let mut body = String::new();
+            io::stdin().read_to_string(&mut body)?;
+            body
+        }
+        (None, Some(path)) => std::fs::read_to_string(path)?,
+        (None, None) => format!(
+            "This is a test message sent by rtoolkit smtp via {} at {}.\n",
+            host,
+            now.format("%Y-%m-%d %H:%M:%S %:z")
+        ),
+    };
+    let data = build_message(&from, &send.to, &send.subject, &body, &headers, now);
+    Ok(Outgoing {
+        from,
+        to: send.to.clone(),
+        data,
+    })
+}
+
+async fn session(
+    opts: &SmtpOpts,
+    host: &str,
+    port: u16,
+    password: Option<&str>,
+    message: Option<Outgoing>,
+) -> Result<(), SmtpError> {
+    let wait = Duration::from_millis(opts.time_out);
+    let started = Instant::now();
+    let tcp = timeout(wait, TcpStream::connect((host, port)))
+        .await
+        .map_err(|_| SmtpError::Timeout(format!("connecting to {}:{}", host, port)))??;
+    let peer = tcp.peer_addr()?;
+    let helo = opts
+        .helo
+        .clone()
+        .unwrap_or_else(|| address_literal(tcp.local_addr().ok()));
+    let mut stream: Box<dyn HttpStream> = Box::new(tcp);
+    let mut cert = None;
+    if opts.tls {
+        let (tls_stream, info) = start_tls(stream, host, opts.insecure).await?;
+        stream = tls_stream;
+        cert = info;
+    }
+    println!(
+        "Connected:    {}:{} ({}) in {} ms",
+        host,
+        port,
+        peer,
+        started.elapsed().as_millis()
+    );
+    if let Some(info) = &cert {
+        print_tls("implicit TLS", info);
+    }
+
+    let mut conn = Conn::new(stream, wait, opts.verbose);
+    let banner = conn.reply("greeting").await?;
+    println!("Banner:       {}", banner);
+    if banner.code != 220 {
+        return Err(SmtpError::Rejected {
+            command: "connection".to_string(),
+            reply: banner,
+        });
+    }
+    let mut capabilities = conn.hello(&helo).await?;
+
+    if opts.starttls {
+        if !has_capability(&capabilities, "STARTTLS") {
+            return Err(SmtpError::NoStartTls);
+        }
+        conn.expect("STARTTLS", 220).await?;
+        let (upgraded, info) = conn.upgrade(host, opts.insecure).await?;
+        conn = upgraded;
+        if let Some(info) = &info {
+            print_tls("STARTTLS", info);
+        }
+        // 升级后服务器会丢弃之前的状态，必须重新 EHLO，能力列表也可能变化（例如此时才提供 AUTH）
+        capabilities = conn.hello(&helo).await?;
+    }
+
+    println!("Capabilities:");
+    if capabilities.is_empty() {
+        println!("  (none, server only supports HELO)");
+    }
+    for capability in &capabilities {
+        println!("  {}", describe_capability(capability));
+    }
+
+    if let (Some(user), Some(password)) = (&opts.user, password) {
+        let mechanism = choose_mechanism(&capabilities, opts.auth)?;
+        if !opts.tls && !opts.starttls {
+            eprintln!("warning: sending credentials over an unencrypted connection");
+        }
+        let reply = conn.authenticate(mechanism, user, password).await?;
+        println!("Auth:         {} as {}: {}", mechanism.name(), user, reply);
+    }
+
+    if let Some(message) = message {
+        send_message(&mut conn, &message).await?;
+    }
+    // 服务器对 QUIT 的应答无关紧要，有的服务器直接断开连接
+    let _ = conn.command("QUIT").await;
+    Ok(())
+}
+
+async fn send_message(conn: &mut Conn, message: &Outgoing) -> Result<(), SmtpError> {
+    conn.expect(&format!("MAIL FROM:<{}>", message.from), 250)
+        .await?;
+    let mut accepted = 0;
+    for rcpt in &message.to {
+        let reply = conn.command(&format!("RCPT TO:<{}>", rcpt)).await?;
+        if reply.code == 250 || reply.code == 251 {
+            accepted += 1;
+        } else {
+            println!("Rejected:     {}: {}", rcpt, reply);
+        }
+    }
+    if accepted == 0 {
+        let _ = conn.command("RSET").await;
+        return Err(SmtpError::NoRecipients);
+    }
+    conn.expect("DATA", 354).await?;
+    let reply = conn.data(&message.data).await?;
+    if reply.code != 250 {
+        return Err(SmtpError::Rejected {
+            command: "message".to_string(),
+            reply,
+        });
+    }
+    println!(
+        "Sent:         {} from {} to {} recipient{}: {}",
+        human_bytes(message.data.len() as u64),
+        message.from,
+        accepted,
+        if accepted == 1 { "" } else { "s" },
+        reply
+    );
+    Ok(())
+}
+
+fn print_tls(how: &str, info: &TlsCertInfo) {
+    println!("TLS:          {} via {}", info.version, how);
+    println!("Certificate:  {}", info.subject);
+    println!(
+        "Expires:      {} ({} days left)",
+        info.not_after, info.days_left
+    );
+}
+
+// 已建立的 SMTP 连接，负责按行收发和会话记录
+struct Conn {
+    stream: BufReader<Box<dyn HttpStream>>,
+    wait: Duration,
+    verbose: bool,
+}
+
+impl Conn {
+    fn new(stream: Box<dyn HttpStream>, wait: Duration, verbose: bool) -> Self {
+        Self {
+            stream: BufReader::new(stream),
+            wait,
+            verbose,
+        }
+    }
+
+    async fn reply(&mut self, waiting_for: &str) -> Result<Reply, SmtpError> {
+        let mut reply = Reply {
+            code: 0,
+            lines: Vec::new(),
+        };
+        loop {
+            let mut line = String::new();
+            let mut limited = (&mut self.stream).take(MAX_LINE_BYTES as u64);
+            let reading = limited.read_line(&mut line);
+            let read = timeout(self.wait, reading).await.map_err(|_| {
+                SmtpError::Timeout(format!("waiting for reply to {}", waiting_for))
+            })??;
+            if read == 0 {
+                return Err(SmtpError::Protocol(
+                    "connection closed by server".to_string(),
+                ));
+            }
+            let line = line.trim_end_matches(['\r', '\n']);
+            if self.verbose {
+                eprintln!("S: {}", line);
+            }
+            // 多行应答的每一行状态码必须相同
+            let (code, more, text) = parse_reply_line(line)
+                .filter(|(code, _, _)| reply.lines.is_empty() || *code == reply.code)
+                .ok_or_else(|| SmtpError::Protocol(line.to_string()))?;
+            reply.code = code;
+            reply.lines.push(text.to_string());
+            if !more {
+                return Ok(reply);
+            }
+        }
+    }
+
+    async fn send_line(&mut self, line: &str, shown: &str) -> Result<(), SmtpError> {
+        if self.verbose {
+            eprintln!("C: {}", shown);
+        }
+        let stream = self.stream.get_mut();
+        stream.write_all(format!("{}\r\n", line).as_bytes()).await?;
+        stream.flush().await?;
+        Ok(())
+    }
+
+    async fn command(&mut self, line: &str) -> Result<Reply, SmtpError> {
+        self.send_line(line, line).await?;
+        self.reply(verb(line)).await
+    }
+
+    async fn expect(&mut self, line: &str, code: u16) -> Result<Reply, SmtpError> {
+        let reply = self.command(line).await?;
+        if reply.code != code {
+            return Err(SmtpError::Rejected {
+                command: verb(line).to_string(),
+                reply,
+            });
+        }
+        Ok(reply)
+    }
+
+    // EHLO 并返回能力列表；服务器不支持 EHLO 时退回 HELO，能力列表为空
+    async fn hello(&mut self, helo: &str) -> Result<Vec<String>, SmtpError> {
+        let reply = self.command(&format!("EHLO {}", helo)).await?;
+        if reply.code == 250 {
+            return Ok(reply.lines.into_iter().skip(1).collect());
+        }
+        self.expect(&format!("HELO {}", helo), 250).await?;
+        Ok(Vec::new())
+    }
+
+    async fn upgrade(
+        self,
+        host: &str,
+        insecure: bool,
+    ) -> Result<(Conn, Option<TlsCertInfo>), SmtpError> {
+        // 220 之后、握手之前不应有任何数据，否则可能是明文命令注入
+        if !self.stream.buffer().is_empty() {
+            return Err(SmtpError::Protocol(
+                "unexpected data after STARTTLS reply".to_string(),
+            ));
+        }
+        let (wait, verbose) = (self.wait, self.verbose);
+        let (stream, info) = start_tls(self.stream.into_inner(), host, insecure).await?;
+        Ok((Conn::new(stream, wait, verbose), info))
+    }
+
+    async fn authenticate(
+        &mut self,
+        mechanism: AuthMechanism,
+        user: &str,
+        password: &str,
+    ) -> Result<Reply, SmtpError> {
+        let reply = match mechanism {
+            AuthMechanism::Plain => {
+                let token = BASE64.encode(format!("\0{}\0{}", user, password));
+                self.send_line(&format!("AUTH PLAIN {}", token), "AUTH PLAIN ********")
+                    .await?;
+                self.reply("AUTH").await?
+            }
+            AuthMechanism::Login => {
+                self.expect("AUTH LOGIN", 334).await?;
+                self.send_line(&BASE64.encode(user), "********").await?;
+                let reply = self.reply("AUTH").await?;
+                if reply.code != 334 {
+                    return Err(SmtpError::Rejected {
+                        command: "AUTH".to_string(),
+                        reply,
+                    });
+                }
+                self.send_line(&BASE64.encode(password), "********").await?;
+                self.reply("AUTH").await?
+            }
+        };
+        if reply.code != 235 {
+            return Err(SmtpError::Rejected {
+                command: "AUTH".to_string(),
+                reply,
+            });
+        }
+        Ok(reply)
+    }
+
+    // 发送已按 SMTP 规则转义的正文和结束标记 <CRLF>.<CRLF>
+    async fn data(&mut self, data: &str) -> Result<Reply, SmtpError> {
+        if self.verbose {
+            eprintln!("C: <message, {} bytes>", data.len());
+            eprintln!("C: .");
+        }
+        let stream = self.stream.get_mut();
+        stream.write_all(data.as_bytes()).await?;
+        stream.write_all(b".\r\n").await?;
+        stream.flush().await?;
+        self.reply("message data").await
+    }
+}
+
+async fn start_tls(
+    stream: Box<dyn HttpStream>,
+    host: &str,
+    insecure: bool,
+) -> Result<(Box<dyn HttpStream>, Option<TlsCertInfo>), SmtpError> {
+    let config = if insecure {
+        tls::insecure_client_config()
+    } else {
+        tls::verified_client_config()
+    };
+    let server_name = match host.parse::<IpAddr>() {
+        Ok(ip) => ServerName::IpAddress(ip.into()),
+        Err(_) => ServerName::try_from(host.to_string())
+            .map_err(|_| SmtpError::InvalidServer(host.to_string()))?,
+    };
+    let stream = TlsConnector::from(Arc::new(config))
+        .connect(server_name, stream)
+        .await
+        .map_err(SmtpError::Tls)?;
+    let info = tls::session_certificate(stream.get_ref().1);
+    Ok((Box::new(stream), info))
+}
+
+// "250-SIZE 1000" 解析为 (250, true, "SIZE 1000")，最后一行的分隔符是空格或没有文本
+fn parse_reply_line(line: &str) -> Option<(u16, bool, &str)> {
+    let code = line.get(..3)?;
+    if !code.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let code = code.parse().ok()?;
+    match line.as_bytes().get(3) {
+        None => Some((code, false, "")),
+        Some(b'-') => Some((code, true, &line[4..])),
+        Some(b' ') => Some((code, false, &line[4..])),
+        Some(_) => None,
+    }
+}
+
+fn verb(line: &str) -> &str {
+    match line.split_once(':') {
+        Some((verb, _)) if verb.starts_with("MAIL") || verb.starts_with("RCPT") => verb,
+        _ => line.split(' ').next().unwrap_or(line),
+    }
+}
+
+fn has_capability(capabilities: &[String], name: &str) -> bool {
+    capabilities.iter().any(|capability| {
+        capability
+            .split([' ', '='])
+            .next()
+            .is_some_and(|keyword| keyword.eq_ignore_ascii_case(name))
+    })
+}
+
+// AUTH 能力后列出的认证方式，兼容旧服务器的 AUTH=PLAIN LOGIN 写法
+fn auth_mechanisms(capabilities: &[String]) -> Vec<String> {
+    capabilities
+        .iter()
+        .filter_map(|capability| {
+            let upper = capability.to_ascii_uppercase();
+            upper
+                .strip_prefix("AUTH ")
+                .or_else(|| upper.strip_prefix("AUTH="))
+                .map(str::to_string)
+        })
+        .flat_map(|list| {
+            list.split_whitespace()
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+fn choose_mechanism(
+    capabilities: &[String],
+    wanted: Option<AuthMechanism>,
+) -> Result<AuthMechanism, SmtpError> {
+    let offered = auth_mechanisms(capabilities);
+    if offered.is_empty() {
+        return Err(SmtpError::NoAuth);
+    }
+    let candidates = match wanted {
+        Some(mechanism) => vec![mechanism],
+        None => vec![AuthMechanism::Plain, AuthMechanism::Login],
+    };
+    candidates
+        .into_iter()
+        .find(|mechanism| offered.iter().any(|name| name == mechanism.name()))
+        .ok_or_else(|| SmtpError::UnsupportedAuth(offered.join(" ")))
+}
+
+fn describe_capability(capability: &str) -> String {
+    match capability.split_once(' ') {
+        Some((keyword, size)) if keyword.eq_ignore_ascii_case("SIZE") => match size.parse() {
+            Ok(0) => format!("{} (no limit)", capability),
+            Ok(bytes) => format!("{} ({})", capability, human_bytes(bytes)),
+            Err(_) => capability.to_string(),
+        },
+        _ => capability.to_string(),
+    }
+}
+
+// host、host:port、[v6]:port 或不带方括号的 IPv6 地址
+fn parse_server(raw: &str, default_port: u16) -> Option<(String, u16)> {
+    let (host, port) = if let Some(rest) = raw.strip_prefix('[') {
+        let (host, tail) = rest.split_once(']')?;
+        match tail.strip_prefix(':') {
+            Some(port) => (host, port.parse().ok()?),
+            None if tail.is_empty() => (host, default_port),
+            None => return None,
+        }
+    } else if raw.parse::<IpAddr>().is_ok() {
+        (raw, default_port)
+    } else {
+        match raw.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse().ok()?),
+            None => (raw, default_port),
+        }
+    };
+    if host.is_empty() || host.contains(['/', '@']) {
+        return None;
+    }
+    Some((host.to_string(), port))
+}
+
+// 没有可用的主机名时，RFC 5321 允许 EHLO 使用地址字面量
+fn address_literal(addr: Option<SocketAddr>) -> String {
+    match addr.map(|addr| addr.ip()) {
+        Some(IpAddr::V4(ip)) => format!("[{}]", ip),
+        Some(IpAddr::V6(ip)) => format!("[IPv6:{}]", ip),
+        None => "localhost".to_string(),
+    }
+}
+
+// 组装完整的邮件并按 SMTP DATA 的要求处理：换行统一为 CRLF，以 . 开头的行再加一个 .
+fn build_message(
+    from: &str,
+    to: &[String],
+    subject: &str,
+    body: &str,
+    custom: &[(String, String)],
+    now: DateTime<Local>,
+) -> String {
+    let domain = from
+        .rsplit_once('@')
+        .map_or("localhost", |(_, domain)| domain);
+    let message_id = format!("<{:016x}.rtoolkit@{}>", rand::random::<u64>(), domain);
+    let ascii = body.is_ascii();
+    let mut headers = vec![
+        ("Date".to_string(), now.to_rfc2822()),
+        ("From".to_string(), from.to_string()),
+        ("To".to_string(), to.join(", ")),
+        ("Subject".to_string(), encode_header(subject)),
+        ("Message-ID".to_string(), message_id),
+        ("MIME-Version".to_string(), "1.0".to_string()),
+        (
+            "Content-Type".to_string(),
+            "text/plain; charset=utf-8".to_string(),
+        ),
+        (
+            "Content-Transfer-Encoding".to_string(),
+            if ascii { "7bit" } else { "base64" }.to_string(),
+        ),
+    ];
+    for (name, value) in custom {
+        match headers
+            .iter_mut()
+            .find(|(existing, _)| existing.eq_ignore_ascii_case(name))
+        {
+            Some(header) => header.1 = encode_header(value),
+            None => headers.push((name.clone(), encode_header(value))),
+        }
+    }
+
+    let mut message = String::new();
+    for (name, value) in &headers {
+        message.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    message.push_str("\r\n");
+    let encoded;
+    let body = if ascii {
+        body
+    } else {
+        // 非 ASCII 正文用 base64，不依赖服务器是否支持 8BITMIME
+        let raw = BASE64.encode(body.as_bytes());
+        encoded = raw
+            .as_bytes()
+            .chunks(76)
+            .map(|chunk| String::from_utf8_lossy(chunk).into_owned())
+            .collect::<Vec<_>>()
+            .join("\n");
+        &encoded
+    };
+    for line in body.lines() {
+        if line.starts_with('.') {
+            message.push('.');
+        }
+        message.push_str(line);
+        message.push_str("\r\n");
+    }
+    message
+}
+
+// 含非 ASCII 字符的头部值按 RFC 2047 编码为 =?UTF-8?B?...?=
+fn encode_header(value: &str) -> String {
+    if value.is_ascii() {
+        value.to_string()
+    } else {
+        format!("=?UTF-8?B?{}?=", BASE64.encode(value.as_bytes()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_reply_and_capabilities() {
+        assert_eq!(
+            parse_reply_line("250-SIZE 10240000"),
+            Some((250, true, "SIZE 10240000"))
+        );
+        assert_eq!(parse_reply_line("250 OK"), Some((250, false, "OK")));
+        assert_eq!(parse_reply_line("354"), Some((354, false, "")));
+        assert_eq!(parse_reply_line("hello"), None);
+
+        let capabilities = vec![
+            "PIPELINING".to_string(),
+            "SIZE 10240000".to_string(),
+            "auth=LOGIN".to_string(),
+            "AUTH LOGIN CRAM-MD5".to_string(),
+        ];
+        assert!(has_capability(&capabilities, "pipelining"));
+        assert!(!has_capability(&capabilities, "STARTTLS"));
+        assert_eq!(
+            choose_mechanism(&capabilities, None).unwrap(),
+            AuthMechanism::Login
+        );
+        assert!(matches!(
+            choose_mechanism(&capabilities, Some(AuthMechanism::Plain)),
+            Err(SmtpError::UnsupportedAuth(_))
+        ));
+        assert_eq!(
+            parse_server("[::1]:2525", 25),
+            Some(("::1".to_string(), 2525))
+        );
+        assert_eq!(
+            parse_server("smtp.example.com", 587),
+            Some(("smtp.example.com".to_string(), 587))
+        );
+    }
+
+    #[test]
+    fn test_build_message() {
+        let message = build_message(
+            "ops@example.com",
+            &["a@example.com".to_string(), "b@example.com".to_string()],
+            "测试",
+            "hello\n.hidden line\r\nbye",
+            &[("Subject".to_string(), "override".to_string())],
+            Local::now(),
+        );
+        let (head, body) = message.split_once("\r\n\r\n").unwrap();
+        assert!(head.contains("To: a@example.com, b@example.com\r\n"));
+        assert!(head.contains("Subject: override\r\n"));
+        assert!(head.contains("Message-ID: <"));
+        assert!(head.contains("@example.com>"));
+        assert_eq!(body, "hello\r\n..hidden line\r\nbye\r\n");
+        assert_eq!(encode_header("测试"), "=?UTF-8?B?5rWL6K+V?=");
+    }
+}
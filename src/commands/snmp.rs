@@ -0,0 +1,843 @@
+use std::fmt;
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::{Duration, Instant};
+
+use clap::{Subcommand, ValueEnum};
+use rand::{rng, Rng};
+use serde::Serialize;
+
+use crate::utils::a11y;
+use crate::utils::network::{self, NetworkError};
+use crate::utils::output::RecordOutput;
+
+const TAG_INTEGER: u8 = 0x02;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_NULL: u8 = 0x05;
+const TAG_OID: u8 = 0x06;
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_IP_ADDRESS: u8 = 0x40;
+const TAG_COUNTER32: u8 = 0x41;
+const TAG_GAUGE32: u8 = 0x42;
+const TAG_TIMETICKS: u8 = 0x43;
+const TAG_OPAQUE: u8 = 0x44;
+const TAG_COUNTER64: u8 = 0x46;
+const TAG_NO_SUCH_OBJECT: u8 = 0x80;
+const TAG_NO_SUCH_INSTANCE: u8 = 0x81;
+const TAG_END_OF_MIB_VIEW: u8 = 0x82;
+
+pub const PDU_GET: u8 = 0xA0;
+const PDU_GET_NEXT: u8 = 0xA1;
+const PDU_RESPONSE: u8 = 0xA2;
+const PDU_GET_BULK: u8 = 0xA5;
+
+const MAX_WALK_ROWS: usize = 100_000;
+
+#[derive(clap::Args)]
+pub struct SnmpOpts {
+    #[command(subcommand)]
+    command: SnmpCommand,
+
+    // 全局 --where / --fields / --rename，由命令行入口注入
+    #[arg(skip)]
+    pub(crate) record_output: RecordOutput,
+}
+
+#[derive(Subcommand)]
+enum SnmpCommand {
+    #[command(about = "读取指定 OID 的值")]
+    Get {
+        #[command(flatten)]
+        target: SnmpTarget,
+        #[arg(value_name = "OID", required = true, num_args = 1.., help = "要读取的 OID 列表")]
+        oids: Vec<String>,
+    },
+    #[command(about = "遍历 OID 子树")]
+    Walk {
+        #[command(flatten)]
+        target: SnmpTarget,
+        #[arg(
+            value_name = "OID",
+            default_value = "1.3.6.1.2.1.1",
+            help = "遍历起点 OID，默认 system 子树"
+        )]
+        oid: String,
+    },
+}
+
+#[derive(clap::Args)]
+struct SnmpTarget {
+    #[arg(value_name = "HOST", help = "目标设备地址")]
+    host: String,
+
+    #[arg(
+        short = 'c',
+        long,
+        default_value = "public",
+        value_name = "COMMUNITY",
+        help = "团体名"
+    )]
+    community: String,
+
+    #[arg(
+        value_enum,
+        short = 'v',
+        long = "version",
+        default_value_t = SnmpVersion::V2c,
+        help = "SNMP 版本 v1 | v2c"
+    )]
+    version: SnmpVersion,
+
+    #[arg(short, long, default_value_t = 161, help = "SNMP 端口")]
+    port: u16,
+
+    #[arg(
+        long = "timeout",
+        default_value_t = 2000,
+        value_name = "MS",
+        help = "单次请求超时时间(毫秒)"
+    )]
+    time_out: u64,
+
+    #[arg(long, default_value_t = 1, value_name = "N", help = "超时重试次数")]
+    retries: u32,
+
+    #[arg(long, help = "以 JSON 输出结果")]
+    json: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SnmpVersion {
+    V1,
+    V2c,
+}
+
+impl SnmpVersion {
+    fn wire_value(self) -> i64 {
+        match self {
+            SnmpVersion::V1 => 0,
+            SnmpVersion::V2c => 1,
+        }
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum SnmpError {
+    #[error("invalid oid: {0}")]
+    InvalidOid(String),
+    #[error("cannot resolve host: {0}")]
+    Resolve(String),
+    #[error("request timed out after {0} attempts")]
+    Timeout(u32),
+    #[error("malformed response: {0}")]
+    Decode(String),
+    #[error("agent returned error {status} ({name}) at index {index}")]
+    Agent {
+        status: i64,
+        name: &'static str,
+        index: i64,
+    },
+    #[error(transparent)]
+    Network(#[from] NetworkError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("json serialize failed: {0}")]
+    Serialize(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Oid(pub Vec<u32>);
+
+impl Oid {
+    pub fn parse(text: &str) -> Result<Self, SnmpError> {
+        let arcs = text
+            .trim()
+            .trim_start_matches('.')
+            .split('.')
+            .map(|part| part.parse::<u32>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| SnmpError::InvalidOid(text.to_string()))?;
+        if arcs.len() < 2 || arcs[0] > 2 || (arcs[0] < 2 && arcs[1] >= 40) {
+            return Err(SnmpError::InvalidOid(text.to_string()));
+        }
+        // 前两段合并编码为 arcs[0] * 40 + arcs[1]，必须能放进 u32
+        if first_sub_id(arcs[0], arcs[1]).is_none() {
+            return Err(SnmpError::InvalidOid(text.to_string()));
+        }
+        Ok(Oid(arcs))
+    }
+
+    pub fn starts_with(&self, prefix: &Oid) -> bool {
+        self.0.starts_with(&prefix.0)
+    }
+}
+
+impl fmt::Display for Oid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts: Vec<String> = self.0.iter().map(|arc| arc.to_string()).collect();
+        write!(f, "{}", parts.join("."))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnmpValue {
+    Integer(i64),
+    OctetString(Vec<u8>),
+    Null,
+    Oid(Oid),
+    IpAddress([u8; 4]),
+    Counter32(u32),
+    Gauge32(u32),
+    TimeTicks(u32),
+    Opaque(Vec<u8>),
+    Counter64(u64),
+    NoSuchObject,
+    NoSuchInstance,
+    EndOfMibView,
+}
+
+impl SnmpValue {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            SnmpValue::Integer(_) => "INTEGER",
+            SnmpValue::OctetString(_) => "STRING",
+            SnmpValue::Null => "NULL",
+            SnmpValue::Oid(_) => "OID",
+            SnmpValue::IpAddress(_) => "IpAddress",
+            SnmpValue::Counter32(_) => "Counter32",
+            SnmpValue::Gauge32(_) => "Gauge32",
+            SnmpValue::TimeTicks(_) => "Timeticks",
+            SnmpValue::Opaque(_) => "Opaque",
+            SnmpValue::Counter64(_) => "Counter64",
+            SnmpValue::NoSuchObject => "noSuchObject",
+            SnmpValue::NoSuchInstance => "noSuchInstance",
+            SnmpValue::EndOfMibView => "endOfMibView",
+        }
+    }
+
+    fn is_exception(&self) -> bool {
+        matches!(
+            self,
+            SnmpValue::NoSuchObject | SnmpValue::NoSuchInstance | SnmpValue::EndOfMibView
+        )
+    }
+}
+
+impl fmt::Display for SnmpValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnmpValue::Integer(value) => write!(f, "{}", value),
+            SnmpValue::OctetString(bytes) => match std::str::from_utf8(bytes) {
+                Ok(text)
+                    if !text
+                        .chars()
+                        .any(|c| c.is_control() && c != '\n' && c != '\r') =>
+                {
+                    write!(f, "{}", text)
+                }
+                _ => write!(f, "{}", hex_string(bytes)),
+            },
+            SnmpValue::Null => write!(f, ""),
+            SnmpValue::Oid(oid) => write!(f, "{}", oid),
+            SnmpValue::IpAddress(ip) => write!(f, "{}.{}.{}.{}", ip[0], ip[1], ip[2], ip[3]),
+            SnmpValue::Counter32(value) | SnmpValue::Gauge32(value) => write!(f, "{}", value),
+            SnmpValue::TimeTicks(ticks) => {
+                let total = ticks / 100;
+                write!(
+                    f,
+                    "{} ({}d {:02}:{:02}:{:02}.{:02})",
+                    ticks,
+                    total / 86_400,
+                    total % 86_400 / 3600,
+                    total % 3600 / 60,
+                    total % 60,
+                    ticks % 100
+                )
+            }
+            SnmpValue::Opaque(bytes) => write!(f, "{}", hex_string(bytes)),
+            SnmpValue::Counter64(value) => write!(f, "{}", value),
+            SnmpValue::NoSuchObject | SnmpValue::NoSuchInstance | SnmpValue::EndOfMibView => {
+                write!(f, "{}", self.type_name())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VarBind {
+    pub oid: Oid,
+    pub value: SnmpValue,
+}
+
+#[derive(Serialize)]
+struct VarBindRow {
+    oid: String,
+    #[serde(rename = "type")]
+    kind: &'static str,
+    value: String,
+}
+
+#[derive(Debug)]
+pub struct SnmpResponse {
+    pub request_id: i64,
+    pub error_status: i64,
+    pub error_index: i64,
+    pub varbinds: Vec<VarBind>,
+}
+
+pub fn run_snmp(opts: SnmpOpts) -> Result<(), SnmpError> {
+    match opts.command {
+        SnmpCommand::Get { target, oids } => {
+            let oids = oids
+                .iter()
+                .map(|oid| Oid::parse(oid))
+                .collect::<Result<Vec<_>, _>>()?;
+            let client = SnmpClient::connect(&target)?;
+            let response = client.request(PDU_GET, 0, 0, &oids)?;
+            print_varbinds(&response.varbinds, target.json, &opts.record_output)
+        }
+        SnmpCommand::Walk { target, oid } => {
+            let base = Oid::parse(&oid)?;
+            let client = SnmpClient::connect(&target)?;
+            let varbinds = client.walk(&base)?;
+            print_varbinds(&varbinds, target.json, &opts.record_output)
+        }
+    }
+}
+
+fn print_varbinds(
+    varbinds: &[VarBind],
+    json: bool,
+    output: &RecordOutput,
+) -> Result<(), SnmpError> {
+    let mut rows: Vec<VarBindRow> = varbinds
+        .iter()
+        .map(|vb| VarBindRow {
+            oid: vb.oid.to_string(),
+            kind: vb.value.type_name(),
+            value: vb.value.to_string(),
+        })
+        .collect();
+    output.retain(&mut rows)?;
+
+    if json {
+        output.write_json(&rows, io::stdout().lock())?;
+        return Ok(());
+    }
+
+    if a11y::is_enabled() {
+        for row in &rows {
+            println!(
+                "{}",
+                a11y::line(&[
+                    ("OID", &row.oid),
+                    ("type", &row.kind),
+                    ("value", &row.value)
+                ])
+            );
+        }
+        return Ok(());
+    }
+    let oid_width = rows
+        .iter()
+        .map(|row| row.oid.len())
+        .max()
+        .unwrap_or(3)
+        .max(3);
+    let type_width = rows
+        .iter()
+        .map(|row| row.kind.len())
+        .max()
+        .unwrap_or(4)
+        .max(4);
+    println!("{:<oid_width$}  {:<type_width$}  VALUE", "OID", "TYPE");
+    for row in &rows {
+        println!(
+            "{:<oid_width$}  {:<type_width$}  {}",
+            row.oid, row.kind, row.value
+        );
+    }
+    Ok(())
+}
+
+struct SnmpClient<'a> {
+    socket: UdpSocket,
+    target: &'a SnmpTarget,
+}
+
+impl<'a> SnmpClient<'a> {
+    fn connect(target: &'a SnmpTarget) -> Result<Self, SnmpError> {
+        network::check_host("snmp", &target.host)?;
+        let addr: SocketAddr = (target.host.as_str(), target.port)
+            .to_socket_addrs()
+            .map_err(|e| SnmpError::Resolve(format!("{}: {}", target.host, e)))?
+            .next()
+            .ok_or_else(|| SnmpError::Resolve(target.host.clone()))?;
+        let bind = if addr.is_ipv4() {
+            "0.0.0.0:0"
+        } else {
+            "[::]:0"
+        };
+        let socket = UdpSocket::bind(bind)?;
+        socket.connect(addr)?;
+        Ok(Self { socket, target })
+    }
+
+    fn request(
+        &self,
+        pdu_type: u8,
+        field2: i64,
+        field3: i64,
+        oids: &[Oid],
+    ) -> Result<SnmpResponse, SnmpError> {
+        let request_id = rng().random_range(1..i32::MAX) as i64;
+        let message = encode_request(
+            self.target.version,
+            &self.target.community,
+            pdu_type,
+            request_id,
+            field2,
+            field3,
+            oids,
+        );
+
+        let mut buf = vec![0u8; 65_535];
+        let attempts = self.target.retries + 1;
+        let wait = Duration::from_millis(self.target.time_out.max(1));
+        for _ in 0..attempts {
+            self.socket.send(&message)?;
+            let deadline = Instant::now() + wait;
+            loop {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if remaining.is_zero() {
+                    break;
+                }
+                self.socket.set_read_timeout(Some(remaining))?;
+                match self.socket.recv(&mut buf) {
+                    Ok(len) => {
+                        // 忽略无法解析的报文和迟到的旧响应，继续等待到超时
+                        let Ok(response) = decode_response(&buf[..len]) else {
+                            continue;
+                        };
+                        if response.request_id != request_id {
+                            continue;
+                        }
+                        if response.error_status != 0 {
+                            return Err(SnmpError::Agent {
+                                status: response.error_status,
+                                name: error_status_name(response.error_status),
+                                index: response.error_index,
+                            });
+                        }
+                        return Ok(response);
+                    }
+                    Err(e)
+                        if matches!(
+                            e.kind(),
+                            io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                        ) =>
+                    {
+                        break
+                    }
+                    Err(e) => return Err(SnmpError::Io(e)),
+                }
+            }
+        }
+        Err(SnmpError::Timeout(attempts))
+    }
+
+    fn walk(&self, base: &Oid) -> Result<Vec<VarBind>, SnmpError> {
+        let mut rows = Vec::new();
+        let mut current = base.clone();
+        loop {
+            let response = match self.target.version {
+                SnmpVersion::V1 => match self.request(PDU_GET_NEXT, 0, 0, &[current.clone()]) {
+                    Ok(response) => response,
+                    // v1 代理在 MIB 末尾返回 noSuchName
+                    Err(SnmpError::Agent { status: 2, .. }) => break,
+                    Err(error) => return Err(error),
+                },
+                SnmpVersion::V2c => self.request(PDU_GET_BULK, 0, 20, &[current.clone()])?,
+            };
+            if response.varbinds.is_empty() {
+                break;
+            }
+
+            let mut finished = false;
+            for vb in response.varbinds {
+                if vb.value.is_exception() || !vb.oid.starts_with(base) || vb.oid <= current {
+                    finished = true;
+                    break;
+                }
+                current = vb.oid.clone();
+                rows.push(vb);
+            }
+            if finished || rows.len() >= MAX_WALK_ROWS {
+                break;
+            }
+        }
+        Ok(rows)
+    }
+}
+
+fn error_status_name(status: i64) -> &'static str {
+    match status {
+        1 => "tooBig",
+        2 => "noSuchName",
+        3 => "badValue",
+        4 => "readOnly",
+        5 => "genErr",
+        6 => "noAccess",
+        7 => "wrongType",
+        8 => "wrongLength",
+        9 => "wrongEncoding",
+        10 => "wrongValue",
+        11 => "noCreation",
+        12 => "inconsistentValue",
+        13 => "resourceUnavailable",
+        14 => "commitFailed",
+        15 => "undoFailed",
+        16 => "authorizationError",
+        17 => "notWritable",
+        18 => "inconsistentName",
+        _ => "unknown",
+    }
+}
+
+fn hex_string(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+pub fn encode_request(
+    version: SnmpVersion,
+    community: &str,
+    pdu_type: u8,
+    request_id: i64,
+    field2: i64,
+    field3: i64,
+    oids: &[Oid],
+) -> Vec<u8> {
+    let mut varbinds = Vec::new();
+    for oid in oids {
+        let mut varbind = encode_tlv(TAG_OID, &encode_oid(oid));
+        varbind.extend(encode_tlv(TAG_NULL, &[]));
+        varbinds.extend(encode_tlv(TAG_SEQUENCE, &varbind));
+    }
+
+    let mut pdu = encode_integer(request_id);
+    pdu.extend(encode_integer(field2));
+    pdu.extend(encode_integer(field3));
+    pdu.extend(encode_tlv(TAG_SEQUENCE, &varbinds));
+
+    let mut message = encode_integer(version.wire_value());
+    message.extend(encode_tlv(TAG_OCTET_STRING, community.as_bytes()));
+    message.extend(encode_tlv(pdu_type, &pdu));
+    encode_tlv(TAG_SEQUENCE, &message)
+}
+
+pub fn decode_response(data: &[u8]) -> Result<SnmpResponse, SnmpError> {
+    let mut outer = Reader::new(data);
+    let (tag, message) = outer.read_tlv()?;
+    expect_tag(tag, TAG_SEQUENCE)?;
+
+    let mut message = Reader::new(message);
+    message.read_integer()?; // version
+    let (tag, _) = message.read_tlv()?; // community
+    expect_tag(tag, TAG_OCTET_STRING)?;
+    let (tag, pdu) = message.read_tlv()?;
+    expect_tag(tag, PDU_RESPONSE)?;
+
+    let mut pdu = Reader::new(pdu);
+    let request_id = pdu.read_integer()?;
+    let error_status = pdu.read_integer()?;
+    let error_index = pdu.read_integer()?;
+    let (tag, list) = pdu.read_tlv()?;
+    expect_tag(tag, TAG_SEQUENCE)?;
+
+    let mut list = Reader::new(list);
+    let mut varbinds = Vec::new();
+    while !list.is_empty() {
+        let (tag, varbind) = list.read_tlv()?;
+        expect_tag(tag, TAG_SEQUENCE)?;
+        let mut varbind = Reader::new(varbind);
+        let (tag, oid) = varbind.read_tlv()?;
+        expect_tag(tag, TAG_OID)?;
+        let oid = decode_oid(oid)?;
+        let (tag, value) = varbind.read_tlv()?;
+        varbinds.push(VarBind {
+            oid,
+            value: decode_value(tag, value)?,
+        });
+    }
+
+    Ok(SnmpResponse {
+        request_id,
+        error_status,
+        error_index,
+        varbinds,
+    })
+}
+
+fn expect_tag(actual: u8, expected: u8) -> Result<(), SnmpError> {
+    if actual == expected {
+        Ok(())
+    } else {
+        Err(SnmpError::Decode(format!(
+            "expected tag 0x{:02x}, got 0x{:02x}",
+            expected, actual
+        )))
+    }
+}
+
+fn decode_value(tag: u8, value: &[u8]) -> Result<SnmpValue, SnmpError> {
+    Ok(match tag {
+        TAG_INTEGER => SnmpValue::Integer(decode_integer(value)?),
+        TAG_OCTET_STRING => SnmpValue::OctetString(value.to_vec()),
+        TAG_NULL => SnmpValue::Null,
+        TAG_OID => SnmpValue::Oid(decode_oid(value)?),
+        TAG_IP_ADDRESS => {
+            let ip: [u8; 4] = value
+                .try_into()
+                .map_err(|_| SnmpError::Decode("ip address must be 4 bytes".to_string()))?;
+            SnmpValue::IpAddress(ip)
+        }
+        TAG_COUNTER32 => SnmpValue::Counter32(decode_unsigned(value)? as u32),
+        TAG_GAUGE32 => SnmpValue::Gauge32(decode_unsigned(value)? as u32),
+        TAG_TIMETICKS => SnmpValue::TimeTicks(decode_unsigned(value)? as u32),
+        TAG_OPAQUE => SnmpValue::Opaque(value.to_vec()),
+        TAG_COUNTER64 => SnmpValue::Counter64(decode_unsigned(value)?),
+        TAG_NO_SUCH_OBJECT => SnmpValue::NoSuchObject,
+        TAG_NO_SUCH_INSTANCE => SnmpValue::NoSuchInstance,
+        TAG_END_OF_MIB_VIEW => SnmpValue::EndOfMibView,
+        other => {
+            return Err(SnmpError::Decode(format!(
+                "unsupported value tag 0x{:02x}",
+                other
+            )))
+        }
+    })
+}
+
+fn encode_tlv(tag: u8, value: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    let len = value.len();
+    if len < 0x80 {
+        out.push(len as u8);
+    } else {
+        let bytes: Vec<u8> = len
+            .to_be_bytes()
+            .iter()
+            .copied()
+            .skip_while(|b| *b == 0)
+            .collect();
+        out.push(0x80 | bytes.len() as u8);
+        out.extend(bytes);
+    }
+    out.extend_from_slice(value);
+    out
+}
+
+fn encode_integer(value: i64) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let mut start = 0;
+    // 去掉冗余的符号扩展字节，保持最短补码表示
+    while start < 7
+        && ((bytes[start] == 0x00 && bytes[start + 1] & 0x80 == 0)
+            || (bytes[start] == 0xFF && bytes[start + 1] & 0x80 != 0))
+    {
+        start += 1;
+    }
+    encode_tlv(TAG_INTEGER, &bytes[start..])
+}
+
+fn encode_oid(oid: &Oid) -> Vec<u8> {
+    let arcs = &oid.0;
+    let mut out = Vec::new();
+    let mut push_arc = |arc: u32| {
+        let mut chunk = vec![(arc & 0x7F) as u8];
+        let mut rest = arc >> 7;
+        while rest > 0 {
+            chunk.push(((rest & 0x7F) as u8) | 0x80);
+            rest >>= 7;
+        }
+        chunk.reverse();
+        out.extend(chunk);
+    };
+    push_arc(first_sub_id(arcs[0], arcs[1]).expect("oid arcs are validated on parse"));
+    for arc in &arcs[2..] {
+        push_arc(*arc);
+    }
+    out
+}
+
+fn first_sub_id(first: u32, second: u32) -> Option<u32> {
+    first.checked_mul(40)?.checked_add(second)
+}
+
+fn decode_oid(data: &[u8]) -> Result<Oid, SnmpError> {
+    let mut sub_ids = Vec::new();
+    let mut current: u64 = 0;
+    for byte in data {
+        current = (current << 7) | u64::from(byte & 0x7F);
+        if current > u64::from(u32::MAX) {
+            return Err(SnmpError::Decode("oid arc overflow".to_string()));
+        }
+        if byte & 0x80 == 0 {
+            sub_ids.push(current as u32);
+            current = 0;
+        }
+    }
+    let Some(first) = sub_ids.first().copied() else {
+        return Err(SnmpError::Decode("empty oid".to_string()));
+    };
+    let (a, b) = match first {
+        0..=39 => (0, first),
+        40..=79 => (1, first - 40),
+        _ => (2, first - 80),
+    };
+    let mut arcs = vec![a, b];
+    arcs.extend(&sub_ids[1..]);
+    Ok(Oid(arcs))
+}
+
+fn decode_integer(data: &[u8]) -> Result<i64, SnmpError> {
+    if data.is_empty() || data.len() > 8 {
+        return Err(SnmpError::Decode("invalid integer length".to_string()));
+    }
+    let mut value: i64 = if data[0] & 0x80 != 0 { -1 } else { 0 };
+    for byte in data {
+        value = (value << 8) | i64::from(*byte);
+    }
+    Ok(value)
+}
+
+fn decode_unsigned(data: &[u8]) -> Result<u64, SnmpError> {
+    if data.is_empty() || data.len() > 9 {
+        return Err(SnmpError::Decode("invalid unsigned length".to_string()));
+    }
+    Ok(data
+        .iter()
+        .fold(0u64, |acc, byte| (acc << 8) | u64::from(*byte)))
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+
+    fn read_byte(&mut self) -> Result<u8, SnmpError> {
+        let byte = *self
+            .data
+            .get(self.pos)
+            .ok_or_else(|| SnmpError::Decode("unexpected end of data".to_string()))?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    fn read_tlv(&mut self) -> Result<(u8, &'a [u8]), SnmpError> {
+        let tag = self.read_byte()?;
+        let first = self.read_byte()?;
+        let len = if first & 0x80 == 0 {
+            first as usize
+        } else {
+            let count = (first & 0x7F) as usize;
+            if count == 0 || count > 4 {
+                return Err(SnmpError::Decode("unsupported length encoding".to_string()));
+            }
+            let mut len = 0usize;
+            for _ in 0..count {
+                len = (len << 8) | self.read_byte()? as usize;
+            }
+            len
+        };
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or_else(|| SnmpError::Decode("length exceeds data".to_string()))?;
+        let value = &self.data[self.pos..end];
+        self.pos = end;
+        Ok((tag, value))
+    }
+
+    fn read_integer(&mut self) -> Result<i64, SnmpError> {
+        let (tag, value) = self.read_tlv()?;
+        expect_tag(tag, TAG_INTEGER)?;
+        decode_integer(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_get_request_like_net_snmp() {
+        let oid = Oid::parse("1.3.6.1.2.1.1.1.0").unwrap();
+        let message = encode_request(SnmpVersion::V2c, "public", PDU_GET, 1, 0, 0, &[oid]);
+        let expected = [
+            0x30, 0x26, 0x02, 0x01, 0x01, 0x04, 0x06, b'p', b'u', b'b', b'l', b'i', b'c', 0xA0,
+            0x19, 0x02, 0x01, 0x01, 0x02, 0x01, 0x00, 0x02, 0x01, 0x00, 0x30, 0x0E, 0x30, 0x0C,
+            0x06, 0x08, 0x2B, 0x06, 0x01, 0x02, 0x01, 0x01, 0x01, 0x00, 0x05, 0x00,
+        ];
+        assert_eq!(message, expected);
+    }
+
+    #[test]
+    fn oid_round_trips_with_large_arcs() {
+        let oid = Oid::parse(".1.3.6.1.4.1.2021.10.1.3.1").unwrap();
+        assert_eq!(decode_oid(&encode_oid(&oid)).unwrap(), oid);
+        assert_eq!(oid.to_string(), "1.3.6.1.4.1.2021.10.1.3.1");
+        assert!(Oid::parse("1.3.x").is_err());
+        assert!(Oid::parse("2.4294967295").is_err());
+        assert!(Oid::parse("2.4294967215").is_ok());
+    }
+
+    #[test]
+    fn integers_use_minimal_twos_complement() {
+        assert_eq!(encode_integer(0), vec![0x02, 0x01, 0x00]);
+        assert_eq!(encode_integer(128), vec![0x02, 0x02, 0x00, 0x80]);
+        assert_eq!(encode_integer(-1), vec![0x02, 0x01, 0xFF]);
+        assert_eq!(decode_integer(&[0x00, 0x80]).unwrap(), 128);
+        assert_eq!(decode_integer(&[0xFF, 0x7F]).unwrap(), -129);
+    }
+
+    #[test]
+    fn decodes_response_varbinds() {
+        let oid = Oid::parse("1.3.6.1.2.1.1.3.0").unwrap();
+        let mut varbind = encode_tlv(TAG_OID, &encode_oid(&oid));
+        varbind.extend(encode_tlv(TAG_TIMETICKS, &[0x01, 0x00]));
+        let list = encode_tlv(TAG_SEQUENCE, &encode_tlv(TAG_SEQUENCE, &varbind));
+        let mut pdu = encode_integer(42);
+        pdu.extend(encode_integer(0));
+        pdu.extend(encode_integer(0));
+        pdu.extend(list);
+        let mut message = encode_integer(1);
+        message.extend(encode_tlv(TAG_OCTET_STRING, b"public"));
+        message.extend(encode_tlv(PDU_RESPONSE, &pdu));
+        let data = encode_tlv(TAG_SEQUENCE, &message);
+
+        let response = decode_response(&data).unwrap();
+        assert_eq!(response.request_id, 42);
+        assert_eq!(response.varbinds.len(), 1);
+        assert_eq!(response.varbinds[0].oid, oid);
+        assert_eq!(response.varbinds[0].value, SnmpValue::TimeTicks(256));
+        assert_eq!(
+            response.varbinds[0].value.to_string(),
+            "256 (0d 00:00:02.56)"
+        );
+    }
+}
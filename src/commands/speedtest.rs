@@ -0,0 +1,574 @@
+use std::io::{self, IsTerminal};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use indicatif::{ProgressBar, ProgressStyle};
+use serde::Serialize;
+use tokio::io::{AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::sync::Mutex;
+
+use crate::utils::a11y;
+use crate::utils::duration::parse_duration;
+use crate::utils::format::human_bytes;
+use crate::utils::http::{self, HttpError, Request, Url};
+use crate::utils::network::{self, NetworkError};
+use crate::utils::output::RecordOutput;
+
+// Cloudflare 公开的测速接口：__down?bytes=N 返回 N 字节，__up 接收任意请求体
+const DEFAULT_DOWNLOAD: &str = "https://speed.cloudflare.com/__down?bytes=100000000";
+const DEFAULT_UPLOAD: &str = "https://speed.cloudflare.com/__up";
+// 单个上传请求的请求体大小，发送完后在同一任务里发起下一个请求
+const UPLOAD_REQUEST_BYTES: u64 = 25 << 20;
+const CHUNK_SIZE: usize = 64 * 1024;
+// --a11y 模式下输出文字进度的间隔
+const A11Y_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(clap::Args)]
+pub struct SpeedTestOpts {
+    #[arg(
+        long,
+        value_name = "URL",
+        default_value = DEFAULT_DOWNLOAD,
+        help = "下载测速地址，应返回足够大的响应体，读完后会重复请求直到时间用完"
+    )]
+    download: String,
+
+    #[arg(
+        long,
+        value_name = "URL",
+        default_value = DEFAULT_UPLOAD,
+        help = "上传测速地址，接收 POST 请求体"
+    )]
+    upload: String,
+
+    #[arg(long = "no-download", help = "跳过下载测速")]
+    no_download: bool,
+
+    #[arg(long = "no-upload", help = "跳过上传测速")]
+    no_upload: bool,
+
+    #[arg(
+        short = 'd',
+        long,
+        default_value = "10s",
+        value_name = "DURATION",
+        help = "下载和上传各自的测速时长，例如 10s、1m"
+    )]
+    duration: String,
+
+    #[arg(
+        short = 'c',
+        long,
+        default_value_t = 4,
+        value_name = "N",
+        value_parser = clap::value_parser!(u32).range(1..=64),
+        help = "并发连接数，多条连接更容易跑满带宽"
+    )]
+    connections: u32,
+
+    #[arg(
+        long,
+        default_value_t = 10,
+        value_name = "N",
+        help = "测量延迟时建立 TCP 连接的次数，0 跳过延迟测量"
+    )]
+    pings: u32,
+
+    #[arg(
+        long = "timeout",
+        default_value_t = 10000,
+        value_name = "MS",
+        help = "建立连接和等待响应头的超时时间(毫秒)"
+    )]
+    time_out: u64,
+
+    #[arg(short = 'k', long, help = "不校验 HTTPS 证书")]
+    insecure: bool,
+
+    #[arg(long, help = "以 JSON 输出结果")]
+    json: bool,
+
+    // 全局 --where / --fields / --rename，由命令行入口注入
+    #[arg(skip)]
+    pub(crate) record_output: RecordOutput,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum SpeedTestError {
+    #[error("invalid duration '{0}', expected e.g. 10s, 1m")]
+    InvalidDuration(String),
+    #[error("nothing to measure: both --no-download and --no-upload are set")]
+    NothingToDo,
+    #[error("cannot resolve {0}")]
+    Resolve(String),
+    #[error("{phase} failed: {reason}")]
+    Failed { phase: &'static str, reason: String },
+    #[error(transparent)]
+    Http(#[from] HttpError),
+    #[error(transparent)]
+    Network(#[from] NetworkError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("json serialize failed: {0}")]
+    Serialize(#[from] serde_json::Error),
+}
+
+// 延迟统计，单位毫秒；抖动为相邻两次测量差值的平均
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Latency {
+    pub min: f64,
+    pub avg: f64,
+    pub median: f64,
+    pub max: f64,
+    pub jitter: f64,
+    pub samples: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Throughput {
+    pub url: String,
+    pub bytes: u64,
+    pub secs: f64,
+    pub mbps: f64,
+    pub connections: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SpeedReport {
+    pub server: String,
+    pub address: String,
+    pub latency_ms: Option<Latency>,
+    pub download: Option<Throughput>,
+    pub upload: Option<Throughput>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Download,
+    Upload,
+}
+
+impl Direction {
+    fn label(self) -> &'static str {
+        match self {
+            Direction::Download => "download",
+            Direction::Upload => "upload",
+        }
+    }
+}
+
+// 一个测速阶段所有连接共享的参数和计数
+struct Phase {
+    url: Url,
+    direction: Direction,
+    insecure: bool,
+    timeout: Duration,
+    deadline: Instant,
+    bytes: AtomicU64,
+    // 第一个错误，全部连接都没有传输数据时报告
+    error: Mutex<Option<String>>,
+}
+
+pub fn run_speed_test(opts: SpeedTestOpts) -> Result<(), SpeedTestError> {
+    if opts.no_download && opts.no_upload {
+        return Err(SpeedTestError::NothingToDo);
+    }
+    let duration = parse_duration(&opts.duration)
+        .ok_or_else(|| SpeedTestError::InvalidDuration(opts.duration.clone()))?;
+    let download = Url::parse(&opts.download)?;
+    let upload = Url::parse(&opts.upload)?;
+    if !opts.no_download {
+        network::check_host("speedtest", &download.host)?;
+    }
+    if !opts.no_upload {
+        network::check_host("speedtest", &upload.host)?;
+    }
+    // 延迟针对下载服务器测量，只测上传时针对上传服务器
+    let server = if opts.no_download { &upload } else { &download };
+    let show_progress = !opts.json && io::stderr().is_terminal();
+
+    let rt = tokio::runtime::Runtime::new()?;
+    let report = rt.block_on(async {
+        let addr = tokio::net::lookup_host((server.host.as_str(), server.port))
+            .await
+            .ok()
+            .and_then(|mut addrs| addrs.next())
+            .ok_or_else(|| SpeedTestError::Resolve(server.host.clone()))?;
+        let timeout = Duration::from_millis(opts.time_out.max(1));
+        if !opts.json {
+            println!("Server:    {} ({})", server.host, addr.ip());
+        }
+        let latency = if opts.pings > 0 {
+            let latency = measure_latency(addr, opts.pings, timeout).await;
+            if !opts.json {
+                match &latency {
+                    Some(latency) => println!(
+                        "Latency:   {:.2} ms (min {:.2}, max {:.2}, jitter {:.2})",
+                        latency.median, latency.min, latency.max, latency.jitter
+                    ),
+                    None => println!("Latency:   no TCP connection succeeded"),
+                }
+            }
+            latency
+        } else {
+            None
+        };
+
+        let mut results = Vec::new();
+        for (direction, url, skip) in [
+            (Direction::Download, &download, opts.no_download),
+            (Direction::Upload, &upload, opts.no_upload),
+        ] {
+            if skip {
+                results.push(None);
+                continue;
+            }
+            let phase = Arc::new(Phase {
+                url: url.clone(),
+                direction,
+                insecure: opts.insecure,
+                timeout,
+                deadline: Instant::now() + duration,
+                bytes: AtomicU64::new(0),
+                error: Mutex::new(None),
+            });
+            let result = run_phase(phase, opts.connections, duration, show_progress).await?;
+            if !opts.json {
+                println!(
+                    "{:<10} {:.2} Mbit/s ({} in {:.1} s, {} connections)",
+                    format!("{}:", capitalize(direction.label())),
+                    result.mbps,
+                    human_bytes(result.bytes),
+                    result.secs,
+                    result.connections
+                );
+            }
+            results.push(Some(result));
+        }
+        let upload = results.pop().flatten();
+        let download = results.pop().flatten();
+        Ok::<_, SpeedTestError>(SpeedReport {
+            server: server.host.clone(),
+            address: addr.ip().to_string(),
+            latency_ms: latency,
+            download,
+            upload,
+        })
+    })?;
+    // 还在阻塞读写的连接不再等待
+    rt.shutdown_background();
+
+    if opts.json && opts.record_output.keep(&report)? {
+        opts.record_output
+            .write_report(&report, None, io::stdout().lock())?;
+    }
+    Ok(())
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    chars
+        .next()
+        .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+        .unwrap_or_default()
+}
+
+// 依次建立 count 次 TCP 连接，以三次握手的耗时近似往返延迟，不受服务端处理请求的时间影响
+async fn measure_latency(addr: SocketAddr, count: u32, timeout: Duration) -> Option<Latency> {
+    let mut samples = Vec::new();
+    for _ in 0..count {
+        let started = Instant::now();
+        if let Ok(Ok(stream)) = tokio::time::timeout(timeout, TcpStream::connect(addr)).await {
+            samples.push(started.elapsed().as_micros() as u64);
+            drop(stream);
+        }
+    }
+    latency_stats(&samples)
+}
+
+// samples 为按测量顺序排列的微秒数
+fn latency_stats(samples: &[u64]) -> Option<Latency> {
+    if samples.is_empty() {
+        return None;
+    }
+    let ms = |us: f64| (us / 10.0).round() / 100.0;
+    let jitter = if samples.len() > 1 {
+        samples
+            .windows(2)
+            .map(|pair| pair[0].abs_diff(pair[1]) as f64)
+            .sum::<f64>()
+            / (samples.len() - 1) as f64
+    } else {
+        0.0
+    };
+    let mut sorted = samples.to_vec();
+    sorted.sort_unstable();
+    let median = if sorted.len().is_multiple_of(2) {
+        (sorted[sorted.len() / 2 - 1] + sorted[sorted.len() / 2]) as f64 / 2.0
+    } else {
+        sorted[sorted.len() / 2] as f64
+    };
+    Some(Latency {
+        min: ms(sorted[0] as f64),
+        avg: ms(sorted.iter().sum::<u64>() as f64 / sorted.len() as f64),
+        median: ms(median),
+        max: ms(sorted[sorted.len() - 1] as f64),
+        jitter: ms(jitter),
+        samples: sorted.len(),
+    })
+}
+
+async fn run_phase(
+    phase: Arc<Phase>,
+    connections: u32,
+    duration: Duration,
+    show_progress: bool,
+) -> Result<Throughput, SpeedTestError> {
+    let started = Instant::now();
+    let display = show_progress.then(|| tokio::spawn(show_rate(Arc::clone(&phase), duration)));
+    let workers: Vec<_> = (0..connections)
+        .map(|_| {
+            let phase = Arc::clone(&phase);
+            tokio::spawn(async move {
+                // 单次请求结束（响应读完或上传完）后继续发起下一次，直到时间用完
+                while Instant::now() < phase.deadline {
+                    let result = match phase.direction {
+                        Direction::Download => download_once(&phase).await,
+                        Direction::Upload => upload_once(&phase).await,
+                    };
+                    if let Err(error) = result {
+                        phase.error.lock().await.get_or_insert(error);
+                        return;
+                    }
+                }
+            })
+        })
+        .collect();
+    // 到时间后正在读写的连接直接放弃，不等待请求结束
+    let _ = tokio::time::timeout_at(phase.deadline.into(), async {
+        for worker in workers {
+            let _ = worker.await;
+        }
+    })
+    .await;
+    let secs = started.elapsed().as_secs_f64().max(f64::EPSILON);
+    if let Some(display) = display {
+        display.abort();
+        let _ = display.await;
+    }
+
+    let bytes = phase.bytes.load(Ordering::Relaxed);
+    if bytes == 0 {
+        let reason = phase
+            .error
+            .lock()
+            .await
+            .take()
+            .unwrap_or_else(|| "no data transferred".to_string());
+        return Err(SpeedTestError::Failed {
+            phase: phase.direction.label(),
+            reason,
+        });
+    }
+    Ok(Throughput {
+        url: phase.url.to_string(),
+        bytes,
+        secs: (secs * 100.0).round() / 100.0,
+        mbps: ((bytes as f64 * 8.0 / secs / 1e6) * 100.0).round() / 100.0,
+        connections,
+    })
+}
+
+async fn download_once(phase: &Phase) -> Result<(), String> {
+    let request = Request {
+        method: "GET",
+        url: &phase.url,
+        headers: &[],
+        body: &[],
+    };
+    let mut reader = tokio::time::timeout(phase.timeout, async {
+        let mut stream = http::connect(&phase.url, None, phase.insecure).await?;
+        http::write_head(&mut stream, &request, 0).await?;
+        let mut reader = BufReader::new(stream);
+        let response = http::read_head(&mut reader).await?;
+        if !(200..300).contains(&response.status) {
+            return Err(HttpError::InvalidResponse(
+                "download URL returned a non-2xx status",
+            ));
+        }
+        Ok(reader)
+    })
+    .await
+    .map_err(|_| "timed out waiting for the server".to_string())?
+    .map_err(|e| e.to_string())?;
+
+    // 请求带 Connection: close，响应体读到连接关闭为止；chunked 的分块长度行也计入，可以忽略
+    let mut buf = vec![0u8; CHUNK_SIZE];
+    loop {
+        match reader.read(&mut buf).await {
+            Ok(0) => return Ok(()),
+            Ok(n) => {
+                phase.bytes.fetch_add(n as u64, Ordering::Relaxed);
+            }
+            // 不少服务端关闭 TLS 连接时不发 close_notify
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+}
+
+async fn upload_once(phase: &Phase) -> Result<(), String> {
+    let headers = [(
+        "Content-Type".to_string(),
+        "application/octet-stream".to_string(),
+    )];
+    let request = Request {
+        method: "POST",
+        url: &phase.url,
+        headers: &headers,
+        body: &[],
+    };
+    let mut stream = tokio::time::timeout(phase.timeout, async {
+        let mut stream = http::connect(&phase.url, None, phase.insecure).await?;
+        http::write_head(&mut stream, &request, UPLOAD_REQUEST_BYTES).await?;
+        Ok::<_, HttpError>(stream)
+    })
+    .await
+    .map_err(|_| "timed out connecting to the server".to_string())?
+    .map_err(|e| e.to_string())?;
+
+    // 随机数据避免链路上的压缩影响结果
+    let chunk: Vec<u8> = (0..CHUNK_SIZE).map(|_| rand::random()).collect();
+    let mut remaining = UPLOAD_REQUEST_BYTES;
+    while remaining > 0 {
+        let n = remaining.min(CHUNK_SIZE as u64) as usize;
+        stream
+            .write_all(&chunk[..n])
+            .await
+            .map_err(|e| e.to_string())?;
+        phase.bytes.fetch_add(n as u64, Ordering::Relaxed);
+        remaining -= n as u64;
+    }
+    stream.flush().await.map_err(|e| e.to_string())?;
+    let mut reader = BufReader::new(stream);
+    let response = tokio::time::timeout(phase.timeout, http::read_head(&mut reader))
+        .await
+        .map_err(|_| "timed out waiting for the upload response".to_string())?
+        .map_err(|e| e.to_string())?;
+    if !(200..300).contains(&response.status) {
+        return Err(format!(
+            "upload URL returned {} {}",
+            response.status, response.reason
+        ));
+    }
+    Ok(())
+}
+
+// 进度条按时间推进，消息显示最近一秒的速率；--a11y 模式下每隔几秒输出一行文字
+async fn show_rate(phase: Arc<Phase>, duration: Duration) {
+    let label = capitalize(phase.direction.label());
+    let started = Instant::now();
+    let mut history: Vec<(Instant, u64)> = Vec::new();
+    let rate = |history: &[(Instant, u64)]| match (history.first(), history.last()) {
+        (Some((t0, b0)), Some((t1, b1))) if t1 > t0 => {
+            (b1 - b0) as f64 * 8.0 / (*t1 - *t0).as_secs_f64() / 1e6
+        }
+        _ => 0.0,
+    };
+    if a11y::is_enabled() {
+        let mut interval = tokio::time::interval(A11Y_INTERVAL);
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            let bytes = phase.bytes.load(Ordering::Relaxed);
+            eprintln!(
+                "{}: {} of {} seconds, {:.2} Mbit/s so far",
+                label,
+                started.elapsed().as_secs(),
+                duration.as_secs(),
+                bytes as f64 * 8.0 / started.elapsed().as_secs_f64() / 1e6
+            );
+        }
+    }
+
+    let style = ProgressStyle::with_template(&format!(
+        "{:<9} {{bar:40.cyan/blue}} {{msg}}",
+        format!("{}:", label)
+    ))
+    .unwrap_or_else(|_| ProgressStyle::default_bar())
+    .progress_chars("=> ");
+    let bar = ProgressBar::new(duration.as_millis() as u64).with_style(style);
+    // 任务被取消时清除进度条
+    struct Clear(ProgressBar);
+    impl Drop for Clear {
+        fn drop(&mut self) {
+            self.0.finish_and_clear();
+        }
+    }
+    let bar = Clear(bar);
+    let mut interval = tokio::time::interval(Duration::from_millis(200));
+    loop {
+        interval.tick().await;
+        let now = Instant::now();
+        history.push((now, phase.bytes.load(Ordering::Relaxed)));
+        history.retain(|(time, _)| now.duration_since(*time) <= Duration::from_secs(1));
+        bar.0.set_position(started.elapsed().as_millis() as u64);
+        bar.0.set_message(format!("{:.2} Mbit/s", rate(&history)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latency_stats() {
+        let latency = latency_stats(&[10_000, 12_000, 11_000, 15_000]).unwrap();
+        assert_eq!(
+            latency,
+            Latency {
+                min: 10.0,
+                avg: 12.0,
+                median: 11.5,
+                max: 15.0,
+                jitter: 2.33,
+                samples: 4,
+            }
+        );
+        assert_eq!(latency_stats(&[]), None);
+        assert_eq!(latency_stats(&[800]).unwrap().jitter, 0.0);
+    }
+
+    #[test]
+    fn test_download_counts_body_bytes() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            tokio::spawn(async move {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = [0u8; 1024];
+                let _ = stream.read(&mut request).await;
+                stream
+                    .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 100000\r\n\r\n")
+                    .await
+                    .unwrap();
+                stream.write_all(&[0u8; 100_000]).await.unwrap();
+            });
+            let phase = Phase {
+                url: Url::parse(&format!("http://{}/blob", addr)).unwrap(),
+                direction: Direction::Download,
+                insecure: false,
+                timeout: Duration::from_secs(5),
+                deadline: Instant::now() + Duration::from_secs(5),
+                bytes: AtomicU64::new(0),
+                error: Mutex::new(None),
+            };
+            download_once(&phase).await.unwrap();
+            assert_eq!(phase.bytes.load(Ordering::Relaxed), 100_000);
+        });
+    }
+}
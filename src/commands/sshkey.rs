@@ -0,0 +1,523 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use clap::{Subcommand, ValueEnum};
+use md5::{Digest, Md5};
+use serde::Serialize;
+use ssh_key::private::{KeypairData, RsaKeypair};
+use ssh_key::public::KeyData;
+use ssh_key::rand_core::OsRng;
+use ssh_key::{authorized_keys::Entry, Algorithm, EcdsaCurve, HashAlg, LineEnding, PrivateKey};
+
+use crate::commands::syslog::local_hostname;
+use crate::utils::output::RecordOutput;
+use crate::utils::pki;
+
+const PASSPHRASE_ENV: &str = "RTOOLKIT_SSH_PASSPHRASE";
+// 与 ssh-keygen 的默认值一致
+const DEFAULT_RSA_BITS: usize = 3072;
+const MIN_RSA_BITS: usize = 2048;
+
+#[derive(clap::Args)]
+pub struct SshkeyOpts {
+    #[command(subcommand)]
+    command: SshkeyCommand,
+}
+
+impl SshkeyOpts {
+    // 只有 audit 输出密钥记录，支持全局 --where / --fields / --rename
+    pub(crate) fn record_output(&mut self) -> Option<&mut RecordOutput> {
+        match &mut self.command {
+            SshkeyCommand::Gen(_) => None,
+            SshkeyCommand::Audit(opts) => Some(&mut opts.record_output),
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum SshkeyCommand {
+    #[command(about = "生成 OpenSSH 格式的密钥对，类型 ed25519 | rsa | ecdsa")]
+    Gen(GenOpts),
+    #[command(
+        about = "检查 authorized_keys：列出密钥类型、长度、注释和指纹，标记弱密钥、重复密钥和无法解析的行"
+    )]
+    Audit(AuditOpts),
+}
+
+#[derive(clap::Args)]
+struct GenOpts {
+    #[arg(
+        short = 't',
+        long = "type",
+        value_enum,
+        value_name = "TYPE",
+        default_value_t = KeyType::Ed25519,
+        help = "密钥类型 ed25519 | rsa | ecdsa"
+    )]
+    key_type: KeyType,
+
+    #[arg(
+        short = 'b',
+        long,
+        value_name = "BITS",
+        help = "RSA 密钥长度，默认 3072，最少 2048；ECDSA 曲线 256 | 384，默认 256"
+    )]
+    bits: Option<usize>,
+
+    #[arg(
+        short = 'C',
+        long,
+        value_name = "COMMENT",
+        help = "公钥注释，默认 用户名@主机名"
+    )]
+    comment: Option<String>,
+
+    #[arg(
+        short = 'f',
+        long = "out",
+        value_name = "FILE",
+        help = "私钥写入文件（权限 0600），公钥写入 FILE.pub；默认都输出到 stdout"
+    )]
+    out: Option<PathBuf>,
+
+    #[arg(
+        short = 'N',
+        long,
+        value_name = "PASSPHRASE",
+        help = "用口令加密私钥（bcrypt-pbkdf + AES-256-CTR）；也可通过环境变量 RTOOLKIT_SSH_PASSPHRASE 传入，默认不加密"
+    )]
+    passphrase: Option<String>,
+
+    #[arg(long, requires = "out", help = "覆盖已存在的密钥文件")]
+    force: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum KeyType {
+    Ed25519,
+    Rsa,
+    Ecdsa,
+}
+
+#[derive(clap::Args)]
+struct AuditOpts {
+    #[arg(
+        value_name = "FILE",
+        help = "authorized_keys 或 .pub 公钥文件，可以给多个，重复检查跨文件进行；默认 ~/.ssh/authorized_keys"
+    )]
+    files: Vec<PathBuf>,
+
+    #[arg(
+        long = "min-rsa-bits",
+        value_name = "BITS",
+        default_value_t = 2048,
+        help = "RSA 密钥短于该长度时标记为弱密钥"
+    )]
+    min_rsa_bits: u32,
+
+    #[arg(
+        value_enum,
+        short = 'o',
+        long = "output",
+        value_name = "FMT",
+        default_value_t = OutputFormat::Plain,
+        help = "输出格式 plain | json"
+    )]
+    output: OutputFormat,
+
+    // 全局 --where / --fields / --rename，由命令行入口注入
+    #[arg(skip)]
+    pub(crate) record_output: RecordOutput,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    Plain,
+    Json,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum SshkeyError {
+    #[error(transparent)]
+    Key(#[from] ssh_key::Error),
+    #[error("{0}")]
+    InvalidBits(String),
+    #[error("{0} already exists, pass --force to overwrite")]
+    Exists(PathBuf),
+    #[error("cannot read {path}: {source}")]
+    Open {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("cannot find the home directory, pass the authorized_keys file explicitly")]
+    NoHome,
+    #[error("{0} issue(s) found")]
+    Issues(usize),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("json serialize failed: {0}")]
+    Serialize(#[from] serde_json::Error),
+}
+
+pub fn run_sshkey(opts: SshkeyOpts) -> Result<(), SshkeyError> {
+    match opts.command {
+        SshkeyCommand::Gen(opts) => run_gen(opts),
+        SshkeyCommand::Audit(opts) => run_audit(opts),
+    }
+}
+
+fn run_gen(opts: GenOpts) -> Result<(), SshkeyError> {
+    if let Some(path) = &opts.out {
+        for path in [path.clone(), pub_path(path)] {
+            if path.exists() && !opts.force {
+                return Err(SshkeyError::Exists(path));
+            }
+        }
+    }
+    let comment = opts.comment.clone().unwrap_or_else(default_comment);
+    let mut key = generate(opts.key_type, opts.bits, &comment)?;
+    let public = format!("{}\n", key.public_key().to_openssh()?);
+    let fingerprint = format!(
+        "{} {} ({})",
+        key.fingerprint(HashAlg::Sha256),
+        comment,
+        key.algorithm()
+    );
+    let passphrase = opts
+        .passphrase
+        .clone()
+        .or_else(|| env::var(PASSPHRASE_ENV).ok())
+        .filter(|passphrase| !passphrase.is_empty());
+    if let Some(passphrase) = passphrase {
+        key = key.encrypt(&mut OsRng, passphrase)?;
+    }
+    let private = key.to_openssh(LineEnding::LF)?;
+    match &opts.out {
+        Some(path) => {
+            pki::write_private_file(path, private.as_bytes())?;
+            eprintln!("Wrote {}", path.display());
+            let pub_path = pub_path(path);
+            fs::write(&pub_path, &public)?;
+            eprintln!("Wrote {}", pub_path.display());
+        }
+        None => print!("{}{}", private.as_str(), public),
+    }
+    eprintln!("{}", fingerprint);
+    Ok(())
+}
+
+fn generate(
+    key_type: KeyType,
+    bits: Option<usize>,
+    comment: &str,
+) -> Result<PrivateKey, SshkeyError> {
+    let mut key = match key_type {
+        KeyType::Ed25519 => {
+            if bits.is_some_and(|bits| bits != 256) {
+                return Err(SshkeyError::InvalidBits(
+                    "ed25519 keys have a fixed size, drop --bits".to_string(),
+                ));
+            }
+            PrivateKey::random(&mut OsRng, Algorithm::Ed25519)?
+        }
+        KeyType::Rsa => {
+            let bits = bits.unwrap_or(DEFAULT_RSA_BITS);
+            if !(MIN_RSA_BITS..=16384).contains(&bits) {
+                return Err(SshkeyError::InvalidBits(format!(
+                    "RSA keys must be {}-16384 bits",
+                    MIN_RSA_BITS
+                )));
+            }
+            let keypair = RsaKeypair::random(&mut OsRng, bits)?;
+            PrivateKey::new(KeypairData::from(keypair), comment)?
+        }
+        KeyType::Ecdsa => {
+            let curve = match bits.unwrap_or(256) {
+                256 => EcdsaCurve::NistP256,
+                384 => EcdsaCurve::NistP384,
+                _ => {
+                    return Err(SshkeyError::InvalidBits(
+                        "ECDSA keys support 256 or 384 bits".to_string(),
+                    ))
+                }
+            };
+            PrivateKey::random(&mut OsRng, Algorithm::Ecdsa { curve })?
+        }
+    };
+    key.set_comment(comment);
+    Ok(key)
+}
+
+fn pub_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".pub");
+    PathBuf::from(name)
+}
+
+// 与 ssh-keygen 一致，默认注释为 用户名@主机名
+fn default_comment() -> String {
+    let user = env::var("USER")
+        .or_else(|_| env::var("USERNAME"))
+        .unwrap_or_else(|_| "user".to_string());
+    format!("{}@{}", user, local_hostname())
+}
+
+#[derive(Debug, Serialize)]
+struct KeyReport {
+    file: String,
+    line: usize,
+    #[serde(rename = "type")]
+    key_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bits: Option<u32>,
+    comment: String,
+    // from=、command= 等限制选项
+    #[serde(skip_serializing_if = "String::is_empty")]
+    options: String,
+    sha256: String,
+    md5: String,
+    issues: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct InvalidLine {
+    file: String,
+    line: usize,
+    error: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct AuditReport {
+    keys: Vec<KeyReport>,
+    invalid: Vec<InvalidLine>,
+}
+
+impl AuditReport {
+    fn issue_count(&self) -> usize {
+        self.keys.iter().map(|key| key.issues.len()).sum::<usize>() + self.invalid.len()
+    }
+}
+
+fn run_audit(opts: AuditOpts) -> Result<(), SshkeyError> {
+    let files = if opts.files.is_empty() {
+        let home = env::var_os("HOME")
+            .or_else(|| env::var_os("USERPROFILE"))
+            .ok_or(SshkeyError::NoHome)?;
+        vec![Path::new(&home).join(".ssh").join("authorized_keys")]
+    } else {
+        opts.files.clone()
+    };
+    let mut sources = Vec::new();
+    for path in &files {
+        let text = fs::read_to_string(path).map_err(|source| SshkeyError::Open {
+            path: path.clone(),
+            source,
+        })?;
+        sources.push((path.display().to_string(), text));
+    }
+    let mut report = audit(&sources, opts.min_rsa_bits);
+    // --where 只筛选列出的密钥，退出状态仍按全部问题计算
+    let issues = report.issue_count();
+    opts.record_output.retain(&mut report.keys)?;
+    let mut out = io::stdout().lock();
+    match opts.output {
+        OutputFormat::Json => {
+            opts.record_output
+                .write_report(&report, Some("keys"), &mut out)?;
+        }
+        OutputFormat::Plain => write_report(&report, &mut out)?,
+    }
+    match issues {
+        0 => Ok(()),
+        issues => Err(SshkeyError::Issues(issues)),
+    }
+}
+
+// sources 为（文件名, 内容），重复密钥按密钥本身比较，忽略注释和选项
+fn audit(sources: &[(String, String)], min_rsa_bits: u32) -> AuditReport {
+    let mut report = AuditReport::default();
+    let mut seen: HashMap<KeyData, String> = HashMap::new();
+    for (file, text) in sources {
+        for (index, line) in text.lines().enumerate() {
+            let line_no = index + 1;
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            let entry = match Entry::from_str(trimmed) {
+                Ok(entry) => entry,
+                Err(e) => {
+                    report.invalid.push(InvalidLine {
+                        file: file.clone(),
+                        line: line_no,
+                        error: e.to_string(),
+                    });
+                    continue;
+                }
+            };
+            let key = entry.public_key();
+            let bits = key_bits(key.key_data());
+            let mut issues = Vec::new();
+            match key.key_data() {
+                KeyData::Rsa(_) if bits.is_some_and(|bits| bits < min_rsa_bits) => {
+                    issues.push(format!(
+                        "weak: RSA key is {} bits, below {}",
+                        bits.unwrap_or_default(),
+                        min_rsa_bits
+                    ));
+                }
+                KeyData::Dsa(_) => issues.push(
+                    "weak: DSA keys are limited to 1024 bits and disabled since OpenSSH 7.0"
+                        .to_string(),
+                ),
+                _ => {}
+            }
+            let location = format!("{}:{}", file, line_no);
+            match seen.get(key.key_data()) {
+                Some(first) => issues.push(format!("duplicate of {}", first)),
+                None => {
+                    seen.insert(key.key_data().clone(), location);
+                }
+            }
+            report.keys.push(KeyReport {
+                file: file.clone(),
+                line: line_no,
+                key_type: key.algorithm().to_string(),
+                bits,
+                comment: key.comment().to_string(),
+                options: entry.config_opts().as_str().to_string(),
+                sha256: key.fingerprint(HashAlg::Sha256).to_string(),
+                md5: md5_fingerprint(key.key_data()),
+                issues,
+            });
+        }
+    }
+    report
+}
+
+fn key_bits(key: &KeyData) -> Option<u32> {
+    match key {
+        KeyData::Rsa(rsa) => rsa.n.as_positive_bytes().map(mpint_bits),
+        KeyData::Dsa(dsa) => dsa.p.as_positive_bytes().map(mpint_bits),
+        KeyData::Ecdsa(ecdsa) => Some(match ecdsa.curve() {
+            EcdsaCurve::NistP256 => 256,
+            EcdsaCurve::NistP384 => 384,
+            EcdsaCurve::NistP521 => 521,
+        }),
+        KeyData::Ed25519(_) | KeyData::SkEd25519(_) => Some(256),
+        KeyData::SkEcdsaSha2NistP256(_) => Some(256),
+        _ => None,
+    }
+}
+
+fn mpint_bits(bytes: &[u8]) -> u32 {
+    match bytes.iter().position(|&byte| byte != 0) {
+        Some(index) => (bytes.len() - index) as u32 * 8 - bytes[index].leading_zeros(),
+        None => 0,
+    }
+}
+
+// 旧版 OpenSSH 和部分平台界面使用的 MD5 指纹：公钥 blob 的 MD5，冒号分隔
+fn md5_fingerprint(key: &KeyData) -> String {
+    let blob = ssh_key::PublicKey::from(key.clone())
+        .to_bytes()
+        .unwrap_or_default();
+    let hex: Vec<String> = Md5::digest(blob)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    format!("MD5:{}", hex.join(":"))
+}
+
+fn write_report<W: Write>(report: &AuditReport, out: &mut W) -> io::Result<()> {
+    for key in &report.keys {
+        let bits = key
+            .bits
+            .map(|bits| format!("  {} bits", bits))
+            .unwrap_or_default();
+        writeln!(
+            out,
+            "{}:{}  {}{}  {}",
+            key.file, key.line, key.key_type, bits, key.comment
+        )?;
+        if !key.options.is_empty() {
+            writeln!(out, "    options  {}", key.options)?;
+        }
+        writeln!(out, "    {}", key.sha256)?;
+        writeln!(out, "    {}", key.md5)?;
+        for issue in &key.issues {
+            writeln!(out, "    WARN  {}", issue)?;
+        }
+    }
+    for invalid in &report.invalid {
+        writeln!(
+            out,
+            "{}:{}  INVALID  {}",
+            invalid.file, invalid.line, invalid.error
+        )?;
+    }
+    writeln!(
+        out,
+        "\n{} key(s), {} issue(s)",
+        report.keys.len(),
+        report.issue_count()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WEAK_RSA: &str = "ssh-rsa AAAAB3NzaC1yc2EAAAADAQABAAAAgQDhEVCd8I4hHcI0x2aCREEX83BEOKWx8JOHqLhfDVm1hNHeG5ay6UF6P4O/1/4agxAtXLqbxP+7m6cKqmAhaw9D3otse/vRydoUR8AMXRwpVl8dE1dOUhO/gSfv6RDOvqiN+rjDs8rD4H/zNOWryQNb8VG5NgUsZtjyeT07Y20HfQ== old@legacy";
+
+    #[test]
+    fn test_audit_flags_weak_and_duplicate_keys() {
+        let key = generate(KeyType::Ed25519, None, "alice@laptop").unwrap();
+        let public = key.public_key().to_openssh().unwrap();
+        let reloaded = PrivateKey::from_openssh(key.to_openssh(LineEnding::LF).unwrap()).unwrap();
+        assert_eq!(reloaded.public_key(), key.public_key());
+
+        let authorized = format!(
+            "# deploy keys\n{}\n\n{}\nfrom=\"10.0.0.0/8\",no-pty {} ci@runner\nssh-ed25519 not-base64\n",
+            public,
+            WEAK_RSA,
+            public.rsplit_once(' ').unwrap().0
+        );
+        let report = audit(&[("authorized_keys".to_string(), authorized)], 2048);
+        assert_eq!(report.keys.len(), 3);
+        assert_eq!(report.keys[0].key_type, "ssh-ed25519");
+        assert_eq!(report.keys[0].bits, Some(256));
+        assert!(report.keys[0].issues.is_empty());
+
+        let weak = &report.keys[1];
+        assert_eq!((weak.line, weak.bits), (4, Some(1024)));
+        assert_eq!(weak.comment, "old@legacy");
+        // 与 ssh-keygen -l [-E md5] 的输出一致
+        assert_eq!(
+            weak.sha256,
+            "SHA256:D+TkgqT9N0nitgByMEFLI2vImpHQBikgAsE6u+XJBN0"
+        );
+        assert_eq!(
+            weak.md5,
+            "MD5:09:ad:04:07:c2:f2:16:8e:10:2a:f4:53:a0:a0:d7:89"
+        );
+        assert_eq!(weak.issues, ["weak: RSA key is 1024 bits, below 2048"]);
+
+        let duplicate = &report.keys[2];
+        assert_eq!(duplicate.options, "from=\"10.0.0.0/8\",no-pty");
+        assert_eq!(duplicate.issues, ["duplicate of authorized_keys:2"]);
+        assert_eq!(report.invalid.len(), 1);
+        assert_eq!(report.invalid[0].line, 6);
+        assert_eq!(report.issue_count(), 3);
+
+        assert!(matches!(
+            generate(KeyType::Rsa, Some(1024), ""),
+            Err(SshkeyError::InvalidBits(_))
+        ));
+    }
+}
@@ -0,0 +1,699 @@
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs, UdpSocket};
+use std::sync::mpsc;
+use std::thread;
+
+use chrono::Local;
+use clap::{Subcommand, ValueEnum};
+use serde::Serialize;
+
+use crate::utils::network::{self, NetworkError};
+use crate::utils::output::RecordOutput;
+
+// 单条消息上限，超出部分截断
+const MAX_MESSAGE_LEN: usize = 64 * 1024;
+
+#[derive(clap::Args)]
+pub struct SyslogOpts {
+    #[command(subcommand)]
+    command: SyslogCommand,
+
+    // 全局 --where / --fields / --rename，由命令行入口注入
+    #[arg(skip)]
+    pub(crate) record_output: RecordOutput,
+}
+
+impl SyslogOpts {
+    pub(crate) fn is_listen(&self) -> bool {
+        matches!(self.command, SyslogCommand::Listen(_))
+    }
+}
+
+#[derive(Subcommand)]
+enum SyslogCommand {
+    #[command(about = "发送测试日志")]
+    Send(SendOpts),
+    #[command(about = "监听并打印收到的日志")]
+    Listen(ListenOpts),
+}
+
+#[derive(clap::Args)]
+struct SendOpts {
+    #[arg(value_name = "MESSAGE", help = "日志内容")]
+    message: String,
+
+    #[arg(
+        short = 's',
+        long,
+        default_value = "127.0.0.1:514",
+        value_name = "HOST:PORT",
+        help = "syslog 服务器地址"
+    )]
+    server: String,
+
+    #[arg(value_enum, long, default_value_t = Severity::Info, help = "日志级别")]
+    severity: Severity,
+
+    #[arg(value_enum, long, default_value_t = Facility::User, help = "设施类型")]
+    facility: Facility,
+
+    #[arg(value_enum, short = 'f', long = "format", default_value_t = SyslogFormat::Rfc5424, help = "消息格式")]
+    format: SyslogFormat,
+
+    #[arg(long, help = "使用 TCP 发送（默认 UDP）")]
+    tcp: bool,
+
+    #[arg(long, default_value = "rtoolkit", help = "应用名 / TAG")]
+    app_name: String,
+
+    #[arg(long, help = "消息中的主机名，默认取本机主机名")]
+    hostname: Option<String>,
+
+    #[arg(short = 'n', long, default_value_t = 1, help = "发送次数")]
+    count: u32,
+}
+
+#[derive(clap::Args)]
+struct ListenOpts {
+    #[arg(
+        short = 'b',
+        long,
+        default_value = "0.0.0.0:514",
+        value_name = "ADDR",
+        help = "监听地址"
+    )]
+    bind: String,
+
+    #[arg(long, help = "同时监听 TCP")]
+    tcp: bool,
+
+    #[arg(
+        short = 'n',
+        long,
+        help = "收到指定条数后退出，被 --where 过滤掉的消息也计入"
+    )]
+    count: Option<u64>,
+
+    #[arg(long, help = "以 JSON Lines 输出解析结果")]
+    json: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Emerg,
+    Alert,
+    Crit,
+    Err,
+    Warning,
+    Notice,
+    Info,
+    Debug,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Facility {
+    Kern,
+    User,
+    Mail,
+    Daemon,
+    Auth,
+    Syslog,
+    Lpr,
+    News,
+    Uucp,
+    Cron,
+    Authpriv,
+    Ftp,
+    Ntp,
+    Security,
+    Console,
+    #[serde(rename = "solaris-cron")]
+    SolarisCron,
+    Local0,
+    Local1,
+    Local2,
+    Local3,
+    Local4,
+    Local5,
+    Local6,
+    Local7,
+}
+
+const SEVERITIES: [Severity; 8] = [
+    Severity::Emerg,
+    Severity::Alert,
+    Severity::Crit,
+    Severity::Err,
+    Severity::Warning,
+    Severity::Notice,
+    Severity::Info,
+    Severity::Debug,
+];
+
+impl Facility {
+    fn code(self) -> u8 {
+        match self {
+            Facility::Kern => 0,
+            Facility::User => 1,
+            Facility::Mail => 2,
+            Facility::Daemon => 3,
+            Facility::Auth => 4,
+            Facility::Syslog => 5,
+            Facility::Lpr => 6,
+            Facility::News => 7,
+            Facility::Uucp => 8,
+            Facility::Cron => 9,
+            Facility::Authpriv => 10,
+            Facility::Ftp => 11,
+            Facility::Ntp => 12,
+            Facility::Security => 13,
+            Facility::Console => 14,
+            Facility::SolarisCron => 15,
+            Facility::Local0 => 16,
+            Facility::Local1 => 17,
+            Facility::Local2 => 18,
+            Facility::Local3 => 19,
+            Facility::Local4 => 20,
+            Facility::Local5 => 21,
+            Facility::Local6 => 22,
+            Facility::Local7 => 23,
+        }
+    }
+
+    fn name(self) -> String {
+        self.to_possible_value()
+            .map(|value| value.get_name().to_string())
+            .unwrap_or_default()
+    }
+
+    fn from_code(code: u8) -> Option<Self> {
+        Facility::value_variants()
+            .iter()
+            .copied()
+            .find(|facility| facility.code() == code)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyslogFormat {
+    Rfc3164,
+    Rfc5424,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum SyslogError {
+    #[error("count must be at least 1")]
+    InvalidCount,
+    #[error(transparent)]
+    Network(#[from] NetworkError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("json serialize failed: {0}")]
+    Serialize(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SyslogMessage {
+    pub format: SyslogFormat,
+    pub facility: Option<Facility>,
+    pub severity: Severity,
+    pub timestamp: Option<String>,
+    pub hostname: Option<String>,
+    pub app_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proc_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub msg_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub structured_data: Option<String>,
+    pub message: String,
+}
+
+#[derive(Serialize)]
+struct ReceivedMessage<'a> {
+    peer: String,
+    transport: &'static str,
+    #[serde(flatten)]
+    message: &'a SyslogMessage,
+}
+
+pub fn run_syslog(opts: SyslogOpts) -> Result<(), SyslogError> {
+    match opts.command {
+        SyslogCommand::Send(send) => run_send(send),
+        SyslogCommand::Listen(listen) => run_listen(listen, &opts.record_output),
+    }
+}
+
+fn run_send(opts: SendOpts) -> Result<(), SyslogError> {
+    if opts.count == 0 {
+        return Err(SyslogError::InvalidCount);
+    }
+    network::check_endpoint("syslog send", &opts.server)?;
+    let hostname = opts.hostname.clone().unwrap_or_else(local_hostname);
+
+    let mut tcp = if opts.tcp {
+        Some(TcpStream::connect(&opts.server)?)
+    } else {
+        None
+    };
+    let udp = if opts.tcp {
+        None
+    } else {
+        let addr = opts
+            .server
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, opts.server.clone()))?;
+        let bind = if addr.is_ipv4() {
+            "0.0.0.0:0"
+        } else {
+            "[::]:0"
+        };
+        let socket = UdpSocket::bind(bind)?;
+        socket.connect(addr)?;
+        Some(socket)
+    };
+
+    for _ in 0..opts.count {
+        let line = format_message(
+            opts.format,
+            opts.facility,
+            opts.severity,
+            &hostname,
+            &opts.app_name,
+            &opts.message,
+        );
+        if let Some(stream) = tcp.as_mut() {
+            // RFC 6587 octet-counting 分帧
+            stream.write_all(format!("{} {}", line.len(), line).as_bytes())?;
+        } else if let Some(socket) = &udp {
+            socket.send(line.as_bytes())?;
+        }
+    }
+    if let Some(stream) = tcp.as_mut() {
+        stream.flush()?;
+    }
+
+    println!(
+        "Sent {} message(s) to {} via {}.",
+        opts.count,
+        opts.server,
+        if opts.tcp { "tcp" } else { "udp" }
+    );
+    Ok(())
+}
+
+pub fn format_message(
+    format: SyslogFormat,
+    facility: Facility,
+    severity: Severity,
+    hostname: &str,
+    app_name: &str,
+    message: &str,
+) -> String {
+    let pri = u16::from(facility.code()) * 8 + severity as u16;
+    let now = Local::now();
+    match format {
+        SyslogFormat::Rfc3164 => format!(
+            "<{}>{} {} {}[{}]: {}",
+            pri,
+            now.format("%b %e %H:%M:%S"),
+            hostname,
+            app_name,
+            std::process::id(),
+            message
+        ),
+        SyslogFormat::Rfc5424 => format!(
+            "<{}>1 {} {} {} {} - - {}",
+            pri,
+            now.to_rfc3339_opts(chrono::SecondsFormat::Millis, false),
+            nil_if_empty(hostname),
+            nil_if_empty(app_name),
+            std::process::id(),
+            message
+        ),
+    }
+}
+
+fn nil_if_empty(value: &str) -> &str {
+    if value.is_empty() {
+        "-"
+    } else {
+        value
+    }
+}
+
+pub(crate) fn local_hostname() -> String {
+    std::env::var("HOSTNAME")
+        .or_else(|_| std::env::var("COMPUTERNAME"))
+        .ok()
+        .filter(|name| !name.trim().is_empty())
+        .or_else(|| {
+            std::fs::read_to_string("/etc/hostname")
+                .ok()
+                .map(|name| name.trim().to_string())
+                .filter(|name| !name.is_empty())
+        })
+        .unwrap_or_else(|| "localhost".to_string())
+}
+
+fn run_listen(opts: ListenOpts, output: &RecordOutput) -> Result<(), SyslogError> {
+    network::check_endpoint("syslog listen", &opts.bind)?;
+    let (tx, rx) = mpsc::channel::<(SocketAddr, &'static str, Vec<u8>)>();
+
+    let udp = UdpSocket::bind(&opts.bind)?;
+    eprintln!("Listening on udp://{}", udp.local_addr()?);
+    let udp_tx = tx.clone();
+    thread::spawn(move || {
+        let mut buf = vec![0u8; MAX_MESSAGE_LEN];
+        while let Ok((len, peer)) = udp.recv_from(&mut buf) {
+            if udp_tx.send((peer, "udp", buf[..len].to_vec())).is_err() {
+                break;
+            }
+        }
+    });
+
+    if opts.tcp {
+        let listener = TcpListener::bind(&opts.bind)?;
+        eprintln!("Listening on tcp://{}", listener.local_addr()?);
+        let tcp_tx = tx.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let conn_tx = tcp_tx.clone();
+                thread::spawn(move || {
+                    let _ = read_tcp_frames(stream, conn_tx);
+                });
+            }
+        });
+    }
+    drop(tx);
+
+    // 与其它命令一致，-n 按收到的消息计数，被 --where 过滤掉的消息也计入
+    for (received, (peer, transport, raw)) in (1u64..).zip(rx) {
+        let text = String::from_utf8_lossy(&raw);
+        let message = parse_message(text.trim_end_matches(['\r', '\n', '\0']));
+        let record = ReceivedMessage {
+            peer: peer.to_string(),
+            transport,
+            message: &message,
+        };
+        if output.keep(&record)? {
+            print_message(&record, opts.json, output)?;
+        }
+        if opts.count.is_some_and(|limit| received >= limit) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+fn print_message(
+    record: &ReceivedMessage,
+    json: bool,
+    output: &RecordOutput,
+) -> Result<(), SyslogError> {
+    if json {
+        output.write_json_line(record, io::stdout().lock())?;
+        return Ok(());
+    }
+    let message = record.message;
+    println!(
+        "{} {} {}.{} {} {}: {}",
+        record.peer,
+        message.timestamp.as_deref().unwrap_or("-"),
+        message.facility.map_or("-".to_string(), Facility::name),
+        format!("{:?}", message.severity).to_lowercase(),
+        message.hostname.as_deref().unwrap_or("-"),
+        message.app_name.as_deref().unwrap_or("-"),
+        message.message
+    );
+    Ok(())
+}
+
+// 同时兼容 octet-counting（"长度 消息"）与换行分隔两种 TCP 分帧
+fn read_tcp_frames(
+    stream: TcpStream,
+    tx: mpsc::Sender<(SocketAddr, &'static str, Vec<u8>)>,
+) -> io::Result<()> {
+    let peer = stream.peer_addr()?;
+    let mut reader = BufReader::new(stream);
+    loop {
+        let buf = reader.fill_buf()?;
+        let Some(&first) = buf.first() else {
+            return Ok(());
+        };
+
+        let frame = if first.is_ascii_digit() {
+            let mut len_text = Vec::new();
+            reader.read_until(b' ', &mut len_text)?;
+            let len: usize = std::str::from_utf8(&len_text)
+                .ok()
+                .and_then(|text| text.trim().parse().ok())
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "bad frame length"))?;
+            let mut frame = vec![0u8; len.min(MAX_MESSAGE_LEN)];
+            reader.read_exact(&mut frame)?;
+            if len > MAX_MESSAGE_LEN {
+                io::copy(
+                    &mut (&mut reader).take((len - MAX_MESSAGE_LEN) as u64),
+                    &mut io::sink(),
+                )?;
+            }
+            frame
+        } else {
+            let mut line = Vec::new();
+            reader.read_until(b'\n', &mut line)?;
+            line.truncate(MAX_MESSAGE_LEN);
+            line
+        };
+
+        if tx.send((peer, "tcp", frame)).is_err() {
+            return Ok(());
+        }
+    }
+}
+
+// 解析 RFC 5424 或 RFC 3164 消息，无法识别的部分原样放入 message
+pub fn parse_message(raw: &str) -> SyslogMessage {
+    let (pri, rest) = match parse_pri(raw) {
+        Some((pri, rest)) => (Some(pri), rest),
+        None => (None, raw),
+    };
+    // 没有 PRI 时按 RFC 3164 的约定视为 user.notice
+    let facility = pri.map_or(Some(Facility::User), |p| Facility::from_code(p / 8));
+    let severity = SEVERITIES[pri.map_or(5, |p| p % 8) as usize];
+
+    if let Some(rest) = rest.strip_prefix("1 ") {
+        let mut fields = rest.splitn(6, ' ');
+        let mut next = || fields.next().filter(|f| *f != "-").map(str::to_string);
+        let timestamp = next();
+        let hostname = next();
+        let app_name = next();
+        let proc_id = next();
+        let msg_id = next();
+        let tail = fields.next().unwrap_or("");
+        let (structured_data, message) = split_structured_data(tail);
+        return SyslogMessage {
+            format: SyslogFormat::Rfc5424,
+            facility,
+            severity,
+            timestamp,
+            hostname,
+            app_name,
+            proc_id,
+            msg_id,
+            structured_data,
+            message: message.trim_start_matches('\u{feff}').to_string(),
+        };
+    }
+
+    let (timestamp, rest) = split_bsd_timestamp(rest);
+    let (hostname, rest) = match timestamp {
+        Some(_) => match rest.split_once(' ') {
+            Some((host, rest)) if !host.ends_with(':') => (Some(host.to_string()), rest),
+            _ => (None, rest),
+        },
+        None => (None, rest),
+    };
+    let (app_name, proc_id, message) = split_bsd_tag(rest);
+
+    SyslogMessage {
+        format: SyslogFormat::Rfc3164,
+        facility,
+        severity,
+        timestamp,
+        hostname,
+        app_name,
+        proc_id,
+        msg_id: None,
+        structured_data: None,
+        message: message.to_string(),
+    }
+}
+
+fn parse_pri(raw: &str) -> Option<(u8, &str)> {
+    let rest = raw.strip_prefix('<')?;
+    let end = rest.find('>')?;
+    if end == 0 || end > 3 {
+        return None;
+    }
+    let pri: u8 = rest[..end].parse().ok()?;
+    (pri <= 191).then_some((pri, &rest[end + 1..]))
+}
+
+fn split_structured_data(tail: &str) -> (Option<String>, &str) {
+    if let Some(message) = tail.strip_prefix('-') {
+        return (None, message.strip_prefix(' ').unwrap_or(message));
+    }
+    if !tail.starts_with('[') {
+        return (None, tail);
+    }
+
+    // 逐字符扫描，跳过参数值中转义的 ] 和 "
+    let mut in_quotes = false;
+    let mut escaped = false;
+    let mut end = 0;
+    for (index, ch) in tail.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match ch {
+            '\\' => escaped = true,
+            '"' => in_quotes = !in_quotes,
+            ']' if !in_quotes => {
+                end = index + 1;
+                if !tail[end..].starts_with('[') {
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+    if end == 0 {
+        return (None, tail);
+    }
+    let message = &tail[end..];
+    (
+        Some(tail[..end].to_string()),
+        message.strip_prefix(' ').unwrap_or(message),
+    )
+}
+
+fn split_bsd_timestamp(rest: &str) -> (Option<String>, &str) {
+    // "Mmm dd hh:mm:ss " 固定 16 个字符
+    let bytes = rest.as_bytes();
+    let looks_like_timestamp = bytes.len() > 16
+        && bytes[3] == b' '
+        && bytes[6] == b' '
+        && bytes[9] == b':'
+        && bytes[12] == b':'
+        && bytes[15] == b' '
+        && bytes[..3].iter().all(|b| b.is_ascii_alphabetic());
+    if looks_like_timestamp && rest.is_char_boundary(16) {
+        (Some(rest[..15].to_string()), &rest[16..])
+    } else {
+        (None, rest)
+    }
+}
+
+fn split_bsd_tag(rest: &str) -> (Option<String>, Option<String>, &str) {
+    let Some((tag, message)) = rest.split_once(": ") else {
+        return (None, None, rest);
+    };
+    if tag.is_empty() || tag.len() > 48 || tag.contains(' ') {
+        return (None, None, rest);
+    }
+    match tag.split_once('[') {
+        Some((app, pid)) if pid.ends_with(']') => (
+            Some(app.to_string()),
+            Some(pid.trim_end_matches(']').to_string()),
+            message,
+        ),
+        _ => (Some(tag.to_string()), None, message),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_rfc5424() {
+        let line = format_message(
+            SyslogFormat::Rfc5424,
+            Facility::Local3,
+            Severity::Warning,
+            "web01",
+            "rtoolkit",
+            "disk almost full",
+        );
+        assert!(line.starts_with("<156>1 "));
+
+        let parsed = parse_message(&line);
+        assert_eq!(parsed.format, SyslogFormat::Rfc5424);
+        assert_eq!(parsed.facility, Some(Facility::Local3));
+        assert_eq!(parsed.severity, Severity::Warning);
+        assert_eq!(parsed.hostname.as_deref(), Some("web01"));
+        assert_eq!(parsed.app_name.as_deref(), Some("rtoolkit"));
+        assert_eq!(parsed.message, "disk almost full");
+    }
+
+    #[test]
+    fn round_trips_rfc3164() {
+        let line = format_message(
+            SyslogFormat::Rfc3164,
+            Facility::Auth,
+            Severity::Err,
+            "gw",
+            "sshd",
+            "failed password",
+        );
+        let parsed = parse_message(&line);
+        assert_eq!(parsed.format, SyslogFormat::Rfc3164);
+        assert_eq!(parsed.facility, Some(Facility::Auth));
+        assert_eq!(parsed.severity, Severity::Err);
+        assert!(parsed.timestamp.is_some());
+        assert_eq!(parsed.hostname.as_deref(), Some("gw"));
+        assert_eq!(parsed.app_name.as_deref(), Some("sshd"));
+        assert_eq!(parsed.proc_id, Some(std::process::id().to_string()));
+        assert_eq!(parsed.message, "failed password");
+    }
+
+    #[test]
+    fn every_facility_code_decodes() {
+        for code in 0..24 {
+            let facility = Facility::from_code(code).unwrap();
+            assert_eq!(facility.code(), code);
+        }
+        assert_eq!(Facility::SolarisCron.name(), "solaris-cron");
+        assert_eq!(
+            parse_message("<98>Oct 11 22:14:15 ntp1 ntpd: clock step").facility,
+            Some(Facility::Ntp)
+        );
+    }
+
+    #[test]
+    fn parses_structured_data() {
+        let parsed = parse_message(
+            r#"<165>1 2003-10-11T22:14:15.003Z mymachine evntslog - ID47 [exampleSDID@32473 iut="3" eventSource="App\]lication"][meta seq="1"] An application event"#,
+        );
+        assert_eq!(parsed.msg_id.as_deref(), Some("ID47"));
+        assert_eq!(parsed.proc_id, None);
+        assert_eq!(
+            parsed.structured_data.as_deref(),
+            Some(r#"[exampleSDID@32473 iut="3" eventSource="App\]lication"][meta seq="1"]"#)
+        );
+        assert_eq!(parsed.message, "An application event");
+    }
+
+    #[test]
+    fn tolerates_messages_without_header() {
+        let parsed = parse_message("just some text");
+        assert_eq!(parsed.format, SyslogFormat::Rfc3164);
+        assert_eq!(parsed.severity, Severity::Notice);
+        assert_eq!(parsed.message, "just some text");
+    }
+}
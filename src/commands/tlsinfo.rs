@@ -0,0 +1,386 @@
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tokio::net::TcpStream;
+use tokio_rustls::rustls::pki_types::{CertificateDer, ServerName};
+use tokio_rustls::TlsConnector;
+use x509_parser::certificate::X509Certificate;
+use x509_parser::objects::{oid2sn, oid_registry};
+use x509_parser::public_key::PublicKey;
+
+use crate::utils::http::Url;
+use crate::utils::network::{self, NetworkError};
+use crate::utils::output::RecordOutput;
+use crate::utils::tls;
+
+#[derive(clap::Args)]
+pub struct TlsInfoOpts {
+    #[arg(
+        value_name = "HOST[:PORT]",
+        help = "目标主机，默认端口 443；也可以直接给 https:// URL"
+    )]
+    target: String,
+
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "握手时发送的 SNI 和校验证书用的主机名，默认与目标主机相同"
+    )]
+    sni: Option<String>,
+
+    #[arg(
+        long = "warn-days",
+        value_name = "N",
+        default_value_t = 0,
+        help = "证书链中任一证书剩余有效天数少于 N 天时以非零状态退出；默认只在已过期时失败"
+    )]
+    warn_days: i64,
+
+    #[arg(
+        long = "timeout",
+        default_value_t = 10000,
+        value_name = "MS",
+        help = "连接和握手的超时时间(毫秒)"
+    )]
+    time_out: u64,
+
+    #[arg(long, help = "以 JSON 输出结果")]
+    json: bool,
+
+    // 全局 --where / --fields / --rename，由命令行入口注入
+    #[arg(skip)]
+    pub(crate) record_output: RecordOutput,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum TlsInfoError {
+    #[error("invalid target '{0}', expected HOST[:PORT] or https://...")]
+    InvalidTarget(String),
+    #[error("invalid server name '{0}'")]
+    InvalidServerName(String),
+    #[error("cannot resolve {0}")]
+    Resolve(String),
+    #[error("connection to {0} timed out")]
+    Timeout(String),
+    #[error("TLS handshake with {addr} failed: {source}")]
+    Handshake { addr: SocketAddr, source: io::Error },
+    #[error("server sent no certificate")]
+    NoCertificate,
+    #[error("certificate '{subject}' expired {} days ago", -days_left)]
+    Expired { subject: String, days_left: i64 },
+    #[error("certificate '{subject}' expires in {days_left} days, within --warn-days {warn_days}")]
+    Expiring {
+        subject: String,
+        days_left: i64,
+        warn_days: i64,
+    },
+    #[error(transparent)]
+    Network(#[from] NetworkError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("json serialize failed: {0}")]
+    Serialize(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Serialize)]
+struct TlsReport {
+    host: String,
+    port: u16,
+    address: String,
+    server_name: String,
+    protocol: String,
+    cipher: String,
+    alpn: Option<String>,
+    // 按内置根证书和主机名校验的结果，失败时 trust_error 给出原因
+    trusted: bool,
+    trust_error: Option<String>,
+    chain: Vec<ChainCert>,
+}
+
+#[derive(Debug, Serialize)]
+struct ChainCert {
+    subject: String,
+    issuer: String,
+    serial: String,
+    sans: Vec<String>,
+    not_before: String,
+    not_after: String,
+    days_left: i64,
+    key: String,
+    signature: String,
+    sha256: String,
+    self_signed: bool,
+}
+
+pub fn run_tls_info(opts: TlsInfoOpts) -> Result<(), TlsInfoError> {
+    let (host, port) = parse_target(&opts.target)?;
+    network::check_host("tlsinfo", &host)?;
+    let rt = tokio::runtime::Runtime::new()?;
+    let report = rt.block_on(async {
+        tokio::time::timeout(
+            Duration::from_millis(opts.time_out.max(1)),
+            inspect(&host, port, opts.sni.as_deref()),
+        )
+        .await
+        .map_err(|_| TlsInfoError::Timeout(format!("{}:{}", host, port)))?
+    })?;
+
+    // --where 不匹配时不输出报告，退出状态照常
+    if opts.record_output.keep(&report)? {
+        if opts.json {
+            opts.record_output
+                .write_report(&report, None, io::stdout().lock())?;
+        } else {
+            print_report(&report);
+        }
+    }
+
+    // 链中最早到期的证书决定退出状态
+    let Some(first) = report.chain.iter().min_by_key(|cert| cert.days_left) else {
+        return Err(TlsInfoError::NoCertificate);
+    };
+    if first.days_left < 0 {
+        return Err(TlsInfoError::Expired {
+            subject: first.subject.clone(),
+            days_left: first.days_left,
+        });
+    }
+    if first.days_left < opts.warn_days {
+        return Err(TlsInfoError::Expiring {
+            subject: first.subject.clone(),
+            days_left: first.days_left,
+            warn_days: opts.warn_days,
+        });
+    }
+    Ok(())
+}
+
+// host、host:port、[v6]:port 或 https:// URL
+fn parse_target(raw: &str) -> Result<(String, u16), TlsInfoError> {
+    let invalid = || TlsInfoError::InvalidTarget(raw.to_string());
+    if raw.contains("://") {
+        let url = Url::parse(raw).map_err(|_| invalid())?;
+        return Ok((url.host, url.port));
+    }
+    if raw.parse::<IpAddr>().is_ok() {
+        return Ok((raw.to_string(), 443));
+    }
+    let url = Url::parse(&format!("https://{}", raw)).map_err(|_| invalid())?;
+    if url.path != "/" {
+        return Err(invalid());
+    }
+    Ok((url.host, url.port))
+}
+
+async fn inspect(host: &str, port: u16, sni: Option<&str>) -> Result<TlsReport, TlsInfoError> {
+    let addr = tokio::net::lookup_host((host, port))
+        .await
+        .ok()
+        .and_then(|mut addrs| addrs.next())
+        .ok_or_else(|| TlsInfoError::Resolve(host.to_string()))?;
+    let name = sni.unwrap_or(host);
+    let server_name = match name.parse::<IpAddr>() {
+        Ok(ip) => ServerName::IpAddress(ip.into()),
+        Err(_) => ServerName::try_from(name.to_string())
+            .map_err(|_| TlsInfoError::InvalidServerName(name.to_string()))?,
+    };
+
+    let stream = TcpStream::connect(addr).await?;
+    // 接受任何证书完成握手，信任与否在下面单独校验，这样过期或自签名的证书也能看到内容
+    let mut config = tls::insecure_client_config();
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    let stream = TlsConnector::from(Arc::new(config))
+        .connect(server_name.clone(), stream)
+        .await
+        .map_err(|source| TlsInfoError::Handshake { addr, source })?;
+    let (_, session) = stream.get_ref();
+    let protocol = session
+        .protocol_version()
+        .and_then(|version| version.as_str())
+        .unwrap_or("TLS")
+        .replace('_', ".")
+        .replace("TLSv", "TLS ");
+    let cipher = session
+        .negotiated_cipher_suite()
+        .and_then(|suite| suite.suite().as_str())
+        .unwrap_or("unknown")
+        .to_string();
+    let alpn = session
+        .alpn_protocol()
+        .map(|alpn| String::from_utf8_lossy(alpn).into_owned());
+    let certs: Vec<CertificateDer<'static>> = session
+        .peer_certificates()
+        .ok_or(TlsInfoError::NoCertificate)?
+        .iter()
+        .map(|cert| cert.clone().into_owned())
+        .collect();
+
+    let trust = tls::verify_server_chain(&certs, &server_name);
+    let now = chrono::Utc::now().timestamp();
+    Ok(TlsReport {
+        host: host.to_string(),
+        port,
+        address: addr.ip().to_string(),
+        server_name: name.to_string(),
+        protocol,
+        cipher,
+        alpn,
+        trusted: trust.is_ok(),
+        trust_error: trust.err(),
+        chain: certs
+            .iter()
+            .filter_map(|der| describe_certificate(der, now))
+            .collect(),
+    })
+}
+
+fn describe_certificate(der: &[u8], now: i64) -> Option<ChainCert> {
+    let (_, cert) = x509_parser::parse_x509_certificate(der).ok()?;
+    let validity = cert.validity();
+    let not_after = validity.not_after.timestamp();
+    Some(ChainCert {
+        subject: cert.subject().to_string(),
+        issuer: cert.issuer().to_string(),
+        serial: cert.raw_serial_as_string(),
+        sans: tls::subject_alt_names(&cert),
+        not_before: tls::format_timestamp(validity.not_before.timestamp()),
+        not_after: tls::format_timestamp(not_after),
+        days_left: (not_after - now).div_euclid(86_400),
+        key: describe_key(&cert),
+        signature: oid2sn(&cert.signature_algorithm.algorithm, oid_registry())
+            .map(str::to_string)
+            .unwrap_or_else(|_| cert.signature_algorithm.algorithm.to_id_string()),
+        sha256: fingerprint(der),
+        self_signed: cert.subject() == cert.issuer(),
+    })
+}
+
+// 公钥算法和长度，例如 RSA 2048、EC P-256
+fn describe_key(cert: &X509Certificate<'_>) -> String {
+    let spki = cert.public_key();
+    match spki.parsed() {
+        Ok(PublicKey::RSA(rsa)) => format!("RSA {}", rsa.key_size()),
+        Ok(PublicKey::EC(point)) => {
+            let curve = spki
+                .algorithm
+                .parameters
+                .as_ref()
+                .and_then(|params| params.as_oid().ok())
+                .map(|oid| oid.to_id_string());
+            match curve.as_deref() {
+                Some("1.2.840.10045.3.1.7") => "EC P-256".to_string(),
+                Some("1.3.132.0.34") => "EC P-384".to_string(),
+                Some("1.3.132.0.35") => "EC P-521".to_string(),
+                _ => format!("EC {}", point.key_size()),
+            }
+        }
+        _ => oid2sn(&spki.algorithm.algorithm, oid_registry())
+            .map(str::to_string)
+            .unwrap_or_else(|_| spki.algorithm.algorithm.to_id_string()),
+    }
+}
+
+// 与浏览器证书查看器一致的大写、冒号分隔的 SHA-256 指纹
+fn fingerprint(der: &[u8]) -> String {
+    Sha256::digest(der)
+        .iter()
+        .map(|byte| format!("{:02X}", byte))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+fn print_report(report: &TlsReport) {
+    let host = if report.host.contains(':') {
+        format!("[{}]:{}", report.host, report.port)
+    } else {
+        format!("{}:{}", report.host, report.port)
+    };
+    println!("Host:      {} ({})", host, report.address);
+    if report.server_name != report.host {
+        println!("SNI:       {}", report.server_name);
+    }
+    println!("Protocol:  {}", report.protocol);
+    println!("Cipher:    {}", report.cipher);
+    println!("ALPN:      {}", report.alpn.as_deref().unwrap_or("(none)"));
+    match &report.trust_error {
+        None => println!("Trusted:   yes"),
+        Some(error) => println!("Trusted:   no ({})", error),
+    }
+    if let Some(leaf) = report.chain.first() {
+        let expiry = if leaf.days_left < 0 {
+            format!("expired {} days ago", -leaf.days_left)
+        } else {
+            format!("in {} days", leaf.days_left)
+        };
+        println!("Expires:   {} ({})", expiry, leaf.not_after);
+    }
+    println!();
+    println!("Certificate chain:");
+    for (depth, cert) in report.chain.iter().enumerate() {
+        println!(
+            "  {} {}{}",
+            depth,
+            cert.subject,
+            if cert.self_signed {
+                " (self-signed)"
+            } else {
+                ""
+            }
+        );
+        println!("      issuer:    {}", cert.issuer);
+        if !cert.sans.is_empty() {
+            println!("      SANs:      {}", cert.sans.join(", "));
+        }
+        println!(
+            "      valid:     {} .. {} ({} days left)",
+            cert.not_before, cert.not_after, cert.days_left
+        );
+        println!(
+            "      key:       {}, signed with {}",
+            cert.key, cert.signature
+        );
+        println!("      serial:    {}", cert.serial);
+        println!("      sha256:    {}", cert.sha256);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_target() {
+        assert_eq!(
+            parse_target("example.com").unwrap(),
+            ("example.com".to_string(), 443)
+        );
+        assert_eq!(
+            parse_target("example.com:8443").unwrap(),
+            ("example.com".to_string(), 8443)
+        );
+        assert_eq!(
+            parse_target("https://example.com/login").unwrap(),
+            ("example.com".to_string(), 443)
+        );
+        assert_eq!(parse_target("[::1]:993").unwrap(), ("::1".to_string(), 993));
+        assert_eq!(parse_target("::1").unwrap(), ("::1".to_string(), 443));
+        assert!(parse_target("example.com/path").is_err());
+    }
+
+    #[test]
+    fn test_describe_certificate() {
+        let key = rcgen::KeyPair::generate().unwrap();
+        let params = rcgen::CertificateParams::new(vec!["dev.local".to_string()]).unwrap();
+        let cert = params.self_signed(&key).unwrap();
+        let info = describe_certificate(cert.der(), chrono::Utc::now().timestamp()).unwrap();
+        assert_eq!(info.sans, vec!["dev.local"]);
+        assert_eq!(info.key, "EC P-256");
+        assert_eq!(info.signature, "ecdsa-with-SHA256");
+        assert!(info.self_signed);
+        assert_eq!(info.sha256.len(), 32 * 3 - 1);
+    }
+}
@@ -0,0 +1,741 @@
+use std::collections::HashMap;
+use std::io;
+use std::mem::MaybeUninit;
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::{Duration, Instant};
+
+use hickory_resolver::TokioResolver;
+use serde::Serialize;
+use socket2::{Domain, Protocol, SockRef, Socket, Type};
+
+use crate::utils::icmp::{self, IcmpError};
+use crate::utils::network::{self, NetworkError};
+use crate::utils::output::RecordOutput;
+
+const PROTO_ICMP: u8 = 1;
+const PROTO_UDP: u8 = 17;
+const PROTO_ICMPV6: u8 = 58;
+const RESOLVE_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(clap::Args)]
+pub struct TraceOpts {
+    #[arg(value_name = "HOST", help = "目标主机")]
+    host: String,
+
+    #[arg(
+        short = 'I',
+        long,
+        help = "使用 ICMP 回显代替 UDP 探测（需要原始套接字权限）"
+    )]
+    icmp: bool,
+
+    #[arg(
+        short = 'm',
+        long = "max-hops",
+        default_value_t = 30,
+        value_name = "N",
+        value_parser = clap::value_parser!(u8).range(1..=64),
+        help = "最大跳数"
+    )]
+    max_hops: u8,
+
+    #[arg(
+        short = 'q',
+        long,
+        default_value_t = 3,
+        value_name = "N",
+        value_parser = clap::value_parser!(u8).range(1..=10),
+        help = "每一跳发送的探测数"
+    )]
+    queries: u8,
+
+    #[arg(
+        short = 'p',
+        long,
+        default_value_t = 33434,
+        value_name = "PORT",
+        help = "UDP 探测的起始目标端口，每个探测依次加一"
+    )]
+    port: u16,
+
+    #[arg(
+        long = "timeout",
+        default_value_t = 1000,
+        value_name = "MS",
+        help = "每一跳等待回应的超时时间(毫秒)"
+    )]
+    time_out: u64,
+
+    #[arg(short = 'n', long = "no-dns", help = "不反向解析各跳地址")]
+    no_dns: bool,
+
+    #[arg(long, help = "以 JSON 输出结果")]
+    json: bool,
+
+    // 全局 --where / --fields / --rename，由命令行入口注入
+    #[arg(skip)]
+    pub(crate) record_output: RecordOutput,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum TraceError {
+    #[error("cannot resolve host: {0}")]
+    Resolve(String),
+    #[error("{0}")]
+    Permission(String),
+    #[error(transparent)]
+    Network(#[from] NetworkError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("json serialize failed: {0}")]
+    Serialize(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Serialize)]
+pub struct Hop {
+    pub ttl: u8,
+    // 第一个回应探测的地址，整跳都没有回应时为 None
+    pub addr: Option<String>,
+    pub hostname: Option<String>,
+    pub rtts_ms: Vec<Option<f64>>,
+    // 目标或路由器回送的不可达差错，例如 !H、!N、!X
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TraceReport {
+    pub host: String,
+    pub addr: String,
+    pub protocol: &'static str,
+    pub max_hops: u8,
+    pub reached: bool,
+    pub hops: Vec<Hop>,
+}
+
+// 一个探测收到的回应
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Response {
+    seq: u16,
+    from: IpAddr,
+    at: Instant,
+    // None 为目标的回显应答
+    error: Option<IcmpError>,
+}
+
+enum Tracer {
+    // 原始 ICMP 套接字能收到所有 ICMP 报文，UDP 模式另用普通 UDP 套接字发送探测
+    Raw {
+        icmp: Socket,
+        udp: Option<UdpSocket>,
+        id: u16,
+    },
+    // 无权限时的 UDP 模式：开启 IP_RECVERR 后，内核把探测引发的 ICMP 差错放进套接字的错误队列
+    #[cfg(target_os = "linux")]
+    ErrQueue(UdpSocket),
+}
+
+struct Probe {
+    target: IpAddr,
+    base_port: u16,
+}
+
+impl Tracer {
+    fn open(target: IpAddr, use_icmp: bool) -> Result<Self, TraceError> {
+        let (domain, protocol) = if target.is_ipv4() {
+            (Domain::IPV4, Protocol::ICMPV4)
+        } else {
+            (Domain::IPV6, Protocol::ICMPV6)
+        };
+        let raw_error = match Socket::new(domain, Type::RAW, Some(protocol)) {
+            Ok(icmp) => {
+                let udp = if use_icmp {
+                    None
+                } else {
+                    Some(UdpSocket::bind(unspecified(target))?)
+                };
+                return Ok(Tracer::Raw {
+                    icmp,
+                    udp,
+                    id: std::process::id() as u16,
+                });
+            }
+            Err(e) => e,
+        };
+        #[cfg(target_os = "linux")]
+        if !use_icmp {
+            return Ok(Tracer::ErrQueue(linux::open(target)?));
+        }
+        Err(TraceError::Permission(if use_icmp {
+            format!(
+                "ICMP traceroute needs a raw socket ({}), run as root or use the default UDP mode",
+                raw_error
+            )
+        } else {
+            format!(
+                "traceroute needs a raw socket ({}), run as root / administrator",
+                raw_error
+            )
+        }))
+    }
+
+    fn protocol(&self) -> &'static str {
+        match self {
+            Tracer::Raw { udp: None, .. } => "icmp",
+            _ => "udp",
+        }
+    }
+
+    fn send(&self, probe: &Probe, ttl: u8, seq: u16) -> io::Result<()> {
+        let ipv4 = probe.target.is_ipv4();
+        let udp = match self {
+            Tracer::Raw { udp: Some(udp), .. } => udp,
+            Tracer::Raw { icmp, id, .. } => {
+                set_hop_limit(&SockRef::from(icmp), ipv4, ttl)?;
+                let packet = icmp::echo_request(ipv4, *id, seq, 32);
+                icmp.send_to(&packet, &SocketAddr::new(probe.target, 0).into())?;
+                return Ok(());
+            }
+            #[cfg(target_os = "linux")]
+            Tracer::ErrQueue(udp) => udp,
+        };
+        set_hop_limit(&SockRef::from(udp), ipv4, ttl)?;
+        let port = probe.base_port.wrapping_add(seq);
+        let dest = SocketAddr::new(probe.target, port);
+        // 开启 IP_RECVERR 后，前一个探测引发的差错会让本次发送失败（报文没有发出），重发即可
+        let mut pending = 0;
+        loop {
+            match udp.send_to(&[0u8; 32], dest) {
+                Ok(_) => return Ok(()),
+                Err(e) if pending < 8 && is_icmp_error(&e) => pending += 1,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    // 等待下一个属于本次追踪的回应，到 deadline 仍没有时为 None
+    fn recv(&self, probe: &Probe, deadline: Instant) -> io::Result<Option<Response>> {
+        match self {
+            Tracer::Raw { icmp, udp, id } => {
+                let local_port = udp.as_ref().map(UdpSocket::local_addr).transpose()?;
+                let ipv4 = probe.target.is_ipv4();
+                let mut buf = [0u8; 2048];
+                loop {
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    if remaining.is_zero() {
+                        return Ok(None);
+                    }
+                    icmp.set_read_timeout(Some(remaining))?;
+                    let (n, from) = match recv_from(icmp, &mut buf) {
+                        Ok(received) => received,
+                        Err(e) if is_timeout(&e) => return Ok(None),
+                        Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                        Err(e) => return Err(e),
+                    };
+                    let at = Instant::now();
+                    let packet = if ipv4 {
+                        icmp::strip_ipv4_header(&buf[..n])
+                    } else {
+                        Some(&buf[..n])
+                    };
+                    let Some(packet) = packet else {
+                        continue;
+                    };
+                    let matched = match local_port {
+                        Some(local) => match_udp(packet, probe, local.port()),
+                        None => match_icmp(packet, probe, *id, from),
+                    };
+                    if let Some((seq, error)) = matched {
+                        return Ok(Some(Response {
+                            seq,
+                            from,
+                            at,
+                            error,
+                        }));
+                    }
+                }
+            }
+            #[cfg(target_os = "linux")]
+            Tracer::ErrQueue(udp) => linux::recv(udp, probe, deadline),
+        }
+    }
+}
+
+// 引用的 UDP 报文由本套接字发往目标，目标端口换算出探测序号
+fn match_udp(packet: &[u8], probe: &Probe, local_port: u16) -> Option<(u16, Option<IcmpError>)> {
+    let quoted = icmp::parse_icmp_error(probe.target.is_ipv4(), packet)?;
+    let src = u16::from_be_bytes([quoted.transport[0], quoted.transport[1]]);
+    let dst = u16::from_be_bytes([quoted.transport[2], quoted.transport[3]]);
+    if quoted.protocol != PROTO_UDP || quoted.dst != probe.target || src != local_port {
+        return None;
+    }
+    Some((dst.wrapping_sub(probe.base_port), Some(quoted.error)))
+}
+
+// 目标的回显应答，或引用了本进程回显请求的差错报文
+fn match_icmp(
+    packet: &[u8],
+    probe: &Probe,
+    id: u16,
+    from: IpAddr,
+) -> Option<(u16, Option<IcmpError>)> {
+    let ipv4 = probe.target.is_ipv4();
+    if let Some(reply) = icmp::parse_echo_reply(ipv4, packet) {
+        return (reply.id == id && from == probe.target).then_some((reply.seq, None));
+    }
+    let quoted = icmp::parse_icmp_error(ipv4, packet)?;
+    let protocol = if ipv4 { PROTO_ICMP } else { PROTO_ICMPV6 };
+    let echo = icmp::echo_request(ipv4, id, 0, 0)[0];
+    let transport = quoted.transport;
+    let quoted_id = u16::from_be_bytes([transport[4], transport[5]]);
+    if quoted.protocol != protocol || quoted.dst != probe.target || transport[0] != echo {
+        return None;
+    }
+    (quoted_id == id).then(|| {
+        (
+            u16::from_be_bytes([transport[6], transport[7]]),
+            Some(quoted.error),
+        )
+    })
+}
+
+fn recv_from(socket: &Socket, buf: &mut [u8]) -> io::Result<(usize, IpAddr)> {
+    // SAFETY: recv_from 只向缓冲区写入，已初始化的字节切片可以当作 MaybeUninit 使用
+    let uninit = unsafe { &mut *(buf as *mut [u8] as *mut [MaybeUninit<u8>]) };
+    let (n, addr) = socket.recv_from(uninit)?;
+    let from = addr
+        .as_socket()
+        .map(|addr| addr.ip())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "non-IP source address"))?;
+    Ok((n, from))
+}
+
+fn set_hop_limit(socket: &SockRef<'_>, ipv4: bool, ttl: u8) -> io::Result<()> {
+    if ipv4 {
+        socket.set_ttl_v4(u32::from(ttl))
+    } else {
+        socket.set_unicast_hops_v6(u32::from(ttl))
+    }
+}
+
+fn unspecified(target: IpAddr) -> SocketAddr {
+    match target {
+        IpAddr::V4(_) => SocketAddr::from(([0, 0, 0, 0], 0)),
+        IpAddr::V6(_) => SocketAddr::from(([0u16; 8], 0)),
+    }
+}
+
+fn is_timeout(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+    )
+}
+
+fn is_icmp_error(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::ConnectionRefused
+            | io::ErrorKind::HostUnreachable
+            | io::ErrorKind::NetworkUnreachable
+    )
+}
+
+// 与 traceroute 一致的不可达标记；目标回送的端口不可达表示已到达，不加标记
+fn unreachable_note(ipv4: bool, code: u8) -> String {
+    let note = match (ipv4, code) {
+        (true, 0) | (false, 0) => "!N",
+        (true, 1) | (false, 3) => "!H",
+        (true, 2) => "!P",
+        (true, 9 | 10 | 13) | (false, 1) => "!X",
+        _ => return format!("!<{}>", code),
+    };
+    note.to_string()
+}
+
+fn is_port_unreachable(ipv4: bool, code: u8) -> bool {
+    if ipv4 {
+        code == 3
+    } else {
+        code == 4
+    }
+}
+
+// 逐跳发送探测并收集回应；到达目标或收到不可达差错后停止，每完成一跳调用一次 on_hop
+fn trace(
+    tracer: &Tracer,
+    probe: &Probe,
+    max_hops: u8,
+    queries: u8,
+    timeout: Duration,
+    mut on_hop: impl FnMut(&mut Hop),
+) -> io::Result<(Vec<Hop>, bool)> {
+    let ipv4 = probe.target.is_ipv4();
+    let mut hops = Vec::new();
+    for ttl in 1..=max_hops {
+        let first = u16::from(ttl - 1) * u16::from(queries);
+        let mut sent = Vec::with_capacity(queries as usize);
+        for query in 0..u16::from(queries) {
+            sent.push(Instant::now());
+            tracer.send(probe, ttl, first + query)?;
+        }
+        let mut hop = Hop {
+            ttl,
+            addr: None,
+            hostname: None,
+            rtts_ms: vec![None; queries as usize],
+            note: None,
+        };
+        let mut reached = false;
+        let mut stop = false;
+        let deadline = Instant::now() + timeout;
+        while hop.rtts_ms.iter().any(Option::is_none) {
+            let Some(response) = tracer.recv(probe, deadline)? else {
+                break;
+            };
+            // 前几跳迟到的回应序号不在本跳范围内，直接忽略
+            let Some(index) = response
+                .seq
+                .checked_sub(first)
+                .map(usize::from)
+                .filter(|index| *index < sent.len())
+            else {
+                continue;
+            };
+            let rtt = response.at.duration_since(sent[index]).as_secs_f64();
+            hop.rtts_ms[index].get_or_insert((rtt * 100_000.0).round() / 100.0);
+            hop.addr.get_or_insert_with(|| response.from.to_string());
+            match response.error {
+                None => reached = true,
+                Some(IcmpError::TimeExceeded) => {}
+                Some(IcmpError::Unreachable(code)) => {
+                    stop = true;
+                    if response.from == probe.target && is_port_unreachable(ipv4, code) {
+                        reached = true;
+                    } else {
+                        hop.note = Some(unreachable_note(ipv4, code));
+                    }
+                }
+            }
+        }
+        on_hop(&mut hop);
+        hops.push(hop);
+        if reached || stop {
+            return Ok((hops, reached));
+        }
+    }
+    Ok((hops, false))
+}
+
+pub fn run_trace(opts: TraceOpts) -> Result<(), TraceError> {
+    network::check_host("trace", &opts.host)?;
+    let target = (opts.host.as_str(), 0)
+        .to_socket_addrs()
+        .map_err(|e| TraceError::Resolve(format!("{}: {}", opts.host, e)))?
+        .next()
+        .ok_or_else(|| TraceError::Resolve(opts.host.clone()))?
+        .ip();
+    let tracer = Tracer::open(target, opts.icmp)?;
+    let probe = Probe {
+        target,
+        base_port: opts.port,
+    };
+    let timeout = Duration::from_millis(opts.time_out.clamp(50, 30_000));
+
+    // 反向解析在每跳结束后进行，同一地址只查一次
+    let rt = tokio::runtime::Runtime::new()?;
+    let resolver = if opts.no_dns || network::skip("reverse DNS of hops") {
+        None
+    } else {
+        match TokioResolver::builder_tokio() {
+            Ok(builder) => Some(builder.build()),
+            Err(_) => {
+                eprintln!("warning: cannot load system resolver config, reverse DNS skipped");
+                None
+            }
+        }
+    };
+    let mut names: HashMap<String, Option<String>> = HashMap::new();
+    let mut lookup = |addr: &str| -> Option<String> {
+        let resolver = resolver.as_ref()?;
+        names
+            .entry(addr.to_string())
+            .or_insert_with(|| {
+                let ip: IpAddr = addr.parse().ok()?;
+                let lookup = rt
+                    .block_on(async {
+                        tokio::time::timeout(RESOLVE_TIMEOUT, resolver.reverse_lookup(ip)).await
+                    })
+                    .ok()?
+                    .ok()?;
+                let name = lookup.iter().next()?.to_utf8();
+                Some(name.trim_end_matches('.').to_string())
+            })
+            .clone()
+    };
+
+    if !opts.json {
+        println!(
+            "traceroute to {} ({}), {} hops max, {}",
+            opts.host,
+            target,
+            opts.max_hops,
+            tracer.protocol()
+        );
+    }
+    // 回调里不能用 ?，先记下第一个 --where 错误，探测结束后再返回
+    let mut filter_error = None;
+    let (mut hops, reached) = trace(
+        &tracer,
+        &probe,
+        opts.max_hops,
+        opts.queries,
+        timeout,
+        |hop| {
+            hop.hostname = hop.addr.as_deref().and_then(&mut lookup);
+            if !opts.json {
+                match opts.record_output.keep(&*hop) {
+                    Ok(true) => print_hop(hop),
+                    Ok(false) => {}
+                    Err(e) => {
+                        filter_error.get_or_insert(e);
+                    }
+                }
+            }
+        },
+    )?;
+    if let Some(e) = filter_error {
+        return Err(e.into());
+    }
+    if opts.json {
+        opts.record_output.retain(&mut hops)?;
+    }
+    let report = TraceReport {
+        host: opts.host,
+        addr: target.to_string(),
+        protocol: tracer.protocol(),
+        max_hops: opts.max_hops,
+        reached,
+        hops,
+    };
+    if opts.json {
+        opts.record_output
+            .write_report(&report, Some("hops"), io::stdout().lock())?;
+    } else if !report.reached {
+        match report
+            .hops
+            .last()
+            .and_then(|hop| Some((hop.ttl, hop.note.as_ref()?)))
+        {
+            Some((ttl, note)) => eprintln!(
+                "warning: {} is unreachable, hop {} answered {}",
+                report.host, ttl, note
+            ),
+            None => eprintln!(
+                "warning: {} not reached within {} hops",
+                report.host, report.max_hops
+            ),
+        }
+    }
+    Ok(())
+}
+
+fn print_hop(hop: &Hop) {
+    let mut line = format!("{:>2}", hop.ttl);
+    match (&hop.addr, &hop.hostname) {
+        (Some(addr), Some(name)) => line.push_str(&format!("  {} ({})", name, addr)),
+        (Some(addr), None) => line.push_str(&format!("  {}", addr)),
+        (None, _) => {}
+    }
+    for rtt in &hop.rtts_ms {
+        match rtt {
+            Some(rtt) => line.push_str(&format!("  {} ms", rtt)),
+            None => line.push_str("  *"),
+        }
+    }
+    if let Some(note) = &hop.note {
+        line.push_str(&format!(" {}", note));
+    }
+    println!("{}", line);
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::io;
+    use std::mem;
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket};
+    use std::os::fd::AsRawFd;
+    use std::time::Instant;
+
+    use super::{unspecified, Probe, Response};
+    use crate::utils::icmp::IcmpError;
+
+    pub fn open(target: IpAddr) -> io::Result<UdpSocket> {
+        let socket = UdpSocket::bind(unspecified(target))?;
+        let (level, name) = if target.is_ipv4() {
+            (libc::SOL_IP, libc::IP_RECVERR)
+        } else {
+            (libc::SOL_IPV6, libc::IPV6_RECVERR)
+        };
+        let enable: libc::c_int = 1;
+        let ret = unsafe {
+            libc::setsockopt(
+                socket.as_raw_fd(),
+                level,
+                name,
+                &enable as *const libc::c_int as *const libc::c_void,
+                mem::size_of::<libc::c_int>() as libc::socklen_t,
+            )
+        };
+        if ret != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(socket)
+    }
+
+    // 错误队列中的报文：msg_name 为探测的原目标（端口即序号），控制消息携带 ICMP 类型和回送差错的地址
+    pub fn recv(
+        socket: &UdpSocket,
+        probe: &Probe,
+        deadline: Instant,
+    ) -> io::Result<Option<Response>> {
+        let fd = socket.as_raw_fd();
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Ok(None);
+            }
+            // events 为 0 时 poll 仍会报告 POLLERR，即错误队列非空
+            let mut poll = libc::pollfd {
+                fd,
+                events: 0,
+                revents: 0,
+            };
+            let wait = remaining.as_millis().clamp(1, i32::MAX as u128) as libc::c_int;
+            match unsafe { libc::poll(&mut poll, 1, wait) } {
+                0 => return Ok(None),
+                n if n < 0 => {
+                    let e = io::Error::last_os_error();
+                    if e.kind() == io::ErrorKind::Interrupted {
+                        continue;
+                    }
+                    return Err(e);
+                }
+                _ => {}
+            }
+
+            let mut data = [0u8; 512];
+            let mut control = [0u8; 512];
+            let mut name: libc::sockaddr_storage = unsafe { mem::zeroed() };
+            let mut iov = libc::iovec {
+                iov_base: data.as_mut_ptr().cast(),
+                iov_len: data.len(),
+            };
+            let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+            msg.msg_name = (&mut name as *mut libc::sockaddr_storage).cast();
+            msg.msg_namelen = mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+            msg.msg_iov = &mut iov;
+            msg.msg_iovlen = 1;
+            msg.msg_control = control.as_mut_ptr().cast();
+            msg.msg_controllen = control.len() as _;
+            if unsafe { libc::recvmsg(fd, &mut msg, libc::MSG_ERRQUEUE) } < 0 {
+                let e = io::Error::last_os_error();
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::Interrupted
+                ) {
+                    continue;
+                }
+                return Err(e);
+            }
+            let at = Instant::now();
+
+            let Some(dst) = (unsafe { socket_addr(msg.msg_name as *const libc::sockaddr) }) else {
+                continue;
+            };
+            if dst.ip() != probe.target {
+                continue;
+            }
+            let mut cmsg = unsafe { libc::CMSG_FIRSTHDR(&msg) };
+            while !cmsg.is_null() {
+                let header = unsafe { &*cmsg };
+                let recverr = (header.cmsg_level == libc::SOL_IP
+                    && header.cmsg_type == libc::IP_RECVERR)
+                    || (header.cmsg_level == libc::SOL_IPV6
+                        && header.cmsg_type == libc::IPV6_RECVERR);
+                if recverr {
+                    let ee = unsafe { libc::CMSG_DATA(cmsg) } as *const libc::sock_extended_err;
+                    let err = unsafe { ee.read_unaligned() };
+                    let icmp = matches!(
+                        err.ee_origin,
+                        libc::SO_EE_ORIGIN_ICMP | libc::SO_EE_ORIGIN_ICMP6
+                    );
+                    let error = IcmpError::from_type(dst.is_ipv4(), err.ee_type, err.ee_code);
+                    let from = unsafe { socket_addr(libc::SO_EE_OFFENDER(ee)) };
+                    if let (true, Some(error), Some(from)) = (icmp, error, from) {
+                        return Ok(Some(Response {
+                            seq: dst.port().wrapping_sub(probe.base_port),
+                            from: from.ip(),
+                            at,
+                            error: Some(error),
+                        }));
+                    }
+                }
+                cmsg = unsafe { libc::CMSG_NXTHDR(&msg, cmsg) };
+            }
+        }
+    }
+
+    unsafe fn socket_addr(addr: *const libc::sockaddr) -> Option<SocketAddr> {
+        match i32::from(unsafe { addr.read_unaligned() }.sa_family) {
+            libc::AF_INET => {
+                let addr = unsafe { (addr as *const libc::sockaddr_in).read_unaligned() };
+                let ip = Ipv4Addr::from(u32::from_be(addr.sin_addr.s_addr));
+                Some(SocketAddr::new(IpAddr::V4(ip), u16::from_be(addr.sin_port)))
+            }
+            libc::AF_INET6 => {
+                let addr = unsafe { (addr as *const libc::sockaddr_in6).read_unaligned() };
+                let ip = Ipv6Addr::from(addr.sin6_addr.s6_addr);
+                Some(SocketAddr::new(
+                    IpAddr::V6(ip),
+                    u16::from_be(addr.sin6_port),
+                ))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trace_loopback() {
+        let target: IpAddr = "127.0.0.1".parse().unwrap();
+        let probe = Probe {
+            target,
+            base_port: 33434,
+        };
+        // 无论是否有原始套接字权限，Linux 上 UDP 追踪都可用
+        #[cfg(target_os = "linux")]
+        let tracers = vec![
+            Tracer::ErrQueue(linux::open(target).unwrap()),
+            Tracer::open(target, false).unwrap(),
+        ];
+        #[cfg(not(target_os = "linux"))]
+        let tracers: Vec<Tracer> = Tracer::open(target, false).into_iter().collect();
+        for tracer in tracers {
+            let mut seen = 0;
+            let (hops, reached) =
+                trace(&tracer, &probe, 5, 2, Duration::from_secs(1), |_| seen += 1).unwrap();
+            assert!(reached);
+            assert_eq!(seen, 1);
+            assert_eq!(hops[0].addr.as_deref(), Some("127.0.0.1"));
+            assert!(hops[0].rtts_ms.iter().all(Option::is_some));
+            assert_eq!(hops[0].note, None);
+        }
+        assert_eq!(unreachable_note(true, 1), "!H");
+        assert_eq!(unreachable_note(false, 1), "!X");
+    }
+}
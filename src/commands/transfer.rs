@@ -0,0 +1,977 @@
+// 局域网文件传输（类似 croc）：send 生成一次性口令并等待，recv 凭口令通过 mDNS 或中继找到发送方。
+// 握手复用 pipe 的 PBKDF2 + HMAC 双向认证，口令错误的连接无法完成握手；
+// 之后控制消息始终加密，文件数据默认加密，--no-encrypt 时明文传输但仍由加密的 SHA-256 摘要校验。
+
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::net::{IpAddr, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use chrono::Local;
+use rand::{Rng, RngCore};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::oneshot;
+
+use crate::commands::pipe::{self, PipeError, SALT_LEN};
+use crate::utils::format::human_bytes;
+use crate::utils::mdns;
+use crate::utils::network::{self, NetworkError};
+use crate::utils::progress::{Progress, ProgressDisplay};
+
+const SERVICE: &str = "_rtoolkit-send._tcp";
+// 口令字符集去掉了容易混淆的 l、o、0、1
+const CODE_CHARSET: &[u8] = b"abcdefghijkmnpqrstuvwxyz23456789";
+const CODE_GROUPS: usize = 4;
+const GROUP_LEN: usize = 4;
+const MIN_CODE_LEN: usize = 12;
+const CHUNK_SIZE: usize = 64 * 1024;
+const TAG_LEN: usize = 16;
+const MAX_FRAME: usize = CHUNK_SIZE + TAG_LEN;
+
+const RELAY_MAGIC: &[u8; 8] = b"RTKRELY1";
+const DEFAULT_RELAY_PORT: u16 = 9009;
+const ROLE_SEND: u8 = 0;
+const ROLE_RECV: u8 = 1;
+const RELAY_PAIRED: u8 = 0;
+const RELAY_BUSY: u8 = 1;
+const RELAY_TIMEOUT: u8 = 2;
+// 中继等待客户端发送房间号的时间
+const RELAY_HELLO_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(clap::Args)]
+pub struct SendOpts {
+    #[arg(value_name = "FILE", help = "要发送的文件")]
+    file: PathBuf,
+
+    #[arg(
+        long,
+        value_name = "CODE",
+        help = "自定义口令，至少 12 个字符，只能包含字母、数字和 -；默认随机生成"
+    )]
+    code: Option<String>,
+
+    #[arg(short = 'p', long, default_value_t = 0, help = "监听端口，默认随机")]
+    port: u16,
+
+    #[arg(
+        short = 'b',
+        long,
+        default_value = "0.0.0.0",
+        value_name = "ADDR",
+        help = "监听地址"
+    )]
+    bind: IpAddr,
+
+    #[arg(
+        long,
+        value_name = "HOST[:PORT]",
+        help = "通过中继转发，适用于不在同一局域网或组播不通的情况，端口默认 9009"
+    )]
+    relay: Option<String>,
+
+    #[arg(long, help = "不加密文件数据，传输完成后仍校验 SHA-256 摘要")]
+    no_encrypt: bool,
+
+    #[arg(
+        long,
+        default_value_t = 10,
+        value_name = "SECS",
+        help = "连接和握手超时时间（秒）"
+    )]
+    timeout: u64,
+}
+
+#[derive(clap::Args)]
+pub struct RecvOpts {
+    #[arg(value_name = "CODE", help = "发送方显示的口令")]
+    code: String,
+
+    #[arg(
+        long,
+        value_name = "HOST:PORT",
+        conflicts_with = "relay",
+        help = "直接连接发送方，跳过 mDNS 发现"
+    )]
+    peer: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "HOST[:PORT]",
+        help = "通过中继连接，需和发送方使用同一个中继，端口默认 9009"
+    )]
+    relay: Option<String>,
+
+    #[arg(
+        short = 'o',
+        long,
+        default_value = ".",
+        value_name = "DIR",
+        help = "保存目录"
+    )]
+    out_dir: PathBuf,
+
+    #[arg(long, help = "覆盖已存在的同名文件")]
+    force: bool,
+
+    #[arg(
+        long,
+        default_value_t = 10,
+        value_name = "SECS",
+        help = "发现、连接和握手超时时间（秒）"
+    )]
+    timeout: u64,
+}
+
+#[derive(clap::Args)]
+pub struct RelayOpts {
+    #[arg(
+        short = 'l',
+        long,
+        default_value = "0.0.0.0:9009",
+        value_name = "ADDR:PORT",
+        help = "监听地址"
+    )]
+    listen: SocketAddr,
+
+    #[arg(
+        long,
+        default_value_t = 300,
+        value_name = "SECS",
+        help = "一端等待另一端到达的最长时间（秒）"
+    )]
+    wait: u64,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum TransferError {
+    #[error("{0} is not a regular file")]
+    NotAFile(PathBuf),
+    #[error("code must be at least {MIN_CODE_LEN} characters of letters, digits and '-'")]
+    InvalidCode,
+    #[error("failed to open {path}: {source}")]
+    Open { path: PathBuf, source: io::Error },
+    #[error("sender offered an unsafe file name '{0}'")]
+    UnsafeName(String),
+    #[error("{0} already exists, pass --force to overwrite")]
+    Exists(PathBuf),
+    #[error("handshake failed, check that the code is correct")]
+    WrongCode,
+    #[error(transparent)]
+    Pipe(PipeError),
+    #[error("relay: {0}")]
+    Relay(&'static str),
+    #[error("no sender found for this code, check the code or pass --peer / --relay")]
+    NotFound,
+    #[error("invalid address: {0}")]
+    InvalidAddress(String),
+    #[error("failed to bind {addr}: {source}")]
+    Bind { addr: String, source: io::Error },
+    #[error("receiver declined the file: {0}")]
+    Declined(String),
+    #[error("receiver reported a failure: {0}")]
+    Rejected(String),
+    #[error("protocol error: {0}")]
+    Protocol(&'static str),
+    #[error("connection closed before the transfer finished")]
+    Truncated,
+    #[error("checksum mismatch, the partial file was removed")]
+    Checksum,
+    #[error(transparent)]
+    Network(#[from] NetworkError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+}
+
+impl From<PipeError> for TransferError {
+    fn from(error: PipeError) -> Self {
+        match error {
+            PipeError::AuthFailed => TransferError::WrongCode,
+            PipeError::BadHandshake => {
+                TransferError::Protocol("peer is not an rtoolkit send / recv")
+            }
+            error => TransferError::Pipe(error),
+        }
+    }
+}
+
+// 口令的第一组同时作为 mDNS 实例名和中继房间号，只暴露 4 个字符，其余部分只参与密钥派生
+#[derive(Debug, PartialEq, Eq)]
+struct Code {
+    secret: String,
+    id: String,
+}
+
+impl Code {
+    fn generate() -> Self {
+        let mut rng = rand::rng();
+        let secret = (0..CODE_GROUPS)
+            .map(|_| {
+                (0..GROUP_LEN)
+                    .map(|_| CODE_CHARSET[rng.random_range(0..CODE_CHARSET.len())] as char)
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("-");
+        Self::parse(&secret).expect("generated code is valid")
+    }
+
+    // 口令不区分大小写，方便口头转述
+    fn parse(code: &str) -> Result<Self, TransferError> {
+        let secret = code.trim().to_ascii_lowercase();
+        let id: String = secret
+            .chars()
+            .filter(|c| *c != '-')
+            .take(GROUP_LEN)
+            .collect();
+        let valid = secret.len() >= MIN_CODE_LEN
+            && id.len() == GROUP_LEN
+            && secret
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b == b'-');
+        if !valid {
+            return Err(TransferError::InvalidCode);
+        }
+        Ok(Self { secret, id })
+    }
+
+    fn room(&self) -> [u8; 32] {
+        Sha256::new()
+            .chain_update(b"rtoolkit-relay")
+            .chain_update(self.id.as_bytes())
+            .finalize()
+            .into()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Offer {
+    name: String,
+    size: u64,
+    // 文件名、大小和修改时间的摘要，接收方据此找到可以续传的临时文件
+    id: String,
+    encrypted: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Answer {
+    offset: u64,
+    accept: bool,
+    reason: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Done {
+    ok: bool,
+    reason: Option<String>,
+}
+
+pub fn run_send(opts: SendOpts) -> Result<(), TransferError> {
+    let open_error = |source| TransferError::Open {
+        path: opts.file.clone(),
+        source,
+    };
+    let mut file = File::open(&opts.file).map_err(open_error)?;
+    let meta = file.metadata().map_err(open_error)?;
+    if !meta.is_file() {
+        return Err(TransferError::NotAFile(opts.file.clone()));
+    }
+    let name = opts
+        .file
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .ok_or_else(|| TransferError::NotAFile(opts.file.clone()))?;
+    let offer = Offer {
+        id: file_id(&name, &meta),
+        name,
+        size: meta.len(),
+        encrypted: !opts.no_encrypt,
+    };
+    let code = match &opts.code {
+        Some(code) => Code::parse(code)?,
+        None => Code::generate(),
+    };
+    let timeout = Duration::from_secs(opts.timeout.max(1));
+    // 每次发送随机生成盐，抓包得到的握手无法复用预计算结果
+    let mut salt = [0u8; SALT_LEN];
+    rand::rng().fill_bytes(&mut salt);
+    let psk = pipe::derive_psk(&code.secret, &salt);
+
+    eprintln!("Sending '{}' ({})", offer.name, human_bytes(offer.size));
+    if opts.no_encrypt {
+        eprintln!("warning: --no-encrypt is set, file data is sent in plain text");
+    }
+    let (stream, session) = match &opts.relay {
+        Some(relay) => {
+            let relay = with_default_port(relay, DEFAULT_RELAY_PORT);
+            eprintln!("Code: {}", code.secret);
+            eprintln!("On the other machine run:");
+            eprintln!("  rtoolkit recv {} --relay {}", code.secret, relay);
+            let mut stream = relay_connect(&relay, &code, ROLE_SEND, timeout)?;
+            stream.set_read_timeout(Some(timeout))?;
+            let session = pipe::server_handshake(&mut stream, &psk, &salt, true)?;
+            eprintln!("Connected through relay {}", relay);
+            (stream, session)
+        }
+        None => {
+            network::check_addr("send", opts.bind)?;
+            let addr = SocketAddr::new(opts.bind, opts.port);
+            let listener = TcpListener::bind(addr).map_err(|source| TransferError::Bind {
+                addr: addr.to_string(),
+                source,
+            })?;
+            let port = listener.local_addr()?.port();
+            advertise(&code, opts.bind, port);
+            eprintln!("Code: {}", code.secret);
+            eprintln!("On the other machine run:");
+            eprintln!("  rtoolkit recv {}", code.secret);
+            eprintln!(
+                "If discovery fails, add --peer {}:{}",
+                display_addr(opts.bind),
+                port
+            );
+            // 握手失败的连接直接丢弃，继续等待，避免被端口扫描或输错口令打断
+            loop {
+                let (mut stream, peer) = listener.accept()?;
+                stream.set_read_timeout(Some(timeout))?;
+                match pipe::server_handshake(&mut stream, &psk, &salt, true) {
+                    Ok(session) => {
+                        eprintln!("Connected from {}", peer);
+                        break (stream, session);
+                    }
+                    Err(error) => eprintln!("Rejected {}: {}", peer, TransferError::from(error)),
+                }
+            }
+        }
+    };
+
+    // 握手完成后对方可能要先计算已有部分的摘要，取消读超时
+    stream.set_read_timeout(None)?;
+    let mut channel = Channel::new(stream, &session, ROLE_SEND);
+    channel.send(&offer)?;
+    let answer: Answer = channel.recv()?;
+    if !answer.accept {
+        return Err(TransferError::Declined(
+            answer
+                .reason
+                .unwrap_or_else(|| "no reason given".to_string()),
+        ));
+    }
+    if answer.offset > offer.size {
+        return Err(TransferError::Protocol(
+            "resume offset is beyond the end of the file",
+        ));
+    }
+    if answer.offset > 0 {
+        eprintln!("Resuming from {}", human_bytes(answer.offset));
+    }
+
+    let started = Instant::now();
+    let mut hasher = Sha256::new();
+    // 续传时先计算已传部分的摘要，最终校验覆盖整个文件
+    io::copy(&mut (&mut file).take(answer.offset), &mut hasher)?;
+    file.seek(SeekFrom::Start(answer.offset))?;
+    let progress = Arc::new(Progress::default());
+    progress.set_total(offer.size);
+    progress.advance(answer.offset);
+    let display = ProgressDisplay::bytes(Arc::clone(&progress), "Sent");
+    let mut input = (&mut file).take(offer.size - answer.offset);
+    let mut buf = vec![0u8; CHUNK_SIZE];
+    loop {
+        let n = match input.read(&mut buf) {
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        };
+        // 空帧表示数据结束
+        channel.write_frame(&buf[..n], offer.encrypted)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        progress.advance(n as u64);
+    }
+    display.finish();
+    let digest = hasher.finalize();
+    channel.write_frame(&digest, true)?;
+
+    let done: Done = channel.recv()?;
+    if !done.ok {
+        return Err(TransferError::Rejected(
+            done.reason.unwrap_or_else(|| "no reason given".to_string()),
+        ));
+    }
+    eprintln!(
+        "Sent '{}' ({}) in {}",
+        offer.name,
+        human_bytes(offer.size),
+        rate(offer.size - answer.offset, started.elapsed())
+    );
+    Ok(())
+}
+
+pub fn run_recv(opts: RecvOpts) -> Result<(), TransferError> {
+    let code = Code::parse(&opts.code)?;
+    let timeout = Duration::from_secs(opts.timeout.max(1));
+    let mut stream = match (&opts.peer, &opts.relay) {
+        (Some(peer), _) => {
+            network::check_endpoint("recv", peer)?;
+            let addr = resolve(peer)?;
+            TcpStream::connect_timeout(&addr, timeout)?
+        }
+        (None, Some(relay)) => {
+            let relay = with_default_port(relay, DEFAULT_RELAY_PORT);
+            eprintln!("Waiting for the sender on relay {}", relay);
+            relay_connect(&relay, &code, ROLE_RECV, timeout)?
+        }
+        (None, None) => discover(&code, timeout)?,
+    };
+    stream.set_read_timeout(Some(timeout))?;
+    let session = pipe::client_handshake(&mut stream, &code.secret, true)?;
+    stream.set_read_timeout(None)?;
+    let mut channel = Channel::new(stream, &session, ROLE_RECV);
+
+    let offer: Offer = channel.recv()?;
+    let decline = |channel: &mut Channel, reason: &str| {
+        channel.send(&Answer {
+            offset: 0,
+            accept: false,
+            reason: Some(reason.to_string()),
+        })
+    };
+    let Some(name) = safe_name(&offer.name) else {
+        decline(&mut channel, "unsafe file name")?;
+        return Err(TransferError::UnsafeName(offer.name));
+    };
+    let dest = opts.out_dir.join(name);
+    if dest.exists() && !opts.force {
+        decline(&mut channel, "file already exists on the receiver")?;
+        return Err(TransferError::Exists(dest));
+    }
+    if !offer.encrypted {
+        eprintln!("warning: the sender disabled encryption, file data is sent in plain text");
+    }
+    // 临时文件名带上文件摘要，只有同一个文件才会续传
+    let part = opts.out_dir.join(format!(
+        "{}.{}.part",
+        name,
+        safe_name(&offer.id).unwrap_or("0")
+    ));
+    let mut file = OpenOptions::new()
+        .create(true)
+        .read(true)
+        .append(true)
+        .open(&part)
+        .map_err(|source| TransferError::Open {
+            path: part.clone(),
+            source,
+        })?;
+    let mut offset = file.metadata()?.len();
+    if offset > offer.size {
+        file.set_len(0)?;
+        offset = 0;
+    }
+    let mut hasher = Sha256::new();
+    io::copy(&mut (&mut file).take(offset), &mut hasher)?;
+    channel.send(&Answer {
+        offset,
+        accept: true,
+        reason: None,
+    })?;
+    eprintln!(
+        "Receiving '{}' ({}){}",
+        name,
+        human_bytes(offer.size),
+        if offset > 0 {
+            format!(", resuming from {}", human_bytes(offset))
+        } else {
+            String::new()
+        }
+    );
+
+    let started = Instant::now();
+    let progress = Arc::new(Progress::default());
+    progress.set_total(offer.size);
+    progress.advance(offset);
+    let display = ProgressDisplay::bytes(Arc::clone(&progress), "Received");
+    let mut received = offset;
+    let result = (|| loop {
+        let data = channel.read_frame(offer.encrypted)?;
+        if data.is_empty() {
+            return Ok(());
+        }
+        received += data.len() as u64;
+        if received > offer.size {
+            return Err(TransferError::Protocol(
+                "sender sent more data than offered",
+            ));
+        }
+        file.write_all(&data)?;
+        hasher.update(&data);
+        progress.advance(data.len() as u64);
+    })();
+    display.finish();
+    if let Err(error) = result {
+        eprintln!(
+            "Partial file kept at {}, send the same file again to resume",
+            part.display()
+        );
+        return Err(error);
+    }
+    file.sync_all()?;
+    drop(file);
+
+    let digest = channel.read_frame(true)?;
+    if received != offer.size || digest.as_slice() != hasher.finalize().as_slice() {
+        fs::remove_file(&part)?;
+        channel.send(&Done {
+            ok: false,
+            reason: Some("checksum mismatch".to_string()),
+        })?;
+        return Err(TransferError::Checksum);
+    }
+    fs::rename(&part, &dest)?;
+    channel.send(&Done {
+        ok: true,
+        reason: None,
+    })?;
+    eprintln!(
+        "Received '{}' ({}) in {}",
+        dest.display(),
+        human_bytes(offer.size),
+        rate(offer.size - offset, started.elapsed())
+    );
+    Ok(())
+}
+
+pub fn run_relay(opts: RelayOpts) -> Result<(), TransferError> {
+    network::check_addr("relay", opts.listen.ip())?;
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(relay(opts))
+}
+
+// 在应用层加密的连接上传输的帧：4 字节大端长度 + 负载；加密帧的 nonce 由方向和序号组成，
+// 两个方向各自计数，重放、重排或截断的帧都无法通过校验
+struct Channel {
+    stream: TcpStream,
+    cipher: ChaCha20Poly1305,
+    role: u8,
+    sent: u64,
+    received: u64,
+}
+
+impl Channel {
+    fn new(stream: TcpStream, session: &[u8; 32], role: u8) -> Self {
+        Self {
+            stream,
+            cipher: ChaCha20Poly1305::new(Key::from_slice(session)),
+            role,
+            sent: 0,
+            received: 0,
+        }
+    }
+
+    fn nonce(direction: u8, seq: u64) -> Nonce {
+        let mut nonce = [0u8; 12];
+        nonce[0] = direction;
+        nonce[4..].copy_from_slice(&seq.to_be_bytes());
+        *Nonce::from_slice(&nonce)
+    }
+
+    fn write_frame(&mut self, payload: &[u8], seal: bool) -> Result<(), TransferError> {
+        let sealed;
+        let payload = if seal {
+            sealed = self
+                .cipher
+                .encrypt(&Self::nonce(self.role, self.sent), payload)
+                .expect("encryption of in-memory buffer cannot fail");
+            &sealed
+        } else {
+            payload
+        };
+        let mut frame = Vec::with_capacity(4 + payload.len());
+        frame.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+        frame.extend_from_slice(payload);
+        self.stream.write_all(&frame)?;
+        self.sent += 1;
+        Ok(())
+    }
+
+    fn read_frame(&mut self, seal: bool) -> Result<Vec<u8>, TransferError> {
+        let mut len = [0u8; 4];
+        self.stream
+            .read_exact(&mut len)
+            .map_err(|_| TransferError::Truncated)?;
+        let len = u32::from_be_bytes(len) as usize;
+        if len > MAX_FRAME {
+            return Err(TransferError::Protocol("frame too large"));
+        }
+        let mut frame = vec![0u8; len];
+        self.stream
+            .read_exact(&mut frame)
+            .map_err(|_| TransferError::Truncated)?;
+        let frame = if seal {
+            self.cipher
+                .decrypt(&Self::nonce(self.role ^ 1, self.received), frame.as_slice())
+                .map_err(|_| TransferError::Protocol("frame failed integrity check"))?
+        } else {
+            frame
+        };
+        self.received += 1;
+        Ok(frame)
+    }
+
+    fn send<T: Serialize>(&mut self, message: &T) -> Result<(), TransferError> {
+        self.write_frame(&serde_json::to_vec(message)?, true)
+    }
+
+    fn recv<T: DeserializeOwned>(&mut self) -> Result<T, TransferError> {
+        Ok(serde_json::from_slice(&self.read_frame(true)?)?)
+    }
+}
+
+fn rate(bytes: u64, elapsed: Duration) -> String {
+    let secs = elapsed.as_secs_f64().max(0.001);
+    format!(
+        "{:.1}s, {}/s",
+        secs,
+        human_bytes((bytes as f64 / secs) as u64)
+    )
+}
+
+fn file_id(name: &str, meta: &fs::Metadata) -> String {
+    let mtime = meta
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |time| time.as_nanos());
+    Sha256::new()
+        .chain_update(name.as_bytes())
+        .chain_update(meta.len().to_be_bytes())
+        .chain_update(mtime.to_be_bytes())
+        .finalize()[..6]
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+// 只接受单个文件名，拒绝路径分隔符、.. 和控制字符，防止写到保存目录之外
+fn safe_name(name: &str) -> Option<&str> {
+    let valid = !name.is_empty()
+        && name != "."
+        && name != ".."
+        && !name
+            .chars()
+            .any(|c| c == '/' || c == '\\' || c.is_control())
+        && Path::new(name).file_name().is_some_and(|file| file == name);
+    valid.then_some(name)
+}
+
+fn with_default_port(target: &str, port: u16) -> String {
+    if target.contains(':') && !target.ends_with(']') {
+        target.to_string()
+    } else {
+        format!("{}:{}", target, port)
+    }
+}
+
+fn resolve(target: &str) -> Result<SocketAddr, TransferError> {
+    target
+        .to_socket_addrs()
+        .map_err(|_| TransferError::InvalidAddress(target.to_string()))?
+        .next()
+        .ok_or_else(|| TransferError::InvalidAddress(target.to_string()))
+}
+
+fn display_addr(bind: IpAddr) -> String {
+    match bind {
+        IpAddr::V4(addr) if addr.is_unspecified() => mdns::local_ipv4()
+            .first()
+            .map_or_else(|| "<sender-ip>".to_string(), |addr| addr.to_string()),
+        IpAddr::V6(addr) if addr.is_unspecified() => "<sender-ip>".to_string(),
+        IpAddr::V6(addr) => format!("[{}]", addr),
+        addr => addr.to_string(),
+    }
+}
+
+// 在后台线程中通过 mDNS 发布监听端口；失败时只提示，接收方仍可用 --peer 连接
+fn advertise(code: &Code, bind: IpAddr, port: u16) {
+    let addrs = match bind {
+        IpAddr::V4(addr) if addr.is_loopback() => return,
+        IpAddr::V4(addr) if !addr.is_unspecified() => vec![addr],
+        IpAddr::V4(_) => mdns::local_ipv4(),
+        IpAddr::V6(_) => Vec::new(),
+    };
+    let Some(name) = mdns::service_name(&code.id, SERVICE) else {
+        return;
+    };
+    match mdns::Responder::bind() {
+        Ok(responder) => {
+            thread::spawn(move || responder.serve(name, port, addrs));
+        }
+        Err(error) => eprintln!(
+            "warning: mDNS is unavailable ({}), the receiver needs --peer",
+            error
+        ),
+    }
+}
+
+// 通过 mDNS 查找发送方，按应答中的地址依次尝试连接
+fn discover(code: &Code, timeout: Duration) -> Result<TcpStream, TransferError> {
+    network::require("recv discovery", "the local network (mDNS)")?;
+    let name = mdns::service_name(&code.id, SERVICE).ok_or(TransferError::InvalidCode)?;
+    eprintln!("Looking for the sender on the local network...");
+    let candidates = mdns::query(&name, timeout)?;
+    let mut last_error = None;
+    for addr in candidates {
+        match TcpStream::connect_timeout(&addr, timeout) {
+            Ok(stream) => {
+                eprintln!("Found sender at {}", addr);
+                return Ok(stream);
+            }
+            Err(error) => last_error = Some(error),
+        }
+    }
+    Err(last_error.map_or(TransferError::NotFound, TransferError::Io))
+}
+
+// 中继协议：客户端发送 MAGIC + 房间号 + 角色，中继在另一端到达后回复一个状态字节，
+// 之后原样转发两端的数据；中继看不到口令，也无法解密
+fn relay_connect(
+    relay: &str,
+    code: &Code,
+    role: u8,
+    timeout: Duration,
+) -> Result<TcpStream, TransferError> {
+    network::check_endpoint("relay", relay)?;
+    let addr = resolve(relay)?;
+    let mut stream = TcpStream::connect_timeout(&addr, timeout)?;
+    stream.write_all(RELAY_MAGIC)?;
+    stream.write_all(&code.room())?;
+    stream.write_all(&[role])?;
+    // 等待时间由中继的 --wait 控制
+    let mut status = [0u8; 1];
+    stream
+        .read_exact(&mut status)
+        .map_err(|_| TransferError::Relay("connection closed while waiting for the peer"))?;
+    match status[0] {
+        RELAY_PAIRED => Ok(stream),
+        RELAY_BUSY => Err(TransferError::Relay(
+            "another transfer is already waiting with this code",
+        )),
+        RELAY_TIMEOUT => Err(TransferError::Relay("the peer did not connect in time")),
+        _ => Err(TransferError::Relay("unexpected reply")),
+    }
+}
+
+struct Waiting {
+    role: u8,
+    token: u64,
+    peer: oneshot::Sender<(tokio::net::TcpStream, SocketAddr)>,
+}
+
+type Rooms = Arc<Mutex<HashMap<[u8; 32], Waiting>>>;
+
+async fn relay(opts: RelayOpts) -> Result<(), TransferError> {
+    let listener = tokio::net::TcpListener::bind(opts.listen)
+        .await
+        .map_err(|source| TransferError::Bind {
+            addr: opts.listen.to_string(),
+            source,
+        })?;
+    eprintln!("Relay listening on {}", listener.local_addr()?);
+    let rooms: Rooms = Arc::default();
+    let tokens = Arc::new(AtomicU64::new(0));
+    let wait = Duration::from_secs(opts.wait.max(1));
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        let token = tokens.fetch_add(1, Ordering::Relaxed);
+        tokio::spawn(relay_client(stream, peer, rooms.clone(), token, wait));
+    }
+}
+
+fn relay_log(event: std::fmt::Arguments) {
+    eprintln!(
+        "{}  {}",
+        Local::now().format("%Y-%m-%d %H:%M:%S%.3f"),
+        event
+    );
+}
+
+async fn relay_client(
+    mut stream: tokio::net::TcpStream,
+    peer: SocketAddr,
+    rooms: Rooms,
+    token: u64,
+    wait: Duration,
+) {
+    let mut hello = [0u8; 8 + 32 + 1];
+    let read = tokio::time::timeout(RELAY_HELLO_TIMEOUT, stream.read_exact(&mut hello)).await;
+    if !matches!(read, Ok(Ok(_))) || &hello[..8] != RELAY_MAGIC || hello[40] > ROLE_RECV {
+        return;
+    }
+    let room: [u8; 32] = hello[8..40].try_into().expect("slice has 32 bytes");
+    let role = hello[40];
+    let label = room[..4]
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<String>();
+
+    let (tx, rx) = oneshot::channel();
+    let busy = {
+        let mut rooms = rooms.lock().expect("relay rooms lock poisoned");
+        let waiting = Waiting {
+            role,
+            token,
+            peer: tx,
+        };
+        match rooms.remove(&room) {
+            Some(other) if other.role != role => match other.peer.send((stream, peer)) {
+                Ok(()) => return,
+                // 等待方刚好超时或断开，改为由自己等待
+                Err((returned, _)) => {
+                    stream = returned;
+                    rooms.insert(room, waiting);
+                    false
+                }
+            },
+            Some(other) => {
+                rooms.insert(room, other);
+                true
+            }
+            None => {
+                rooms.insert(room, waiting);
+                false
+            }
+        }
+    };
+    if busy {
+        relay_log(format_args!("{} busy room {}", peer, label));
+        let _ = stream.write_all(&[RELAY_BUSY]).await;
+        return;
+    }
+
+    // 配对前客户端不会发送数据，可读说明对方已断开
+    let mut probe = [0u8; 1];
+    let paired = tokio::select! {
+        result = tokio::time::timeout(wait, rx) => result.ok().and_then(Result::ok),
+        _ = stream.read(&mut probe) => None,
+    };
+    let Some((mut other, other_peer)) = paired else {
+        {
+            let mut rooms = rooms.lock().expect("relay rooms lock poisoned");
+            if rooms
+                .get(&room)
+                .is_some_and(|waiting| waiting.token == token)
+            {
+                rooms.remove(&room);
+            }
+        }
+        let _ = stream.write_all(&[RELAY_TIMEOUT]).await;
+        return;
+    };
+
+    if stream.write_all(&[RELAY_PAIRED]).await.is_err()
+        || other.write_all(&[RELAY_PAIRED]).await.is_err()
+    {
+        return;
+    }
+    relay_log(format_args!(
+        "{} <-> {} paired in room {}",
+        peer, other_peer, label
+    ));
+    match tokio::io::copy_bidirectional(&mut stream, &mut other).await {
+        Ok((up, down)) => relay_log(format_args!(
+            "{} <-> {} closed, relayed {}",
+            peer,
+            other_peer,
+            human_bytes(up + down)
+        )),
+        Err(error) => relay_log(format_args!(
+            "{} <-> {} failed: {}",
+            peer, other_peer, error
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn test_codes_and_names() {
+        let code = Code::generate();
+        assert_eq!(code.secret.len(), 19);
+        assert_eq!(code.secret.split('-').count(), CODE_GROUPS);
+        assert_eq!(code.id, code.secret[..GROUP_LEN]);
+        assert_eq!(Code::parse(&code.secret.to_uppercase()).unwrap(), code);
+        assert_eq!(
+            Code::parse("my-long-secret").unwrap().id,
+            "mylo".to_string()
+        );
+        assert!(matches!(
+            Code::parse("short"),
+            Err(TransferError::InvalidCode)
+        ));
+        assert!(matches!(
+            Code::parse("has spaces in it"),
+            Err(TransferError::InvalidCode)
+        ));
+
+        assert_eq!(safe_name("report.pdf"), Some("report.pdf"));
+        assert_eq!(safe_name("../etc/passwd"), None);
+        assert_eq!(safe_name("a\\b"), None);
+        assert_eq!(safe_name(".."), None);
+        assert_eq!(safe_name(""), None);
+    }
+
+    #[test]
+    fn test_channel_round_trip() {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (server, _) = listener.accept().unwrap();
+        let session = [7u8; 32];
+        let mut sender = Channel::new(server, &session, ROLE_SEND);
+        let mut receiver = Channel::new(client, &session, ROLE_RECV);
+
+        sender
+            .send(&Offer {
+                name: "a.bin".to_string(),
+                size: 3,
+                id: "abc".to_string(),
+                encrypted: false,
+            })
+            .unwrap();
+        sender.write_frame(b"xyz", false).unwrap();
+        sender.write_frame(b"", true).unwrap();
+        let offer: Offer = receiver.recv().unwrap();
+        assert_eq!((offer.name.as_str(), offer.size), ("a.bin", 3));
+        assert_eq!(receiver.read_frame(false).unwrap(), b"xyz");
+        assert!(receiver.read_frame(true).unwrap().is_empty());
+
+        // 两个方向的 nonce 不同，把对方发来的帧原样反射回去无法通过校验
+        receiver.write_frame(b"ok", true).unwrap();
+        assert_eq!(sender.read_frame(true).unwrap(), b"ok");
+        let reflected = receiver
+            .cipher
+            .encrypt(&Channel::nonce(ROLE_SEND, 1), b"ok".as_slice())
+            .unwrap();
+        receiver.write_frame(&reflected, false).unwrap();
+        assert!(matches!(
+            sender.read_frame(true),
+            Err(TransferError::Protocol(_))
+        ));
+    }
+}
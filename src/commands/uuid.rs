@@ -0,0 +1,416 @@
+use std::fmt;
+use std::io::{self, BufRead};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use chrono::{DateTime, Local, SecondsFormat, Utc};
+use clap::ValueEnum;
+use rand::{Rng, RngCore};
+use serde::Serialize;
+
+use crate::utils::output::RecordOutput;
+
+// v1 / v6 的时间戳从 1582-10-15 起，以 100ns 为单位；这是到 Unix 纪元的偏移
+const GREGORIAN_OFFSET: u64 = 0x01B2_1DD2_1381_4000;
+
+#[derive(clap::Args)]
+pub struct UuidOpts {
+    #[arg(
+        value_enum,
+        short = 't',
+        long = "type",
+        default_value_t = UuidVersion::V4,
+        help = "生成的版本：v4 随机 | v7 按时间排序（同一毫秒内单调递增）"
+    )]
+    version: UuidVersion,
+
+    #[arg(short = 'n', long, default_value_t = 1, help = "生成数量")]
+    count: u32,
+
+    #[arg(
+        value_enum,
+        short = 'f',
+        long,
+        default_value_t = UuidFormat::Hyphenated,
+        help = "输出格式 hyphenated | simple | braced | urn"
+    )]
+    format: UuidFormat,
+
+    #[arg(short = 'u', long, help = "输出大写十六进制")]
+    upper: bool,
+
+    #[arg(
+        short = 'd',
+        long,
+        help = "解析给出的 UUID：版本、变体，以及 v1 / v6 / v7 中的时间戳"
+    )]
+    decode: bool,
+
+    #[arg(
+        value_name = "UUID",
+        requires = "decode",
+        help = "--decode 时要解析的 UUID，省略或为 - 时从 stdin 逐行读取"
+    )]
+    uuids: Vec<String>,
+
+    #[arg(long, requires = "decode", help = "以 JSON 输出解析结果")]
+    json: bool,
+
+    // 全局 --where / --fields / --rename，由命令行入口注入
+    #[arg(skip)]
+    pub(crate) record_output: RecordOutput,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum UuidVersion {
+    V4,
+    V7,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum UuidFormat {
+    // 6ba7b810-9dad-11d1-80b4-00c04fd430c8
+    Hyphenated,
+    // 6ba7b8109dad11d180b400c04fd430c8
+    Simple,
+    // {6ba7b810-9dad-11d1-80b4-00c04fd430c8}
+    Braced,
+    // urn:uuid:6ba7b810-9dad-11d1-80b4-00c04fd430c8
+    Urn,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum UuidError {
+    #[error("invalid UUID '{0}'")]
+    InvalidUuid(String),
+    #[error("no UUIDs to decode")]
+    NoInput,
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("json serialize failed: {0}")]
+    Serialize(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Uuid(pub [u8; 16]);
+
+impl Uuid {
+    // 接受带或不带连字符、花括号和 urn:uuid: 前缀的写法，大小写均可
+    pub fn parse(raw: &str) -> Option<Self> {
+        let raw = raw.trim();
+        let raw = raw
+            .get(..9)
+            .filter(|prefix| prefix.eq_ignore_ascii_case("urn:uuid:"))
+            .map_or(raw, |_| &raw[9..]);
+        let raw = raw
+            .strip_prefix('{')
+            .and_then(|rest| rest.strip_suffix('}'))
+            .unwrap_or(raw);
+        let hex = if raw.len() == 36 {
+            let dashes = [8, 13, 18, 23];
+            if !dashes.iter().all(|&index| raw.as_bytes()[index] == b'-') {
+                return None;
+            }
+            raw.replace('-', "")
+        } else {
+            raw.to_string()
+        };
+        if hex.len() != 32 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        Some(Self(u128::from_str_radix(&hex, 16).ok()?.to_be_bytes()))
+    }
+
+    pub fn new_v4() -> Self {
+        let mut bytes = [0u8; 16];
+        rand::rng().fill_bytes(&mut bytes);
+        Self::with_version(bytes, 4)
+    }
+
+    // 写入版本号和 RFC 9562 变体位
+    fn with_version(mut bytes: [u8; 16], version: u8) -> Self {
+        bytes[6] = (bytes[6] & 0x0f) | (version << 4);
+        bytes[8] = (bytes[8] & 0x3f) | 0x80;
+        Self(bytes)
+    }
+
+    fn is_nil(&self) -> bool {
+        self.0 == [0; 16]
+    }
+
+    fn is_max(&self) -> bool {
+        self.0 == [0xff; 16]
+    }
+
+    fn variant(&self) -> &'static str {
+        match self.0[8] {
+            byte if byte & 0x80 == 0 => "ncs",
+            byte if byte & 0xc0 == 0x80 => "rfc9562",
+            byte if byte & 0xe0 == 0xc0 => "microsoft",
+            _ => "future",
+        }
+    }
+
+    // 只有 RFC 9562 变体的版本号有意义
+    fn version(&self) -> Option<u8> {
+        (self.variant() == "rfc9562").then_some(self.0[6] >> 4)
+    }
+
+    // v1 / v6 精确到 100ns，v7 精确到毫秒
+    fn timestamp(&self) -> Option<DateTime<Utc>> {
+        let b = &self.0;
+        let ticks = match self.version()? {
+            1 => {
+                (u64::from(b[6] & 0x0f) << 56)
+                    | (u64::from(b[7]) << 48)
+                    | (u64::from(u16::from_be_bytes([b[4], b[5]])) << 32)
+                    | u64::from(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+            }
+            6 => {
+                (u64::from_be_bytes([0, 0, b[0], b[1], b[2], b[3], b[4], b[5]]) << 12)
+                    | (u64::from(b[6] & 0x0f) << 8)
+                    | u64::from(b[7])
+            }
+            7 => {
+                let ms = u64::from_be_bytes([0, 0, b[0], b[1], b[2], b[3], b[4], b[5]]);
+                return DateTime::from_timestamp_millis(ms as i64);
+            }
+            _ => return None,
+        };
+        let nanos = (i128::from(ticks) - i128::from(GREGORIAN_OFFSET)) * 100;
+        Some(DateTime::from_timestamp_nanos(i64::try_from(nanos).ok()?))
+    }
+
+    fn format(&self, format: UuidFormat, upper: bool) -> String {
+        let hex = if upper {
+            format!("{:032X}", u128::from_be_bytes(self.0))
+        } else {
+            format!("{:032x}", u128::from_be_bytes(self.0))
+        };
+        let hyphenated = format!(
+            "{}-{}-{}-{}-{}",
+            &hex[..8],
+            &hex[8..12],
+            &hex[12..16],
+            &hex[16..20],
+            &hex[20..]
+        );
+        match format {
+            UuidFormat::Hyphenated => hyphenated,
+            UuidFormat::Simple => hex,
+            UuidFormat::Braced => format!("{{{}}}", hyphenated),
+            UuidFormat::Urn => format!("urn:uuid:{}", hyphenated),
+        }
+    }
+}
+
+impl fmt::Display for Uuid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.format(UuidFormat::Hyphenated, false))
+    }
+}
+
+// v7 生成器：同一毫秒内把 74 位随机部分当作计数器加一，保证批量生成的结果严格递增
+#[derive(Default)]
+struct V7Generator {
+    ms: u64,
+    counter: u128,
+}
+
+impl V7Generator {
+    fn next(&mut self, now_ms: u64) -> Uuid {
+        if now_ms > self.ms {
+            self.ms = now_ms;
+            self.counter = Self::seed();
+        } else {
+            self.counter += 1;
+            // 计数器用完时借用下一毫秒，时钟回拨时同样沿用上次的时间
+            if self.counter >> 74 != 0 {
+                self.ms += 1;
+                self.counter = Self::seed();
+            }
+        }
+        let mut bytes = [0u8; 16];
+        bytes[..6].copy_from_slice(&self.ms.to_be_bytes()[2..]);
+        // 12 位 rand_a 放在版本号之后，62 位 rand_b 放在变体位之后
+        let rand_a = (self.counter >> 62) as u16;
+        let rand_b = self.counter as u64 & ((1 << 62) - 1);
+        bytes[6..8].copy_from_slice(&rand_a.to_be_bytes());
+        bytes[8..].copy_from_slice(&rand_b.to_be_bytes());
+        Uuid::with_version(bytes, 7)
+    }
+
+    // 最高位清零，给同一毫秒内的递增留出余量
+    fn seed() -> u128 {
+        rand::rng().random::<u128>() & ((1 << 73) - 1)
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct UuidRecord {
+    pub uuid: String,
+    pub variant: &'static str,
+    pub version: Option<u8>,
+    // RFC 3339（UTC）
+    pub timestamp: Option<String>,
+    pub unix_ms: Option<i64>,
+    // v1 / v6 的时钟序列和节点
+    pub clock_seq: Option<u16>,
+    pub node: Option<String>,
+}
+
+impl UuidRecord {
+    fn new(uuid: &Uuid) -> Self {
+        let timestamp = uuid.timestamp();
+        let (clock_seq, node) = match uuid.version() {
+            Some(1 | 6) => (
+                Some(u16::from_be_bytes([uuid.0[8] & 0x3f, uuid.0[9]])),
+                Some(
+                    uuid.0[10..]
+                        .iter()
+                        .map(|byte| format!("{:02x}", byte))
+                        .collect::<Vec<_>>()
+                        .join(":"),
+                ),
+            ),
+            _ => (None, None),
+        };
+        Self {
+            uuid: uuid.to_string(),
+            variant: uuid.variant(),
+            version: uuid.version(),
+            timestamp: timestamp.map(|ts| ts.to_rfc3339_opts(SecondsFormat::AutoSi, true)),
+            unix_ms: timestamp.map(|ts| ts.timestamp_millis()),
+            clock_seq,
+            node,
+        }
+    }
+}
+
+pub fn run_uuid(opts: UuidOpts) -> Result<(), UuidError> {
+    if opts.decode {
+        return run_decode(&opts);
+    }
+    let mut v7 = V7Generator::default();
+    for _ in 0..opts.count {
+        let uuid = match opts.version {
+            UuidVersion::V4 => Uuid::new_v4(),
+            UuidVersion::V7 => v7.next(now_ms()),
+        };
+        println!("{}", uuid.format(opts.format, opts.upper));
+    }
+    Ok(())
+}
+
+fn run_decode(opts: &UuidOpts) -> Result<(), UuidError> {
+    let mut uuids = Vec::new();
+    if !opts.uuids.is_empty() && opts.uuids.iter().all(|arg| arg != "-") {
+        for raw in &opts.uuids {
+            uuids.push(Uuid::parse(raw).ok_or_else(|| UuidError::InvalidUuid(raw.clone()))?);
+        }
+    } else {
+        for line in io::stdin().lock().lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            uuids.push(Uuid::parse(&line).ok_or_else(|| UuidError::InvalidUuid(line.clone()))?);
+        }
+    }
+    if uuids.is_empty() {
+        return Err(UuidError::NoInput);
+    }
+
+    let mut records: Vec<UuidRecord> = uuids.iter().map(UuidRecord::new).collect();
+    if opts.json {
+        opts.record_output.retain(&mut records)?;
+        opts.record_output
+            .write_json(&records, io::stdout().lock())?;
+        return Ok(());
+    }
+    for (uuid, record) in uuids.iter().zip(&records) {
+        if !opts.record_output.keep(record)? {
+            continue;
+        }
+        let mut fields = Vec::new();
+        if uuid.is_nil() {
+            fields.push("nil".to_string());
+        } else if uuid.is_max() {
+            fields.push("max".to_string());
+        } else if let Some(version) = record.version {
+            fields.push(format!("v{}", version));
+            if version == 4 {
+                fields.push("random".to_string());
+            }
+        } else {
+            fields.push(format!("{} variant", record.variant));
+        }
+        if let Some(ts) = uuid.timestamp() {
+            fields.push(format!(
+                "{} ({})",
+                record.timestamp.as_deref().unwrap_or_default(),
+                ts.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S%.3f %:z")
+            ));
+        }
+        if let (Some(clock_seq), Some(node)) = (record.clock_seq, &record.node) {
+            fields.push(format!("clock_seq {}", clock_seq));
+            fields.push(format!("node {}", node));
+        }
+        println!("{}  {}", record.uuid, fields.join("  "));
+    }
+    Ok(())
+}
+
+pub(crate) fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_decode() {
+        // RFC 9562 附录中的示例，三者都是 2022-02-22 19:22:22 UTC
+        let v1 = Uuid::parse("C232AB00-9414-11EC-B3C8-9F6BDECED846").unwrap();
+        let v6 = Uuid::parse("{1ec9414c-232a-6b00-b3c8-9f6bdeced846}").unwrap();
+        let v7 = Uuid::parse("urn:uuid:017f22e279b07cc398c4dc0c0c07398f").unwrap();
+        for uuid in [v1, v6, v7] {
+            let record = UuidRecord::new(&uuid);
+            assert_eq!(record.timestamp.as_deref(), Some("2022-02-22T19:22:22Z"));
+            assert_eq!(record.variant, "rfc9562");
+        }
+        let record = UuidRecord::new(&v1);
+        assert_eq!(record.version, Some(1));
+        assert_eq!(record.clock_seq, Some(0x33c8));
+        assert_eq!(record.node.as_deref(), Some("9f:6b:de:ce:d8:46"));
+        assert_eq!(UuidRecord::new(&v7).node, None);
+
+        assert_eq!(
+            v7.format(UuidFormat::Braced, true),
+            "{017F22E2-79B0-7CC3-98C4-DC0C0C07398F}"
+        );
+        assert_eq!(Uuid::parse(&v7.format(UuidFormat::Simple, false)), Some(v7));
+        assert_eq!(Uuid::parse("017f22e2-79b07cc3-98c4-dc0c0c07398fa"), None);
+        assert_eq!(Uuid::parse("017f22e2-79b0-7cc3-98c4-dc0c0c07398g"), None);
+        assert_eq!(Uuid::new_v4().version(), Some(4));
+    }
+
+    #[test]
+    fn test_v7_monotonic() {
+        let mut generator = V7Generator::default();
+        let first = generator.next(1_645_557_742_000);
+        let mut previous = first;
+        for _ in 0..1000 {
+            let next = generator.next(1_645_557_742_000);
+            assert!(next.0 > previous.0);
+            assert_eq!(next.version(), Some(7));
+            previous = next;
+        }
+        assert_eq!(first.timestamp(), previous.timestamp());
+        // 时钟回拨时不会生成更小的值
+        assert!(generator.next(1_645_557_741_000).0 > previous.0);
+    }
+}
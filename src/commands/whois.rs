@@ -0,0 +1,446 @@
+use std::io::{self, Read, Write};
+use std::net::{IpAddr, TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use hickory_resolver::Name;
+use serde::Serialize;
+
+use crate::utils::network::{self, NetworkError};
+use crate::utils::output::RecordOutput;
+
+const IANA_SERVER: &str = "whois.iana.org";
+const WHOIS_PORT: u16 = 43;
+// 注册局 -> 注册商之类的转介最多跟随的次数
+const MAX_HOPS: usize = 4;
+// 单个响应的上限，正常的 whois 响应只有几 KB
+const MAX_RESPONSE_BYTES: u64 = 1 << 20;
+
+// 常用顶级域直接查询注册局，省去一次 IANA 查询
+const TLD_SERVERS: [(&str, &str); 7] = [
+    ("com", "whois.verisign-grs.com"),
+    ("net", "whois.verisign-grs.com"),
+    ("org", "whois.pir.org"),
+    ("cn", "whois.cnnic.cn"),
+    // .中国、.中國
+    ("xn--fiqs8s", "whois.cnnic.cn"),
+    ("xn--fiqz9s", "whois.cnnic.cn"),
+    ("io", "whois.nic.io"),
+];
+
+#[derive(clap::Args)]
+pub struct WhoisOpts {
+    #[arg(value_name = "DOMAIN|IP", help = "要查询的域名或 IP，支持中文域名")]
+    query: String,
+
+    #[arg(
+        short = 's',
+        long,
+        value_name = "HOST",
+        help = "直接查询指定的 whois 服务器，默认按顶级域或 IANA 的指引选择"
+    )]
+    server: Option<String>,
+
+    #[arg(long = "no-follow", help = "不跟随注册局返回的注册商 whois 服务器")]
+    no_follow: bool,
+
+    #[arg(
+        short = 'p',
+        long,
+        help = "解析出注册商、注册和到期日期、状态、DNS 服务器等字段，不输出原文"
+    )]
+    parse: bool,
+
+    #[arg(long, help = "以 JSON 输出解析结果和各服务器的原文")]
+    json: bool,
+
+    #[arg(
+        long = "timeout",
+        default_value_t = 10000,
+        value_name = "MS",
+        help = "单个服务器的连接和读取超时时间(毫秒)"
+    )]
+    time_out: u64,
+
+    // 全局 --where / --fields / --rename，由命令行入口注入
+    #[arg(skip)]
+    pub(crate) record_output: RecordOutput,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum WhoisError {
+    #[error("invalid domain '{0}'")]
+    InvalidQuery(String),
+    #[error("cannot resolve whois server {0}")]
+    Resolve(String),
+    #[error("whois query to {server} failed: {source}")]
+    Query { server: String, source: io::Error },
+    #[error(transparent)]
+    Network(#[from] NetworkError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("json serialize failed: {0}")]
+    Serialize(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Serialize)]
+pub struct WhoisResponse {
+    pub server: String,
+    pub text: String,
+}
+
+// 从各服务器响应中提取的常用字段，域名和 IP 的字段各自只有一部分会出现
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct WhoisRecord {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub domain: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub registrar: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub updated: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub status: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub name_servers: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub network: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cidr: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub netname: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub organization: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub asn: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WhoisReport {
+    pub query: String,
+    pub record: WhoisRecord,
+    pub responses: Vec<WhoisResponse>,
+}
+
+pub fn run_whois(opts: WhoisOpts) -> Result<(), WhoisError> {
+    let query = normalize_query(&opts.query)?;
+    let timeout = Duration::from_millis(opts.time_out.max(1));
+    let mut server = match &opts.server {
+        Some(server) => server.clone(),
+        None => initial_server(&query).to_string(),
+    };
+    let mut responses: Vec<WhoisResponse> = Vec::new();
+    loop {
+        network::check_host("whois", &server)?;
+        let text = query_server(&server, &whois_query(&server, &query), timeout)?;
+        let referral = (!opts.no_follow)
+            .then(|| referral(&text))
+            .flatten()
+            .filter(|next| {
+                !next.eq_ignore_ascii_case(&server)
+                    && !responses
+                        .iter()
+                        .any(|response| response.server.eq_ignore_ascii_case(next))
+            });
+        responses.push(WhoisResponse {
+            server: server.clone(),
+            text,
+        });
+        match referral {
+            Some(next) if responses.len() < MAX_HOPS => server = next,
+            _ => break,
+        }
+    }
+    // IANA 只负责指引，后面还有响应时不再输出
+    if responses.len() > 1 && responses[0].server.eq_ignore_ascii_case(IANA_SERVER) {
+        responses.remove(0);
+    }
+
+    let texts: Vec<&str> = responses
+        .iter()
+        .map(|response| response.text.as_str())
+        .collect();
+    let report = WhoisReport {
+        query,
+        record: parse_record(&texts),
+        responses,
+    };
+    if !opts.record_output.keep(&report)? {
+        return Ok(());
+    }
+    if opts.json {
+        opts.record_output
+            .write_report(&report, None, io::stdout().lock())?;
+    } else if opts.parse {
+        print_record(&report.record);
+    } else {
+        for (i, response) in report.responses.iter().enumerate() {
+            if i > 0 {
+                println!();
+            }
+            println!("% {}", response.server);
+            println!("{}", response.text.trim_end());
+        }
+    }
+    Ok(())
+}
+
+// 域名转为小写的 ASCII 形式（中文域名转换为 punycode），IP 原样保留
+fn normalize_query(raw: &str) -> Result<String, WhoisError> {
+    let raw = raw.trim().trim_end_matches('.');
+    if let Ok(ip) = raw.parse::<IpAddr>() {
+        return Ok(ip.to_string());
+    }
+    let name = Name::from_utf8(raw).map_err(|_| WhoisError::InvalidQuery(raw.to_string()))?;
+    let ascii = name.to_ascii();
+    let ascii = ascii.trim_end_matches('.').to_ascii_lowercase();
+    if ascii.is_empty() || !ascii.contains('.') {
+        return Err(WhoisError::InvalidQuery(raw.to_string()));
+    }
+    Ok(ascii)
+}
+
+fn initial_server(query: &str) -> &'static str {
+    if query.parse::<IpAddr>().is_ok() {
+        return IANA_SERVER;
+    }
+    let tld = query.rsplit('.').next().unwrap_or_default();
+    TLD_SERVERS
+        .iter()
+        .find(|(known, _)| *known == tld)
+        .map_or(IANA_SERVER, |(_, server)| server)
+}
+
+// ARIN 默认会同时列出组织、联系人等多种记录，n + 只查询网段
+fn whois_query(server: &str, query: &str) -> String {
+    if server.eq_ignore_ascii_case("whois.arin.net") && query.parse::<IpAddr>().is_ok() {
+        format!("n + {}", query)
+    } else {
+        query.to_string()
+    }
+}
+
+fn query_server(server: &str, query: &str, timeout: Duration) -> Result<String, WhoisError> {
+    let addr = (server, WHOIS_PORT)
+        .to_socket_addrs()
+        .map_err(|_| WhoisError::Resolve(server.to_string()))?
+        .next()
+        .ok_or_else(|| WhoisError::Resolve(server.to_string()))?;
+    let failed = |source| WhoisError::Query {
+        server: server.to_string(),
+        source,
+    };
+    let mut stream = TcpStream::connect_timeout(&addr, timeout).map_err(failed)?;
+    stream.set_read_timeout(Some(timeout)).map_err(failed)?;
+    stream.set_write_timeout(Some(timeout)).map_err(failed)?;
+    stream
+        .write_all(format!("{}\r\n", query).as_bytes())
+        .map_err(failed)?;
+    let mut raw = Vec::new();
+    stream
+        .take(MAX_RESPONSE_BYTES)
+        .read_to_end(&mut raw)
+        .map_err(failed)?;
+    Ok(String::from_utf8_lossy(&raw).replace("\r\n", "\n"))
+}
+
+// IANA 的 refer:、注册局的 Registrar WHOIS Server: 和 ARIN 的 ReferralServer: 指向下一个服务器
+fn referral(text: &str) -> Option<String> {
+    text.lines().find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        let key = key.trim().to_ascii_lowercase();
+        if !matches!(
+            key.as_str(),
+            "refer" | "whois" | "registrar whois server" | "referralserver"
+        ) {
+            return None;
+        }
+        let value = value.trim();
+        // rwhois:// 等其他协议不支持
+        let host = match value.split_once("://") {
+            Some(("whois", rest)) => rest,
+            Some(_) => return None,
+            None => value,
+        };
+        let host = host.split([':', '/']).next().unwrap_or_default().trim();
+        (!host.is_empty() && !host.contains(' ')).then(|| host.to_ascii_lowercase())
+    })
+}
+
+// texts 按查询顺序排列；越靠后的服务器（通常是注册商）信息越详细，优先采用
+fn parse_record(texts: &[&str]) -> WhoisRecord {
+    let mut record = WhoisRecord::default();
+    for text in texts.iter().rev() {
+        let mut status = Vec::new();
+        let mut name_servers: Vec<String> = Vec::new();
+        for line in text.lines() {
+            let line = line.trim();
+            if line.starts_with(['%', '#', '>']) {
+                continue;
+            }
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            if value.is_empty() {
+                continue;
+            }
+            let field = match key.trim().to_ascii_lowercase().as_str() {
+                "domain name" | "domain" => &mut record.domain,
+                "registrar" | "sponsoring registrar" | "registrar name" => &mut record.registrar,
+                "creation date" | "registration time" | "created" | "registered" | "regdate" => {
+                    &mut record.created
+                }
+                "updated date" | "last-modified" | "last modified" | "changed" | "updated" => {
+                    &mut record.updated
+                }
+                "registry expiry date"
+                | "registrar registration expiration date"
+                | "expiration time"
+                | "expiration date"
+                | "expiry date"
+                | "expires"
+                | "paid-till" => &mut record.expires,
+                "inetnum" | "inet6num" | "netrange" => &mut record.network,
+                "cidr" | "route" | "route6" => &mut record.cidr,
+                "netname" => &mut record.netname,
+                "orgname" | "org-name" | "organization" | "owner" => &mut record.organization,
+                "country" => &mut record.country,
+                "originas" | "origin" | "aut-num" => &mut record.asn,
+                "domain status" | "status" => {
+                    // 去掉 clientTransferProhibited 之后附带的 ICANN 说明链接
+                    let value = value.split(" http").next().unwrap_or(value).trim();
+                    if !status.iter().any(|known| known == value) {
+                        status.push(value.to_string());
+                    }
+                    continue;
+                }
+                "name server" | "nserver" | "nameserver" => {
+                    let value = value.to_ascii_lowercase();
+                    if !name_servers.contains(&value) {
+                        name_servers.push(value);
+                    }
+                    continue;
+                }
+                _ => continue,
+            };
+            if field.is_none() {
+                *field = Some(value.to_string());
+            }
+        }
+        if record.status.is_empty() {
+            record.status = status;
+        }
+        if record.name_servers.is_empty() {
+            record.name_servers = name_servers;
+        }
+    }
+    if let Some(domain) = &mut record.domain {
+        *domain = domain.to_ascii_lowercase();
+    }
+    record
+}
+
+fn print_record(record: &WhoisRecord) {
+    let fields = [
+        ("Domain", &record.domain),
+        ("Registrar", &record.registrar),
+        ("Created", &record.created),
+        ("Updated", &record.updated),
+        ("Expires", &record.expires),
+        ("Network", &record.network),
+        ("CIDR", &record.cidr),
+        ("Netname", &record.netname),
+        ("Organization", &record.organization),
+        ("Country", &record.country),
+        ("ASN", &record.asn),
+    ];
+    for (name, value) in fields {
+        if let Some(value) = value {
+            println!("{:<14}{}", format!("{}:", name), value);
+        }
+    }
+    for (name, values) in [
+        ("Status", &record.status),
+        ("Name servers", &record.name_servers),
+    ] {
+        if !values.is_empty() {
+            println!("{:<14}{}", format!("{}:", name), values.join(", "));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_and_referral() {
+        assert_eq!(normalize_query("Example.COM.").unwrap(), "example.com");
+        assert_eq!(
+            normalize_query("例子.中国").unwrap(),
+            "xn--fsqu00a.xn--fiqs8s"
+        );
+        assert_eq!(normalize_query("2001:DB8::1").unwrap(), "2001:db8::1");
+        assert!(normalize_query("localhost").is_err());
+        assert_eq!(initial_server("baidu.com.cn"), "whois.cnnic.cn");
+        assert_eq!(initial_server("xn--fsqu00a.xn--fiqs8s"), "whois.cnnic.cn");
+        assert_eq!(initial_server("example.dev"), IANA_SERVER);
+        assert_eq!(initial_server("8.8.8.8"), IANA_SERVER);
+        assert_eq!(whois_query("whois.arin.net", "8.8.8.8"), "n + 8.8.8.8");
+
+        assert_eq!(
+            referral("domain:       DEV\n\nrefer:        whois.nic.google\n").as_deref(),
+            Some("whois.nic.google")
+        );
+        assert_eq!(
+            referral("ReferralServer:  whois://whois.apnic.net:43\n").as_deref(),
+            Some("whois.apnic.net")
+        );
+        assert_eq!(
+            referral("ReferralServer: rwhois://rwhois.example.net:4321\n"),
+            None
+        );
+        assert_eq!(referral("Registrar WHOIS Server: \n"), None);
+    }
+
+    #[test]
+    fn test_parse_record() {
+        let registry = "   Domain Name: EXAMPLE.COM\n   Registrar WHOIS Server: whois.example-registrar.com\n   Updated Date: 2024-08-14T07:01:34Z\n   Creation Date: 1995-08-14T04:00:00Z\n   Registry Expiry Date: 2025-08-13T04:00:00Z\n   Registrar: RESERVED-Internet Assigned Numbers Authority\n   Domain Status: clientDeleteProhibited https://icann.org/epp#clientDeleteProhibited\n   Domain Status: clientTransferProhibited https://icann.org/epp#clientTransferProhibited\n   Name Server: A.IANA-SERVERS.NET\n   Name Server: B.IANA-SERVERS.NET\n>>> Last update of whois database: 2024-09-01T00:00:00Z <<<\n";
+        let registrar = "Domain Name: example.com\nRegistrar: Example Registrar, Inc.\nRegistrar Registration Expiration Date: 2025-08-13T04:00:00Z\n";
+        let record = parse_record(&[registry, registrar]);
+        assert_eq!(record.domain.as_deref(), Some("example.com"));
+        assert_eq!(record.registrar.as_deref(), Some("Example Registrar, Inc."));
+        assert_eq!(record.created.as_deref(), Some("1995-08-14T04:00:00Z"));
+        assert_eq!(record.expires.as_deref(), Some("2025-08-13T04:00:00Z"));
+        assert_eq!(
+            record.status,
+            ["clientDeleteProhibited", "clientTransferProhibited"]
+        );
+        assert_eq!(
+            record.name_servers,
+            ["a.iana-servers.net", "b.iana-servers.net"]
+        );
+
+        // CNNIC 的字段名
+        let cnnic = "Domain Name: baidu.cn\nDomain Status: clientDeleteProhibited\nSponsoring Registrar: 北京新网数码信息技术有限公司\nName Server: ns1.baidu.com\nRegistration Time: 2003-03-17 12:20:05\nExpiration Time: 2026-03-17 12:48:36\n";
+        let record = parse_record(&[cnnic]);
+        assert_eq!(
+            record.registrar.as_deref(),
+            Some("北京新网数码信息技术有限公司")
+        );
+        assert_eq!(record.created.as_deref(), Some("2003-03-17 12:20:05"));
+        assert_eq!(record.expires.as_deref(), Some("2026-03-17 12:48:36"));
+
+        let ripe = "% This is the RIPE Database query service.\ninetnum:        193.0.0.0 - 193.0.7.255\nnetname:        RIPE-NCC\ncountry:        NL\nstatus:         ASSIGNED PA\n\nroute:          193.0.0.0/21\norigin:         AS3333\n";
+        let record = parse_record(&[ripe]);
+        assert_eq!(record.network.as_deref(), Some("193.0.0.0 - 193.0.7.255"));
+        assert_eq!(record.cidr.as_deref(), Some("193.0.0.0/21"));
+        assert_eq!(record.asn.as_deref(), Some("AS3333"));
+        assert_eq!(record.country.as_deref(), Some("NL"));
+    }
+}
@@ -0,0 +1,536 @@
+// 交互式向导：逐步询问常用任务（生成测试身份数据、扫描主机、转换文件）的参数，
+// 最后打印等价的完整命令行，让用户顺便记住对应的参数；确认后以子进程运行这条命令。
+
+use std::fmt;
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use dialoguer::{Confirm, Input, Select};
+
+use crate::commands::portscan::parse_port_spec;
+use crate::utils::a11y;
+
+#[derive(clap::Args)]
+pub struct WizardOpts {
+    #[arg(long = "print-only", help = "只打印拼好的命令，不询问是否执行")]
+    print_only: bool,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum WizardError {
+    #[error("wizard needs an interactive terminal")]
+    NotInteractive,
+    #[error("cancelled")]
+    Cancelled,
+    #[error("prompt failed: {0}")]
+    Prompt(#[from] dialoguer::Error),
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+}
+
+pub fn run_wizard(opts: WizardOpts) -> Result<(), WizardError> {
+    if !(io::stdin().is_terminal() && io::stderr().is_terminal()) {
+        return Err(WizardError::NotInteractive);
+    }
+    let command = match ask() {
+        Ok(command) => command,
+        Err(WizardError::Cancelled) => {
+            eprintln!("Cancelled.");
+            return Ok(());
+        }
+        Err(e) => return Err(e),
+    };
+    eprintln!();
+    eprintln!("Equivalent command:");
+    println!("{}", command);
+    if opts.print_only || !confirm("Run it now?", true)? {
+        return Ok(());
+    }
+    let status = Command::new(std::env::current_exe()?)
+        .args(&command.0)
+        .status()?;
+    if !status.success() {
+        std::process::exit(status.code().unwrap_or(1));
+    }
+    Ok(())
+}
+
+fn ask() -> Result<CommandLine, WizardError> {
+    let task = select(
+        "What do you want to do?",
+        &[
+            "Generate test identities (ID numbers or person records)",
+            "Scan a host for open ports",
+            "Convert an image or certificate file",
+        ],
+    )?;
+    match task {
+        0 => ask_identities(),
+        1 => ask_scan(),
+        _ => ask_convert(),
+    }
+}
+
+// 拼出的命令行，不含程序名
+#[derive(Debug, Default, PartialEq, Eq)]
+struct CommandLine(Vec<String>);
+
+impl CommandLine {
+    fn new(subcommand: &[&str]) -> Self {
+        Self(subcommand.iter().map(|arg| arg.to_string()).collect())
+    }
+
+    fn arg(&mut self, value: impl Into<String>) -> &mut Self {
+        self.0.push(value.into());
+        self
+    }
+
+    fn opt(&mut self, flag: &str, value: impl Into<String>) -> &mut Self {
+        self.arg(flag).arg(value)
+    }
+}
+
+// 按 POSIX shell 的规则给含空格、引号等字符的参数加单引号，可以直接复制到终端运行
+impl fmt::Display for CommandLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "rtoolkit")?;
+        for arg in &self.0 {
+            write!(f, " {}", shell_quote(arg))?;
+        }
+        Ok(())
+    }
+}
+
+fn shell_quote(arg: &str) -> String {
+    let safe = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:,=@%+".contains(c));
+    if safe {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IdentityKind {
+    IdNumbers,
+    Persons,
+}
+
+#[derive(Debug)]
+struct IdentityAnswers {
+    kind: IdentityKind,
+    count: u32,
+    region: Option<String>,
+    // idgen 的 -g，any 时省略
+    gender: &'static str,
+    // person 的 --family
+    family: bool,
+    format: &'static str,
+    output: Option<String>,
+}
+
+fn ask_identities() -> Result<CommandLine, WizardError> {
+    let kind = match select(
+        "What kind of test data?",
+        &[
+            "ID numbers only (idgen)",
+            "Person records with name, phone and address (person)",
+        ],
+    )? {
+        0 => IdentityKind::IdNumbers,
+        _ => IdentityKind::Persons,
+    };
+    let count = Input::<u32>::new()
+        .with_prompt("How many records?")
+        .default(10)
+        .validate_with(|count: &u32| {
+            if *count == 0 {
+                Err("Enter a number greater than 0")
+            } else {
+                Ok(())
+            }
+        })
+        .interact_text()?;
+    let region = optional_text("Region code, e.g. 11 or 110101 (empty for random regions)")?;
+    let mut answers = IdentityAnswers {
+        kind,
+        count,
+        region,
+        gender: "any",
+        family: false,
+        format: "text",
+        output: None,
+    };
+    match kind {
+        IdentityKind::IdNumbers => {
+            answers.gender =
+                ["any", "male", "female"][select("Gender", &["any", "male", "female"])?];
+            // idgen 输出到终端时固定为文本，只有写文件时才能选择格式
+            answers.output = optional_text("Output file (empty prints to the terminal)")?;
+            if answers.output.is_some() {
+                let formats = ["text", "csv", "json", "excel"];
+                answers.format = formats[select("File format", &formats)?];
+            }
+        }
+        IdentityKind::Persons => {
+            answers.family = confirm(
+                "Generate households (head, spouse and children sharing an address)?",
+                false,
+            )?;
+            let formats = ["text", "csv", "json"];
+            answers.format = formats[select("Output format", &formats)?];
+            answers.output = optional_text("Output file (empty prints to the terminal)")?;
+        }
+    }
+    Ok(identity_command(&answers))
+}
+
+fn identity_command(answers: &IdentityAnswers) -> CommandLine {
+    let mut command = match answers.kind {
+        IdentityKind::IdNumbers => CommandLine::new(&["idgen"]),
+        IdentityKind::Persons => CommandLine::new(&["person"]),
+    };
+    command.opt("-n", answers.count.to_string());
+    if let Some(region) = &answers.region {
+        command.opt("-r", region);
+    }
+    if answers.gender != "any" {
+        command.opt("-g", answers.gender);
+    }
+    if answers.family {
+        command.arg("--family");
+    }
+    if answers.format != "text" {
+        command.opt("-t", answers.format);
+    }
+    if let Some(output) = &answers.output {
+        command.opt("-o", output);
+    }
+    command
+}
+
+#[derive(Debug)]
+enum PortChoice {
+    Top(&'static str),
+    Spec(String),
+}
+
+#[derive(Debug)]
+struct ScanAnswers {
+    target: String,
+    ports: PortChoice,
+    service_detect: bool,
+    tls_probe: bool,
+    http_probe: bool,
+    open_only: bool,
+    format: &'static str,
+}
+
+fn ask_scan() -> Result<CommandLine, WizardError> {
+    let target = Input::<String>::new()
+        .with_prompt("Target host, IP or CIDR (comma-separated for several)")
+        .default("127.0.0.1".to_string())
+        .interact_text()?;
+    let ports = match select(
+        "Which ports?",
+        &[
+            "Top 100 common ports",
+            "Top 1000 common ports",
+            "Specific ports or ranges",
+            "All 65535 ports",
+        ],
+    )? {
+        0 => PortChoice::Top("100"),
+        1 => PortChoice::Top("1000"),
+        2 => PortChoice::Spec(
+            Input::<String>::new()
+                .with_prompt("Ports, e.g. 22,80,443,8000-8100 or http,https,ssh")
+                .validate_with(|spec: &String| {
+                    parse_port_spec(spec).map(|_| ()).map_err(|e| e.to_string())
+                })
+                .interact_text()?,
+        ),
+        _ => PortChoice::Spec("-".to_string()),
+    };
+    let answers = ScanAnswers {
+        target,
+        ports,
+        service_detect: confirm("Identify services and versions from banners?", false)?,
+        tls_probe: confirm("Read TLS certificates on open ports?", false)?,
+        http_probe: confirm("Fetch status, server and title from web ports?", false)?,
+        open_only: confirm("List only open ports?", true)?,
+        format: {
+            let formats = ["plain", "json", "csv"];
+            formats[select("Output format", &formats)?]
+        },
+    };
+    Ok(scan_command(&answers))
+}
+
+fn scan_command(answers: &ScanAnswers) -> CommandLine {
+    let mut command = CommandLine::new(&["port-scan"]);
+    command.opt("-t", &answers.target);
+    match &answers.ports {
+        PortChoice::Top(count) => command.opt("--top-ports", *count),
+        PortChoice::Spec(spec) => command.opt("-p", spec),
+    };
+    for (enabled, flag) in [
+        (answers.service_detect, "--service-detect"),
+        (answers.tls_probe, "--tls-probe"),
+        (answers.http_probe, "--http-probe"),
+        (answers.open_only, "--open"),
+    ] {
+        if enabled {
+            command.arg(flag);
+        }
+    }
+    if answers.format != "plain" {
+        command.opt("-o", answers.format);
+    }
+    command
+}
+
+const IMAGE_FORMATS: [&str; 7] = ["png", "jpg", "webp", "bmp", "gif", "tiff", "ico"];
+const CERT_EXTENSIONS: [&str; 7] = ["pem", "crt", "cer", "der", "key", "p12", "pfx"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FileKind {
+    Image,
+    Certificate,
+}
+
+// 按扩展名判断文件类型，无法判断时返回 None
+fn file_kind(path: &Path) -> Option<FileKind> {
+    let ext = extension(path);
+    if IMAGE_FORMATS.contains(&ext.as_str()) || ["jpeg", "tif"].contains(&ext.as_str()) {
+        Some(FileKind::Image)
+    } else if CERT_EXTENSIONS.contains(&ext.as_str()) {
+        Some(FileKind::Certificate)
+    } else {
+        None
+    }
+}
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .map(|ext| ext.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default()
+}
+
+#[derive(Debug)]
+struct ConvertAnswers {
+    kind: FileKind,
+    input: PathBuf,
+    // 图片为 png、jpg 等，证书为 pem、der 或 p12
+    format: &'static str,
+    output: PathBuf,
+}
+
+fn ask_convert() -> Result<CommandLine, WizardError> {
+    let input = PathBuf::from(
+        Input::<String>::new()
+            .with_prompt("File to convert")
+            .validate_with(|path: &String| {
+                if Path::new(path).is_file() {
+                    Ok(())
+                } else {
+                    Err(format!("{} is not a file", path))
+                }
+            })
+            .interact_text()?,
+    );
+    let kind = match file_kind(&input) {
+        Some(kind) => kind,
+        None => match select(
+            "What kind of file is it?",
+            &["Image", "Certificate or private key"],
+        )? {
+            0 => FileKind::Image,
+            _ => FileKind::Certificate,
+        },
+    };
+    let source = extension(&input);
+    let format = match kind {
+        FileKind::Image => {
+            let formats: Vec<&'static str> = IMAGE_FORMATS
+                .into_iter()
+                .filter(|format| *format != source)
+                .collect();
+            formats[select("Convert to", &formats)?]
+        }
+        FileKind::Certificate => {
+            let formats = ["pem", "der", "p12"];
+            let format = formats[select("Convert to", &["PEM", "DER", "PKCS#12"])?];
+            if format == "p12" || source == "p12" || source == "pfx" {
+                eprintln!(
+                    "The PKCS#12 password is read from RTOOLKIT_CERT_PASSWORD and is not put on the command line."
+                );
+            }
+            format
+        }
+    };
+    let output = PathBuf::from(
+        Input::<String>::new()
+            .with_prompt("Output file")
+            .default(input.with_extension(format).to_string_lossy().into_owned())
+            .interact_text()?,
+    );
+    Ok(convert_command(&ConvertAnswers {
+        kind,
+        input,
+        format,
+        output,
+    }))
+}
+
+fn convert_command(answers: &ConvertAnswers) -> CommandLine {
+    let input = answers.input.to_string_lossy();
+    let output = answers.output.to_string_lossy();
+    match answers.kind {
+        FileKind::Image => {
+            let mut command = CommandLine::new(&["imgtool", "basic", "convert"]);
+            command
+                .opt("-i", input)
+                .opt("-o", output)
+                .opt("-f", answers.format);
+            command
+        }
+        FileKind::Certificate => {
+            let mut command = CommandLine::new(&["cert", "convert"]);
+            command
+                .arg(input)
+                .opt("--to", answers.format)
+                .opt("--out", output);
+            command
+        }
+    }
+}
+
+// 单选，--a11y 时改为输入编号；Esc / q 取消整个向导
+fn select(prompt: &str, items: &[&str]) -> Result<usize, WizardError> {
+    let choice = if a11y::is_enabled() {
+        let labels: Vec<String> = items.iter().map(|item| item.to_string()).collect();
+        a11y::pick_numbered(prompt, &labels, false)?.map(|chosen| chosen[0])
+    } else {
+        Select::new()
+            .with_prompt(prompt)
+            .items(items)
+            .default(0)
+            .interact_opt()?
+    };
+    choice.ok_or(WizardError::Cancelled)
+}
+
+fn confirm(prompt: &str, default: bool) -> Result<bool, WizardError> {
+    Confirm::new()
+        .with_prompt(prompt)
+        .default(default)
+        .interact_opt()?
+        .ok_or(WizardError::Cancelled)
+}
+
+// 可以留空的文本输入，空输入返回 None
+fn optional_text(prompt: &str) -> Result<Option<String>, WizardError> {
+    let text = Input::<String>::new()
+        .with_prompt(prompt)
+        .allow_empty(true)
+        .interact_text()?;
+    let text = text.trim();
+    Ok((!text.is_empty()).then(|| text.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::super::Cli;
+    use super::*;
+
+    // 拼出的命令必须能被 CLI 原样解析，防止参数改名后向导输出过时的命令
+    fn assert_parses(command: &CommandLine) {
+        let args = std::iter::once("rtoolkit".to_string()).chain(command.0.iter().cloned());
+        if let Err(e) = Cli::try_parse_from(args) {
+            panic!("{} does not parse: {}", command, e);
+        }
+    }
+
+    #[test]
+    fn test_built_commands_parse() {
+        let command = identity_command(&IdentityAnswers {
+            kind: IdentityKind::IdNumbers,
+            count: 20,
+            region: Some("110101".into()),
+            gender: "female",
+            family: false,
+            format: "excel",
+            output: Some("my ids.xlsx".into()),
+        });
+        assert_eq!(
+            command.to_string(),
+            "rtoolkit idgen -n 20 -r 110101 -g female -t excel -o 'my ids.xlsx'"
+        );
+        assert_parses(&command);
+
+        let command = identity_command(&IdentityAnswers {
+            kind: IdentityKind::Persons,
+            count: 5,
+            region: None,
+            gender: "any",
+            family: true,
+            format: "json",
+            output: None,
+        });
+        assert_eq!(command.to_string(), "rtoolkit person -n 5 --family -t json");
+        assert_parses(&command);
+
+        let command = scan_command(&ScanAnswers {
+            target: "192.168.1.0/24".into(),
+            ports: PortChoice::Top("100"),
+            service_detect: true,
+            tls_probe: true,
+            http_probe: true,
+            open_only: true,
+            format: "json",
+        });
+        assert_eq!(
+            command.to_string(),
+            "rtoolkit port-scan -t 192.168.1.0/24 --top-ports 100 --service-detect --tls-probe --http-probe --open -o json"
+        );
+        assert_parses(&command);
+        assert_parses(&scan_command(&ScanAnswers {
+            target: "db.internal".into(),
+            ports: PortChoice::Spec("-".into()),
+            service_detect: false,
+            tls_probe: false,
+            http_probe: false,
+            open_only: false,
+            format: "plain",
+        }));
+
+        for (kind, format) in [(FileKind::Image, "webp"), (FileKind::Certificate, "p12")] {
+            assert_parses(&convert_command(&ConvertAnswers {
+                kind,
+                input: "in.file".into(),
+                format,
+                output: format!("out.{}", format).into(),
+            }));
+        }
+    }
+
+    #[test]
+    fn test_shell_quote_and_file_kind() {
+        assert_eq!(shell_quote("a-b_c.d/e:1,2"), "a-b_c.d/e:1,2");
+        assert_eq!(shell_quote(""), "''");
+        assert_eq!(shell_quote("it's here"), r"'it'\''s here'");
+        assert_eq!(file_kind(Path::new("photo.JPEG")), Some(FileKind::Image));
+        assert_eq!(
+            file_kind(Path::new("bundle.pfx")),
+            Some(FileKind::Certificate)
+        );
+        assert_eq!(file_kind(Path::new("notes.txt")), None);
+    }
+}
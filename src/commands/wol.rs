@@ -0,0 +1,354 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs, UdpSocket};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::commands::mac::MacAddr;
+use crate::utils::network::{self, NetworkError};
+
+const DEFAULT_PORT: u16 = 9;
+// 魔术包：6 个 0xFF，再重复 16 次目标 MAC
+const MAGIC_LEN: usize = 6 + 16 * 6;
+
+#[derive(clap::Args)]
+pub struct WolOpts {
+    #[arg(
+        value_name = "HOST|MAC",
+        required_unless_present = "list",
+        help = "要唤醒的 MAC 地址或配置文件中的主机名，可指定多个"
+    )]
+    targets: Vec<String>,
+
+    #[arg(
+        short = 'b',
+        long,
+        value_name = "ADDR",
+        help = "魔术包的目标地址，通常是网段的广播地址，例如 192.168.1.255；也可以是做了端口转发的公网地址或域名。默认取配置中的值，再退回 255.255.255.255"
+    )]
+    broadcast: Option<String>,
+
+    #[arg(
+        short = 'p',
+        long,
+        value_name = "PORT",
+        help = "UDP 端口，常用 9 或 7；默认取配置中的值，再退回 9"
+    )]
+    port: Option<u16>,
+
+    #[arg(
+        short = 'n',
+        long,
+        value_name = "N",
+        default_value_t = 3,
+        value_parser = clap::value_parser!(u32).range(1..=100),
+        help = "每个目标发送的次数，UDP 可能丢包，间隔 100 毫秒"
+    )]
+    count: u32,
+
+    #[arg(
+        short = 'c',
+        long,
+        value_name = "FILE",
+        help = "主机配置文件（YAML），默认 $XDG_CONFIG_HOME/rtoolkit/wol.yaml，未设置时为 ~/.config/rtoolkit/wol.yaml"
+    )]
+    config: Option<PathBuf>,
+
+    #[arg(short = 'l', long, help = "列出配置文件中的主机")]
+    list: bool,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum WolError {
+    #[error(
+        "cannot determine the config directory, set XDG_CONFIG_HOME or HOME, or pass --config"
+    )]
+    NoConfigDir,
+    #[error("failed to read {path}: {source}")]
+    Read { path: PathBuf, source: io::Error },
+    #[error("invalid config {path}: {source}")]
+    Config {
+        path: PathBuf,
+        source: serde_yaml::Error,
+    },
+    #[error("host '{host}' in {path} has an invalid MAC address '{mac}'")]
+    HostMac {
+        host: String,
+        mac: String,
+        path: PathBuf,
+    },
+    #[error("'{target}' is neither a MAC address nor a host in {path}")]
+    UnknownTarget { target: String, path: PathBuf },
+    #[error("cannot resolve '{0}'")]
+    Resolve(String),
+    #[error(transparent)]
+    Network(#[from] NetworkError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WolConfig {
+    #[serde(default)]
+    hosts: BTreeMap<String, HostEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct HostEntry {
+    mac: String,
+    broadcast: Option<String>,
+    port: Option<u16>,
+}
+
+// 解析后的一个唤醒目标
+#[derive(Debug, PartialEq, Eq)]
+struct Wake {
+    name: Option<String>,
+    mac: MacAddr,
+    broadcast: String,
+    port: u16,
+}
+
+pub fn run_wol(opts: WolOpts) -> Result<(), WolError> {
+    let path = match &opts.config {
+        Some(path) => path.clone(),
+        None => default_config_path()?,
+    };
+    // 只给 MAC 时不需要配置文件，默认位置的文件不存在也不报错
+    let config = load_config(&path, opts.config.is_some())?;
+
+    if opts.list {
+        print_hosts(&config, &path);
+        return Ok(());
+    }
+
+    let wakes = opts
+        .targets
+        .iter()
+        .map(|target| {
+            let wake =
+                resolve_target(target, &config, &path, opts.broadcast.as_deref(), opts.port)?;
+            let addr = resolve_addr(&wake.broadcast, wake.port)?;
+            Ok((wake, addr))
+        })
+        .collect::<Result<Vec<_>, WolError>>()?;
+
+    for round in 0..opts.count {
+        if round > 0 {
+            std::thread::sleep(Duration::from_millis(100));
+        }
+        for (wake, addr) in &wakes {
+            send(&magic_packet(wake.mac), *addr)?;
+        }
+    }
+    let times = format!(
+        "{} magic packet{}",
+        opts.count,
+        if opts.count == 1 { "" } else { "s" }
+    );
+    for (wake, addr) in &wakes {
+        match &wake.name {
+            Some(name) => println!("Sent {} to {} ({}) via {}", times, name, wake.mac, addr),
+            None => println!("Sent {} to {} via {}", times, wake.mac, addr),
+        }
+    }
+    Ok(())
+}
+
+// 遵循 XDG 规范：$XDG_CONFIG_HOME/rtoolkit/wol.yaml，未设置时为 ~/.config/rtoolkit/wol.yaml
+fn default_config_path() -> Result<PathBuf, WolError> {
+    let base = match env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => env::var_os("HOME")
+            .or_else(|| env::var_os("USERPROFILE"))
+            .map(|home| Path::new(&home).join(".config"))
+            .ok_or(WolError::NoConfigDir)?,
+    };
+    Ok(base.join("rtoolkit").join("wol.yaml"))
+}
+
+fn load_config(path: &Path, explicit: bool) -> Result<WolConfig, WolError> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound && !explicit => {
+            return Ok(WolConfig::default())
+        }
+        Err(source) => {
+            return Err(WolError::Read {
+                path: path.to_path_buf(),
+                source,
+            })
+        }
+    };
+    // 空文件视为没有主机
+    if text.trim().is_empty() {
+        return Ok(WolConfig::default());
+    }
+    serde_yaml::from_str(&text).map_err(|source| WolError::Config {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+// 先按主机名查配置，再当作 MAC 地址；命令行的 --broadcast / --port 优先于配置
+fn resolve_target(
+    target: &str,
+    config: &WolConfig,
+    path: &Path,
+    broadcast: Option<&str>,
+    port: Option<u16>,
+) -> Result<Wake, WolError> {
+    let broadcast = |entry: Option<&str>| {
+        broadcast
+            .or(entry)
+            .map(str::to_string)
+            .unwrap_or_else(|| Ipv4Addr::BROADCAST.to_string())
+    };
+    if let Some(entry) = config.hosts.get(target) {
+        let mac = MacAddr::parse(&entry.mac).ok_or_else(|| WolError::HostMac {
+            host: target.to_string(),
+            mac: entry.mac.clone(),
+            path: path.to_path_buf(),
+        })?;
+        return Ok(Wake {
+            name: Some(target.to_string()),
+            mac,
+            broadcast: broadcast(entry.broadcast.as_deref()),
+            port: port.or(entry.port).unwrap_or(DEFAULT_PORT),
+        });
+    }
+    let mac = MacAddr::parse(target).ok_or_else(|| WolError::UnknownTarget {
+        target: target.to_string(),
+        path: path.to_path_buf(),
+    })?;
+    Ok(Wake {
+        name: None,
+        mac,
+        broadcast: broadcast(None),
+        port: port.unwrap_or(DEFAULT_PORT),
+    })
+}
+
+// 广播地址可以是 IP（IPv6 可带方括号）或需要 DNS 的主机名
+fn resolve_addr(host: &str, port: u16) -> Result<SocketAddr, WolError> {
+    network::check_host("wol", host)?;
+    let bare = host.trim_start_matches('[').trim_end_matches(']');
+    if let Ok(ip) = bare.parse() {
+        return Ok(SocketAddr::new(ip, port));
+    }
+    (host, port)
+        .to_socket_addrs()
+        .ok()
+        .and_then(|mut addrs| addrs.next())
+        .ok_or_else(|| WolError::Resolve(host.to_string()))
+}
+
+fn send(packet: &[u8], addr: SocketAddr) -> io::Result<()> {
+    let local: SocketAddr = match addr {
+        SocketAddr::V4(_) => (Ipv4Addr::UNSPECIFIED, 0).into(),
+        SocketAddr::V6(_) => (Ipv6Addr::UNSPECIFIED, 0).into(),
+    };
+    let socket = UdpSocket::bind(local)?;
+    socket.set_broadcast(addr.is_ipv4())?;
+    socket.send_to(packet, addr)?;
+    Ok(())
+}
+
+fn magic_packet(mac: MacAddr) -> [u8; MAGIC_LEN] {
+    let mut packet = [0xff; MAGIC_LEN];
+    for chunk in packet[6..].chunks_exact_mut(6) {
+        chunk.copy_from_slice(&mac.0);
+    }
+    packet
+}
+
+fn print_hosts(config: &WolConfig, path: &Path) {
+    if config.hosts.is_empty() {
+        eprintln!("No hosts configured in {}", path.display());
+        return;
+    }
+    let width = config
+        .hosts
+        .keys()
+        .map(|name| name.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max(4);
+    println!(
+        "{:<width$}  {:<17}  {:<15}  PORT",
+        "NAME",
+        "MAC",
+        "BROADCAST",
+        width = width
+    );
+    for (name, entry) in &config.hosts {
+        let mac = MacAddr::parse(&entry.mac)
+            .map(|mac| mac.to_string())
+            .unwrap_or_else(|| format!("{} (invalid)", entry.mac));
+        println!(
+            "{:<width$}  {:<17}  {:<15}  {}",
+            name,
+            mac,
+            entry.broadcast.as_deref().unwrap_or("-"),
+            entry
+                .port
+                .map(|port| port.to_string())
+                .unwrap_or_else(|| "-".to_string()),
+            width = width
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_magic_packet() {
+        let mac = MacAddr([0x00, 0x11, 0x32, 0xaa, 0xbb, 0xcc]);
+        let packet = magic_packet(mac);
+        assert_eq!(packet.len(), 102);
+        assert_eq!(&packet[..6], &[0xff; 6]);
+        assert!(packet[6..].chunks(6).all(|chunk| chunk == mac.0));
+    }
+
+    #[test]
+    fn test_resolve_targets() {
+        let config: WolConfig = serde_yaml::from_str(
+            "hosts:\n  nas:\n    mac: 00-11-32-AA-BB-CC\n    broadcast: 192.168.1.255\n  pc:\n    mac: 0011.32aa.bbcd\n    port: 7\n  bad:\n    mac: nope\n",
+        )
+        .unwrap();
+        let path = Path::new("wol.yaml");
+
+        let nas = resolve_target("nas", &config, path, None, None).unwrap();
+        assert_eq!(nas.name.as_deref(), Some("nas"));
+        assert_eq!(nas.mac.to_string(), "00:11:32:aa:bb:cc");
+        assert_eq!((nas.broadcast.as_str(), nas.port), ("192.168.1.255", 9));
+
+        // 命令行参数优先于配置
+        let pc = resolve_target("pc", &config, path, Some("10.0.0.255"), None).unwrap();
+        assert_eq!((pc.broadcast.as_str(), pc.port), ("10.0.0.255", 7));
+        let pc = resolve_target("pc", &config, path, None, Some(9)).unwrap();
+        assert_eq!((pc.broadcast.as_str(), pc.port), ("255.255.255.255", 9));
+
+        let raw = resolve_target("b8:27:eb:12:34:56", &config, path, None, None).unwrap();
+        assert_eq!(raw.name, None);
+        assert!(matches!(
+            resolve_target("bad", &config, path, None, None),
+            Err(WolError::HostMac { .. })
+        ));
+        assert!(matches!(
+            resolve_target("printer", &config, path, None, None),
+            Err(WolError::UnknownTarget { .. })
+        ));
+        assert!(
+            serde_yaml::from_str::<WolConfig>("hosts:\n  nas:\n    mac: x\n    ip: y\n").is_err()
+        );
+    }
+}
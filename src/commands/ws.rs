@@ -0,0 +1,595 @@
+use std::io;
+use std::time::Duration;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use sha1::{Digest, Sha1};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+use crate::commands::http::{parse_header, HttpCmdError};
+use crate::utils::format::hex_dump;
+use crate::utils::http::{self, HttpError, Request, Url};
+use crate::utils::network::{self, NetworkError};
+use crate::utils::proxy::{Proxy, ProxyError};
+
+// RFC 6455 中计算 Sec-WebSocket-Accept 用的固定 GUID
+const WS_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+// 单条消息（含全部分片）的上限
+const MAX_MESSAGE_BYTES: usize = 16 << 20;
+// 握手失败时最多显示的响应体长度
+const MAX_ERROR_BODY: usize = 4096;
+
+const OP_CONTINUATION: u8 = 0x0;
+const OP_TEXT: u8 = 0x1;
+const OP_BINARY: u8 = 0x2;
+const OP_CLOSE: u8 = 0x8;
+const OP_PING: u8 = 0x9;
+const OP_PONG: u8 = 0xa;
+
+#[derive(clap::Args)]
+pub struct WsOpts {
+    #[arg(value_name = "URL", help = "ws:// 或 wss:// 地址，省略协议时为 ws://")]
+    url: String,
+
+    #[arg(
+        short = 's',
+        long = "send",
+        value_name = "MSG",
+        help = "依次发送的消息，可重复；指定后不读 stdin，等待回复后关闭连接"
+    )]
+    send: Vec<String>,
+
+    #[arg(
+        short = 'H',
+        long = "header",
+        value_name = "NAME: VALUE",
+        help = "握手请求头，可重复，例如 Authorization、Cookie"
+    )]
+    headers: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "PROTO",
+        help = "请求的子协议（Sec-WebSocket-Protocol），多个用逗号分隔"
+    )]
+    protocol: Option<String>,
+
+    #[arg(long, help = "以二进制帧发送消息，默认文本帧")]
+    binary: bool,
+
+    #[arg(
+        long,
+        value_name = "SECS",
+        help = "每隔 SECS 秒发送 ping 保活，并显示 pong 的往返时间"
+    )]
+    ping: Option<u64>,
+
+    #[arg(
+        short = 'w',
+        long,
+        default_value_t = 3,
+        value_name = "SECS",
+        help = "--send 发送完或 stdin 结束后等待回复的时间，期间收到消息则重新计时"
+    )]
+    wait: u64,
+
+    #[arg(short = 'n', long, value_name = "N", help = "收到 N 条消息后关闭连接")]
+    count: Option<u64>,
+
+    #[arg(short = 't', long, help = "在收到的每条消息前标出距连接建立的时间")]
+    timing: bool,
+
+    #[arg(
+        short = 'x',
+        long,
+        value_name = "URL",
+        help = "经代理连接：socks5://、socks5h:// 或 http://[user:pass@]host[:port]（CONNECT 隧道）"
+    )]
+    proxy: Option<String>,
+
+    #[arg(short = 'k', long, help = "不校验 wss 证书")]
+    insecure: bool,
+
+    #[arg(
+        long = "timeout",
+        default_value_t = 10000,
+        value_name = "MS",
+        help = "连接和握手的超时时间(毫秒)"
+    )]
+    time_out: u64,
+
+    #[arg(short = 'q', long, help = "不在 stderr 输出连接、pong 和关闭信息")]
+    quiet: bool,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum WsError {
+    #[error("invalid URL '{0}', expected ws://host[:port]/path or wss://...")]
+    InvalidUrl(String),
+    #[error("handshake failed: server returned {0}")]
+    Rejected(String),
+    #[error("handshake failed: {0}")]
+    Handshake(&'static str),
+    #[error("connection timed out after {0} ms")]
+    Timeout(u64),
+    #[error("protocol error: {0}")]
+    Protocol(&'static str),
+    #[error("message larger than {} MiB", MAX_MESSAGE_BYTES >> 20)]
+    TooLarge,
+    #[error("connection closed without a close frame")]
+    Abnormal,
+    #[error(transparent)]
+    Http(#[from] HttpError),
+    #[error(transparent)]
+    Header(#[from] HttpCmdError),
+    #[error(transparent)]
+    Proxy(#[from] ProxyError),
+    #[error(transparent)]
+    Network(#[from] NetworkError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+struct Frame {
+    fin: bool,
+    opcode: u8,
+    payload: Vec<u8>,
+}
+
+// 分片已合并的完整消息和控制帧
+#[derive(Debug, PartialEq, Eq)]
+enum Incoming {
+    Text(String),
+    Binary(Vec<u8>),
+    Ping(Vec<u8>),
+    Pong(Vec<u8>),
+    Close(Option<u16>, String),
+}
+
+pub fn run_ws(opts: WsOpts) -> Result<(), WsError> {
+    let rt = tokio::runtime::Runtime::new()?;
+    let result = rt.block_on(ws(opts));
+    // 读 stdin 的阻塞线程可能还在等待输入，不等它结束
+    rt.shutdown_background();
+    result
+}
+
+async fn ws(opts: WsOpts) -> Result<(), WsError> {
+    let url = parse_ws_url(&opts.url)?;
+    let proxy = opts.proxy.as_deref().map(Proxy::parse).transpose()?;
+    match &proxy {
+        Some(proxy) => network::check_host("ws proxy", &proxy.host)?,
+        None => network::check_host("ws", &url.host)?,
+    }
+    let mut headers = opts
+        .headers
+        .iter()
+        .map(|header| parse_header(header))
+        .collect::<Result<Vec<_>, _>>()?;
+    let key = STANDARD.encode(rand::random::<[u8; 16]>());
+    headers.push(("Upgrade".into(), "websocket".into()));
+    headers.push(("Sec-WebSocket-Key".into(), key.clone()));
+    headers.push(("Sec-WebSocket-Version".into(), "13".into()));
+    if let Some(protocol) = &opts.protocol {
+        headers.push(("Sec-WebSocket-Protocol".into(), protocol.clone()));
+    }
+
+    let started = Instant::now();
+    let (response, stream, connected) =
+        tokio::time::timeout(Duration::from_millis(opts.time_out.max(1)), async {
+            let stream = http::connect(&url, proxy.as_ref(), opts.insecure).await?;
+            let connected = started.elapsed();
+            let request = Request {
+                method: "GET",
+                url: &url,
+                headers: &headers,
+                body: &[],
+            };
+            let (response, stream) = http::upgrade(stream, &request, MAX_ERROR_BODY).await?;
+            Ok::<_, HttpError>((response, stream, connected))
+        })
+        .await
+        .map_err(|_| WsError::Timeout(opts.time_out))??;
+    let upgraded = started.elapsed();
+    if response.status != 101 {
+        let body = String::from_utf8_lossy(&response.body);
+        let mut status = format!("{} {}", response.status, response.reason);
+        if !body.trim().is_empty() {
+            status = format!("{}: {}", status, body.trim());
+        }
+        return Err(WsError::Rejected(status));
+    }
+    if response.header("Sec-WebSocket-Accept") != Some(accept_key(&key).as_str()) {
+        return Err(WsError::Handshake(
+            "Sec-WebSocket-Accept does not match the key",
+        ));
+    }
+    if !opts.quiet {
+        eprintln!(
+            "Connected to {} (connect {}, handshake {})",
+            display_url(&url),
+            format_ms(connected),
+            format_ms(upgraded - connected)
+        );
+        if let Some(protocol) = response.header("Sec-WebSocket-Protocol") {
+            eprintln!("Subprotocol: {}", protocol);
+        }
+    }
+
+    let (mut reader, mut writer) = tokio::io::split(stream);
+    let (tx, mut incoming) = mpsc::channel(64);
+    tokio::spawn(async move {
+        let mut partial = None;
+        loop {
+            let message = read_message(&mut reader, &mut partial).await;
+            let last = matches!(message, Err(_) | Ok(Incoming::Close(..)));
+            if tx.send(message).await.is_err() || last {
+                return;
+            }
+        }
+    });
+
+    let opcode = if opts.binary { OP_BINARY } else { OP_TEXT };
+    let wait = Duration::from_secs(opts.wait);
+    let mut lines = None;
+    let mut deadline = None;
+    if opts.send.is_empty() {
+        lines = Some(BufReader::new(tokio::io::stdin()).lines());
+    } else {
+        for message in &opts.send {
+            send_frame(&mut writer, opcode, message.as_bytes()).await?;
+        }
+        deadline = Some(Instant::now() + wait);
+    }
+    let mut keepalive = opts.ping.map(|secs| {
+        let period = Duration::from_secs(secs.max(1));
+        tokio::time::interval_at(Instant::now() + period, period)
+    });
+    let mut received = 0u64;
+
+    loop {
+        tokio::select! {
+            message = incoming.recv() => {
+                match message.ok_or(WsError::Abnormal)?? {
+                    Incoming::Ping(payload) => send_frame(&mut writer, OP_PONG, &payload).await?,
+                    Incoming::Pong(payload) => {
+                        // 自己发出的 ping 载荷是发送时距连接建立的纳秒数
+                        if let Ok(sent) = <[u8; 8]>::try_from(payload.as_slice()) {
+                            let rtt = started
+                                .elapsed()
+                                .saturating_sub(Duration::from_nanos(u64::from_be_bytes(sent)));
+                            if !opts.quiet {
+                                eprintln!("Pong in {}", format_ms(rtt));
+                            }
+                        }
+                    }
+                    Incoming::Close(code, reason) => {
+                        // 回应关闭帧后结束，对端可能已经断开，忽略发送错误
+                        let payload = code.map(u16::to_be_bytes).unwrap_or_default();
+                        let _ = send_frame(&mut writer, OP_CLOSE, &payload[..code.map_or(0, |_| 2)]).await;
+                        if !opts.quiet {
+                            eprintln!("Server closed the connection{}", describe_close(code, &reason));
+                        }
+                        return Ok(());
+                    }
+                    message => {
+                        print_message(&message, opts.timing.then(|| started.elapsed()));
+                        received += 1;
+                        if opts.count.is_some_and(|count| received >= count) {
+                            break;
+                        }
+                        if deadline.is_some() {
+                            deadline = Some(Instant::now() + wait);
+                        }
+                    }
+                }
+            }
+            line = async { lines.as_mut().expect("guarded by select").next_line().await }, if lines.is_some() => {
+                match line? {
+                    Some(line) => match line.trim_end_matches('\r') {
+                        "/close" => break,
+                        "/ping" => send_ping(&mut writer, started).await?,
+                        line => send_frame(&mut writer, opcode, line.as_bytes()).await?,
+                    },
+                    None => {
+                        lines = None;
+                        deadline = Some(Instant::now() + wait);
+                    }
+                }
+            }
+            _ = async { keepalive.as_mut().expect("guarded by select").tick().await }, if keepalive.is_some() => {
+                send_ping(&mut writer, started).await?;
+            }
+            _ = async { tokio::time::sleep_until(deadline.expect("guarded by select")).await }, if deadline.is_some() => break,
+        }
+    }
+
+    // 主动关闭：发送 1000 并等待服务端的关闭帧，最多 1 秒
+    send_frame(&mut writer, OP_CLOSE, &1000u16.to_be_bytes()).await?;
+    let _ = tokio::time::timeout(Duration::from_secs(1), async {
+        while let Some(Ok(message)) = incoming.recv().await {
+            if matches!(message, Incoming::Close(..)) {
+                break;
+            }
+        }
+    })
+    .await;
+    if !opts.quiet {
+        eprintln!(
+            "Closed after {}, {} messages received",
+            format_ms(started.elapsed()),
+            received
+        );
+    }
+    Ok(())
+}
+
+// ws:// 和 wss:// 分别按 http:// 和 https:// 解析，握手本身就是一次 HTTP 请求
+fn parse_ws_url(raw: &str) -> Result<Url, WsError> {
+    let http_url = if let Some(rest) = raw.strip_prefix("wss://") {
+        format!("https://{}", rest)
+    } else if let Some(rest) = raw.strip_prefix("ws://") {
+        format!("http://{}", rest)
+    } else if raw.contains("://") {
+        return Err(WsError::InvalidUrl(raw.to_string()));
+    } else {
+        format!("http://{}", raw)
+    };
+    Url::parse(&http_url).map_err(|_| WsError::InvalidUrl(raw.to_string()))
+}
+
+fn display_url(url: &Url) -> String {
+    let url = url.to_string();
+    match url.strip_prefix("https") {
+        Some(rest) => format!("wss{}", rest),
+        None => format!("ws{}", url.trim_start_matches("http")),
+    }
+}
+
+fn accept_key(key: &str) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(key.as_bytes());
+    hasher.update(WS_GUID.as_bytes());
+    STANDARD.encode(hasher.finalize())
+}
+
+fn format_ms(duration: Duration) -> String {
+    format!("{:.1} ms", duration.as_secs_f64() * 1000.0)
+}
+
+fn describe_close(code: Option<u16>, reason: &str) -> String {
+    let Some(code) = code else {
+        return String::new();
+    };
+    let name = match code {
+        1000 => "normal closure",
+        1001 => "going away",
+        1002 => "protocol error",
+        1003 => "unsupported data",
+        1007 => "invalid payload",
+        1008 => "policy violation",
+        1009 => "message too big",
+        1011 => "internal error",
+        1012 => "service restart",
+        1013 => "try again later",
+        _ => "",
+    };
+    let mut text = format!(": {}", code);
+    if !name.is_empty() {
+        text.push_str(&format!(" ({})", name));
+    }
+    if !reason.is_empty() {
+        text.push_str(&format!(" {}", reason));
+    }
+    text
+}
+
+fn print_message(message: &Incoming, elapsed: Option<Duration>) {
+    let prefix = elapsed
+        .map(|elapsed| format!("[+{}] ", format_ms(elapsed)))
+        .unwrap_or_default();
+    match message {
+        Incoming::Text(text) => println!("{}{}", prefix, text),
+        Incoming::Binary(data) => print!(
+            "{}[binary, {} bytes]\n{}",
+            prefix,
+            data.len(),
+            hex_dump(data, 0)
+        ),
+        _ => {}
+    }
+}
+
+async fn send_ping<W: AsyncWrite + Unpin>(writer: &mut W, started: Instant) -> io::Result<()> {
+    let sent = started.elapsed().as_nanos() as u64;
+    send_frame(writer, OP_PING, &sent.to_be_bytes()).await
+}
+
+async fn send_frame<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    opcode: u8,
+    payload: &[u8],
+) -> io::Result<()> {
+    writer
+        .write_all(&encode_frame(opcode, payload, rand::random()))
+        .await?;
+    writer.flush().await
+}
+
+// 客户端发出的帧必须加掩码，消息不分片
+fn encode_frame(opcode: u8, payload: &[u8], mask: [u8; 4]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(payload.len() + 14);
+    frame.push(0x80 | opcode);
+    match payload.len() {
+        len @ 0..=125 => frame.push(0x80 | len as u8),
+        len @ 126..=0xffff => {
+            frame.push(0x80 | 126);
+            frame.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            frame.push(0x80 | 127);
+            frame.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(&mask);
+    frame.extend(
+        payload
+            .iter()
+            .enumerate()
+            .map(|(i, byte)| byte ^ mask[i % 4]),
+    );
+    frame
+}
+
+// 连接在帧边界关闭时返回 None
+async fn read_frame<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Option<Frame>, WsError> {
+    let mut head = [0u8; 2];
+    match reader.read_exact(&mut head).await {
+        Ok(_) => {}
+        Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(error) => return Err(error.into()),
+    }
+    if head[0] & 0x70 != 0 {
+        return Err(WsError::Protocol("reserved bits set without an extension"));
+    }
+    let len = match head[1] & 0x7f {
+        126 => reader.read_u16().await? as u64,
+        127 => reader.read_u64().await?,
+        len => len as u64,
+    };
+    if len > MAX_MESSAGE_BYTES as u64 {
+        return Err(WsError::TooLarge);
+    }
+    // 服务端发来的帧不应加掩码，加了也照样解开
+    let mut mask = None;
+    if head[1] & 0x80 != 0 {
+        let mut key = [0u8; 4];
+        reader.read_exact(&mut key).await?;
+        mask = Some(key);
+    }
+    let mut payload = vec![0u8; len as usize];
+    reader.read_exact(&mut payload).await?;
+    if let Some(mask) = mask {
+        for (i, byte) in payload.iter_mut().enumerate() {
+            *byte ^= mask[i % 4];
+        }
+    }
+    Ok(Some(Frame {
+        fin: head[0] & 0x80 != 0,
+        opcode: head[0] & 0x0f,
+        payload,
+    }))
+}
+
+// 读到一条完整消息或一个控制帧为止；控制帧可以插在分片之间，partial 保存未完成的消息
+async fn read_message<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    partial: &mut Option<(u8, Vec<u8>)>,
+) -> Result<Incoming, WsError> {
+    loop {
+        let frame = read_frame(reader).await?.ok_or(WsError::Abnormal)?;
+        match frame.opcode {
+            OP_PING => return Ok(Incoming::Ping(frame.payload)),
+            OP_PONG => return Ok(Incoming::Pong(frame.payload)),
+            OP_CLOSE => {
+                let code = frame
+                    .payload
+                    .get(..2)
+                    .map(|code| u16::from_be_bytes([code[0], code[1]]));
+                let reason = frame.payload.get(2..).unwrap_or_default();
+                return Ok(Incoming::Close(
+                    code,
+                    String::from_utf8_lossy(reason).into_owned(),
+                ));
+            }
+            OP_TEXT | OP_BINARY if partial.is_none() => {
+                *partial = Some((frame.opcode, frame.payload));
+            }
+            OP_TEXT | OP_BINARY => {
+                return Err(WsError::Protocol(
+                    "new message started before the previous one finished",
+                ))
+            }
+            OP_CONTINUATION => {
+                let Some((_, data)) = partial.as_mut() else {
+                    return Err(WsError::Protocol("continuation frame without a message"));
+                };
+                if data.len() + frame.payload.len() > MAX_MESSAGE_BYTES {
+                    return Err(WsError::TooLarge);
+                }
+                data.extend_from_slice(&frame.payload);
+            }
+            _ => return Err(WsError::Protocol("unknown opcode")),
+        }
+        if frame.fin {
+            let (opcode, data) = partial.take().expect("set above");
+            return Ok(if opcode == OP_TEXT {
+                Incoming::Text(
+                    String::from_utf8(data)
+                        .map_err(|_| WsError::Protocol("text message is not valid UTF-8"))?,
+                )
+            } else {
+                Incoming::Binary(data)
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accept_key_and_url() {
+        // RFC 6455 第 1.3 节的示例
+        assert_eq!(
+            accept_key("dGhlIHNhbXBsZSBub25jZQ=="),
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+        );
+        let url = parse_ws_url("wss://example.com/chat?room=1").unwrap();
+        assert!(url.https);
+        assert_eq!((url.port, url.path.as_str()), (443, "/chat?room=1"));
+        assert_eq!(display_url(&url), "wss://example.com/chat?room=1");
+        let url = parse_ws_url("localhost:8080").unwrap();
+        assert_eq!(display_url(&url), "ws://localhost:8080/");
+        assert!(parse_ws_url("https://example.com").is_err());
+    }
+
+    #[test]
+    fn test_frame_round_trip() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let long = "x".repeat(300);
+            // 分片的文本消息中间插入一个 ping，后面跟一条加掩码的长消息和关闭帧
+            let mut data = vec![0x01, 3];
+            data.extend_from_slice(b"hel");
+            data.extend_from_slice(&[0x89, 1, b'p']);
+            data.extend_from_slice(&[0x80, 2]);
+            data.extend_from_slice(b"lo");
+            data.extend_from_slice(&encode_frame(OP_TEXT, long.as_bytes(), [1, 2, 3, 4]));
+            data.extend_from_slice(&encode_frame(OP_CLOSE, b"\x03\xe8bye", [9, 9, 9, 9]));
+
+            let mut reader = data.as_slice();
+            let mut partial = None;
+            let mut messages = Vec::new();
+            for _ in 0..4 {
+                messages.push(read_message(&mut reader, &mut partial).await.unwrap());
+            }
+            assert_eq!(
+                messages,
+                vec![
+                    Incoming::Ping(b"p".to_vec()),
+                    Incoming::Text("hello".into()),
+                    Incoming::Text(long),
+                    Incoming::Close(Some(1000), "bye".into()),
+                ]
+            );
+            assert!(matches!(
+                read_message(&mut reader, &mut partial).await,
+                Err(WsError::Abnormal)
+            ));
+        });
+    }
+}
@@ -0,0 +1,349 @@
+// Web 后台任务队列：耗时请求（例如对 /16 网段扫描）提交后立即返回任务 ID，由固定数量的工作线程执行，
+// HTTP 处理不再被阻塞。客户端轮询 GET /jobs/<id> 获取状态、进度和结果，DELETE /jobs/<id> 取消。
+//
+// 排队数有上限，满时拒绝提交；已结束的任务超过保留时长或保留数量后淘汰，结果随之释放。
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chrono::{SecondsFormat, Utc};
+use serde::Serialize;
+
+use crate::utils::progress::{Progress, ProgressSnapshot};
+
+type Task = Box<dyn FnOnce(Arc<Progress>) -> Result<serde_json::Value, String> + Send>;
+
+#[derive(Debug, Clone, Copy)]
+pub struct JobLimits {
+    // 同时执行的任务数
+    pub workers: usize,
+    // 等待执行的任务数上限
+    pub max_queued: usize,
+    // 保留结果的已结束任务数上限
+    pub max_retained: usize,
+    // 已结束任务的保留时长
+    pub retention: Duration,
+}
+
+impl Default for JobLimits {
+    fn default() -> Self {
+        Self {
+            workers: 2,
+            max_queued: 16,
+            max_retained: 100,
+            retention: Duration::from_secs(3600),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+struct Job {
+    id: String,
+    kind: &'static str,
+    created_at: String,
+    status: JobStatus,
+    progress: Arc<Progress>,
+    started: Option<Instant>,
+    finished: Option<Instant>,
+    result: Option<serde_json::Value>,
+    error: Option<String>,
+}
+
+// 对外返回的任务状态；列表中不带结果，避免一次返回所有扫描结果
+#[derive(Debug, Serialize)]
+pub struct JobView {
+    pub id: String,
+    pub kind: &'static str,
+    pub status: JobStatus,
+    pub created_at: String,
+    pub progress: ProgressSnapshot,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub elapsed_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Job {
+    fn view(&self, with_result: bool) -> JobView {
+        let elapsed = self.started.map(|started| {
+            self.finished
+                .map_or_else(|| started.elapsed(), |finished| finished - started)
+        });
+        JobView {
+            id: self.id.clone(),
+            kind: self.kind,
+            status: self.status,
+            created_at: self.created_at.clone(),
+            progress: self.progress.snapshot(),
+            elapsed_ms: elapsed.map(|elapsed| elapsed.as_millis() as u64),
+            result: self.result.clone().filter(|_| with_result),
+            error: self.error.clone(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct QueueState {
+    // 按提交顺序排列
+    jobs: Vec<Job>,
+    pending: VecDeque<(String, Task)>,
+}
+
+pub struct JobQueue {
+    state: Mutex<QueueState>,
+    ready: Condvar,
+    limits: JobLimits,
+}
+
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum JobError {
+    #[error("job queue is full ({0} jobs waiting), try again later")]
+    QueueFull(usize),
+    #[error("job {0} not found")]
+    NotFound(String),
+    #[error("job {0} has already finished")]
+    Finished(String),
+}
+
+impl JobQueue {
+    // 创建队列并启动工作线程
+    pub fn start(limits: JobLimits) -> Arc<Self> {
+        let queue = Arc::new(Self {
+            state: Mutex::new(QueueState::default()),
+            ready: Condvar::new(),
+            limits,
+        });
+        for _ in 0..limits.workers.max(1) {
+            let queue = Arc::clone(&queue);
+            thread::spawn(move || queue.work());
+        }
+        queue
+    }
+
+    pub fn submit<F>(&self, kind: &'static str, task: F) -> Result<JobView, JobError>
+    where
+        F: FnOnce(Arc<Progress>) -> Result<serde_json::Value, String> + Send + 'static,
+    {
+        let mut state = self.lock();
+        self.prune(&mut state);
+        if state.pending.len() >= self.limits.max_queued {
+            return Err(JobError::QueueFull(state.pending.len()));
+        }
+        let job = Job {
+            id: format!("{:016x}", rand::random::<u64>()),
+            kind,
+            created_at: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+            status: JobStatus::Queued,
+            progress: Arc::default(),
+            started: None,
+            finished: None,
+            result: None,
+            error: None,
+        };
+        let view = job.view(false);
+        state.pending.push_back((job.id.clone(), Box::new(task)));
+        state.jobs.push(job);
+        self.ready.notify_one();
+        Ok(view)
+    }
+
+    pub fn get(&self, id: &str) -> Result<JobView, JobError> {
+        let mut state = self.lock();
+        self.prune(&mut state);
+        state
+            .jobs
+            .iter()
+            .find(|job| job.id == id)
+            .map(|job| job.view(true))
+            .ok_or_else(|| JobError::NotFound(id.to_string()))
+    }
+
+    pub fn list(&self) -> Vec<JobView> {
+        let mut state = self.lock();
+        self.prune(&mut state);
+        state.jobs.iter().map(|job| job.view(false)).collect()
+    }
+
+    // 排队中的任务直接移出队列；执行中的任务只设置取消标记，由执行方尽快停止
+    pub fn cancel(&self, id: &str) -> Result<JobView, JobError> {
+        let mut state = self.lock();
+        let QueueState { jobs, pending } = &mut *state;
+        let job = jobs
+            .iter_mut()
+            .find(|job| job.id == id)
+            .ok_or_else(|| JobError::NotFound(id.to_string()))?;
+        match job.status {
+            JobStatus::Queued => {
+                pending.retain(|(pending_id, _)| pending_id != id);
+                job.status = JobStatus::Cancelled;
+                job.finished = Some(Instant::now());
+            }
+            JobStatus::Running => job.progress.cancel(),
+            _ => return Err(JobError::Finished(id.to_string())),
+        }
+        Ok(job.view(false))
+    }
+
+    fn work(&self) {
+        loop {
+            let (id, task, progress) = {
+                let mut state = self.lock();
+                let (id, task) = loop {
+                    if let Some(next) = state.pending.pop_front() {
+                        break next;
+                    }
+                    state = self
+                        .ready
+                        .wait(state)
+                        .unwrap_or_else(PoisonError::into_inner);
+                };
+                let Some(job) = state.jobs.iter_mut().find(|job| job.id == id) else {
+                    continue;
+                };
+                job.status = JobStatus::Running;
+                job.started = Some(Instant::now());
+                (id, task, Arc::clone(&job.progress))
+            };
+
+            let outcome = task(Arc::clone(&progress));
+
+            let mut state = self.lock();
+            if let Some(job) = state.jobs.iter_mut().find(|job| job.id == id) {
+                job.finished = Some(Instant::now());
+                match outcome {
+                    _ if progress.is_cancelled() => job.status = JobStatus::Cancelled,
+                    Ok(result) => {
+                        job.status = JobStatus::Succeeded;
+                        job.result = Some(result);
+                    }
+                    Err(error) => {
+                        job.status = JobStatus::Failed;
+                        job.error = Some(error);
+                    }
+                }
+            }
+            self.prune(&mut state);
+        }
+    }
+
+    // 先淘汰超过保留时长的已结束任务，数量仍超限时再淘汰最早结束的
+    fn prune(&self, state: &mut QueueState) {
+        let retention = self.limits.retention;
+        state
+            .jobs
+            .retain(|job| job.finished.is_none_or(|at| at.elapsed() < retention));
+        let mut finished: Vec<Instant> = state.jobs.iter().filter_map(|job| job.finished).collect();
+        if finished.len() <= self.limits.max_retained {
+            return;
+        }
+        finished.sort();
+        let cutoff = finished[finished.len() - self.limits.max_retained - 1];
+        state
+            .jobs
+            .retain(|job| job.finished.is_none_or(|at| at > cutoff));
+    }
+
+    fn lock(&self) -> MutexGuard<'_, QueueState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    fn wait_for(queue: &JobQueue, id: &str, status: JobStatus) -> JobView {
+        for _ in 0..200 {
+            let view = queue.get(id).unwrap();
+            if view.status == status {
+                return view;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        panic!("job {} never reached {:?}", id, status);
+    }
+
+    #[test]
+    fn test_jobs_run_queue_and_cancel() {
+        let queue = JobQueue::start(JobLimits {
+            workers: 1,
+            max_queued: 1,
+            max_retained: 1,
+            ..JobLimits::default()
+        });
+
+        // 第一个任务占住唯一的工作线程，直到收到信号或被取消
+        let (release, wait) = mpsc::channel::<()>();
+        let running = queue
+            .submit("test", move |progress| {
+                progress.set_total(2);
+                progress.advance(1);
+                while !progress.is_cancelled() {
+                    if wait.recv_timeout(Duration::from_millis(5)).is_ok() {
+                        progress.advance(1);
+                        return Ok(serde_json::json!({ "answer": 42 }));
+                    }
+                }
+                Err("stopped".into())
+            })
+            .unwrap();
+        let view = wait_for(&queue, &running.id, JobStatus::Running);
+        assert_eq!(view.progress, ProgressSnapshot { done: 1, total: 2 });
+
+        let queued = queue
+            .submit("test", |_| Ok(serde_json::Value::Null))
+            .unwrap();
+        assert_eq!(queued.status, JobStatus::Queued);
+        assert_eq!(
+            queue
+                .submit("test", |_| Ok(serde_json::Value::Null))
+                .unwrap_err(),
+            JobError::QueueFull(1)
+        );
+        assert_eq!(
+            queue.cancel(&queued.id).unwrap().status,
+            JobStatus::Cancelled
+        );
+
+        release.send(()).unwrap();
+        let done = wait_for(&queue, &running.id, JobStatus::Succeeded);
+        assert_eq!(done.result.unwrap()["answer"], 42);
+        assert_eq!(
+            queue.cancel(&running.id).unwrap_err(),
+            JobError::Finished(running.id.clone())
+        );
+        // 只保留 1 个已结束任务，先结束的已取消任务被淘汰
+        assert_eq!(
+            queue.get(&queued.id).unwrap_err(),
+            JobError::NotFound(queued.id.clone())
+        );
+
+        let cancelled = queue
+            .submit("test", |progress| {
+                while !progress.is_cancelled() {
+                    thread::sleep(Duration::from_millis(5));
+                }
+                Ok(serde_json::Value::Null)
+            })
+            .unwrap();
+        wait_for(&queue, &cancelled.id, JobStatus::Running);
+        queue.cancel(&cancelled.id).unwrap();
+        let view = wait_for(&queue, &cancelled.id, JobStatus::Cancelled);
+        assert!(view.result.is_none());
+        assert_eq!(queue.list().len(), 1);
+    }
+}
@@ -1,3 +1,7 @@
 pub mod commands;
+pub mod jobs;
 pub mod utils;
 pub mod web;
+
+// 供其他 Rust 程序直接调用的端口扫描接口：rtoolkit::portscan::scan
+pub use commands::portscan;
@@ -0,0 +1,119 @@
+// 读屏友好的输出模式（--a11y）：不画进度条、二维码和按空格对齐的表格，交互选择改为输入编号，
+// 表格的每一行输出为一行“标签: 值”，状态用完整的单词表示，不依赖颜色、符号或列位置。
+
+use std::fmt::Display;
+use std::io::{self, BufRead};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+// 把一行表格拼成 "name: api, status: passed, time: 12 ms"，空值的字段省略
+pub fn line(fields: &[(&str, &dyn Display)]) -> String {
+    fields
+        .iter()
+        .map(|(label, value)| (label, value.to_string()))
+        .filter(|(_, value)| !value.is_empty())
+        .map(|(label, value)| format!("{}: {}", label, value))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// --a11y 下的选择：按编号列出选项，读取一行输入，读屏软件不需要跟踪方向键移动的高亮行。
+// 返回 None 表示取消
+pub fn pick_numbered(
+    title: &str,
+    labels: &[String],
+    multiple: bool,
+) -> io::Result<Option<Vec<usize>>> {
+    eprintln!("{}:", title);
+    for (index, label) in labels.iter().enumerate() {
+        eprintln!("  {}. {}", index + 1, label);
+    }
+    let prompt = if multiple {
+        "Enter numbers separated by commas, or press Enter for all; q cancels: "
+    } else {
+        "Enter one number, or press Enter for 1; q cancels: "
+    };
+    let mut stdin = io::stdin().lock();
+    loop {
+        eprint!("{}", prompt);
+        let mut input = String::new();
+        if stdin.read_line(&mut input)? == 0 {
+            return Ok(None);
+        }
+        match parse_choice(&input, labels.len(), multiple) {
+            Ok(chosen) => return Ok(chosen),
+            Err(message) => eprintln!("{}", message),
+        }
+    }
+}
+
+// 解析编号输入，编号从 1 开始；空输入在多选时选中全部、单选时选第一项
+fn parse_choice(input: &str, count: usize, multiple: bool) -> Result<Option<Vec<usize>>, String> {
+    let input = input.trim();
+    if input.eq_ignore_ascii_case("q") {
+        return Ok(None);
+    }
+    if input.is_empty() {
+        return Ok(Some(if multiple {
+            (0..count).collect()
+        } else {
+            vec![0]
+        }));
+    }
+    let mut chosen = Vec::new();
+    for item in input
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+    {
+        match item.parse::<usize>() {
+            Ok(number) if (1..=count).contains(&number) => {
+                if !chosen.contains(&(number - 1)) {
+                    chosen.push(number - 1);
+                }
+            }
+            _ => return Err(format!("Not a number between 1 and {}: {}", count, item)),
+        }
+    }
+    if !multiple && chosen.len() != 1 {
+        return Err("Enter exactly one number".to_string());
+    }
+    Ok(Some(chosen))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_labels_fields() {
+        assert_eq!(
+            line(&[
+                ("host", &"10.0.0.1"),
+                ("detail", &""),
+                ("latency", &"1.50 ms")
+            ]),
+            "host: 10.0.0.1, latency: 1.50 ms"
+        );
+    }
+
+    #[test]
+    fn test_parse_numbered_choice() {
+        assert_eq!(parse_choice("3, 1,3\n", 3, true), Ok(Some(vec![2, 0])));
+        assert_eq!(parse_choice("", 3, true), Ok(Some(vec![0, 1, 2])));
+        assert_eq!(parse_choice("\n", 3, false), Ok(Some(vec![0])));
+        assert_eq!(parse_choice("Q", 3, false), Ok(None));
+        assert!(parse_choice("4", 3, true).is_err());
+        assert!(parse_choice("1,2", 3, false).is_err());
+        assert!(parse_choice("web", 3, false).is_err());
+    }
+}
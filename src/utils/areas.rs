@@ -8,6 +8,7 @@ use std::error::Error;
 const PROVINCES_CSV: &str = include_str!("../../data/provinces.csv");
 const CITIES_CSV: &str = include_str!("../../data/cities.csv");
 const AREAS_CSV: &str = include_str!("../../data/areas.csv");
+const STREETS_CSV: &str = include_str!("../../data/streets.csv");
 
 // 区
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -39,6 +40,16 @@ pub struct Province {
     pub name: String,
 }
 
+// 街道
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Street {
+    // code,name,areaCode,provinceCode,cityCode
+    pub code: String,
+    pub name: String,
+    #[serde(rename = "areaCode")]
+    pub area_code: String,
+}
+
 // 通用的区域类型枚举
 #[derive(Debug, Clone)]
 pub enum AreaType {
@@ -65,6 +76,8 @@ pub struct RegionCache {
 
 // 全局区域数据缓存
 static REGION_CACHE: OnceLock<RegionCache> = OnceLock::new();
+// 街道数据较大，单独按需加载，按区县代码分组
+static STREET_CACHE: OnceLock<HashMap<String, Vec<Street>>> = OnceLock::new();
 
 impl RegionCache {
     pub fn new() -> Result<Self, Box<dyn Error>> {
@@ -369,6 +382,11 @@ pub fn get_region_name(code: &str) -> Option<String> {
     get_area_cache().get_region(code).map(|r| r.name.clone())
 }
 
+// 根据代码查找省份名称
+pub fn get_province_name(code: &str) -> Option<String> {
+    get_area_cache().get_province(code).map(|p| p.name.clone())
+}
+
 // 获取所有区域数量
 pub fn region_count() -> usize {
     get_area_cache().areas.len()
@@ -399,3 +417,34 @@ pub fn get_cities_by_province(province_code: &str) -> Vec<City> {
 pub fn get_regions_by_city(city_code: &str) -> Vec<Area> {
     get_area_cache().get_regions_by_city(city_code).to_vec()
 }
+
+// 获取全局街道缓存
+fn get_street_cache() -> &'static HashMap<String, Vec<Street>> {
+    STREET_CACHE.get_or_init(|| {
+        let mut streets_by_area: HashMap<String, Vec<Street>> = HashMap::new();
+        let mut rdr = csv::Reader::from_reader(STREETS_CSV.as_bytes());
+        for result in rdr.deserialize::<Street>() {
+            match result {
+                Ok(street) => streets_by_area
+                    .entry(street.area_code.clone())
+                    .or_default()
+                    .push(street),
+                Err(e) => {
+                    eprintln!("Failed to load street data: {}", e);
+                    break;
+                }
+            }
+        }
+        streets_by_area
+    })
+}
+
+// 在指定区县内随机获取街道
+pub fn random_street_in_region(region_code: &str) -> Option<Street> {
+    let streets = get_street_cache().get(region_code)?;
+    if streets.is_empty() {
+        return None;
+    }
+    let index = rng().random_range(0..streets.len());
+    Some(streets[index].clone())
+}
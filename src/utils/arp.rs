@@ -0,0 +1,181 @@
+// 以太网上的 IPv4 ARP 请求和应答（RFC 826），arpscan 使用。
+// 发送原始 ARP 需要 Linux 的 AF_PACKET 套接字和 root / CAP_NET_RAW，内核负责补上以太网头；
+// 其他系统由调用方让内核自己解析地址，再读取 netif::neighbors() 的 ARP 缓存。
+
+use std::net::Ipv4Addr;
+
+const HTYPE_ETHERNET: u16 = 1;
+const PTYPE_IPV4: u16 = 0x0800;
+const OP_REQUEST: u16 = 1;
+const OP_REPLY: u16 = 2;
+// 以太网类型 ARP，同时是 AF_PACKET 套接字的协议号
+#[cfg(target_os = "linux")]
+const ETH_P_ARP: u16 = 0x0806;
+
+pub const PACKET_LEN: usize = 28;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArpReply {
+    pub sender_mac: [u8; 6],
+    pub sender_ip: Ipv4Addr,
+    pub target_ip: Ipv4Addr,
+}
+
+// 询问 target_ip 的 MAC 地址；目标 MAC 填 0，由以太网广播送达
+pub fn request(sender_mac: [u8; 6], sender_ip: Ipv4Addr, target_ip: Ipv4Addr) -> [u8; PACKET_LEN] {
+    let mut packet = [0u8; PACKET_LEN];
+    packet[0..2].copy_from_slice(&HTYPE_ETHERNET.to_be_bytes());
+    packet[2..4].copy_from_slice(&PTYPE_IPV4.to_be_bytes());
+    packet[4] = 6;
+    packet[5] = 4;
+    packet[6..8].copy_from_slice(&OP_REQUEST.to_be_bytes());
+    packet[8..14].copy_from_slice(&sender_mac);
+    packet[14..18].copy_from_slice(&sender_ip.octets());
+    packet[24..28].copy_from_slice(&target_ip.octets());
+    packet
+}
+
+// 只接受以太网 / IPv4 的应答，其他 ARP 报文（包括别人的请求）返回 None
+pub fn parse_reply(packet: &[u8]) -> Option<ArpReply> {
+    if packet.len() < PACKET_LEN
+        || u16::from_be_bytes([packet[0], packet[1]]) != HTYPE_ETHERNET
+        || u16::from_be_bytes([packet[2], packet[3]]) != PTYPE_IPV4
+        || packet[4] != 6
+        || packet[5] != 4
+        || u16::from_be_bytes([packet[6], packet[7]]) != OP_REPLY
+    {
+        return None;
+    }
+    let ip = |offset: usize| {
+        Ipv4Addr::new(
+            packet[offset],
+            packet[offset + 1],
+            packet[offset + 2],
+            packet[offset + 3],
+        )
+    };
+    Some(ArpReply {
+        sender_mac: packet[8..14].try_into().ok()?,
+        sender_ip: ip(14),
+        target_ip: ip(24),
+    })
+}
+
+#[cfg(target_os = "linux")]
+pub use self::linux::ArpSocket;
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::ffi::CString;
+    use std::io;
+    use std::mem::MaybeUninit;
+    use std::time::Duration;
+
+    use socket2::{Domain, Protocol, SockAddr, SockAddrStorage, Socket, Type};
+
+    use super::ETH_P_ARP;
+
+    // 绑定到一块网卡的 ARP 套接字（SOCK_DGRAM，收发的都是不含以太网头的 ARP 报文）
+    pub struct ArpSocket {
+        socket: Socket,
+        ifindex: i32,
+    }
+
+    impl ArpSocket {
+        // 没有权限时返回 PermissionDenied，网卡不存在时返回 NotFound
+        pub fn open(interface: &str) -> io::Result<Self> {
+            let name = CString::new(interface).map_err(|_| {
+                io::Error::new(io::ErrorKind::InvalidInput, "invalid interface name")
+            })?;
+            let ifindex = unsafe { libc::if_nametoindex(name.as_ptr()) } as i32;
+            if ifindex == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("no interface named {}", interface),
+                ));
+            }
+            let protocol = Protocol::from(i32::from(ETH_P_ARP.to_be()));
+            let socket = Socket::new(Domain::PACKET, Type::DGRAM, Some(protocol))?;
+            socket.bind(&link_addr(ifindex, None))?;
+            Ok(Self { socket, ifindex })
+        }
+
+        pub fn send_broadcast(&self, packet: &[u8]) -> io::Result<()> {
+            self.socket
+                .send_to(packet, &link_addr(self.ifindex, Some([0xff; 6])))?;
+            Ok(())
+        }
+
+        // 等待下一个 ARP 报文，超时返回 None
+        pub fn recv(&self, buf: &mut [u8], timeout: Duration) -> io::Result<Option<usize>> {
+            self.socket
+                .set_read_timeout(Some(timeout.max(Duration::from_millis(1))))?;
+            // SAFETY: recv 只会写入缓冲区，MaybeUninit<u8> 与 u8 布局相同
+            let uninit = unsafe { &mut *(buf as *mut [u8] as *mut [MaybeUninit<u8>]) };
+            match self.socket.recv(uninit) {
+                Ok(n) => Ok(Some(n)),
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                    ) =>
+                {
+                    Ok(None)
+                }
+                Err(e) => Err(e),
+            }
+        }
+    }
+
+    // sockaddr_ll：绑定时只需网卡序号，发送时再带上目标 MAC
+    fn link_addr(ifindex: i32, mac: Option<[u8; 6]>) -> SockAddr {
+        let mut storage = SockAddrStorage::zeroed();
+        // SAFETY: sockaddr_ll 是本平台的 sockaddr 类型之一，且不大于 sockaddr_storage
+        let addr = unsafe { storage.view_as::<libc::sockaddr_ll>() };
+        addr.sll_family = libc::AF_PACKET as u16;
+        addr.sll_protocol = ETH_P_ARP.to_be();
+        addr.sll_ifindex = ifindex;
+        if let Some(mac) = mac {
+            addr.sll_halen = 6;
+            addr.sll_addr[..6].copy_from_slice(&mac);
+        }
+        let len = std::mem::size_of::<libc::sockaddr_ll>() as libc::socklen_t;
+        // SAFETY: storage 中是按 len 初始化好的 sockaddr_ll
+        unsafe { SockAddr::new(storage, len) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_and_reply() {
+        let mac = [0x52, 0x54, 0x00, 0x12, 0x34, 0x56];
+        let packet = request(
+            mac,
+            Ipv4Addr::new(192, 168, 1, 23),
+            Ipv4Addr::new(192, 168, 1, 1),
+        );
+        assert_eq!(&packet[..8], &[0, 1, 8, 0, 6, 4, 0, 1]);
+        // 请求不是应答
+        assert_eq!(parse_reply(&packet), None);
+
+        // 把请求改写成 192.168.1.1 的应答，以太网帧可能带尾部填充
+        let mut reply = packet.to_vec();
+        reply[7] = 2;
+        reply[8..14].copy_from_slice(&[0xa0, 0xb1, 0xc2, 0xd3, 0xe4, 0xf5]);
+        reply[14..18].copy_from_slice(&[192, 168, 1, 1]);
+        reply[24..28].copy_from_slice(&[192, 168, 1, 23]);
+        reply.extend([0u8; 18]);
+        assert_eq!(
+            parse_reply(&reply),
+            Some(ArpReply {
+                sender_mac: [0xa0, 0xb1, 0xc2, 0xd3, 0xe4, 0xf5],
+                sender_ip: Ipv4Addr::new(192, 168, 1, 1),
+                target_ip: Ipv4Addr::new(192, 168, 1, 23),
+            })
+        );
+        assert_eq!(parse_reply(&reply[..20]), None);
+    }
+}
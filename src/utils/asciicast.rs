@@ -0,0 +1,236 @@
+// asciicast 录制文件（asciinema 使用的格式）。v2 首行为 JSON 头，之后每行一个 [秒数, 类型, 数据] 事件；
+// 回放时也接受 v1：整个文件是一个 JSON 对象，stdout 为 [距上一帧的秒数, 数据] 数组。
+// 只处理输出事件（"o"），输入、窗口大小变化等事件在回放时忽略。
+
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(thiserror::Error, Debug)]
+pub enum CastError {
+    #[error("line {line}: {source}")]
+    Json {
+        line: usize,
+        source: serde_json::Error,
+    },
+    #[error("empty recording")]
+    Empty,
+    #[error("unsupported asciicast version {0}, expected 1 or 2")]
+    Version(u64),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Header {
+    pub version: u8,
+    pub width: u16,
+    pub height: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+}
+
+// 一次终端输出，time 为距录制开始的秒数
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+    pub time: f64,
+    pub data: String,
+}
+
+// 逐块写入终端输出；一次读到的字节可能截断多字节字符，未完整的尾部留到下一块
+pub struct CastWriter<W: Write> {
+    out: W,
+    started: Instant,
+    pending: Vec<u8>,
+}
+
+impl<W: Write> CastWriter<W> {
+    pub fn new(mut out: W, header: &Header) -> io::Result<Self> {
+        serde_json::to_writer(&mut out, header)?;
+        writeln!(out)?;
+        Ok(Self {
+            out,
+            started: Instant::now(),
+            pending: Vec::new(),
+        })
+    }
+
+    pub fn output(&mut self, bytes: &[u8]) -> io::Result<()> {
+        let time = self.started.elapsed().as_secs_f64();
+        self.output_at(time, bytes)
+    }
+
+    fn output_at(&mut self, time: f64, bytes: &[u8]) -> io::Result<()> {
+        self.pending.extend_from_slice(bytes);
+        let mut text = String::new();
+        loop {
+            match std::str::from_utf8(&self.pending) {
+                Ok(valid) => {
+                    text.push_str(valid);
+                    self.pending.clear();
+                    break;
+                }
+                Err(error) => {
+                    let valid = error.valid_up_to();
+                    text.push_str(&String::from_utf8_lossy(&self.pending[..valid]));
+                    match error.error_len() {
+                        // 不完整的字符，等下一块
+                        None => {
+                            self.pending.drain(..valid);
+                            break;
+                        }
+                        Some(len) => {
+                            text.push(char::REPLACEMENT_CHARACTER);
+                            self.pending.drain(..valid + len);
+                        }
+                    }
+                }
+            }
+        }
+        if text.is_empty() {
+            return Ok(());
+        }
+        // 保留到微秒，与 asciinema 一致
+        let time = (time * 1_000_000.0).round() / 1_000_000.0;
+        serde_json::to_writer(&mut self.out, &(time, "o", text))?;
+        writeln!(self.out)
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        if !self.pending.is_empty() {
+            let time = self.started.elapsed().as_secs_f64();
+            let rest = String::from_utf8_lossy(&std::mem::take(&mut self.pending)).into_owned();
+            self.output_at(time, rest.as_bytes())?;
+        }
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+pub fn parse(text: &str) -> Result<(Header, Vec<Event>), CastError> {
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty());
+    let (_, first) = lines.next().ok_or(CastError::Empty)?;
+    let json_error = |line: usize| move |source| CastError::Json { line, source };
+    // v1 是跨多行的单个 JSON 对象，首行解析失败时按整个文件解析
+    let head: Value = match serde_json::from_str(first) {
+        Ok(head) => head,
+        Err(_) => return parse_v1(serde_json::from_str(text).map_err(json_error(1))?),
+    };
+    match head.get("version").and_then(Value::as_u64) {
+        Some(1) => parse_v1(head),
+        Some(2) => {
+            let header: Header = serde_json::from_value(head).map_err(json_error(1))?;
+            let mut events = Vec::new();
+            for (index, line) in lines {
+                let (time, kind, data): (f64, String, String) =
+                    serde_json::from_str(line).map_err(json_error(index + 1))?;
+                if kind == "o" {
+                    events.push(Event { time, data });
+                }
+            }
+            Ok((header, events))
+        }
+        version => Err(CastError::Version(version.unwrap_or_default())),
+    }
+}
+
+fn parse_v1(value: Value) -> Result<(Header, Vec<Event>), CastError> {
+    #[derive(Deserialize)]
+    struct V1 {
+        width: u16,
+        height: u16,
+        #[serde(default)]
+        command: Option<String>,
+        #[serde(default)]
+        env: BTreeMap<String, String>,
+        stdout: Vec<(f64, String)>,
+    }
+    let version = value.get("version").and_then(Value::as_u64);
+    if version != Some(1) {
+        return Err(CastError::Version(version.unwrap_or_default()));
+    }
+    let v1: V1 =
+        serde_json::from_value(value).map_err(|source| CastError::Json { line: 1, source })?;
+    let mut time = 0.0;
+    let events = v1
+        .stdout
+        .into_iter()
+        .map(|(delay, data)| {
+            time += delay;
+            Event { time, data }
+        })
+        .collect();
+    let header = Header {
+        version: 1,
+        width: v1.width,
+        height: v1.height,
+        timestamp: None,
+        command: v1.command,
+        env: v1.env,
+    };
+    Ok((header, events))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_and_parse_cast() {
+        let header = Header {
+            version: 2,
+            width: 100,
+            height: 30,
+            timestamp: Some(1_700_000_000),
+            command: Some("rtoolkit discover -t 10.0.0.0/24 --pick".into()),
+            env: BTreeMap::from([("TERM".to_string(), "xterm-256color".to_string())]),
+        };
+        let mut writer = CastWriter::new(Vec::new(), &header).unwrap();
+        let text = "主机\r\n".as_bytes();
+        // “主”的 3 个字节被拆到两次读取中
+        writer.output_at(0.1, &text[..2]).unwrap();
+        writer.output_at(0.25, &text[2..]).unwrap();
+        writer.output_at(1.5, b"\xff\x1b[0m").unwrap();
+        let cast = String::from_utf8(writer.finish().unwrap()).unwrap();
+        let lines: Vec<&str> = cast.lines().collect();
+        assert_eq!(lines[1], r#"[0.25,"o","主机\r\n"]"#);
+        assert_eq!(lines[2], r#"[1.5,"o","�\u001b[0m"]"#);
+
+        let (parsed, events) = parse(&format!("{}[2.0,\"i\",\"q\"]\n", cast)).unwrap();
+        assert_eq!(parsed, header);
+        assert_eq!(
+            events,
+            [
+                Event {
+                    time: 0.25,
+                    data: "主机\r\n".into()
+                },
+                Event {
+                    time: 1.5,
+                    data: "\u{fffd}\x1b[0m".into()
+                },
+            ]
+        );
+
+        let v1 = r#"{
+  "version": 1, "width": 80, "height": 24,
+  "stdout": [[0.5, "a"], [1.0, "b"]]
+}"#;
+        let (header, events) = parse(v1).unwrap();
+        assert_eq!((header.version, header.width), (1, 80));
+        assert_eq!(events[1].time, 1.5);
+        assert!(matches!(
+            parse(r#"{"version": 3}"#),
+            Err(CastError::Version(3))
+        ));
+        assert!(matches!(parse("\n"), Err(CastError::Empty)));
+    }
+}
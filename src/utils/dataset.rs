@@ -0,0 +1,94 @@
+// 读取已有的数据集文件（mutate、datadiff 的输入），按扩展名识别格式：
+// .json 为对象数组，.jsonl / .ndjson 为每行一个对象，其它按首行为表头的 CSV 处理。
+// CSV 的单元格一律读成字符串。
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde_json::{Map, Value};
+
+pub type Record = Map<String, Value>;
+
+#[derive(thiserror::Error, Debug)]
+pub enum DatasetError {
+    #[error("failed to read {path}: {source}")]
+    Read { path: PathBuf, source: io::Error },
+    #[error("{path}: {source}")]
+    Json {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    #[error("{path}: {source}")]
+    Csv { path: PathBuf, source: csv::Error },
+    #[error("{path}: record {index} is not an object")]
+    NotAnObject { path: PathBuf, index: usize },
+}
+
+pub fn read_records(path: &Path) -> Result<Vec<Record>, DatasetError> {
+    let text = fs::read_to_string(path).map_err(|source| DatasetError::Read {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let json_error = |source| DatasetError::Json {
+        path: path.to_path_buf(),
+        source,
+    };
+    let values: Vec<Value> = match extension.as_str() {
+        "json" => serde_json::from_str(&text).map_err(json_error)?,
+        "jsonl" | "ndjson" => text
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()
+            .map_err(json_error)?,
+        _ => {
+            return parse_csv(&text).map_err(|source| DatasetError::Csv {
+                path: path.to_path_buf(),
+                source,
+            })
+        }
+    };
+    values
+        .into_iter()
+        .enumerate()
+        .map(|(index, value)| match value {
+            Value::Object(object) => Ok(object),
+            _ => Err(DatasetError::NotAnObject {
+                path: path.to_path_buf(),
+                index: index + 1,
+            }),
+        })
+        .collect()
+}
+
+fn parse_csv(text: &str) -> Result<Vec<Record>, csv::Error> {
+    let mut reader = csv::Reader::from_reader(text.as_bytes());
+    let header = reader.headers()?.clone();
+    let mut records = Vec::new();
+    for row in reader.records() {
+        let row = row?;
+        records.push(
+            header
+                .iter()
+                .zip(row.iter())
+                .map(|(key, value)| (key.to_string(), Value::String(value.to_string())))
+                .collect(),
+        );
+    }
+    Ok(records)
+}
+
+// 字段值的文本形式：字符串原样返回，null 为空串，其它值按 JSON 表示
+pub fn text_of(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
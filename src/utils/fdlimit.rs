@@ -0,0 +1,75 @@
+// 文件描述符上限：每个在途的连接探测占用一个描述符，并发数超过进程的软限制时
+// 连接会大量以 EMFILE（Too many open files）失败，探测结果全被误判为 filtered
+
+use std::io;
+
+// 软限制低于 wanted 时尝试提高到 wanted（不超过硬限制），返回最终生效的软限制；
+// 无法读取时为 None，调用方按原并发数继续
+#[cfg(unix)]
+pub fn raise_open_files(wanted: u64) -> Option<u64> {
+    let mut limit = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    if unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, &mut limit) } != 0 {
+        return None;
+    }
+    if limit.rlim_cur == libc::RLIM_INFINITY {
+        return Some(u64::MAX);
+    }
+    let wanted = wanted as libc::rlim_t;
+    let current: libc::rlim_t = limit.rlim_cur;
+    if current >= wanted {
+        return Some(current as u64);
+    }
+    let target: libc::rlim_t = if limit.rlim_max == libc::RLIM_INFINITY {
+        wanted
+    } else {
+        wanted.min(limit.rlim_max)
+    };
+    let raised = libc::rlimit {
+        rlim_cur: target,
+        rlim_max: limit.rlim_max,
+    };
+    if unsafe { libc::setrlimit(libc::RLIMIT_NOFILE, &raised) } == 0 {
+        Some(target as u64)
+    } else {
+        Some(current as u64)
+    }
+}
+
+#[cfg(not(unix))]
+pub fn raise_open_files(_wanted: u64) -> Option<u64> {
+    None
+}
+
+// 本机资源耗尽导致的失败：描述符、内核缓冲区或临时端口用完，探测并没有真正发出
+#[cfg(unix)]
+pub fn is_exhausted(err: &io::Error) -> bool {
+    matches!(
+        err.raw_os_error(),
+        Some(libc::EMFILE | libc::ENFILE | libc::ENOBUFS | libc::ENOMEM | libc::EADDRNOTAVAIL)
+    )
+}
+
+// WSAEMFILE、WSAENOBUFS、WSAEADDRNOTAVAIL
+#[cfg(not(unix))]
+pub fn is_exhausted(err: &io::Error) -> bool {
+    matches!(err.raw_os_error(), Some(10024 | 10055 | 10049))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_open_file_limit() {
+        #[cfg(unix)]
+        {
+            assert!(raise_open_files(16).is_some_and(|limit| limit >= 16));
+            assert!(is_exhausted(&io::Error::from_raw_os_error(libc::EMFILE)));
+        }
+        assert!(!is_exhausted(&io::ErrorKind::ConnectionRefused.into()));
+        assert!(!is_exhausted(&io::ErrorKind::TimedOut.into()));
+    }
+}
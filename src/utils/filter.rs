@@ -0,0 +1,485 @@
+// 结构化输出的记录过滤表达式（全局 `--where`）。
+//
+// 表达式针对每条记录序列化后的 JSON 字段求值，例如：
+// `state == "open" && latency_ms < 50`、`status >= 500 || path contains "/admin"`。
+// 支持 `== != < <= > >=`、`contains`、`in [..]`、`&& || !`（或 `and or not`）和括号，
+// 字段可用 `a.b` 访问嵌套对象，不存在的字段视为 `null`。
+
+use serde_json::Value;
+
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+#[error("invalid --where expression at column {column}: {message}")]
+pub struct FilterError {
+    pub column: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CmpOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Operand {
+    Field(Vec<String>),
+    Literal(Value),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Or(Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Compare(Operand, CmpOp, Operand),
+    In(Operand, Vec<Value>),
+    Truthy(Operand),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filter {
+    expr: Expr,
+}
+
+impl Filter {
+    pub fn parse(input: &str) -> Result<Self, FilterError> {
+        let tokens = tokenize(input)?;
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            end: input.chars().count() + 1,
+        };
+        let expr = parser.parse_or()?;
+        if let Some((column, token)) = parser.tokens.get(parser.pos) {
+            return Err(FilterError {
+                column: *column,
+                message: format!("unexpected {}", token.describe()),
+            });
+        }
+        Ok(Self { expr })
+    }
+
+    pub fn matches(&self, record: &Value) -> bool {
+        eval(&self.expr, record)
+    }
+}
+
+fn eval(expr: &Expr, record: &Value) -> bool {
+    match expr {
+        Expr::Or(left, right) => eval(left, record) || eval(right, record),
+        Expr::And(left, right) => eval(left, record) && eval(right, record),
+        Expr::Not(inner) => !eval(inner, record),
+        Expr::Compare(left, op, right) => {
+            compare(&resolve(left, record), *op, &resolve(right, record))
+        }
+        Expr::In(operand, list) => {
+            let value = resolve(operand, record);
+            list.iter().any(|item| values_equal(&value, item))
+        }
+        Expr::Truthy(operand) => match resolve(operand, record) {
+            Value::Null => false,
+            Value::Bool(value) => value,
+            Value::Number(number) => number.as_f64() != Some(0.0),
+            Value::String(text) => !text.is_empty(),
+            Value::Array(items) => !items.is_empty(),
+            Value::Object(_) => true,
+        },
+    }
+}
+
+fn resolve(operand: &Operand, record: &Value) -> Value {
+    match operand {
+        Operand::Literal(value) => value.clone(),
+        Operand::Field(path) => {
+            let mut current = record;
+            for segment in path {
+                current = match current {
+                    Value::Object(map) => match map.get(segment) {
+                        Some(value) => value,
+                        None => return Value::Null,
+                    },
+                    Value::Array(items) => {
+                        match segment.parse::<usize>().ok().and_then(|i| items.get(i)) {
+                            Some(value) => value,
+                            None => return Value::Null,
+                        }
+                    }
+                    _ => return Value::Null,
+                };
+            }
+            current.clone()
+        }
+    }
+}
+
+// 数字按数值比较（1 与 1.0 相等），字符串形式的数字也可以和数字字面量比较
+fn as_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(number) => number.as_f64(),
+        Value::String(text) => text.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+fn values_equal(left: &Value, right: &Value) -> bool {
+    match (left, right) {
+        (Value::Number(_), _) | (_, Value::Number(_)) => {
+            matches!((as_number(left), as_number(right)), (Some(a), Some(b)) if a == b)
+        }
+        _ => left == right,
+    }
+}
+
+fn compare(left: &Value, op: CmpOp, right: &Value) -> bool {
+    match op {
+        CmpOp::Eq => values_equal(left, right),
+        CmpOp::Ne => !values_equal(left, right),
+        CmpOp::Contains => match (left, right) {
+            (Value::String(haystack), Value::String(needle)) => haystack.contains(needle.as_str()),
+            (Value::Array(items), needle) => items.iter().any(|item| values_equal(item, needle)),
+            _ => false,
+        },
+        CmpOp::Lt | CmpOp::Le | CmpOp::Gt | CmpOp::Ge => {
+            let ordering = match (left, right) {
+                (Value::String(a), Value::String(b)) => a.partial_cmp(b),
+                _ => match (as_number(left), as_number(right)) {
+                    (Some(a), Some(b)) => a.partial_cmp(&b),
+                    _ => None,
+                },
+            };
+            match ordering {
+                Some(ordering) => match op {
+                    CmpOp::Lt => ordering.is_lt(),
+                    CmpOp::Le => ordering.is_le(),
+                    CmpOp::Gt => ordering.is_gt(),
+                    _ => ordering.is_ge(),
+                },
+                None => false,
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Num(f64),
+    Op(&'static str),
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Comma,
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Ident(name) => format!("'{}'", name),
+            Token::Str(text) => format!("string \"{}\"", text),
+            Token::Num(number) => format!("number {}", number),
+            Token::Op(op) => format!("'{}'", op),
+            Token::LParen => "'('".to_string(),
+            Token::RParen => "')'".to_string(),
+            Token::LBracket => "'['".to_string(),
+            Token::RBracket => "']'".to_string(),
+            Token::Comma => "','".to_string(),
+        }
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<(usize, Token)>, FilterError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let column = i + 1;
+        let error = |message: String| FilterError { column, message };
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        let token = match c {
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            '[' => Token::LBracket,
+            ']' => Token::RBracket,
+            ',' => Token::Comma,
+            '"' | '\'' => {
+                let mut text = String::new();
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        None => return Err(error("unterminated string".to_string())),
+                        Some(&q) if q == c => break,
+                        Some('\\') => {
+                            match chars.get(i + 1) {
+                                Some(&escaped) => text.push(escaped),
+                                None => return Err(error("unterminated string".to_string())),
+                            }
+                            i += 2;
+                        }
+                        Some(&other) => {
+                            text.push(other);
+                            i += 1;
+                        }
+                    }
+                }
+                Token::Str(text)
+            }
+            '=' | '!' | '<' | '>' | '&' | '|' => {
+                let next = chars.get(i + 1).copied();
+                let (op, len) = match (c, next) {
+                    ('=', Some('=')) => ("==", 2),
+                    ('!', Some('=')) => ("!=", 2),
+                    ('<', Some('=')) => ("<=", 2),
+                    ('>', Some('=')) => (">=", 2),
+                    ('&', Some('&')) => ("&&", 2),
+                    ('|', Some('|')) => ("||", 2),
+                    ('=', _) => ("==", 1),
+                    ('!', _) => ("!", 1),
+                    ('<', _) => ("<", 1),
+                    ('>', _) => (">", 1),
+                    _ => return Err(error(format!("unexpected '{}'", c))),
+                };
+                tokens.push((column, Token::Op(op)));
+                i += len;
+                continue;
+            }
+            c if c.is_ascii_digit()
+                || (c == '-' && chars.get(i + 1).is_some_and(|n| n.is_ascii_digit())) =>
+            {
+                let start = i;
+                i += 1;
+                while chars
+                    .get(i)
+                    .is_some_and(|n| n.is_ascii_digit() || matches!(n, '.' | 'e' | 'E'))
+                {
+                    i += 1;
+                }
+                let text: String = chars[start..i].iter().collect();
+                let number = text
+                    .parse()
+                    .map_err(|_| error(format!("invalid number '{}'", text)))?;
+                tokens.push((column, Token::Num(number)));
+                continue;
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let start = i;
+                while chars
+                    .get(i)
+                    .is_some_and(|n| n.is_alphanumeric() || matches!(n, '_' | '.' | '-'))
+                {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+                tokens.push((column, Token::Ident(word)));
+                continue;
+            }
+            other => return Err(error(format!("unexpected '{}'", other))),
+        };
+        tokens.push((column, token));
+        i += 1;
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+    // 表达式末尾的列号，用于报告“意外结束”
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(_, token)| token)
+    }
+
+    fn column(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .map(|(column, _)| *column)
+            .unwrap_or(self.end)
+    }
+
+    fn error<T>(&self, message: impl Into<String>) -> Result<T, FilterError> {
+        Err(FilterError {
+            column: self.column(),
+            message: message.into(),
+        })
+    }
+
+    fn eat_keyword(&mut self, symbol: &str, word: &str) -> bool {
+        let matched = match self.peek() {
+            Some(Token::Op(op)) => *op == symbol,
+            Some(Token::Ident(ident)) => ident.eq_ignore_ascii_case(word),
+            _ => false,
+        };
+        if matched {
+            self.pos += 1;
+        }
+        matched
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, FilterError> {
+        let mut left = self.parse_and()?;
+        while self.eat_keyword("||", "or") {
+            left = Expr::Or(Box::new(left), Box::new(self.parse_and()?));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, FilterError> {
+        let mut left = self.parse_unary()?;
+        while self.eat_keyword("&&", "and") {
+            left = Expr::And(Box::new(left), Box::new(self.parse_unary()?));
+        }
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, FilterError> {
+        if self.eat_keyword("!", "not") {
+            return Ok(Expr::Not(Box::new(self.parse_unary()?)));
+        }
+        if self.peek() == Some(&Token::LParen) {
+            self.pos += 1;
+            let inner = self.parse_or()?;
+            if self.peek() != Some(&Token::RParen) {
+                return self.error("expected ')'");
+            }
+            self.pos += 1;
+            return Ok(inner);
+        }
+        self.parse_comparison()
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expr, FilterError> {
+        let left = self.parse_operand()?;
+        let op = match self.peek() {
+            Some(Token::Op("==")) => CmpOp::Eq,
+            Some(Token::Op("!=")) => CmpOp::Ne,
+            Some(Token::Op("<")) => CmpOp::Lt,
+            Some(Token::Op("<=")) => CmpOp::Le,
+            Some(Token::Op(">")) => CmpOp::Gt,
+            Some(Token::Op(">=")) => CmpOp::Ge,
+            Some(Token::Ident(word)) if word.eq_ignore_ascii_case("contains") => CmpOp::Contains,
+            Some(Token::Ident(word)) if word.eq_ignore_ascii_case("in") => {
+                self.pos += 1;
+                return Ok(Expr::In(left, self.parse_list()?));
+            }
+            _ => return Ok(Expr::Truthy(left)),
+        };
+        self.pos += 1;
+        let right = self.parse_operand()?;
+        Ok(Expr::Compare(left, op, right))
+    }
+
+    fn parse_list(&mut self) -> Result<Vec<Value>, FilterError> {
+        if self.peek() != Some(&Token::LBracket) {
+            return self.error("expected '[' after 'in'");
+        }
+        self.pos += 1;
+        let mut items = Vec::new();
+        loop {
+            if self.peek() == Some(&Token::RBracket) {
+                self.pos += 1;
+                return Ok(items);
+            }
+            match self.parse_operand()? {
+                Operand::Literal(value) => items.push(value),
+                Operand::Field(_) => {
+                    self.pos -= 1;
+                    return self.error("list items must be literals");
+                }
+            }
+            match self.peek() {
+                Some(Token::Comma) => self.pos += 1,
+                Some(Token::RBracket) => {}
+                _ => return self.error("expected ',' or ']'"),
+            }
+        }
+    }
+
+    fn parse_operand(&mut self) -> Result<Operand, FilterError> {
+        let operand = match self.peek() {
+            Some(Token::Str(text)) => Operand::Literal(Value::String(text.clone())),
+            Some(Token::Num(number)) => Operand::Literal(Value::from(*number)),
+            Some(Token::Ident(word)) => match word.as_str() {
+                "true" => Operand::Literal(Value::Bool(true)),
+                "false" => Operand::Literal(Value::Bool(false)),
+                "null" => Operand::Literal(Value::Null),
+                path => Operand::Field(path.split('.').map(str::to_string).collect()),
+            },
+            Some(token) => {
+                let message = format!("expected a field or value, found {}", token.describe());
+                return self.error(message);
+            }
+            None => return self.error("unexpected end of expression"),
+        };
+        self.pos += 1;
+        Ok(operand)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn check(expr: &str, record: &Value) -> bool {
+        Filter::parse(expr).unwrap().matches(record)
+    }
+
+    #[test]
+    fn test_comparisons_and_logic() {
+        let record = json!({
+            "host": "10.0.0.5",
+            "port": 443,
+            "state": "open",
+            "latency_ms": 12.5,
+            "service": null,
+            "tags": ["web", "tls"],
+            "geo": {"country_code": "CN"}
+        });
+        assert!(check(r#"state == "open" && latency_ms < 50"#, &record));
+        assert!(!check(r#"state == "open" && latency_ms < 10"#, &record));
+        assert!(check("port == 443.0 and not (latency_ms >= 100)", &record));
+        assert!(check("port in [22, 80, 443] || port > 1000", &record));
+        assert!(check(
+            r#"host contains "10.0." && tags contains 'tls'"#,
+            &record
+        ));
+        assert!(check(
+            r#"geo.country_code = "CN" && service == null"#,
+            &record
+        ));
+        assert!(check("!service && missing.field == null", &record));
+        assert!(check(r#"port >= "400""#, &record));
+        // 类型不匹配的大小比较一律为 false
+        assert!(!check(r#"state > 1"#, &record));
+    }
+
+    #[test]
+    fn test_parse_errors_report_column() {
+        let cases = [
+            ("state ==", 9),
+            (r#"state == "open"#, 10),
+            ("(port > 1", 10),
+            ("port in 80", 9),
+            ("port > 1 80", 10),
+            ("a & b", 3),
+        ];
+        for (expr, column) in cases {
+            let error = Filter::parse(expr).unwrap_err();
+            assert_eq!(error.column, column, "{}: {}", expr, error);
+        }
+    }
+}
@@ -0,0 +1,91 @@
+// 按 1024 进制格式化字节数，例如 12.3 KB
+pub fn human_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+// 十六进制编码，upper 时使用大写字母
+pub fn encode_hex(bytes: &[u8], upper: bool) -> String {
+    bytes
+        .iter()
+        .map(|byte| {
+            if upper {
+                format!("{:02X}", byte)
+            } else {
+                format!("{:02x}", byte)
+            }
+        })
+        .collect()
+}
+
+// 解析十六进制字符串，允许 0x 前缀和首尾空白；长度为奇数或含非法字符时为 None
+pub fn decode_hex(raw: &str) -> Option<Vec<u8>> {
+    let raw = raw.trim();
+    let raw = raw
+        .strip_prefix("0x")
+        .or_else(|| raw.strip_prefix("0X"))
+        .unwrap_or(raw);
+    if !raw.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..raw.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(raw.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+// hexdump -C 风格的多行输出，offset 为这段数据在整个流中的起始位置
+pub fn hex_dump(data: &[u8], offset: u64) -> String {
+    let mut out = String::new();
+    for (index, line) in data.chunks(16).enumerate() {
+        let mut hex = String::new();
+        for (i, byte) in line.iter().enumerate() {
+            if i == 8 {
+                hex.push(' ');
+            }
+            hex.push_str(&format!("{:02x} ", byte));
+        }
+        let text: String = line
+            .iter()
+            .map(|&byte| {
+                if byte.is_ascii_graphic() || byte == b' ' {
+                    byte as char
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+        out.push_str(&format!(
+            "{:08x}  {:<49} |{}|\n",
+            offset + index as u64 * 16,
+            hex,
+            text
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex_dump() {
+        assert_eq!(
+            hex_dump(b"GET / HTTP/1.1\r\nHost", 32),
+            "00000020  47 45 54 20 2f 20 48 54  54 50 2f 31 2e 31 0d 0a  |GET / HTTP/1.1..|\n\
+             00000030  48 6f 73 74                                       |Host|\n"
+        );
+        assert_eq!(hex_dump(b"", 0), "");
+    }
+}
@@ -0,0 +1,730 @@
+// 最小的 HTTP/1.1 客户端，供需要发起简单请求的命令使用（例如 smoketest 的 HTTP 检查、http 命令）。
+// 每次请求新建连接并带 Connection: close，响应体按 chunked 解码，超过上限的部分丢弃。
+// 压测之类需要复用连接的场景用 Connection，按响应头确定响应体的边界后在同一连接上发送下一个请求。
+// HTTPS 默认按内置的 Mozilla 根证书校验服务端证书；指定代理时经 CONNECT / SOCKS5 隧道连接。
+// 文件末尾是 serve、share 等服务端命令共用的媒体类型推断和 URL 编码。
+
+use std::fmt;
+use std::io;
+use std::sync::Arc;
+
+use tokio::io::{
+    AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader,
+};
+use tokio::net::TcpStream;
+use tokio_rustls::rustls::pki_types::ServerName;
+use tokio_rustls::TlsConnector;
+
+use crate::utils::proxy::{Proxy, ProxyError};
+use crate::utils::tls::{insecure_client_config, verified_client_config};
+
+// 读取的响应上限（含响应头），健康检查之类的请求远小于此
+const MAX_RESPONSE_BYTES: usize = 1 << 20;
+// 长连接上单个响应头的上限
+const MAX_HEAD_BYTES: u64 = 64 * 1024;
+
+#[derive(thiserror::Error, Debug)]
+pub enum HttpError {
+    #[error("invalid URL '{0}', expected http://host[:port]/path or https://...")]
+    InvalidUrl(String),
+    #[error("TLS handshake failed: {0}")]
+    Tls(io::Error),
+    #[error("invalid HTTP response: {0}")]
+    InvalidResponse(&'static str),
+    #[error(transparent)]
+    Proxy(#[from] ProxyError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Url {
+    pub https: bool,
+    // IPv6 地址不带方括号
+    pub host: String,
+    pub port: u16,
+    // 路径和查询串，至少为 /
+    pub path: String,
+}
+
+impl Url {
+    pub fn parse(raw: &str) -> Result<Self, HttpError> {
+        let invalid = || HttpError::InvalidUrl(raw.to_string());
+        let (https, rest) = if let Some(rest) = raw.strip_prefix("https://") {
+            (true, rest)
+        } else if let Some(rest) = raw.strip_prefix("http://") {
+            (false, rest)
+        } else {
+            return Err(invalid());
+        };
+        let (authority, path) = match rest.find(['/', '?']) {
+            Some(index) if rest[index..].starts_with('?') => {
+                (&rest[..index], format!("/{}", &rest[index..]))
+            }
+            Some(index) => (&rest[..index], rest[index..].to_string()),
+            None => (rest, "/".to_string()),
+        };
+        // 不支持 user:pass@host
+        if authority.contains('@') {
+            return Err(invalid());
+        }
+        let default_port = if https { 443 } else { 80 };
+        let (host, port) = if let Some(bracketed) = authority.strip_prefix('[') {
+            let (host, tail) = bracketed.split_once(']').ok_or_else(invalid)?;
+            let port = match tail.strip_prefix(':') {
+                Some(port) => port.parse().map_err(|_| invalid())?,
+                None if tail.is_empty() => default_port,
+                None => return Err(invalid()),
+            };
+            (host, port)
+        } else {
+            match authority.rsplit_once(':') {
+                Some((host, port)) => (host, port.parse().map_err(|_| invalid())?),
+                None => (authority, default_port),
+            }
+        };
+        if host.is_empty() {
+            return Err(invalid());
+        }
+        Ok(Self {
+            https,
+            host: host.to_string(),
+            port,
+            path,
+        })
+    }
+
+    // 重定向的 Location：绝对 URL、// 开头的协议相对地址、绝对路径或相对当前路径的地址
+    pub fn join(&self, location: &str) -> Result<Self, HttpError> {
+        let location = location.trim();
+        if location.contains("://") {
+            return Self::parse(location);
+        }
+        if let Some(rest) = location.strip_prefix("//") {
+            let scheme = if self.https { "https" } else { "http" };
+            return Self::parse(&format!("{}://{}", scheme, rest));
+        }
+        let path = if location.starts_with('/') {
+            location.to_string()
+        } else if location.starts_with('?') {
+            let base = self.path.split('?').next().unwrap_or("/");
+            format!("{}{}", base, location)
+        } else {
+            let base = self.path.split('?').next().unwrap_or("/");
+            let dir = &base[..=base.rfind('/').unwrap_or(0)];
+            format!("{}{}", dir, location)
+        };
+        Ok(Self {
+            path,
+            ..self.clone()
+        })
+    }
+
+    // Host 请求头，默认端口省略
+    fn authority(&self) -> String {
+        let host = if self.host.contains(':') {
+            format!("[{}]", self.host)
+        } else {
+            self.host.clone()
+        };
+        match (self.https, self.port) {
+            (true, 443) | (false, 80) => host,
+            (_, port) => format!("{}:{}", host, port),
+        }
+    }
+}
+
+impl fmt::Display for Url {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let scheme = if self.https { "https" } else { "http" };
+        write!(f, "{}://{}{}", scheme, self.authority(), self.path)
+    }
+}
+
+#[derive(Debug)]
+pub struct Response {
+    pub status: u16,
+    // 状态行中的原因短语，例如 OK、Not Found
+    pub reason: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+// insecure 为 true 时接受任何证书，用于自签名的测试环境
+pub async fn get(url: &Url, insecure: bool) -> Result<Response, HttpError> {
+    send(url, &request_head("GET", url, ""), insecure).await
+}
+
+// 在调用方已经建立的连接上发送 GET（例如经代理的连接），url 只用于请求行和 Host 头。
+// 最多读取 limit 字节，超出的部分连同截断的响应体一起丢弃
+pub async fn get_on<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: S,
+    url: &Url,
+    limit: usize,
+) -> Result<Response, HttpError> {
+    exchange(stream, &request_head("GET", url, ""), limit).await
+}
+
+// 以 application/json 提交请求体，例如 webhook 通知
+pub async fn post_json(url: &Url, body: &[u8], insecure: bool) -> Result<Response, HttpError> {
+    let headers = format!(
+        "Content-Type: application/json\r\nContent-Length: {}\r\n",
+        body.len()
+    );
+    let mut request = request_head("POST", url, &headers);
+    request.extend_from_slice(body);
+    send(url, &request, insecure).await
+}
+
+// 调用方指定方法、请求头和请求体的完整请求
+pub struct Request<'a> {
+    pub method: &'a str,
+    pub url: &'a Url,
+    pub headers: &'a [(String, String)],
+    pub body: &'a [u8],
+}
+
+impl Request<'_> {
+    // 未在 headers 中给出的 User-Agent、Accept 使用默认值；有请求体时补 Content-Length。
+    // connection 为 Connection 请求头的值：close 要求服务端发完响应后关闭连接，Upgrade 用于协议升级
+    fn encode(&self, connection: &str) -> Vec<u8> {
+        let mut request = self
+            .encode_head(connection, self.body.len() as u64)
+            .into_bytes();
+        request.extend_from_slice(self.body);
+        request
+    }
+
+    // 请求行和请求头，请求体长度由 content_length 给出
+    fn encode_head(&self, connection: &str, content_length: u64) -> String {
+        let has = |name: &str| {
+            self.headers
+                .iter()
+                .any(|(key, _)| key.eq_ignore_ascii_case(name))
+        };
+        let mut head = format!(
+            "{} {} HTTP/1.1\r\nHost: {}\r\n",
+            self.method,
+            self.url.path,
+            self.url.authority()
+        );
+        if !has("User-Agent") {
+            head.push_str(&format!(
+                "User-Agent: rtoolkit/{}\r\n",
+                env!("CARGO_PKG_VERSION")
+            ));
+        }
+        if !has("Accept") {
+            head.push_str("Accept: */*\r\n");
+        }
+        // Host、Connection 和 Content-Length 由这里生成，调用方给出的同名请求头忽略
+        for (key, value) in self.headers {
+            let managed = ["Host", "Connection", "Content-Length"]
+                .iter()
+                .any(|name| key.eq_ignore_ascii_case(name));
+            if !managed {
+                head.push_str(&format!("{}: {}\r\n", key, value));
+            }
+        }
+        if content_length > 0 || matches!(self.method, "POST" | "PUT" | "PATCH") {
+            head.push_str(&format!("Content-Length: {}\r\n", content_length));
+        }
+        head.push_str(&format!("Connection: {}\r\n\r\n", connection));
+        head
+    }
+
+    // 经可选的代理发送，最多读取 limit 字节的响应
+    pub async fn send(
+        &self,
+        proxy: Option<&Proxy>,
+        insecure: bool,
+        limit: usize,
+    ) -> Result<Response, HttpError> {
+        let stream = connect(self.url, proxy, insecure).await?;
+        exchange(stream, &self.encode("close"), limit).await
+    }
+}
+
+// 可以承载 HTTP 的连接：TCP 或其上的 TLS
+pub trait HttpStream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> HttpStream for T {}
+
+// 连接到 url 的主机（或经代理），https 时完成 TLS 握手
+pub async fn connect(
+    url: &Url,
+    proxy: Option<&Proxy>,
+    insecure: bool,
+) -> Result<Box<dyn HttpStream>, HttpError> {
+    let stream = match proxy {
+        Some(proxy) => proxy.connect(&url.host, url.port).await?,
+        None => TcpStream::connect((url.host.as_str(), url.port)).await?,
+    };
+    if !url.https {
+        return Ok(Box::new(stream));
+    }
+    let config = if insecure {
+        insecure_client_config()
+    } else {
+        verified_client_config()
+    };
+    let server_name = ServerName::try_from(url.host.clone())
+        .map_err(|_| HttpError::InvalidUrl(url.host.clone()))?;
+    let stream = TlsConnector::from(Arc::new(config))
+        .connect(server_name, stream)
+        .await
+        .map_err(HttpError::Tls)?;
+    Ok(Box::new(stream))
+}
+
+// 在已建立的连接上发送带 Connection: Upgrade 的请求（Upgrade 头由调用方给出），返回响应头和之后的连接。
+// 服务端同意升级时状态码为 101，之后的数据（可能已有一部分读入缓冲区）属于新协议；
+// 其他状态码的响应体最多保留 limit 字节，供调用方报错时显示
+pub async fn upgrade(
+    stream: Box<dyn HttpStream>,
+    request: &Request<'_>,
+    limit: usize,
+) -> Result<(Response, BufReader<Box<dyn HttpStream>>), HttpError> {
+    let mut stream = BufReader::new(stream);
+    stream
+        .get_mut()
+        .write_all(&request.encode("Upgrade"))
+        .await?;
+    stream.get_mut().flush().await?;
+    let (response, _) = read_response(&mut stream, false, limit).await?;
+    Ok((response, stream))
+}
+
+// 请求体太大、需要边生成边发送时（例如测速上传）只发送请求头，request.body 被忽略，
+// 之后由调用方写入 content_length 字节的请求体；要求服务端发完响应后关闭连接
+pub async fn write_head<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    request: &Request<'_>,
+    content_length: u64,
+) -> Result<(), HttpError> {
+    writer
+        .write_all(request.encode_head("close", content_length).as_bytes())
+        .await?;
+    Ok(())
+}
+
+// 可复用的长连接，一次只能有一个请求在途
+pub struct Connection {
+    stream: BufReader<Box<dyn HttpStream>>,
+}
+
+impl Connection {
+    pub async fn open(url: &Url, proxy: Option<&Proxy>, insecure: bool) -> Result<Self, HttpError> {
+        Ok(Self {
+            stream: BufReader::new(connect(url, proxy, insecure).await?),
+        })
+    }
+
+    // 发送请求并读完整个响应，响应体最多保留 limit 字节，其余读出后丢弃。
+    // 返回的 bool 表示连接能否继续使用：服务端要求关闭或响应体读到连接关闭为止时为 false
+    pub async fn send(
+        &mut self,
+        request: &Request<'_>,
+        limit: usize,
+    ) -> Result<(Response, bool), HttpError> {
+        self.stream
+            .get_mut()
+            .write_all(&request.encode("keep-alive"))
+            .await?;
+        self.stream.get_mut().flush().await?;
+        read_response(&mut self.stream, request.method == "HEAD", limit).await
+    }
+}
+
+fn request_head(method: &str, url: &Url, headers: &str) -> Vec<u8> {
+    format!(
+        "{} {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: rtoolkit/{}\r\nAccept: */*\r\n{}Connection: close\r\n\r\n",
+        method,
+        url.path,
+        url.authority(),
+        env!("CARGO_PKG_VERSION"),
+        headers
+    )
+    .into_bytes()
+}
+
+async fn send(url: &Url, request: &[u8], insecure: bool) -> Result<Response, HttpError> {
+    let stream = connect(url, None, insecure).await?;
+    exchange(stream, request, MAX_RESPONSE_BYTES).await
+}
+
+async fn exchange<S: AsyncRead + AsyncWrite + Unpin>(
+    mut stream: S,
+    request: &[u8],
+    limit: usize,
+) -> Result<Response, HttpError> {
+    stream.write_all(request).await?;
+    let mut raw = Vec::new();
+    let mut buf = [0u8; 8192];
+    while raw.len() < limit {
+        match stream.read(&mut buf).await {
+            Ok(0) => break,
+            Ok(n) => raw.extend_from_slice(&buf[..n]),
+            // 不少服务端关闭 TLS 连接时不发 close_notify，已经读到的内容仍然有效
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof && !raw.is_empty() => break,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    parse_response(&raw)
+}
+
+fn parse_response(raw: &[u8]) -> Result<Response, HttpError> {
+    let head_end = raw
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .ok_or(HttpError::InvalidResponse("incomplete response headers"))?;
+    let mut response = parse_head(&raw[..head_end])?;
+    response.body = raw[head_end + 4..].to_vec();
+    if response
+        .header("Transfer-Encoding")
+        .is_some_and(|value| value.eq_ignore_ascii_case("chunked"))
+    {
+        response.body = decode_chunked(&response.body);
+    } else if let Some(length) = response
+        .header("Content-Length")
+        .and_then(|value| value.parse::<usize>().ok())
+    {
+        response.body.truncate(length);
+    }
+    Ok(response)
+}
+
+// 状态行和响应头，不含结尾的空行
+fn parse_head(raw: &[u8]) -> Result<Response, HttpError> {
+    let head = std::str::from_utf8(raw)
+        .map_err(|_| HttpError::InvalidResponse("response headers are not UTF-8"))?;
+    let mut lines = head.split("\r\n");
+    let mut status_line = lines
+        .next()
+        .filter(|line| line.starts_with("HTTP/"))
+        .ok_or(HttpError::InvalidResponse("malformed status line"))?
+        .splitn(3, ' ');
+    let status = status_line
+        .nth(1)
+        .and_then(|code| code.parse().ok())
+        .ok_or(HttpError::InvalidResponse("malformed status line"))?;
+    let reason = status_line.next().unwrap_or_default().trim().to_string();
+    let headers: Vec<(String, String)> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect();
+    Ok(Response {
+        status,
+        reason,
+        headers,
+        body: Vec::new(),
+    })
+}
+
+// 读取一个响应头，跳过 100 Continue 之类的临时响应（101 除外）；响应体留在 reader 中，
+// 由调用方按需读取，例如测速时边下载边计数
+pub async fn read_head<R: AsyncBufRead + Unpin>(reader: &mut R) -> Result<Response, HttpError> {
+    loop {
+        let mut head = Vec::new();
+        loop {
+            let start = head.len();
+            let n = (&mut *reader)
+                .take(MAX_HEAD_BYTES)
+                .read_until(b'\n', &mut head)
+                .await?;
+            if n == 0 {
+                return Err(if head.is_empty() {
+                    io::Error::from(io::ErrorKind::UnexpectedEof).into()
+                } else {
+                    HttpError::InvalidResponse("incomplete response headers")
+                });
+            }
+            if head.len() as u64 > MAX_HEAD_BYTES {
+                return Err(HttpError::InvalidResponse("response headers too large"));
+            }
+            if head[start..].trim_ascii().is_empty() {
+                // 上一个响应体之后多余的空行
+                if start == 0 {
+                    head.clear();
+                    continue;
+                }
+                break;
+            }
+        }
+        let response = parse_head(head.trim_ascii_end())?;
+        if !(100..200).contains(&response.status) || response.status == 101 {
+            return Ok(response);
+        }
+    }
+}
+
+// 从长连接上读一个完整的响应，跳过 100 Continue 之类的临时响应。
+// HEAD 请求和 204、304 响应没有响应体，其余按 chunked、Content-Length、读到关闭的顺序确定边界
+async fn read_response<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+    head_request: bool,
+    limit: usize,
+) -> Result<(Response, bool), HttpError> {
+    let mut response = read_head(reader).await?;
+    let mut reusable = !response
+        .header("Connection")
+        .is_some_and(|value| value.eq_ignore_ascii_case("close"));
+    if head_request || matches!(response.status, 101 | 204 | 304) {
+        let reusable = reusable && response.status != 101;
+        return Ok((response, reusable));
+    }
+    if response
+        .header("Transfer-Encoding")
+        .is_some_and(|value| value.eq_ignore_ascii_case("chunked"))
+    {
+        loop {
+            let mut line = Vec::new();
+            (&mut *reader)
+                .take(MAX_HEAD_BYTES)
+                .read_until(b'\n', &mut line)
+                .await?;
+            let size = std::str::from_utf8(&line)
+                .ok()
+                .and_then(|line| line.split(';').next())
+                .and_then(|size| u64::from_str_radix(size.trim(), 16).ok())
+                .ok_or(HttpError::InvalidResponse("malformed chunk size"))?;
+            if size == 0 {
+                // 跳过 trailer，直到空行
+                loop {
+                    line.clear();
+                    let n = (&mut *reader)
+                        .take(MAX_HEAD_BYTES)
+                        .read_until(b'\n', &mut line)
+                        .await?;
+                    if n == 0 || line.trim_ascii().is_empty() {
+                        break;
+                    }
+                }
+                break;
+            }
+            read_body(reader, size, &mut response.body, limit).await?;
+            read_body(reader, 2, &mut Vec::new(), 0).await?;
+        }
+    } else if let Some(length) = response.header("Content-Length") {
+        let length = length
+            .parse()
+            .map_err(|_| HttpError::InvalidResponse("malformed Content-Length"))?;
+        read_body(reader, length, &mut response.body, limit).await?;
+    } else {
+        reusable = false;
+        let mut rest = Vec::new();
+        match reader.read_to_end(&mut rest).await {
+            Ok(_) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {}
+            Err(e) => return Err(e.into()),
+        }
+        rest.truncate(limit.saturating_sub(response.body.len()));
+        response.body.extend_from_slice(&rest);
+    }
+    Ok((response, reusable))
+}
+
+// 读出 length 字节，body 不超过 limit 的部分保留，其余丢弃；连接提前关闭时报错
+async fn read_body<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+    length: u64,
+    body: &mut Vec<u8>,
+    limit: usize,
+) -> io::Result<()> {
+    let keep = (limit.saturating_sub(body.len()) as u64).min(length);
+    let start = body.len();
+    body.resize(start + keep as usize, 0);
+    reader.read_exact(&mut body[start..]).await?;
+    let skip = length - keep;
+    let skipped = tokio::io::copy(&mut (&mut *reader).take(skip), &mut tokio::io::sink()).await?;
+    if skipped < skip {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(())
+}
+
+// 截断的 chunked 响应体尽量返回已经完整的部分
+fn decode_chunked(mut data: &[u8]) -> Vec<u8> {
+    let mut body = Vec::new();
+    while let Some(line_end) = data.windows(2).position(|window| window == b"\r\n") {
+        let size = std::str::from_utf8(&data[..line_end])
+            .ok()
+            .and_then(|line| line.split(';').next())
+            .and_then(|size| usize::from_str_radix(size.trim(), 16).ok());
+        let Some(size) = size.filter(|size| *size > 0) else {
+            break;
+        };
+        let start = line_end + 2;
+        let end = (start + size).min(data.len());
+        body.extend_from_slice(&data[start..end]);
+        data = data.get(end + 2..).unwrap_or_default();
+    }
+    body
+}
+
+// 按扩展名推断媒体类型，供 serve、share、httpmock 等服务端命令设置 Content-Type
+pub fn content_type(name: &str) -> &'static str {
+    let ext = name
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_ascii_lowercase())
+        .unwrap_or_default();
+    match ext.as_str() {
+        "txt" | "log" | "md" | "csv" => "text/plain; charset=utf-8",
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "json" | "map" => "application/json",
+        "xml" => "application/xml",
+        "svg" => "image/svg+xml",
+        "ico" => "image/x-icon",
+        "avif" => "image/avif",
+        "wasm" => "application/wasm",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        "webm" => "video/webm",
+        "wav" => "audio/wav",
+        "ogg" => "audio/ogg",
+        "webmanifest" => "application/manifest+json",
+        "gz" => "application/gzip",
+        "tar" => "application/x-tar",
+        "pdf" => "application/pdf",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "mp4" => "video/mp4",
+        "mp3" => "audio/mpeg",
+        "zip" => "application/zip",
+        "apk" => "application/vnd.android.package-archive",
+        _ => "application/octet-stream",
+    }
+}
+
+// 把路径段或文件名编码为 URL 中可用的形式，只保留 RFC 3986 的非保留字符
+pub fn percent_encode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for byte in s.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_url_and_response() {
+        let url = Url::parse("https://api.example.com/health?full=1").unwrap();
+        assert_eq!(
+            url,
+            Url {
+                https: true,
+                host: "api.example.com".into(),
+                port: 443,
+                path: "/health?full=1".into(),
+            }
+        );
+        assert_eq!(url.authority(), "api.example.com");
+        let url = Url::parse("http://[::1]:8080").unwrap();
+        assert_eq!(
+            (url.host.as_str(), url.port, url.path.as_str()),
+            ("::1", 8080, "/")
+        );
+        assert_eq!(url.authority(), "[::1]:8080");
+        assert_eq!(url.to_string(), "http://[::1]:8080/");
+        let base = Url::parse("https://a.test:8443/api/v1/users?page=2").unwrap();
+        for (location, joined) in [
+            ("/login", "https://a.test:8443/login"),
+            ("items", "https://a.test:8443/api/v1/items"),
+            ("?page=3", "https://a.test:8443/api/v1/users?page=3"),
+            ("//b.test/x", "https://b.test/x"),
+            ("http://c.test", "http://c.test/"),
+        ] {
+            assert_eq!(base.join(location).unwrap().to_string(), joined);
+        }
+        assert_eq!(Url::parse("http://a.test?x=1").unwrap().path, "/?x=1");
+        for bad in [
+            "ftp://a",
+            "http://",
+            "http://a:x/",
+            "http://u@a/",
+            "http://[::1/",
+        ] {
+            assert!(Url::parse(bad).is_err(), "{}", bad);
+        }
+
+        let response = parse_response(
+            b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n4\r\nWiki\r\n5;x=y\r\npedia\r\n0\r\n\r\n",
+        )
+        .unwrap();
+        assert_eq!((response.status, response.reason.as_str()), (200, "OK"));
+        assert_eq!(response.header("transfer-encoding"), Some("chunked"));
+        assert_eq!(response.body, b"Wikipedia");
+        let response =
+            parse_response(b"HTTP/1.0 503 Service Unavailable\r\nContent-Length: 2\r\n\r\nokEXTRA")
+                .unwrap();
+        assert_eq!(
+            (response.status, response.body.as_slice()),
+            (503, &b"ok"[..])
+        );
+        assert!(parse_response(b"SSH-2.0-OpenSSH\r\n").is_err());
+
+        let headers = vec![("accept".to_string(), "application/json".to_string())];
+        let request = Request {
+            method: "PUT",
+            url: &Url::parse("http://a.test/items/1").unwrap(),
+            headers: &headers,
+            body: b"{}",
+        }
+        .encode("close");
+        let text = String::from_utf8(request).unwrap();
+        assert!(text.starts_with("PUT /items/1 HTTP/1.1\r\nHost: a.test\r\n"));
+        assert!(text.contains("accept: application/json\r\n") && !text.contains("Accept: */*"));
+        assert!(text.ends_with("Content-Length: 2\r\nConnection: close\r\n\r\n{}"));
+    }
+
+    #[test]
+    fn test_content_type_and_percent_encode() {
+        assert_eq!(content_type("App.JS"), "text/javascript; charset=utf-8");
+        assert_eq!(content_type("noext"), "application/octet-stream");
+        assert_eq!(percent_encode("a&b 文.txt"), "a%26b%20%E6%96%87.txt");
+    }
+
+    #[test]
+    fn test_read_response_keep_alive() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let mut stream: &[u8] = b"HTTP/1.1 100 Continue\r\n\r\n\
+                HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhello\
+                HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n2\r\nde\r\n0\r\n\r\n\
+                HTTP/1.1 204 No Content\r\n\r\n\
+                HTTP/1.0 200 OK\r\nConnection: close\r\n\r\nrest of stream";
+            let (response, reusable) = read_response(&mut stream, false, 1024).await.unwrap();
+            assert_eq!((response.status, response.body.as_slice(), reusable), (200, &b"hello"[..], true));
+            // 超过上限的部分读出后丢弃，不影响下一个响应
+            let (response, _) = read_response(&mut stream, false, 4).await.unwrap();
+            assert_eq!(response.body, b"abcd");
+            let (response, reusable) = read_response(&mut stream, false, 1024).await.unwrap();
+            assert_eq!((response.status, response.body.len(), reusable), (204, 0, true));
+            let (response, reusable) = read_response(&mut stream, false, 1024).await.unwrap();
+            assert_eq!((response.body.as_slice(), reusable), (&b"rest of stream"[..], false));
+            assert!(read_response(&mut stream, false, 1024).await.is_err());
+        });
+    }
+}
@@ -0,0 +1,261 @@
+// ICMP 回显：优先使用无需 root 的 ping 套接字（Linux 的 net.ipv4.ping_group_range、macOS 默认允许），
+// 不可用时尝试原始套接字；两者都失败时由调用方改用 TCP 连接测量往返时间
+
+use std::io::{self, Read};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::{Duration, Instant};
+
+use socket2::{Domain, Protocol, Socket, Type};
+
+const ECHO_REQUEST_V4: u8 = 8;
+const ECHO_REPLY_V4: u8 = 0;
+const ECHO_REQUEST_V6: u8 = 128;
+const ECHO_REPLY_V6: u8 = 129;
+const TIME_EXCEEDED_V4: u8 = 11;
+const UNREACHABLE_V4: u8 = 3;
+const TIME_EXCEEDED_V6: u8 = 3;
+const UNREACHABLE_V6: u8 = 1;
+
+pub struct EchoSocket {
+    socket: Socket,
+    ipv4: bool,
+    // 原始套接字收到的 IPv4 报文带 IP 头，且会收到所有 ICMP 报文，需要自己按标识符过滤；
+    // ping 套接字由内核改写标识符并只投递本套接字的回显应答
+    raw: bool,
+}
+
+impl EchoSocket {
+    // 连接到目标后只会收到来自该地址的报文
+    pub fn open(addr: SocketAddr) -> io::Result<Self> {
+        let (domain, protocol) = if addr.is_ipv4() {
+            (Domain::IPV4, Protocol::ICMPV4)
+        } else {
+            (Domain::IPV6, Protocol::ICMPV6)
+        };
+        let (socket, raw) = match Socket::new(domain, Type::DGRAM, Some(protocol)) {
+            Ok(socket) => (socket, false),
+            Err(_) => (Socket::new(domain, Type::RAW, Some(protocol))?, true),
+        };
+        socket.connect(&SocketAddr::new(addr.ip(), 0).into())?;
+        Ok(Self {
+            socket,
+            ipv4: addr.is_ipv4(),
+            raw,
+        })
+    }
+
+    pub fn send(&self, id: u16, seq: u16, payload_len: usize) -> io::Result<()> {
+        let packet = echo_request(self.ipv4, id, seq, payload_len);
+        self.socket.send(&packet)?;
+        Ok(())
+    }
+
+    // 等待序号为 seq 的回显应答，返回应答的 ICMP 报文长度；超时为 None
+    pub fn recv(&self, id: u16, seq: u16, timeout: Duration) -> io::Result<Option<usize>> {
+        let deadline = Instant::now() + timeout;
+        let mut buf = [0u8; 65_536];
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Ok(None);
+            }
+            self.socket.set_read_timeout(Some(remaining))?;
+            let n = match (&self.socket).read(&mut buf) {
+                Ok(n) => n,
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                    ) =>
+                {
+                    return Ok(None)
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            let packet = if self.raw && self.ipv4 {
+                strip_ipv4_header(&buf[..n])
+            } else {
+                Some(&buf[..n])
+            };
+            let Some(reply) = packet.and_then(|packet| parse_echo_reply(self.ipv4, packet)) else {
+                continue;
+            };
+            if reply.seq == seq && (!self.raw || reply.id == id) {
+                return Ok(Some(reply.len));
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EchoReply {
+    pub id: u16,
+    pub seq: u16,
+    pub len: usize,
+}
+
+// ICMPv6 的校验和包含伪首部，由内核计算
+pub fn echo_request(ipv4: bool, id: u16, seq: u16, payload_len: usize) -> Vec<u8> {
+    let kind = if ipv4 {
+        ECHO_REQUEST_V4
+    } else {
+        ECHO_REQUEST_V6
+    };
+    let mut packet = vec![kind, 0, 0, 0];
+    packet.extend_from_slice(&id.to_be_bytes());
+    packet.extend_from_slice(&seq.to_be_bytes());
+    packet.extend((0..payload_len).map(|i| (i % 256) as u8));
+    if ipv4 {
+        let sum = checksum(&packet);
+        packet[2..4].copy_from_slice(&sum.to_be_bytes());
+    }
+    packet
+}
+
+pub fn parse_echo_reply(ipv4: bool, packet: &[u8]) -> Option<EchoReply> {
+    let expected = if ipv4 { ECHO_REPLY_V4 } else { ECHO_REPLY_V6 };
+    if packet.len() < 8 || packet[0] != expected || packet[1] != 0 {
+        return None;
+    }
+    Some(EchoReply {
+        id: u16::from_be_bytes([packet[4], packet[5]]),
+        seq: u16::from_be_bytes([packet[6], packet[7]]),
+        len: packet.len(),
+    })
+}
+
+// 路由器或目标回送的 ICMP 差错
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IcmpError {
+    TimeExceeded,
+    Unreachable(u8),
+}
+
+impl IcmpError {
+    // 按报文类型和代码识别，IPv4 与 IPv6 的类型编号不同
+    pub fn from_type(ipv4: bool, kind: u8, code: u8) -> Option<Self> {
+        match (ipv4, kind) {
+            (true, TIME_EXCEEDED_V4) | (false, TIME_EXCEEDED_V6) => Some(IcmpError::TimeExceeded),
+            (true, UNREACHABLE_V4) | (false, UNREACHABLE_V6) => Some(IcmpError::Unreachable(code)),
+            _ => None,
+        }
+    }
+}
+
+// 差错报文引用的原始报文：协议号、原目标地址和传输层头部（至少 8 字节）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuotedPacket<'a> {
+    pub error: IcmpError,
+    pub protocol: u8,
+    pub dst: IpAddr,
+    pub transport: &'a [u8],
+}
+
+// packet 从 ICMP 头开始；IPv6 引用的报文假定没有扩展头
+pub fn parse_icmp_error(ipv4: bool, packet: &[u8]) -> Option<QuotedPacket<'_>> {
+    let error = IcmpError::from_type(ipv4, *packet.first()?, *packet.get(1)?)?;
+    let inner = packet.get(8..)?;
+    let (protocol, dst, transport) = if ipv4 {
+        let header_len = usize::from(inner.first()? & 0x0f) * 4;
+        let dst: [u8; 4] = inner.get(16..20)?.try_into().ok()?;
+        (
+            *inner.get(9)?,
+            IpAddr::V4(Ipv4Addr::from(dst)),
+            inner.get(header_len.max(20)..)?,
+        )
+    } else {
+        let dst: [u8; 16] = inner.get(24..40)?.try_into().ok()?;
+        (
+            *inner.get(6)?,
+            IpAddr::V6(Ipv6Addr::from(dst)),
+            inner.get(40..)?,
+        )
+    };
+    (transport.len() >= 8).then_some(QuotedPacket {
+        error,
+        protocol,
+        dst,
+        transport,
+    })
+}
+
+pub fn strip_ipv4_header(packet: &[u8]) -> Option<&[u8]> {
+    let header_len = usize::from(packet.first()? & 0x0f) * 4;
+    packet.get(header_len..).filter(|_| header_len >= 20)
+}
+
+// RFC 1071 互联网校验和
+pub fn checksum(data: &[u8]) -> u16 {
+    let mut sum: u32 = data
+        .chunks(2)
+        .map(|chunk| u32::from(u16::from_be_bytes([chunk[0], *chunk.get(1).unwrap_or(&0)])))
+        .sum();
+    while sum >> 16 != 0 {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_echo_packets() {
+        let request = echo_request(true, 0x1234, 7, 5);
+        assert_eq!(request.len(), 13);
+        assert_eq!(&request[4..8], &[0x12, 0x34, 0, 7]);
+        // 带校验和的报文再算一次校验和为 0
+        assert_eq!(checksum(&request), 0);
+        assert_eq!(parse_echo_reply(true, &request), None);
+
+        let mut reply = request.clone();
+        reply[0] = ECHO_REPLY_V4;
+        assert_eq!(
+            parse_echo_reply(true, &reply),
+            Some(EchoReply {
+                id: 0x1234,
+                seq: 7,
+                len: 13
+            })
+        );
+        let mut ip = vec![0x45u8; 20];
+        ip.extend_from_slice(&reply);
+        assert_eq!(strip_ipv4_header(&ip), Some(&reply[..]));
+
+        let v6 = echo_request(false, 1, 2, 0);
+        assert_eq!(v6, vec![ECHO_REQUEST_V6, 0, 0, 0, 0, 1, 0, 2]);
+    }
+
+    #[test]
+    fn test_parse_icmp_error() {
+        // 路由器回送的 TTL 超时，引用了发往 192.0.2.9:33435 的 UDP 报文
+        let mut packet = vec![TIME_EXCEEDED_V4, 0, 0, 0, 0, 0, 0, 0];
+        let mut ip = vec![0u8; 20];
+        ip[0] = 0x45;
+        ip[9] = 17;
+        ip[16..20].copy_from_slice(&[192, 0, 2, 9]);
+        packet.extend_from_slice(&ip);
+        packet.extend_from_slice(&[0x9c, 0x40, 0x82, 0x9b, 0, 8, 0, 0]);
+        let quoted = parse_icmp_error(true, &packet).unwrap();
+        assert_eq!(quoted.error, IcmpError::TimeExceeded);
+        assert_eq!(quoted.protocol, 17);
+        assert_eq!(quoted.dst, "192.0.2.9".parse::<IpAddr>().unwrap());
+        assert_eq!(&quoted.transport[2..4], &33435u16.to_be_bytes());
+
+        // 引用的报文不足 8 字节传输层头部
+        assert_eq!(parse_icmp_error(true, &packet[..packet.len() - 1]), None);
+        packet[0] = UNREACHABLE_V4;
+        packet[1] = 3;
+        assert_eq!(
+            parse_icmp_error(true, &packet).unwrap().error,
+            IcmpError::Unreachable(3)
+        );
+        assert_eq!(
+            IcmpError::from_type(false, 3, 0),
+            Some(IcmpError::TimeExceeded)
+        );
+        assert_eq!(IcmpError::from_type(false, ECHO_REPLY_V6, 0), None);
+    }
+}
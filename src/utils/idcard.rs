@@ -0,0 +1,231 @@
+use chrono::{Datelike, Local, NaiveDate};
+use serde::Serialize;
+
+use crate::utils::areas::{get_province_name, get_region_name};
+
+const WEIGHTS: [u32; 17] = [7, 9, 10, 5, 8, 4, 2, 1, 6, 3, 7, 9, 10, 5, 8, 4, 2];
+const CHECK_CHARS: [char; 11] = ['1', '0', 'X', '9', '8', '7', '6', '5', '4', '3', '2'];
+
+// 身份证号不合法的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InvalidReason {
+    Length,
+    Format,
+    Region,
+    Date,
+    Checksum,
+}
+
+impl InvalidReason {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            InvalidReason::Length => "length",
+            InvalidReason::Format => "format",
+            InvalidReason::Region => "region",
+            InvalidReason::Date => "date",
+            InvalidReason::Checksum => "checksum",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidId {
+    pub reason: InvalidReason,
+    pub detail: String,
+}
+
+// 校验通过后解析出的字段
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedId {
+    pub region: String,
+    pub birthday: NaiveDate,
+    pub gender: &'static str,
+    // 15 位旧版号码
+    pub legacy: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegionCheck {
+    // 6 位地区码必须存在于内置地区表
+    Strict,
+    // 只要求省级代码存在，兼容已撤销的旧地区码
+    Province,
+}
+
+// 计算前 17 位对应的 GB 11643 校验位
+pub fn checksum_char(id17: &str) -> char {
+    let sum: u32 = id17
+        .chars()
+        .zip(WEIGHTS.iter())
+        .map(|(c, w)| c.to_digit(10).unwrap_or(0) * w)
+        .sum();
+    CHECK_CHARS[(sum % 11) as usize]
+}
+
+// 由地区码、出生日期和 3 位顺序码拼出完整 18 位号码，顺序码末位奇数为男、偶数为女
+pub fn compose_id_number(region: &str, birthday: NaiveDate, seq: u16) -> String {
+    let id17 = format!("{}{}{:03}", region, birthday.format("%Y%m%d"), seq % 1000);
+    let check = checksum_char(&id17);
+    format!("{}{}", id17, check)
+}
+
+// 校验 15 位或 18 位身份证号
+pub fn validate_id_number(input: &str, region_check: RegionCheck) -> Result<ParsedId, InvalidId> {
+    let id = input.trim();
+    let legacy = match id.len() {
+        18 => false,
+        15 => true,
+        len => {
+            return Err(invalid(
+                InvalidReason::Length,
+                format!("expected 15 or 18 characters, got {}", len),
+            ))
+        }
+    };
+
+    if !id.is_ascii() {
+        return Err(invalid(
+            InvalidReason::Format,
+            "contains non-ASCII characters",
+        ));
+    }
+    let body = if legacy { id } else { &id[..17] };
+    if !body.chars().all(|c| c.is_ascii_digit()) {
+        return Err(invalid(
+            InvalidReason::Format,
+            "contains non-digit characters",
+        ));
+    }
+    if !legacy {
+        let last = id.as_bytes()[17];
+        if !(last.is_ascii_digit() || last == b'X' || last == b'x') {
+            return Err(invalid(
+                InvalidReason::Format,
+                "last character must be a digit or X",
+            ));
+        }
+    }
+
+    let region = &id[..6];
+    let region_ok = match region_check {
+        RegionCheck::Strict => get_region_name(region).is_some(),
+        RegionCheck::Province => get_province_name(&region[..2]).is_some(),
+    };
+    if !region_ok {
+        return Err(invalid(
+            InvalidReason::Region,
+            format!("unknown region code {}", region),
+        ));
+    }
+
+    let birth_text = if legacy {
+        format!("19{}", &id[6..12])
+    } else {
+        id[6..14].to_string()
+    };
+    let birthday = NaiveDate::parse_from_str(&birth_text, "%Y%m%d").map_err(|_| {
+        invalid(
+            InvalidReason::Date,
+            format!("invalid birth date {}", birth_text),
+        )
+    })?;
+    if birthday.year() < 1800 || birthday > Local::now().date_naive() {
+        return Err(invalid(
+            InvalidReason::Date,
+            format!("birth date {} out of range", birthday),
+        ));
+    }
+
+    if !legacy {
+        let expected = checksum_char(&id[..17]);
+        let actual = id.as_bytes()[17].to_ascii_uppercase() as char;
+        if expected != actual {
+            return Err(invalid(
+                InvalidReason::Checksum,
+                format!("expected check digit {}, got {}", expected, actual),
+            ));
+        }
+    }
+
+    let seq_digit = if legacy { &id[14..15] } else { &id[16..17] };
+    let gender = if seq_digit.parse::<u32>().unwrap_or(0) % 2 == 0 {
+        "female"
+    } else {
+        "male"
+    };
+
+    Ok(ParsedId {
+        region: region.to_string(),
+        birthday,
+        gender,
+        legacy,
+    })
+}
+
+fn invalid(reason: InvalidReason, detail: impl Into<String>) -> InvalidId {
+    InvalidId {
+        reason,
+        detail: detail.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_valid_18_digit_id() {
+        let parsed = validate_id_number("11010519491231002X", RegionCheck::Strict).unwrap();
+        assert_eq!(parsed.region, "110105");
+        assert_eq!(
+            parsed.birthday,
+            NaiveDate::from_ymd_opt(1949, 12, 31).unwrap()
+        );
+        assert_eq!(parsed.gender, "female");
+        assert!(!parsed.legacy);
+
+        assert!(validate_id_number("11010519491231002x", RegionCheck::Strict).is_ok());
+    }
+
+    #[test]
+    fn accepts_valid_15_digit_id() {
+        let parsed = validate_id_number("110105491231001", RegionCheck::Strict).unwrap();
+        assert_eq!(
+            parsed.birthday,
+            NaiveDate::from_ymd_opt(1949, 12, 31).unwrap()
+        );
+        assert_eq!(parsed.gender, "male");
+        assert!(parsed.legacy);
+    }
+
+    #[test]
+    fn reports_each_reason() {
+        let reason = |id: &str| {
+            validate_id_number(id, RegionCheck::Strict)
+                .unwrap_err()
+                .reason
+        };
+
+        assert_eq!(reason("1101051949123100"), InvalidReason::Length);
+        assert_eq!(reason("11010519491231A02X"), InvalidReason::Format);
+        assert_eq!(reason("身份证号码六"), InvalidReason::Format);
+        assert_eq!(reason("99010519491231002X"), InvalidReason::Region);
+        assert_eq!(reason("110105194913310021"), InvalidReason::Date);
+        assert_eq!(reason("110105194912310021"), InvalidReason::Checksum);
+    }
+
+    #[test]
+    fn province_check_accepts_retired_area_codes() {
+        let id17 = "11019919491231002";
+        let id = format!("{}{}", id17, checksum_char(id17));
+
+        assert_eq!(
+            validate_id_number(&id, RegionCheck::Strict)
+                .unwrap_err()
+                .reason,
+            InvalidReason::Region
+        );
+        assert!(validate_id_number(&id, RegionCheck::Province).is_ok());
+    }
+}
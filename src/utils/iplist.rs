@@ -0,0 +1,61 @@
+use std::io::{self, BufRead};
+use std::net::{IpAddr, Ipv4Addr};
+
+// 命令行参数为空或为 - 时从 stdin 读取；每行取第一个可解析为 IP 的字段，
+// 兼容 "IP 次数" 之类的统计输出和访问日志。参数中的非法 IP 交给调用方构造错误
+pub fn collect_ips<E, F>(args: &[String], invalid: F) -> Result<Vec<IpAddr>, E>
+where
+    E: From<io::Error>,
+    F: Fn(String) -> E,
+{
+    if !args.is_empty() && args.iter().all(|arg| arg != "-") {
+        return args
+            .iter()
+            .map(|arg| arg.trim().parse().map_err(|_| invalid(arg.clone())))
+            .collect();
+    }
+
+    let mut ips = Vec::new();
+    for line in io::stdin().lock().lines() {
+        if let Some(ip) = first_ip(&line?) {
+            ips.push(ip);
+        }
+    }
+    Ok(ips)
+}
+
+pub fn first_ip(line: &str) -> Option<IpAddr> {
+    line.split(|c: char| c.is_whitespace() || matches!(c, ',' | ';' | '"' | '[' | ']'))
+        .find_map(|field| {
+            let field = field.trim();
+            field.parse::<IpAddr>().ok().or_else(|| {
+                // 兼容 1.2.3.4:443 这类带端口的写法
+                field
+                    .rsplit_once(':')
+                    .and_then(|(host, _)| host.parse::<Ipv4Addr>().ok())
+                    .map(IpAddr::V4)
+            })
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_ip_from_log_lines() {
+        let cases = [
+            (
+                "1.2.3.4 - - [10/Oct/2026:13:55:36] \"GET / HTTP/1.1\"",
+                "1.2.3.4",
+            ),
+            ("port 443 open on 10.0.0.5:443", "10.0.0.5"),
+            ("42,2001:db8::1,blocked", "2001:db8::1"),
+            ("8.8.8.8 120", "8.8.8.8"),
+        ];
+        for (line, expected) in cases {
+            assert_eq!(first_ip(line), Some(expected.parse().unwrap()), "{}", line);
+        }
+        assert_eq!(first_ip("no address here"), None);
+    }
+}
@@ -0,0 +1,244 @@
+// 最小的 mDNS（RFC 6762）服务发布和查询，send / recv 在局域网内互相发现时使用。
+// 只处理单个服务实例的 SRV 查询：发布方加入 224.0.0.251:5353 组播组并应答；
+// 查询方从临时端口发出查询，按“传统单播”规则（第 6.7 节）由发布方直接单播回复，
+// 因此查询方不需要占用 5353 端口，也能和系统自带的 Avahi / Bonjour 共存。
+
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket};
+use std::time::{Duration, Instant};
+
+use hickory_resolver::proto::op::{Message, MessageType, OpCode, Query};
+use hickory_resolver::proto::rr::rdata::{A, SRV};
+use hickory_resolver::proto::rr::{RData, Record, RecordType};
+use hickory_resolver::Name;
+use ipnet::IpNet;
+use rand::Rng;
+use socket2::{Domain, Protocol, Socket, Type};
+
+use crate::utils::netif;
+
+pub const MDNS_ADDR: Ipv4Addr = Ipv4Addr::new(224, 0, 0, 251);
+pub const MDNS_PORT: u16 = 5353;
+// 传统单播应答的 TTL 不应超过 10 秒
+const TTL: u32 = 10;
+// 查询期间重发的间隔
+const RETRY: Duration = Duration::from_secs(1);
+
+// instance.service.local.，例如 k7fq._rtoolkit-send._tcp.local.
+pub fn service_name(instance: &str, service: &str) -> Option<Name> {
+    let mut name = Name::from_ascii(format!("{}.{}.local.", instance, service)).ok()?;
+    name.set_fqdn(true);
+    Some(name)
+}
+
+// 已启用的非回环网卡上的 IPv4 地址，发布时作为 A 记录，查询时逐个网卡发出
+pub fn local_ipv4() -> Vec<Ipv4Addr> {
+    netif::interfaces()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|iface| iface.up && !iface.loopback)
+        .flat_map(|iface| iface.addrs)
+        .filter_map(|net| match net {
+            IpNet::V4(net) => Some(net.addr()),
+            IpNet::V6(_) => None,
+        })
+        .collect()
+}
+
+pub struct Responder {
+    socket: UdpSocket,
+}
+
+impl Responder {
+    // 和系统的 mDNS 服务共用 5353 端口，需要 SO_REUSEADDR（Unix 上还需要 SO_REUSEPORT）
+    pub fn bind() -> io::Result<Self> {
+        let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
+        socket.set_reuse_address(true)?;
+        #[cfg(all(unix, not(any(target_os = "solaris", target_os = "illumos"))))]
+        socket.set_reuse_port(true)?;
+        socket.bind(&SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, MDNS_PORT).into())?;
+        socket.join_multicast_v4(&MDNS_ADDR, &Ipv4Addr::UNSPECIFIED)?;
+        // 多网卡时在每块网卡上都加入组播组，已加入的会报错，忽略即可
+        for addr in local_ipv4() {
+            let _ = socket.join_multicast_v4(&MDNS_ADDR, &addr);
+        }
+        Ok(Self {
+            socket: socket.into(),
+        })
+    }
+
+    // 阻塞地应答对 name 的查询，通常放在后台线程中运行到进程退出
+    pub fn serve(self, name: Name, port: u16, addrs: Vec<Ipv4Addr>) {
+        let mut buf = [0u8; 9000];
+        loop {
+            let Ok((n, peer)) = self.socket.recv_from(&mut buf) else {
+                continue;
+            };
+            if let Some(response) = respond(&buf[..n], &name, port, &addrs) {
+                // 查询方用 5353 端口时按规范组播应答，否则单播回查询方
+                let target = if peer.port() == MDNS_PORT {
+                    SocketAddr::from((MDNS_ADDR, MDNS_PORT))
+                } else {
+                    peer
+                };
+                let _ = self.socket.send_to(&response, target);
+            }
+        }
+    }
+}
+
+// 查询中包含 name 的 SRV（或 ANY）问题时，返回 SRV 应答和目标主机的 A 记录
+fn respond(request: &[u8], name: &Name, port: u16, addrs: &[Ipv4Addr]) -> Option<Vec<u8>> {
+    let request = Message::from_vec(request).ok()?;
+    if request.message_type() != MessageType::Query || request.op_code() != OpCode::Query {
+        return None;
+    }
+    let query = request.queries().iter().find(|query| {
+        query.name() == name && matches!(query.query_type(), RecordType::SRV | RecordType::ANY)
+    })?;
+    // SRV 的目标主机名取实例名加前缀，避免和局域网里的真实主机重名
+    let instance = String::from_utf8_lossy(name.iter().next()?);
+    let host = Name::from_ascii(format!("rtoolkit-{}.local.", instance)).ok()?;
+
+    let mut response = Message::new();
+    response
+        .set_id(request.id())
+        .set_message_type(MessageType::Response)
+        .set_op_code(OpCode::Query)
+        .set_authoritative(true)
+        .add_query(Query::query(query.name().clone(), query.query_type()))
+        .add_answer(Record::from_rdata(
+            name.clone(),
+            TTL,
+            RData::SRV(SRV::new(0, 0, port, host.clone())),
+        ))
+        .add_additionals(
+            addrs
+                .iter()
+                .map(|addr| Record::from_rdata(host.clone(), TTL, RData::A(A(*addr)))),
+        );
+    response.to_vec().ok()
+}
+
+fn query_message(name: &Name, id: u16) -> Option<Vec<u8>> {
+    let mut message = Message::new();
+    message
+        .set_id(id)
+        .set_message_type(MessageType::Query)
+        .set_op_code(OpCode::Query)
+        .add_query(Query::query(name.clone(), RecordType::SRV));
+    message.to_vec().ok()
+}
+
+// 应答中的服务端口和目标主机的地址；没有 A 记录时只能使用应答的源地址
+fn parse_answer(response: &[u8], name: &Name, id: u16) -> Option<(u16, Vec<Ipv4Addr>)> {
+    let response = Message::from_vec(response).ok()?;
+    if response.message_type() != MessageType::Response || response.id() != id {
+        return None;
+    }
+    let (port, target) = response
+        .answers()
+        .iter()
+        .filter(|record| record.name() == name)
+        .find_map(|record| match record.data() {
+            RData::SRV(srv) => Some((srv.port(), srv.target().clone())),
+            _ => None,
+        })?;
+    let addrs = response
+        .additionals()
+        .iter()
+        .chain(response.answers())
+        .filter(|record| *record.name() == target)
+        .filter_map(|record| match record.data() {
+            RData::A(a) => Some(a.0),
+            _ => None,
+        })
+        .collect();
+    Some((port, addrs))
+}
+
+// 查询 name 对应的服务，返回可以尝试连接的地址（应答的源地址排在最前），超时返回空列表
+pub fn query(name: &Name, timeout: Duration) -> io::Result<Vec<SocketAddr>> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))?;
+    socket.set_multicast_loop_v4(true)?;
+    socket.set_multicast_ttl_v4(255)?;
+    let id = rand::rng().random::<u16>();
+    let packet = query_message(name, id)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid service name"))?;
+    let interfaces = local_ipv4();
+    let deadline = Instant::now() + timeout;
+    let mut buf = [0u8; 9000];
+    loop {
+        // 依次从每块网卡发出查询，最后再走默认路由
+        let sock = socket2::SockRef::from(&socket);
+        for addr in &interfaces {
+            if sock.set_multicast_if_v4(addr).is_ok() {
+                let _ = socket.send_to(&packet, (MDNS_ADDR, MDNS_PORT));
+            }
+        }
+        sock.set_multicast_if_v4(&Ipv4Addr::UNSPECIFIED)?;
+        socket.send_to(&packet, (MDNS_ADDR, MDNS_PORT))?;
+
+        let round_end = (Instant::now() + RETRY).min(deadline);
+        loop {
+            let remaining = round_end.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                break;
+            }
+            socket.set_read_timeout(Some(remaining))?;
+            let (n, peer) = match socket.recv_from(&mut buf) {
+                Ok(received) => received,
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                    ) =>
+                {
+                    break
+                }
+                Err(e) => return Err(e),
+            };
+            if let Some((port, addrs)) = parse_answer(&buf[..n], name, id) {
+                let source = SocketAddr::new(peer.ip(), port);
+                let mut candidates = vec![source];
+                candidates.extend(
+                    addrs
+                        .into_iter()
+                        .map(|addr| SocketAddr::new(IpAddr::V4(addr), port))
+                        .filter(|addr| *addr != source),
+                );
+                return Ok(candidates);
+            }
+        }
+        if Instant::now() >= deadline {
+            return Ok(Vec::new());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_and_respond() {
+        let name = service_name("k7fq", "_rtoolkit-send._tcp").unwrap();
+        let other = service_name("zzzz", "_rtoolkit-send._tcp").unwrap();
+        let addrs = [Ipv4Addr::new(192, 168, 1, 5), Ipv4Addr::new(10, 0, 0, 5)];
+
+        let query = query_message(&name, 0x1234).unwrap();
+        let response = respond(&query, &name, 40123, &addrs).unwrap();
+        assert_eq!(
+            parse_answer(&response, &name, 0x1234),
+            Some((40123, addrs.to_vec()))
+        );
+        // ID 不匹配或查询的是其他实例时忽略
+        assert_eq!(parse_answer(&response, &name, 0x4321), None);
+        assert_eq!(
+            respond(&query_message(&other, 1).unwrap(), &name, 40123, &addrs),
+            None
+        );
+        // 应答本身不会再被应答
+        assert_eq!(respond(&response, &name, 40123, &addrs), None);
+    }
+}
@@ -0,0 +1,287 @@
+// 进程内运行指标：全局 `--metrics-listen` 打开后以 Prometheus 文本格式在 /metrics 暴露，
+// `rtoolkit web` 同时在自身端口提供 /metrics。
+//
+// 计数用原子变量和互斥锁保护，扫描任务、Web 请求线程可以并发更新；未开启时只做计数、不对外暴露。
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Mutex, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+// 请求耗时直方图的桶上限（秒）
+const LATENCY_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static PROBES_TCP: AtomicU64 = AtomicU64::new(0);
+static PROBES_UDP: AtomicU64 = AtomicU64::new(0);
+static OPEN_PORTS: AtomicU64 = AtomicU64::new(0);
+static RECORDS: Mutex<BTreeMap<&'static str, u64>> = Mutex::new(BTreeMap::new());
+static REQUESTS: Mutex<BTreeMap<&'static str, Histogram>> = Mutex::new(BTreeMap::new());
+static STARTED: Mutex<Option<(Instant, u64)>> = Mutex::new(None);
+
+#[derive(Debug, Default, Clone)]
+struct Histogram {
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl Histogram {
+    fn observe(&mut self, seconds: f64) {
+        for (bucket, bound) in self.buckets.iter_mut().zip(LATENCY_BUCKETS) {
+            if seconds <= bound {
+                *bucket += 1;
+            }
+        }
+        self.count += 1;
+        self.sum += seconds;
+    }
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+// 发出一个端口探测（TCP 连接、SYN 包或 UDP 报文）
+pub fn probe_sent(udp: bool) {
+    let counter = if udp { &PROBES_UDP } else { &PROBES_TCP };
+    counter.fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn open_ports_found(count: usize) {
+    OPEN_PORTS.fetch_add(count as u64, Ordering::Relaxed);
+}
+
+// 生成类命令产出的记录数，按命令名分组
+pub fn records_generated(command: &'static str, count: usize) {
+    let mut records = RECORDS.lock().unwrap_or_else(PoisonError::into_inner);
+    *records.entry(command).or_insert(0) += count as u64;
+}
+
+// Web 请求耗时，handler 必须取自固定集合，避免标签数量随请求路径膨胀
+pub fn observe_request(handler: &'static str, elapsed: Duration) {
+    let mut requests = REQUESTS.lock().unwrap_or_else(PoisonError::into_inner);
+    requests
+        .entry(handler)
+        .or_default()
+        .observe(elapsed.as_secs_f64());
+}
+
+// 以 Prometheus 文本格式（0.0.4）输出当前指标
+pub fn render() -> String {
+    let mut out = String::new();
+    let started = *STARTED.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some((instant, epoch)) = started {
+        metric_header(
+            &mut out,
+            "rtoolkit_start_time_seconds",
+            "gauge",
+            "Unix time the process started.",
+        );
+        let _ = writeln!(out, "rtoolkit_start_time_seconds {}", epoch);
+        metric_header(
+            &mut out,
+            "rtoolkit_uptime_seconds",
+            "gauge",
+            "Seconds since the process started.",
+        );
+        let _ = writeln!(
+            out,
+            "rtoolkit_uptime_seconds {:.3}",
+            instant.elapsed().as_secs_f64()
+        );
+    }
+
+    metric_header(
+        &mut out,
+        "rtoolkit_probes_sent_total",
+        "counter",
+        "Port probes sent by port-scan and discover.",
+    );
+    for (protocol, counter) in [("tcp", &PROBES_TCP), ("udp", &PROBES_UDP)] {
+        let _ = writeln!(
+            out,
+            "rtoolkit_probes_sent_total{{protocol=\"{}\"}} {}",
+            protocol,
+            counter.load(Ordering::Relaxed)
+        );
+    }
+    metric_header(
+        &mut out,
+        "rtoolkit_open_ports_total",
+        "counter",
+        "Open ports found by scans.",
+    );
+    let _ = writeln!(
+        out,
+        "rtoolkit_open_ports_total {}",
+        OPEN_PORTS.load(Ordering::Relaxed)
+    );
+
+    metric_header(
+        &mut out,
+        "rtoolkit_records_generated_total",
+        "counter",
+        "Records produced by generator commands.",
+    );
+    for (command, count) in RECORDS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .iter()
+    {
+        let _ = writeln!(
+            out,
+            "rtoolkit_records_generated_total{{command=\"{}\"}} {}",
+            command, count
+        );
+    }
+
+    metric_header(
+        &mut out,
+        "rtoolkit_request_duration_seconds",
+        "histogram",
+        "Web request latency.",
+    );
+    for (handler, histogram) in REQUESTS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .iter()
+    {
+        for (bound, count) in LATENCY_BUCKETS.iter().zip(histogram.buckets) {
+            let _ = writeln!(
+                out,
+                "rtoolkit_request_duration_seconds_bucket{{handler=\"{}\",le=\"{}\"}} {}",
+                handler, bound, count
+            );
+        }
+        let _ = writeln!(
+            out,
+            "rtoolkit_request_duration_seconds_bucket{{handler=\"{}\",le=\"+Inf\"}} {}",
+            handler, histogram.count
+        );
+        let _ = writeln!(
+            out,
+            "rtoolkit_request_duration_seconds_sum{{handler=\"{}\"}} {}",
+            handler, histogram.sum
+        );
+        let _ = writeln!(
+            out,
+            "rtoolkit_request_duration_seconds_count{{handler=\"{}\"}} {}",
+            handler, histogram.count
+        );
+    }
+    out
+}
+
+fn metric_header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+// 打开指标：记录启动时间；`rtoolkit web` 据此在自身端口提供 /metrics
+pub fn enable() {
+    let epoch = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0);
+    *STARTED.lock().unwrap_or_else(PoisonError::into_inner) = Some((Instant::now(), epoch));
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+// 在后台线程监听 addr，只响应 GET /metrics，返回实际监听的地址
+pub fn serve(addr: &str) -> io::Result<SocketAddr> {
+    let listener = TcpListener::bind(addr)?;
+    let local = listener.local_addr()?;
+    enable();
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            if let Err(error) = handle_scrape(stream) {
+                eprintln!("metrics request failed: {}", error);
+            }
+        }
+    });
+    Ok(local)
+}
+
+fn handle_scrape(mut stream: TcpStream) -> io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // 丢弃其余请求头
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 2 {
+        line.clear();
+    }
+    let mut parts = request_line.split_whitespace();
+    let (status, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/metrics")) => ("200 OK", render()),
+        _ => ("404 Not Found", "not found\n".to_string()),
+    };
+    write_response(&mut stream, status, &body)
+}
+
+pub fn write_response<W: Write>(out: &mut W, status: &str, body: &str) -> io::Result<()> {
+    write!(
+        out,
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )?;
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metrics_endpoint_renders_prometheus_text() {
+        let addr = serve("127.0.0.1:0").unwrap();
+        probe_sent(false);
+        open_ports_found(2);
+        records_generated("idgen", 5);
+        observe_request("/test", Duration::from_millis(30));
+
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream
+            .write_all(b"GET /metrics HTTP/1.1\r\nHost: x\r\n\r\n")
+            .unwrap();
+        let mut response = String::new();
+        io::Read::read_to_string(&mut stream, &mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("# TYPE rtoolkit_probes_sent_total counter"));
+        // 其它测试也会扫描端口，计数只能断言下限
+        let value = |prefix: &str| -> u64 {
+            let line = response
+                .lines()
+                .find(|line| line.starts_with(prefix))
+                .unwrap();
+            line.rsplit(' ').next().unwrap().parse().unwrap()
+        };
+        assert!(value("rtoolkit_probes_sent_total{protocol=\"tcp\"}") >= 1);
+        assert!(value("rtoolkit_open_ports_total") >= 2);
+        assert!(value("rtoolkit_records_generated_total{command=\"idgen\"}") >= 5);
+        assert_eq!(
+            value("rtoolkit_request_duration_seconds_bucket{handler=\"/test\",le=\"0.05\"}"),
+            1
+        );
+        assert_eq!(
+            value("rtoolkit_request_duration_seconds_bucket{handler=\"/test\",le=\"0.025\"}"),
+            0
+        );
+
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+        let mut response = String::new();
+        io::Read::read_to_string(&mut stream, &mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 404"));
+    }
+}
@@ -1 +1,35 @@
+pub mod a11y;
 pub mod areas;
+pub mod arp;
+pub mod asciicast;
+pub mod dataset;
+pub mod duration;
+pub mod fdlimit;
+pub mod filter;
+pub mod format;
+pub mod http;
+pub mod icmp;
+pub mod idcard;
+pub mod iplist;
+pub mod mdns;
+pub mod metrics;
+pub mod names;
+pub mod netif;
+pub mod network;
+pub mod osguess;
+pub mod oui;
+pub mod output;
+pub mod pki;
+pub mod progress;
+pub mod proxy;
+pub mod ratelimit;
+pub mod record;
+pub mod scandb;
+pub mod script;
+pub mod services;
+pub mod sm2;
+pub mod sm3;
+pub mod sm4;
+pub mod sockets;
+pub mod tls;
+pub mod version;
@@ -0,0 +1,347 @@
+// 姓名生成：汉族姓名之外，按权重混入维吾尔族、藏族、蒙古族等少数民族常见的音译姓名，
+// 以及复姓、父名加祖名等超出常见长度的写法，用于测试对间隔号“·”、无姓氏、长姓名处理不当的系统。
+// 各民族的写法：
+// - 维吾尔族：本名·父名，子女的父名部分取父亲的本名
+// - 藏族：没有姓氏，由两到三个常用名组合
+// - 蒙古族：通常只有名字，较长的写法带氏族名前缀，例如 孛儿只斤·巴特尔
+
+use fake::faker::name::raw::LastName;
+use fake::locales::ZH_CN;
+use fake::Fake;
+use rand::seq::IndexedRandom;
+use rand::{rng, Rng};
+
+const MALE_GIVEN_CHARS: &[&str] = &[
+    "伟", "强", "磊", "军", "洋", "勇", "杰", "涛", "斌", "超", "明", "刚", "辉", "鹏", "飞", "鑫",
+    "波", "宇", "浩", "凯", "健", "俊", "帆", "旭", "龙", "峰", "建", "国", "志", "博", "文", "哲",
+];
+const FEMALE_GIVEN_CHARS: &[&str] = &[
+    "芳", "娜", "敏", "静", "丽", "艳", "娟", "霞", "秀", "玲", "婷", "雪", "慧", "颖", "琳", "倩",
+    "晶", "洁", "燕", "梅", "莉", "萍", "琴", "欣", "怡", "悦", "佳", "璐", "瑶", "雯", "思", "涵",
+];
+const COMPOUND_SURNAMES: &[&str] = &[
+    "欧阳", "司马", "上官", "诸葛", "东方", "皇甫", "尉迟", "公孙", "慕容", "令狐", "长孙", "宇文",
+    "司徒", "轩辕",
+];
+
+const UYGHUR_MALE: &[&str] = &[
+    "艾力",
+    "买买提",
+    "阿不都",
+    "吐尔逊",
+    "艾买提",
+    "亚森",
+    "热合曼",
+    "努尔",
+    "依明",
+    "塔依尔",
+    "库尔班",
+    "阿迪力",
+    "伊力哈木",
+    "艾尔肯",
+    "麦麦提明",
+];
+const UYGHUR_FEMALE: &[&str] = &[
+    "古丽",
+    "阿依古丽",
+    "热依拉",
+    "帕提古丽",
+    "米热古丽",
+    "迪丽娜尔",
+    "阿依努尔",
+    "再努尔",
+    "古丽娜尔",
+    "努尔古丽",
+];
+const UYGHUR_MALE_LONG: &[&str] = &[
+    "阿卜杜热合曼",
+    "阿卜杜克热木",
+    "麦麦提依明",
+    "阿不都热西提",
+    "艾合买提江",
+    "阿卜杜瓦依提",
+];
+const UYGHUR_FEMALE_LONG: &[&str] = &[
+    "热孜万古丽",
+    "阿依古丽巴哈尔",
+    "帕热旦木古丽",
+    "布威海丽其古丽",
+    "米热班古丽",
+];
+const TIBETAN_COMMON: &[&str] = &[
+    "扎西", "次仁", "格桑", "洛桑", "丹增", "尼玛", "达瓦", "索朗", "白玛", "旦增",
+];
+const TIBETAN_MALE: &[&str] = &[
+    "顿珠", "多吉", "平措", "旺堆", "嘉措", "强巴", "罗布", "朗杰", "顿旦",
+];
+const TIBETAN_FEMALE: &[&str] = &[
+    "卓玛", "拉姆", "央金", "德吉", "曲珍", "梅朵", "措姆", "普珍", "玉珍",
+];
+const MONGOLIAN_MALE: &[&str] = &[
+    "巴特尔",
+    "苏和",
+    "额尔敦",
+    "宝音",
+    "朝鲁",
+    "孟和",
+    "那顺",
+    "巴雅尔",
+    "乌力吉",
+    "阿拉坦",
+    "毕力格",
+    "特木尔",
+];
+const MONGOLIAN_FEMALE: &[&str] = &[
+    "其木格",
+    "萨仁",
+    "娜仁",
+    "高娃",
+    "乌兰",
+    "其其格",
+    "图雅",
+    "塔娜",
+    "阿拉坦其其格",
+];
+const MONGOLIAN_CLANS: &[&str] = &["孛儿只斤", "博尔济吉特", "乌梁海", "扎鲁特"];
+
+#[derive(thiserror::Error, Debug)]
+pub enum NameMixError {
+    #[error("invalid name mix entry '{0}', expected STYLE=WEIGHT, e.g. han=80,uyghur=20")]
+    InvalidEntry(String),
+    #[error("unknown name style '{0}', expected han, uyghur, tibetan or mongolian")]
+    UnknownStyle(String),
+    #[error("name mix weights must not all be 0")]
+    ZeroWeights,
+    #[error("invalid ratio '{0}', expected 0-1 or a percentage such as 20%")]
+    InvalidRatio(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NameStyle {
+    Han,
+    Uyghur,
+    Tibetan,
+    Mongolian,
+}
+
+impl NameStyle {
+    fn parse(raw: &str) -> Option<Self> {
+        match raw {
+            "han" => Some(NameStyle::Han),
+            "uyghur" => Some(NameStyle::Uyghur),
+            "tibetan" => Some(NameStyle::Tibetan),
+            "mongolian" => Some(NameStyle::Mongolian),
+            _ => None,
+        }
+    }
+}
+
+// 各写法的权重，例如 han=80,uyghur=10,tibetan=5,mongolian=5；未列出的写法权重为 0
+#[derive(Debug, Clone, PartialEq)]
+pub struct NameMix {
+    weights: Vec<(NameStyle, u32)>,
+}
+
+impl Default for NameMix {
+    fn default() -> Self {
+        Self {
+            weights: vec![(NameStyle::Han, 1)],
+        }
+    }
+}
+
+impl NameMix {
+    pub fn parse(spec: &str) -> Result<Self, NameMixError> {
+        let mut weights = Vec::new();
+        for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (style, weight) = entry
+                .split_once('=')
+                .ok_or_else(|| NameMixError::InvalidEntry(entry.to_string()))?;
+            let style = style.trim().to_ascii_lowercase();
+            let style = NameStyle::parse(&style).ok_or(NameMixError::UnknownStyle(style))?;
+            let weight = weight
+                .trim()
+                .trim_end_matches('%')
+                .parse::<u32>()
+                .map_err(|_| NameMixError::InvalidEntry(entry.to_string()))?;
+            weights.retain(|(existing, _)| *existing != style);
+            weights.push((style, weight));
+        }
+        if weights.iter().all(|(_, weight)| *weight == 0) {
+            return Err(NameMixError::ZeroWeights);
+        }
+        Ok(Self { weights })
+    }
+
+    pub fn pick(&self) -> NameStyle {
+        let total: u32 = self.weights.iter().map(|(_, weight)| weight).sum();
+        let mut roll = rng().random_range(0..total);
+        for &(style, weight) in &self.weights {
+            if roll < weight {
+                return style;
+            }
+            roll -= weight;
+        }
+        NameStyle::Han
+    }
+}
+
+// 解析 0-1 的小数或百分比，例如 0.2、20%
+pub fn parse_ratio(raw: &str) -> Result<f64, NameMixError> {
+    let raw = raw.trim();
+    let ratio = match raw.strip_suffix('%') {
+        Some(percent) => percent.trim().parse::<f64>().map(|value| value / 100.0),
+        None => raw.parse::<f64>(),
+    };
+    match ratio {
+        Ok(ratio) if (0.0..=1.0).contains(&ratio) => Ok(ratio),
+        _ => Err(NameMixError::InvalidRatio(raw.to_string())),
+    }
+}
+
+// 生成的姓名和可由子女继承的部分：汉族为姓氏，维吾尔族为本名（子女的父名），
+// 蒙古族为氏族名（没有时为空），藏族为空
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeneratedName {
+    pub full: String,
+    pub inherited: String,
+}
+
+// family 为从父亲继承的部分，为 None 或空时随机生成；long 时使用复姓、长音译名、三段藏名或带氏族名的写法
+pub fn generate(style: NameStyle, male: bool, family: Option<&str>, long: bool) -> GeneratedName {
+    let family = family.filter(|family| !family.is_empty());
+    let mut rng = rng();
+    let mut pick = |pool: &[&'static str]| pool.choose(&mut rng).copied().unwrap_or_default();
+    match style {
+        NameStyle::Han => {
+            let surname = match family {
+                Some(surname) => surname.to_string(),
+                None if long => pick(COMPOUND_SURNAMES).to_string(),
+                None => LastName(ZH_CN).fake(),
+            };
+            let given = if long {
+                han_given_chars(male, 2)
+            } else {
+                han_given_name(male)
+            };
+            GeneratedName {
+                full: format!("{}{}", surname, given),
+                inherited: surname,
+            }
+        }
+        NameStyle::Uyghur => {
+            let own = match (male, long) {
+                (true, false) => pick(UYGHUR_MALE),
+                (true, true) => pick(UYGHUR_MALE_LONG),
+                (false, false) => pick(UYGHUR_FEMALE),
+                (false, true) => pick(UYGHUR_FEMALE_LONG),
+            };
+            let father = match family {
+                Some(father) => father.to_string(),
+                None if long => pick(UYGHUR_MALE_LONG).to_string(),
+                None => pick(UYGHUR_MALE).to_string(),
+            };
+            GeneratedName {
+                full: format!("{}·{}", own, father),
+                inherited: own.to_string(),
+            }
+        }
+        NameStyle::Tibetan => {
+            let last = if male {
+                pick(TIBETAN_MALE)
+            } else {
+                pick(TIBETAN_FEMALE)
+            };
+            let mut full = pick(TIBETAN_COMMON).to_string();
+            if long {
+                full.push_str(pick(TIBETAN_COMMON));
+            }
+            full.push_str(last);
+            GeneratedName {
+                full,
+                inherited: String::new(),
+            }
+        }
+        NameStyle::Mongolian => {
+            let own = if male {
+                pick(MONGOLIAN_MALE)
+            } else {
+                pick(MONGOLIAN_FEMALE)
+            };
+            let clan = match family {
+                Some(clan) => clan.to_string(),
+                None if long => pick(MONGOLIAN_CLANS).to_string(),
+                None => String::new(),
+            };
+            let full = if clan.is_empty() {
+                own.to_string()
+            } else {
+                format!("{}·{}", clan, own)
+            };
+            GeneratedName {
+                full,
+                inherited: clan,
+            }
+        }
+    }
+}
+
+// 汉族名字：七成为两个字，其余为一个字
+pub fn han_given_name(male: bool) -> String {
+    let len = if rng().random_bool(0.7) { 2 } else { 1 };
+    han_given_chars(male, len)
+}
+
+fn han_given_chars(male: bool, len: usize) -> String {
+    let pool = if male {
+        MALE_GIVEN_CHARS
+    } else {
+        FEMALE_GIVEN_CHARS
+    };
+    let mut rng = rng();
+    (0..len)
+        .map(|_| pool.choose(&mut rng).copied().unwrap_or("明"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_name_mix_and_styles() {
+        let mix = NameMix::parse("han=0, Uyghur=3,mongolian=1,uyghur=1").unwrap();
+        assert_eq!(
+            mix.weights,
+            [
+                (NameStyle::Han, 0),
+                (NameStyle::Mongolian, 1),
+                (NameStyle::Uyghur, 1)
+            ]
+        );
+        assert!((0..50).all(|_| mix.pick() != NameStyle::Han));
+        assert!(matches!(
+            NameMix::parse("han=0"),
+            Err(NameMixError::ZeroWeights)
+        ));
+        assert!(NameMix::parse("korean=5").is_err());
+        assert_eq!(parse_ratio("20%").unwrap(), 0.2);
+        assert!(parse_ratio("1.5").is_err());
+
+        let father = generate(NameStyle::Uyghur, true, None, false);
+        let child = generate(NameStyle::Uyghur, false, Some(&father.inherited), true);
+        assert!(child.full.ends_with(&format!("·{}", father.inherited)));
+        assert!(child.full.chars().count() >= 7);
+        let tibetan = generate(NameStyle::Tibetan, false, Some("扎西"), true);
+        assert_eq!(tibetan.full.chars().count(), 6);
+        assert!(tibetan.inherited.is_empty());
+        let mongolian = generate(NameStyle::Mongolian, true, None, true);
+        assert!(mongolian.full.contains('·'));
+        let han = generate(NameStyle::Han, true, None, true);
+        assert_eq!(han.full.chars().count(), 4);
+        assert_eq!(
+            generate(NameStyle::Han, true, Some("王"), false).inherited,
+            "王"
+        );
+    }
+}
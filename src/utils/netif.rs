@@ -0,0 +1,695 @@
+// 读取本机网卡、默认网关、DNS 服务器和 ARP 缓存（类似 ip addr / ifconfig / ipconfig /all / arp -a）。
+// Unix 通过 getifaddrs 列出网卡，Linux 从 /sys/class/net 读取 MAC 和 MTU、解析 /proc/net 的路由表和 ARP 缓存，
+// macOS 调用 netstat -rn 取默认网关；Windows 解析 ipconfig /all（英文输出）；其他系统的 ARP 缓存解析 arp -a。
+
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, UdpSocket};
+#[cfg(not(target_os = "linux"))]
+use std::process::Command;
+
+use ipnet::IpNet;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Interface {
+    pub name: String,
+    pub up: bool,
+    pub loopback: bool,
+    pub mtu: Option<u32>,
+    pub mac: Option<[u8; 6]>,
+    // 地址和前缀长度；Windows 的 ipconfig 不给 IPv6 前缀，按 /64 记录
+    pub addrs: Vec<IpNet>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Gateway {
+    pub addr: IpAddr,
+    pub interface: Option<String>,
+}
+
+// ARP 缓存中已解析的 IPv4 邻居；Windows 的 arp -a 只按网卡地址分组，不给网卡名
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Neighbor {
+    pub addr: Ipv4Addr,
+    pub mac: [u8; 6],
+    pub interface: Option<String>,
+}
+
+#[cfg(unix)]
+pub fn interfaces() -> io::Result<Vec<Interface>> {
+    use std::ffi::CStr;
+
+    let mut head: *mut libc::ifaddrs = std::ptr::null_mut();
+    if unsafe { libc::getifaddrs(&mut head) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // getifaddrs 对每个地址返回一项，同一网卡的多项按名字合并
+    let mut list: Vec<Interface> = Vec::new();
+    let mut cursor = head;
+    while !cursor.is_null() {
+        let ifa = unsafe { &*cursor };
+        cursor = ifa.ifa_next;
+        let name = unsafe { CStr::from_ptr(ifa.ifa_name) }
+            .to_string_lossy()
+            .into_owned();
+        let index = match list.iter().position(|iface| iface.name == name) {
+            Some(index) => index,
+            None => {
+                list.push(Interface {
+                    name,
+                    up: false,
+                    loopback: false,
+                    mtu: None,
+                    mac: None,
+                    addrs: Vec::new(),
+                });
+                list.len() - 1
+            }
+        };
+        let iface = &mut list[index];
+        let flags = ifa.ifa_flags as libc::c_int;
+        iface.up = flags & libc::IFF_UP != 0 && flags & libc::IFF_RUNNING != 0;
+        iface.loopback = flags & libc::IFF_LOOPBACK != 0;
+        if ifa.ifa_addr.is_null() {
+            continue;
+        }
+        if let Some(ip) = unsafe { sockaddr_ip(ifa.ifa_addr) } {
+            let prefix = if ifa.ifa_netmask.is_null() {
+                None
+            } else {
+                unsafe { sockaddr_ip(ifa.ifa_netmask) }.map(mask_prefix)
+            };
+            let full = if ip.is_ipv4() { 32 } else { 128 };
+            if let Ok(net) = IpNet::new(ip, prefix.unwrap_or(full)) {
+                iface.addrs.push(net);
+            }
+            continue;
+        }
+        #[cfg(target_os = "macos")]
+        unsafe {
+            link_info(ifa, iface)
+        };
+    }
+    unsafe { libc::freeifaddrs(head) };
+
+    #[cfg(target_os = "linux")]
+    for iface in &mut list {
+        let dir = std::path::Path::new("/sys/class/net").join(&iface.name);
+        iface.mtu = std::fs::read_to_string(dir.join("mtu"))
+            .ok()
+            .and_then(|mtu| mtu.trim().parse().ok());
+        iface.mac = std::fs::read_to_string(dir.join("address"))
+            .ok()
+            .and_then(|mac| parse_mac(mac.trim()));
+    }
+    Ok(list)
+}
+
+#[cfg(windows)]
+pub fn interfaces() -> io::Result<Vec<Interface>> {
+    Ok(ipconfig()?
+        .into_iter()
+        .map(|adapter| adapter.iface)
+        .collect())
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn interfaces() -> io::Result<Vec<Interface>> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "listing network interfaces is not supported on this platform",
+    ))
+}
+
+// 默认路由的下一跳，按路由优先级排序；读取失败时返回空列表
+#[cfg(target_os = "linux")]
+pub fn default_gateways() -> Vec<Gateway> {
+    let mut gateways = std::fs::read_to_string("/proc/net/route")
+        .map(|text| parse_proc_route(&text))
+        .unwrap_or_default();
+    gateways.extend(
+        std::fs::read_to_string("/proc/net/ipv6_route")
+            .map(|text| parse_proc_ipv6_route(&text))
+            .unwrap_or_default(),
+    );
+    gateways
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+pub fn default_gateways() -> Vec<Gateway> {
+    Command::new("netstat")
+        .arg("-rn")
+        .output()
+        .map(|output| parse_netstat_routes(&String::from_utf8_lossy(&output.stdout)))
+        .unwrap_or_default()
+}
+
+#[cfg(windows)]
+pub fn default_gateways() -> Vec<Gateway> {
+    ipconfig()
+        .unwrap_or_default()
+        .into_iter()
+        .flat_map(|adapter| {
+            let name = adapter.iface.name;
+            adapter.gateways.into_iter().map(move |addr| Gateway {
+                addr,
+                interface: Some(name.clone()),
+            })
+        })
+        .collect()
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn default_gateways() -> Vec<Gateway> {
+    Vec::new()
+}
+
+// 系统配置的 DNS 服务器，去重并保持原有顺序
+#[cfg(unix)]
+pub fn dns_servers() -> Vec<IpAddr> {
+    let servers = std::fs::read_to_string("/etc/resolv.conf")
+        .map(|text| parse_resolv_conf(&text))
+        .unwrap_or_default();
+    // systemd-resolved 的本地存根 127.0.0.53 背后才是真正的上游
+    if !servers.is_empty() && servers.iter().all(|ip| *ip == Ipv4Addr::new(127, 0, 0, 53)) {
+        if let Ok(text) = std::fs::read_to_string("/run/systemd/resolve/resolv.conf") {
+            let upstream = parse_resolv_conf(&text);
+            if !upstream.is_empty() {
+                return upstream;
+            }
+        }
+    }
+    servers
+}
+
+#[cfg(windows)]
+pub fn dns_servers() -> Vec<IpAddr> {
+    let mut servers = Vec::new();
+    for adapter in ipconfig().unwrap_or_default() {
+        for server in adapter.dns {
+            if !servers.contains(&server) {
+                servers.push(server);
+            }
+        }
+    }
+    servers
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn dns_servers() -> Vec<IpAddr> {
+    Vec::new()
+}
+
+// 访问公网时使用的本机地址：对 UDP 套接字 connect 只查路由表，不发送任何数据包
+pub fn outbound_addr(v6: bool) -> Option<IpAddr> {
+    let (bind, target): (IpAddr, IpAddr) = if v6 {
+        (
+            Ipv6Addr::UNSPECIFIED.into(),
+            Ipv6Addr::new(0x2001, 0x4860, 0x4860, 0, 0, 0, 0, 0x8888).into(),
+        )
+    } else {
+        (
+            Ipv4Addr::UNSPECIFIED.into(),
+            Ipv4Addr::new(8, 8, 8, 8).into(),
+        )
+    };
+    let socket = UdpSocket::bind((bind, 0)).ok()?;
+    socket.connect((target, 53)).ok()?;
+    let ip = socket.local_addr().ok()?.ip();
+    (!ip.is_unspecified()).then_some(ip)
+}
+
+// 系统 ARP 缓存中已完成解析的项，未解析（incomplete）和广播 / 组播地址不返回
+#[cfg(target_os = "linux")]
+pub fn neighbors() -> io::Result<Vec<Neighbor>> {
+    Ok(parse_proc_arp(&std::fs::read_to_string("/proc/net/arp")?))
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn neighbors() -> io::Result<Vec<Neighbor>> {
+    // -n 不做反向解析，Windows 的 arp 本来就不解析且不认识 -n
+    let args: &[&str] = if cfg!(windows) { &["-a"] } else { &["-an"] };
+    let output = Command::new("arp").args(args).output()?;
+    Ok(parse_arp_output(&String::from_utf8_lossy(&output.stdout)))
+}
+
+#[cfg(unix)]
+unsafe fn sockaddr_ip(addr: *const libc::sockaddr) -> Option<IpAddr> {
+    match (*addr).sa_family as libc::c_int {
+        libc::AF_INET => {
+            let addr = &*(addr as *const libc::sockaddr_in);
+            Some(Ipv4Addr::from(u32::from_be(addr.sin_addr.s_addr)).into())
+        }
+        libc::AF_INET6 => {
+            let addr = &*(addr as *const libc::sockaddr_in6);
+            Some(Ipv6Addr::from(addr.sin6_addr.s6_addr).into())
+        }
+        _ => None,
+    }
+}
+
+// macOS 的 AF_LINK 项带 MAC 地址，ifa_data 指向带 MTU 的 if_data
+#[cfg(target_os = "macos")]
+unsafe fn link_info(ifa: &libc::ifaddrs, iface: &mut Interface) {
+    if (*ifa.ifa_addr).sa_family as libc::c_int != libc::AF_LINK {
+        return;
+    }
+    let link = &*(ifa.ifa_addr as *const libc::sockaddr_dl);
+    if link.sdl_alen == 6 {
+        // sdl_data 先是网卡名，之后才是地址
+        let data = link.sdl_data.as_ptr().add(link.sdl_nlen as usize) as *const u8;
+        let mut mac = [0u8; 6];
+        mac.copy_from_slice(std::slice::from_raw_parts(data, 6));
+        if mac != [0; 6] {
+            iface.mac = Some(mac);
+        }
+    }
+    if !ifa.ifa_data.is_null() {
+        iface.mtu = Some((*(ifa.ifa_data as *const libc::if_data)).ifi_mtu);
+    }
+}
+
+fn mask_prefix(mask: IpAddr) -> u8 {
+    match mask {
+        IpAddr::V4(mask) => u32::from(mask).leading_ones() as u8,
+        IpAddr::V6(mask) => u128::from(mask).leading_ones() as u8,
+    }
+}
+
+fn parse_mac(raw: &str) -> Option<[u8; 6]> {
+    let bytes: Vec<u8> = raw
+        .split([':', '-'])
+        .map(|byte| u8::from_str_radix(byte, 16).ok())
+        .collect::<Option<_>>()?;
+    let mac: [u8; 6] = bytes.try_into().ok()?;
+    // 回环和隧道网卡的地址为全 0
+    (mac != [0; 6]).then_some(mac)
+}
+
+// 去掉 fe80::1%eth0 这样的区域标识
+fn parse_ip(raw: &str) -> Option<IpAddr> {
+    raw.split('%').next()?.parse().ok()
+}
+
+// /proc/net/route：Iface Destination Gateway Flags RefCnt Use Metric Mask ...，地址为小端序十六进制
+#[cfg(any(target_os = "linux", test))]
+fn parse_proc_route(text: &str) -> Vec<Gateway> {
+    const RTF_UP: u32 = 0x1;
+    const RTF_GATEWAY: u32 = 0x2;
+    let mut routes: Vec<(u32, Gateway)> = text
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let hex = |index: usize| u32::from_str_radix(fields.get(index)?, 16).ok();
+            let (destination, gateway, flags, metric, mask) = (
+                hex(1)?,
+                hex(2)?,
+                hex(3)?,
+                fields.get(6)?.parse().ok()?,
+                hex(7)?,
+            );
+            if destination != 0
+                || mask != 0
+                || flags & (RTF_UP | RTF_GATEWAY) != RTF_UP | RTF_GATEWAY
+            {
+                return None;
+            }
+            Some((
+                metric,
+                Gateway {
+                    addr: Ipv4Addr::from(gateway.to_le_bytes()).into(),
+                    interface: Some(fields[0].to_string()),
+                },
+            ))
+        })
+        .collect();
+    routes.sort_by_key(|(metric, _)| *metric);
+    routes.into_iter().map(|(_, gateway)| gateway).collect()
+}
+
+// /proc/net/ipv6_route：目的 前缀 源 源前缀 下一跳 metric refcnt use flags iface，地址为 32 位十六进制
+#[cfg(any(target_os = "linux", test))]
+fn parse_proc_ipv6_route(text: &str) -> Vec<Gateway> {
+    let mut routes: Vec<(u32, Gateway)> = text
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 10 || fields[1] != "00" {
+                return None;
+            }
+            let destination = u128::from_str_radix(fields[0], 16).ok()?;
+            let next_hop = u128::from_str_radix(fields[4], 16).ok()?;
+            if destination != 0 || next_hop == 0 {
+                return None;
+            }
+            Some((
+                u32::from_str_radix(fields[5], 16).ok()?,
+                Gateway {
+                    addr: Ipv6Addr::from(next_hop).into(),
+                    interface: Some(fields[9].to_string()),
+                },
+            ))
+        })
+        .collect();
+    routes.sort_by_key(|(metric, _)| *metric);
+    routes.into_iter().map(|(_, gateway)| gateway).collect()
+}
+
+// netstat -rn：Destination Gateway Flags Netif Expire，只取 default 行；下一跳为 link#N 的直连路由跳过
+#[cfg(any(all(unix, not(target_os = "linux")), test))]
+fn parse_netstat_routes(text: &str) -> Vec<Gateway> {
+    text.lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.first() != Some(&"default") {
+                return None;
+            }
+            Some(Gateway {
+                addr: parse_ip(fields.get(1)?)?,
+                interface: fields.get(3).map(|name| name.to_string()),
+            })
+        })
+        .collect()
+}
+
+// /proc/net/arp：IP address, HW type, Flags, HW address, Mask, Device；Flags 含 0x2（ATF_COM）表示已解析
+#[cfg(any(target_os = "linux", test))]
+fn parse_proc_arp(text: &str) -> Vec<Neighbor> {
+    text.lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let flags = u32::from_str_radix(fields.get(2)?.trim_start_matches("0x"), 16).ok()?;
+            if flags & 0x2 == 0 {
+                return None;
+            }
+            neighbor(fields.first()?, fields.get(3)?, fields.get(5).copied())
+        })
+        .collect()
+}
+
+// BSD / macOS 的 arp -an："? (192.168.1.1) at 0:11:22:33:44:55 on en0 ifscope [ethernet]"；
+// Windows 的 arp -a："  192.168.1.1           00-11-22-33-44-55     dynamic"
+#[cfg(any(not(target_os = "linux"), test))]
+fn parse_arp_output(text: &str) -> Vec<Neighbor> {
+    text.lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields.iter().position(|field| *field == "at") {
+                Some(at) => {
+                    let ip = fields.get(at.checked_sub(1)?)?;
+                    let interface = fields
+                        .iter()
+                        .position(|field| *field == "on")
+                        .and_then(|on| fields.get(on + 1).copied());
+                    neighbor(
+                        ip.trim_start_matches('(').trim_end_matches(')'),
+                        fields.get(at + 1)?,
+                        interface,
+                    )
+                }
+                None => neighbor(fields.first()?, fields.get(1)?, None),
+            }
+        })
+        .collect()
+}
+
+fn neighbor(ip: &str, mac: &str, interface: Option<&str>) -> Option<Neighbor> {
+    let mac = parse_mac(mac)?;
+    // 组播位为 1 的是广播或组播的静态项，不是真实主机
+    if mac[0] & 0x01 != 0 {
+        return None;
+    }
+    Some(Neighbor {
+        addr: ip.parse().ok()?,
+        mac,
+        interface: interface.map(str::to_string),
+    })
+}
+
+#[cfg(any(unix, test))]
+fn parse_resolv_conf(text: &str) -> Vec<IpAddr> {
+    let mut servers = Vec::new();
+    for line in text.lines() {
+        let mut fields = line.split_whitespace();
+        if fields.next() != Some("nameserver") {
+            continue;
+        }
+        if let Some(ip) = fields.next().and_then(parse_ip) {
+            if !servers.contains(&ip) {
+                servers.push(ip);
+            }
+        }
+    }
+    servers
+}
+
+#[cfg(any(windows, test))]
+#[derive(Debug, Default)]
+struct Adapter {
+    iface: Interface,
+    gateways: Vec<IpAddr>,
+    dns: Vec<IpAddr>,
+}
+
+#[cfg(windows)]
+fn ipconfig() -> io::Result<Vec<Adapter>> {
+    let output = Command::new("ipconfig").arg("/all").output()?;
+    Ok(parse_ipconfig(&String::from_utf8_lossy(&output.stdout)))
+}
+
+#[cfg(any(windows, test))]
+impl Default for Interface {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            // ipconfig 只对断开的网卡输出 Media State
+            up: true,
+            loopback: false,
+            mtu: None,
+            mac: None,
+            addrs: Vec::new(),
+        }
+    }
+}
+
+// ipconfig /all：顶格的 "Ethernet adapter 以太网:" 开始一块网卡，缩进的 "键 . . . : 值" 为属性，
+// 多个值的属性（DNS Servers、Default Gateway）后续值单独成行
+#[cfg(any(windows, test))]
+fn parse_ipconfig(text: &str) -> Vec<Adapter> {
+    let mut adapters: Vec<Adapter> = Vec::new();
+    let mut key = String::new();
+    for line in text.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if !line.starts_with(' ') {
+            key.clear();
+            if let Some((_, name)) = line
+                .trim_end()
+                .trim_end_matches(':')
+                .split_once(" adapter ")
+            {
+                adapters.push(Adapter {
+                    iface: Interface {
+                        name: name.to_string(),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                });
+            }
+            continue;
+        }
+        let Some(adapter) = adapters.last_mut() else {
+            continue;
+        };
+        let value = match line.split_once(" : ") {
+            Some((raw_key, value)) => {
+                key = raw_key.trim().trim_end_matches(['.', ' ']).to_string();
+                value.trim()
+            }
+            None => line.trim(),
+        };
+        // 地址后面带 (Preferred) / (Deprecated) 等状态
+        let value = value.split('(').next().unwrap_or_default().trim();
+        let iface = &mut adapter.iface;
+        match key.as_str() {
+            "Media State" => iface.up = !value.contains("disconnected"),
+            "Physical Address" => iface.mac = parse_mac(value),
+            "Description" => iface.loopback = value.contains("Loopback"),
+            "IPv4 Address" | "Autoconfiguration IPv4 Address" => {
+                if let Some(ip) = parse_ip(value) {
+                    iface.addrs.push(ip.into());
+                }
+            }
+            "IPv6 Address" | "Temporary IPv6 Address" | "Link-local IPv6 Address" => {
+                if let Some(ip) = parse_ip(value) {
+                    iface
+                        .addrs
+                        .push(IpNet::new(ip, 64).unwrap_or_else(|_| ip.into()));
+                }
+            }
+            // 掩码紧跟在 IPv4 地址之后，补到最近一个 IPv4 地址上
+            "Subnet Mask" => {
+                if let (Ok(mask), Some(net)) = (
+                    value.parse::<Ipv4Addr>(),
+                    iface
+                        .addrs
+                        .iter_mut()
+                        .rev()
+                        .find(|net| net.addr().is_ipv4()),
+                ) {
+                    let prefix = mask_prefix(mask.into());
+                    *net = IpNet::new(net.addr(), prefix).unwrap_or(*net);
+                }
+            }
+            "Default Gateway" => adapter.gateways.extend(parse_ip(value)),
+            "DNS Servers" => adapter.dns.extend(parse_ip(value)),
+            _ => {}
+        }
+    }
+    adapters
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_route_tables() {
+        let route =
+            "Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\t\tMTU\tWindow\tIRTT\n\
+            wlan0\t00000000\t0101A8C0\t0003\t0\t0\t600\t00000000\t0\t0\t0\n\
+            eth0\t00000000\tFE01A8C0\t0003\t0\t0\t100\t00000000\t0\t0\t0\n\
+            eth0\t0001A8C0\t00000000\t0001\t0\t0\t100\t00FFFFFF\t0\t0\t0\n";
+        let gateways = parse_proc_route(route);
+        assert_eq!(
+            gateways,
+            vec![
+                Gateway {
+                    addr: "192.168.1.254".parse().unwrap(),
+                    interface: Some("eth0".into()),
+                },
+                Gateway {
+                    addr: "192.168.1.1".parse().unwrap(),
+                    interface: Some("wlan0".into()),
+                },
+            ]
+        );
+        let ipv6 = "00000000000000000000000000000000 00 00000000000000000000000000000000 00 fe800000000000000000000000000001 00000400 00000001 00000000 00000003 eth0\n\
+            00000000000000000000000000000000 00 00000000000000000000000000000000 00 00000000000000000000000000000000 ffffffff 00000001 00000000 00200200 lo\n";
+        assert_eq!(
+            parse_proc_ipv6_route(ipv6),
+            vec![Gateway {
+                addr: "fe80::1".parse().unwrap(),
+                interface: Some("eth0".into()),
+            }]
+        );
+        let netstat = "Routing tables\n\nInternet:\nDestination        Gateway            Flags           Netif Expire\n\
+            default            192.168.1.1        UGScg             en0\n\
+            default            link#17            UCSIg         bridge0      !\n\n\
+            Internet6:\ndefault                                 fe80::1%en0                             UGcg              en0\n";
+        assert_eq!(
+            parse_netstat_routes(netstat),
+            vec![
+                Gateway {
+                    addr: "192.168.1.1".parse().unwrap(),
+                    interface: Some("en0".into()),
+                },
+                Gateway {
+                    addr: "fe80::1".parse().unwrap(),
+                    interface: Some("en0".into()),
+                },
+            ]
+        );
+        assert_eq!(
+            parse_resolv_conf("# generated\nnameserver 1.1.1.1\nnameserver fe80::1%eth0\nsearch lan\nnameserver 1.1.1.1\n"),
+            vec![
+                "1.1.1.1".parse::<IpAddr>().unwrap(),
+                "fe80::1".parse().unwrap()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_arp_tables() {
+        let proc_arp =
+            "IP address       HW type     Flags       HW address            Mask     Device\n\
+            192.168.1.1      0x1         0x2         a0:b1:c2:d3:e4:f5     *        eth0\n\
+            192.168.1.77     0x1         0x0         00:00:00:00:00:00     *        eth0\n";
+        assert_eq!(
+            parse_proc_arp(proc_arp),
+            vec![Neighbor {
+                addr: Ipv4Addr::new(192, 168, 1, 1),
+                mac: [0xa0, 0xb1, 0xc2, 0xd3, 0xe4, 0xf5],
+                interface: Some("eth0".into()),
+            }]
+        );
+
+        let bsd = "? (192.168.1.1) at 0:11:22:33:44:5 on en0 ifscope [ethernet]\n\
+            ? (192.168.1.9) at (incomplete) on en0 ifscope [ethernet]\n\
+            ? (224.0.0.251) at 1:0:5e:0:0:fb on en0 ifscope permanent [ethernet]\n";
+        assert_eq!(
+            parse_arp_output(bsd),
+            vec![Neighbor {
+                addr: Ipv4Addr::new(192, 168, 1, 1),
+                mac: [0x00, 0x11, 0x22, 0x33, 0x44, 0x05],
+                interface: Some("en0".into()),
+            }]
+        );
+
+        let windows = "\r\nInterface: 192.168.1.23 --- 0xb\r\n  \
+            Internet Address      Physical Address      Type\r\n  \
+            192.168.1.1           00-11-22-33-44-55     dynamic\r\n  \
+            192.168.1.255         ff-ff-ff-ff-ff-ff     static\r\n";
+        let neighbors = parse_arp_output(windows);
+        assert_eq!(neighbors.len(), 1);
+        assert_eq!(neighbors[0].addr, Ipv4Addr::new(192, 168, 1, 1));
+        assert_eq!(neighbors[0].interface, None);
+    }
+
+    #[test]
+    fn test_parse_ipconfig() {
+        let text = "\r\nWindows IP Configuration\r\n\r\n   Host Name . . . . . . . . . . . . : DESKTOP\r\n\r\n\
+            Ethernet adapter Ethernet:\r\n\r\n\
+            \x20  Description . . . . . . . . . . . : Intel(R) Ethernet Connection\r\n\
+            \x20  Physical Address. . . . . . . . . : 00-1A-2B-3C-4D-5E\r\n\
+            \x20  Link-local IPv6 Address . . . . . : fe80::1c2d:3e4f:5a6b:7c8d%12(Preferred) \r\n\
+            \x20  IPv4 Address. . . . . . . . . . . : 192.168.1.23(Preferred) \r\n\
+            \x20  Subnet Mask . . . . . . . . . . . : 255.255.255.0\r\n\
+            \x20  Default Gateway . . . . . . . . . : fe80::1%12\r\n\
+            \x20                                      192.168.1.1\r\n\
+            \x20  DNS Servers . . . . . . . . . . . : 192.168.1.1\r\n\
+            \x20                                      8.8.8.8\r\n\
+            \x20  NetBIOS over Tcpip. . . . . . . . : Enabled\r\n\r\n\
+            Wireless LAN adapter Wi-Fi:\r\n\r\n\
+            \x20  Media State . . . . . . . . . . . : Media disconnected\r\n\
+            \x20  Physical Address. . . . . . . . . : 00-1A-2B-3C-4D-5F\r\n";
+        let adapters = parse_ipconfig(text);
+        assert_eq!(adapters.len(), 2);
+        let ethernet = &adapters[0];
+        assert_eq!(ethernet.iface.name, "Ethernet");
+        assert!(ethernet.iface.up);
+        assert_eq!(
+            ethernet.iface.mac,
+            Some([0x00, 0x1a, 0x2b, 0x3c, 0x4d, 0x5e])
+        );
+        assert_eq!(
+            ethernet.iface.addrs,
+            vec![
+                "fe80::1c2d:3e4f:5a6b:7c8d/64".parse::<IpNet>().unwrap(),
+                "192.168.1.23/24".parse().unwrap()
+            ]
+        );
+        assert_eq!(
+            ethernet.gateways,
+            vec![
+                "fe80::1".parse::<IpAddr>().unwrap(),
+                "192.168.1.1".parse().unwrap()
+            ]
+        );
+        assert_eq!(ethernet.dns.len(), 2);
+        assert_eq!(adapters[1].iface.name, "Wi-Fi");
+        assert!(!adapters[1].iface.up);
+    }
+}
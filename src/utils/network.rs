@@ -0,0 +1,99 @@
+// 网络访问闸门：全局 `--offline` 打开后禁止访问本机以外的地址。
+// 必须联网的操作调用 check_* 尽早失败；可选的补充信息（反向 DNS、DNSBL 等）调用 skip 跳过。
+// 回环地址和 localhost 不算联网，离线实验环境里仍可扫描、收发本机服务。
+
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum NetworkError {
+    #[error("{what} needs network access to {target}, which --offline forbids")]
+    Offline { what: String, target: String },
+}
+
+pub fn set_offline(offline: bool) {
+    OFFLINE.store(offline, Ordering::Relaxed);
+}
+
+pub fn is_offline() -> bool {
+    OFFLINE.load(Ordering::Relaxed)
+}
+
+// 无论目标是什么都必须联网的操作
+pub fn require(what: &str, target: &str) -> Result<(), NetworkError> {
+    if is_offline() {
+        return Err(offline(what, target));
+    }
+    Ok(())
+}
+
+// 主机可以是 IP（IPv6 可带方括号或区域 ID）、localhost 或主机名；主机名需要 DNS，离线时拒绝
+pub fn check_host(what: &str, host: &str) -> Result<(), NetworkError> {
+    if is_loopback_host(host) {
+        return Ok(());
+    }
+    require(what, host)
+}
+
+// host:port 形式的地址，IPv6 需带方括号，例如 [::1]:514
+pub fn check_endpoint(what: &str, endpoint: &str) -> Result<(), NetworkError> {
+    let host = endpoint.rsplit_once(':').map_or(endpoint, |(host, _)| host);
+    if is_loopback_host(host) {
+        return Ok(());
+    }
+    require(what, endpoint)
+}
+
+// 监听或连接的具体地址；0.0.0.0 / :: 会接受外部连接，离线时同样拒绝
+pub fn check_addr(what: &str, addr: IpAddr) -> Result<(), NetworkError> {
+    if addr.is_loopback() {
+        return Ok(());
+    }
+    require(what, &addr.to_string())
+}
+
+// 可选的联网步骤：离线时打印提示并返回 true，调用方跳过该步骤
+pub fn skip(what: &str) -> bool {
+    if is_offline() {
+        eprintln!("warning: --offline is set, skipping {}", what);
+    }
+    is_offline()
+}
+
+fn is_loopback_host(host: &str) -> bool {
+    let host = host.trim().trim_start_matches('[').trim_end_matches(']');
+    let ip = host.split_once('%').map_or(host, |(ip, _)| ip);
+    match ip.parse::<IpAddr>() {
+        Ok(addr) => addr.is_loopback(),
+        Err(_) => host.eq_ignore_ascii_case("localhost"),
+    }
+}
+
+fn offline(what: &str, target: &str) -> NetworkError {
+    NetworkError::Offline {
+        what: what.to_string(),
+        target: target.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 不切换全局开关，避免影响并行运行的其它测试
+    #[test]
+    fn test_loopback_hosts_stay_allowed() {
+        for host in ["127.0.0.1", "127.8.0.1", "::1", "[::1]", "LocalHost"] {
+            assert!(is_loopback_host(host), "{}", host);
+        }
+        for host in ["10.0.0.1", "example.com", "fe80::1%lo", "0.0.0.0"] {
+            assert!(!is_loopback_host(host), "{}", host);
+        }
+        assert_eq!(
+            offline("snmp", "10.0.0.1").to_string(),
+            "snmp needs network access to 10.0.0.1, which --offline forbids"
+        );
+    }
+}
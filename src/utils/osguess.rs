@@ -0,0 +1,179 @@
+// 启发式的操作系统猜测：由 ping 回包的 TTL 推算初始 TTL（常见 64 / 128 / 255）和经过的跳数，
+// 再结合 TCP 连接中对端通告的窗口扩大因子区分 Linux（通常为 7 及以上）、Windows（8）和 macOS / BSD（6 及以下）。
+// 这两项都能被中间设备和系统参数改变，结果只是猜测，输出时明确标注。
+
+use std::time::Duration;
+
+use serde::Serialize;
+use tokio::process::Command;
+
+// 常见系统的初始 TTL
+const INITIAL_TTLS: [u8; 4] = [32, 64, 128, 255];
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OsGuess {
+    pub os: &'static str,
+    // ping 回包中观察到的 TTL
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<u8>,
+    // 按初始 TTL 推算的跳数
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hops: Option<u8>,
+    // 对端 SYN/ACK 中的窗口大小，按协议规定不经扩大因子换算
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub window: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub window_scale: Option<u8>,
+}
+
+impl OsGuess {
+    pub fn describe(&self) -> String {
+        let mut evidence = Vec::new();
+        if let (Some(ttl), Some(hops)) = (self.ttl, self.hops) {
+            evidence.push(format!("TTL {}, {} hops", ttl, hops));
+        }
+        if let Some(window) = self.window {
+            evidence.push(format!("window {}", window));
+        }
+        if let Some(scale) = self.window_scale {
+            evidence.push(format!("window scale {}", scale));
+        }
+        format!("{} ({})", self.os, evidence.join(", "))
+    }
+}
+
+// TCP 连接中对端的窗口信息，scale 为 None 表示没有协商窗口扩大选项
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TcpWindow {
+    pub window: u32,
+    pub scale: Option<u8>,
+}
+
+pub fn guess(ttl: Option<u8>, window: Option<TcpWindow>) -> Option<OsGuess> {
+    let initial = ttl.and_then(|ttl| INITIAL_TTLS.into_iter().find(|&initial| ttl <= initial));
+    let scale = window.and_then(|window| window.scale);
+    let os = match (initial, scale) {
+        (Some(64), Some(scale)) if scale <= 6 => "macOS / BSD",
+        (Some(64), Some(_)) => "Linux",
+        (Some(64), None) => "Linux / Unix",
+        (Some(128), _) => "Windows",
+        (Some(255), _) => "network device / Solaris",
+        (Some(_), _) => "embedded / legacy",
+        (None, Some(8)) => "Windows",
+        (None, Some(scale)) if scale <= 6 => "macOS / BSD",
+        (None, Some(_)) => "Linux",
+        (None, None) => return None,
+    };
+    Some(OsGuess {
+        os,
+        ttl,
+        hops: ttl.zip(initial).map(|(ttl, initial)| initial - ttl),
+        window: window.map(|window| window.window),
+        window_scale: scale,
+    })
+}
+
+// 调用系统的 ping 发送一个回显请求，读取回包的 TTL；没有 ping 命令或无响应时为 None
+pub async fn ping_ttl(host: &str, wait: Duration, extra: &[String]) -> Option<u8> {
+    let mut command = Command::new("ping");
+    if cfg!(windows) {
+        command.args(["-n", "1"]);
+    } else {
+        command.args(["-c", "1"]);
+    }
+    command.args(extra).arg(host).kill_on_drop(true);
+    let output = tokio::time::timeout(wait, command.output())
+        .await
+        .ok()?
+        .ok()?;
+    parse_ttl(&String::from_utf8_lossy(&output.stdout))
+}
+
+// 兼容 Linux / macOS 的 ttl=64、Windows 的 TTL=128 和 macOS ping6 的 hlim=64
+fn parse_ttl(output: &str) -> Option<u8> {
+    let lower = output.to_ascii_lowercase();
+    ["ttl=", "hlim="].into_iter().find_map(|key| {
+        let start = lower.find(key)? + key.len();
+        let digits: String = lower[start..]
+            .chars()
+            .take_while(char::is_ascii_digit)
+            .collect();
+        digits.parse().ok()
+    })
+}
+
+// 读取刚建立连接的 TCP_INFO：对端发送数据前 tcpi_snd_wnd 仍是 SYN/ACK 中的窗口，
+// tcpi_snd_wscale 为对端的窗口扩大因子
+#[cfg(target_os = "linux")]
+pub fn peer_window(stream: &tokio::net::TcpStream) -> Option<TcpWindow> {
+    use std::os::fd::AsRawFd;
+
+    // libc 的 tcp_info 只定义到 tcpi_total_retrans，按内核头文件的布局读取到 tcpi_snd_wnd（Linux 5.4+）
+    const OPTIONS_OFFSET: usize = 5;
+    const WSCALE_OFFSET: usize = 6;
+    const SND_WND_OFFSET: usize = 228;
+    const TCPI_OPT_WSCALE: u8 = 4;
+    let mut buf = [0u8; SND_WND_OFFSET + 4];
+    let mut len = buf.len() as libc::socklen_t;
+    let ret = unsafe {
+        libc::getsockopt(
+            stream.as_raw_fd(),
+            libc::IPPROTO_TCP,
+            libc::TCP_INFO,
+            buf.as_mut_ptr().cast(),
+            &mut len,
+        )
+    };
+    if ret != 0 || (len as usize) < buf.len() {
+        return None;
+    }
+    let window = u32::from_ne_bytes(buf[SND_WND_OFFSET..].try_into().ok()?);
+    let scale = (buf[OPTIONS_OFFSET] & TCPI_OPT_WSCALE != 0).then_some(buf[WSCALE_OFFSET] & 0x0f);
+    Some(TcpWindow { window, scale })
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn peer_window(_stream: &tokio::net::TcpStream) -> Option<TcpWindow> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guess_from_ttl_and_window_scale() {
+        let window = |scale| {
+            Some(TcpWindow {
+                window: 65535,
+                scale,
+            })
+        };
+        let linux = guess(Some(61), window(Some(7))).unwrap();
+        assert_eq!((linux.os, linux.hops), ("Linux", Some(3)));
+        assert_eq!(
+            linux.describe(),
+            "Linux (TTL 61, 3 hops, window 65535, window scale 7)"
+        );
+        assert_eq!(guess(Some(64), window(Some(6))).unwrap().os, "macOS / BSD");
+        assert_eq!(guess(Some(64), None).unwrap().os, "Linux / Unix");
+        assert_eq!(guess(Some(117), window(Some(8))).unwrap().os, "Windows");
+        assert_eq!(
+            guess(Some(250), None).unwrap().os,
+            "network device / Solaris"
+        );
+        assert_eq!(guess(None, window(Some(8))).unwrap().os, "Windows");
+        assert_eq!(guess(None, window(None)), None);
+
+        assert_eq!(
+            parse_ttl("64 bytes from 10.0.0.1: icmp_seq=1 ttl=63 time=0.4 ms"),
+            Some(63)
+        );
+        assert_eq!(
+            parse_ttl("Reply from 10.0.0.1: bytes=32 time<1ms TTL=128"),
+            Some(128)
+        );
+        assert_eq!(parse_ttl("16 bytes from ::1, icmp_seq=0 hlim=64"), Some(64));
+        assert_eq!(parse_ttl("Request timed out."), None);
+    }
+}
@@ -0,0 +1,62 @@
+// MAC 地址前 24 位（OUI）与厂商名的对照表，数据为 IEEE MA-L 注册表中常见网卡、网络设备、
+// 虚拟化平台和 IoT 芯片厂商的精选子集；另补充了 QEMU、Docker 等惯用的本地管理前缀。
+// 需要完整列表时由调用方加载 IEEE 发布的 oui.txt / oui.csv。
+//
+// 表在首次查询时解析一次，之后只读。
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+const OUI_CSV: &str = include_str!("../../data/oui.csv");
+
+static VENDORS: OnceLock<HashMap<[u8; 3], &'static str>> = OnceLock::new();
+
+fn table() -> &'static HashMap<[u8; 3], &'static str> {
+    VENDORS.get_or_init(|| {
+        OUI_CSV
+            .lines()
+            .skip(1)
+            .filter_map(|line| {
+                let (prefix, vendor) = line.split_once(',')?;
+                Some((parse_prefix(prefix)?, vendor))
+            })
+            .collect()
+    })
+}
+
+// 内置表中 OUI 对应的厂商名
+pub fn vendor(oui: [u8; 3]) -> Option<&'static str> {
+    table().get(&oui).copied()
+}
+
+// 6 位十六进制前缀，允许 : - . 分隔，例如 00:1A:11、00-1A-11、001A11
+pub fn parse_prefix(raw: &str) -> Option<[u8; 3]> {
+    let hex: String = raw
+        .trim()
+        .chars()
+        .filter(|c| !matches!(c, ':' | '-' | '.'))
+        .collect();
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let value = u32::from_str_radix(&hex, 16).ok()?;
+    let [_, a, b, c] = value.to_be_bytes();
+    Some([a, b, c])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vendor_lookup() {
+        assert_eq!(vendor([0x00, 0x00, 0x0c]), Some("Cisco Systems"));
+        assert_eq!(vendor([0xb8, 0x27, 0xeb]), Some("Raspberry Pi Foundation"));
+        assert_eq!(vendor([0x00, 0x50, 0x56]), Some("VMware"));
+        assert_eq!(vendor([0x12, 0x34, 0x56]), None);
+        assert!(table().len() > 300);
+        assert_eq!(parse_prefix("00-1a-11"), Some([0x00, 0x1a, 0x11]));
+        assert_eq!(parse_prefix("001A11"), Some([0x00, 0x1a, 0x11]));
+        assert_eq!(parse_prefix("00:1A"), None);
+    }
+}
@@ -0,0 +1,434 @@
+// 结构化输出层：全局 `--where`、`--fields`、`--rename` 由命令行入口注入到支持的命令，
+// 命令写出 JSON / CSV 记录前统一交给这里过滤和整形。
+//
+// `--fields` 按给定顺序挑选字段，支持 `a.b` 取嵌套字段；`--rename old=new` 修改输出的字段名
+// （对 --fields 挑选后的名字生效）。不存在的字段输出为 null / 空单元格。
+// `--map-script` 最先执行，--where / --fields / --rename 作用于脚本处理后的记录。
+
+use std::io::Write;
+use std::sync::Arc;
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::utils::filter::Filter;
+use crate::utils::script::MapScript;
+
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum OutputError {
+    #[error("invalid --rename '{0}', expected old=new")]
+    InvalidRename(String),
+    #[error("--fields must not be empty")]
+    EmptyFields,
+    #[error("--map-script {path}: {message}")]
+    Script { path: String, message: String },
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RecordOutput {
+    filter: Option<Filter>,
+    fields: Option<Vec<String>>,
+    renames: Vec<(String, String)>,
+    script: Option<Arc<MapScript>>,
+}
+
+impl RecordOutput {
+    pub fn new(
+        filter: Option<Filter>,
+        fields: Option<Vec<String>>,
+        renames: Vec<(String, String)>,
+    ) -> Result<Self, OutputError> {
+        let fields = fields.map(|fields| {
+            fields
+                .into_iter()
+                .map(|field| field.trim().to_string())
+                .filter(|field| !field.is_empty())
+                .collect::<Vec<_>>()
+        });
+        if fields.as_ref().is_some_and(Vec::is_empty) {
+            return Err(OutputError::EmptyFields);
+        }
+        Ok(Self {
+            filter,
+            fields,
+            renames,
+            script: None,
+        })
+    }
+
+    pub fn with_script(mut self, script: MapScript) -> Self {
+        self.script = Some(Arc::new(script));
+        self
+    }
+
+    // clap 的 --rename 解析函数
+    pub fn parse_rename(raw: &str) -> Result<(String, String), OutputError> {
+        match raw.split_once('=') {
+            Some((from, to)) if !from.trim().is_empty() && !to.trim().is_empty() => {
+                Ok((from.trim().to_string(), to.trim().to_string()))
+            }
+            _ => Err(OutputError::InvalidRename(raw.to_string())),
+        }
+    }
+
+    // 是否需要改变记录的字段结构；命令据此决定是否改用通用 CSV 写出
+    pub fn is_shaped(&self) -> bool {
+        self.fields.is_some() || !self.renames.is_empty() || self.script.is_some()
+    }
+
+    // 记录无法序列化为 JSON 时返回错误，而不是静默丢弃
+    pub fn keep<T: Serialize + ?Sized>(&self, record: &T) -> Result<bool, serde_json::Error> {
+        if self.filter.is_none() && self.script.is_none() {
+            return Ok(true);
+        }
+        let Some(value) = self.mapped(record)? else {
+            return Ok(false);
+        };
+        Ok(self
+            .filter
+            .as_ref()
+            .is_none_or(|filter| filter.matches(&value)))
+    }
+
+    // 执行 --map-script，返回 None 表示记录被脚本丢弃
+    fn mapped<T: Serialize + ?Sized>(
+        &self,
+        record: &T,
+    ) -> Result<Option<Value>, serde_json::Error> {
+        let value = serde_json::to_value(record)?;
+        match &self.script {
+            Some(script) => script.apply(value).map_err(serde::ser::Error::custom),
+            None => Ok(Some(value)),
+        }
+    }
+
+    pub fn retain<T: Serialize>(&self, records: &mut Vec<T>) -> Result<(), serde_json::Error> {
+        if self.filter.is_none() && self.script.is_none() {
+            return Ok(());
+        }
+        let mut kept = Vec::with_capacity(records.len());
+        for record in records.drain(..) {
+            if self.keep(&record)? {
+                kept.push(record);
+            }
+        }
+        *records = kept;
+        Ok(())
+    }
+
+    // 按 --map-script / --fields / --rename 整形单条记录；非对象记录原样返回
+    pub fn shape<T: Serialize + ?Sized>(&self, record: &T) -> Result<Value, serde_json::Error> {
+        if !self.is_shaped() {
+            return serde_json::to_value(record);
+        }
+        // 调用方已用 keep 过滤，这里遇到被脚本丢弃的记录只能输出 null
+        let Some(value) = self.mapped(record)? else {
+            return Ok(Value::Null);
+        };
+        let Value::Object(object) = value else {
+            return Ok(value);
+        };
+        let selected: Vec<(String, Value)> = match &self.fields {
+            Some(fields) => fields
+                .iter()
+                .map(|field| (field.clone(), lookup(&object, field)))
+                .collect(),
+            None => object.into_iter().collect(),
+        };
+        Ok(Value::Object(
+            selected
+                .into_iter()
+                .map(|(key, value)| (self.rename(key), value))
+                .collect(),
+        ))
+    }
+
+    fn rename(&self, key: String) -> String {
+        self.renames
+            .iter()
+            .find(|(from, _)| *from == key)
+            .map_or(key, |(_, to)| to.clone())
+    }
+
+    // 以 JSON 数组（缩进格式）写出整形后的记录
+    pub fn write_json<T: Serialize, W: Write>(
+        &self,
+        records: &[T],
+        mut out: W,
+    ) -> Result<(), serde_json::Error> {
+        let values = records
+            .iter()
+            .map(|record| self.shape(record))
+            .collect::<Result<Vec<_>, _>>()?;
+        serde_json::to_writer_pretty(&mut out, &values)?;
+        writeln!(out).map_err(serde_json::Error::io)
+    }
+
+    // 以缩进 JSON 写出一份报告：records 为报告中记录列表的字段名，逐条整形、
+    // 其余汇总字段原样保留；为 None 时整份报告作为一条记录整形
+    pub fn write_report<T: Serialize + ?Sized, W: Write>(
+        &self,
+        report: &T,
+        records: Option<&str>,
+        mut out: W,
+    ) -> Result<(), serde_json::Error> {
+        let value = match records {
+            None => self.shape(report)?,
+            Some(key) => {
+                let mut value = serde_json::to_value(report)?;
+                if let Some(Value::Array(items)) = value.get_mut(key) {
+                    for item in items {
+                        *item = self.shape(&*item)?;
+                    }
+                }
+                value
+            }
+        };
+        serde_json::to_writer_pretty(&mut out, &value)?;
+        writeln!(out).map_err(serde_json::Error::io)
+    }
+
+    // 写出一行 JSON Lines
+    pub fn write_json_line<T: Serialize + ?Sized, W: Write>(
+        &self,
+        record: &T,
+        mut out: W,
+    ) -> Result<(), serde_json::Error> {
+        serde_json::to_writer(&mut out, &self.shape(record)?)?;
+        writeln!(out).map_err(serde_json::Error::io)
+    }
+
+    // 通用 CSV：列为所有记录整形后字段的并集（按首次出现顺序），
+    // 指定 --fields 时列与 --fields 一一对应
+    pub fn write_csv<T: Serialize, W: Write>(&self, records: &[T], out: W) -> csv::Result<()> {
+        let rows = records
+            .iter()
+            .map(|record| self.shape(record))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(csv_error)?;
+        let mut header: Vec<String> = Vec::new();
+        for row in &rows {
+            if let Value::Object(object) = row {
+                for key in object.keys() {
+                    if !header.contains(key) {
+                        header.push(key.clone());
+                    }
+                }
+            }
+        }
+        let mut writer = CsvRecords::with_header(out, header)?;
+        for row in &rows {
+            writer.write_value(row)?;
+        }
+        writer.flush()
+    }
+
+    // 逐条写出的 CSV，表头取自第一条记录
+    pub fn csv_stream<W: Write>(&self, out: W) -> CsvRecords<W> {
+        CsvRecords {
+            writer: csv::Writer::from_writer(out),
+            header: None,
+        }
+    }
+}
+
+pub struct CsvRecords<W: Write> {
+    writer: csv::Writer<W>,
+    header: Option<Vec<String>>,
+}
+
+impl<W: Write> CsvRecords<W> {
+    fn with_header(out: W, header: Vec<String>) -> csv::Result<Self> {
+        let mut writer = csv::Writer::from_writer(out);
+        // 所有记录都被过滤掉时没有列，输出空文件而不是一个空表头
+        if !header.is_empty() {
+            writer.write_record(&header)?;
+        }
+        Ok(Self {
+            writer,
+            header: Some(header),
+        })
+    }
+
+    pub fn write(&mut self, output: &RecordOutput, record: &impl Serialize) -> csv::Result<()> {
+        let value = output.shape(record).map_err(csv_error)?;
+        self.write_value(&value)
+    }
+
+    fn write_value(&mut self, value: &Value) -> csv::Result<()> {
+        let empty = Map::new();
+        let object = value.as_object().unwrap_or(&empty);
+        if self.header.is_none() {
+            let header: Vec<String> = object.keys().cloned().collect();
+            self.writer.write_record(&header)?;
+            self.header = Some(header);
+        }
+        let header = self.header.as_deref().unwrap_or_default();
+        self.writer.write_record(
+            header
+                .iter()
+                .map(|key| csv_cell(object.get(key).unwrap_or(&Value::Null))),
+        )
+    }
+
+    pub fn flush(&mut self) -> csv::Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+fn lookup(object: &Map<String, Value>, path: &str) -> Value {
+    if let Some(value) = object.get(path) {
+        return value.clone();
+    }
+    let mut segments = path.split('.');
+    let mut current = segments.next().and_then(|first| object.get(first));
+    for segment in segments {
+        current = current.and_then(|value| value.get(segment));
+    }
+    current.cloned().unwrap_or(Value::Null)
+}
+
+fn csv_cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        Value::Array(items)
+            if items
+                .iter()
+                .all(|item| !item.is_object() && !item.is_array()) =>
+        {
+            items.iter().map(csv_cell).collect::<Vec<_>>().join(";")
+        }
+        other => other.to_string(),
+    }
+}
+
+fn csv_error(error: serde_json::Error) -> csv::Error {
+    csv::Error::from(std::io::Error::other(error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn shaped(fields: &[&str], renames: &[&str]) -> RecordOutput {
+        RecordOutput::new(
+            None,
+            (!fields.is_empty()).then(|| fields.iter().map(|f| f.to_string()).collect()),
+            renames
+                .iter()
+                .map(|raw| RecordOutput::parse_rename(raw).unwrap())
+                .collect(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_fields_select_order_and_rename() {
+        let record = json!({"id_number": "110", "name": "张三", "tls": {"subject": "CN=a"}});
+        let output = shaped(
+            &["name", "id_number", "tls.subject", "missing"],
+            &["id_number=card_no"],
+        );
+        assert_eq!(
+            output.shape(&record).unwrap(),
+            json!({"name": "张三", "card_no": "110", "tls.subject": "CN=a", "missing": null})
+        );
+        // 只改名时保留全部字段和原顺序
+        let output = shaped(&[], &["name=full_name"]);
+        assert_eq!(
+            output.shape(&record).unwrap(),
+            json!({"id_number": "110", "full_name": "张三", "tls": {"subject": "CN=a"}})
+        );
+        assert_eq!(
+            RecordOutput::parse_rename("id"),
+            Err(OutputError::InvalidRename("id".into()))
+        );
+        assert_eq!(
+            RecordOutput::new(None, Some(vec![" ".into()]), Vec::new()).unwrap_err(),
+            OutputError::EmptyFields
+        );
+    }
+
+    #[test]
+    fn test_csv_uses_shaped_columns() {
+        let records = vec![
+            json!({"host": "a", "port": 22, "sans": ["x", "y"]}),
+            json!({"host": "b", "port": 80, "banner": "nginx"}),
+        ];
+        let mut out = Vec::new();
+        shaped(&[], &["host=ip"])
+            .write_csv(&records, &mut out)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "ip,port,sans,banner\na,22,x;y,\nb,80,,nginx\n"
+        );
+
+        let mut out = Vec::new();
+        let output = shaped(&["port", "host"], &[]);
+        let mut writer = output.csv_stream(&mut out);
+        for record in &records {
+            writer.write(&output, record).unwrap();
+        }
+        writer.flush().unwrap();
+        drop(writer);
+        assert_eq!(String::from_utf8(out).unwrap(), "port,host\n22,a\n80,b\n");
+    }
+
+    #[test]
+    fn test_report_shapes_records_and_keeps_summary() {
+        let report =
+            json!({"host": "h", "hops": [{"ttl": 1, "addr": "a"}, {"ttl": 2, "addr": "b"}]});
+        let output = shaped(&["addr"], &["addr=ip"]);
+        let mut out = Vec::new();
+        output
+            .write_report(&report, Some("hops"), &mut out)
+            .unwrap();
+        let written: Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(
+            written,
+            json!({"host": "h", "hops": [{"ip": "a"}, {"ip": "b"}]})
+        );
+
+        // 没有记录列表时整份报告作为一条记录整形
+        let mut out = Vec::new();
+        output
+            .write_report(&json!({"addr": "c", "rtt": 1}), None, &mut out)
+            .unwrap();
+        assert_eq!(
+            serde_json::from_slice::<Value>(&out).unwrap(),
+            json!({"ip": "c"})
+        );
+    }
+
+    #[test]
+    fn test_report_script_runs_per_record() {
+        let script = MapScript::compile("test.rhai", "record.slow = record.rtt > 50;").unwrap();
+        let output = RecordOutput::default().with_script(script);
+        let mut report = json!({"host": "h", "replies": [{"rtt": 10}, {"rtt": 80}]});
+        let mut out = Vec::new();
+        output
+            .write_report(&report, Some("replies"), &mut out)
+            .unwrap();
+        report["replies"] = json!([{"rtt": 10, "slow": false}, {"rtt": 80, "slow": true}]);
+        assert_eq!(serde_json::from_slice::<Value>(&out).unwrap(), report);
+    }
+
+    #[test]
+    fn test_record_filter_surfaces_serialization_errors() {
+        let output =
+            RecordOutput::new(Some(Filter::parse("port == 22").unwrap()), None, Vec::new())
+                .unwrap();
+        let mut records = vec![json!({"port": 22}), json!({"port": 80})];
+        output.retain(&mut records).unwrap();
+        assert_eq!(records, vec![json!({"port": 22})]);
+
+        // JSON 对象的键只能是字符串，元组键的 map 无法序列化
+        let bad: std::collections::HashMap<(u8, u8), u8> = [((1, 2), 3)].into();
+        assert!(output.keep(&bad).is_err());
+        assert!(RecordOutput::default().keep(&bad).unwrap());
+    }
+}
@@ -0,0 +1,292 @@
+// 测试证书相关的公共逻辑：生成 RSA / EC 密钥、读取 PEM 私钥、按 CN 和 SAN 生成 CSR、自签名证书和由本地 CA 签发的证书。
+// 签名由 rcgen（ring 后端）完成；ring 不支持生成 RSA 密钥，RSA 密钥由 rsa crate 生成后以 PKCS#8 导入
+
+use std::fs;
+use std::io::{self, Write};
+use std::net::IpAddr;
+use std::path::Path;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use rand::{rng, Rng};
+use rcgen::{
+    BasicConstraints, CertificateParams, DistinguishedName, DnType, ExtendedKeyUsagePurpose, IsCa,
+    Issuer, KeyPair, KeyUsagePurpose, SanType, SerialNumber, PKCS_ECDSA_P256_SHA256,
+    PKCS_ECDSA_P384_SHA384,
+};
+use rsa::pkcs1::DecodeRsaPrivateKey;
+use rsa::pkcs8::EncodePrivateKey;
+use rsa::RsaPrivateKey;
+use time::{Duration, OffsetDateTime};
+use x509_parser::pem::Pem;
+
+#[derive(thiserror::Error, Debug)]
+pub enum PkiError {
+    #[error("RSA key generation failed: {0}")]
+    Rsa(String),
+    #[error(
+        "unsupported private key ({0}); convert it to PKCS#8 with `openssl pkcs8 -topk8 -nocrypt`"
+    )]
+    UnsupportedKey(String),
+    #[error("invalid subject alternative name '{0}'")]
+    InvalidSan(String),
+    #[error("certificate needs --cn or at least one --san")]
+    MissingSubject,
+    #[error(transparent)]
+    Rcgen(#[from] rcgen::Error),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum EcCurve {
+    P256,
+    P384,
+}
+
+pub fn generate_rsa(bits: usize) -> Result<KeyPair, PkiError> {
+    let key = RsaPrivateKey::new(&mut rsa::rand_core::OsRng, bits)
+        .map_err(|e| PkiError::Rsa(e.to_string()))?;
+    let der = key
+        .to_pkcs8_der()
+        .map_err(|e| PkiError::Rsa(e.to_string()))?;
+    Ok(KeyPair::try_from(der.as_bytes())?)
+}
+
+pub fn generate_ec(curve: EcCurve) -> Result<KeyPair, PkiError> {
+    let alg = match curve {
+        EcCurve::P256 => &PKCS_ECDSA_P256_SHA256,
+        EcCurve::P384 => &PKCS_ECDSA_P384_SHA384,
+    };
+    Ok(KeyPair::generate_for(alg)?)
+}
+
+// 支持 PKCS#8（PRIVATE KEY）和 PKCS#1（RSA PRIVATE KEY）；SEC1 的 EC PRIVATE KEY 需要先转换。
+// 文件里可以同时有证书，取第一个私钥块
+pub fn load_key_pair(pem: &str) -> Result<KeyPair, PkiError> {
+    let block = Pem::iter_from_buffer(pem.as_bytes())
+        .filter_map(Result::ok)
+        .find(|block| block.label.ends_with("PRIVATE KEY"))
+        .ok_or_else(|| PkiError::UnsupportedKey("no PEM private key found".to_string()))?;
+    decode_key(&block.label, &block.contents)
+}
+
+// label 为 PEM 块的类型；DER 格式的私钥按 PKCS#8（"PRIVATE KEY"）解析
+pub fn decode_key(label: &str, der: &[u8]) -> Result<KeyPair, PkiError> {
+    let unsupported = |e: &dyn std::fmt::Display| PkiError::UnsupportedKey(e.to_string());
+    match label {
+        "PRIVATE KEY" => KeyPair::try_from(der).map_err(|e| unsupported(&e)),
+        "RSA PRIVATE KEY" => {
+            let key = RsaPrivateKey::from_pkcs1_der(der).map_err(|e| unsupported(&e))?;
+            let der = key.to_pkcs8_der().map_err(|e| unsupported(&e))?;
+            Ok(KeyPair::try_from(der.as_bytes())?)
+        }
+        "EC PRIVATE KEY" => Err(unsupported(&"SEC1 EC PRIVATE KEY")),
+        "ENCRYPTED PRIVATE KEY" => Err(unsupported(&"encrypted PKCS#8")),
+        other => Err(unsupported(&other)),
+    }
+}
+
+// 标准 PEM 编码，base64 每行 64 个字符
+pub fn encode_pem(label: &str, der: &[u8]) -> String {
+    let body = STANDARD.encode(der);
+    let mut out = format!("-----BEGIN {}-----\n", label);
+    for line in body.as_bytes().chunks(64) {
+        out.push_str(std::str::from_utf8(line).expect("base64 is ascii"));
+        out.push('\n');
+    }
+    out.push_str(&format!("-----END {}-----\n", label));
+    out
+}
+
+// 证书主题和扩展；CSR 只使用主题和 SAN
+#[derive(Debug, Clone, Default)]
+pub struct Subject {
+    pub common_name: Option<String>,
+    pub organization: Option<String>,
+    pub sans: Vec<String>,
+}
+
+impl Subject {
+    // 没有 --san 时把 CN 作为唯一的 SAN，现代浏览器和 TLS 库只看 SAN；CA 证书不需要 SAN，CN 可以是任意名称
+    fn params(&self, is_ca: bool) -> Result<CertificateParams, PkiError> {
+        let common_name = self
+            .common_name
+            .clone()
+            .or_else(|| self.sans.first().cloned())
+            .ok_or(PkiError::MissingSubject)?;
+        let sans = if self.sans.is_empty() && !is_ca {
+            vec![common_name.clone()]
+        } else {
+            self.sans.clone()
+        };
+        let mut params = CertificateParams::default();
+        params.subject_alt_names = sans
+            .iter()
+            .map(|san| parse_san(san))
+            .collect::<Result<_, _>>()?;
+        let mut name = DistinguishedName::new();
+        name.push(DnType::CommonName, common_name);
+        if let Some(org) = &self.organization {
+            name.push(DnType::OrganizationName, org.clone());
+        }
+        params.distinguished_name = name;
+        Ok(params)
+    }
+
+    pub fn csr_pem(&self, key: &KeyPair) -> Result<String, PkiError> {
+        Ok(self.params(false)?.serialize_request(key)?.pem()?)
+    }
+
+    // is_ca 为 true 时生成可签发下级证书的根证书，否则生成服务端 / 客户端通用的叶子证书
+    pub fn self_signed_pem(
+        &self,
+        key: &KeyPair,
+        days: u32,
+        is_ca: bool,
+    ) -> Result<String, PkiError> {
+        Ok(self.cert_params(days, is_ca)?.self_signed(key)?.pem())
+    }
+
+    // 由本地 CA 签发叶子证书，ca_cert 为 CA 证书的 PEM，ca_key 为 CA 的私钥
+    pub fn signed_pem(
+        &self,
+        key: &KeyPair,
+        days: u32,
+        ca_cert: &str,
+        ca_key: &KeyPair,
+    ) -> Result<String, PkiError> {
+        let issuer = Issuer::from_ca_cert_pem(ca_cert, ca_key)?;
+        Ok(self
+            .cert_params(days, false)?
+            .signed_by(key, &issuer)?
+            .pem())
+    }
+
+    fn cert_params(&self, days: u32, is_ca: bool) -> Result<CertificateParams, PkiError> {
+        let mut params = self.params(is_ca)?;
+        let now = OffsetDateTime::now_utc();
+        // 往前留几分钟，避免客户端时钟略慢时证书尚未生效
+        params.not_before = now - Duration::minutes(5);
+        params.not_after = now + Duration::days(days.into());
+        params.serial_number = Some(random_serial());
+        if is_ca {
+            params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+            params.key_usages = vec![
+                KeyUsagePurpose::KeyCertSign,
+                KeyUsagePurpose::CrlSign,
+                KeyUsagePurpose::DigitalSignature,
+            ];
+        } else {
+            params.is_ca = IsCa::ExplicitNoCa;
+            params.key_usages = vec![
+                KeyUsagePurpose::DigitalSignature,
+                KeyUsagePurpose::KeyEncipherment,
+            ];
+            params.extended_key_usages = vec![
+                ExtendedKeyUsagePurpose::ServerAuth,
+                ExtendedKeyUsagePurpose::ClientAuth,
+            ];
+        }
+        Ok(params)
+    }
+}
+
+// IP 地址、邮箱（含 @）或域名（允许 *.example.com 通配）
+fn parse_san(raw: &str) -> Result<SanType, PkiError> {
+    let invalid = || PkiError::InvalidSan(raw.to_string());
+    if let Ok(ip) = raw.parse::<IpAddr>() {
+        return Ok(SanType::IpAddress(ip));
+    }
+    let value = raw.to_string().try_into().map_err(|_| invalid())?;
+    if raw.contains('@') {
+        return Ok(SanType::Rfc822Name(value));
+    }
+    if raw.is_empty() || raw.contains(char::is_whitespace) {
+        return Err(invalid());
+    }
+    Ok(SanType::DnsName(value))
+}
+
+// RFC 5280 要求序列号为正数且不超过 20 字节，这里取 16 字节随机数并清掉最高位
+fn random_serial() -> SerialNumber {
+    let mut bytes = [0u8; 16];
+    rng().fill(&mut bytes[..]);
+    bytes[0] &= 0x7f;
+    SerialNumber::from_slice(&bytes)
+}
+
+// 私钥文件只允许所有者读写
+#[cfg(unix)]
+pub fn write_private_file(path: &Path, data: &[u8]) -> io::Result<()> {
+    use std::os::unix::fs::OpenOptionsExt;
+
+    fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)?
+        .write_all(data)
+}
+
+#[cfg(not(unix))]
+pub fn write_private_file(path: &Path, data: &[u8]) -> io::Result<()> {
+    fs::write(path, data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use x509_parser::extensions::GeneralName;
+    use x509_parser::pem::parse_x509_pem;
+    use x509_parser::prelude::FromDer;
+
+    #[test]
+    fn test_self_signed_cert_and_csr() {
+        let key = generate_ec(EcCurve::P256).unwrap();
+        let reloaded = load_key_pair(&key.serialize_pem()).unwrap();
+        assert_eq!(reloaded.public_key_pem(), key.public_key_pem());
+
+        let subject = Subject {
+            common_name: Some("dev.local".to_string()),
+            organization: Some("rtoolkit".to_string()),
+            sans: vec!["dev.local".into(), "*.dev.local".into(), "127.0.0.1".into()],
+        };
+        let pem = subject.self_signed_pem(&key, 30, false).unwrap();
+        let (_, pem) = parse_x509_pem(pem.as_bytes()).unwrap();
+        let cert = pem.parse_x509().unwrap();
+        assert_eq!(cert.subject().to_string(), "CN=dev.local, O=rtoolkit");
+        assert_eq!(cert.subject(), cert.issuer());
+        let days = (cert.validity().not_after.timestamp() - cert.validity().not_before.timestamp())
+            / 86_400;
+        assert_eq!(days, 30);
+        let sans = cert.subject_alternative_name().unwrap().unwrap();
+        assert!(matches!(
+            sans.value.general_names[1],
+            GeneralName::DNSName("*.dev.local")
+        ));
+        assert!(matches!(
+            sans.value.general_names[2],
+            GeneralName::IPAddress([127, 0, 0, 1])
+        ));
+        assert!(!cert.is_ca());
+
+        let csr = subject.csr_pem(&key).unwrap();
+        let (_, pem) = parse_x509_pem(csr.as_bytes()).unwrap();
+        let (_, csr) =
+            x509_parser::certification_request::X509CertificationRequest::from_der(&pem.contents)
+                .unwrap();
+        assert_eq!(
+            csr.certification_request_info.subject.to_string(),
+            "CN=dev.local, O=rtoolkit"
+        );
+
+        assert!(matches!(
+            Subject::default().csr_pem(&key),
+            Err(PkiError::MissingSubject)
+        ));
+        assert!(matches!(
+            parse_san("bad host"),
+            Err(PkiError::InvalidSan(_))
+        ));
+    }
+}
@@ -0,0 +1,177 @@
+// 长任务的进度和取消标记，执行方累加完成数，观察方（后台任务查询、进度条）随时读取。
+// 所有字段都是原子变量，可以在线程、异步任务之间共享同一个 Arc<Progress>。
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use indicatif::{ProgressBar, ProgressStyle};
+use serde::Serialize;
+
+use crate::utils::a11y;
+use crate::utils::format::human_bytes;
+
+// --a11y 模式下输出文字进度的间隔
+const A11Y_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Default)]
+pub struct Progress {
+    total: AtomicU64,
+    done: AtomicU64,
+    // 过程中的发现数，例如开放端口
+    found: AtomicU64,
+    cancelled: AtomicBool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ProgressSnapshot {
+    pub done: u64,
+    pub total: u64,
+}
+
+impl Progress {
+    pub fn set_total(&self, total: u64) {
+        self.total.store(total, Ordering::Relaxed);
+    }
+
+    pub fn advance(&self, count: u64) {
+        self.done.fetch_add(count, Ordering::Relaxed);
+    }
+
+    pub fn add_found(&self, count: u64) {
+        self.found.fetch_add(count, Ordering::Relaxed);
+    }
+
+    pub fn found(&self) -> u64 {
+        self.found.load(Ordering::Relaxed)
+    }
+
+    // 提前结束时把完成数补齐到总数
+    pub fn finish(&self) {
+        self.done
+            .store(self.total.load(Ordering::Relaxed), Ordering::Relaxed);
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    pub fn snapshot(&self) -> ProgressSnapshot {
+        ProgressSnapshot {
+            done: self.done.load(Ordering::Relaxed),
+            total: self.total.load(Ordering::Relaxed),
+        }
+    }
+}
+
+// 终端进度条：后台线程定时读取 Progress 刷新，结束时清除，不残留在随后打印的结果和汇总中；
+// --a11y 模式下不画进度条，每隔几秒输出一行完整的进度说明
+pub struct ProgressDisplay {
+    stop: mpsc::Sender<()>,
+    handle: JoinHandle<()>,
+}
+
+impl ProgressDisplay {
+    // unit 为计数单位，found 为发现数的说明，例如 ("ports", "open")
+    pub fn start(progress: Arc<Progress>, unit: &'static str, found: &'static str) -> Self {
+        let template = format!(
+            "{{bar:40.cyan/blue}} {{pos}}/{{len}} {}  {{msg}}  ETA {{eta}}",
+            unit
+        );
+        Self::spawn(
+            progress,
+            template,
+            move |progress| progress_line(progress, unit, found),
+            move |progress| format!("{} {}", progress.found(), found),
+        )
+    }
+
+    // 按字节计数，显示速率；verb 用于 --a11y 的文字进度，例如 "Sent"
+    pub fn bytes(progress: Arc<Progress>, verb: &'static str) -> Self {
+        Self::spawn(
+            progress,
+            "{bar:40.cyan/blue} {bytes}/{total_bytes}  {binary_bytes_per_sec}  ETA {eta}"
+                .to_string(),
+            move |progress| bytes_line(progress, verb),
+            |_| String::new(),
+        )
+    }
+
+    fn spawn(
+        progress: Arc<Progress>,
+        template: String,
+        line: impl Fn(&Progress) -> String + Send + 'static,
+        message: impl Fn(&Progress) -> String + Send + 'static,
+    ) -> Self {
+        let (stop, stopped) = mpsc::channel();
+        if a11y::is_enabled() {
+            let handle = thread::spawn(move || {
+                while stopped.recv_timeout(A11Y_INTERVAL) == Err(RecvTimeoutError::Timeout) {
+                    eprintln!("{}", line(&progress));
+                }
+            });
+            return Self { stop, handle };
+        }
+        let handle = thread::spawn(move || {
+            let style = ProgressStyle::with_template(&template)
+                .unwrap_or_else(|_| ProgressStyle::default_bar())
+                .progress_chars("=> ");
+            let bar = ProgressBar::new(0).with_style(style);
+            loop {
+                let snapshot = progress.snapshot();
+                bar.set_length(snapshot.total);
+                bar.set_position(snapshot.done);
+                bar.set_message(message(&progress));
+                if stopped.recv_timeout(Duration::from_millis(100))
+                    != Err(RecvTimeoutError::Timeout)
+                {
+                    break;
+                }
+            }
+            bar.finish_and_clear();
+        });
+        Self { stop, handle }
+    }
+
+    pub fn finish(self) {
+        let _ = self.stop.send(());
+        let _ = self.handle.join();
+    }
+}
+
+fn percent(snapshot: ProgressSnapshot) -> u64 {
+    match snapshot.total {
+        0 => 0,
+        total => snapshot.done * 100 / total,
+    }
+}
+
+fn bytes_line(progress: &Progress, verb: &str) -> String {
+    let snapshot = progress.snapshot();
+    format!(
+        "{} {} of {}, {}%",
+        verb,
+        human_bytes(snapshot.done),
+        human_bytes(snapshot.total),
+        percent(snapshot)
+    )
+}
+
+fn progress_line(progress: &Progress, unit: &str, found: &str) -> String {
+    let snapshot = progress.snapshot();
+    format!(
+        "Progress: {} of {} {} done ({} percent), {} {}",
+        snapshot.done,
+        snapshot.total,
+        unit,
+        percent(snapshot),
+        progress.found(),
+        found
+    )
+}
@@ -0,0 +1,106 @@
+// 令牌桶限速：按固定速率补充令牌，桶满后不再累积，每次操作消耗一个令牌（按字节限速时消耗字节数个）。
+// 令牌不足时预先扣成负数并返回需要等待的时长，多个调用方按到达顺序依次排开，不会同时醒来。
+
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+#[derive(Debug)]
+pub struct RateLimiter {
+    // 每秒补充的令牌数
+    rate: f64,
+    // 桶容量，即允许的突发数
+    burst: f64,
+    state: Mutex<Bucket>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(per_second: u32, burst: u32) -> Self {
+        let burst = f64::from(burst.max(1));
+        Self {
+            rate: f64::from(per_second.max(1)),
+            burst,
+            state: Mutex::new(Bucket {
+                tokens: burst,
+                updated: Instant::now(),
+            }),
+        }
+    }
+
+    // 取一个令牌，返回调用方在执行操作前需要等待的时长
+    pub fn reserve(&self) -> Duration {
+        self.reserve_n(1)
+    }
+
+    // 一次取 count 个令牌，可以超过桶容量，超出的部分同样按速率排队
+    pub fn reserve_n(&self, count: u32) -> Duration {
+        let mut bucket = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let now = Instant::now();
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.rate).min(self.burst);
+        bucket.updated = now;
+        bucket.tokens -= f64::from(count);
+        if bucket.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-bucket.tokens / self.rate)
+        }
+    }
+
+    pub async fn acquire(&self) {
+        let wait = self.reserve();
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    pub async fn acquire_n(&self, count: u32) {
+        let wait = self.reserve_n(count);
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    // 供阻塞线程使用，例如 SYN 扫描的收发循环
+    pub fn acquire_blocking(&self) {
+        let wait = self.reserve();
+        if !wait.is_zero() {
+            std::thread::sleep(wait);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_limiter_spaces_out_calls() {
+        let limiter = RateLimiter::new(100, 2);
+        // 桶初始是满的，突发额度用完后每个令牌间隔 10ms，排队的调用方等待时长依次累加
+        assert_eq!(limiter.reserve(), Duration::ZERO);
+        assert_eq!(limiter.reserve(), Duration::ZERO);
+        let third = limiter.reserve();
+        let fourth = limiter.reserve();
+        assert!(third > Duration::from_millis(8) && third <= Duration::from_millis(10));
+        assert!(fourth > Duration::from_millis(18) && fourth <= Duration::from_millis(20));
+
+        let limiter = RateLimiter::new(200, 1);
+        let started = Instant::now();
+        for _ in 0..11 {
+            limiter.acquire_blocking();
+        }
+        // 首个令牌不等待，其余 10 个按 5ms 间隔发放
+        assert!(started.elapsed() >= Duration::from_millis(45));
+
+        // 按字节取令牌：1000 字节/秒、突发 100 字节，取 300 字节需要等待 200ms
+        let limiter = RateLimiter::new(1000, 100);
+        let wait = limiter.reserve_n(300);
+        assert!(wait > Duration::from_millis(195) && wait <= Duration::from_millis(200));
+    }
+}
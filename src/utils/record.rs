@@ -0,0 +1,253 @@
+// 录制交互会话：在伪终端中重新运行当前命令（去掉 --record），子进程看到的是真实终端，
+// 交互式选择列表、进度条照常工作；父进程把按键转给子进程，把终端输出同时写到屏幕和 asciicast 文件。
+
+use std::ffi::OsString;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+#[derive(thiserror::Error, Debug)]
+pub enum RecordError {
+    #[error("--record needs a Unix pseudo terminal, which this platform does not provide")]
+    Unsupported,
+    #[error("--record only records interactive sessions, stdin and stdout must be a terminal")]
+    NotInteractive,
+    #[error("failed to create {path}: {source}")]
+    Create { path: PathBuf, source: io::Error },
+    #[error("failed to open a pseudo terminal: {0}")]
+    Pty(io::Error),
+    #[error("failed to start {command}: {source}")]
+    Spawn { command: String, source: io::Error },
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+// 去掉命令行中的 --record FILE / --record=FILE，得到在伪终端中运行的参数
+pub fn strip_record_arg(args: impl IntoIterator<Item = OsString>) -> Vec<OsString> {
+    let mut stripped = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--record" {
+            args.next();
+        } else if !arg.to_str().is_some_and(|arg| arg.starts_with("--record=")) {
+            stripped.push(arg);
+        }
+    }
+    stripped
+}
+
+// 返回子进程的退出码，被信号终止时为 128 + 信号值
+#[cfg(unix)]
+pub fn record(command: Command, path: &Path) -> Result<i32, RecordError> {
+    use std::io::IsTerminal;
+
+    // 输入或输出被重定向时没有可录制的交互：管道数据会被伪终端改写（换行变 CRLF、回显），
+    // 一次性命令也不需要在伪终端中重新执行
+    if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+        return Err(RecordError::NotInteractive);
+    }
+    unix::record(command, path, &mut io::stdout().lock())
+}
+
+#[cfg(not(unix))]
+pub fn record(_command: Command, _path: &Path) -> Result<i32, RecordError> {
+    Err(RecordError::Unsupported)
+}
+
+#[cfg(unix)]
+mod unix {
+    use std::collections::BTreeMap;
+    use std::env;
+    use std::fs::File;
+    use std::io::{self, BufWriter, IsTerminal, Read, Write};
+    use std::os::fd::{FromRawFd, OwnedFd};
+    use std::os::unix::process::{CommandExt, ExitStatusExt};
+    use std::path::Path;
+    use std::process::{Command, Stdio};
+    use std::thread;
+
+    use super::RecordError;
+    use crate::utils::asciicast::{CastWriter, Header};
+
+    // screen 为实时显示输出的位置，通常是 stdout
+    pub fn record(
+        mut command: Command,
+        path: &Path,
+        screen: &mut dyn Write,
+    ) -> Result<i32, RecordError> {
+        let file = File::create(path).map_err(|source| RecordError::Create {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let size = terminal_size();
+        let (mut master, mut slave) = (-1, -1);
+        let ret = unsafe {
+            libc::openpty(
+                &mut master,
+                &mut slave,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                &size,
+            )
+        };
+        if ret != 0 {
+            return Err(RecordError::Pty(io::Error::last_os_error()));
+        }
+        let (master, slave) =
+            unsafe { (OwnedFd::from_raw_fd(master), OwnedFd::from_raw_fd(slave)) };
+
+        let title = std::iter::once(command.get_program())
+            .chain(command.get_args())
+            .map(|arg| arg.to_string_lossy())
+            .collect::<Vec<_>>()
+            .join(" ");
+        command
+            .stdin(Stdio::from(slave.try_clone()?))
+            .stdout(Stdio::from(slave.try_clone()?))
+            .stderr(Stdio::from(slave));
+        // 子进程自成会话并以伪终端为控制终端，才能正常读取按键、响应 Ctrl+C
+        unsafe {
+            command.pre_exec(|| {
+                if libc::setsid() == -1 || libc::ioctl(0, libc::TIOCSCTTY, 0) == -1 {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+        let mut child = command.spawn().map_err(|source| RecordError::Spawn {
+            command: title.clone(),
+            source,
+        })?;
+        // 父进程不能继续持有从端，否则子进程退出后读主端不会结束
+        drop(command);
+
+        let env: BTreeMap<String, String> = ["SHELL", "TERM"]
+            .into_iter()
+            .filter_map(|key| Some((key.to_string(), env::var(key).ok()?)))
+            .collect();
+        let header = Header {
+            version: 2,
+            width: size.ws_col,
+            height: size.ws_row,
+            timestamp: Some(chrono::Utc::now().timestamp()),
+            command: Some(title),
+            env,
+        };
+        let mut writer = CastWriter::new(BufWriter::new(file), &header)?;
+
+        let raw_mode = RawMode::enable();
+        let mut input = File::from(master.try_clone()?);
+        // 标准输入只在这个线程中读取，子进程退出后随进程一起结束
+        thread::spawn(move || io::copy(&mut io::stdin().lock(), &mut input));
+        let mut output = File::from(master);
+        let mut buf = [0u8; 8192];
+        loop {
+            match output.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => {
+                    screen.write_all(&buf[..n])?;
+                    screen.flush()?;
+                    writer.output(&buf[..n])?;
+                }
+                // Linux 上从端全部关闭后读主端返回 EIO
+                Err(e) if e.raw_os_error() == Some(libc::EIO) => break,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        let status = child.wait()?;
+        drop(raw_mode);
+        writer.finish()?;
+        Ok(status
+            .code()
+            .unwrap_or_else(|| 128 + status.signal().unwrap_or_default()))
+    }
+
+    // 录制文件记录的终端尺寸，stdout 不是终端时按 80x24
+    fn terminal_size() -> libc::winsize {
+        let mut size = libc::winsize {
+            ws_row: 0,
+            ws_col: 0,
+            ws_xpixel: 0,
+            ws_ypixel: 0,
+        };
+        let ret = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) };
+        if ret != 0 || size.ws_col == 0 || size.ws_row == 0 {
+            size.ws_col = 80;
+            size.ws_row = 24;
+        }
+        size
+    }
+
+    // 录制期间本地终端切到原始模式，按键原样交给子进程的终端处理，结束时恢复
+    struct RawMode(libc::termios);
+
+    impl RawMode {
+        fn enable() -> Option<Self> {
+            if !io::stdin().is_terminal() {
+                return None;
+            }
+            let mut original = unsafe { std::mem::zeroed::<libc::termios>() };
+            if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut original) } != 0 {
+                return None;
+            }
+            let mut raw = original;
+            unsafe { libc::cfmakeraw(&mut raw) };
+            if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) } != 0 {
+                return None;
+            }
+            Some(Self(original))
+        }
+    }
+
+    impl Drop for RawMode {
+        fn drop(&mut self) {
+            unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.0) };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_record_arg() {
+        let args = [
+            "rtoolkit",
+            "--record",
+            "demo.cast",
+            "discover",
+            "--record=x",
+            "--pick",
+        ]
+        .map(OsString::from);
+        assert_eq!(
+            strip_record_arg(args),
+            ["rtoolkit", "discover", "--pick"].map(OsString::from)
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_record_runs_command_in_pty() {
+        use crate::utils::asciicast;
+
+        let path =
+            std::env::temp_dir().join(format!("rtoolkit-record-{}.cast", std::process::id()));
+        let mut command = Command::new("sh");
+        command.args(["-c", "test -t 1 && printf 'tty\\n'; exit 3"]);
+        let code = unix::record(command, &path, &mut io::sink()).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(code, 3);
+        let (header, events) = asciicast::parse(&text).unwrap();
+        assert_eq!(
+            header.command.as_deref(),
+            Some("sh -c test -t 1 && printf 'tty\\n'; exit 3")
+        );
+        // 子进程的输出是终端，换行经过伪终端转换为 \r\n
+        let output: String = events.iter().map(|event| event.data.as_str()).collect();
+        assert_eq!(output, "tty\r\n");
+    }
+}
@@ -0,0 +1,243 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use rusqlite::{params, Connection, OptionalExtension};
+use thiserror::Error;
+
+// 扫描历史库：每次 --save 的扫描一行，开放端口逐个记录，供 port-scan diff 比较
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS scans (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    started TEXT NOT NULL,
+    finished TEXT NOT NULL,
+    target TEXT NOT NULL,
+    ports TEXT NOT NULL,
+    protocol TEXT NOT NULL,
+    args TEXT NOT NULL,
+    total INTEGER NOT NULL,
+    open_count INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS open_ports (
+    scan_id INTEGER NOT NULL REFERENCES scans(id) ON DELETE CASCADE,
+    host TEXT NOT NULL,
+    port INTEGER NOT NULL,
+    hostname TEXT,
+    service TEXT,
+    PRIMARY KEY (scan_id, host, port)
+);
+";
+
+#[derive(Debug, Error)]
+pub enum ScanDbError {
+    #[error("cannot locate the data directory, set HOME or XDG_DATA_HOME")]
+    NoDataDir,
+    #[error("scan history {path}: {source}")]
+    Open {
+        path: PathBuf,
+        source: rusqlite::Error,
+    },
+    #[error("scan #{0} not found in the history, run `port-scan history` to list saved scans")]
+    NotFound(i64),
+    #[error(transparent)]
+    Sqlite(#[from] rusqlite::Error),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SavedScan {
+    pub id: i64,
+    pub started: String,
+    pub finished: String,
+    pub target: String,
+    pub ports: String,
+    pub protocol: String,
+    pub args: String,
+    pub total: usize,
+    pub open_count: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SavedPort {
+    pub host: String,
+    pub port: u16,
+    pub hostname: Option<String>,
+    pub service: Option<String>,
+}
+
+pub struct ScanDb {
+    conn: Connection,
+}
+
+// 遵循 XDG 规范：$XDG_DATA_HOME/rtoolkit/scans.db，未设置时为 ~/.local/share/rtoolkit/scans.db
+pub fn default_path() -> Result<PathBuf, ScanDbError> {
+    let base = match env::var_os("XDG_DATA_HOME").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => env::var_os("HOME")
+            .or_else(|| env::var_os("USERPROFILE"))
+            .map(|home| Path::new(&home).join(".local").join("share"))
+            .ok_or(ScanDbError::NoDataDir)?,
+    };
+    Ok(base.join("rtoolkit").join("scans.db"))
+}
+
+impl ScanDb {
+    pub fn open(path: &Path) -> Result<Self, ScanDbError> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let open = || -> rusqlite::Result<Connection> {
+            let conn = Connection::open(path)?;
+            conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+            conn.execute_batch(SCHEMA)?;
+            Ok(conn)
+        };
+        let conn = open().map_err(|source| ScanDbError::Open {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Ok(Self { conn })
+    }
+
+    // 一次扫描及其开放端口在同一事务中写入，返回新扫描的编号
+    pub fn save(&mut self, scan: &SavedScan, ports: &[SavedPort]) -> Result<i64, ScanDbError> {
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "INSERT INTO scans (started, finished, target, ports, protocol, args, total, open_count)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                scan.started,
+                scan.finished,
+                scan.target,
+                scan.ports,
+                scan.protocol,
+                scan.args,
+                scan.total as i64,
+                scan.open_count as i64,
+            ],
+        )?;
+        let id = tx.last_insert_rowid();
+        {
+            let mut insert = tx.prepare(
+                "INSERT OR IGNORE INTO open_ports (scan_id, host, port, hostname, service)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?;
+            for port in ports {
+                insert.execute(params![
+                    id,
+                    port.host,
+                    port.port,
+                    port.hostname,
+                    port.service
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(id)
+    }
+
+    // 最近的 limit 次扫描，按编号从新到旧
+    pub fn list(&self, limit: usize) -> Result<Vec<SavedScan>, ScanDbError> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, started, finished, target, ports, protocol, args, total, open_count
+             FROM scans ORDER BY id DESC LIMIT ?1",
+        )?;
+        let scans = stmt
+            .query_map([limit as i64], saved_scan)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(scans)
+    }
+
+    pub fn scan(&self, id: i64) -> Result<SavedScan, ScanDbError> {
+        self.conn
+            .query_row(
+                "SELECT id, started, finished, target, ports, protocol, args, total, open_count
+                 FROM scans WHERE id = ?1",
+                [id],
+                saved_scan,
+            )
+            .optional()?
+            .ok_or(ScanDbError::NotFound(id))
+    }
+
+    pub fn open_ports(&self, id: i64) -> Result<Vec<SavedPort>, ScanDbError> {
+        let mut stmt = self.conn.prepare(
+            "SELECT host, port, hostname, service FROM open_ports
+             WHERE scan_id = ?1 ORDER BY host, port",
+        )?;
+        let ports = stmt
+            .query_map([id], |row| {
+                Ok(SavedPort {
+                    host: row.get(0)?,
+                    port: row.get(1)?,
+                    hostname: row.get(2)?,
+                    service: row.get(3)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(ports)
+    }
+}
+
+fn saved_scan(row: &rusqlite::Row) -> rusqlite::Result<SavedScan> {
+    Ok(SavedScan {
+        id: row.get(0)?,
+        started: row.get(1)?,
+        finished: row.get(2)?,
+        target: row.get(3)?,
+        ports: row.get(4)?,
+        protocol: row.get(5)?,
+        args: row.get(6)?,
+        total: row.get::<_, i64>(7)? as usize,
+        open_count: row.get::<_, i64>(8)? as usize,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_and_load_scans() {
+        let dir = env::temp_dir().join(format!("rtoolkit-scandb-{}", std::process::id()));
+        let path = dir.join("nested").join("scans.db");
+        let _ = fs::remove_dir_all(&dir);
+        let mut db = ScanDb::open(&path).unwrap();
+        let scan = SavedScan {
+            id: 0,
+            started: "2026-01-02T03:04:05+08:00".into(),
+            finished: "2026-01-02T03:04:09+08:00".into(),
+            target: "10.0.0.0/30".into(),
+            ports: "22,80".into(),
+            protocol: "tcp".into(),
+            args: "rtoolkit port-scan -t 10.0.0.0/30 -p 22,80 --save".into(),
+            total: 4,
+            open_count: 1,
+        };
+        let ports = vec![SavedPort {
+            host: "10.0.0.1".into(),
+            port: 22,
+            hostname: None,
+            service: Some("ssh".into()),
+        }];
+        let first = db.save(&scan, &ports).unwrap();
+        let second = db.save(&scan, &[]).unwrap();
+        assert!(second > first);
+
+        // 重新打开后数据仍在
+        drop(db);
+        let db = ScanDb::open(&path).unwrap();
+        let listed = db.list(10).unwrap();
+        assert_eq!(
+            listed.iter().map(|scan| scan.id).collect::<Vec<_>>(),
+            vec![second, first]
+        );
+        assert_eq!(db.scan(first).unwrap().target, "10.0.0.0/30");
+        assert_eq!(db.open_ports(first).unwrap(), ports);
+        assert!(db.open_ports(second).unwrap().is_empty());
+        assert!(matches!(db.scan(999), Err(ScanDbError::NotFound(999))));
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,150 @@
+// 全局 `--map-script` 的 Rhai 脚本：输出层对每条结构化记录执行一次，用于改字段名、计算派生值或丢弃记录。
+//
+// 脚本里的 `record` 是当前记录（对象），可以直接修改；脚本的返回值决定结果：
+// 返回 false 丢弃记录，返回对象则替换记录，其它值（包括没有返回值）输出修改后的 record。
+// 原有字段保持原来的顺序，脚本新增的字段排在后面。
+
+use std::fmt;
+use std::path::Path;
+
+use rhai::{Dynamic, Engine, Scope, AST};
+use serde_json::{Map, Value};
+
+use crate::utils::output::OutputError;
+
+// 单条记录允许执行的操作数，防止脚本死循环卡住整个输出
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+pub struct MapScript {
+    path: String,
+    engine: Engine,
+    ast: AST,
+}
+
+impl fmt::Debug for MapScript {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MapScript")
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+impl MapScript {
+    pub fn load(path: &Path) -> Result<Self, OutputError> {
+        let source = std::fs::read_to_string(path).map_err(|error| OutputError::Script {
+            path: path.display().to_string(),
+            message: error.to_string(),
+        })?;
+        Self::compile(&path.display().to_string(), &source)
+    }
+
+    pub fn compile(path: &str, source: &str) -> Result<Self, OutputError> {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        // print / debug 写到 stderr，避免混进 stdout 上的 JSON / CSV
+        engine.on_print(|text| eprintln!("{}", text));
+        engine.on_debug(|text, _, _| eprintln!("{}", text));
+        let ast = engine
+            .compile(source)
+            .map_err(|error| OutputError::Script {
+                path: path.to_string(),
+                message: error.to_string(),
+            })?;
+        Ok(Self {
+            path: path.to_string(),
+            engine,
+            ast,
+        })
+    }
+
+    // 返回 None 表示脚本丢弃了这条记录
+    pub fn apply(&self, record: Value) -> Result<Option<Value>, String> {
+        let order: Vec<String> = match &record {
+            Value::Object(object) => object.keys().cloned().collect(),
+            _ => Vec::new(),
+        };
+        let input = rhai::serde::to_dynamic(&record).map_err(|error| self.error(&error))?;
+        let mut scope = Scope::new();
+        scope.push("record", input);
+        let result: Dynamic = self
+            .engine
+            .eval_ast_with_scope(&mut scope, &self.ast)
+            .map_err(|error| self.error(&error))?;
+        if result.as_bool() == Ok(false) {
+            return Ok(None);
+        }
+        let output = if result.is_map() {
+            result
+        } else {
+            scope.get_value::<Dynamic>("record").unwrap_or_default()
+        };
+        let value: Value =
+            rhai::serde::from_dynamic(&output).map_err(|error| self.error(&error))?;
+        Ok(Some(restore_order(value, &order)))
+    }
+
+    fn error(&self, error: &dyn fmt::Display) -> String {
+        format!("--map-script {}: {}", self.path, error)
+    }
+}
+
+// Rhai 的对象按键名排序，这里恢复原记录的字段顺序
+fn restore_order(value: Value, order: &[String]) -> Value {
+    let Value::Object(mut object) = value else {
+        return value;
+    };
+    let mut ordered = Map::with_capacity(object.len());
+    for key in order {
+        if let Some(value) = object.remove(key) {
+            ordered.insert(key.clone(), value);
+        }
+    }
+    ordered.extend(object);
+    Value::Object(ordered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_map_script_transforms_and_drops() {
+        let script = MapScript::compile(
+            "test.rhai",
+            r#"
+                if record.state != "open" { return false; }
+                record.endpoint = record.host + ":" + record.port;
+                record.slow = record.latency_ms > 50.0;
+                record.remove("latency_ms");
+            "#,
+        )
+        .unwrap();
+        let record = json!({"host": "10.0.0.1", "port": 22, "state": "open", "latency_ms": 80.5});
+        let output = script.apply(record).unwrap().unwrap();
+        assert_eq!(
+            output,
+            json!({"host": "10.0.0.1", "port": 22, "state": "open", "endpoint": "10.0.0.1:22", "slow": true})
+        );
+        let keys: Vec<&String> = output.as_object().unwrap().keys().collect();
+        assert_eq!(keys, ["host", "port", "state", "endpoint", "slow"]);
+        assert_eq!(script.apply(json!({"state": "closed"})).unwrap(), None);
+
+        // 返回对象时替换整条记录
+        let script = MapScript::compile("test.rhai", "#{ ip: record.host }").unwrap();
+        assert_eq!(
+            script.apply(json!({"host": "a", "port": 1})).unwrap(),
+            Some(json!({"ip": "a"}))
+        );
+
+        let script = MapScript::compile("test.rhai", "loop {}").unwrap();
+        assert!(script
+            .apply(json!({}))
+            .unwrap_err()
+            .starts_with("--map-script test.rhai:"));
+        assert!(matches!(
+            MapScript::compile("bad.rhai", "record.("),
+            Err(OutputError::Script { .. })
+        ));
+    }
+}
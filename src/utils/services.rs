@@ -0,0 +1,69 @@
+// 端口号与服务名的对照表，数据取自 IANA Service Name and Transport Protocol Port Number Registry，
+// 同一端口有多个注册名时只保留最常用的一个；另补充了 redis、kafka 等未在 IANA 注册但事实通用的端口。
+//
+// 表在首次查询时解析一次，之后只读。
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+const SERVICES_CSV: &str = include_str!("../../data/services.csv");
+
+struct ServiceTable {
+    names: HashMap<(u16, bool), &'static str>,
+    ports: HashMap<(&'static str, bool), u16>,
+}
+
+static SERVICES: OnceLock<ServiceTable> = OnceLock::new();
+
+fn table() -> &'static ServiceTable {
+    SERVICES.get_or_init(|| {
+        let mut names = HashMap::new();
+        let mut ports = HashMap::new();
+        for line in SERVICES_CSV.lines().skip(1) {
+            let mut fields = line.split(',');
+            let (Some(port), Some(protocol), Some(name)) =
+                (fields.next(), fields.next(), fields.next())
+            else {
+                continue;
+            };
+            let Ok(port) = port.parse::<u16>() else {
+                continue;
+            };
+            let udp = protocol == "udp";
+            names.insert((port, udp), name);
+            // 同名服务占用多个端口时取最小的端口
+            ports.entry((name, udp)).or_insert(port);
+        }
+        ServiceTable { names, ports }
+    })
+}
+
+// 端口对应的服务名，例如 80/tcp -> http
+pub fn service_name(port: u16, udp: bool) -> Option<&'static str> {
+    table().names.get(&(port, udp)).copied()
+}
+
+// 服务名对应的端口，大小写不敏感，例如 mysql -> 3306
+pub fn service_port(name: &str, udp: bool) -> Option<u16> {
+    let name = name.to_ascii_lowercase();
+    table().ports.get(&(name.as_str(), udp)).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_service_lookup() {
+        assert_eq!(service_name(80, false), Some("http"));
+        assert_eq!(service_name(3306, false), Some("mysql"));
+        assert_eq!(service_name(6379, false), Some("redis"));
+        assert_eq!(service_name(53, true), Some("domain"));
+        assert_eq!(service_name(51820, true), Some("wireguard"));
+        assert_eq!(service_name(6379, true), None);
+        assert_eq!(service_name(1, true), None);
+        assert_eq!(service_port("MySQL", false), Some(3306));
+        assert_eq!(service_port("ntp", true), Some(123));
+        assert_eq!(service_port("no-such-service", false), None);
+    }
+}
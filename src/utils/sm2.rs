@@ -0,0 +1,678 @@
+// GM/T 0003-2012 SM2 椭圆曲线公钥算法：推荐曲线上的密钥生成、带用户标识的签名 / 验签和公钥加密 / 解密，
+// 以及 OpenSSL 兼容的 PKCS#8 / SEC1 私钥和 SubjectPublicKeyInfo 公钥的 PEM 读写。
+// 点运算使用仿射坐标和大整数，速度和常量时间都不是目标，只用于本地构造和核对测试报文。
+
+use std::sync::OnceLock;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use num_bigint::BigUint;
+use rand::{rng, Rng};
+
+use crate::utils::pki::encode_pem;
+use crate::utils::sm3::{Sm3, DIGEST_LEN};
+
+// 未指定用户标识时使用 GM/T 0009 规定的默认值
+pub const DEFAULT_ID: &[u8] = b"1234567812345678";
+
+const FIELD_LEN: usize = 32;
+// id-ecPublicKey 1.2.840.10045.2.1 和 SM2 曲线 1.2.156.10197.1.301 的 DER 编码
+const OID_EC_PUBLIC_KEY: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01];
+const OID_SM2: &[u8] = &[0x2a, 0x81, 0x1c, 0xcf, 0x55, 0x01, 0x82, 0x2d];
+
+const TAG_INTEGER: u8 = 0x02;
+const TAG_BIT_STRING: u8 = 0x03;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_OID: u8 = 0x06;
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_EXPLICIT_1: u8 = 0xa1;
+
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum Sm2Error {
+    #[error("invalid SM2 private key: {0}")]
+    InvalidPrivateKey(&'static str),
+    #[error("invalid SM2 public key: {0}")]
+    InvalidPublicKey(&'static str),
+    #[error("malformed PEM or DER key data")]
+    MalformedKey,
+    #[error("key is not on the SM2 curve (OID 1.2.156.10197.1.301)")]
+    NotSm2,
+    #[error("malformed SM2 signature, expected 64 raw bytes or DER")]
+    MalformedSignature,
+    #[error("SM2 decryption failed: {0}")]
+    Decrypt(&'static str),
+}
+
+struct Curve {
+    p: BigUint,
+    a: BigUint,
+    b: BigUint,
+    n: BigUint,
+    g: Point,
+}
+
+fn curve() -> &'static Curve {
+    static CURVE: OnceLock<Curve> = OnceLock::new();
+    CURVE.get_or_init(|| {
+        let hex =
+            |raw: &str| BigUint::parse_bytes(raw.as_bytes(), 16).expect("valid curve constant");
+        Curve {
+            p: hex("FFFFFFFEFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF00000000FFFFFFFFFFFFFFFF"),
+            a: hex("FFFFFFFEFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF00000000FFFFFFFFFFFFFFFC"),
+            b: hex("28E9FA9E9D9F5E344D5A9E4BCF6509A7F39789F515AB8F92DDBCBD414D940E93"),
+            n: hex("FFFFFFFEFFFFFFFFFFFFFFFFFFFFFFFF7203DF6B21C6052B53BBF40939D54123"),
+            g: Point {
+                x: hex("32C4AE2C1F1981195F9904466A39C9948FE30BBFF2660BE1715A4589334C74C7"),
+                y: hex("BC3736A2F4F6779C59BDCEE36B692153D0A9877CC62A474002DF32E52139F0A0"),
+            },
+        }
+    })
+}
+
+// 仿射坐标下的曲线点，无穷远点用 None 表示
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Point {
+    x: BigUint,
+    y: BigUint,
+}
+
+impl Point {
+    fn on_curve(&self) -> bool {
+        let c = curve();
+        if self.x >= c.p || self.y >= c.p {
+            return false;
+        }
+        let lhs = &self.y * &self.y % &c.p;
+        let rhs = (&self.x * &self.x * &self.x + &c.a * &self.x + &c.b) % &c.p;
+        lhs == rhs
+    }
+
+    // 未压缩编码 04 || x || y
+    fn to_uncompressed(&self) -> Vec<u8> {
+        let mut out = vec![0x04];
+        out.extend(field_bytes(&self.x));
+        out.extend(field_bytes(&self.y));
+        out
+    }
+}
+
+fn field_bytes(value: &BigUint) -> [u8; FIELD_LEN] {
+    let bytes = value.to_bytes_be();
+    let mut out = [0u8; FIELD_LEN];
+    out[FIELD_LEN - bytes.len()..].copy_from_slice(&bytes);
+    out
+}
+
+fn sub_mod(a: &BigUint, b: &BigUint, m: &BigUint) -> BigUint {
+    (a + m - b % m) % m
+}
+
+fn inv_mod(value: &BigUint, m: &BigUint) -> BigUint {
+    // m 为素数，按费马小定理求逆
+    value.modpow(&(m - 2u32), m)
+}
+
+fn point_add(lhs: Option<&Point>, rhs: Option<&Point>) -> Option<Point> {
+    let (lhs, rhs) = match (lhs, rhs) {
+        (None, other) | (other, None) => return other.cloned(),
+        (Some(lhs), Some(rhs)) => (lhs, rhs),
+    };
+    let c = curve();
+    let p = &c.p;
+    let lambda = if lhs.x == rhs.x {
+        if (&lhs.y + &rhs.y) % p == BigUint::ZERO {
+            return None;
+        }
+        (3u32 * &lhs.x * &lhs.x + &c.a) * inv_mod(&(2u32 * &lhs.y), p) % p
+    } else {
+        sub_mod(&rhs.y, &lhs.y, p) * inv_mod(&sub_mod(&rhs.x, &lhs.x, p), p) % p
+    };
+    let x = sub_mod(&(&lambda * &lambda), &(&lhs.x + &rhs.x), p);
+    let y = sub_mod(&(&lambda * sub_mod(&lhs.x, &x, p)), &lhs.y, p);
+    Some(Point { x, y })
+}
+
+fn point_mul(k: &BigUint, point: &Point) -> Option<Point> {
+    let mut acc = None;
+    for i in (0..k.bits()).rev() {
+        acc = point_add(acc.as_ref(), acc.as_ref());
+        if k.bit(i) {
+            acc = point_add(acc.as_ref(), Some(point));
+        }
+    }
+    acc
+}
+
+// [1, n - 1 - reserve] 内的随机数，多取 8 字节降低取模偏差
+fn random_scalar(reserve: u32) -> BigUint {
+    let mut bytes = [0u8; FIELD_LEN + 8];
+    rng().fill(&mut bytes[..]);
+    BigUint::from_bytes_be(&bytes) % (&curve().n - 1u32 - reserve) + 1u32
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublicKey {
+    point: Point,
+}
+
+impl PublicKey {
+    // 接受 04 || x || y 或省略 04 前缀的 64 字节坐标
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Sm2Error> {
+        let coords = match bytes.len() {
+            65 if bytes[0] == 0x04 => &bytes[1..],
+            64 => bytes,
+            _ => {
+                return Err(Sm2Error::InvalidPublicKey(
+                    "expected 04 || x || y (65 bytes)",
+                ))
+            }
+        };
+        let point = Point {
+            x: BigUint::from_bytes_be(&coords[..FIELD_LEN]),
+            y: BigUint::from_bytes_be(&coords[FIELD_LEN..]),
+        };
+        if !point.on_curve() {
+            return Err(Sm2Error::InvalidPublicKey("point is not on the SM2 curve"));
+        }
+        Ok(Self { point })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.point.to_uncompressed()
+    }
+
+    // SubjectPublicKeyInfo DER
+    pub fn to_der(&self) -> Vec<u8> {
+        let mut key = vec![0x00];
+        key.extend(self.to_bytes());
+        der(
+            TAG_SEQUENCE,
+            &[algorithm_identifier(), der(TAG_BIT_STRING, &key)].concat(),
+        )
+    }
+
+    pub fn to_pem(&self) -> String {
+        encode_pem("PUBLIC KEY", &self.to_der())
+    }
+
+    pub fn from_der(data: &[u8]) -> Result<Self, Sm2Error> {
+        let (tag, spki, _) = read_tlv(data).ok_or(Sm2Error::MalformedKey)?;
+        if tag != TAG_SEQUENCE {
+            return Err(Sm2Error::MalformedKey);
+        }
+        let (_, algorithm, rest) = read_tlv(spki).ok_or(Sm2Error::MalformedKey)?;
+        check_algorithm(algorithm)?;
+        let (tag, key, _) = read_tlv(rest).ok_or(Sm2Error::MalformedKey)?;
+        match (tag, key.split_first()) {
+            (TAG_BIT_STRING, Some((0, key))) => Self::from_bytes(key),
+            _ => Err(Sm2Error::MalformedKey),
+        }
+    }
+
+    // 用户标识 ID 与曲线参数、公钥一起杂凑得到的 Z_A
+    fn za(&self, id: &[u8]) -> [u8; DIGEST_LEN] {
+        let c = curve();
+        let mut sm3 = Sm3::new();
+        sm3.update(&((id.len() * 8) as u16).to_be_bytes());
+        sm3.update(id);
+        for value in [&c.a, &c.b, &c.g.x, &c.g.y, &self.point.x, &self.point.y] {
+            sm3.update(&field_bytes(value));
+        }
+        sm3.finalize()
+    }
+
+    fn message_digest(&self, message: &[u8], id: &[u8]) -> BigUint {
+        let mut sm3 = Sm3::new();
+        sm3.update(&self.za(id));
+        sm3.update(message);
+        BigUint::from_bytes_be(&sm3.finalize())
+    }
+
+    pub fn verify(&self, message: &[u8], id: &[u8], signature: &Signature) -> bool {
+        let c = curve();
+        let (r, s) = (&signature.r, &signature.s);
+        let valid = |value: &BigUint| *value > BigUint::ZERO && value < &c.n;
+        if !valid(r) || !valid(s) {
+            return false;
+        }
+        let t = (r + s) % &c.n;
+        if t == BigUint::ZERO {
+            return false;
+        }
+        let e = self.message_digest(message, id);
+        let sum = point_add(
+            point_mul(s, &c.g).as_ref(),
+            point_mul(&t, &self.point).as_ref(),
+        );
+        sum.is_some_and(|point| (e + point.x) % &c.n == *r)
+    }
+
+    pub fn encrypt(&self, message: &[u8], order: CipherOrder) -> Vec<u8> {
+        self.encrypt_with(message, order, || random_scalar(0))
+    }
+
+    // 随机数 k 由 nonce 提供，测试中用固定的 k 对照标准示例
+    fn encrypt_with(
+        &self,
+        message: &[u8],
+        order: CipherOrder,
+        mut nonce: impl FnMut() -> BigUint,
+    ) -> Vec<u8> {
+        let c = curve();
+        loop {
+            let k = nonce();
+            let Some(c1) = point_mul(&k, &c.g) else {
+                continue;
+            };
+            let Some(shared) = point_mul(&k, &self.point) else {
+                continue;
+            };
+            let (x2, y2) = (field_bytes(&shared.x), field_bytes(&shared.y));
+            let mask = kdf(&[x2, y2].concat(), message.len());
+            if !message.is_empty() && mask.iter().all(|&byte| byte == 0) {
+                continue;
+            }
+            let c2: Vec<u8> = message.iter().zip(&mask).map(|(m, t)| m ^ t).collect();
+            let mut sm3 = Sm3::new();
+            sm3.update(&x2);
+            sm3.update(message);
+            sm3.update(&y2);
+            let c3 = sm3.finalize();
+            let c1 = c1.to_uncompressed();
+            return match order {
+                CipherOrder::C1C3C2 => [c1, c3.to_vec(), c2].concat(),
+                CipherOrder::C1C2C3 => [c1, c2, c3.to_vec()].concat(),
+            };
+        }
+    }
+}
+
+pub struct PrivateKey {
+    d: BigUint,
+    public: PublicKey,
+}
+
+impl PrivateKey {
+    pub fn generate() -> Self {
+        Self::from_scalar(random_scalar(1)).expect("random scalar in range")
+    }
+
+    fn from_scalar(d: BigUint) -> Result<Self, Sm2Error> {
+        let c = curve();
+        // d 取值范围为 [1, n - 2]，保证签名时 1 + d 可逆
+        if d == BigUint::ZERO || d >= &c.n - 1u32 {
+            return Err(Sm2Error::InvalidPrivateKey("scalar out of range"));
+        }
+        let point = point_mul(&d, &c.g).expect("non-zero scalar below n");
+        Ok(Self {
+            d,
+            public: PublicKey { point },
+        })
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Sm2Error> {
+        if bytes.len() != FIELD_LEN {
+            return Err(Sm2Error::InvalidPrivateKey("expected 32 bytes"));
+        }
+        Self::from_scalar(BigUint::from_bytes_be(bytes))
+    }
+
+    pub fn to_bytes(&self) -> [u8; FIELD_LEN] {
+        field_bytes(&self.d)
+    }
+
+    pub fn public_key(&self) -> &PublicKey {
+        &self.public
+    }
+
+    // PKCS#8 PrivateKeyInfo DER，内含带公钥的 SEC1 ECPrivateKey，与 openssl genpkey 的输出一致
+    pub fn to_der(&self) -> Vec<u8> {
+        let mut public = vec![0x00];
+        public.extend(self.public.to_bytes());
+        let sec1 = der(
+            TAG_SEQUENCE,
+            &[
+                der(TAG_INTEGER, &[1]),
+                der(TAG_OCTET_STRING, &self.to_bytes()),
+                der(TAG_EXPLICIT_1, &der(TAG_BIT_STRING, &public)),
+            ]
+            .concat(),
+        );
+        der(
+            TAG_SEQUENCE,
+            &[
+                der(TAG_INTEGER, &[0]),
+                algorithm_identifier(),
+                der(TAG_OCTET_STRING, &sec1),
+            ]
+            .concat(),
+        )
+    }
+
+    pub fn to_pem(&self) -> String {
+        encode_pem("PRIVATE KEY", &self.to_der())
+    }
+
+    // 接受 PKCS#8 PrivateKeyInfo 或 SEC1 ECPrivateKey
+    pub fn from_der(data: &[u8]) -> Result<Self, Sm2Error> {
+        let (tag, body, _) = read_tlv(data).ok_or(Sm2Error::MalformedKey)?;
+        let (version_tag, version, rest) = read_tlv(body).ok_or(Sm2Error::MalformedKey)?;
+        if tag != TAG_SEQUENCE || version_tag != TAG_INTEGER {
+            return Err(Sm2Error::MalformedKey);
+        }
+        match version {
+            // PKCS#8：version 0，随后是算法标识和包着 SEC1 结构的 OCTET STRING
+            [0] => {
+                let (_, algorithm, rest) = read_tlv(rest).ok_or(Sm2Error::MalformedKey)?;
+                check_algorithm(algorithm)?;
+                match read_tlv(rest) {
+                    Some((TAG_OCTET_STRING, sec1, _)) => Self::from_der(sec1),
+                    _ => Err(Sm2Error::MalformedKey),
+                }
+            }
+            // SEC1：version 1，随后是私钥 OCTET STRING，曲线参数可省略
+            [1] => match read_tlv(rest) {
+                Some((TAG_OCTET_STRING, d, params)) => {
+                    if let Some((0xa0, oid, _)) = read_tlv(params) {
+                        if read_tlv(oid) != Some((TAG_OID, OID_SM2, &[])) {
+                            return Err(Sm2Error::NotSm2);
+                        }
+                    }
+                    Self::from_bytes(d)
+                }
+                _ => Err(Sm2Error::MalformedKey),
+            },
+            _ => Err(Sm2Error::MalformedKey),
+        }
+    }
+
+    pub fn sign(&self, message: &[u8], id: &[u8]) -> Signature {
+        self.sign_with(message, id, || random_scalar(0))
+    }
+
+    fn sign_with(
+        &self,
+        message: &[u8],
+        id: &[u8],
+        mut nonce: impl FnMut() -> BigUint,
+    ) -> Signature {
+        let c = curve();
+        let e = self.public.message_digest(message, id);
+        let inv = inv_mod(&(&self.d + 1u32), &c.n);
+        loop {
+            let k = nonce();
+            let Some(point) = point_mul(&k, &c.g) else {
+                continue;
+            };
+            let r = (&e + &point.x) % &c.n;
+            if r == BigUint::ZERO || &r + &k == c.n {
+                continue;
+            }
+            let s = &inv * sub_mod(&k, &(&r * &self.d), &c.n) % &c.n;
+            if s != BigUint::ZERO {
+                return Signature { r, s };
+            }
+        }
+    }
+
+    pub fn decrypt(&self, data: &[u8], order: CipherOrder) -> Result<Vec<u8>, Sm2Error> {
+        const C1_LEN: usize = 1 + 2 * FIELD_LEN;
+        if data.len() < C1_LEN + DIGEST_LEN || data[0] != 0x04 {
+            return Err(Sm2Error::Decrypt(
+                "ciphertext too short or missing 04 prefix",
+            ));
+        }
+        let c1 = PublicKey::from_bytes(&data[..C1_LEN])
+            .map_err(|_| Sm2Error::Decrypt("C1 is not on the SM2 curve"))?;
+        let rest = &data[C1_LEN..];
+        let (c3, c2) = match order {
+            CipherOrder::C1C3C2 => rest.split_at(DIGEST_LEN),
+            CipherOrder::C1C2C3 => {
+                let (c2, c3) = rest.split_at(rest.len() - DIGEST_LEN);
+                (c3, c2)
+            }
+        };
+        let shared = point_mul(&self.d, &c1.point).ok_or(Sm2Error::Decrypt("invalid C1"))?;
+        let (x2, y2) = (field_bytes(&shared.x), field_bytes(&shared.y));
+        let mask = kdf(&[x2, y2].concat(), c2.len());
+        let message: Vec<u8> = c2.iter().zip(&mask).map(|(c, t)| c ^ t).collect();
+        let mut sm3 = Sm3::new();
+        sm3.update(&x2);
+        sm3.update(&message);
+        sm3.update(&y2);
+        if sm3.finalize() != c3 {
+            return Err(Sm2Error::Decrypt(
+                "C3 mismatch, wrong key or ciphertext order",
+            ));
+        }
+        Ok(message)
+    }
+}
+
+// 密文分量的排列：GM/T 0003-2012 为 C1 || C3 || C2，部分旧接口仍使用 C1 || C2 || C3
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CipherOrder {
+    C1C3C2,
+    C1C2C3,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+    r: BigUint,
+    s: BigUint,
+}
+
+impl Signature {
+    // r || s，各 32 字节
+    pub fn to_raw(&self) -> Vec<u8> {
+        [field_bytes(&self.r), field_bytes(&self.s)].concat()
+    }
+
+    // SEQUENCE { INTEGER r, INTEGER s }，与 OpenSSL 的输出一致
+    pub fn to_der(&self) -> Vec<u8> {
+        let integer = |value: &BigUint| {
+            let mut bytes = value.to_bytes_be();
+            if bytes[0] & 0x80 != 0 {
+                bytes.insert(0, 0);
+            }
+            der(TAG_INTEGER, &bytes)
+        };
+        der(TAG_SEQUENCE, &[integer(&self.r), integer(&self.s)].concat())
+    }
+
+    // 64 字节按 r || s 解析，其余按 DER 解析
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Sm2Error> {
+        if bytes.len() == 2 * FIELD_LEN {
+            return Ok(Self {
+                r: BigUint::from_bytes_be(&bytes[..FIELD_LEN]),
+                s: BigUint::from_bytes_be(&bytes[FIELD_LEN..]),
+            });
+        }
+        let (TAG_SEQUENCE, body, _) = read_tlv(bytes).ok_or(Sm2Error::MalformedSignature)? else {
+            return Err(Sm2Error::MalformedSignature);
+        };
+        match read_tlv(body) {
+            Some((TAG_INTEGER, r, rest)) => match read_tlv(rest) {
+                Some((TAG_INTEGER, s, _)) => Ok(Self {
+                    r: BigUint::from_bytes_be(r),
+                    s: BigUint::from_bytes_be(s),
+                }),
+                _ => Err(Sm2Error::MalformedSignature),
+            },
+            _ => Err(Sm2Error::MalformedSignature),
+        }
+    }
+}
+
+// GM/T 0003 密钥派生函数：SM3(Z || ct) 依次拼接，ct 为从 1 开始的 32 位大端计数器
+fn kdf(z: &[u8], len: usize) -> Vec<u8> {
+    let mut out = Vec::with_capacity(len + DIGEST_LEN);
+    let mut counter = 1u32;
+    while out.len() < len {
+        let mut sm3 = Sm3::new();
+        sm3.update(z);
+        sm3.update(&counter.to_be_bytes());
+        out.extend(sm3.finalize());
+        counter += 1;
+    }
+    out.truncate(len);
+    out
+}
+
+fn algorithm_identifier() -> Vec<u8> {
+    der(
+        TAG_SEQUENCE,
+        &[der(TAG_OID, OID_EC_PUBLIC_KEY), der(TAG_OID, OID_SM2)].concat(),
+    )
+}
+
+fn check_algorithm(algorithm: &[u8]) -> Result<(), Sm2Error> {
+    let (tag, oid, params) = read_tlv(algorithm).ok_or(Sm2Error::MalformedKey)?;
+    if tag != TAG_OID || oid != OID_EC_PUBLIC_KEY {
+        return Err(Sm2Error::NotSm2);
+    }
+    match read_tlv(params) {
+        Some((TAG_OID, OID_SM2, _)) => Ok(()),
+        _ => Err(Sm2Error::NotSm2),
+    }
+}
+
+fn der(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    let len = content.len();
+    if len < 0x80 {
+        out.push(len as u8);
+    } else {
+        let bytes: Vec<u8> = len
+            .to_be_bytes()
+            .into_iter()
+            .skip_while(|&byte| byte == 0)
+            .collect();
+        out.push(0x80 | bytes.len() as u8);
+        out.extend(bytes);
+    }
+    out.extend_from_slice(content);
+    out
+}
+
+// 读取一个 DER TLV，返回 (tag, 内容, 剩余数据)
+fn read_tlv(data: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, rest) = data.split_first()?;
+    let (&first, rest) = rest.split_first()?;
+    let (len, rest) = if first < 0x80 {
+        (first as usize, rest)
+    } else {
+        let count = (first & 0x7f) as usize;
+        if count == 0 || count > 4 || rest.len() < count {
+            return None;
+        }
+        let len = rest[..count]
+            .iter()
+            .fold(0usize, |len, &byte| len << 8 | byte as usize);
+        (len, &rest[count..])
+    };
+    (rest.len() >= len).then(|| (tag, &rest[..len], &rest[len..]))
+}
+
+// 解析 PEM，返回标签和 DER 内容；不是 PEM 时为 None
+pub fn parse_pem(text: &str) -> Option<(String, Vec<u8>)> {
+    let text = text.trim();
+    let label = text
+        .strip_prefix("-----BEGIN ")?
+        .split_once("-----")?
+        .0
+        .to_string();
+    let body: String = text
+        .lines()
+        .filter(|line| !line.starts_with("-----"))
+        .collect::<Vec<_>>()
+        .concat();
+    let der = STANDARD.decode(body.trim()).ok()?;
+    Some((label, der))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sm2_sign_encrypt_and_key_encoding() {
+        let key = PrivateKey::generate();
+        let public = key.public_key();
+        assert!(public.point.on_curve());
+
+        let signature = key.sign(b"amount=100", DEFAULT_ID);
+        assert!(public.verify(b"amount=100", DEFAULT_ID, &signature));
+        assert!(!public.verify(b"amount=101", DEFAULT_ID, &signature));
+        assert!(!public.verify(b"amount=100", b"ALICE123@YAHOO.COM", &signature));
+        for encoded in [signature.to_raw(), signature.to_der()] {
+            assert_eq!(Signature::from_bytes(&encoded).unwrap(), signature);
+        }
+
+        for order in [CipherOrder::C1C3C2, CipherOrder::C1C2C3] {
+            let cipher = public.encrypt(b"encryption standard", order);
+            assert_eq!(cipher.len(), 65 + 32 + 19);
+            assert_eq!(key.decrypt(&cipher, order).unwrap(), b"encryption standard");
+        }
+        let cipher = public.encrypt(b"encryption standard", CipherOrder::C1C3C2);
+        assert!(key.decrypt(&cipher, CipherOrder::C1C2C3).is_err());
+
+        let (label, der) = parse_pem(&key.to_pem()).unwrap();
+        assert_eq!(label, "PRIVATE KEY");
+        let restored = PrivateKey::from_der(&der).unwrap();
+        assert_eq!(restored.to_bytes(), key.to_bytes());
+        let (_, der) = parse_pem(&public.to_pem()).unwrap();
+        assert_eq!(&PublicKey::from_der(&der).unwrap(), public);
+        assert!(PrivateKey::from_bytes(&[0u8; 32]).is_err());
+        assert!(PublicKey::from_bytes(&[4u8; 65]).is_err());
+    }
+
+    // GM/T 0003.5-2012 附录中推荐曲线上的签名和加密示例
+    #[test]
+    fn test_sm2_known_answer() {
+        let hex = |raw: &str| BigUint::parse_bytes(raw.as_bytes(), 16).unwrap();
+        let key = PrivateKey::from_bytes(
+            &hex("3945208F7B2144B13F36E38AC6D39F95889393692860B51A42FB81EF4DF7C5B8").to_bytes_be(),
+        )
+        .unwrap();
+        assert_eq!(
+            key.public_key().point,
+            Point {
+                x: hex("09F9DF311E5421A150DD7D161E4BC5C672179FAD1833FC076BB08FF356F35020"),
+                y: hex("CCEA490CE26775A52DC6EA718CC1AA600AED05FBF35E084A6632F6072DA9AD13"),
+            }
+        );
+        let k = || hex("59276E27D506861A16680F3AD9C02DCCEF3CC1FA3CDBE4CE6D54B80DEAC1BC21");
+
+        let signature = key.sign_with(b"message digest", DEFAULT_ID, k);
+        assert_eq!(
+            signature,
+            Signature {
+                r: hex("F5A03B0648D2C4630EEAC513E1BB81A15944DA3827D5B74143AC7EACEEE720B3"),
+                s: hex("B1B6AA29DF212FD8763182BC0D421CA1BB9038FD1F7F42D4840B69C485BBC1AA"),
+            }
+        );
+        assert!(key
+            .public_key()
+            .verify(b"message digest", DEFAULT_ID, &signature));
+
+        let cipher = key
+            .public_key()
+            .encrypt_with(b"encryption standard", CipherOrder::C1C3C2, k);
+        // C1 || C3 || C2
+        let c1 = Point {
+            x: hex("04EBFC718E8D1798620432268E77FEB6415E2EDE0E073C0F4F640ECD2E149A73"),
+            y: hex("E858F9D81E5430A57B36DAAB8F950A3C64E6EE6A63094D99283AFF767E124DF0"),
+        };
+        let c3 = hex("59983C18F809E262923C53AEC295D30383B54E39D609D160AFCB1908D0BD8766");
+        let c2 = hex("21886CA989CA9C7D58087307CA93092D651EFA");
+        assert_eq!(
+            cipher,
+            [c1.to_uncompressed(), c3.to_bytes_be(), c2.to_bytes_be()].concat()
+        );
+        assert_eq!(
+            key.decrypt(&cipher, CipherOrder::C1C3C2).unwrap(),
+            b"encryption standard"
+        );
+    }
+}
@@ -0,0 +1,216 @@
+// GM/T 0004-2012 SM3 杂凑算法和基于它的 HMAC-SM3（RFC 2104 结构，分组长度 64 字节）。
+// 国内支付、政务接口常要求 SM 系列算法，生态里的 crate 不在依赖中，这里按标准文本直接实现。
+
+pub const DIGEST_LEN: usize = 32;
+const BLOCK_LEN: usize = 64;
+
+const IV: [u32; 8] = [
+    0x7380166f, 0x4914b2b9, 0x172442d7, 0xda8a0600, 0xa96f30bc, 0x163138aa, 0xe38dee4d, 0xb0fb0e4e,
+];
+
+#[derive(Clone)]
+pub struct Sm3 {
+    state: [u32; 8],
+    buf: [u8; BLOCK_LEN],
+    buf_len: usize,
+    // 已处理的消息长度(字节)
+    total: u64,
+}
+
+impl Default for Sm3 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sm3 {
+    pub fn new() -> Self {
+        Self {
+            state: IV,
+            buf: [0; BLOCK_LEN],
+            buf_len: 0,
+            total: 0,
+        }
+    }
+
+    pub fn digest(data: &[u8]) -> [u8; DIGEST_LEN] {
+        let mut sm3 = Self::new();
+        sm3.update(data);
+        sm3.finalize()
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) {
+        self.total += data.len() as u64;
+        if self.buf_len > 0 {
+            let take = data.len().min(BLOCK_LEN - self.buf_len);
+            self.buf[self.buf_len..self.buf_len + take].copy_from_slice(&data[..take]);
+            self.buf_len += take;
+            data = &data[take..];
+            if self.buf_len < BLOCK_LEN {
+                return;
+            }
+            let block = self.buf;
+            compress(&mut self.state, &block);
+            self.buf_len = 0;
+        }
+        let mut blocks = data.chunks_exact(BLOCK_LEN);
+        for block in &mut blocks {
+            compress(&mut self.state, block.try_into().expect("64-byte block"));
+        }
+        let rest = blocks.remainder();
+        self.buf[..rest.len()].copy_from_slice(rest);
+        self.buf_len = rest.len();
+    }
+
+    pub fn finalize(mut self) -> [u8; DIGEST_LEN] {
+        let bit_len = self.total.wrapping_mul(8);
+        // 填充：0x80，补 0 到 56 字节，再追加 64 位大端消息长度
+        let mut tail = [0u8; BLOCK_LEN * 2];
+        tail[..self.buf_len].copy_from_slice(&self.buf[..self.buf_len]);
+        tail[self.buf_len] = 0x80;
+        let len = if self.buf_len < BLOCK_LEN - 8 {
+            BLOCK_LEN
+        } else {
+            BLOCK_LEN * 2
+        };
+        tail[len - 8..len].copy_from_slice(&bit_len.to_be_bytes());
+        for block in tail[..len].chunks_exact(BLOCK_LEN) {
+            compress(&mut self.state, block.try_into().expect("64-byte block"));
+        }
+        let mut out = [0u8; DIGEST_LEN];
+        for (chunk, word) in out.chunks_exact_mut(4).zip(self.state) {
+            chunk.copy_from_slice(&word.to_be_bytes());
+        }
+        out
+    }
+}
+
+fn p0(x: u32) -> u32 {
+    x ^ x.rotate_left(9) ^ x.rotate_left(17)
+}
+
+fn p1(x: u32) -> u32 {
+    x ^ x.rotate_left(15) ^ x.rotate_left(23)
+}
+
+fn compress(state: &mut [u32; 8], block: &[u8; BLOCK_LEN]) {
+    let mut w = [0u32; 68];
+    for (word, bytes) in w.iter_mut().zip(block.chunks_exact(4)) {
+        *word = u32::from_be_bytes(bytes.try_into().expect("4-byte word"));
+    }
+    for j in 16..68 {
+        w[j] = p1(w[j - 16] ^ w[j - 9] ^ w[j - 3].rotate_left(15))
+            ^ w[j - 13].rotate_left(7)
+            ^ w[j - 6];
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+    for j in 0..64 {
+        let t: u32 = if j < 16 { 0x79cc4519 } else { 0x7a879d8a };
+        let ss1 = a
+            .rotate_left(12)
+            .wrapping_add(e)
+            .wrapping_add(t.rotate_left(j as u32 % 32))
+            .rotate_left(7);
+        let ss2 = ss1 ^ a.rotate_left(12);
+        let (ff, gg) = if j < 16 {
+            (a ^ b ^ c, e ^ f ^ g)
+        } else {
+            ((a & b) | (a & c) | (b & c), (e & f) | (!e & g))
+        };
+        let tt1 = ff
+            .wrapping_add(d)
+            .wrapping_add(ss2)
+            .wrapping_add(w[j] ^ w[j + 4]);
+        let tt2 = gg.wrapping_add(h).wrapping_add(ss1).wrapping_add(w[j]);
+        d = c;
+        c = b.rotate_left(9);
+        b = a;
+        a = tt1;
+        h = g;
+        g = f.rotate_left(19);
+        f = e;
+        e = p0(tt2);
+    }
+    for (word, value) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+        *word ^= value;
+    }
+}
+
+#[derive(Clone)]
+pub struct HmacSm3 {
+    inner: Sm3,
+    outer_pad: [u8; BLOCK_LEN],
+}
+
+impl HmacSm3 {
+    pub fn new(key: &[u8]) -> Self {
+        // 超过分组长度的密钥先做一次杂凑
+        let mut block = [0u8; BLOCK_LEN];
+        if key.len() > BLOCK_LEN {
+            block[..DIGEST_LEN].copy_from_slice(&Sm3::digest(key));
+        } else {
+            block[..key.len()].copy_from_slice(key);
+        }
+        let mut inner = Sm3::new();
+        inner.update(&block.map(|byte| byte ^ 0x36));
+        Self {
+            inner,
+            outer_pad: block.map(|byte| byte ^ 0x5c),
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.inner.update(data);
+    }
+
+    pub fn finalize(self) -> [u8; DIGEST_LEN] {
+        let mut outer = Sm3::new();
+        outer.update(&self.outer_pad);
+        outer.update(&self.inner.finalize());
+        outer.finalize()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    #[test]
+    fn test_sm3_and_hmac_vectors() {
+        // GM/T 0004-2012 附录 A 的两个示例
+        assert_eq!(
+            hex(&Sm3::digest(b"abc")),
+            "66c7f0f462eeedd9d1f2d46bdc10e4e24167c4875cf2f7a2297da02b8f4ba8e0"
+        );
+        let long = b"abcd".repeat(16);
+        assert_eq!(
+            hex(&Sm3::digest(&long)),
+            "debe9ff92275b8a138604889c18e5a4d6fdb70e5387e5765293dcba39c0c5732"
+        );
+        // 分段写入与一次写入结果一致
+        let mut split = Sm3::new();
+        for chunk in long.chunks(7) {
+            split.update(chunk);
+        }
+        assert_eq!(split.finalize(), Sm3::digest(&long));
+
+        let mut mac = HmacSm3::new(b"Jefe");
+        mac.update(b"what do ya want for nothing?");
+        assert_eq!(
+            hex(&mac.finalize()),
+            "2e87f1d16862e6d964b50a5200bf2b10b764faa9680a296a2405f24bec39f882"
+        );
+        // 超过分组长度的密钥
+        let mut mac = HmacSm3::new(&[b'k'; 100]);
+        mac.update(b"hello");
+        assert_eq!(
+            hex(&mac.finalize()),
+            "96e7bb987abfaf7745cd259fbbd128db75bdf6054eda0e0773a7ac29dcf12d18"
+        );
+    }
+}
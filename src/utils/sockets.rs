@@ -0,0 +1,350 @@
+// 读取操作系统的套接字表，列出本机正在监听的端口及所属进程（类似 netstat -lnp / ss -lnp）。
+// Linux 直接解析 /proc/net，macOS 调用 lsof，Windows 调用 netstat -ano。
+// 其他用户的进程在没有 root 权限时看不到 PID，对应字段为空
+
+use std::collections::HashMap;
+use std::io;
+use std::net::IpAddr;
+#[cfg(not(target_os = "linux"))]
+use std::process::Command;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transport {
+    Tcp,
+    Udp,
+}
+
+// 单个监听套接字；UDP 为未连接（没有固定对端）的套接字
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListeningSocket {
+    pub addr: IpAddr,
+    pub port: u16,
+    pub pid: Option<u32>,
+}
+
+#[cfg(target_os = "linux")]
+pub fn listening_sockets(transport: Transport) -> io::Result<Vec<ListeningSocket>> {
+    // TCP_LISTEN = 0x0A；未连接的 UDP 套接字状态为 TCP_CLOSE = 0x07
+    let (files, state) = match transport {
+        Transport::Tcp => (["/proc/net/tcp", "/proc/net/tcp6"], "0A"),
+        Transport::Udp => (["/proc/net/udp", "/proc/net/udp6"], "07"),
+    };
+    let mut entries = Vec::new();
+    for (i, path) in files.iter().enumerate() {
+        match std::fs::read_to_string(path) {
+            Ok(text) => entries.extend(parse_proc_net(&text, state)),
+            // 内核关闭了 IPv6 时没有 tcp6 / udp6
+            Err(e) if i > 0 && e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+    }
+    let owners = socket_owners();
+    Ok(entries
+        .into_iter()
+        .map(|(addr, port, inode)| ListeningSocket {
+            addr,
+            port,
+            pid: owners.get(&inode).copied(),
+        })
+        .collect())
+}
+
+#[cfg(target_os = "macos")]
+pub fn listening_sockets(transport: Transport) -> io::Result<Vec<ListeningSocket>> {
+    let args: &[&str] = match transport {
+        Transport::Tcp => &["-nP", "-iTCP", "-sTCP:LISTEN", "-Fptn"],
+        Transport::Udp => &["-nP", "-iUDP", "-Fptn"],
+    };
+    let output = Command::new("lsof").args(args).output()?;
+    Ok(parse_lsof(&String::from_utf8_lossy(&output.stdout)))
+}
+
+#[cfg(windows)]
+pub fn listening_sockets(transport: Transport) -> io::Result<Vec<ListeningSocket>> {
+    let output = Command::new("netstat").arg("-ano").output()?;
+    Ok(parse_netstat(
+        &String::from_utf8_lossy(&output.stdout),
+        transport,
+    ))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+pub fn listening_sockets(_transport: Transport) -> io::Result<Vec<ListeningSocket>> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "reading the socket table is not supported on this platform",
+    ))
+}
+
+// PID 到完整命令行的映射，读取失败的进程不出现在结果中
+#[cfg(target_os = "linux")]
+pub fn process_commands() -> HashMap<u32, String> {
+    let mut commands = HashMap::new();
+    let Ok(dir) = std::fs::read_dir("/proc") else {
+        return commands;
+    };
+    for entry in dir.flatten() {
+        let Some(pid) = entry.file_name().to_str().and_then(|s| s.parse().ok()) else {
+            continue;
+        };
+        // cmdline 以 NUL 分隔参数；内核线程的 cmdline 为空，退回到 comm
+        let cmdline = std::fs::read(entry.path().join("cmdline")).unwrap_or_default();
+        let command = cmdline
+            .split(|&byte| byte == 0)
+            .filter(|arg| !arg.is_empty())
+            .map(String::from_utf8_lossy)
+            .collect::<Vec<_>>()
+            .join(" ");
+        let command = if command.is_empty() {
+            std::fs::read_to_string(entry.path().join("comm"))
+                .map(|comm| comm.trim().to_string())
+                .unwrap_or_default()
+        } else {
+            command
+        };
+        if !command.is_empty() {
+            commands.insert(pid, command);
+        }
+    }
+    commands
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+pub fn process_commands() -> HashMap<u32, String> {
+    let mut commands = HashMap::new();
+    let Ok(output) = Command::new("ps").args(["-axo", "pid=,command="]).output() else {
+        return commands;
+    };
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let Some((pid, command)) = line.trim_start().split_once(' ') else {
+            continue;
+        };
+        if let Ok(pid) = pid.parse() {
+            commands.insert(pid, command.trim().to_string());
+        }
+    }
+    commands
+}
+
+#[cfg(not(unix))]
+pub fn process_commands() -> HashMap<u32, String> {
+    HashMap::new()
+}
+
+// 解析 /proc/net/{tcp,udp}[6]，返回指定状态的 (本地地址, 端口, inode)
+#[cfg(any(target_os = "linux", test))]
+fn parse_proc_net(text: &str, state: &str) -> Vec<(IpAddr, u16, u64)> {
+    text.lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 10 || fields[3] != state {
+                return None;
+            }
+            // UDP 已 connect 的套接字状态是 ESTABLISHED，这里再排除有对端端口的记录
+            if fields[2].rsplit_once(':').map(|(_, port)| port) != Some("0000") {
+                return None;
+            }
+            let (addr, port) = fields[1].split_once(':')?;
+            let port = u16::from_str_radix(port, 16).ok()?;
+            let inode = fields[9].parse().ok()?;
+            Some((parse_proc_addr(addr)?, port, inode))
+        })
+        .collect()
+}
+
+// 地址按主机字节序逐个 32 位字打印，IPv4 一个字，IPv6 四个字
+#[cfg(any(target_os = "linux", test))]
+fn parse_proc_addr(hex: &str) -> Option<IpAddr> {
+    let words = (0..hex.len() / 8)
+        .map(|i| u32::from_str_radix(hex.get(i * 8..i * 8 + 8)?, 16).ok())
+        .collect::<Option<Vec<u32>>>()?;
+    let mut bytes = Vec::with_capacity(16);
+    for word in words {
+        bytes.extend_from_slice(&word.to_ne_bytes());
+    }
+    match bytes.len() {
+        4 => Some(IpAddr::from(<[u8; 4]>::try_from(bytes).ok()?)),
+        16 => {
+            let v6 = std::net::Ipv6Addr::from(<[u8; 16]>::try_from(bytes).ok()?);
+            // 双栈监听的 IPv4 映射地址按 IPv4 显示
+            Some(match v6.to_ipv4_mapped() {
+                Some(v4) => IpAddr::V4(v4),
+                None => IpAddr::V6(v6),
+            })
+        }
+        _ => None,
+    }
+}
+
+// 扫描 /proc/<pid>/fd 中指向 socket:[inode] 的链接，得到 inode 到 PID 的映射
+#[cfg(target_os = "linux")]
+fn socket_owners() -> HashMap<u64, u32> {
+    let mut owners = HashMap::new();
+    let Ok(dir) = std::fs::read_dir("/proc") else {
+        return owners;
+    };
+    for entry in dir.flatten() {
+        let Some(pid) = entry.file_name().to_str().and_then(|s| s.parse().ok()) else {
+            continue;
+        };
+        let Ok(fds) = std::fs::read_dir(entry.path().join("fd")) else {
+            continue;
+        };
+        for fd in fds.flatten() {
+            let Ok(target) = std::fs::read_link(fd.path()) else {
+                continue;
+            };
+            let inode = target
+                .to_str()
+                .and_then(|s| s.strip_prefix("socket:["))
+                .and_then(|s| s.strip_suffix(']'))
+                .and_then(|s| s.parse().ok());
+            if let Some(inode) = inode {
+                owners.entry(inode).or_insert(pid);
+            }
+        }
+    }
+    owners
+}
+
+// lsof -F 输出：p<PID> 开始一个进程，t<IPv4|IPv6> 和 n<地址:端口> 描述其中的每个文件
+#[cfg(any(target_os = "macos", test))]
+fn parse_lsof(text: &str) -> Vec<ListeningSocket> {
+    let mut sockets = Vec::new();
+    let mut pid = None;
+    let mut ipv6 = false;
+    for line in text.lines() {
+        let (tag, value) = line.split_at(line.len().min(1));
+        match tag {
+            "p" => pid = value.parse().ok(),
+            "t" => ipv6 = value == "IPv6",
+            // 带 -> 的是已连接的 UDP 套接字
+            "n" if !value.contains("->") => {
+                let Some((addr, port)) = value.rsplit_once(':') else {
+                    continue;
+                };
+                let addr = match addr.trim_start_matches('[').trim_end_matches(']') {
+                    "*" if ipv6 => Some(IpAddr::from([0u16; 8])),
+                    "*" => Some(IpAddr::from([0u8; 4])),
+                    addr => addr.split('%').next().and_then(|addr| addr.parse().ok()),
+                };
+                if let (Some(addr), Ok(port)) = (addr, port.parse()) {
+                    sockets.push(ListeningSocket { addr, port, pid });
+                }
+            }
+            _ => {}
+        }
+    }
+    sockets
+}
+
+// netstat -ano 的 TCP 行为 协议 本地地址 对端地址 状态 PID，UDP 行没有状态列
+#[cfg(any(windows, test))]
+fn parse_netstat(text: &str, transport: Transport) -> Vec<ListeningSocket> {
+    text.lines()
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            let pid = match (transport, parts.as_slice()) {
+                (Transport::Tcp, [proto, _, _, state, pid])
+                    if proto.eq_ignore_ascii_case("TCP")
+                        && state.eq_ignore_ascii_case("LISTENING") =>
+                {
+                    pid
+                }
+                (Transport::Udp, [proto, _, "*:*", pid]) if proto.eq_ignore_ascii_case("UDP") => {
+                    pid
+                }
+                _ => return None,
+            };
+            let (addr, port) = parts[1].rsplit_once(':')?;
+            let addr = addr
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .split('%')
+                .next()?
+                .parse()
+                .ok()?;
+            Some(ListeningSocket {
+                addr,
+                port: port.parse().ok()?,
+                pid: pid.parse().ok(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_socket_tables() {
+        let word = |addr: [u8; 4]| format!("{:08X}", u32::from_ne_bytes(addr));
+        let tcp = format!(
+            "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode\n\
+             \x20  0: {}:0277 00000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 21345 1 0 100 0 0 10 0\n\
+             \x20  1: {}:1F90 {}:C350 01 00000000:00000000 00:00000000 00000000  1000        0 21400 1 0 20 4 30 10 -1\n",
+            word([127, 0, 0, 1]),
+            word([10, 0, 0, 5]),
+            word([10, 0, 0, 9]),
+        );
+        assert_eq!(
+            parse_proc_net(&tcp, "0A"),
+            vec![("127.0.0.1".parse().unwrap(), 631, 21345)]
+        );
+        // 双栈监听的 ::ffff:192.168.1.2
+        let mapped: String = [[0; 4], [0; 4], [0, 0, 0xff, 0xff], [192, 168, 1, 2]]
+            .into_iter()
+            .map(word)
+            .collect();
+        assert_eq!(
+            parse_proc_addr(&mapped),
+            Some("192.168.1.2".parse().unwrap())
+        );
+        assert_eq!(
+            parse_proc_addr(&"0".repeat(32)),
+            Some("::".parse().unwrap())
+        );
+
+        let lsof = "p312\ntIPv4\nn127.0.0.1:5432\ntIPv6\nn*:8080\np77\ntIPv4\nn10.0.0.5:5353->10.0.0.9:5353\n";
+        assert_eq!(
+            parse_lsof(lsof),
+            vec![
+                ListeningSocket {
+                    addr: "127.0.0.1".parse().unwrap(),
+                    port: 5432,
+                    pid: Some(312)
+                },
+                ListeningSocket {
+                    addr: "::".parse().unwrap(),
+                    port: 8080,
+                    pid: Some(312)
+                },
+            ]
+        );
+
+        let netstat = "  Proto  Local Address          Foreign Address        State           PID\n\
+                       \x20 TCP    0.0.0.0:135            0.0.0.0:0              LISTENING       1052\n\
+                       \x20 TCP    10.0.0.5:49712         20.1.2.3:443           ESTABLISHED     4410\n\
+                       \x20 TCP    [::]:445               [::]:0                 LISTENING       4\n\
+                       \x20 UDP    0.0.0.0:123            *:*                                    1680\n";
+        let ports = |transport| {
+            parse_netstat(netstat, transport)
+                .into_iter()
+                .map(|s| (s.addr.to_string(), s.port, s.pid))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            ports(Transport::Tcp),
+            vec![
+                ("0.0.0.0".into(), 135, Some(1052)),
+                ("::".into(), 445, Some(4))
+            ]
+        );
+        assert_eq!(
+            ports(Transport::Udp),
+            vec![("0.0.0.0".into(), 123, Some(1680))]
+        );
+    }
+}
@@ -0,0 +1,202 @@
+// TLS 客户端配置和证书解析：端口扫描的 --tls-probe、smoketest 的证书检查和 HTTPS 请求共用。
+
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use serde::Serialize;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_rustls::rustls::client::danger::{
+    HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier,
+};
+use tokio_rustls::rustls::client::WebPkiServerVerifier;
+use tokio_rustls::rustls::crypto::{
+    ring, verify_tls12_signature, verify_tls13_signature, CryptoProvider,
+};
+use tokio_rustls::rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use tokio_rustls::rustls::{
+    ClientConfig, ClientConnection, DigitallySignedStruct, Error, RootCertStore, SignatureScheme,
+};
+use tokio_rustls::TlsConnector;
+use x509_parser::certificate::X509Certificate;
+use x509_parser::extensions::GeneralName;
+
+// 探测只为读取证书内容，接受任何证书（包括自签名和过期证书），
+// 但握手签名仍按正常流程校验
+#[derive(Debug)]
+struct AcceptAnyCert(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for AcceptAnyCert {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+pub fn insecure_client_config() -> ClientConfig {
+    let provider = Arc::new(ring::default_provider());
+    ClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .expect("ring provider supports the default protocol versions")
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(AcceptAnyCert(provider)))
+        .with_no_client_auth()
+}
+
+// 按内置的 Mozilla 根证书校验服务端证书，用于需要像普通客户端一样访问服务的场景
+pub fn verified_client_config() -> ClientConfig {
+    let roots = RootCertStore {
+        roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+    };
+    ClientConfig::builder_with_provider(Arc::new(ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .expect("ring provider supports the default protocol versions")
+        .with_root_certificates(roots)
+        .with_no_client_auth()
+}
+
+// 按内置的 Mozilla 根证书和主机名校验服务端发来的证书链（叶子证书在前），失败时返回原因。
+// 握手用 insecure_client_config 完成后再单独校验，证书有问题时仍能读到证书内容
+pub fn verify_server_chain(
+    chain: &[CertificateDer<'static>],
+    server_name: &ServerName<'_>,
+) -> Result<(), String> {
+    let roots = RootCertStore {
+        roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+    };
+    let verifier = WebPkiServerVerifier::builder_with_provider(
+        Arc::new(roots),
+        Arc::new(ring::default_provider()),
+    )
+    .build()
+    .map_err(|e| e.to_string())?;
+    let (leaf, intermediates) = chain
+        .split_first()
+        .ok_or_else(|| "server sent no certificate".to_string())?;
+    verifier
+        .verify_server_cert(leaf, intermediates, server_name, &[], UnixTime::now())
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+// TLS 探测得到的证书摘要，时间均为 UTC
+#[derive(Debug, Clone, Serialize)]
+pub struct TlsCertInfo {
+    pub version: String,
+    pub subject: String,
+    pub issuer: String,
+    pub sans: Vec<String>,
+    pub not_before: String,
+    pub not_after: String,
+    pub days_left: i64,
+    pub self_signed: bool,
+}
+
+// 在已建立的连接上握手并读取服务端的叶子证书，握手失败时返回 None
+pub async fn peer_certificate<S>(
+    connector: &TlsConnector,
+    server_name: ServerName<'static>,
+    stream: S,
+) -> Option<TlsCertInfo>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let stream = connector.connect(server_name, stream).await.ok()?;
+    let (_, session) = stream.get_ref();
+    session_certificate(session)
+}
+
+// 已完成握手的会话的协议版本和叶子证书摘要，用于 STARTTLS 等握手后还要继续通信的场景
+pub fn session_certificate(session: &ClientConnection) -> Option<TlsCertInfo> {
+    let version = session
+        .protocol_version()
+        .and_then(|version| version.as_str())
+        .unwrap_or("TLS")
+        .replace('_', ".")
+        .replace("TLSv", "TLS ");
+    let leaf = session.peer_certificates()?.first()?;
+    parse_certificate(leaf, version)
+}
+
+fn parse_certificate(der: &[u8], version: String) -> Option<TlsCertInfo> {
+    let (_, cert) = x509_parser::parse_x509_certificate(der).ok()?;
+    let validity = cert.validity();
+    let not_after = validity.not_after.timestamp();
+    let days_left = (not_after - chrono::Utc::now().timestamp()).div_euclid(86_400);
+    Some(TlsCertInfo {
+        version,
+        subject: cert.subject().to_string(),
+        issuer: cert.issuer().to_string(),
+        sans: subject_alt_names(&cert),
+        not_before: format_timestamp(validity.not_before.timestamp()),
+        not_after: format_timestamp(not_after),
+        days_left,
+        self_signed: cert.subject() == cert.issuer(),
+    })
+}
+
+// 证书中的 DNS 和 IP 类型 SAN，IP 地址格式化为文本
+pub fn subject_alt_names(cert: &X509Certificate<'_>) -> Vec<String> {
+    cert.subject_alternative_name()
+        .ok()
+        .flatten()
+        .map(|ext| {
+            ext.value
+                .general_names
+                .iter()
+                .filter_map(|name| match name {
+                    GeneralName::DNSName(dns) => Some(dns.to_string()),
+                    GeneralName::IPAddress(bytes) => match bytes.len() {
+                        4 => Some(IpAddr::from(<[u8; 4]>::try_from(*bytes).ok()?).to_string()),
+                        16 => Some(IpAddr::from(<[u8; 16]>::try_from(*bytes).ok()?).to_string()),
+                        _ => None,
+                    },
+                    _ => None,
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+pub fn format_timestamp(secs: i64) -> String {
+    chrono::DateTime::from_timestamp(secs, 0)
+        .map(|time| time.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+        .unwrap_or_default()
+}
@@ -0,0 +1,137 @@
+// `rtoolkit --version [--json]` 的版本和构建信息，字段由 build.rs 在编译期写入。
+//
+// JSON 结构视为稳定接口：只增加字段，不改名、不删除；有破坏性变化时递增 `schema`。
+
+use std::io::Write;
+
+use serde::Serialize;
+
+const SCHEMA: u32 = 1;
+
+#[derive(Debug, Serialize)]
+pub struct VersionInfo {
+    pub schema: u32,
+    pub name: &'static str,
+    pub version: &'static str,
+    // 不在 git 仓库中构建（例如 crates.io 源码包）时为 null
+    pub git_commit: Option<&'static str>,
+    pub git_dirty: bool,
+    pub build_date: &'static str,
+    pub target: &'static str,
+    pub profile: &'static str,
+    pub features: Vec<&'static str>,
+    pub data: DataVersions,
+}
+
+// 内嵌数据表的版本；上游没有发布号的数据用内容指纹加记录数标识
+#[derive(Debug, Serialize)]
+pub struct DataVersions {
+    pub regions: RegionData,
+    pub services: ServiceData,
+    pub oui: OuiData,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RegionData {
+    pub fingerprint: String,
+    pub provinces: u64,
+    pub cities: u64,
+    pub areas: u64,
+    pub streets: u64,
+}
+
+// 端口服务名表（data/services.csv）
+#[derive(Debug, Serialize)]
+pub struct ServiceData {
+    pub fingerprint: String,
+    pub entries: u64,
+}
+
+// MAC 厂商表（data/oui.csv）
+#[derive(Debug, Serialize)]
+pub struct OuiData {
+    pub fingerprint: String,
+    pub entries: u64,
+}
+
+impl VersionInfo {
+    pub fn current() -> Self {
+        let commit = env!("RTOOLKIT_GIT_COMMIT");
+        let features = env!("RTOOLKIT_FEATURES");
+        let mut counts = env!("RTOOLKIT_REGION_COUNTS")
+            .split(',')
+            .map(|count| count.parse().unwrap_or(0));
+        let mut count = || counts.next().unwrap_or(0);
+        Self {
+            schema: SCHEMA,
+            name: env!("CARGO_PKG_NAME"),
+            version: env!("CARGO_PKG_VERSION"),
+            git_commit: (!commit.is_empty()).then_some(commit),
+            git_dirty: env!("RTOOLKIT_GIT_DIRTY") == "true",
+            build_date: env!("RTOOLKIT_BUILD_DATE"),
+            target: env!("RTOOLKIT_TARGET"),
+            profile: env!("RTOOLKIT_PROFILE"),
+            features: features.split(',').filter(|f| !f.is_empty()).collect(),
+            data: DataVersions {
+                regions: RegionData {
+                    fingerprint: format!("fnv1a64:{}", env!("RTOOLKIT_REGION_FINGERPRINT")),
+                    provinces: count(),
+                    cities: count(),
+                    areas: count(),
+                    streets: count(),
+                },
+                services: ServiceData {
+                    fingerprint: format!("fnv1a64:{}", env!("RTOOLKIT_SERVICES_FINGERPRINT")),
+                    entries: env!("RTOOLKIT_SERVICES_COUNT").parse().unwrap_or(0),
+                },
+                oui: OuiData {
+                    fingerprint: format!("fnv1a64:{}", env!("RTOOLKIT_OUI_FINGERPRINT")),
+                    entries: env!("RTOOLKIT_OUI_COUNT").parse().unwrap_or(0),
+                },
+            },
+        }
+    }
+}
+
+pub fn write_version<W: Write>(json: bool, mut out: W) -> Result<(), serde_json::Error> {
+    let info = VersionInfo::current();
+    if json {
+        serde_json::to_writer_pretty(&mut out, &info)?;
+        return writeln!(out).map_err(serde_json::Error::io);
+    }
+    let build = match info.git_commit {
+        Some(commit) if info.git_dirty => format!("{}-dirty, {}", commit, info.build_date),
+        Some(commit) => format!("{}, {}", commit, info.build_date),
+        None => info.build_date.to_string(),
+    };
+    writeln!(out, "{} {} ({})", info.name, info.version, build).map_err(serde_json::Error::io)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_version_is_stable() {
+        let mut out = Vec::new();
+        write_version(true, &mut out).unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(value["schema"], 1);
+        assert_eq!(value["version"], env!("CARGO_PKG_VERSION"));
+        assert!(value["features"].is_array());
+        assert!(value["build_date"].as_str().unwrap().ends_with('Z'));
+        let regions = &value["data"]["regions"];
+        assert!(regions["fingerprint"]
+            .as_str()
+            .unwrap()
+            .starts_with("fnv1a64:"));
+        assert!(regions["provinces"].as_u64().unwrap() > 30);
+        assert!(regions["streets"].as_u64().unwrap() > regions["areas"].as_u64().unwrap());
+        assert!(value["data"]["services"]["entries"].as_u64().unwrap() > 300);
+        assert!(value["data"]["oui"]["entries"].as_u64().unwrap() > 300);
+
+        let mut out = Vec::new();
+        write_version(false, &mut out).unwrap();
+        assert!(String::from_utf8(out).unwrap().starts_with("rtoolkit "));
+    }
+}
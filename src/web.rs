@@ -1,5 +1,7 @@
 use std::io::{BufRead, BufReader, Read, Write};
 use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::time::Instant;
 
 use serde::{Deserialize, Serialize};
 
@@ -10,8 +12,11 @@ use crate::commands::idgen::{
 };
 use crate::commands::imagetool::basic::convert::ImageFormatArg;
 use crate::commands::jsonfmt::{format_json_text, MAX_INDENT};
-use crate::commands::portscan::{scan_ports, PortScanRequest};
+use crate::commands::portscan::{scan_ports, PortScanRequest, ScanPlan, JOB_MAX_PORTS};
+use crate::jobs::{JobError, JobLimits, JobQueue};
 use crate::utils::areas::{all_cities, all_provinces, all_regions, Area, City, Province};
+use crate::utils::duration::parse_duration;
+use crate::utils::{metrics, network};
 
 const INDEX_HTML: &str = include_str!("../static/index.html");
 const IDGEN_HTML: &str = include_str!("../static/idgen.html");
@@ -30,6 +35,39 @@ pub struct WebOpts {
     pub host: String,
     #[arg(short, long, default_value_t = 8080)]
     pub port: u16,
+    #[arg(
+        long,
+        help = "在 Web 端口上提供 Prometheus /metrics（请求耗时、探测数等）"
+    )]
+    pub metrics: bool,
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 2,
+        help = "同时执行的后台任务数（POST /jobs/portscan 等）"
+    )]
+    pub job_workers: usize,
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 16,
+        help = "排队等待的后台任务上限，队列满时提交返回 429"
+    )]
+    pub job_queue: usize,
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 100,
+        help = "保留结果的已结束任务数，超出时淘汰最早结束的任务"
+    )]
+    pub job_retention: usize,
+    #[arg(
+        long,
+        value_name = "DURATION",
+        default_value = "1h",
+        help = "已结束任务的结果保留时长，例如 30m、1h"
+    )]
+    pub job_ttl: String,
 }
 
 #[derive(Serialize)]
@@ -72,14 +110,27 @@ struct IdDownloadRequest {
 }
 
 pub fn run_web(opts: WebOpts) -> anyhow::Result<()> {
+    network::check_host("web", &opts.host)?;
+    let retention = parse_duration(&opts.job_ttl)
+        .ok_or_else(|| anyhow::anyhow!("invalid --job-ttl '{}', e.g. 30m or 1h", opts.job_ttl))?;
+    let jobs = JobQueue::start(JobLimits {
+        workers: opts.job_workers.max(1),
+        max_queued: opts.job_queue,
+        max_retained: opts.job_retention,
+        retention,
+    });
     let addr = format!("{}:{}", opts.host, opts.port);
     let listener = TcpListener::bind(&addr)?;
     println!("rtoolkit web listening on http://{}", addr);
+    if opts.metrics {
+        metrics::enable();
+        println!("metrics available at http://{}/metrics", addr);
+    }
 
     for stream in listener.incoming() {
         match stream {
             Ok(stream) => {
-                if let Err(error) = handle_connection(stream) {
+                if let Err(error) = handle_connection(stream, &jobs) {
                     eprintln!("request failed: {}", error);
                 }
             }
@@ -90,7 +141,7 @@ pub fn run_web(opts: WebOpts) -> anyhow::Result<()> {
     Ok(())
 }
 
-fn handle_connection(mut stream: TcpStream) -> anyhow::Result<()> {
+fn handle_connection(mut stream: TcpStream, jobs: &Arc<JobQueue>) -> anyhow::Result<()> {
     let mut reader = BufReader::new(stream.try_clone()?);
     let mut request_line = String::new();
     reader.read_line(&mut request_line)?;
@@ -137,41 +188,82 @@ fn handle_connection(mut stream: TcpStream) -> anyhow::Result<()> {
     }
 
     let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let started = Instant::now();
+    let result = route(
+        &mut stream,
+        jobs,
+        method,
+        path,
+        query,
+        &body,
+        &content_type_header,
+    );
+    metrics::observe_request(request_handler(path), started.elapsed());
+    result
+}
 
+// 已知 API 单独统计耗时，页面和静态资源合并为 static，其它路径合并为 other
+const API_ROUTES: [&str; 7] = [
+    "/api/health",
+    "/api/regions",
+    "/api/idgen",
+    "/api/idgen/download",
+    "/api/portscan",
+    "/api/jsonfmt",
+    "/api/imgtool/convert",
+];
+
+fn request_handler(path: &str) -> &'static str {
+    if let Some(route) = API_ROUTES.iter().find(|route| **route == path) {
+        return route;
+    }
+    if path == "/jobs" || path.starts_with("/jobs/") {
+        return "/jobs";
+    }
+    if path.starts_with("/api/") {
+        "other"
+    } else {
+        "static"
+    }
+}
+
+fn route(
+    stream: &mut TcpStream,
+    jobs: &Arc<JobQueue>,
+    method: &str,
+    path: &str,
+    query: &str,
+    body: &[u8],
+    content_type_header: &str,
+) -> anyhow::Result<()> {
     match (method, path) {
-        ("GET", "/") | ("GET", "/index.html") => write_html(&mut stream, INDEX_HTML),
-        ("GET", "/idgen") | ("GET", "/idgen.html") => write_html(&mut stream, IDGEN_HTML),
-        ("GET", "/port-scan") | ("GET", "/port-scan.html") => {
-            write_html(&mut stream, PORT_SCAN_HTML)
+        ("GET", "/metrics") if metrics::enabled() => {
+            metrics::write_response(stream, "200 OK", &metrics::render())?;
+            Ok(())
+        }
+        ("GET", "/") | ("GET", "/index.html") => write_html(stream, INDEX_HTML),
+        ("GET", "/idgen") | ("GET", "/idgen.html") => write_html(stream, IDGEN_HTML),
+        ("GET", "/port-scan") | ("GET", "/port-scan.html") => write_html(stream, PORT_SCAN_HTML),
+        ("GET", "/jsonfmt") | ("GET", "/jsonfmt.html") => write_html(stream, JSONFMT_HTML),
+        ("GET", "/imgtool") | ("GET", "/imgtool.html") => write_html(stream, IMGTOOL_HTML),
+        ("GET", "/idgen.js") => {
+            write_text(stream, "application/javascript; charset=utf-8", IDGEN_JS)
         }
-        ("GET", "/jsonfmt") | ("GET", "/jsonfmt.html") => write_html(&mut stream, JSONFMT_HTML),
-        ("GET", "/imgtool") | ("GET", "/imgtool.html") => write_html(&mut stream, IMGTOOL_HTML),
-        ("GET", "/idgen.js") => write_text(
-            &mut stream,
-            "application/javascript; charset=utf-8",
-            IDGEN_JS,
-        ),
         ("GET", "/port-scan.js") => write_text(
-            &mut stream,
+            stream,
             "application/javascript; charset=utf-8",
             PORT_SCAN_JS,
         ),
-        ("GET", "/jsonfmt.js") => write_text(
-            &mut stream,
-            "application/javascript; charset=utf-8",
-            JSONFMT_JS,
-        ),
-        ("GET", "/imgtool.js") => write_text(
-            &mut stream,
-            "application/javascript; charset=utf-8",
-            IMGTOOL_JS,
-        ),
-        ("GET", "/styles.css") => write_text(&mut stream, "text/css; charset=utf-8", STYLES_CSS),
-        ("GET", "/api/health") => {
-            write_json(&mut stream, 200, &serde_json::json!({ "status": "ok" }))
+        ("GET", "/jsonfmt.js") => {
+            write_text(stream, "application/javascript; charset=utf-8", JSONFMT_JS)
+        }
+        ("GET", "/imgtool.js") => {
+            write_text(stream, "application/javascript; charset=utf-8", IMGTOOL_JS)
         }
+        ("GET", "/styles.css") => write_text(stream, "text/css; charset=utf-8", STYLES_CSS),
+        ("GET", "/api/health") => write_json(stream, 200, &serde_json::json!({ "status": "ok" })),
         ("GET", "/api/regions") => write_json(
-            &mut stream,
+            stream,
             200,
             &RegionOptionsResponse {
                 provinces: all_provinces(),
@@ -180,11 +272,11 @@ fn handle_connection(mut stream: TcpStream) -> anyhow::Result<()> {
             },
         ),
         ("POST", "/api/idgen") => {
-            let payload: IdGenerateRequest = serde_json::from_slice(&body)?;
+            let payload: IdGenerateRequest = serde_json::from_slice(body)?;
             match generate_ids(payload) {
-                Ok(records) => write_json(&mut stream, 200, &IdGenerateResponse { records }),
+                Ok(records) => write_json(stream, 200, &IdGenerateResponse { records }),
                 Err(error) => write_json(
-                    &mut stream,
+                    stream,
                     400,
                     &ErrorResponse {
                         error: error.to_string(),
@@ -193,17 +285,17 @@ fn handle_connection(mut stream: TcpStream) -> anyhow::Result<()> {
             }
         }
         ("POST", "/api/idgen/download") => {
-            let payload: IdDownloadRequest = serde_json::from_slice(&body)?;
+            let payload: IdDownloadRequest = serde_json::from_slice(body)?;
             let format = payload.format.unwrap_or(OutputType::Text);
             match validate_id_download_request(&payload.params, format) {
                 Ok(_) => {
-                    write_download_header(&mut stream, format)?;
-                    write_generated_ids(payload.params, format, &mut stream)?;
+                    write_download_header(stream, format)?;
+                    write_generated_ids(payload.params, format, &mut *stream)?;
                     stream.flush()?;
                     Ok(())
                 }
                 Err(error) => write_json(
-                    &mut stream,
+                    stream,
                     400,
                     &ErrorResponse {
                         error: error.to_string(),
@@ -216,13 +308,13 @@ fn handle_connection(mut stream: TcpStream) -> anyhow::Result<()> {
             let format = payload.format.unwrap_or(OutputType::Text);
             match validate_id_download_request(&payload.params, format) {
                 Ok(_) => {
-                    write_download_header(&mut stream, format)?;
-                    write_generated_ids(payload.params, format, &mut stream)?;
+                    write_download_header(stream, format)?;
+                    write_generated_ids(payload.params, format, &mut *stream)?;
                     stream.flush()?;
                     Ok(())
                 }
                 Err(error) => write_json(
-                    &mut stream,
+                    stream,
                     400,
                     &ErrorResponse {
                         error: error.to_string(),
@@ -231,12 +323,12 @@ fn handle_connection(mut stream: TcpStream) -> anyhow::Result<()> {
             }
         }
         ("POST", "/api/portscan") => {
-            let payload: PortScanRequest = serde_json::from_slice(&body)?;
+            let payload: PortScanRequest = serde_json::from_slice(body)?;
             let rt = tokio::runtime::Runtime::new()?;
             match rt.block_on(scan_ports(payload)) {
-                Ok(result) => write_json(&mut stream, 200, &result),
+                Ok(result) => write_json(stream, 200, &result),
                 Err(error) => write_json(
-                    &mut stream,
+                    stream,
                     400,
                     &ErrorResponse {
                         error: error.to_string(),
@@ -244,8 +336,38 @@ fn handle_connection(mut stream: TcpStream) -> anyhow::Result<()> {
                 ),
             }
         }
+        ("GET", "/jobs") => write_json(stream, 200, &jobs.list()),
+        ("POST", "/jobs/portscan") => {
+            let payload: PortScanRequest = serde_json::from_slice(body)?;
+            let plan = match ScanPlan::new(payload, JOB_MAX_PORTS) {
+                Ok(plan) => plan,
+                Err(error) => {
+                    return write_json(
+                        stream,
+                        400,
+                        &ErrorResponse {
+                            error: error.to_string(),
+                        },
+                    )
+                }
+            };
+            let submitted = jobs.submit("portscan", move |progress| {
+                let rt = tokio::runtime::Runtime::new().map_err(|e| e.to_string())?;
+                let result = rt
+                    .block_on(plan.with_progress(progress).run())
+                    .map_err(|e| e.to_string())?;
+                serde_json::to_value(result).map_err(|e| e.to_string())
+            });
+            write_job(stream, submitted, 202)
+        }
+        ("GET", path) if path.starts_with("/jobs/") => {
+            write_job(stream, jobs.get(&path["/jobs/".len()..]), 200)
+        }
+        ("DELETE", path) if path.starts_with("/jobs/") => {
+            write_job(stream, jobs.cancel(&path["/jobs/".len()..]), 200)
+        }
         ("POST", "/api/jsonfmt") => {
-            let payload: JsonFmtRequest = serde_json::from_slice(&body)?;
+            let payload: JsonFmtRequest = serde_json::from_slice(body)?;
             let indent = payload.indent.unwrap_or(2).min(MAX_INDENT);
             match format_json_text(
                 &payload.input,
@@ -257,7 +379,7 @@ fn handle_connection(mut stream: TcpStream) -> anyhow::Result<()> {
                     let lines = output.lines().count();
                     let bytes = output.len();
                     write_json(
-                        &mut stream,
+                        stream,
                         200,
                         &JsonFmtResponse {
                             output,
@@ -267,7 +389,7 @@ fn handle_connection(mut stream: TcpStream) -> anyhow::Result<()> {
                     )
                 }
                 Err(error) => write_json(
-                    &mut stream,
+                    stream,
                     400,
                     &ErrorResponse {
                         error: error.to_string(),
@@ -275,11 +397,9 @@ fn handle_connection(mut stream: TcpStream) -> anyhow::Result<()> {
                 ),
             }
         }
-        ("POST", "/api/imgtool/convert") => {
-            handle_img_convert(&mut stream, &body, &content_type_header)
-        }
+        ("POST", "/api/imgtool/convert") => handle_img_convert(stream, body, content_type_header),
         _ => write_json(
-            &mut stream,
+            stream,
             404,
             &ErrorResponse {
                 error: "not found".to_string(),
@@ -288,6 +408,29 @@ fn handle_connection(mut stream: TcpStream) -> anyhow::Result<()> {
     }
 }
 
+fn write_job<T: Serialize>(
+    stream: &mut TcpStream,
+    job: Result<T, JobError>,
+    status: u16,
+) -> anyhow::Result<()> {
+    let error = match job {
+        Ok(view) => return write_json(stream, status, &view),
+        Err(error) => error,
+    };
+    let status = match error {
+        JobError::QueueFull(_) => 429,
+        JobError::NotFound(_) => 404,
+        JobError::Finished(_) => 409,
+    };
+    write_json(
+        stream,
+        status,
+        &ErrorResponse {
+            error: error.to_string(),
+        },
+    )
+}
+
 fn handle_img_convert(
     stream: &mut TcpStream,
     body: &[u8],
@@ -540,6 +683,8 @@ fn parse_id_download_query(query: &str) -> IdDownloadRequest {
         min_birth: None,
         max_birth: None,
         gender: None,
+        name_mix: None,
+        long_names: None,
     };
     let mut format = None;
 
@@ -553,6 +698,8 @@ fn parse_id_download_query(query: &str) -> IdDownloadRequest {
             "min_birth" => request.min_birth = non_empty(value),
             "max_birth" => request.max_birth = non_empty(value),
             "gender" => request.gender = serde_json::from_str(&format!("\"{}\"", value)).ok(),
+            "name_mix" => request.name_mix = non_empty(value),
+            "long_names" => request.long_names = value.parse().ok(),
             "format" => format = serde_json::from_str(&format!("\"{}\"", value)).ok(),
             _ => {}
         }
@@ -572,7 +719,7 @@ fn non_empty(value: String) -> Option<String> {
     }
 }
 
-fn url_decode(value: &str) -> String {
+pub(crate) fn url_decode(value: &str) -> String {
     let bytes = value.as_bytes();
     let mut output = Vec::with_capacity(bytes.len());
     let mut index = 0;
@@ -635,8 +782,11 @@ fn write_response(
 ) -> anyhow::Result<()> {
     let status_text = match status {
         200 => "OK",
+        202 => "Accepted",
         400 => "Bad Request",
         404 => "Not Found",
+        409 => "Conflict",
+        429 => "Too Many Requests",
         _ => "Internal Server Error",
     };
     write!(
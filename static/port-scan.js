@@ -9,6 +9,7 @@ Vue.createApp({
       form: {
         target: '127.0.0.1',
         port: '80',
+        protocol: 'tcp',
         concurrency: 100,
         timeout_ms: 1000
       }
@@ -53,10 +54,12 @@ Vue.createApp({
         }).length;
         this.metrics = [
           { label: '目标', value: data.target },
+          { label: '主机', value: (data.hosts || []).length },
           { label: '扫描端口', value: data.total },
           { label: '开放端口', value: data.open_count },
           { label: 'PID', value: pidCount },
           { label: '命令', value: commandCount },
+          { label: '延迟 min/avg/max', value: data.latency ? [data.latency.min_ms, data.latency.avg_ms, data.latency.max_ms].join(' / ') + 'ms' : '-' },
           { label: '超时', value: String(data.timeout_ms) + 'ms' }
         ];        this.lastText = JSON.stringify(data, null, 2);
       } catch (error) {